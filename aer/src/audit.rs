@@ -0,0 +1,323 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the functionality for checking the health of every link that is
+//! referenced in the package files, and fixing the links that have trivial
+//! fixes (*ie: links that are also available over https*).
+
+use std::fmt::Display;
+
+use aer_upd::data::{LicenseType, PackageData, Url};
+use aer_upd::web::errors::WebError;
+use aer_upd::web::{LinkCheck, WebRequest};
+
+/// The health of a single link.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkStatus {
+    /// The link can be reached, and do not need to be changed.
+    Healthy,
+    /// The link could not be reached, or the server responded with an
+    /// unsuccessful status code.
+    Dead(String),
+    /// The link redirects to a different domain.
+    Moved(Url),
+    /// The link uses `http`, but is also available using `https`.
+    HttpsUpgrade(Url),
+}
+
+impl LinkStatus {
+    /// Returns the url the link can be automatically replaced with, if the
+    /// problem is trivial to fix.
+    pub fn fixed_url(&self) -> Option<&Url> {
+        match self {
+            LinkStatus::HttpsUpgrade(url) => Some(url),
+            _ => None,
+        }
+    }
+}
+
+impl Display for LinkStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            LinkStatus::Healthy => f.write_str("healthy"),
+            LinkStatus::Dead(reason) => write!(f, "dead ({})", reason),
+            LinkStatus::Moved(url) => write!(f, "moved to '{}'", url),
+            LinkStatus::HttpsUpgrade(url) => write!(f, "available as '{}'", url),
+        }
+    }
+}
+
+/// Returns all links referenced in the package data, together with the
+/// dotted path of the key the link was found in.
+pub fn find_links(data: &PackageData) -> Vec<(&'static str, Url)> {
+    let mut links = vec![(
+        "metadata.project_url",
+        data.metadata().project_url().clone(),
+    )];
+
+    match data.metadata().license() {
+        LicenseType::Location(url) | LicenseType::ExpressionAndLocation { url, .. } => {
+            links.push(("metadata.license", url.clone()));
+        }
+        _ => {}
+    }
+
+    let choco = data.metadata().chocolatey();
+    if let Some(ref url) = choco.documentation_url {
+        links.push(("metadata.chocolatey.documentation_url", url.clone()));
+    }
+    if let Some(ref url) = choco.issues_url {
+        links.push(("metadata.chocolatey.issues_url", url.clone()));
+    }
+
+    let updater = data.updater();
+    let parse_urls = [
+        ("updater.urls.parse_url", updater.urls.parse_url.clone()),
+        (
+            "updater.chocolatey.parse_url",
+            updater.chocolatey().parse_url.clone(),
+        ),
+        (
+            "updater.winget.parse_url",
+            updater.winget().parse_url.clone(),
+        ),
+        ("updater.nuget.parse_url", updater.nuget().parse_url.clone()),
+    ];
+    for (key, parse_url) in parse_urls.iter() {
+        if let Some(ref parse_url) = parse_url {
+            links.push((key, parse_url.url().clone()));
+        }
+    }
+
+    links
+}
+
+/// Checks the health of a single link. Links using `http` that can be
+/// reached are additionally checked for being available over `https`.
+pub fn audit_link(request: &WebRequest, url: &Url) -> LinkStatus {
+    let status = classify(url, request.check_link(url.as_str()));
+
+    if status == LinkStatus::Healthy && url.scheme() == "http" {
+        if let Some(secure) = with_https(url) {
+            if matches!(request.check_link(secure.as_str()), Ok(ref check) if check.is_success()) {
+                return LinkStatus::HttpsUpgrade(secure);
+            }
+        }
+    }
+
+    status
+}
+
+/// Classifies the result of checking the specified link.
+pub fn classify(url: &Url, result: Result<LinkCheck, WebError>) -> LinkStatus {
+    let check = match result {
+        Ok(check) => check,
+        Err(err) => return LinkStatus::Dead(err.to_string()),
+    };
+
+    if !check.is_success() {
+        return LinkStatus::Dead(format!("status {}", check.status));
+    }
+
+    if domain(url) != domain(&check.url) {
+        return LinkStatus::Moved(check.url);
+    }
+
+    if url.scheme() == "http" && check.url.scheme() == "https" {
+        if let Some(secure) = with_https(url) {
+            return LinkStatus::HttpsUpgrade(secure);
+        }
+    }
+
+    LinkStatus::Healthy
+}
+
+/// Replaces every occurrence of the old links in the specified text with the
+/// fixed links. Links are matched both with and without the trailing slash
+/// that is added when the link is parsed, and only where the whole link
+/// matches (*ie: `http://example.org` do not match the start of
+/// `http://example.org/issues`*).
+pub fn fix_links(text: &str, fixes: &[(Url, Url)]) -> String {
+    let mut text = text.to_owned();
+
+    for (old, new) in fixes {
+        let (old, new) = (old.as_str(), new.as_str());
+        let (fixed, count) = replace_link(&text, old, new);
+        text = match (count, old.strip_suffix('/'), new.strip_suffix('/')) {
+            (0, Some(old), Some(new)) => replace_link(&text, old, new).0,
+            _ => fixed,
+        };
+    }
+
+    text
+}
+
+fn replace_link(text: &str, old: &str, new: &str) -> (String, usize) {
+    let mut result = String::with_capacity(text.len());
+    let mut count = 0;
+    let mut rest = text;
+
+    while let Some(index) = rest.find(old) {
+        let end = index + old.len();
+        let is_whole_link = rest[end..]
+            .chars()
+            .next()
+            .map(|c| c.is_whitespace() || matches!(c, '"' | '\'' | ',' | ']' | '}' | ')' | '>'))
+            .unwrap_or(true);
+
+        result.push_str(&rest[..index]);
+        if is_whole_link {
+            result.push_str(new);
+            count += 1;
+        } else {
+            result.push_str(old);
+        }
+        rest = &rest[end..];
+    }
+    result.push_str(rest);
+
+    (result, count)
+}
+
+fn with_https(url: &Url) -> Option<Url> {
+    let mut secure = url.clone();
+    secure.set_scheme("https").ok()?;
+
+    Some(secure)
+}
+
+fn domain(url: &Url) -> &str {
+    let host = url.host_str().unwrap_or_default();
+
+    host.strip_prefix("www.").unwrap_or(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_upd::data::chocolatey::{
+        ChocolateyMetadata, ChocolateyParseUrl, ChocolateyUpdaterData,
+    };
+    use aer_upd::data::{ParseUrl, UpdaterUrls};
+    use rstest::rstest;
+
+    use super::*;
+
+    fn check(status: u16, url: &str) -> Result<LinkCheck, WebError> {
+        Ok(LinkCheck {
+            status,
+            url: Url::parse(url).unwrap(),
+            etag: None,
+        })
+    }
+
+    #[rstest(
+        url,
+        result,
+        expected,
+        case("https://example.org/", check(200, "https://example.org/"), LinkStatus::Healthy),
+        case(
+            "https://example.org/",
+            check(200, "https://www.example.org/home"),
+            LinkStatus::Healthy
+        ),
+        case(
+            "https://example.org/",
+            check(404, "https://example.org/"),
+            LinkStatus::Dead("status 404".into())
+        ),
+        case(
+            "https://example.org/",
+            Err(WebError::Other("connection refused".into())),
+            LinkStatus::Dead("connection refused".into())
+        ),
+        case(
+            "https://example.org/",
+            check(200, "https://example.com/"),
+            LinkStatus::Moved(Url::parse("https://example.com/").unwrap())
+        ),
+        case(
+            "http://example.org/docs",
+            check(200, "https://example.org/docs/"),
+            LinkStatus::HttpsUpgrade(Url::parse("https://example.org/docs").unwrap())
+        )
+    )]
+    fn classify_should_return_expected_status(
+        url: &str,
+        result: Result<LinkCheck, WebError>,
+        expected: LinkStatus,
+    ) {
+        let actual = classify(&Url::parse(url).unwrap(), result);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn find_links_should_return_all_referenced_links() {
+        let mut data = PackageData::new("test-package");
+        data.metadata_mut().set_project_url("https://example.org");
+        data.metadata_mut().set_license(LicenseType::Location(
+            Url::parse("https://example.org/license").unwrap(),
+        ));
+        let mut choco = ChocolateyMetadata::new();
+        choco.issues_url = Some(Url::parse("https://example.org/issues").unwrap());
+        data.metadata_mut().set_chocolatey(choco);
+        let mut updater = ChocolateyUpdaterData::new();
+        updater.parse_url = Some(ChocolateyParseUrl::Url(
+            Url::parse("https://example.org/releases").unwrap(),
+        ));
+        data.updater_mut().set_chocolatey(updater);
+        let mut winget = UpdaterUrls::new();
+        winget.parse_url = Some(ParseUrl::Url(
+            Url::parse("https://example.org/winget").unwrap(),
+        ));
+        data.updater_mut().set_winget(winget);
+
+        let actual: Vec<(&str, String)> = find_links(&data)
+            .into_iter()
+            .map(|(key, url)| (key, url.to_string()))
+            .collect();
+
+        assert_eq!(
+            actual,
+            [
+                ("metadata.project_url", "https://example.org/".to_owned()),
+                ("metadata.license", "https://example.org/license".to_owned()),
+                (
+                    "metadata.chocolatey.issues_url",
+                    "https://example.org/issues".to_owned()
+                ),
+                (
+                    "updater.chocolatey.parse_url",
+                    "https://example.org/releases".to_owned()
+                ),
+                (
+                    "updater.winget.parse_url",
+                    "https://example.org/winget".to_owned()
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn fix_links_should_replace_links_with_and_without_trailing_slash() {
+        let text =
+            "project_url = \"http://example.org\"\nissues_url = \"http://example.org/issues\"";
+        let fixes = [
+            (
+                Url::parse("http://example.org").unwrap(),
+                Url::parse("https://example.org").unwrap(),
+            ),
+            (
+                Url::parse("http://example.org/issues").unwrap(),
+                Url::parse("https://example.org/issues").unwrap(),
+            ),
+        ];
+
+        let actual = fix_links(text, &fixes);
+
+        assert_eq!(
+            actual,
+            "project_url = \"https://example.org\"\nissues_url = \"https://example.org/issues\""
+        );
+    }
+}
@@ -0,0 +1,563 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for finding historical versions of a package, and for
+//! building the package of each of these versions. This is used when
+//! onboarding a package that needs previous versions published.
+//!
+//! The historical versions are read from the source configured in the updater
+//! data of the package, either the releases of the repository (*ie: the GitHub
+//! releases*) or the (possibly paginated) release listing of the upstream
+//! software.
+
+use std::collections::BTreeMap;
+
+use aer_upd::context::UpdateContext;
+use aer_upd::data::{PackageData, ParseUrl, UpdaterSource, UpdaterUrls, Url, Versions};
+use aer_upd::web::errors::WebError;
+use aer_upd::web::{LinkElement, WebRequest, WebResponse};
+use log::{info, warn};
+use regex::Regex;
+
+use crate::build::{self, BuildOptions, BuildOutput};
+use crate::update::{self, FoundLinks, TargetLinks};
+
+/// Finds the historical versions of the package, and returns the links that
+/// was found for each version. The links of every version are matched using
+/// the regexes of the primary target of the package, the same way as when
+/// updating the package.
+///
+/// Packages using a repository as the source reads the releases of the
+/// repository, while other packages walks the release listing at the url to
+/// parse. Pages are followed through links marked with `rel="next"` (*ie: the
+/// `Link` header of the GitHub and GitLab APIs, or the links on the release
+/// listing*) until no more pages are found or `max_pages` have been read.
+pub fn find_historical_links(
+    request: &WebRequest,
+    data: &PackageData,
+    max_pages: usize,
+) -> Result<Vec<FoundLinks>, Box<dyn std::error::Error>> {
+    let urls = data.updater().primary_urls().unwrap_or_default();
+    let request = update::package_request(request, data);
+
+    match data.updater().source {
+        UpdaterSource::GitHub | UpdaterSource::GitLab | UpdaterSource::SourceForge => {
+            find_release_links(&request, data, &urls, max_pages)
+        }
+        _ => find_page_links(&request, data, &urls, max_pages),
+    }
+}
+
+/// Selects the `count` newest unique versions out of the specified links, and
+/// returns them ordered from the oldest to the newest version. This is the
+/// order packages are expected to be published in.
+pub fn select_versions(mut links: Vec<FoundLinks>, count: usize) -> Vec<FoundLinks> {
+    links.retain(|found| found.version().is_some());
+    links.sort_by_key(|found| found.version().cloned());
+    links.dedup_by(|a, b| a.version() == b.version());
+
+    let skip = links.len().saturating_sub(count);
+    links.into_iter().skip(skip).collect()
+}
+
+/// Builds the package of every historical version using the links that was
+/// found for the version, in the order the versions are specified. The
+/// package data is loaded again for every version, as building a package
+/// updates the package data with the version that was built. Each package is
+/// created in a directory named after the version inside the output directory
+/// of the options, to avoid the files of one version being included in the
+/// package of another version.
+pub fn build_versions<F>(
+    context: &UpdateContext,
+    load_data: F,
+    versions: Vec<FoundLinks>,
+    options: &BuildOptions,
+) -> Result<Vec<BuildOutput>, Box<dyn std::error::Error>>
+where
+    F: Fn() -> Result<PackageData, Box<dyn std::error::Error>>,
+{
+    let mut outputs = vec![];
+
+    for found in versions {
+        let mut data = load_data()?;
+        let found = resolve_release_page(context.request(), &data, found)?;
+        let version = match found.version() {
+            Some(version) => version.to_string(),
+            None => continue,
+        };
+        context
+            .logger()
+            .info(format_args!("Building the package of version {}", version));
+
+        let options = BuildOptions {
+            output_dir: options.output_dir.join(&version),
+            ..options.clone()
+        };
+        outputs.push(build::build_package(
+            context,
+            &mut data,
+            &TargetLinks::from(found),
+            &options,
+        )?);
+    }
+
+    Ok(outputs)
+}
+
+fn find_release_links(
+    request: &WebRequest,
+    data: &PackageData,
+    urls: &UpdaterUrls,
+    max_pages: usize,
+) -> Result<Vec<FoundLinks>, Box<dyn std::error::Error>> {
+    let repository = match data.updater().repository {
+        Some(ref repository) => repository,
+        None => {
+            return Err(Box::new(WebError::Other(
+                "No repository have been specified to read releases from!".into(),
+            )))
+        }
+    };
+
+    let releases = match data.updater().source {
+        UpdaterSource::GitLab => request.get_gitlab_release_history(repository, max_pages)?,
+        UpdaterSource::SourceForge => request.get_sourceforge_releases(repository)?,
+        _ => request.get_github_release_history(repository, max_pages)?,
+    };
+
+    releases
+        .into_iter()
+        .filter(|release| update::allows_release(release, data))
+        .map(|release| {
+            let found = FoundLinks {
+                release: Some(release.link),
+                ..FoundLinks::default()
+            };
+            update::match_links(data, urls, found, &release.assets)
+        })
+        .collect()
+}
+
+fn find_page_links(
+    request: &WebRequest,
+    data: &PackageData,
+    urls: &UpdaterUrls,
+    max_pages: usize,
+) -> Result<Vec<FoundLinks>, Box<dyn std::error::Error>> {
+    let (url, regexes) = match urls.parse_url {
+        Some(ParseUrl::UrlWithRegex { ref url, ref regex }) => (url.clone(), vec![regex.clone()]),
+        Some(ParseUrl::Url(ref url)) => (url.clone(), urls.regexes().values().cloned().collect()),
+        None => {
+            return Err(Box::new(WebError::Other(
+                "No url have been specified to parse!".into(),
+            )))
+        }
+    };
+
+    let regexes = regexes
+        .iter()
+        .map(|re| Regex::new(re))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut visited: Vec<Url> = vec![];
+    let mut next = Some(url);
+    let mut versions: BTreeMap<Versions, Vec<LinkElement>> = BTreeMap::new();
+
+    while let Some(url) = next.take() {
+        if visited.len() >= max_pages {
+            warn!("Maximum number of pages ({}) reached, stopping!", max_pages);
+            break;
+        }
+        if visited.contains(&url) {
+            break;
+        }
+
+        info!("Parsing links on '{}'", url);
+        let (_, links) = request.get_html_response(url.as_str())?.read(None)?;
+        next = find_next_page(&links);
+        visited.push(url);
+
+        for link in links
+            .into_iter()
+            .filter_map(|link| with_version(link, &regexes))
+        {
+            let version = link.version.clone().expect("Version should always be set");
+            versions.entry(version).or_default().push(link);
+        }
+    }
+
+    versions
+        .into_values()
+        .map(|links| match urls.parse_url {
+            // The links are the release pages of each version, the files of
+            // the version are read from the release page before building.
+            Some(ParseUrl::UrlWithRegex { .. }) => Ok(FoundLinks {
+                release: links.into_iter().next(),
+                ..FoundLinks::default()
+            }),
+            _ => update::match_links(data, urls, FoundLinks::default(), &links),
+        })
+        .collect()
+}
+
+/// Reads the links on the release page of the version, when the version was
+/// found by matching the release pages of the package.
+fn resolve_release_page(
+    request: &WebRequest,
+    data: &PackageData,
+    found: FoundLinks,
+) -> Result<FoundLinks, Box<dyn std::error::Error>> {
+    let urls = data.updater().primary_urls().unwrap_or_default();
+    let release = match (&urls.parse_url, &found.release) {
+        (Some(ParseUrl::UrlWithRegex { .. }), Some(release))
+            if found.architectures.is_empty() && found.others.is_empty() =>
+        {
+            release.clone()
+        }
+        _ => return Ok(found),
+    };
+
+    let request = update::package_request(request, data);
+    info!("Parsing links on '{}'", release.link);
+    let (_, links) = request
+        .get_html_response(release.link.as_str())?
+        .read(None)?;
+
+    update::match_links(data, &urls, found, &links)
+}
+
+fn find_next_page(links: &[LinkElement]) -> Option<Url> {
+    links
+        .iter()
+        .find(|link| {
+            link.attributes
+                .get("rel")
+                .into_iter()
+                .flat_map(|rel| rel.split_whitespace())
+                .any(|val| val.eq_ignore_ascii_case("next"))
+        })
+        .map(|link| link.link.clone())
+}
+
+fn with_version(mut link: LinkElement, regexes: &[Regex]) -> Option<LinkElement> {
+    let version = regexes.iter().find_map(|re| {
+        let captures = re.captures(link.link.as_str())?;
+        Versions::parse(captures.name("version")?.as_str()).ok()
+    })?;
+
+    link.version = Some(version);
+    Some(link)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::path::Path;
+
+    use aer_upd::data::chocolatey::ChocolateyMetadata;
+    use aer_upd::data::testing::{PackageDataFixture, FIXTURE_LICENSE_URL};
+    use aer_upd::data::{Architecture, LicenseType};
+    use aer_upd::web::fixtures::{FixtureMode, Fixtures};
+    use aer_upd::web::sources::github::releases_url;
+    use aer_upd::web::LinkType;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::*;
+
+    fn link(url: &str, version: Option<&str>) -> LinkElement {
+        let mut link = LinkElement::new(Url::parse(url).unwrap(), LinkType::Unknown);
+        link.version = version.map(|v| Versions::parse(v).unwrap());
+        link
+    }
+
+    fn release(url: &str, version: Option<&str>) -> FoundLinks {
+        FoundLinks {
+            release: Some(link(url, version)),
+            ..FoundLinks::default()
+        }
+    }
+
+    #[test]
+    fn select_versions_should_return_newest_versions_in_ascending_order() {
+        let links = vec![
+            release("https://test.com/3.0.0", Some("3.0.0")),
+            release("https://test.com/1.0.0", Some("1.0.0")),
+            release("https://test.com/2.1.0", Some("2.1.0")),
+            release("https://test.com/2.0.0", Some("2.0.0")),
+        ];
+
+        let actual = select_versions(links, 3);
+
+        assert_eq!(
+            actual,
+            [
+                release("https://test.com/2.0.0", Some("2.0.0")),
+                release("https://test.com/2.1.0", Some("2.1.0")),
+                release("https://test.com/3.0.0", Some("3.0.0")),
+            ]
+        );
+    }
+
+    #[test]
+    fn select_versions_should_ignore_duplicate_and_missing_versions() {
+        let links = vec![
+            release("https://test.com/1.0.0", Some("1.0.0")),
+            release("https://test.com/unknown", None),
+            release("https://test.com/1.0.0/other", Some("1.0.0")),
+        ];
+
+        let actual = select_versions(links, 5);
+
+        assert_eq!(actual, [release("https://test.com/1.0.0", Some("1.0.0"))]);
+    }
+
+    #[test]
+    fn find_next_page_should_use_link_marked_as_next() {
+        let mut next = link("https://test.com/releases?page=2", None);
+        next.attributes.insert("rel".into(), "nofollow next".into());
+        let links = vec![link("https://test.com/releases/v1.0.0", None), next];
+
+        let actual = find_next_page(&links);
+
+        assert_eq!(
+            actual,
+            Some(Url::parse("https://test.com/releases?page=2").unwrap())
+        );
+    }
+
+    #[test]
+    fn with_version_should_set_version_from_matching_regex() {
+        let regexes = [Regex::new(r"/v(?P<version>[\d\.]+)$").unwrap()];
+
+        let actual = with_version(link("https://test.com/releases/v1.2.3", None), &regexes);
+
+        assert_eq!(
+            actual,
+            Some(link("https://test.com/releases/v1.2.3", Some("1.2.3")))
+        );
+    }
+
+    /// Starts a web server answering every request with the path that was
+    /// requested, returning the url of the server.
+    fn file_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut request = vec![];
+                let mut buffer = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let read = stream.read(&mut buffer).unwrap();
+                    if read == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buffer[..read]);
+                }
+                let request = String::from_utf8_lossy(&request);
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: \
+                     {}\r\nConnection: close\r\n\r\n{}",
+                    path.len(),
+                    path
+                );
+            }
+        });
+
+        url
+    }
+
+    /// Creates the fixtures of the GitHub releases of the `WormieCorp/app`
+    /// repository, where each page links to the next page and the assets of
+    /// each release are hosted by the specified server.
+    fn github_fixtures(dir: &Path, server: &str, pages: &[&[&str]]) -> Fixtures {
+        let fixtures = Fixtures::new(dir, FixtureMode::Replay);
+        let page_url = |page: usize| {
+            let mut url = releases_url("WormieCorp/app").unwrap();
+            url.query_pairs_mut().append_pair("per_page", "100");
+            if page > 1 {
+                url.query_pairs_mut().append_pair("page", &page.to_string());
+            }
+            url
+        };
+
+        for (index, versions) in pages.iter().enumerate() {
+            let url = page_url(index + 1);
+            let releases: Vec<_> = versions
+                .iter()
+                .map(|version| {
+                    json!({
+                        "tag_name": format!("v{}", version),
+                        "html_url": format!(
+                            "https://github.com/WormieCorp/app/releases/tag/v{}",
+                            version
+                        ),
+                        "assets": [{
+                            "name": format!("app-{}-x64.exe", version),
+                            "browser_download_url": format!("{}/app-{}-x64.exe", server, version),
+                        }],
+                    })
+                })
+                .collect();
+            let mut headers = json!({ "content-type": "application/json" });
+            if index + 1 < pages.len() {
+                headers["link"] = json!(format!("<{}>; rel=\"next\"", page_url(index + 2)));
+            }
+            let recorded = json!({
+                "url": url.as_str(),
+                "final_url": url.as_str(),
+                "status": 200,
+                "headers": headers,
+                "body": json!(releases).to_string(),
+            });
+            std::fs::write(fixtures.path_for(&url), recorded.to_string()).unwrap();
+        }
+
+        fixtures
+    }
+
+    #[test]
+    fn find_historical_links_should_read_releases_of_repository() {
+        let dir = std::env::temp_dir().join("aer-backfill-releases-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let fixtures = github_fixtures(&dir, "https://example.org", &[&["2.0.0", "1.0.0"]]);
+        let request = WebRequest::create().with_fixtures(fixtures);
+        let data = PackageDataFixture::new("backfill-test")
+            .with_updater_github("WormieCorp/app")
+            .with_updater_regex("arch64", r"app-(?P<version>[\d\.]+)-x64\.exe$")
+            .build();
+
+        let actual = find_historical_links(&request, &data, 1).unwrap();
+
+        let actual: Vec<_> = actual
+            .iter()
+            .map(|found| {
+                (
+                    found.version().map(|version| version.to_string()),
+                    found
+                        .arch(Architecture::X64)
+                        .map(|link| link.link.to_string()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            actual,
+            [
+                (
+                    Some("2.0.0".into()),
+                    Some("https://example.org/app-2.0.0-x64.exe".into())
+                ),
+                (
+                    Some("1.0.0".into()),
+                    Some("https://example.org/app-1.0.0-x64.exe".into())
+                ),
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[rstest(
+        max_pages,
+        expected,
+        case(1, &["3.0.0"]),
+        case(2, &["3.0.0", "2.0.0", "1.0.0"]),
+        case(10, &["3.0.0", "2.0.0", "1.0.0", "0.9.0"])
+    )]
+    fn find_historical_links_should_follow_pages_of_releases(max_pages: usize, expected: &[&str]) {
+        let dir = std::env::temp_dir().join(format!("aer-backfill-pages-test-{}", max_pages));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let fixtures = github_fixtures(
+            &dir,
+            "https://example.org",
+            &[&["3.0.0"], &["2.0.0", "1.0.0"], &["0.9.0"]],
+        );
+        let request = WebRequest::create().with_fixtures(fixtures);
+        let data = PackageDataFixture::new("backfill-test")
+            .with_updater_github("WormieCorp/app")
+            .with_updater_regex("arch64", r"app-(?P<version>[\d\.]+)-x64\.exe$")
+            .build();
+
+        let actual = find_historical_links(&request, &data, max_pages).unwrap();
+
+        let actual: Vec<_> = actual
+            .iter()
+            .filter_map(|found| found.version().map(|version| version.to_string()))
+            .collect();
+        assert_eq!(actual, expected);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn build_versions_should_create_package_of_each_selected_version() {
+        let dir = std::env::temp_dir().join("aer-backfill-build-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let server = file_server();
+        let fixtures = github_fixtures(&dir, &server, &[&["3.0.0"], &["2.0.0", "1.0.0"]]);
+        let request = WebRequest::create().with_fixtures(fixtures);
+        let package_data = || {
+            PackageDataFixture::new("backfill-test")
+                .with_license(LicenseType::Location(
+                    Url::parse(FIXTURE_LICENSE_URL).unwrap(),
+                ))
+                .with_choco(|choco| {
+                    *choco = ChocolateyMetadata::with_authors(&["AdmiringWorm"]);
+                    choco.set_description_str("Package used when testing the back-fill process");
+                })
+                .with_updater_github("WormieCorp/app")
+                .with_updater_regex("arch64", r"app-(?P<version>[\d\.]+)-x64\.exe$")
+                .build()
+        };
+        let output_dir = dir.join("output");
+        let options = BuildOptions {
+            output_dir: output_dir.clone(),
+            ..BuildOptions::default()
+        };
+        let versions = select_versions(
+            find_historical_links(&request, &package_data(), 10).unwrap(),
+            2,
+        );
+
+        let actual = build_versions(
+            &UpdateContext::new().for_package("backfill-test"),
+            || Ok(package_data()),
+            versions,
+            &options,
+        )
+        .unwrap();
+
+        let packages: Vec<_> = actual
+            .iter()
+            .map(|output| output.package_file.clone())
+            .collect();
+        assert_eq!(
+            packages,
+            [
+                output_dir.join("2.0.0").join("backfill-test.2.0.0.nupkg"),
+                output_dir.join("3.0.0").join("backfill-test.3.0.0.nupkg"),
+            ]
+        );
+        for (output, version) in actual.iter().zip(&["2.0.0", "3.0.0"]) {
+            assert!(output.package_file.is_file());
+            let nuspec =
+                std::fs::read_to_string(output.package_dir.join("backfill-test.nuspec")).unwrap();
+            assert!(nuspec.contains(&format!("<version>{}</version>", version)));
+            assert_eq!(output.files.len(), 1);
+            assert_eq!(
+                output.files[0].link.link.as_str(),
+                format!("{}/app-{}-x64.exe", server, version)
+            );
+            assert_eq!(
+                std::fs::read_to_string(&output.files[0].path).unwrap(),
+                format!("/app-{}-x64.exe", version)
+            );
+        }
+        assert!(!output_dir.join("1.0.0").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
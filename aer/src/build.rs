@@ -0,0 +1,1388 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the build step of the update process, responsible for downloading
+//! the files that was found, running the scripts of the package, rendering the
+//! script templates and creating the package itself.
+
+use std::path::{Path, PathBuf};
+
+use aer_archive::{Archive, ArchiveError};
+use aer_pkg::chocolatey::ChocolateyPackage;
+use aer_pkg::nuget::NugetPackage;
+use aer_upd::context::UpdateContext;
+use aer_upd::data::chocolatey::ChocolateyUpdaterType;
+use aer_upd::data::{LicenseType, PackageData, UpdaterTarget, VersionSource, Versions};
+use aer_upd::generators::chocolatey::{NuspecGenerator, TOOLS_DIR};
+use aer_upd::generators::nuget::NugetGenerator;
+use aer_upd::generators::templates::{self, TemplateValues};
+use aer_upd::generators::verification::{
+    EmbeddedFile, VerificationGenerator, LEGAL_DIR, LICENSE_FILE, VERIFICATION_FILE,
+};
+use aer_upd::generators::winget::{WingetArchitecture, WingetGenerator, WingetInstaller};
+use aer_upd::generators::PackageGenerator;
+use aer_upd::observer::Stage;
+use aer_upd::runners::{RunnerCombiner, RunnerData};
+use aer_upd::web::checksum::{find_checksum, ChecksumEntry};
+use aer_upd::web::delta::DeltaOptions;
+use aer_upd::web::errors::WebError;
+use aer_upd::web::virustotal::DetectionRatio;
+use aer_upd::web::{LinkElement, ResponseType};
+use aer_upd::{features, runners};
+use aer_version::binary::{self, BinaryVersionError, BinaryVersionKind};
+
+use crate::cache::{CacheEntry, DownloadCache};
+use crate::codes::{self, CodedError};
+use crate::existing::ExistingPackage;
+use crate::reputation::{self, VirusTotalSettings};
+use crate::resolved::ResolvedUpdate;
+use crate::signature::{self, SignatureCheck};
+use crate::update::{FoundLinks, TargetLinks};
+use crate::ChecksumType;
+
+/// The directory inside the cache directory where the previously downloaded
+/// files are kept when using delta downloads.
+const DELTA_CACHE_DIR: &str = "delta";
+
+/// The directory inside the output directory where the plain NuGet package is
+/// created, to avoid conflicting with the Chocolatey package of the same
+/// identifier.
+const NUGET_DIR: &str = "nuget";
+
+/// A file that was downloaded during the build of a package.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadedFile {
+    /// The link the file was downloaded from.
+    pub link: LinkElement,
+    /// The path the file was downloaded to.
+    pub path: PathBuf,
+    /// The checksum of the downloaded file.
+    pub checksum: String,
+    /// The algorithm used to generate the checksum.
+    pub checksum_type: ChecksumType,
+    /// The results of verifying the signatures of the file.
+    pub signatures: Vec<SignatureCheck>,
+    /// The directory the file was extracted to, this is only set for archives
+    /// of packages using the `Archive` updater type.
+    pub extracted: Option<PathBuf>,
+    /// The number of anti virus engines on VirusTotal that flagged the file,
+    /// only set when the file have been looked up on VirusTotal.
+    pub detection: Option<DetectionRatio>,
+}
+
+/// The options that controls how a package is built.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildOptions {
+    /// The directory the package will be created in.
+    pub output_dir: PathBuf,
+    /// The directory that relative paths in the package data are resolved
+    /// against, this is normally the directory of the package data file.
+    pub base_dir: PathBuf,
+    /// The algorithm used when generating checksums of downloaded files.
+    pub checksum_type: ChecksumType,
+    /// Re-use the unchanged chunks of the previously downloaded files in the
+    /// cache directory, and only download the chunks that have changed.
+    pub delta: bool,
+    /// Record the etag and last modified date of the downloaded files in the
+    /// cache directory, and re-use the previously downloaded files when the
+    /// server reports that they have not changed.
+    pub download_cache: bool,
+    /// The directory containing an existing package that is updated in place,
+    /// instead of generating the package from the package data. Only the
+    /// version, urls and checksums of the existing package are replaced.
+    pub existing_dir: Option<PathBuf>,
+    /// Look up the downloaded files on VirusTotal, failing the build when a
+    /// file have been flagged by more anti virus engines than the threshold
+    /// allows. The files are only looked up when the API key is available.
+    pub virustotal: Option<VirusTotalSettings>,
+}
+
+impl Default for BuildOptions {
+    fn default() -> Self {
+        Self {
+            output_dir: PathBuf::from("."),
+            base_dir: PathBuf::from("."),
+            checksum_type: ChecksumType::default(),
+            delta: false,
+            download_cache: false,
+            existing_dir: None,
+            virustotal: None,
+        }
+    }
+}
+
+/// The result of building a single package.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildOutput {
+    /// The directory the package was created in.
+    pub package_dir: PathBuf,
+    /// The path to the package archive (*ie: the `.nupkg` file*) that was
+    /// packed from the package directory.
+    pub package_file: PathBuf,
+    /// The files that was downloaded while building the package.
+    pub files: Vec<DownloadedFile>,
+    /// The directory the winget manifests was created in, only set when the
+    /// package data contains winget metadata.
+    pub manifest_dir: Option<PathBuf>,
+    /// The path to the plain NuGet package archive, only set when the package
+    /// data contains NuGet metadata.
+    pub nuget_file: Option<PathBuf>,
+    /// The resolved result of the update, holding the version the package
+    /// was built with together with the links and checksums of the files.
+    pub resolved: ResolvedUpdate,
+}
+
+/// Builds the package based on the links that was found, by downloading the
+/// architecture files, running the scripts of the package and generating the
+/// package files. The package is built from the links of the primary target,
+/// while the winget manifests uses the links of the winget target when these
+/// differs from the primary links. The version of the package is updated to the
+/// version that was found before the scripts are run, and the script templates
+/// are rendered using the links and checksums of the downloaded files.
+pub fn build_package(
+    context: &UpdateContext,
+    data: &mut PackageData,
+    links: &TargetLinks,
+    options: &BuildOptions,
+) -> Result<BuildOutput, Box<dyn std::error::Error>> {
+    let default = FoundLinks::default();
+    let found = links.primary().unwrap_or(&default);
+
+    if !options.checksum_type.is_chocolatey_supported() {
+        return Err(format!(
+            "The checksum type '{}' can not be used in Chocolatey packages!",
+            options.checksum_type
+        )
+        .into());
+    }
+
+    let files = context.stage(Stage::Download, || {
+        download_files(context, data, found, options)
+    })?;
+
+    let version = match read_file_version(context, data, &files)? {
+        Some(version) => Some(version),
+        None => found.version().cloned(),
+    };
+
+    if let Some(ref version) = version {
+        let id = data
+            .updater()
+            .package_id(data.metadata().id(), version)
+            .into_owned();
+        if id != data.metadata().id() {
+            context
+                .logger()
+                .info(format_args!("Using the pre-release package id '{}'", id));
+            data.metadata_mut().set_id(&id);
+        }
+        let mut choco = data.metadata().chocolatey().into_owned();
+        choco.version = version.clone();
+        data.metadata_mut().set_chocolatey(choco);
+        if data.metadata().has_winget() {
+            let mut winget = data.metadata().winget().into_owned();
+            winget.version = version.clone();
+            data.metadata_mut().set_winget(winget);
+        }
+        if data.metadata().has_nuget() {
+            let mut nuget = data.metadata().nuget().into_owned();
+            nuget.version = version.clone();
+            data.metadata_mut().set_nuget(nuget);
+        }
+    }
+
+    let resolved = ResolvedUpdate::new(found, &files, version);
+
+    for script in data.updater().scripts.clone() {
+        let script = options.base_dir.join(script);
+        if let Some(feature) = features::for_script(&script) {
+            features::require(feature, format_args!("Running '{}'", script.display()))?;
+        }
+        runners::run_script_in(context, script, &mut ScriptData::new(data, &resolved))?;
+    }
+
+    if data.metadata().has_winget() || data.metadata().has_nuget() {
+        detect_license(context, data);
+    }
+
+    let (package_dir, package_file) = context.stage(Stage::CreatePackage, || {
+        create_package(context, data, &resolved, &files, options)
+    })?;
+    let manifest_dir = if data.metadata().has_winget() {
+        let winget_found = links
+            .get(UpdaterTarget::Winget)
+            .filter(|winget_found| *winget_found != found);
+        let winget_files = match winget_found {
+            Some(winget_found) => Some(context.stage(Stage::Download, || {
+                download_files(context, data, winget_found, options)
+            })?),
+            None => None,
+        };
+        Some(context.stage(Stage::CreatePackage, || {
+            create_manifests(
+                context,
+                data,
+                winget_found.unwrap_or(found),
+                winget_files.as_deref().unwrap_or(&files),
+                options,
+            )
+        })?)
+    } else {
+        None
+    };
+    let nuget_file = if data.metadata().has_nuget() {
+        Some(context.stage(Stage::CreatePackage, || {
+            create_nuget_package(context, data, options)
+        })?)
+    } else {
+        None
+    };
+
+    Ok(BuildOutput {
+        package_dir,
+        package_file,
+        files,
+        manifest_dir,
+        nuget_file,
+        resolved,
+    })
+}
+
+/// Adds the SPDX expression of the license when the package only specifies
+/// the location of the license, as the expression is preferred by winget and
+/// NuGet. The license is kept as is when the expression can not be detected.
+fn detect_license(context: &UpdateContext, data: &mut PackageData) {
+    let url = match data.metadata().license() {
+        LicenseType::Location(url) => url.clone(),
+        _ => return,
+    };
+
+    match context.request().detect_license_from_url(url.as_str()) {
+        Ok(Some(expression)) => {
+            context.logger().info(format_args!(
+                "Using the detected license expression '{}'",
+                expression
+            ));
+            data.metadata_mut()
+                .set_license(LicenseType::ExpressionAndLocation {
+                    expression: expression.into(),
+                    url,
+                });
+        }
+        Ok(None) => context.logger().warn(format_args!(
+            "The license at '{}' do not match any known license, the location is used instead",
+            url
+        )),
+        Err(err) => context.logger().warn(format_args!(
+            "Unable to detect the license from '{}': {}",
+            url, err
+        )),
+    }
+}
+
+/// Reads the version embedded in the downloaded files when the package reads
+/// its version from the files, the first file containing a version is used.
+fn read_file_version(
+    context: &UpdateContext,
+    data: &PackageData,
+    files: &[DownloadedFile],
+) -> Result<Option<Versions>, CodedError> {
+    let kind = match data.updater().version_source {
+        VersionSource::Link => return Ok(None),
+        VersionSource::ProductVersion => BinaryVersionKind::ProductVersion,
+        VersionSource::FileVersion => BinaryVersionKind::FileVersion,
+    };
+
+    for file in files {
+        match binary::read_version(&file.path, kind) {
+            Ok(version) => {
+                context.logger().info(format_args!(
+                    "Read version {} from '{}'",
+                    version,
+                    file_name(&file.path).to_string_lossy()
+                ));
+                return Ok(Some(version));
+            }
+            Err(BinaryVersionError::Unsupported) => continue,
+            Err(err) => {
+                return Err(CodedError::new(
+                    codes::FILE_VERSION,
+                    format!(
+                        "The version of '{}' could not be read: {}",
+                        file_name(&file.path).to_string_lossy(),
+                        err
+                    ),
+                ))
+            }
+        }
+    }
+
+    Err(CodedError::new(
+        codes::FILE_VERSION,
+        "None of the downloaded files is an executable or installer containing a version!",
+    ))
+}
+
+fn download_files(
+    context: &UpdateContext,
+    data: &PackageData,
+    found: &FoundLinks,
+    options: &BuildOptions,
+) -> Result<Vec<DownloadedFile>, Box<dyn std::error::Error>> {
+    let checksum_type = options.checksum_type;
+    let mut files = vec![];
+    let checksums = match found.checksums {
+        Some(ref link) => {
+            context.check_cancelled()?;
+            Some(context.request().get_checksum_file(link.link.as_str())?)
+        }
+        None => None,
+    };
+    let virustotal = options
+        .virustotal
+        .as_ref()
+        .and_then(|settings| settings.api_key().map(|api_key| (settings, api_key)));
+    let mut cache = if options.download_cache {
+        Some(DownloadCache::load(
+            context.cache_dir(),
+            context.package_id(),
+        )?)
+    } else {
+        None
+    };
+
+    for link in found.architectures.values() {
+        context.check_cancelled()?;
+        context.progress(format_args!("Downloading '{}'", link.link));
+
+        let (path, checksum) = match delta_name(options, link) {
+            Some(name) => {
+                let path = download_delta(context, link, &name)?;
+                let checksum = checksum_type.generate(&path)?;
+                (path, checksum)
+            }
+            None => match cache {
+                Some(ref mut cache) => download_cached(context, cache, link, checksum_type)?,
+                None => download_file(context, link, checksum_type)?,
+            },
+        };
+        if let Some(ref checksums) = checksums {
+            verify_checksum(context, link, &path, &checksum, checksum_type, checksums)?;
+        }
+        let settings = &data.updater().signature;
+        let signatures = if settings.is_enabled() {
+            signature::verify_file(context, settings, &options.base_dir, link, &path)?
+        } else {
+            vec![]
+        };
+        let detection = match virustotal {
+            Some((settings, ref api_key)) => reputation::check_file(
+                context,
+                settings,
+                api_key,
+                &path,
+                (&checksum, checksum_type),
+            )?,
+            None => None,
+        };
+        let extracted = if data.updater().chocolatey().updater_type
+            == ChocolateyUpdaterType::Archive
+            && Archive::is_archive(&path)
+        {
+            Some(extract_archive(context, &path)?)
+        } else {
+            None
+        };
+        context.logger().info(format_args!(
+            "Downloaded '{}' ({}: {})",
+            path.display(),
+            checksum_type,
+            checksum
+        ));
+
+        files.push(DownloadedFile {
+            link: link.clone(),
+            path,
+            checksum,
+            checksum_type,
+            signatures,
+            extracted,
+            detection,
+        });
+    }
+
+    if let Some(cache) = cache {
+        cache.save()?;
+    }
+
+    Ok(files)
+}
+
+/// Extracts the downloaded archive to a directory next to the archive, named
+/// after the archive without its extension.
+fn extract_archive(context: &UpdateContext, path: &Path) -> Result<PathBuf, ArchiveError> {
+    let target = path.with_extension("");
+    let files = Archive::open(path)?.extract(&target)?;
+    context.logger().info(format_args!(
+        "Extracted {} files from '{}'",
+        files.len(),
+        file_name(path).to_string_lossy()
+    ));
+
+    Ok(target)
+}
+
+fn download_file(
+    context: &UpdateContext,
+    link: &LinkElement,
+    checksum_type: ChecksumType,
+) -> Result<(PathBuf, String), Box<dyn std::error::Error>> {
+    match context
+        .request()
+        .get_binary_response(link.link.as_str(), None, None)?
+    {
+        ResponseType::New(mut response, _) => {
+            response.set_work_dir(context.work_dir());
+            Ok(response.read_with_checksum(None, checksum_type)?)
+        }
+        ResponseType::Updated(status) => Err(Box::new(WebError::Other(format!(
+            "The server responded with status {} when downloading '{}'",
+            status, link.link
+        )))),
+    }
+}
+
+/// Downloads the file of the link using a conditional request, with the etag
+/// and last modified date recorded in the cache. The previously downloaded
+/// file is copied to the work directory when the server reports that the file
+/// have not changed.
+fn download_cached(
+    context: &UpdateContext,
+    cache: &mut DownloadCache,
+    link: &LinkElement,
+    checksum_type: ChecksumType,
+) -> Result<(PathBuf, String), Box<dyn std::error::Error>> {
+    let url = link.link.as_str();
+    let entry = cache.get(url).filter(|entry| entry.has_validators());
+
+    match context.request().get_binary_response(
+        url,
+        entry.and_then(|entry| entry.etag.as_deref()),
+        entry.and_then(|entry| entry.last_modified.as_deref()),
+    )? {
+        ResponseType::New(mut response, _) => {
+            let etag = response.etag().map(String::from);
+            let last_modified = response.last_modified().map(String::from);
+            let final_url = response.final_url().to_string();
+            response.set_work_dir(context.work_dir());
+            let (path, checksum) = response.read_with_checksum(None, checksum_type)?;
+
+            cache.store(
+                url,
+                CacheEntry {
+                    etag,
+                    last_modified,
+                    final_url: Some(final_url).filter(|final_url| final_url != url),
+                    checksum: checksum.clone(),
+                    checksum_type: checksum_type.to_string(),
+                    file: String::new(),
+                },
+                &path,
+            )?;
+
+            Ok((path, checksum))
+        }
+        ResponseType::Updated(status) => {
+            let entry = entry.ok_or_else(|| {
+                WebError::Other(format!(
+                    "The server responded with status {} when downloading '{}'",
+                    status, link.link
+                ))
+            })?;
+            let path = context.work_dir().join(&entry.file);
+            std::fs::copy(cache.file_path(entry), &path)?;
+            context.logger().info(format_args!(
+                "The file '{}' have not changed, re-using the previous download",
+                entry.file
+            ));
+
+            let checksum = if entry.checksum_type == checksum_type.to_string() {
+                entry.checksum.clone()
+            } else {
+                checksum_type.generate(&path)?
+            };
+
+            Ok((path, checksum))
+        }
+    }
+}
+
+/// Verifies the downloaded file against the checksum published in the
+/// checksum file of the package, the file is removed when the checksums do not
+/// match. Files without a published checksum are only reported as a warning.
+fn verify_checksum(
+    context: &UpdateContext,
+    link: &LinkElement,
+    path: &Path,
+    checksum: &str,
+    checksum_type: ChecksumType,
+    checksums: &[ChecksumEntry],
+) -> Result<(), WebError> {
+    let name = file_name(path).to_string_lossy();
+    let entry = find_checksum(checksums, &name).or_else(|| {
+        let segment = link.link.path_segments()?.next_back()?;
+        find_checksum(checksums, segment)
+    });
+    let entry = match entry {
+        Some(entry) => entry,
+        None => {
+            context.logger().warn(format_args!(
+                "No checksum was published for '{}', the file was not verified!",
+                name
+            ));
+            return Ok(());
+        }
+    };
+
+    let entry_type = entry.checksum_type.unwrap_or(checksum_type);
+    let actual = if entry_type == checksum_type {
+        checksum.to_owned()
+    } else {
+        entry_type.generate(path).map_err(WebError::IoError)?
+    };
+
+    if actual.eq_ignore_ascii_case(&entry.checksum) {
+        context.logger().info(format_args!(
+            "Verified '{}' against the published {} checksum",
+            name, entry_type
+        ));
+        Ok(())
+    } else {
+        let _ = std::fs::remove_file(path);
+        Err(WebError::ChecksumMismatch {
+            expected: entry.checksum.clone(),
+            actual,
+        })
+    }
+}
+
+/// Downloads the file using the previously downloaded file in the cache
+/// directory as the base, the downloaded file is copied back to the cache to
+/// be used as the base of the next update.
+fn download_delta(
+    context: &UpdateContext,
+    link: &LinkElement,
+    name: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let cache_dir = context
+        .cache_dir()
+        .join(DELTA_CACHE_DIR)
+        .join(context.package_id());
+    std::fs::create_dir_all(&cache_dir)?;
+    let previous = cache_dir.join(name);
+
+    let download = context.request().download_delta(
+        link.link.as_str(),
+        &previous,
+        &context.work_dir().join(name),
+        &DeltaOptions::default(),
+    )?;
+    if !download.full {
+        context.logger().info(format_args!(
+            "Re-used {} bytes of the previously downloaded '{}'",
+            download.reused, name
+        ));
+    }
+    std::fs::copy(&download.path, &previous)?;
+
+    Ok(download.path)
+}
+
+/// Returns the name the file of the link is cached as, nothing is returned
+/// when delta downloads are disabled or the name can not be resolved from the
+/// link.
+fn delta_name(options: &BuildOptions, link: &LinkElement) -> Option<String> {
+    if !options.delta {
+        return None;
+    }
+
+    link.link
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+}
+
+fn create_package(
+    context: &UpdateContext,
+    data: &PackageData,
+    resolved: &ResolvedUpdate,
+    files: &[DownloadedFile],
+    options: &BuildOptions,
+) -> Result<(PathBuf, PathBuf), Box<dyn std::error::Error>> {
+    let values = template_values(data, resolved);
+    let package_dir = match options.existing_dir {
+        Some(ref existing_dir) => update_existing(context, data, existing_dir, &values)?,
+        None => NuspecGenerator::new()
+            .with_base_dir(&options.base_dir)
+            .generate(data, &options.output_dir)?,
+    };
+
+    if data.updater().chocolatey().embedded {
+        let tools_dir = package_dir.join(TOOLS_DIR);
+        for file in files {
+            match file.extracted {
+                Some(ref extracted) => copy_dir(context, extracted, &tools_dir)?,
+                None => {
+                    let target = tools_dir.join(file_name(&file.path));
+                    std::fs::copy(&file.path, &target)?;
+                    context.artifact(&target);
+                }
+            }
+        }
+
+        if !files.is_empty() {
+            embed_legal_files(context, data, resolved, &package_dir)?;
+        }
+    }
+
+    if !data.updater().templates.is_empty() {
+        for template in &data.updater().templates {
+            let target = template_target(&package_dir, template);
+            templates::render_file(&options.base_dir.join(template), &target, &values)?;
+            context.artifact(&target);
+        }
+    }
+
+    context.artifact(&package_dir);
+
+    let package_file = ChocolateyPackage::new(&package_dir).pack(data, &options.output_dir)?;
+    context.artifact(&package_file);
+
+    Ok((package_dir, package_file))
+}
+
+/// Downloads the license of the software to the `legal` directory of the
+/// package, and creates the verification file listing the resolved files
+/// that was downloaded. The license is only downloaded when the package
+/// references the license by its location.
+fn embed_legal_files(
+    context: &UpdateContext,
+    data: &PackageData,
+    resolved: &ResolvedUpdate,
+    package_dir: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut generator = VerificationGenerator::new();
+    for file in resolved.files.values() {
+        if let (Some(name), Some(checksum), Some(checksum_type)) =
+            (&file.file_name, &file.checksum, file.checksum_type)
+        {
+            generator = generator.with_file(EmbeddedFile {
+                name: name.clone(),
+                url: file.url.clone(),
+                checksum: checksum.clone(),
+                checksum_type: checksum_type.to_string(),
+            });
+        }
+    }
+
+    let license_url = match data.metadata().license() {
+        LicenseType::Location(url) | LicenseType::ExpressionAndLocation { url, .. } => Some(url),
+        _ => None,
+    };
+    if let Some(url) = license_url {
+        let license = context
+            .request()
+            .get_license_file(url.as_str())
+            .map_err(|err| {
+                CodedError::new(
+                    codes::INVALID_LICENSE,
+                    format!("The license could not be embedded: {}", err),
+                )
+            })?;
+        let legal_dir = package_dir.join(LEGAL_DIR);
+        std::fs::create_dir_all(&legal_dir)?;
+        let target = legal_dir.join(LICENSE_FILE);
+        std::fs::write(&target, license.text)?;
+        context.artifact(&target);
+        generator = generator.with_license_url(license.url);
+    }
+
+    let legal_dir = generator.generate(data, package_dir)?;
+    context.artifact(&legal_dir.join(VERIFICATION_FILE));
+
+    Ok(())
+}
+
+/// Creates the winget manifests of the package in the output directory, using
+/// the downloaded files as the installers of the software.
+fn create_manifests(
+    context: &UpdateContext,
+    data: &PackageData,
+    found: &FoundLinks,
+    files: &[DownloadedFile],
+    options: &BuildOptions,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let mut generator = WingetGenerator::new();
+
+    for file in files {
+        let architecture = found
+            .architectures
+            .iter()
+            .find(|(_, link)| **link == file.link)
+            .map(|(arch, _)| WingetArchitecture::from(*arch))
+            .unwrap_or(WingetArchitecture::X86);
+        let sha256 = if file.checksum_type == ChecksumType::Sha256 {
+            file.checksum.clone()
+        } else {
+            ChecksumType::Sha256.generate(&file.path)?
+        };
+        generator = generator.with_installer(WingetInstaller {
+            architecture,
+            url: file.link.link.clone(),
+            sha256,
+        });
+    }
+
+    let manifest_dir = generator.generate(data, &options.output_dir)?;
+    context.artifact(&manifest_dir);
+
+    Ok(manifest_dir)
+}
+
+/// Creates and packs the plain NuGet package in the output directory, and
+/// returns the path to the packed archive.
+fn create_nuget_package(
+    context: &UpdateContext,
+    data: &PackageData,
+    options: &BuildOptions,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let output_dir = options.output_dir.join(NUGET_DIR);
+    let package_dir = NugetGenerator::new().generate(data, &output_dir)?;
+    context.artifact(&package_dir);
+
+    let package_file = NugetPackage::new(&package_dir).pack(data, &output_dir)?;
+    context.artifact(&package_file);
+
+    Ok(package_file)
+}
+
+/// Updates the existing package in place, and returns the directory of the
+/// package.
+fn update_existing(
+    context: &UpdateContext,
+    data: &PackageData,
+    existing_dir: &Path,
+    values: &TemplateValues,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let existing = ExistingPackage::locate(existing_dir, data.metadata().id())?;
+    let version = data.metadata().chocolatey().version.to_choco().to_string();
+
+    for path in existing.update(&version, values)? {
+        context
+            .logger()
+            .info(format_args!("Updated '{}'", path.display()));
+        context.artifact(&path);
+    }
+
+    Ok(existing.package_dir().to_path_buf())
+}
+
+/// Creates the values available to the script templates from the resolved
+/// update, the links and checksums are only available for the architectures
+/// that was found.
+///
+/// - `id` and `version`: The identifier and version of the package.
+/// - `url32`, `url64` and `urlArm64`: The links of the 32bit, 64bit and ARM64
+///   files, the link of universal files are available as `url`.
+/// - `checksum32`, `checksum64` and `checksumArm64`: The checksums of the
+///   downloaded files.
+/// - `checksumType32`, `checksumType64` and `checksumTypeArm64`: The algorithms
+///   of the checksums.
+fn template_values(data: &PackageData, resolved: &ResolvedUpdate) -> TemplateValues {
+    let mut values = TemplateValues::new()
+        .with_value("id", data.metadata().id())
+        .with_value("version", data.metadata().chocolatey().version.to_string());
+
+    for (name, value) in resolved.values() {
+        values.set(&name, value);
+    }
+
+    values
+}
+
+/// The data exposed to the scripts of a package, holding the package data
+/// together with the read-only `downloads` value containing the links and
+/// checksums of the resolved files (*ie: `url64`, `checksum64` and
+/// `checksumType64`*), and the directories archives was extracted to (*ie:
+/// `extracted64`*). The values uses the same architecture suffixes as the
+/// script templates.
+#[derive(Debug)]
+struct ScriptData<'a> {
+    data: &'a mut PackageData,
+    downloads: RunnerData,
+}
+
+impl<'a> ScriptData<'a> {
+    fn new(data: &'a mut PackageData, resolved: &ResolvedUpdate) -> Self {
+        let mut downloads = RunnerData::new();
+
+        for (name, value) in resolved.values() {
+            downloads.insert(name, value);
+        }
+        for (arch, file) in &resolved.files {
+            if let Some(ref extracted) = file.extracted {
+                downloads.insert(format!("extracted{}", arch.suffix()), extracted.display());
+            }
+        }
+
+        ScriptData { data, downloads }
+    }
+}
+
+impl RunnerCombiner for ScriptData<'_> {
+    fn to_runner_data(&self) -> RunnerData {
+        let mut data = self.data.to_runner_data();
+        data.insert_child("downloads", self.downloads.clone());
+
+        data
+    }
+
+    fn from_runner_data(&mut self, data: RunnerData) {
+        self.data.from_runner_data(data);
+    }
+}
+
+/// Copies all files in the source directory recursively to the target
+/// directory, keeping the directory layout of the source.
+fn copy_dir(context: &UpdateContext, source: &Path, target: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(target)?;
+
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        let target = target.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(context, &entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)?;
+            context.artifact(&target);
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the path the template is rendered to, relative templates keeps
+/// their path inside the package directory while absolute templates are
+/// rendered to the tools directory.
+fn template_target(package_dir: &Path, template: &Path) -> PathBuf {
+    if template.is_relative() {
+        package_dir.join(template)
+    } else {
+        package_dir.join(TOOLS_DIR).join(file_name(template))
+    }
+}
+
+fn file_name(path: &Path) -> &std::ffi::OsStr {
+    path.file_name().unwrap_or(path.as_os_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_upd::data::chocolatey::ChocolateyMetadata;
+    use aer_upd::data::nuget::NugetMetadata;
+    use aer_upd::data::testing::{PackageDataFixture, FIXTURE_LICENSE_URL};
+    use aer_upd::data::winget::WingetMetadata;
+    use aer_upd::data::{Architecture, Url};
+    use aer_upd::runners::RunnerChildType;
+    use aer_upd::web::checksum::parse_checksum_file;
+    use aer_upd::web::LinkType;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[test]
+    fn build_package_should_create_embedded_package_without_links() {
+        let dir = std::env::temp_dir().join("aer-build-package-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut data = PackageDataFixture::new("build-test")
+            .with_license(LicenseType::Location(
+                Url::parse(FIXTURE_LICENSE_URL).unwrap(),
+            ))
+            .with_choco(|choco| {
+                *choco = ChocolateyMetadata::with_authors(&["AdmiringWorm"]);
+                choco.set_description_str("Package used when testing the build step");
+            })
+            .with_choco_updater(|updater| updater.embedded = true)
+            .build();
+        let options = BuildOptions {
+            output_dir: dir.clone(),
+            ..BuildOptions::default()
+        };
+
+        let actual = build_package(
+            &UpdateContext::new().for_package("build-test"),
+            &mut data,
+            &TargetLinks::default(),
+            &options,
+        )
+        .unwrap();
+
+        assert_eq!(actual.package_dir, dir.join("build-test"));
+        assert!(actual.files.is_empty());
+        assert!(actual.package_dir.join("build-test.nuspec").is_file());
+        assert_eq!(actual.package_file, dir.join("build-test.0.0.0.nupkg"));
+        assert!(actual.package_file.is_file());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn build_package_should_return_error_on_checksum_type_not_supported_by_chocolatey() {
+        let mut data = PackageData::new("build-test");
+        let options = BuildOptions {
+            checksum_type: ChecksumType::Blake3,
+            ..BuildOptions::default()
+        };
+
+        let actual = build_package(
+            &UpdateContext::new().for_package("build-test"),
+            &mut data,
+            &TargetLinks::default(),
+            &options,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            actual.to_string(),
+            "The checksum type 'blake3' can not be used in Chocolatey packages!"
+        );
+    }
+
+    #[rstest(
+        name,
+        content,
+        expected_ok,
+        case("md5", "ab66430167ceb33784387abe71cf7c7d  checksum-test.bin.txt", true),
+        case(
+            "sha1",
+            "86263d6db9edba53dca1cafca3853e2c81983afa *checksum-test.bin.txt",
+            true
+        ),
+        case("unpublished", "ab66430167ceb33784387abe71cf7c7d  other.zip", true),
+        case("mismatch", "00000000000000000000000000000000", false)
+    )]
+    fn verify_checksum_should_verify_file_against_published_checksum(
+        name: &str,
+        content: &str,
+        expected_ok: bool,
+    ) {
+        let dir = std::env::temp_dir().join(format!("aer-verify-checksum-{}", name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("checksum-test.bin.txt");
+        std::fs::copy("../aer_web/test-data/checksum-test.bin.txt", &path).unwrap();
+        let link = LinkElement::new(
+            Url::parse("https://example.org/checksum-test.bin.txt").unwrap(),
+            LinkType::Binary,
+        );
+        let checksum = ChecksumType::Md5.generate(&path).unwrap();
+
+        let actual = verify_checksum(
+            &UpdateContext::new(),
+            &link,
+            &path,
+            &checksum,
+            ChecksumType::Md5,
+            &parse_checksum_file(content),
+        );
+
+        assert_eq!(actual.is_ok(), expected_ok);
+        assert_eq!(path.exists(), expected_ok);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn script_data_should_expose_downloaded_files() {
+        let link = LinkElement::new(
+            Url::parse("https://example.org/app-1.0.0-x64.exe").unwrap(),
+            LinkType::Binary,
+        );
+        let found = FoundLinks::default().with_arch(Architecture::X64, link.clone());
+        let files = [DownloadedFile {
+            link,
+            path: PathBuf::from("app-1.0.0-x64.exe"),
+            checksum: "0123456789abcdef".into(),
+            checksum_type: ChecksumType::Sha256,
+            signatures: vec![],
+            extracted: None,
+            detection: None,
+        }];
+        let mut data = PackageDataFixture::minimal().build();
+
+        let actual =
+            ScriptData::new(&mut data, &ResolvedUpdate::new(&found, &files, None)).to_runner_data();
+
+        let downloads = match actual.get("downloads") {
+            Some(RunnerChildType::Child(downloads)) => downloads,
+            val => panic!("Unexpected downloads: {:?}", val),
+        };
+        assert_eq!(
+            downloads.get("url64"),
+            Some(&RunnerChildType::Data(
+                "https://example.org/app-1.0.0-x64.exe".into()
+            ))
+        );
+        assert_eq!(
+            downloads.get("checksum64"),
+            Some(&RunnerChildType::Data("0123456789abcdef".into()))
+        );
+        assert_eq!(downloads.get("url32"), None);
+        assert_eq!(
+            actual.get("id"),
+            Some(&RunnerChildType::Data("test-package".into()))
+        );
+    }
+
+    #[test]
+    fn script_data_should_expose_extracted_archives() {
+        let link = LinkElement::new(
+            Url::parse("https://example.org/app-1.0.0.zip").unwrap(),
+            LinkType::Binary,
+        );
+        let found = FoundLinks::default().with_arch(Architecture::X86, link.clone());
+        let files = [DownloadedFile {
+            link,
+            path: PathBuf::from("app-1.0.0.zip"),
+            checksum: "0123456789abcdef".into(),
+            checksum_type: ChecksumType::Sha256,
+            signatures: vec![],
+            extracted: Some(PathBuf::from("app-1.0.0")),
+            detection: None,
+        }];
+        let mut data = PackageDataFixture::minimal().build();
+
+        let actual =
+            ScriptData::new(&mut data, &ResolvedUpdate::new(&found, &files, None)).to_runner_data();
+
+        let downloads = match actual.get("downloads") {
+            Some(RunnerChildType::Child(downloads)) => downloads,
+            val => panic!("Unexpected downloads: {:?}", val),
+        };
+        assert_eq!(
+            downloads.get("extracted32"),
+            Some(&RunnerChildType::Data("app-1.0.0".into()))
+        );
+    }
+
+    #[rstest(
+        source,
+        expected_ok,
+        case(VersionSource::Link, true),
+        case(VersionSource::ProductVersion, false),
+        case(VersionSource::FileVersion, false)
+    )]
+    fn read_file_version_should_only_read_version_when_configured(
+        source: VersionSource,
+        expected_ok: bool,
+    ) {
+        let link = LinkElement::new(
+            Url::parse("https://example.org/app-1.0.0.zip").unwrap(),
+            LinkType::Binary,
+        );
+        let files = [DownloadedFile {
+            link,
+            path: PathBuf::from("Cargo.toml"),
+            checksum: "0123456789abcdef".into(),
+            checksum_type: ChecksumType::Sha256,
+            signatures: vec![],
+            extracted: None,
+            detection: None,
+        }];
+        let mut data = PackageDataFixture::minimal().build();
+        data.updater_mut().version_source = source;
+
+        let actual = read_file_version(&UpdateContext::new(), &data, &files);
+
+        match actual {
+            Ok(version) => {
+                assert!(expected_ok);
+                assert_eq!(version, None);
+            }
+            Err(err) => {
+                assert!(!expected_ok);
+                assert_eq!(codes::error_code(&err), codes::FILE_VERSION);
+            }
+        }
+    }
+
+    #[test]
+    fn copy_dir_should_copy_nested_files() {
+        let dir = std::env::temp_dir().join("aer-build-copy-dir-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("source").join("bin")).unwrap();
+        std::fs::write(dir.join("source").join("bin").join("app.exe"), "MZ").unwrap();
+        std::fs::write(dir.join("source").join("version.txt"), "1.2.3").unwrap();
+
+        copy_dir(
+            &UpdateContext::new(),
+            &dir.join("source"),
+            &dir.join("tools"),
+        )
+        .unwrap();
+
+        assert!(dir.join("tools").join("bin").join("app.exe").exists());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("tools").join("version.txt")).unwrap(),
+            "1.2.3"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn build_package_should_render_script_templates() {
+        let dir = std::env::temp_dir().join("aer-build-template-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("tools")).unwrap();
+        std::fs::write(
+            dir.join("tools").join("chocolateyInstall.ps1"),
+            "$packageName = '{{id}}'\n$version = '{{ version }}'\n",
+        )
+        .unwrap();
+        let mut data = PackageDataFixture::minimal().with_choco_defaults().build();
+        data.updater_mut()
+            .templates
+            .push(PathBuf::from("tools/chocolateyInstall.ps1"));
+        let options = BuildOptions {
+            output_dir: dir.join("output"),
+            base_dir: dir.clone(),
+            ..BuildOptions::default()
+        };
+
+        let actual = build_package(
+            &UpdateContext::new().for_package("test-package"),
+            &mut data,
+            &TargetLinks::default(),
+            &options,
+        )
+        .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(
+                actual
+                    .package_dir
+                    .join("tools")
+                    .join("chocolateyInstall.ps1")
+            )
+            .unwrap(),
+            "$packageName = 'test-package'\n$version = '1.0.0'\n"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn create_manifests_should_use_downloaded_files_as_installers() {
+        let dir = std::env::temp_dir().join("aer-build-winget-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app-1.0.0-x64.exe");
+        std::fs::write(&path, "installer").unwrap();
+        let link = LinkElement::new(
+            Url::parse("https://example.org/app-1.0.0-x64.exe").unwrap(),
+            LinkType::Binary,
+        );
+        let found = FoundLinks::default().with_arch(Architecture::X64, link.clone());
+        let files = [DownloadedFile {
+            link,
+            checksum: ChecksumType::Md5.generate(&path).unwrap(),
+            path,
+            checksum_type: ChecksumType::Md5,
+            signatures: vec![],
+            extracted: None,
+            detection: None,
+        }];
+        let mut data = PackageDataFixture::minimal()
+            .with_summary("Package used when testing winget manifests")
+            .build();
+        data.metadata_mut()
+            .set_winget(WingetMetadata::new("Test.Package", "Test"));
+        let options = BuildOptions {
+            output_dir: dir.clone(),
+            ..BuildOptions::default()
+        };
+
+        let actual =
+            create_manifests(&UpdateContext::new(), &data, &found, &files, &options).unwrap();
+
+        let installer =
+            std::fs::read_to_string(actual.join("Test.Package.installer.yaml")).unwrap();
+        assert_eq!(actual, dir.join("Test.Package").join("0.0.0"));
+        assert!(installer.contains("- Architecture: x64\n"));
+        assert!(installer.contains(&format!(
+            "  InstallerSha256: {}\n",
+            ChecksumType::Sha256
+                .generate(&files[0].path)
+                .unwrap()
+                .to_uppercase()
+        )));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn create_nuget_package_should_pack_package_in_nuget_directory() {
+        let dir = std::env::temp_dir().join("aer-build-nuget-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut data = PackageDataFixture::new("Test.Tool")
+            .with_license(LicenseType::Expression("MIT".into()))
+            .with_summary("Package used when testing NuGet packages")
+            .build();
+        data.metadata_mut().set_nuget(NugetMetadata::new());
+        let options = BuildOptions {
+            output_dir: dir.clone(),
+            ..BuildOptions::default()
+        };
+
+        let actual = create_nuget_package(&UpdateContext::new(), &data, &options).unwrap();
+
+        assert_eq!(actual, dir.join("nuget").join("Test.Tool.0.0.0.nupkg"));
+        assert!(actual.is_file());
+        assert!(dir
+            .join("nuget")
+            .join("Test.Tool")
+            .join("Test.Tool.nuspec")
+            .is_file());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn build_package_should_update_existing_package_in_place() {
+        let dir = std::env::temp_dir().join("aer-build-existing-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let package_dir = dir.join("packages").join("test-package");
+        std::fs::create_dir_all(package_dir.join("tools")).unwrap();
+        std::fs::write(
+            package_dir.join("test-package.nuspec"),
+            "<package>\n  <metadata>\n    <id>test-package</id>\n    \
+             <version>0.5.0</version>\n    <!-- Manual edit -->\n  </metadata>\n</package>\n",
+        )
+        .unwrap();
+        let mut data = PackageDataFixture::minimal().with_choco_defaults().build();
+        let options = BuildOptions {
+            output_dir: dir.join("output"),
+            existing_dir: Some(dir.join("packages")),
+            ..BuildOptions::default()
+        };
+
+        let actual = build_package(
+            &UpdateContext::new().for_package("test-package"),
+            &mut data,
+            &TargetLinks::default(),
+            &options,
+        )
+        .unwrap();
+
+        assert_eq!(actual.package_dir, package_dir);
+        assert_eq!(
+            std::fs::read_to_string(package_dir.join("test-package.nuspec")).unwrap(),
+            "<package>\n  <metadata>\n    <id>test-package</id>\n    \
+             <version>1.0.0</version>\n    <!-- Manual edit -->\n  </metadata>\n</package>\n"
+        );
+        assert_eq!(
+            actual.package_file,
+            dir.join("output").join("test-package.1.0.0.nupkg")
+        );
+        assert!(actual.package_file.is_file());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn template_values_should_include_links_and_checksums() {
+        let link = LinkElement::new(
+            Url::parse("https://example.org/app-1.0.0-x64.exe").unwrap(),
+            LinkType::Binary,
+        );
+        let found = FoundLinks::default().with_arch(Architecture::X64, link.clone());
+        let files = vec![DownloadedFile {
+            link,
+            path: PathBuf::from("app-1.0.0-x64.exe"),
+            checksum: "ABCDEF".into(),
+            checksum_type: ChecksumType::Sha256,
+            signatures: vec![],
+            extracted: None,
+            detection: None,
+        }];
+        let data = PackageDataFixture::minimal().build();
+
+        let actual = template_values(&data, &ResolvedUpdate::new(&found, &files, None));
+
+        assert_eq!(
+            actual.get("url64"),
+            Some("https://example.org/app-1.0.0-x64.exe")
+        );
+        assert_eq!(actual.get("checksum64"), Some("ABCDEF"));
+        assert_eq!(actual.get("checksumType64"), Some("sha256"));
+        assert_eq!(actual.get("url32"), None);
+    }
+
+    #[test]
+    fn template_values_should_include_arm64_and_universal_links() {
+        let arm64 = LinkElement::new(
+            Url::parse("https://example.org/app-1.0.0-arm64.exe").unwrap(),
+            LinkType::Binary,
+        );
+        let universal = LinkElement::new(
+            Url::parse("https://example.org/app-1.0.0.msix").unwrap(),
+            LinkType::Binary,
+        );
+        let found = FoundLinks::default()
+            .with_arch(Architecture::Arm64, arm64)
+            .with_arch(Architecture::Universal, universal);
+        let data = PackageDataFixture::minimal().build();
+
+        let actual = template_values(&data, &ResolvedUpdate::new(&found, &[], None));
+
+        assert_eq!(
+            actual.get("urlArm64"),
+            Some("https://example.org/app-1.0.0-arm64.exe")
+        );
+        assert_eq!(
+            actual.get("url"),
+            Some("https://example.org/app-1.0.0.msix")
+        );
+        assert_eq!(actual.get("checksumArm64"), None);
+    }
+
+    #[rstest(
+        template,
+        expected,
+        case(
+            "tools/chocolateyInstall.ps1",
+            "build-test/tools/chocolateyInstall.ps1"
+        ),
+        case("legal/VERIFICATION.txt", "build-test/legal/VERIFICATION.txt")
+    )]
+    fn template_target_should_keep_relative_path(template: &str, expected: &str) {
+        let actual = template_target(Path::new("build-test"), Path::new(template));
+
+        assert_eq!(actual, PathBuf::from(expected));
+    }
+
+    #[rstest(
+        url,
+        delta,
+        expected,
+        case("https://example.org/files/app-1.0.0.exe", true, Some("app-1.0.0.exe")),
+        case("https://example.org/files/app-1.0.0.exe", false, None),
+        case("https://example.org/files/", true, None)
+    )]
+    fn delta_name_should_use_last_segment_of_link(url: &str, delta: bool, expected: Option<&str>) {
+        let options = BuildOptions {
+            delta,
+            ..BuildOptions::default()
+        };
+        let link = LinkElement::new(Url::parse(url).unwrap(), LinkType::Binary);
+
+        let actual = delta_name(&options, &link);
+
+        assert_eq!(actual.as_deref(), expected);
+    }
+}
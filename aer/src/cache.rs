@@ -0,0 +1,195 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the cache of the files downloaded when building packages, which
+//! records the etag, last modified date, final url and checksum of every
+//! download. The recorded validators are sent on the next run, allowing the
+//! previously downloaded file to be re-used when the server reports that the
+//! file have not changed.
+//!
+//! The cache of each package is kept in its own directory of the cache
+//! directory (*ie: `<cache>/downloads/<package-id>/cache.toml`*), together
+//! with a copy of every downloaded file.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// The name of the directory in the cache directory where the downloads are
+/// cached.
+const DOWNLOADS_DIR: &str = "downloads";
+
+/// The name of the file holding the cached information of the downloads.
+const CACHE_FILE_NAME: &str = "cache.toml";
+
+/// The information recorded for a single downloaded url.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CacheEntry {
+    /// The etag the server responded with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    /// The last modified date the server responded with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    /// The url the file was downloaded from, after any redirects have been
+    /// followed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub final_url: Option<String>,
+    /// The checksum of the downloaded file.
+    pub checksum: String,
+    /// The algorithm used to generate the checksum (*ie: `sha256`*).
+    pub checksum_type: String,
+    /// The name of the copy of the downloaded file in the cache directory.
+    pub file: String,
+}
+
+impl CacheEntry {
+    /// Returns wether the entry have any validators that can be sent with a
+    /// conditional request.
+    pub fn has_validators(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+}
+
+/// The cached downloads of a single package.
+#[derive(Debug, Default, PartialEq)]
+pub struct DownloadCache {
+    dir: PathBuf,
+    entries: BTreeMap<String, CacheEntry>,
+}
+
+impl DownloadCache {
+    /// Loads the cached downloads of the package with the specified
+    /// identifier, an empty cache is returned if nothing have been cached
+    /// before.
+    pub fn load(cache_dir: &Path, id: &str) -> Result<DownloadCache, Box<dyn std::error::Error>> {
+        let dir = cache_dir.join(DOWNLOADS_DIR).join(id);
+        let path = dir.join(CACHE_FILE_NAME);
+        let entries = if path.exists() {
+            toml::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            BTreeMap::new()
+        };
+
+        Ok(DownloadCache { dir, entries })
+    }
+
+    /// Returns the cached entry of the specified url, nothing is returned when
+    /// the url have not been cached or the copy of the file have been
+    /// removed.
+    pub fn get(&self, url: &str) -> Option<&CacheEntry> {
+        self.entries
+            .get(url)
+            .filter(|entry| self.file_path(entry).is_file())
+    }
+
+    /// Returns the path to the copy of the downloaded file of the entry.
+    pub fn file_path(&self, entry: &CacheEntry) -> PathBuf {
+        self.dir.join(&entry.file)
+    }
+
+    /// Records the downloaded file of the specified url, a copy of the file
+    /// is kept in the cache directory.
+    pub fn store(&mut self, url: &str, mut entry: CacheEntry, path: &Path) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        if entry.file.is_empty() {
+            entry.file = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+        }
+        std::fs::copy(path, self.file_path(&entry))?;
+        self.entries.insert(url.into(), entry);
+
+        Ok(())
+    }
+
+    /// Saves the cached information to the cache directory, creating the
+    /// directory if it do not already exist.
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(
+            self.dir.join(CACHE_FILE_NAME),
+            toml::to_string_pretty(&self.entries)?,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aer-cache-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn load_should_return_empty_cache_when_nothing_is_cached() {
+        let dir = temp_dir("empty");
+
+        let cache = DownloadCache::load(&dir, "test-package").unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(cache.get("https://example.org/app.exe"), None);
+    }
+
+    #[test]
+    fn save_should_persist_stored_entries() {
+        let dir = temp_dir("roundtrip");
+        let file = dir.join("app.exe");
+        std::fs::write(&file, "MZ").unwrap();
+        let entry = CacheEntry {
+            etag: Some("\"abc\"".into()),
+            last_modified: None,
+            final_url: Some("https://cdn.example.org/app.exe".into()),
+            checksum: "ABCDEF".into(),
+            checksum_type: "sha256".into(),
+            file: String::new(),
+        };
+
+        let mut cache = DownloadCache::load(&dir, "test-package").unwrap();
+        cache
+            .store("https://example.org/app.exe", entry.clone(), &file)
+            .unwrap();
+        cache.save().unwrap();
+        let actual = DownloadCache::load(&dir, "test-package").unwrap();
+        let cached = actual.get("https://example.org/app.exe").cloned();
+        let content = cached
+            .as_ref()
+            .map(|cached| std::fs::read_to_string(actual.file_path(cached)).unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            cached,
+            Some(CacheEntry {
+                file: "app.exe".into(),
+                ..entry
+            })
+        );
+        assert_eq!(content.as_deref(), Some("MZ"));
+    }
+
+    #[test]
+    fn get_should_ignore_entries_without_cached_file() {
+        let dir = temp_dir("missing");
+        let file = dir.join("app.exe");
+        std::fs::write(&file, "MZ").unwrap();
+        let mut cache = DownloadCache::load(&dir, "test-package").unwrap();
+        cache
+            .store("https://example.org/app.exe", CacheEntry::default(), &file)
+            .unwrap();
+        let cached = cache.file_path(&cache.entries["https://example.org/app.exe"]);
+        std::fs::remove_file(cached).unwrap();
+
+        let actual = cache.get("https://example.org/app.exe");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(actual, None);
+    }
+}
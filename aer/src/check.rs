@@ -0,0 +1,404 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the functionality for validating package files and reporting the
+//! problems found as diagnostics with accurate positions, in a format that
+//! can be consumed by editor plugins.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use aer_upd::data::PackageData;
+use aer_upd::parsers;
+use aer_upd::parsers::toml::key_position;
+use serde_json::{json, Value};
+
+use crate::policy::Policy;
+use crate::readiness::{self, CheckStatus};
+use crate::validation::{self, RuleSet};
+
+/// The file suffixes used to discover package files inside directories.
+const PACKAGE_FILE_SUFFIXES: &[&str] = &[".aer.toml", ".aer.yml", ".aer.yaml", ".aer.json"];
+
+/// The rule identifier used when a package file can not be parsed.
+pub const PARSE_RULE: &str = "parse";
+
+/// The format used when outputting the diagnostics of package files.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OutputFormat {
+    /// Outputs each diagnostic on a line readable by humans.
+    #[default]
+    Text,
+    /// Outputs a single JSON object per line for every checked file.
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = &'static str;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val.trim().to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err("The value is not a supported output format!"),
+        }
+    }
+}
+
+impl OutputFormat {
+    pub fn variants_str() -> &'static [&'static str] {
+        static VARIANTS: &[&str] = &["text", "json"];
+
+        VARIANTS
+    }
+}
+
+/// The severity of a single diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    /// The package will be rejected, or could not be read at all.
+    Error,
+    /// The package breaks a guideline.
+    Warning,
+    /// The problem have been explicitly allowed for the package.
+    Allowed,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            Severity::Error => f.write_str("error"),
+            Severity::Warning => f.write_str("warning"),
+            Severity::Allowed => f.write_str("allowed"),
+        }
+    }
+}
+
+/// A single problem found in a package file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// The line the problem was found on, starting at `1`.
+    pub line: usize,
+    /// The column the problem was found on, starting at `1`.
+    pub column: usize,
+    /// The severity of the problem.
+    pub severity: Severity,
+    /// The message describing the problem.
+    pub message: String,
+    /// The identifier of the rule that found the problem.
+    pub rule: String,
+    /// The stable code of the problem (*ie: `AER0203`*).
+    pub code: &'static str,
+}
+
+impl Diagnostic {
+    /// Converts the diagnostic to a JSON value.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "line": self.line,
+            "column": self.column,
+            "severity": self.severity.to_string(),
+            "message": self.message,
+            "rule": self.rule,
+            "code": self.code,
+        })
+    }
+}
+
+/// Validates the specified package file against the built-in rules and the
+/// rules of the organizational policy, and returns the problems that was
+/// found. No diagnostics are returned for files that do not exist.
+pub fn check_file(path: &Path, policy: &Policy) -> Vec<Diagnostic> {
+    let (text, data) = match read_package(path) {
+        Ok(package) => package,
+        Err(diagnostics) => return diagnostics,
+    };
+
+    readiness::check_readiness_with_policy(&data, policy)
+        .checks()
+        .iter()
+        .filter_map(|check| {
+            let severity = match check.status {
+                CheckStatus::Passed => return None,
+                CheckStatus::Warning => Severity::Warning,
+                CheckStatus::Failed => Severity::Error,
+                CheckStatus::Allowed => Severity::Allowed,
+            };
+            let (line, column) = key_position(&text, &check.key).unwrap_or((1, 1));
+
+            Some(Diagnostic {
+                line,
+                column,
+                severity,
+                message: check.message.clone(),
+                rule: check.rule_id(),
+                code: check.code,
+            })
+        })
+        .collect()
+}
+
+/// Validates the specified package file against the rules of the specified
+/// rule set, instead of the full readiness checklist. No diagnostics are
+/// returned for files that do not exist.
+pub fn check_file_with_rules(path: &Path, rule_set: RuleSet) -> Vec<Diagnostic> {
+    let (text, data) = match read_package(path) {
+        Ok(package) => package,
+        Err(diagnostics) => return diagnostics,
+    };
+
+    validation::validate(&data, rule_set)
+        .results()
+        .iter()
+        .map(|result| {
+            let (line, column) = key_position(&text, result.key).unwrap_or((1, 1));
+
+            Diagnostic {
+                line,
+                column,
+                severity: result.severity,
+                message: result.message.clone(),
+                rule: result.rule.into(),
+                code: result.code,
+            }
+        })
+        .collect()
+}
+
+/// Reads the text and the package data of the specified file, or the
+/// diagnostics to report when the file could not be read.
+fn read_package(path: &Path) -> Result<(String, PackageData), Vec<Diagnostic>> {
+    if !path.exists() {
+        return Err(vec![]);
+    }
+
+    let text = std::fs::read_to_string(path)
+        .map_err(|err| vec![parse_diagnostic(err.to_string(), None, "AER0002")])?;
+
+    let data = parsers::read_file(path).map_err(|err| {
+        vec![parse_diagnostic(
+            err.to_string(),
+            err.position(),
+            err.code(),
+        )]
+    })?;
+
+    Ok((text, data))
+}
+
+fn parse_diagnostic(
+    message: String,
+    position: Option<(usize, usize)>,
+    code: &'static str,
+) -> Diagnostic {
+    let (line, column) = position.unwrap_or((1, 1));
+
+    Diagnostic {
+        line,
+        column,
+        severity: Severity::Error,
+        message,
+        rule: PARSE_RULE.into(),
+        code,
+    }
+}
+
+/// Creates the JSON object reported for a single file, an empty list of
+/// diagnostics means that any previously reported problem have been fixed.
+pub fn file_report(path: &Path, diagnostics: &[Diagnostic]) -> Value {
+    json!({
+        "file": path,
+        "diagnostics": diagnostics.iter().map(Diagnostic::to_json).collect::<Vec<_>>(),
+    })
+}
+
+/// Finds all package files in the specified paths. Files are returned as-is,
+/// while directories are searched recursively for files ending with
+/// `.aer.toml`, `.aer.yml`, `.aer.yaml` or `.aer.json`.
+pub fn find_package_files(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = vec![];
+
+    for path in paths {
+        if path.is_dir() {
+            collect_package_files(path, &mut files);
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    files.sort();
+    files
+}
+
+fn collect_package_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_package_files(&path, files);
+        } else if path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| {
+                PACKAGE_FILE_SUFFIXES
+                    .iter()
+                    .any(|suffix| name.ends_with(suffix))
+            })
+            .unwrap_or_default()
+        {
+            files.push(path);
+        }
+    }
+}
+
+/// Keeps track of the modification time of package files, allowing changed
+/// files to be re-validated.
+#[derive(Debug, Default)]
+pub struct FileWatcher {
+    paths: Vec<PathBuf>,
+    modified: HashMap<PathBuf, Option<SystemTime>>,
+}
+
+impl FileWatcher {
+    /// Creates a new watcher for the specified files and directories.
+    pub fn new(paths: &[PathBuf]) -> FileWatcher {
+        FileWatcher {
+            paths: paths.to_vec(),
+            modified: HashMap::new(),
+        }
+    }
+
+    /// Returns the files that have been added, changed or removed since the
+    /// last time the function was called. All files are returned on the
+    /// first call.
+    pub fn changed_files(&mut self) -> Vec<PathBuf> {
+        let files = find_package_files(&self.paths);
+        let mut changed: Vec<PathBuf> = self
+            .modified
+            .keys()
+            .filter(|path| !files.contains(path))
+            .cloned()
+            .collect();
+
+        for path in &changed {
+            self.modified.remove(path);
+        }
+
+        for file in files {
+            let modified = std::fs::metadata(&file)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+
+            if self.modified.get(&file) != Some(&modified) {
+                self.modified.insert(file.clone(), modified);
+                changed.push(file);
+            }
+        }
+
+        changed.sort();
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest(
+        test,
+        expected,
+        case("text", OutputFormat::Text),
+        case("JSON", OutputFormat::Json)
+    )]
+    fn from_str_should_create_expected_format(test: &str, expected: OutputFormat) {
+        let actual = OutputFormat::from_str(test);
+
+        assert_eq!(actual, Ok(expected));
+    }
+
+    #[test]
+    fn check_file_should_report_position_of_failed_checks() {
+        let actual = check_file(
+            Path::new("test-data/simulate/package.aer.toml"),
+            &Policy::default(),
+        );
+
+        let tags = actual.iter().find(|d| d.rule == "tags").unwrap();
+        assert_eq!(tags.severity, Severity::Warning);
+        assert_eq!((tags.line, tags.column), (1, 1));
+        let authors = actual.iter().find(|d| d.rule == "authors").unwrap();
+        assert_eq!(authors.severity, Severity::Error);
+    }
+
+    #[test]
+    fn check_file_should_report_parse_errors() {
+        let dir = std::env::temp_dir().join("aer-check-parse-error");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("invalid.aer.toml");
+        std::fs::write(&path, "[metadata]\nid = 5\n").unwrap();
+
+        let actual = check_file(&path, &Policy::default());
+
+        assert_eq!(actual.len(), 1);
+        assert_eq!(actual[0].rule, PARSE_RULE);
+        assert_eq!(actual[0].line, 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn check_file_should_report_codes_of_problems() {
+        let actual = check_file(
+            Path::new("test-data/simulate/package.aer.toml"),
+            &Policy::default(),
+        );
+
+        let authors = actual.iter().find(|d| d.rule == "authors").unwrap();
+        assert_eq!(authors.code, "AER0206");
+        assert_eq!(authors.to_json()["code"], "AER0206");
+    }
+
+    #[test]
+    fn check_file_with_rules_should_only_report_rules_of_rule_set() {
+        let actual = check_file_with_rules(
+            Path::new("test-data/simulate/package.aer.toml"),
+            RuleSet::Core,
+        );
+
+        assert_eq!(actual.len(), 1);
+        assert_eq!(actual[0].rule, "license-url");
+        assert_eq!(actual[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn check_file_should_not_report_missing_files() {
+        let actual = check_file(Path::new("non-existing.aer.toml"), &Policy::default());
+
+        assert!(actual.is_empty());
+    }
+
+    #[test]
+    fn changed_files_should_only_return_modified_or_removed_files() {
+        let dir = std::env::temp_dir().join("aer-check-watcher");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("package.aer.toml");
+        std::fs::write(&path, "").unwrap();
+        std::fs::write(dir.join("other.txt"), "").unwrap();
+        let mut watcher = FileWatcher::new(std::slice::from_ref(&dir));
+
+        assert_eq!(watcher.changed_files(), vec![path.clone()]);
+        assert!(watcher.changed_files().is_empty());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(watcher.changed_files(), vec![path]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
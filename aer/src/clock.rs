@@ -0,0 +1,113 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the clock used whenever the application needs the current time,
+//! allowing the time to be frozen to make runs reproducible.
+
+use aer_upd::data::chocolatey::FixTimeZone;
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use structopt::StructOpt;
+
+// Holds the clock that should be used when getting the current date and
+// time.
+//
+// By default the system time is used, but the time can be frozen to a
+// specific point in time. This makes it possible to reproduce bug reports and
+// to run tests that depends on the current date in a deterministic way.
+#[derive(Debug, Default, Clone, PartialEq, StructOpt)]
+pub struct Clock {
+    /// Freeze the current time to the specified timestamp (*ie:
+    /// `2021-06-15T12:00:00Z` or `2021-06-15`). This is mostly useful for
+    /// reproducing bug reports.
+    #[structopt(
+        long = "frozen-time",
+        env = "AER_FROZEN_TIME",
+        global = true,
+        parse(try_from_str = parse_time)
+    )]
+    frozen: Option<DateTime<Utc>>,
+}
+
+impl Clock {
+    /// Creates a new clock that will always return the specified time.
+    pub fn frozen(time: DateTime<Utc>) -> Clock {
+        Clock { frozen: Some(time) }
+    }
+
+    /// Returns `true` if the time have been frozen.
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.is_some()
+    }
+
+    /// Returns the current date and time in UTC.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.frozen.unwrap_or_else(Utc::now)
+    }
+
+    /// Returns the current date in the specified time zone.
+    pub fn today(&self, time_zone: FixTimeZone) -> NaiveDate {
+        let now = self.now();
+
+        match time_zone {
+            FixTimeZone::Utc => now.date().naive_utc(),
+            FixTimeZone::Local => now.with_timezone(&Local).date().naive_local(),
+        }
+    }
+}
+
+/// Parses the specified value as either an RFC 3339 timestamp, or as a date
+/// where midnight UTC will be used as the time.
+pub fn parse_time(value: &str) -> Result<DateTime<Utc>, chrono::ParseError> {
+    match DateTime::parse_from_rfc3339(value) {
+        Ok(time) => Ok(time.with_timezone(&Utc)),
+        Err(err) => match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            Ok(date) => Ok(Utc.from_utc_datetime(&date.and_hms(0, 0, 0))),
+            Err(_) => Err(err),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest(
+        value,
+        expected,
+        case("2021-06-15T12:30:00Z", Utc.ymd(2021, 6, 15).and_hms(12, 30, 0)),
+        case("2021-06-15T12:30:00+02:00", Utc.ymd(2021, 6, 15).and_hms(10, 30, 0)),
+        case("2021-06-15", Utc.ymd(2021, 6, 15).and_hms(0, 0, 0))
+    )]
+    fn parse_time_should_parse_value(value: &str, expected: DateTime<Utc>) {
+        let actual = parse_time(value).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn parse_time_should_return_error_on_invalid_value() {
+        assert!(parse_time("yesterday").is_err());
+    }
+
+    #[test]
+    fn now_should_return_frozen_time() {
+        let expected = Utc.ymd(2021, 6, 15).and_hms(23, 30, 0);
+        let clock = Clock::frozen(expected);
+
+        assert!(clock.is_frozen());
+        assert_eq!(clock.now(), expected);
+        assert_eq!(
+            clock.today(FixTimeZone::Utc),
+            NaiveDate::from_ymd(2021, 6, 15)
+        );
+    }
+
+    #[test]
+    fn default_should_not_be_frozen() {
+        let clock = Clock::default();
+
+        assert!(!clock.is_frozen());
+    }
+}
@@ -0,0 +1,367 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the catalog of the stable codes assigned to every error and
+//! warning the application can report. The codes are grouped by the area they
+//! are reported from:
+//!
+//! - `AER00xx`: Reading and writing package files.
+//! - `AER01xx`: Requests to remote servers.
+//! - `AER02xx`: Validation rules of package files.
+//! - `AER03xx`: Generating the files of packages.
+//! - `AER04xx`: Running the scripts of packages.
+//! - `AER05xx`: Packing the archives of packages.
+//! - `AER06xx`: Inspecting and extracting downloaded archives.
+
+use std::error::Error;
+use std::fmt::Display;
+
+use aer_archive::ArchiveError;
+use aer_pkg::PackError;
+use aer_upd::context::Cancelled;
+use aer_upd::features::FeatureError;
+use aer_upd::generators::GeneratorError;
+use aer_upd::parsers::errors::ParserError;
+use aer_upd::runners::errors::RunnerError;
+use aer_upd::web::errors::WebError;
+
+use crate::check::Severity;
+use crate::config::ConfigError;
+use crate::policy::PolicyError;
+
+/// The code used for errors that have not been assigned a code of their own.
+pub const UNKNOWN: &str = "AER0000";
+
+/// The code used when an existing package could not be imported.
+pub const INVALID_IMPORT: &str = "AER0009";
+
+/// The code used when the API key used when publishing a package have not
+/// been set.
+pub const MISSING_API_KEY: &str = "AER0107";
+
+/// The code used when the authentication profile referenced by a package have
+/// not been configured.
+pub const MISSING_AUTH_PROFILE: &str = "AER0108";
+
+/// The code used when the signature of a downloaded file is invalid or could
+/// not be verified, and signatures are required by the package.
+pub const INVALID_SIGNATURE: &str = "AER0109";
+
+/// The code used when the version could not be read from the downloaded
+/// files of a package.
+pub const FILE_VERSION: &str = "AER0110";
+
+/// The code used when the license of the software could not be downloaded,
+/// or is not a text file that can be embedded in the package.
+pub const INVALID_LICENSE: &str = "AER0111";
+
+/// The code used when a downloaded file have been flagged by more anti virus
+/// engines on VirusTotal than the configured threshold allows.
+pub const VIRUSTOTAL_DETECTION: &str = "AER0112";
+
+/// The code used when the package directory to update in place could not be
+/// found.
+pub const MISSING_EXISTING_PACKAGE: &str = "AER0304";
+
+/// The code used when a package do not satisfy a rule of the organizational
+/// policy.
+pub const POLICY_RULE: &str = "AER0290";
+
+/// The code used when the package data do not satisfy the selected validation
+/// rule set.
+pub const VALIDATION_FAILED: &str = "AER0292";
+
+/// A single entry in the catalog of codes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CodeInfo {
+    /// The code of the error or warning (*ie: `AER0203`*).
+    pub code: &'static str,
+    /// The severity the code is normally reported with.
+    pub severity: Severity,
+    /// The identifier of the validation rule, when the code is reported by a
+    /// validation rule.
+    pub rule: Option<&'static str>,
+    /// A short description of the problem the code represents.
+    pub description: &'static str,
+}
+
+macro_rules! codes {
+    ($($code:literal $severity:ident $rule:expr => $description:literal),+ $(,)?) => {
+        &[$(CodeInfo {
+            code: $code,
+            severity: Severity::$severity,
+            rule: $rule,
+            description: $description,
+        }),+]
+    };
+}
+
+/// The catalog of all codes that can be reported.
+pub const CATALOG: &[CodeInfo] = codes! {
+    "AER0000" Error None => "An error that have not been assigned a code of its own",
+    "AER0001" Error None => "No parser is able to handle the package file",
+    "AER0002" Error None => "The package file could not be read",
+    "AER0003" Error None => "The package file could not be written",
+    "AER0004" Error None => "The package file could not be deserialized",
+    "AER0005" Error None => "The package data could not be serialized",
+    "AER0006" Error None => "An unexpected error occurred while parsing the package file",
+    "AER0007" Error None => "The package requires a feature that is not enabled in this build",
+    "AER0008" Error None => "The configuration file could not be read or parsed",
+    "AER0009" Error None => "The existing package to import could not be read",
+    "AER0010" Error None => "The base file extended by the package file could not be resolved",
+    "AER0100" Error None => "An unexpected error occurred while handling a web request",
+    "AER0101" Error None => "The request to the remote server failed",
+    "AER0102" Error None => "The remote server responded with an unsuccessful status code",
+    "AER0103" Error None => "The request to the remote server timed out",
+    "AER0104" Error None => "Unable to connect to the remote server",
+    "AER0105" Error None => "A downloaded file could not be read or written",
+    "AER0106" Error None => "The checksum of a downloaded file did not match the expected checksum",
+    "AER0107" Error None => "The API key used when publishing the package have not been set",
+    "AER0108" Error None => "The authentication profile referenced by the package is not configured",
+    "AER0109" Error None => "The signature of a downloaded file is invalid or could not be verified",
+    "AER0110" Error None => "The version could not be read from the downloaded files",
+    "AER0111" Error None => "The license could not be downloaded or is not a text file",
+    "AER0112" Error None => "A downloaded file was flagged by too many anti virus engines on VirusTotal",
+    "AER0201" Error Some("identifier") => "The identifier is empty or contains spaces",
+    "AER0202" Error Some("lowercase-identifier") => "The identifier is not lowercase",
+    "AER0203" Error Some("summary") => "The summary of the software is missing",
+    "AER0204" Error Some("project-url") => "The project url have not been specified",
+    "AER0205" Error Some("license-url") => "The license url is missing or could not be resolved",
+    "AER0206" Error Some("authors") => "The authors of the software are missing",
+    "AER0207" Error Some("description") => "The description is missing, too short or too long",
+    "AER0208" Warning Some("tags") => "No tags have been specified",
+    "AER0209" Error Some("tag-format") => "The tags are comma separated",
+    "AER0210" Warning Some("chocolatey-tag") => "The tag 'chocolatey' is used for unrelated software",
+    "AER0211" Warning Some("title") => "The title of the software is missing",
+    "AER0212" Warning Some("copyright") => "The copyright of the software is missing",
+    "AER0213" Warning Some("documentation-url") => "The documentation url is missing",
+    "AER0214" Warning Some("issues-url") => "The issues url is missing",
+    "AER0215" Warning Some("release-notes") => "The release notes are missing",
+    "AER0290" Error None => "A rule of the organizational policy is not satisfied",
+    "AER0291" Error None => "The organizational policy file could not be read or parsed",
+    "AER0292" Error None => "The package data do not satisfy the selected validation rule set",
+    "AER0301" Error None => "A value required by the package manager have not been set",
+    "AER0302" Error None => "A file of the package could not be read or written",
+    "AER0303" Error None => "A script template contains an unknown or malformed token",
+    "AER0304" Error None => "The existing package directory to update could not be found",
+    "AER0305" Error None => "The file the description is read from could not be read",
+    "AER0401" Error None => "A script of the package failed to run",
+    "AER0402" Error None => "The update process was cancelled",
+    "AER0501" Error None => "The package manifest could not be found when packing the package",
+    "AER0502" Error None => "A file could not be read or written when packing the package",
+    "AER0503" Error None => "The package archive could not be created",
+    "AER0601" Error None => "The format of a downloaded archive is not supported",
+    "AER0602" Error None => "A file could not be read or written when handling an archive",
+    "AER0603" Error None => "A downloaded archive is corrupt or could not be read",
+    "AER0604" Error None => "An entry of an archive would be extracted outside of the target directory",
+    "AER0605" Error None => "The requested file do not exist in the archive",
+    "AER0606" Error None => "The external tool used to handle an archive failed or could not be found",
+};
+
+/// Returns the catalog entry of the specified code, the code is matched
+/// without considering the case.
+pub fn lookup(code: &str) -> Option<&'static CodeInfo> {
+    CATALOG
+        .iter()
+        .find(|info| info.code.eq_ignore_ascii_case(code.trim()))
+}
+
+/// Returns the code of the specified validation rule, or [UNKNOWN] when the
+/// rule have not been assigned a code.
+pub fn rule_code(rule: &str) -> &'static str {
+    CATALOG
+        .iter()
+        .find(|info| info.rule == Some(rule))
+        .map(|info| info.code)
+        .unwrap_or(UNKNOWN)
+}
+
+/// Returns the code of the specified error, or [UNKNOWN] when neither the
+/// error or any of its sources have been assigned a code.
+pub fn error_code(err: &(dyn Error + 'static)) -> &'static str {
+    let mut current = Some(err);
+
+    while let Some(err) = current {
+        match own_code(err) {
+            UNKNOWN => current = err.source(),
+            code => return code,
+        }
+    }
+
+    UNKNOWN
+}
+
+fn own_code(err: &(dyn Error + 'static)) -> &'static str {
+    if let Some(err) = err.downcast_ref::<CodedError>() {
+        err.code
+    } else if let Some(err) = err.downcast_ref::<ParserError>() {
+        err.code()
+    } else if let Some(err) = err.downcast_ref::<WebError>() {
+        err.code()
+    } else if let Some(err) = err.downcast_ref::<RunnerError>() {
+        err.code()
+    } else if let Some(err) = err.downcast_ref::<GeneratorError>() {
+        err.code()
+    } else if let Some(err) = err.downcast_ref::<PackError>() {
+        err.code()
+    } else if let Some(err) = err.downcast_ref::<ArchiveError>() {
+        err.code()
+    } else if let Some(err) = err.downcast_ref::<FeatureError>() {
+        err.code()
+    } else if let Some(err) = err.downcast_ref::<ConfigError>() {
+        err.code()
+    } else if err.is::<PolicyError>() {
+        "AER0291"
+    } else if err.is::<Cancelled>() {
+        "AER0402"
+    } else {
+        UNKNOWN
+    }
+}
+
+/// Formats the specified error prefixed with the code of the error (*ie:
+/// `AER0104: error sending request`*).
+pub fn describe(err: &(dyn Error + 'static)) -> String {
+    format!("{}: {}", error_code(err), err)
+}
+
+/// An error with an explicitly assigned code, used for the failures that are
+/// only reported as text (*ie: failures of scripts*).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodedError {
+    code: &'static str,
+    message: String,
+}
+
+impl CodedError {
+    /// Creates a new error with the specified code and message.
+    pub fn new<M: Into<String>>(code: &'static str, message: M) -> CodedError {
+        CodedError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for CodedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for CodedError {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[test]
+    fn catalog_should_not_contain_duplicate_codes() {
+        let codes: HashSet<_> = CATALOG.iter().map(|info| info.code).collect();
+
+        assert_eq!(codes.len(), CATALOG.len());
+    }
+
+    #[rstest(
+        rule,
+        expected,
+        case("summary", "AER0203"),
+        case("release-notes", "AER0215"),
+        case("unknown-rule", UNKNOWN)
+    )]
+    fn rule_code_should_return_code_of_rule(rule: &str, expected: &str) {
+        assert_eq!(rule_code(rule), expected);
+    }
+
+    #[test]
+    fn lookup_should_ignore_case() {
+        let info = lookup("aer0203").unwrap();
+
+        assert_eq!(info.rule, Some("summary"));
+    }
+
+    #[test]
+    fn describe_should_prefix_message_with_code() {
+        let err = ParserError::Deserialize("missing field `id`".into());
+
+        assert_eq!(describe(&err), "AER0004: missing field `id`");
+    }
+
+    #[test]
+    fn describe_should_use_code_of_coded_errors() {
+        let err: Box<dyn Error> = Box::new(CodedError::new(INVALID_IMPORT, "Import failed"));
+
+        assert_eq!(describe(err.as_ref()), "AER0009: Import failed");
+    }
+
+    #[test]
+    fn error_code_should_use_code_of_source() {
+        #[derive(Debug)]
+        struct Wrapper(WebError);
+
+        impl Display for Wrapper {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("Wrapped")
+            }
+        }
+
+        impl Error for Wrapper {
+            fn source(&self) -> Option<&(dyn Error + 'static)> {
+                Some(&self.0)
+            }
+        }
+
+        let err = Wrapper(WebError::ChecksumMismatch {
+            expected: "abc".into(),
+            actual: "def".into(),
+        });
+
+        assert_eq!(describe(&err), "AER0106: Wrapped");
+    }
+
+    #[test]
+    fn error_codes_should_be_in_catalog() {
+        let errors: Vec<Box<dyn Error>> = vec![
+            Box::new(ParserError::Serialize(String::new())),
+            Box::new(ParserError::Extends {
+                path: PathBuf::new(),
+                message: String::new(),
+            }),
+            Box::new(WebError::Other(String::new())),
+            Box::new(GeneratorError::MissingData(String::new())),
+            Box::new(GeneratorError::Template {
+                line: 1,
+                message: String::new(),
+            }),
+            Box::new(GeneratorError::DescriptionFile {
+                path: PathBuf::new(),
+                source: std::io::ErrorKind::NotFound.into(),
+            }),
+            Box::new(PackError::MissingManifest(PathBuf::new())),
+            Box::new(ArchiveError::UnsafePath(String::new())),
+            Box::new(PolicyError::Syntax {
+                line: 1,
+                message: String::new(),
+            }),
+            Box::new(Cancelled),
+            Box::new(RunnerError::NoRunner(PathBuf::new())),
+            Box::new(CodedError::new(VALIDATION_FAILED, String::new())),
+            Box::new(CodedError::new(INVALID_LICENSE, String::new())),
+            Box::new(CodedError::new(VIRUSTOTAL_DETECTION, String::new())),
+            Box::new(FeatureError {
+                feature: "powershell",
+                capability: String::new(),
+            }),
+            Box::new(ConfigError::Parse(String::new())),
+        ];
+
+        for err in errors {
+            assert!(lookup(error_code(err.as_ref())).is_some());
+        }
+    }
+}
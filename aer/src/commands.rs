@@ -0,0 +1,42 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the commands that are shared between the `aer` binary and the
+//! deprecated standalone binaries (`aer-ver` and `aer-web`).
+
+pub mod checksum;
+pub mod config;
+pub mod history;
+pub mod state;
+pub mod ver;
+pub mod web;
+
+use std::fmt::Display;
+
+use lazy_static::lazy_static;
+use log::info;
+use yansi::{Color, Style};
+
+/// Outputs a single aligned line with the specified name and value.
+pub(crate) fn print_line<T: Display, V: Display>(name: T, value: V) {
+    lazy_static! {
+        static ref NAME_STYLE: Style = Color::Magenta.style();
+        static ref VALUE_STYLE: Style = Color::Cyan.style();
+    };
+
+    info!(
+        "{:>18} : {}",
+        NAME_STYLE.paint(name),
+        VALUE_STYLE.paint(value)
+    );
+}
+
+/// Outputs a single aligned line, using `None` as the value when the value is
+/// empty.
+pub(crate) fn print_string<T: Display>(name: T, value: &str) {
+    if value.is_empty() {
+        print_line(name, "None");
+    } else {
+        print_line(name, value);
+    }
+}
@@ -0,0 +1,201 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the command for generating the checksums of local files, and
+//! comparing the checksum of a file against an expected checksum.
+//!
+//! The checksums are outputted in the same format as the `sha256sum` family
+//! of tools (*ie: `<checksum>  <file name>`*), allowing the output of a
+//! directory to be used as a `SHA256SUMS` file or in the `VERIFICATION.txt`
+//! file of a package.
+
+use std::path::{Path, PathBuf};
+
+use log::{error, info};
+use structopt::StructOpt;
+use yansi::Color;
+
+use crate::commands::print_line;
+use crate::ChecksumType;
+
+/// The arguments available when generating checksums.
+#[derive(StructOpt)]
+pub struct ChecksumArguments {
+    /// The file to generate the checksum of, or a directory where the
+    /// checksum of every file in the directory and its sub directories are
+    /// generated.
+    #[structopt(parse(from_os_str))]
+    pub file: PathBuf,
+
+    /// The algorithm to use when generating the checksums.
+    #[structopt(long = "type", short = "t", default_value, possible_values = ChecksumType::variants_str(), env = "AER_CHECKSUM_TYPE")]
+    pub checksum_type: ChecksumType,
+
+    /// The checksum the file is expected to have, the command fails when the
+    /// checksum of the file do not match. This can not be used with
+    /// directories.
+    #[structopt(long)]
+    pub compare: Option<String>,
+}
+
+/// Generates the checksums of the path in the arguments, and compares the
+/// checksum against the expected checksum when specified.
+pub fn run(args: ChecksumArguments) {
+    if args.compare.is_some() && args.file.is_dir() {
+        error!("The checksum of a directory can not be compared, specify a file instead!");
+        std::process::exit(1);
+    }
+
+    let checksums = match generate_checksums(&args.file, args.checksum_type) {
+        Ok(checksums) => checksums,
+        Err(err) => {
+            error!(
+                "Unable to generate the checksums of '{}': {}",
+                args.file.display(),
+                err
+            );
+            std::process::exit(1);
+        }
+    };
+
+    print!("{}", format_sums(&checksums));
+
+    if let (Some(expected), Some((checksum, _))) = (args.compare, checksums.first()) {
+        if is_match(checksum, &expected) {
+            info!(
+                "{}",
+                Color::Green.paint("The checksum of the file matches the expected checksum!")
+            );
+        } else {
+            print_line("Expected", expected.trim());
+            print_line("Actual", checksum);
+            error!("The checksum of the file do not match the expected checksum!");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Generates the checksum of the file, or of every file in the directory and
+/// its sub directories. The checksums are returned together with the path of
+/// the file relative to the directory (*using `/` as the separator*), or the
+/// name of the file when the path is a file. The files are sorted by their
+/// relative path.
+pub fn generate_checksums(
+    path: &Path,
+    checksum_type: ChecksumType,
+) -> Result<Vec<(String, String)>, std::io::Error> {
+    if !path.is_dir() {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+
+        return Ok(vec![(checksum_type.generate(path)?, name)]);
+    }
+
+    let mut files = vec![];
+    collect_files(path, &mut files)?;
+    files.sort();
+
+    files
+        .into_iter()
+        .map(|file| {
+            let name = file
+                .strip_prefix(path)
+                .unwrap_or(&file)
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            Ok((checksum_type.generate(&file)?, name))
+        })
+        .collect()
+}
+
+/// Formats the checksums in the format used by `SHA256SUMS` files, with a
+/// single line for each file.
+pub fn format_sums(checksums: &[(String, String)]) -> String {
+    checksums
+        .iter()
+        .map(|(checksum, name)| format!("{}  {}\n", checksum, name))
+        .collect()
+}
+
+/// Returns wether the checksum matches the expected checksum, ignoring the
+/// casing and surrounding whitespace of the expected checksum.
+fn is_match(checksum: &str, expected: &str) -> bool {
+    checksum.eq_ignore_ascii_case(expected.trim())
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), std::io::Error> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    const HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    fn create_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("tools")).unwrap();
+        std::fs::write(dir.join("tools").join("app.exe"), "hello").unwrap();
+        std::fs::write(dir.join("LICENSE.txt"), "hello").unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn generate_checksums_should_use_name_of_file() {
+        let dir = create_dir("aer-checksum-file-test");
+
+        let actual = generate_checksums(&dir.join("LICENSE.txt"), ChecksumType::Sha256).unwrap();
+
+        assert_eq!(
+            actual,
+            [(HELLO_SHA256.to_owned(), "LICENSE.txt".to_owned())]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn generate_checksums_should_include_files_in_sub_directories() {
+        let dir = create_dir("aer-checksum-dir-test");
+
+        let actual = generate_checksums(&dir, ChecksumType::Sha256).unwrap();
+
+        assert_eq!(
+            format_sums(&actual),
+            format!("{0}  LICENSE.txt\n{0}  tools/app.exe\n", HELLO_SHA256)
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[rstest(
+        expected,
+        matches,
+        case(HELLO_SHA256, true),
+        case(
+            " 2CF24DBA5FB0A30E26E83B2AC5B9E29E1B161E5C1FA7425E73043362938B9824 ",
+            true
+        ),
+        case("abcdef", false)
+    )]
+    fn is_match_should_ignore_casing_and_whitespace(expected: &str, matches: bool) {
+        assert_eq!(is_match(HELLO_SHA256, expected), matches);
+    }
+}
@@ -0,0 +1,288 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the commands for reading and changing the settings in the
+//! configuration file, which defaults to the configuration file of the user
+//! when no file have been specified.
+//!
+//! Nested settings are specified using dotted keys, where keys containing
+//! dots are quoted (*ie: `rate_limits."github.com"`*). Values are parsed as
+//! TOML values, and used as strings when they are not valid TOML values.
+
+use std::error::Error;
+use std::path::Path;
+
+use log::{error, info};
+use structopt::StructOpt;
+use toml_edit::{Document, Item, Key, Table, TableLike, Value};
+
+use crate::config::{self, Config, ConfigError};
+use crate::context::Context;
+
+#[derive(StructOpt)]
+pub struct ConfigKeyArguments {
+    /// The dotted key of the setting (*ie: `proxy.url`*).
+    key: String,
+}
+
+#[derive(StructOpt)]
+pub struct ConfigSetArguments {
+    /// The dotted key of the setting (*ie: `proxy.url`*).
+    key: String,
+
+    /// The new value of the setting, arrays are specified using the TOML
+    /// syntax (*ie: `["AdmiringWorm"]`*).
+    value: String,
+}
+
+#[derive(StructOpt)]
+pub enum ConfigCommands {
+    /// Outputs the path of the configuration file that is used.
+    Path,
+    /// Outputs the value of a single setting in the configuration file.
+    Get(ConfigKeyArguments),
+    /// Changes the value of a single setting in the configuration file, the
+    /// file is created when it does not exist.
+    Set(ConfigSetArguments),
+    /// Removes a single setting from the configuration file.
+    Unset(ConfigKeyArguments),
+}
+
+/// Runs the specified configuration command.
+pub fn run(cmd: ConfigCommands, context: &Context) {
+    if let Err(err) = run_config(cmd, context) {
+        error!(
+            "An error occurred while handling the configuration: '{}'",
+            err
+        );
+        std::process::exit(1);
+    }
+}
+
+fn run_config(cmd: ConfigCommands, context: &Context) -> Result<(), Box<dyn Error>> {
+    let path = match context.config() {
+        Some(path) => path.to_path_buf(),
+        None => config::user_config_path()
+            .ok_or("Unable to locate the configuration directory of the user!")?,
+    };
+
+    match cmd {
+        ConfigCommands::Path => println!("{}", path.display()),
+        ConfigCommands::Get(args) => {
+            let text = read_text(&path)?;
+            match get_value(&text, &args.key)? {
+                Some(value) => println!("{}", value),
+                None => return Err(format!("The setting '{}' have not been set!", args.key).into()),
+            }
+        }
+        ConfigCommands::Set(args) => {
+            let text = set_value(&read_text(&path)?, &args.key, &args.value)?;
+            write_text(&path, &text)?;
+            info!("Set '{}' in '{}'", args.key, path.display());
+        }
+        ConfigCommands::Unset(args) => match unset_value(&read_text(&path)?, &args.key)? {
+            Some(text) => {
+                write_text(&path, &text)?;
+                info!("Removed '{}' from '{}'", args.key, path.display());
+            }
+            None => info!("The setting '{}' have not been set!", args.key),
+        },
+    }
+
+    Ok(())
+}
+
+fn read_text(path: &Path) -> Result<String, ConfigError> {
+    if path.exists() {
+        Ok(std::fs::read_to_string(path)?)
+    } else {
+        Ok(String::new())
+    }
+}
+
+fn write_text(path: &Path, text: &str) -> Result<(), ConfigError> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, text)?;
+
+    Ok(())
+}
+
+/// Returns the value of the setting with the specified key, strings are
+/// returned without quotes.
+pub fn get_value(text: &str, key: &str) -> Result<Option<String>, ConfigError> {
+    let document = parse_document(text)?;
+    let keys = parse_key(key)?;
+
+    let mut item = document.as_item();
+    for key in &keys {
+        item = match item.get(key.get()) {
+            Some(item) => item,
+            None => return Ok(None),
+        };
+    }
+
+    Ok(match item {
+        Item::None => None,
+        Item::Value(Value::String(value)) => Some(value.value().clone()),
+        Item::Value(value) => Some(value.to_string().trim().to_owned()),
+        item => Some(item.to_string().trim().to_owned()),
+    })
+}
+
+/// Changes the value of the setting with the specified key, and returns the
+/// changed configuration. The formatting and comments of the configuration
+/// are preserved, and an error is returned if the changed configuration is
+/// not valid.
+pub fn set_value(text: &str, key: &str, value: &str) -> Result<String, ConfigError> {
+    let mut document = parse_document(text)?;
+    let (last, parents) = split_key(key)?;
+    let mut value = value
+        .parse::<Value>()
+        .unwrap_or_else(|_| Value::from(value));
+
+    let mut table: &mut dyn TableLike = document.as_table_mut();
+    for parent in &parents {
+        table = table
+            .entry(parent.get())
+            .or_insert_with(|| Item::Table(Table::new()))
+            .as_table_like_mut()
+            .ok_or_else(|| {
+                ConfigError::Parse(format!("The setting '{}' is not a table", parent.get()))
+            })?;
+    }
+    match table.get_mut(last.get()) {
+        Some(item) => {
+            if let Some(existing) = item.as_value() {
+                *value.decor_mut() = existing.decor().clone();
+            }
+            *item = Item::Value(value);
+        }
+        None => {
+            table.insert(last.get(), Item::Value(value));
+        }
+    }
+
+    let text = document.to_string();
+    Config::parse(&text)?;
+
+    Ok(text)
+}
+
+/// Removes the setting with the specified key, and returns the changed
+/// configuration, or `None` when the setting have not been set.
+pub fn unset_value(text: &str, key: &str) -> Result<Option<String>, ConfigError> {
+    let mut document = parse_document(text)?;
+    let (last, parents) = split_key(key)?;
+
+    let mut table: &mut dyn TableLike = document.as_table_mut();
+    for parent in &parents {
+        table = match table
+            .get_mut(parent.get())
+            .and_then(Item::as_table_like_mut)
+        {
+            Some(table) => table,
+            None => return Ok(None),
+        };
+    }
+
+    Ok(table.remove(last.get()).map(|_| document.to_string()))
+}
+
+fn parse_document(text: &str) -> Result<Document, ConfigError> {
+    text.parse::<Document>()
+        .map_err(|err| ConfigError::Parse(format!("The configuration file is not valid: {}", err)))
+}
+
+fn parse_key(key: &str) -> Result<Vec<Key>, ConfigError> {
+    Key::parse(key).map_err(|_| ConfigError::Parse(format!("The key '{}' is not a valid key", key)))
+}
+
+fn split_key(key: &str) -> Result<(Key, Vec<Key>), ConfigError> {
+    let mut keys = parse_key(key)?;
+    match keys.pop() {
+        Some(last) => Ok((last, keys)),
+        None => Err(ConfigError::Parse(format!(
+            "The key '{}' is not a valid key",
+            key
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    const TEXT: &str = "# The defaults of the user\njobs = 4\nmaintainers = \
+                        [\"AdmiringWorm\"]\n\n[rate_limits]\n\"github.com\" = 2.0\n";
+
+    #[rstest]
+    #[case("jobs", Some("4"))]
+    #[case("maintainers", Some("[\"AdmiringWorm\"]"))]
+    #[case("rate_limits.\"github.com\"", Some("2.0"))]
+    #[case("rules", None)]
+    #[case("proxy.url", None)]
+    fn get_value_should_return_value_of_setting(#[case] key: &str, #[case] expected: Option<&str>) {
+        let actual = get_value(TEXT, key).unwrap();
+
+        assert_eq!(actual.as_deref(), expected);
+    }
+
+    #[test]
+    fn set_value_should_preserve_comments_and_other_settings() {
+        let actual = set_value(TEXT, "jobs", "8").unwrap();
+
+        assert_eq!(actual, TEXT.replace("jobs = 4", "jobs = 8"));
+    }
+
+    #[rstest]
+    #[case("rules", "community", "community")]
+    #[case("log_level", "\"debug\"", "debug")]
+    #[case("proxy.url", "http://proxy.example.org/", "http://proxy.example.org/")]
+    #[case("rate_limits.\"sourceforge.net\"", "0.5", "0.5")]
+    fn set_value_should_add_setting(
+        #[case] key: &str,
+        #[case] value: &str,
+        #[case] expected: &str,
+    ) {
+        let text = set_value(TEXT, key, value).unwrap();
+
+        let actual = get_value(&text, key).unwrap();
+
+        assert_eq!(actual.as_deref(), Some(expected));
+    }
+
+    #[rstest]
+    #[case("jobs", "many")]
+    #[case("rules", "unknown")]
+    #[case("log_level", "verbose")]
+    #[case("unknown", "value")]
+    #[case("jobs.count", "4")]
+    fn set_value_should_return_error_on_invalid_settings(#[case] key: &str, #[case] value: &str) {
+        let actual = set_value(TEXT, key, value);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn unset_value_should_remove_setting() {
+        let text = unset_value(TEXT, "rate_limits.\"github.com\"")
+            .unwrap()
+            .unwrap();
+
+        let actual = get_value(&text, "rate_limits.\"github.com\"").unwrap();
+
+        assert_eq!(actual, None);
+        assert!(text.contains("jobs = 4"));
+    }
+
+    #[test]
+    fn unset_value_should_return_none_when_setting_is_not_set() {
+        let actual = unset_value(TEXT, "proxy.url").unwrap();
+
+        assert_eq!(actual, None);
+    }
+}
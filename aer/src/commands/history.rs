@@ -0,0 +1,73 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the command for outputting the state recorded for a single
+//! package, like the last time the package was checked and the last version
+//! that was found upstream.
+
+use log::error;
+use serde_json::json;
+use structopt::StructOpt;
+
+use crate::commands::{print_line, print_string};
+use crate::context::Context;
+use crate::state::CheckState;
+
+/// The arguments available when outputting the history of a package.
+#[derive(StructOpt)]
+pub struct HistoryArguments {
+    /// The identifier of the package to output the history of.
+    pub id: String,
+
+    /// Output the history as a single JSON object.
+    #[structopt(long)]
+    pub json: bool,
+}
+
+/// Outputs the recorded history of the package in the arguments.
+pub fn run(args: HistoryArguments, context: &Context) {
+    let state = match CheckState::load(&context.data_root().state_dir()) {
+        Ok(state) => state,
+        Err(err) => {
+            error!("Unable to load the state of previous runs: '{}'", err);
+            std::process::exit(1);
+        }
+    };
+
+    if !state.packages().contains(&args.id.as_str()) {
+        error!(
+            "No history have been recorded for the package '{}'!",
+            args.id
+        );
+        std::process::exit(1);
+    }
+
+    let last_checked = state.last_checked(&args.id);
+    let history = state.history(&args.id);
+
+    if args.json {
+        let mut value = history.to_json();
+        value["id"] = args.id.clone().into();
+        value["checked"] = json!(last_checked.map(|date| date.to_rfc3339()));
+        println!("{}", value);
+        return;
+    }
+
+    let format_date = |date: Option<chrono::DateTime<chrono::Utc>>| {
+        date.map(|date| date.to_rfc3339()).unwrap_or_default()
+    };
+
+    print_line("Package", &args.id);
+    print_string("Last checked", &format_date(last_checked));
+    print_string(
+        "Upstream version",
+        history.version.as_deref().unwrap_or_default(),
+    );
+    print_string("Version changed", &format_date(history.changed));
+    print_line("Failures", history.failures);
+    print_string(
+        "Pushed version",
+        history.pushed_version.as_deref().unwrap_or_default(),
+    );
+    print_string("Last pushed", &format_date(history.pushed));
+}
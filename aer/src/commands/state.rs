@@ -0,0 +1,66 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the commands for exporting and importing the state kept between
+//! runs, allowing the state to be cached as an artifact by ephemeral CI
+//! runners instead of requiring a persistent state directory.
+
+use std::path::PathBuf;
+
+use log::{error, info};
+use structopt::StructOpt;
+
+use crate::context::Context;
+use crate::state::CheckState;
+
+#[derive(StructOpt)]
+pub struct StateFileArguments {
+    /// The path of the exported state file.
+    #[structopt(parse(from_os_str))]
+    file: PathBuf,
+}
+
+#[derive(StructOpt)]
+pub enum StateCommands {
+    /// Exports the last checked times and the update history of all packages
+    /// to a single file.
+    Export(StateFileArguments),
+    /// Imports a previously exported state file, and merges it into the
+    /// current state. Packages are only replaced when the imported package was
+    /// checked more recently.
+    Import(StateFileArguments),
+}
+
+/// Runs the specified state command.
+pub fn run(cmd: StateCommands, context: &Context) {
+    if let Err(err) = run_state(cmd, context) {
+        error!("An error occurred while handling the state: '{}'", err);
+        std::process::exit(1);
+    }
+}
+
+fn run_state(cmd: StateCommands, context: &Context) -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = CheckState::load(&context.data_root().state_dir())?;
+
+    match cmd {
+        StateCommands::Export(args) => {
+            state.export(&args.file)?;
+            info!(
+                "Exported the state of {} packages to '{}'",
+                state.packages().len(),
+                args.file.display()
+            );
+        }
+        StateCommands::Import(args) => {
+            let merged = state.import(&args.file)?;
+            state.save()?;
+            info!(
+                "Imported the state of {} packages from '{}'",
+                merged,
+                args.file.display()
+            );
+        }
+    }
+
+    Ok(())
+}
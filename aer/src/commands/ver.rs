@@ -0,0 +1,89 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the command for testing how version strings are transformed to
+//! the versions used by the supported package managers.
+
+use aer_upd::data::chocolatey::{ChocoVersion, FixScheme, FixTimeZone};
+use aer_upd::data::SemVersion;
+use log::{error, info};
+use structopt::StructOpt;
+
+use crate::clock::Clock;
+use crate::commands::print_line;
+use crate::context::Context;
+
+/// The arguments available when testing the transformation of versions.
+#[derive(StructOpt)]
+pub struct VerArguments {
+    /// The Versions to test what they would be transformed to (*multiple values
+    /// can be specified*).
+    #[structopt(required = true)]
+    pub versions: Vec<String>,
+
+    /// Also display what fix version would be created (if the type allows fix
+    /// versions).
+    #[structopt(long)]
+    pub with_fix_version: bool,
+
+    /// The time zone to use when getting the current date for fix versions.
+    #[structopt(long, default_value, possible_values = &["utc", "local"], env = "AER_FIX_TIME_ZONE")]
+    pub fix_time_zone: FixTimeZone,
+
+    /// The scheme to use when creating fix versions, either the current date
+    /// or an incrementing revision.
+    #[structopt(long, default_value, possible_values = &["date", "revision"])]
+    pub fix_scheme: FixScheme,
+}
+
+/// Outputs the converted values of every version in the arguments.
+pub fn run(args: VerArguments, context: &Context) {
+    let clock = context.clock();
+    info!(
+        "Checking {} {}...",
+        args.versions.len(),
+        if args.versions.len() == 1 {
+            "version"
+        } else {
+            "versions"
+        }
+    );
+
+    for version in &args.versions {
+        println!(); // We don't need to add an empty line in the log file
+        print_line("Raw Version", version);
+        println!();
+        if let Ok(mut choco) = ChocoVersion::parse(version) {
+            print_line("Chocolatey", &choco);
+            let semver: SemVersion = choco.clone().into();
+            print_line("SemVer from Choco", semver);
+            if args.with_fix_version {
+                print_fix_version(&mut choco, &args, clock);
+            }
+        } else {
+            print_line("Chocolatey", "None");
+            print_line("SemVer from Choco", "None");
+        }
+        println!();
+
+        let semver = SemVersion::parse(version);
+        if let Ok(semver) = semver {
+            print_line("SemVer", &semver);
+            let mut choco: ChocoVersion = semver.into();
+            print_line("Choco from SemVer", &choco);
+            if args.with_fix_version {
+                print_fix_version(&mut choco, &args, clock);
+            }
+        } else {
+            print_line("SemVer", "None");
+            print_line("Choco from SemVer", "None");
+        }
+    }
+}
+
+fn print_fix_version(choco: &mut ChocoVersion, args: &VerArguments, clock: &Clock) {
+    match choco.add_fix_with(args.fix_scheme, clock.today(args.fix_time_zone)) {
+        Ok(_) => print_line("Chocolatey Fix", &choco),
+        Err(err) => error!("An error occurred while creating fix version: {}", err),
+    }
+}
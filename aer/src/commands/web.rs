@@ -0,0 +1,702 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the commands for testing different web related tasks, like
+//! parsing html pages and downloading binary files.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use aer_upd::data::Url;
+use aer_upd::web::crawl::CrawlOptions;
+use aer_upd::web::errors::WebError;
+use aer_upd::web::{LinkElement, LinkType, ResponseType, WebRequest, WebResponse};
+#[cfg(feature = "human")]
+use human_bytes::human_bytes;
+use log::{error, info, warn};
+use serde_json::{json, Value};
+use structopt::StructOpt;
+use yansi::Color;
+
+use crate::commands::{print_line, print_string};
+use crate::context::Context;
+use crate::ChecksumType;
+
+#[derive(StructOpt)]
+#[structopt(after_help = "EXAMPLES:
+    Parsing all urls
+      `parse https://github.com/codecove/codecov-exe/releases/latest`Parsing \
+                          on matching urls
+      `parse https://github.com/codecove/codecov-exe/releases/latest \
+                          --regex '.*\\.zip$'`
+    Parsing while extracting version
+      `parse https://github.com/codecov/codecov-exe/releases/latest \
+                          --regex '/(?P<version>[\\d\\.]+)/.*\\.zip$'`")]
+pub struct ParseArguments {
+    /// The url to use to test parsing a single web page.
+    url: Url,
+
+    /// The regular expression to use when parsing the specified `url`.
+    #[structopt(long, short)]
+    regex: Option<String>,
+
+    /// Outputs the found links in the specified format, instead of the lines
+    /// readable by humans. The links are written to the standard output
+    /// unless `--output-file` is specified.
+    #[structopt(long, short, possible_values = LinkFormat::variants_str())]
+    output: Option<LinkFormat>,
+
+    /// The file the found links are written to, the links are written using
+    /// the plain format when no output format have been specified.
+    #[structopt(long, parse(from_os_str))]
+    output_file: Option<PathBuf>,
+}
+
+#[derive(StructOpt)]
+pub struct CrawlArguments {
+    /// The url of the website to start crawling from.
+    url: Url,
+
+    /// The number of links to follow from the first page, a depth of `0` only
+    /// reads the first page.
+    #[structopt(long, short, default_value = "1")]
+    depth: usize,
+
+    /// The regular expression the links must match to be followed, all links
+    /// on the same host are followed when this is not specified.
+    #[structopt(long, short)]
+    follow: Option<String>,
+
+    /// The regular expression the binary links must match to be outputted,
+    /// the version of the links are read from the `version` group.
+    #[structopt(long, short)]
+    regex: Option<String>,
+
+    /// The maximum number of pages to read, including the first page.
+    #[structopt(long, default_value = "50")]
+    max_pages: usize,
+
+    /// Outputs the found links in the specified format, instead of the lines
+    /// readable by humans. The links are written to the standard output
+    /// unless `--output-file` is specified.
+    #[structopt(long, short, possible_values = LinkFormat::variants_str())]
+    output: Option<LinkFormat>,
+
+    /// The file the found links are written to, the links are written using
+    /// the plain format when no output format have been specified.
+    #[structopt(long, parse(from_os_str))]
+    output_file: Option<PathBuf>,
+}
+
+/// The format used when outputting the links found when parsing a web page.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LinkFormat {
+    /// Outputs the url of each link on a separate line.
+    #[default]
+    Plain,
+    /// Outputs the links as a JSON array, containing an object for each link.
+    Json,
+    /// Outputs the links as comma separated values, with a header line naming
+    /// the columns.
+    Csv,
+}
+
+impl FromStr for LinkFormat {
+    type Err = &'static str;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val.trim().to_lowercase().as_str() {
+            "plain" => Ok(LinkFormat::Plain),
+            "json" => Ok(LinkFormat::Json),
+            "csv" => Ok(LinkFormat::Csv),
+            _ => Err("The value is not a supported output format!"),
+        }
+    }
+}
+
+impl LinkFormat {
+    pub fn variants_str() -> &'static [&'static str] {
+        static VARIANTS: &[&str] = &["plain", "json", "csv"];
+
+        VARIANTS
+    }
+
+    /// Formats the links, the returned text always ends with a new line.
+    pub fn format(self, links: &[LinkElement]) -> String {
+        match self {
+            LinkFormat::Plain => links
+                .iter()
+                .map(|link| format!("{}\n", link.link))
+                .collect(),
+            LinkFormat::Json => format!(
+                "{:#}\n",
+                Value::Array(links.iter().map(link_to_json).collect())
+            ),
+            LinkFormat::Csv => {
+                let mut text = String::from("url,type,title,text,version,attributes\n");
+                for link in links {
+                    let fields = [
+                        link.link.to_string(),
+                        link.link_type.to_string(),
+                        link.title.clone(),
+                        link.text.clone(),
+                        link.version
+                            .as_ref()
+                            .map(|version| version.to_string())
+                            .unwrap_or_default(),
+                        sorted_attributes(link)
+                            .iter()
+                            .map(|(key, value)| format!("{}={}", key, value))
+                            .collect::<Vec<_>>()
+                            .join(";"),
+                    ];
+                    let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+                    text.push_str(&fields.join(","));
+                    text.push('\n');
+                }
+
+                text
+            }
+        }
+    }
+}
+
+#[derive(StructOpt)]
+pub struct DownloadArguments {
+    /// The url of the binary file to download.
+    url: Url,
+
+    /// Keep any downloaded file instead of the normal procedure of deleting
+    /// them at the end validation.
+    #[structopt(long)]
+    keep_files: bool,
+
+    /// The etag that will be matched against the download location. If matched
+    /// and the server returns a Not Modified response, then no file will be
+    /// downloaded.
+    #[structopt(long, short)]
+    etag: Option<String>,
+
+    /// The last modified date as a string, this is usually the date that has
+    /// been previously returned by a server. If this date matches and the
+    /// server responds with a Not Modified response, then no file will be
+    /// downloaded.
+    #[structopt(long, short)]
+    last_modified: Option<String>,
+
+    /// The file name to use when downloading a file. This can be used to
+    /// override the default name, or if a name can not be detected.
+    #[structopt(long)]
+    file_name: Option<String>,
+
+    /// The checksum to compare the downladed file with. If an existing file
+    /// with the a matching name and it matches the checksum, then a download
+    /// will not occurr (*NOT IMPLEMENTED*).
+    #[structopt(long, short)]
+    checksum: Option<String>,
+
+    /// The type of the checksum to use when comparing and/or outputting to the
+    /// console.
+    #[structopt(long, default_value, possible_values = ChecksumType::variants_str(), env = "AER_CHECKSUM_TYPE")]
+    checksum_type: ChecksumType,
+
+    /// The directory to use when downloading the files, this is set from the
+    /// global `--work-dir` option.
+    #[structopt(skip)]
+    work_dir: Option<PathBuf>,
+
+    /// The API key to use when looking up the downloaded file on VirusTotal.
+    /// No lookup will be done when this is not specified.
+    #[structopt(long, env = "AER_VIRUSTOTAL_KEY", hide_env_values = true)]
+    virustotal_key: Option<String>,
+
+    /// The number of anti virus engines that are allowed to flag the
+    /// downloaded file before the download is considered a failure.
+    #[structopt(long, default_value = "0")]
+    virustotal_threshold: u32,
+}
+
+#[derive(StructOpt)]
+pub struct HeadArguments {
+    /// The url of the file to read the metadata of.
+    url: Url,
+
+    /// The etag that will be matched against the location, the server will
+    /// respond with a Not Modified status when the etag matches.
+    #[structopt(long, short)]
+    etag: Option<String>,
+
+    /// The last modified date as a string, the server will respond with a Not
+    /// Modified status when the file have not been modified since this date.
+    #[structopt(long, short)]
+    last_modified: Option<String>,
+}
+
+/// The web related tasks that are available.
+#[derive(StructOpt)]
+pub enum WebCommands {
+    /// Allows testing a single parse command using the specified url, and
+    /// optionally an regex. This will output any links found on the website.
+    Parse(ParseArguments),
+    /// Allows downloading a single binary file, by defailt this command will
+    /// use `%TEMP%` as the work directory and will remove the downladed file
+    /// afterwards.
+    Download(DownloadArguments),
+    /// Reads the metadata of a single file without downloading the file, by
+    /// sending a `HEAD` request and outputting the status, size, type, etag,
+    /// last modified date and final location reported by the server.
+    #[structopt(alias = "probe")]
+    Head(HeadArguments),
+    /// Crawls the website at the specified url, following the links to other
+    /// pages on the same host and outputting the binary links found on every
+    /// page that was read.
+    Crawl(CrawlArguments),
+}
+
+/// Runs the specified web command.
+pub fn run(cmd: WebCommands, context: &Context) {
+    let request = context.request();
+    match cmd {
+        WebCommands::Parse(args) => parse_cmd(request, args),
+        WebCommands::Download(args) => download_cmd(request, args, context),
+        WebCommands::Head(args) => head_cmd(request, args),
+        WebCommands::Crawl(args) => crawl_cmd(request, args),
+    }
+}
+
+fn parse_cmd(request: WebRequest, args: ParseArguments) {
+    match parse_website(request, args.url, args.regex) {
+        Ok((_, links)) if args.output.is_some() || args.output_file.is_some() => {
+            output_links(&links, args.output, args.output_file.as_deref());
+        }
+        Ok((parent, links)) => {
+            info!(
+                "Successfully parsed '{}'",
+                Color::Magenta.paint(parent.link)
+            );
+
+            for link in &links {
+                log_link(link);
+            }
+
+            info!(
+                "Found {} links on the webpage!",
+                Color::Cyan.paint(links.len())
+            );
+            info!("The following link types was found!");
+            for link_type in LinkType::variants() {
+                let count = links.iter().filter(|l| l.link_type == *link_type).count();
+
+                info!("Found {:2} {} types!", Color::Cyan.paint(count), link_type);
+            }
+        }
+        Err(err) => {
+            error!("Unable to parse the requested website!");
+            error!("Error message: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn crawl_cmd(request: WebRequest, args: CrawlArguments) {
+    let mut options = CrawlOptions::new(args.depth);
+    options.follow = args.follow;
+    options.regex = args.regex;
+    options.max_pages = args.max_pages;
+
+    match request.crawl(args.url.as_str(), &options) {
+        Ok(links) if args.output.is_some() || args.output_file.is_some() => {
+            output_links(&links, args.output, args.output_file.as_deref());
+        }
+        Ok(links) => {
+            for link in &links {
+                log_link(link);
+            }
+
+            info!(
+                "Found {} binary links while crawling '{}'!",
+                Color::Cyan.paint(links.len()),
+                Color::Magenta.paint(&args.url)
+            );
+        }
+        Err(err) => {
+            error!("Unable to crawl the requested website!");
+            error!("Error message: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Outputs the links in the specified format to the standard output, or to
+/// the file when a file have been specified.
+fn output_links(links: &[LinkElement], format: Option<LinkFormat>, file: Option<&Path>) {
+    let text = format.unwrap_or_default().format(links);
+
+    match file {
+        Some(path) => {
+            if let Err(err) = std::fs::write(path, text) {
+                error!("Unable to write the links to '{}'!", path.display());
+                error!("Error message: {}", err);
+                std::process::exit(1);
+            }
+            info!(
+                "Wrote {} links to '{}'",
+                Color::Cyan.paint(links.len()),
+                path.display()
+            );
+        }
+        None => print!("{}", text),
+    }
+}
+
+fn log_link(link: &LinkElement) {
+    info!(
+        "{} (type: {}, title: {}, version: {}, text: {})",
+        Color::Magenta.paint(&link.link),
+        Color::Cyan.paint(&link.link_type),
+        Color::Cyan.paint(if link.title.is_empty() {
+            "None"
+        } else {
+            &link.title
+        }),
+        Color::Cyan.paint(if let Some(version) = &link.version {
+            version.to_string()
+        } else {
+            "None".into()
+        }),
+        Color::Cyan.paint(&link.text)
+    );
+}
+
+fn download_cmd(request: WebRequest, mut args: DownloadArguments, context: &Context) {
+    args.work_dir = Some(context.work_dir());
+
+    if let Err(err) = download_file(request, args) {
+        error!("Unable to download the file. Error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+fn head_cmd(request: WebRequest, args: HeadArguments) {
+    let response = request.get_head_response(
+        args.url.as_str(),
+        args.etag.as_deref(),
+        args.last_modified.as_deref(),
+    );
+
+    match response {
+        Ok(response) => {
+            print_line("Status", response.status);
+            print_line("Url", &response.url);
+            print_line(
+                "Content Length",
+                response
+                    .content_length
+                    .map(|length| length.to_string())
+                    .unwrap_or_else(|| "None".into()),
+            );
+            print_string(
+                "Content Type",
+                response.content_type.as_deref().unwrap_or_default(),
+            );
+            print_string(
+                "ETag",
+                response
+                    .etag
+                    .as_deref()
+                    .unwrap_or_default()
+                    .trim_matches('"'),
+            );
+            print_string(
+                "Last Modified",
+                response.last_modified.as_deref().unwrap_or_default(),
+            );
+
+            if response.is_not_modified() {
+                info!("The file have not been modified, no download is necessary!");
+            } else if !response.is_success() {
+                error!(
+                    "The web server responded with the unsuccessful status {}!",
+                    response.status
+                );
+                std::process::exit(1);
+            }
+        }
+        Err(err) => {
+            error!("Unable to read the metadata of the file. Error: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Converts the link to a JSON object.
+fn link_to_json(link: &LinkElement) -> Value {
+    json!({
+        "url": link.link.as_str(),
+        "type": link.link_type.to_string(),
+        "title": link.title,
+        "text": link.text,
+        "version": link.version.as_ref().map(|version| version.to_string()),
+        "attributes": sorted_attributes(link),
+    })
+}
+
+/// Returns the attributes of the link, sorted by the name of the attributes.
+fn sorted_attributes(link: &LinkElement) -> BTreeMap<&str, &str> {
+    link.attributes
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .collect()
+}
+
+/// Quotes the value when it contains a comma, a quote or a new line, quotes
+/// inside the value are escaped by doubling them.
+fn csv_field(value: &str) -> String {
+    if value.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+fn parse_website(
+    request: WebRequest,
+    url: Url,
+    regex: Option<String>,
+) -> Result<(LinkElement, Vec<LinkElement>), WebError> {
+    let response = request.get_html_response(url.as_str())?;
+
+    if let Some(ref regex) = regex {
+        response.read(Some(regex))
+    } else {
+        response.read(None)
+    }
+}
+
+fn download_file(request: WebRequest, args: DownloadArguments) -> Result<(), WebError> {
+    let etag = if let Some(ref etag) = args.etag {
+        Some(etag.as_str())
+    } else {
+        None
+    };
+    let last_modified = if let Some(ref last_modified) = args.last_modified {
+        Some(last_modified.as_str())
+    } else {
+        None
+    };
+
+    if let Some(ref file_name) = args.file_name {
+        if validate_local_file(&args, file_name)? {
+            return Ok(());
+        }
+    }
+
+    let response = request.get_binary_response(args.url.as_str(), etag, last_modified)?;
+
+    match response {
+        ResponseType::Updated(_) => {
+            info!("No download is necessary!");
+        }
+        ResponseType::New(mut response, _) => {
+            if args.file_name.is_none() {
+                let file_name = response.file_name().unwrap();
+                if validate_local_file(&args, &file_name)? {
+                    return Ok(());
+                }
+            }
+
+            response.set_work_dir(&args.work_dir.unwrap());
+
+            let (etag, last_modified) = get_info(&response);
+            let file_name = args.file_name.as_deref();
+            let (result, checksum) = match args.checksum {
+                Some(ref expected) => {
+                    let path = response.read_verified(file_name, args.checksum_type, expected)?;
+                    (path, expected.to_lowercase())
+                }
+                None => response.read_with_checksum(file_name, args.checksum_type)?,
+            };
+            info!("The following information was given by the server:");
+            print_string("ETag", etag.trim_matches('"'));
+            print_string("Last Modified", &last_modified);
+            print_line("Checksum", &checksum);
+            print_line("Checksum Type", args.checksum_type);
+
+            if args.checksum.is_some() {
+                info!(
+                    "{}",
+                    Color::Green
+                        .paint("Original Checksum matches the checksum of the downloaded file!")
+                );
+            }
+
+            let len = {
+                #[cfg(feature = "human")]
+                {
+                    human_bytes(result.metadata()?.len() as f64)
+                }
+                #[cfg(not(feature = "human"))]
+                format!("{} bytes", result.metadata()?.len())
+            };
+
+            info!("The resulting file is {} long!", Color::Cyan.paint(len));
+
+            let reputation = if let Some(ref api_key) = args.virustotal_key {
+                check_virustotal(&request, &result, api_key, args.virustotal_threshold)
+            } else {
+                Ok(())
+            };
+
+            if !args.keep_files {
+                let _ = std::fs::remove_file(result);
+            }
+
+            reputation?;
+        }
+    }
+
+    Ok(())
+}
+
+fn check_virustotal(
+    request: &WebRequest,
+    path: &Path,
+    api_key: &str,
+    threshold: u32,
+) -> Result<(), WebError> {
+    let checksum = ChecksumType::Sha256.generate(path)?;
+
+    match request.get_virustotal_report(&checksum, api_key)? {
+        Some(ratio) => {
+            print_line("VirusTotal", ratio);
+            if ratio.exceeds(threshold) {
+                return Err(WebError::Other(format!(
+                    "The file was flagged by {} anti virus engines, which is above the threshold \
+                     of {}!",
+                    ratio, threshold
+                )));
+            }
+        }
+        None => print_line("VirusTotal", "Not Scanned"),
+    }
+
+    Ok(())
+}
+
+fn validate_local_file(args: &DownloadArguments, file_name: &str) -> Result<bool, WebError> {
+    let checksum = if let Some(ref checksum) = args.checksum {
+        checksum
+    } else {
+        return Ok(false);
+    };
+
+    let work_dir = args.work_dir.as_ref().unwrap();
+    let possible_path = work_dir.join(file_name);
+
+    if possible_path.exists() {
+        let file_checksum = args.checksum_type.generate(&possible_path)?;
+        if checksum.to_lowercase() == file_checksum {
+            info!("File exists, and matches the specified checksum. Nothing to download!");
+            return Ok(true);
+        } else {
+            warn!("File exists, but do not match the specified checksum. Re-downloading file!");
+        }
+    } else {
+        info!("File exists, but no checksum available. Continuing download!");
+    }
+
+    Ok(false)
+}
+
+fn get_info<T: WebResponse>(response: &T) -> (String, String) {
+    let headers = response.get_headers();
+    let mut etag = String::new();
+    let mut last_modified = String::new();
+
+    if let Some(etag_val) = headers.get("etag") {
+        etag = etag_val.to_string();
+    }
+    if let Some(modified_val) = headers.get("last-modified") {
+        last_modified = modified_val.to_string();
+    }
+
+    (etag, last_modified)
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_upd::data::Versions;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn links() -> Vec<LinkElement> {
+        let mut link = LinkElement::new(
+            Url::parse("https://example.org/app-1.0.0.zip").unwrap(),
+            LinkType::Binary,
+        );
+        link.title = "App, portable".into();
+        link.text = "Download \"App\"".into();
+        link.version = Some(Versions::parse("1.0.0").unwrap());
+        link.attributes.insert("rel".into(), "nofollow".into());
+        link.attributes.insert("class".into(), "download".into());
+
+        vec![
+            link,
+            LinkElement::new(
+                Url::parse("https://example.org/changelog").unwrap(),
+                LinkType::Html,
+            ),
+        ]
+    }
+
+    #[test]
+    fn format_should_output_plain_links() {
+        let actual = LinkFormat::Plain.format(&links());
+
+        assert_eq!(
+            actual,
+            "https://example.org/app-1.0.0.zip\nhttps://example.org/changelog\n"
+        );
+    }
+
+    #[test]
+    fn format_should_output_json_links() {
+        let actual: Value = serde_json::from_str(&LinkFormat::Json.format(&links())).unwrap();
+
+        assert_eq!(actual[0]["url"], "https://example.org/app-1.0.0.zip");
+        assert_eq!(actual[0]["type"], LinkType::Binary.to_string());
+        assert_eq!(actual[0]["title"], "App, portable");
+        assert_eq!(actual[0]["version"], "1.0.0");
+        assert_eq!(actual[0]["attributes"]["rel"], "nofollow");
+        assert_eq!(actual[1]["version"], Value::Null);
+    }
+
+    #[test]
+    fn format_should_output_csv_links() {
+        let actual = LinkFormat::Csv.format(&links());
+
+        assert_eq!(
+            actual,
+            format!(
+                "url,type,title,text,version,attributes\nhttps://example.org/app-1.0.0.zip,{},\"App, \
+                 portable\",\"Download \"\"App\"\"\",1.0.0,class=download;rel=nofollow\nhttps://\
+                 example.org/changelog,{},,,,\n",
+                LinkType::Binary,
+                LinkType::Html
+            )
+        );
+    }
+
+    #[rstest(
+        value,
+        expected,
+        case("plain", LinkFormat::Plain),
+        case("JSON", LinkFormat::Json),
+        case(" csv ", LinkFormat::Csv)
+    )]
+    fn from_str_should_parse_format(value: &str, expected: LinkFormat) {
+        assert_eq!(LinkFormat::from_str(value), Ok(expected));
+    }
+}
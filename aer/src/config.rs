@@ -0,0 +1,963 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the global configuration file of the application, specified with
+//! the `--config` option. The configuration file of the user (*ie:
+//! `~/.config/aer/config.toml`, or `%APPDATA%\aer\config.toml` on Windows*)
+//! is used when no file have been specified.
+//!
+//! The file can hold the defaults of the options that would otherwise need to
+//! be repeated on every run, these are used when neither the option nor its
+//! environment variable have been specified. The tokens used when reading
+//! releases from GitHub and GitLab are read from the environment variables
+//! named in the file, and the default maintainers are used for packages that
+//! do not specify any maintainers. Relative paths are resolved against the
+//! directory of the configuration file. The settings can be changed using the
+//! `aer config` command.
+//!
+//! The configuration file holds the named authentication profiles of private
+//! or internal feeds, which are used when downloading files from the feeds and
+//! when pushing packages to the feeds. Profiles authenticate using basic
+//! authentication, a bearer token and/or custom headers. Only the names of
+//! the environment variables holding the secrets are stored in the file, the
+//! secrets can also be stored in a separate credentials file (*keyed by the
+//! name of the profile*) which is used when the variables have not been set.
+//! The authentication rules of packages may only use a profile for the host of
+//! its feed, or for the additional hosts listed in the `hosts` of the profile.
+//!
+//! The file can also hold the proxy that requests are sent through, and
+//! additional root certificates that should be trusted (*ie: in environments
+//! where TLS connections are inspected*). Relative paths to certificates are
+//! resolved against the directory of the configuration file. The number of
+//! requests per second sent to specific hosts can be limited in the
+//! `rate_limits` table, which also applies to the sub domains of the hosts.
+//!
+//! The summary of update runs can be posted to webhooks or sent as an email,
+//! configured in the `notifications` table. The urls of the webhooks can be
+//! read from environment variables, as these usually contain secret tokens.
+//! Emails are only sent with credentials over an encrypted connection, where
+//! the `tls` setting is either `opportunistic` (*the default*), `starttls`,
+//! `wrapper` or `none`.
+//!
+//! The downloaded files can be looked up on VirusTotal, configured in the
+//! `virustotal` table, where the update of a package fails when a file have
+//! been flagged by more anti virus engines than the threshold allows.
+//!
+//! ### Examples
+//!
+//! ```toml
+//! work_dir = "work"
+//! maintainers = ["AdmiringWorm"]
+//! rules = "chocolatey-community"
+//! jobs = 4
+//! log_path = "logs/aer.log"
+//! log_level = "debug"
+//! github_token_env = "GITHUB_TOKEN"
+//! gitlab_token_env = "GITLAB_TOKEN"
+//! ca_certificates = ["corporate-root.pem"]
+//!
+//! [proxy]
+//! url = "http://proxy.example.org:8080"
+//! no_proxy = ["localhost", ".example.org"]
+//! username = "ci-user"
+//! password_env = "PROXY_PASSWORD"
+//!
+//! credentials_file = "credentials.toml"
+//!
+//! [rate_limits]
+//! "github.com" = 1.0
+//! "sourceforge.net" = 0.5
+//!
+//! [profiles.internal]
+//! feed = "https://nexus.example.org/repository/choco/"
+//! api_key_env = "NEXUS_API_KEY"
+//! username = "ci-user"
+//! password_env = "NEXUS_PASSWORD"
+//!
+//! [profiles.releases]
+//! feed = "https://releases.example.org/"
+//! token_env = "RELEASES_TOKEN"
+//! headers_env = { "X-Api-Key" = "RELEASES_API_KEY" }
+//! hosts = ["downloads.example.org"]
+//!
+//! [notifications]
+//! when = "failures"
+//!
+//! [[notifications.webhooks]]
+//! url_env = "SLACK_WEBHOOK_URL"
+//! format = "slack"
+//!
+//! [notifications.email]
+//! server = "smtp.example.org"
+//! port = 587
+//! from = "aer@example.org"
+//! to = ["maintainers@example.org"]
+//! username = "aer"
+//! password_env = "SMTP_PASSWORD"
+//! tls = "starttls"
+//!
+//! [virustotal]
+//! api_key_env = "VIRUSTOTAL_API_KEY"
+//! threshold = 2
+//! ```
+//!
+//! With the credentials file holding the secrets of the profiles.
+//!
+//! ```toml
+//! [internal]
+//! api_key = "..."
+//! password = "..."
+//!
+//! [releases]
+//! token = "..."
+//! headers = { "X-Api-Key" = "..." }
+//! ```
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::ffi::OsString;
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use aer_upd::data::Url;
+use aer_upd::web::auth::{AuthProfile, AuthProfiles, Secrets};
+use aer_upd::web::proxy::ProxySettings;
+use serde::Deserialize;
+
+use crate::notify::{EmailSettings, EmailTls, Notifier, NotifyWhen, Webhook, WebhookFormat};
+use crate::reputation::{VirusTotalSettings, DEFAULT_API_KEY_ENV};
+use crate::validation::RuleSet;
+
+/// The environment variable holding the path to the configuration file.
+pub const CONFIG_ENV: &str = "AER_CONFIG";
+
+/// The log levels that can be specified in the configuration file.
+const LOG_LEVELS: &[&str] = &["trace", "debug", "info", "error"];
+
+/// The errors that can occur when loading the configuration file.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The configuration file could not be read.
+    Io(std::io::Error),
+    /// The content of the configuration file is not valid.
+    Parse(String),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => err.fmt(f),
+            ConfigError::Parse(message) => f.write_str(message),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(err: std::io::Error) -> Self {
+        ConfigError::Io(err)
+    }
+}
+
+impl ConfigError {
+    /// Returns the stable code identifying the kind of error (*ie:
+    /// `AER0008`*), which can be used when searching the documentation.
+    pub fn code(&self) -> &'static str {
+        "AER0008"
+    }
+}
+
+/// A single authentication profile in the configuration file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileConfig {
+    feed: Url,
+    #[serde(default)]
+    api_key_env: Option<String>,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    password_env: Option<String>,
+    #[serde(default)]
+    token_env: Option<String>,
+    #[serde(default)]
+    headers_env: BTreeMap<String, String>,
+    #[serde(default)]
+    hosts: Vec<String>,
+}
+
+/// The proxy in the configuration file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProxyConfig {
+    url: Url,
+    #[serde(default)]
+    no_proxy: Vec<String>,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    password_env: Option<String>,
+}
+
+/// A single webhook in the configuration file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WebhookConfig {
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    url_env: Option<String>,
+    #[serde(default)]
+    format: Option<String>,
+}
+
+/// The SMTP server in the configuration file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EmailConfig {
+    server: String,
+    #[serde(default = "default_smtp_port")]
+    port: u16,
+    from: String,
+    to: Vec<String>,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    password_env: Option<String>,
+    #[serde(default)]
+    tls: Option<String>,
+}
+
+fn default_smtp_port() -> u16 {
+    25
+}
+
+/// The notifications in the configuration file.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NotificationsConfig {
+    #[serde(default)]
+    when: Option<String>,
+    #[serde(default)]
+    webhooks: Vec<WebhookConfig>,
+    #[serde(default)]
+    email: Option<EmailConfig>,
+}
+
+/// The VirusTotal lookups in the configuration file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VirusTotalConfig {
+    #[serde(default)]
+    api_key_env: Option<String>,
+    #[serde(default)]
+    threshold: u32,
+}
+
+/// The global configuration of the application.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    work_dir: Option<PathBuf>,
+    #[serde(default)]
+    maintainers: Vec<String>,
+    #[serde(default)]
+    rules: Option<String>,
+    #[serde(default)]
+    jobs: Option<usize>,
+    #[serde(default)]
+    log_path: Option<PathBuf>,
+    #[serde(default)]
+    log_level: Option<String>,
+    #[serde(default)]
+    github_token_env: Option<String>,
+    #[serde(default)]
+    gitlab_token_env: Option<String>,
+    #[serde(default)]
+    profiles: BTreeMap<String, ProfileConfig>,
+    #[serde(default)]
+    proxy: Option<ProxyConfig>,
+    #[serde(default)]
+    ca_certificates: Vec<PathBuf>,
+    #[serde(default)]
+    rate_limits: BTreeMap<String, f64>,
+    #[serde(default)]
+    credentials_file: Option<PathBuf>,
+    #[serde(default)]
+    notifications: NotificationsConfig,
+    #[serde(default)]
+    virustotal: Option<VirusTotalConfig>,
+    #[serde(skip)]
+    secrets: BTreeMap<String, Secrets>,
+}
+
+impl Config {
+    /// Loads the configuration file at the specified path.
+    pub fn load(path: &Path) -> Result<Config, ConfigError> {
+        let text = std::fs::read_to_string(path)?;
+
+        let mut config = Config::parse(&text).map_err(|err| match err {
+            ConfigError::Parse(message) => {
+                ConfigError::Parse(format!("{} ('{}')", message, path.display()))
+            }
+            err => err,
+        })?;
+        if let Some(dir) = path.parent() {
+            for path in config.work_dir.iter_mut().chain(config.log_path.iter_mut()) {
+                *path = dir.join(&path);
+            }
+            for certificate in config.ca_certificates.iter_mut() {
+                *certificate = dir.join(&certificate);
+            }
+            if let Some(ref mut credentials_file) = config.credentials_file {
+                *credentials_file = dir.join(&credentials_file);
+            }
+        }
+        if let Some(ref credentials_file) = config.credentials_file {
+            config.secrets = read_credentials(credentials_file)?;
+        }
+
+        Ok(config)
+    }
+
+    /// Parses the content of a configuration file.
+    pub fn parse(text: &str) -> Result<Config, ConfigError> {
+        let config: Config = toml::from_str(text).map_err(|err| {
+            ConfigError::Parse(format!("The configuration file is not valid: {}", err))
+        })?;
+
+        for (name, profile) in &config.profiles {
+            if profile.username.is_some() != profile.password_env.is_some() {
+                return Err(ConfigError::Parse(format!(
+                    "The profile '{}' must specify both 'username' and 'password_env'",
+                    name
+                )));
+            }
+            if profile.username.is_some() && profile.token_env.is_some() {
+                return Err(ConfigError::Parse(format!(
+                    "The profile '{}' can not specify both 'username' and 'token_env'",
+                    name
+                )));
+            }
+        }
+        if let Some(ref proxy) = config.proxy {
+            if proxy.username.is_some() != proxy.password_env.is_some() {
+                return Err(ConfigError::Parse(
+                    "The proxy must specify both 'username' and 'password_env'".into(),
+                ));
+            }
+        }
+        if let Some(ref rules) = config.rules {
+            RuleSet::from_str(rules).map_err(|err| {
+                ConfigError::Parse(format!("The rule set '{}' is not valid: {}", rules, err))
+            })?;
+        }
+        if let Some(ref log_level) = config.log_level {
+            if !LOG_LEVELS.contains(&log_level.as_str()) {
+                return Err(ConfigError::Parse(format!(
+                    "The log level '{}' must be one of {}",
+                    log_level,
+                    LOG_LEVELS.join(", ")
+                )));
+            }
+        }
+        if let Some(ref when) = config.notifications.when {
+            NotifyWhen::from_str(when).map_err(|err| {
+                ConfigError::Parse(format!("The notification setting '{}': {}", when, err))
+            })?;
+        }
+        for webhook in &config.notifications.webhooks {
+            if webhook.url.is_some() == webhook.url_env.is_some() {
+                return Err(ConfigError::Parse(
+                    "A webhook must specify either 'url' or 'url_env'".into(),
+                ));
+            }
+            if let Some(ref format) = webhook.format {
+                WebhookFormat::from_str(format).map_err(|err| {
+                    ConfigError::Parse(format!("The webhook format '{}': {}", format, err))
+                })?;
+            }
+        }
+        if let Some(ref email) = config.notifications.email {
+            if email.to.is_empty() {
+                return Err(ConfigError::Parse(
+                    "The email notification must specify at least one recipient in 'to'".into(),
+                ));
+            }
+            if email.username.is_some() != email.password_env.is_some() {
+                return Err(ConfigError::Parse(
+                    "The email notification must specify both 'username' and 'password_env'".into(),
+                ));
+            }
+            if let Some(ref tls) = email.tls {
+                let tls = EmailTls::from_str(tls).map_err(|err| {
+                    ConfigError::Parse(format!("The email tls setting '{}': {}", tls, err))
+                })?;
+                if tls == EmailTls::None && email.username.is_some() {
+                    return Err(ConfigError::Parse(
+                        "The email notification must use TLS when 'username' is specified".into(),
+                    ));
+                }
+            }
+        }
+        for (host, rate) in &config.rate_limits {
+            if !rate.is_finite() || *rate < 0.0 {
+                return Err(ConfigError::Parse(format!(
+                    "The rate limit of '{}' must be a positive number of requests per second",
+                    host
+                )));
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Returns the authentication profiles of private feeds.
+    pub fn auth_profiles(&self) -> AuthProfiles {
+        let mut profiles = AuthProfiles::new();
+
+        for (name, config) in &self.profiles {
+            let mut profile = AuthProfile::new(name, config.feed.clone());
+            profile.api_key_env = config.api_key_env.clone();
+            profile.username = config.username.clone();
+            profile.password_env = config.password_env.clone();
+            profile.token_env = config.token_env.clone();
+            profile.headers_env = config.headers_env.clone();
+            profile.hosts = config.hosts.clone();
+            if let Some(secrets) = self.secrets.get(name) {
+                profile = profile.with_secrets(secrets.clone());
+            }
+            profiles.add(profile);
+        }
+
+        profiles
+    }
+
+    /// Returns the proxy that requests are sent through, nothing is returned
+    /// when the proxy should be read from the environment variables.
+    pub fn proxy(&self) -> Option<ProxySettings> {
+        self.proxy.as_ref().map(|config| {
+            let proxy =
+                ProxySettings::new(config.url.clone()).with_no_proxy(config.no_proxy.clone());
+
+            match (config.username.as_ref(), config.password_env.as_ref()) {
+                (Some(username), Some(password_env)) => {
+                    proxy.with_credentials(username, password_env)
+                }
+                _ => proxy,
+            }
+        })
+    }
+
+    /// Returns the paths to the additional root certificates that should be
+    /// trusted.
+    pub fn ca_certificates(&self) -> &[PathBuf] {
+        &self.ca_certificates
+    }
+
+    /// Returns the number of requests per second allowed to specific hosts.
+    pub fn rate_limits(&self) -> &BTreeMap<String, f64> {
+        &self.rate_limits
+    }
+
+    /// Returns the notifications that are sent at the end of an update run.
+    pub fn notifier(&self) -> Notifier {
+        let config = &self.notifications;
+
+        Notifier {
+            when: config
+                .when
+                .as_deref()
+                .and_then(|when| when.parse().ok())
+                .unwrap_or_default(),
+            webhooks: config
+                .webhooks
+                .iter()
+                .map(|webhook| Webhook {
+                    url: webhook.url.clone(),
+                    url_env: webhook.url_env.clone(),
+                    format: webhook
+                        .format
+                        .as_deref()
+                        .and_then(|format| format.parse().ok())
+                        .unwrap_or_default(),
+                })
+                .collect(),
+            email: config.email.as_ref().map(|email| EmailSettings {
+                server: email.server.clone(),
+                port: email.port,
+                from: email.from.clone(),
+                to: email.to.clone(),
+                username: email.username.clone(),
+                password_env: email.password_env.clone(),
+                tls: email
+                    .tls
+                    .as_deref()
+                    .and_then(|tls| tls.parse().ok())
+                    .unwrap_or_default(),
+            }),
+        }
+    }
+
+    /// Returns the settings used when looking up the downloaded files on
+    /// VirusTotal, nothing is returned when VirusTotal have not been
+    /// configured.
+    pub fn virustotal(&self) -> Option<VirusTotalSettings> {
+        self.virustotal.as_ref().map(|config| VirusTotalSettings {
+            api_key_env: config
+                .api_key_env
+                .clone()
+                .unwrap_or_else(|| DEFAULT_API_KEY_ENV.into()),
+            threshold: config.threshold,
+        })
+    }
+
+    /// Returns the defaults of the options in the configuration file, as the
+    /// environment variables of the options together with their values. The
+    /// tokens are only returned when the variables named in the file have
+    /// been set.
+    pub fn defaults(&self) -> Vec<(&'static str, String)> {
+        let mut defaults = vec![];
+
+        if let Some(ref work_dir) = self.work_dir {
+            defaults.push(("AER_WORK_DIR", work_dir.display().to_string()));
+        }
+        if !self.maintainers.is_empty() {
+            defaults.push(("AER_MAINTAINER", self.maintainers.join(",")));
+        }
+        if let Some(ref rules) = self.rules {
+            defaults.push(("AER_RULES", rules.clone()));
+        }
+        if let Some(jobs) = self.jobs {
+            defaults.push(("AER_JOBS", jobs.to_string()));
+        }
+        if let Some(ref log_path) = self.log_path {
+            defaults.push(("AER_LOG_PATH", log_path.display().to_string()));
+        }
+        if let Some(ref log_level) = self.log_level {
+            defaults.push(("AER_LOG_LEVEL", log_level.clone()));
+        }
+        for (name, token_env) in &[
+            ("AER_GITHUB_TOKEN", &self.github_token_env),
+            ("AER_GITLAB_TOKEN", &self.gitlab_token_env),
+        ] {
+            if let Some(token) = token_env.as_ref().and_then(|env| std::env::var(env).ok()) {
+                defaults.push((name, token));
+            }
+        }
+
+        defaults
+    }
+}
+
+/// Returns the location of the configuration file of the current user, this
+/// is `aer/config.toml` in the configuration directory of the user (*ie:
+/// `~/.config` or `$XDG_CONFIG_HOME`, and `%APPDATA%` on Windows*).
+pub fn user_config_path() -> Option<PathBuf> {
+    let dir = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+
+    dir.map(|dir| dir.join("aer").join("config.toml"))
+}
+
+/// Returns the configuration file that should be used, this is the file
+/// specified with the `--config` argument or the `AER_CONFIG` environment
+/// variable, otherwise the configuration file of the user when it exists.
+pub fn locate<I>(args: I) -> Option<PathBuf>
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.to_str().and_then(|arg| arg.strip_prefix("--config=")) {
+            return Some(PathBuf::from(path));
+        }
+    }
+
+    std::env::var_os(CONFIG_ENV)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| user_config_path().filter(|path| path.is_file()))
+}
+
+/// Applies the defaults in the configuration file as environment variables,
+/// variables that have already been set are not changed. As such the
+/// defaults are overridden by both the environment variables and the command
+/// line arguments. The path to the file is also set as the `AER_CONFIG`
+/// environment variable, when this have not been set.
+pub fn apply_defaults(path: &Path) -> Result<(), ConfigError> {
+    let config = Config::load(path)?;
+
+    if std::env::var_os(CONFIG_ENV).is_none() {
+        std::env::set_var(CONFIG_ENV, path);
+    }
+    for (name, value) in config.defaults() {
+        if std::env::var_os(name).is_none() {
+            std::env::set_var(name, value);
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the secrets of the profiles from the credentials file, keyed by the
+/// name of the profile.
+fn read_credentials(path: &Path) -> Result<BTreeMap<String, Secrets>, ConfigError> {
+    let text = std::fs::read_to_string(path)?;
+
+    toml::from_str(&text).map_err(|err| {
+        ConfigError::Parse(format!(
+            "The credentials file is not valid: {} ('{}')",
+            err,
+            path.display()
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[test]
+    fn parse_should_read_auth_profiles() {
+        let config = Config::parse(
+            r#"
+[profiles.internal]
+feed = "https://nexus.example.org/repository/choco/"
+api_key_env = "NEXUS_API_KEY"
+username = "ci-user"
+password_env = "NEXUS_PASSWORD"
+
+[profiles.community]
+feed = "https://push.chocolatey.org/"
+api_key_env = "CHOCOLATEY_API_KEY"
+hosts = ["packages.chocolatey.org"]
+"#,
+        )
+        .unwrap();
+
+        let actual = config.auth_profiles();
+
+        assert_eq!(
+            actual.get("internal"),
+            Some(
+                &AuthProfile::new(
+                    "internal",
+                    Url::parse("https://nexus.example.org/repository/choco/").unwrap()
+                )
+                .with_api_key_env("NEXUS_API_KEY")
+                .with_credentials("ci-user", "NEXUS_PASSWORD")
+            )
+        );
+        assert_eq!(
+            actual.get("community").unwrap().api_key_env.as_deref(),
+            Some("CHOCOLATEY_API_KEY")
+        );
+        assert_eq!(
+            actual.get("community").unwrap().hosts,
+            ["packages.chocolatey.org"]
+        );
+    }
+
+    #[test]
+    fn load_should_read_secrets_from_credentials_file() {
+        let dir = std::env::temp_dir().join("aer-config-credentials-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("aer.toml");
+        std::fs::write(
+            &path,
+            r#"
+credentials_file = "credentials.toml"
+
+[profiles.releases]
+feed = "https://releases.example.org/"
+token_env = "AER_TEST_MISSING_RELEASES_TOKEN"
+headers_env = { "X-Api-Key" = "AER_TEST_MISSING_RELEASES_KEY" }
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("credentials.toml"),
+            "[releases]\ntoken = \"secret-token\"\nheaders = { \"X-Api-Key\" = \"secret-key\" }\n",
+        )
+        .unwrap();
+
+        let actual = Config::load(&path).unwrap().auth_profiles();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let profile = actual.get("releases").unwrap();
+        assert_eq!(profile.token().as_deref(), Some("secret-token"));
+        assert_eq!(
+            profile.headers().get("X-Api-Key").map(String::as_str),
+            Some("secret-key")
+        );
+    }
+
+    #[test]
+    fn parse_should_read_proxy() {
+        let config = Config::parse(
+            r#"
+[proxy]
+url = "http://proxy.example.org:8080"
+no_proxy = ["localhost"]
+username = "ci-user"
+password_env = "PROXY_PASSWORD"
+"#,
+        )
+        .unwrap();
+
+        let actual = config.proxy();
+
+        assert_eq!(
+            actual,
+            Some(
+                ProxySettings::new(Url::parse("http://proxy.example.org:8080").unwrap())
+                    .with_no_proxy(vec!["localhost"])
+                    .with_credentials("ci-user", "PROXY_PASSWORD")
+            )
+        );
+    }
+
+    #[test]
+    fn load_should_resolve_ca_certificates_against_config_dir() {
+        let dir = std::env::temp_dir().join("aer-config-ca-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("aer.toml");
+        std::fs::write(&path, "ca_certificates = [\"corporate-root.pem\"]\n").unwrap();
+
+        let actual = Config::load(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(actual.ca_certificates(), [dir.join("corporate-root.pem")]);
+    }
+
+    #[test]
+    fn defaults_should_return_environment_variables_of_options() {
+        std::env::set_var("AER_CONFIG_TEST_GITHUB_TOKEN", "secret-token");
+        let config = Config::parse(
+            r#"
+work_dir = "/var/lib/aer"
+maintainers = ["AdmiringWorm", "chocolatey-community"]
+rules = "community"
+jobs = 4
+log_level = "debug"
+github_token_env = "AER_CONFIG_TEST_GITHUB_TOKEN"
+gitlab_token_env = "AER_CONFIG_TEST_MISSING_TOKEN"
+"#,
+        )
+        .unwrap();
+
+        let actual = config.defaults();
+
+        assert_eq!(
+            actual,
+            [
+                ("AER_WORK_DIR", "/var/lib/aer".to_string()),
+                (
+                    "AER_MAINTAINER",
+                    "AdmiringWorm,chocolatey-community".to_string()
+                ),
+                ("AER_RULES", "community".to_string()),
+                ("AER_JOBS", "4".to_string()),
+                ("AER_LOG_LEVEL", "debug".to_string()),
+                ("AER_GITHUB_TOKEN", "secret-token".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn load_should_resolve_work_dir_and_log_path_against_config_dir() {
+        let dir = std::env::temp_dir().join("aer-config-paths-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        std::fs::write(&path, "work_dir = \"work\"\nlog_path = \"logs/aer.log\"\n").unwrap();
+
+        let actual = Config::load(&path).unwrap().defaults();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            actual,
+            [
+                ("AER_WORK_DIR", dir.join("work").display().to_string()),
+                (
+                    "AER_LOG_PATH",
+                    dir.join("logs/aer.log").display().to_string()
+                )
+            ]
+        );
+    }
+
+    #[rstest(
+        args,
+        expected,
+        case(&["update", "--config", "aer.toml"], "aer.toml"),
+        case(&["--config=custom.toml", "update"], "custom.toml")
+    )]
+    fn locate_should_return_config_argument(args: &[&str], expected: &str) {
+        let actual = locate(args.iter().map(OsString::from));
+
+        assert_eq!(actual, Some(PathBuf::from(expected)));
+    }
+
+    #[test]
+    fn parse_should_read_rate_limits() {
+        let config =
+            Config::parse("[rate_limits]\n\"github.com\" = 1\n\"sourceforge.net\" = 0.5\n")
+                .unwrap();
+
+        let actual: Vec<_> = config.rate_limits().iter().collect();
+
+        assert_eq!(
+            actual,
+            [
+                (&"github.com".to_string(), &1.0),
+                (&"sourceforge.net".to_string(), &0.5)
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_should_accept_empty_configuration() {
+        let actual = Config::parse("").unwrap();
+
+        assert!(actual.auth_profiles().is_empty());
+    }
+
+    #[rstest(
+        text,
+        case("[profiles.internal]\napi_key_env = \"KEY\""),
+        case("[profiles.internal]\nfeed = \"not a url\""),
+        case("[profiles.internal]\nfeed = \"https://example.org/\"\nusername = \"ci-user\""),
+        case("[profiles.internal]\nfeed = \"https://example.org/\"\npassword = \"secret\""),
+        case(
+            "[profiles.internal]\nfeed = \"https://example.org/\"\nusername = \
+             \"ci-user\"\npassword_env = \"PASSWORD\"\ntoken_env = \"TOKEN\""
+        )
+    )]
+    fn parse_should_return_error_on_invalid_profiles(text: &str) {
+        let actual = Config::parse(text).unwrap_err();
+
+        assert!(matches!(actual, ConfigError::Parse(_)), "{:?}", actual);
+    }
+
+    #[rstest(
+        text,
+        case("[proxy]\nurl = \"http://proxy.example.org/\"\nusername = \"ci-user\""),
+        case("[proxy]\nno_proxy = [\"localhost\"]"),
+        case("[proxy]\nurl = \"http://proxy.example.org/\"\npassword = \"secret\"")
+    )]
+    fn parse_should_return_error_on_invalid_proxy(text: &str) {
+        let actual = Config::parse(text).unwrap_err();
+
+        assert!(matches!(actual, ConfigError::Parse(_)), "{:?}", actual);
+    }
+
+    #[rstest(
+        text,
+        case("[rate_limits]\n\"github.com\" = -1.0"),
+        case("[rate_limits]\n\"github.com\" = \"fast\""),
+        case("[rate_limits]\n\"github.com\" = nan")
+    )]
+    fn parse_should_return_error_on_invalid_rate_limits(text: &str) {
+        let actual = Config::parse(text).unwrap_err();
+
+        assert!(matches!(actual, ConfigError::Parse(_)), "{:?}", actual);
+    }
+
+    #[rstest(
+        text,
+        case("rules = \"unknown\""),
+        case("log_level = \"verbose\""),
+        case("jobs = -1"),
+        case("maintainers = \"AdmiringWorm\""),
+        case("unknown = true")
+    )]
+    fn parse_should_return_error_on_invalid_defaults(text: &str) {
+        let actual = Config::parse(text).unwrap_err();
+
+        assert!(matches!(actual, ConfigError::Parse(_)), "{:?}", actual);
+    }
+
+    #[test]
+    fn parse_should_read_notifications() {
+        let config = Config::parse(
+            "[notifications]\nwhen = \"always\"\n\n[[notifications.webhooks]]\nurl_env = \
+             \"WEBHOOK_URL\"\nformat = \"discord\"\n\n[notifications.email]\nserver = \
+             \"smtp.example.org\"\nfrom = \"aer@example.org\"\nto = \
+             [\"maintainers@example.org\"]\n",
+        )
+        .unwrap();
+
+        let actual = config.notifier();
+
+        assert_eq!(actual.when, NotifyWhen::Always);
+        assert_eq!(
+            actual.webhooks,
+            [Webhook {
+                url: None,
+                url_env: Some("WEBHOOK_URL".into()),
+                format: WebhookFormat::Discord,
+            }]
+        );
+        let email = actual.email.unwrap();
+        assert_eq!(email.server, "smtp.example.org");
+        assert_eq!(email.port, 25);
+        assert_eq!(email.to, ["maintainers@example.org"]);
+        assert_eq!(email.tls, EmailTls::Opportunistic);
+    }
+
+    #[test]
+    fn parse_should_read_virustotal() {
+        let config = Config::parse("[virustotal]\nthreshold = 2").unwrap();
+
+        let actual = config.virustotal();
+
+        assert_eq!(
+            actual,
+            Some(VirusTotalSettings {
+                api_key_env: DEFAULT_API_KEY_ENV.into(),
+                threshold: 2,
+            })
+        );
+    }
+
+    #[rstest(
+        text,
+        case("[notifications]\nwhen = \"sometimes\""),
+        case("[[notifications.webhooks]]\nformat = \"slack\""),
+        case("[[notifications.webhooks]]\nurl = \"https://example.org\"\nurl_env = \"URL\""),
+        case("[[notifications.webhooks]]\nurl = \"https://example.org\"\nformat = \"irc\""),
+        case("[notifications.email]\nserver = \"localhost\"\nfrom = \"aer@example.org\"\nto = []"),
+        case(
+            "[notifications.email]\nserver = \"localhost\"\nfrom = \"aer@example.org\"\nto = \
+             [\"a@example.org\"]\nusername = \"aer\""
+        ),
+        case(
+            "[notifications.email]\nserver = \"localhost\"\nfrom = \"aer@example.org\"\nto = \
+             [\"a@example.org\"]\ntls = \"ssl3\""
+        ),
+        case(
+            "[notifications.email]\nserver = \"localhost\"\nfrom = \"aer@example.org\"\nto = \
+             [\"a@example.org\"]\nusername = \"aer\"\npassword_env = \"PASSWORD\"\ntls = \"none\""
+        )
+    )]
+    fn parse_should_return_error_on_invalid_notifications(text: &str) {
+        let actual = Config::parse(text).unwrap_err();
+
+        assert!(matches!(actual, ConfigError::Parse(_)), "{:?}", actual);
+    }
+}
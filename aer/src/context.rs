@@ -0,0 +1,453 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the context that is shared between every command, holding the
+//! options that are available globally.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use aer_upd::context::UpdateContext;
+use aer_upd::web::auth::AuthProfiles;
+use aer_upd::web::budget::TransferBudget;
+use aer_upd::web::errors::WebError;
+use aer_upd::web::http_log::HttpLog;
+use aer_upd::web::proxy::ProxySettings;
+use aer_upd::web::rate_limit::RateLimiter;
+use aer_upd::web::WebRequest;
+use log::warn;
+use structopt::StructOpt;
+
+use crate::clock::Clock;
+use crate::config::{Config, ConfigError};
+use crate::notify::Notifier;
+use crate::paths::DataRoot;
+use crate::reputation::VirusTotalSettings;
+
+// Holds the global options that every command honors, allowing several
+// isolated instances of the application to be run side by side.
+//
+// The flattened structures only use regular comments, as structopt replaces
+// the about text of the application with the doc comment of any structure
+// flattened into the arguments.
+#[derive(Debug, Default, Clone, PartialEq, StructOpt)]
+pub struct Context {
+    /// The path to the configuration file that should be used instead of the
+    /// default location.
+    #[structopt(long, env = "AER_CONFIG", global = true, parse(from_os_str))]
+    config: Option<PathBuf>,
+
+    /// The directory to use when downloading files and creating packages.
+    /// [default: %TEMP% or the staging directory in the data root]
+    #[structopt(long, env = "AER_WORK_DIR", global = true, parse(from_os_str))]
+    work_dir: Option<PathBuf>,
+
+    /// Records every HTTP request (url, status, duration, size and cache
+    /// status) as JSON lines to the specified file.
+    #[structopt(long, env = "AER_HTTP_LOG", global = true, parse(from_os_str))]
+    http_log: Option<PathBuf>,
+
+    /// The languages to request from web servers, sent as the
+    /// `Accept-Language` header. [default: en-US, en;q=0.8, *;q=0.5]
+    #[structopt(long, env = "AER_ACCEPT_LANGUAGE", global = true)]
+    accept_language: Option<String>,
+
+    /// The personal access token used when reading releases from GitHub,
+    /// avoiding the lower rate limit of anonymous requests.
+    #[structopt(long, env = "AER_GITHUB_TOKEN", global = true, hide_env_values = true)]
+    github_token: Option<String>,
+
+    /// The personal access token used when reading releases from GitLab,
+    /// needed for private projects.
+    #[structopt(long, env = "AER_GITLAB_TOKEN", global = true, hide_env_values = true)]
+    gitlab_token: Option<String>,
+
+    /// The maximum number of downloads and page fetches that may run at the
+    /// same time across all packages, page fetches are prioritized over
+    /// downloads. [default: unlimited]
+    #[structopt(long, env = "AER_MAX_TRANSFERS", global = true)]
+    max_transfers: Option<usize>,
+
+    /// The maximum number of bytes that may be downloaded at the same time
+    /// across all packages, larger files are downloaded one at a time.
+    /// [default: unlimited]
+    #[structopt(long, env = "AER_MAX_IN_FLIGHT_BYTES", global = true)]
+    max_in_flight_bytes: Option<u64>,
+
+    /// The maximum number of requests per second sent to a single host,
+    /// avoiding being banned by the host during large batch runs. Hosts can
+    /// be limited individually in the configuration file. [default:
+    /// unlimited]
+    #[structopt(long, env = "AER_MAX_REQUESTS_PER_SECOND", global = true)]
+    max_requests_per_second: Option<f64>,
+
+    #[structopt(flatten)]
+    data_root: DataRoot,
+
+    #[structopt(flatten)]
+    clock: Clock,
+
+    #[structopt(skip)]
+    auth_profiles: AuthProfiles,
+
+    #[structopt(skip)]
+    proxy: Option<ProxySettings>,
+
+    #[structopt(skip)]
+    ca_certificates: Vec<PathBuf>,
+
+    #[structopt(skip)]
+    rate_limits: BTreeMap<String, f64>,
+
+    #[structopt(skip)]
+    notifier: Notifier,
+
+    #[structopt(skip)]
+    virustotal: Option<VirusTotalSettings>,
+}
+
+impl Context {
+    /// Creates a new context using the specified data root and clock.
+    pub fn new(data_root: DataRoot, clock: Clock) -> Context {
+        Context {
+            config: None,
+            work_dir: None,
+            http_log: None,
+            accept_language: None,
+            github_token: None,
+            gitlab_token: None,
+            max_transfers: None,
+            max_in_flight_bytes: None,
+            max_requests_per_second: None,
+            data_root,
+            clock,
+            auth_profiles: AuthProfiles::default(),
+            proxy: None,
+            ca_certificates: vec![],
+            rate_limits: BTreeMap::new(),
+            notifier: Notifier::default(),
+            virustotal: None,
+        }
+    }
+
+    /// Sets the path to the configuration file.
+    pub fn with_config<P: Into<PathBuf>>(mut self, config: P) -> Context {
+        self.config = Some(config.into());
+        self
+    }
+
+    /// Sets the directory to use when downloading files and creating packages.
+    pub fn with_work_dir<P: Into<PathBuf>>(mut self, work_dir: P) -> Context {
+        self.work_dir = Some(work_dir.into());
+        self
+    }
+
+    /// Sets the file that every HTTP request will be recorded to.
+    pub fn with_http_log<P: Into<PathBuf>>(mut self, http_log: P) -> Context {
+        self.http_log = Some(http_log.into());
+        self
+    }
+
+    /// Sets the languages to request from web servers.
+    pub fn with_accept_language<L: Into<String>>(mut self, accept_language: L) -> Context {
+        self.accept_language = Some(accept_language.into());
+        self
+    }
+
+    /// Sets the personal access token used when reading releases from
+    /// GitHub.
+    pub fn with_github_token<T: Into<String>>(mut self, github_token: T) -> Context {
+        self.github_token = Some(github_token.into());
+        self
+    }
+
+    /// Sets the personal access token used when reading releases from
+    /// GitLab.
+    pub fn with_gitlab_token<T: Into<String>>(mut self, gitlab_token: T) -> Context {
+        self.gitlab_token = Some(gitlab_token.into());
+        self
+    }
+
+    /// Sets the budget limiting the number of concurrent transfers and the
+    /// number of bytes downloaded at the same time, `0` disables a limit.
+    pub fn with_budget(mut self, max_transfers: usize, max_in_flight_bytes: u64) -> Context {
+        self.max_transfers = Some(max_transfers);
+        self.max_in_flight_bytes = Some(max_in_flight_bytes);
+        self
+    }
+
+    /// Sets the authentication profiles used when communicating with private
+    /// feeds, these are normally loaded from the configuration file.
+    pub fn with_auth_profiles(mut self, auth_profiles: AuthProfiles) -> Context {
+        self.auth_profiles = auth_profiles;
+        self
+    }
+
+    /// Sets the maximum number of requests per second sent to a single host,
+    /// `0` disables the limit.
+    pub fn with_max_requests_per_second(mut self, max_requests_per_second: f64) -> Context {
+        self.max_requests_per_second = Some(max_requests_per_second);
+        self
+    }
+
+    /// Sets the proxy that requests are sent through, instead of the proxy
+    /// read from the environment variables.
+    pub fn with_proxy(mut self, proxy: ProxySettings) -> Context {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Adds a file holding additional root certificates that should be
+    /// trusted when sending requests.
+    pub fn with_ca_certificates<P: Into<PathBuf>>(mut self, path: P) -> Context {
+        self.ca_certificates.push(path.into());
+        self
+    }
+
+    /// Returns the path to the configuration file, if one have been specified.
+    pub fn config(&self) -> Option<&Path> {
+        self.config.as_deref()
+    }
+
+    /// Returns the directory to use when downloading files and creating
+    /// packages. This is the staging directory of the data root unless a work
+    /// directory have been specified.
+    pub fn work_dir(&self) -> PathBuf {
+        self.work_dir
+            .clone()
+            .unwrap_or_else(|| self.data_root.staging_dir())
+    }
+
+    /// Returns the data root that all locations written to is resolved
+    /// against.
+    pub fn data_root(&self) -> &DataRoot {
+        &self.data_root
+    }
+
+    /// Returns the notifications that are sent at the end of an update run,
+    /// these are loaded from the configuration file.
+    pub fn notifier(&self) -> &Notifier {
+        &self.notifier
+    }
+
+    /// Returns the settings used when looking up the downloaded files on
+    /// VirusTotal, these are loaded from the configuration file. The default
+    /// settings are used when VirusTotal have not been configured, but the
+    /// API key have been set in the default environment variable.
+    pub fn virustotal(&self) -> Option<VirusTotalSettings> {
+        self.virustotal.clone().or_else(|| {
+            let settings = VirusTotalSettings::default();
+            settings.api_key().map(|_| settings)
+        })
+    }
+
+    /// Returns the clock to use when getting the current time.
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// Creates a new web request using the configured languages, that records
+    /// every request to the http log when one have been specified. Every
+    /// clone of the returned request shares the same transfer budget.
+    pub fn request(&self) -> WebRequest {
+        let request = match self.build_request() {
+            Ok(request) => request,
+            Err(err) => {
+                warn!("Unable to configure the web client: {}", err);
+                WebRequest::create()
+            }
+        };
+        let request = match self.accept_language {
+            Some(ref accept_language) => request.with_accept_language(accept_language),
+            None => request,
+        };
+        let request = match self.github_token {
+            Some(ref github_token) => request.with_github_token(github_token),
+            None => request,
+        };
+        let request = match self.gitlab_token {
+            Some(ref gitlab_token) => request.with_gitlab_token(gitlab_token),
+            None => request,
+        };
+        let request = if self.auth_profiles.is_empty() {
+            request
+        } else {
+            request.with_auth_profiles(self.auth_profiles.clone())
+        };
+        let request = match (self.max_transfers, self.max_in_flight_bytes) {
+            (None, None) => request,
+            (max_transfers, max_bytes) => request.with_budget(TransferBudget::new(
+                max_transfers.unwrap_or_default(),
+                max_bytes.unwrap_or_default(),
+            )),
+        };
+        let request = if self.max_requests_per_second.is_none() && self.rate_limits.is_empty() {
+            request
+        } else {
+            request.with_rate_limiter(self.rate_limits.iter().fold(
+                RateLimiter::new(self.max_requests_per_second.unwrap_or_default()),
+                |limiter, (host, rate)| limiter.with_host_rate(host, *rate),
+            ))
+        };
+
+        match self.http_log {
+            Some(ref path) => match HttpLog::create(path) {
+                Ok(http_log) => request.with_http_log(http_log),
+                Err(err) => {
+                    warn!("Unable to open the http log '{}': {}", path.display(), err);
+                    request
+                }
+            },
+            None => request,
+        }
+    }
+
+    /// Creates the web request using the configured proxy and root
+    /// certificates.
+    fn build_request(&self) -> Result<WebRequest, WebError> {
+        let builder = match self.proxy {
+            Some(ref proxy) => WebRequest::builder().with_proxy(proxy.clone()),
+            None => WebRequest::builder(),
+        };
+
+        self.ca_certificates
+            .iter()
+            .fold(builder, |builder, path| {
+                builder.with_root_certificates(path)
+            })
+            .build()
+    }
+
+    /// Creates the context that is passed through the update process, using
+    /// the paths of this context.
+    pub fn update_context(&self) -> UpdateContext {
+        let context = UpdateContext::new()
+            .with_request(self.request())
+            .with_work_dir(self.work_dir())
+            .with_cache_dir(self.data_root.cache_dir());
+
+        match self.config {
+            Some(ref config) => context.with_config(config),
+            None => context,
+        }
+    }
+
+    /// Validates the paths in the context, loads the authentication profiles
+    /// and notifications from the configuration file and creates the
+    /// directories the application is allowed to write to.
+    pub fn prepare(&mut self) -> Result<(), ConfigError> {
+        if let Some(ref config) = self.config {
+            if !config.is_file() {
+                return Err(ConfigError::Io(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!(
+                        "The configuration file '{}' does not exist!",
+                        config.display()
+                    ),
+                )));
+            }
+            let config = Config::load(config)?;
+            self.auth_profiles = config.auth_profiles();
+            if let Some(proxy) = config.proxy() {
+                self.proxy = Some(proxy);
+            }
+            self.ca_certificates
+                .extend(config.ca_certificates().iter().cloned());
+            self.rate_limits = config.rate_limits().clone();
+            self.notifier = config.notifier();
+            self.virustotal = config.virustotal();
+            if let Err(err) = self.build_request() {
+                return Err(ConfigError::Parse(err.to_string()));
+            }
+        }
+
+        self.data_root.create_dirs()?;
+        if let Some(ref work_dir) = self.work_dir {
+            std::fs::create_dir_all(work_dir)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn work_dir_should_default_to_staging_dir() {
+        let context = Context::new(DataRoot::new("/data"), Clock::default());
+
+        assert_eq!(context.work_dir(), PathBuf::from("/data").join("staging"));
+    }
+
+    #[test]
+    fn work_dir_should_use_specified_directory() {
+        let context = Context::default().with_work_dir("/work");
+
+        assert_eq!(context.work_dir(), PathBuf::from("/work"));
+    }
+
+    #[test]
+    fn update_context_should_use_paths_of_context() {
+        let context =
+            Context::new(DataRoot::new("/data"), Clock::default()).with_config("aer.toml");
+
+        let actual = context.update_context();
+
+        assert_eq!(actual.work_dir(), Path::new("/data/staging"));
+        assert_eq!(actual.cache_dir(), Path::new("/data/cache"));
+        assert_eq!(actual.config(), Some(Path::new("aer.toml")));
+    }
+
+    #[test]
+    fn prepare_should_return_error_on_missing_config() {
+        let mut context = Context::default().with_config("non-existing.toml");
+
+        let actual = context.prepare().unwrap_err();
+
+        assert!(
+            matches!(actual, ConfigError::Io(ref err) if err.kind() == std::io::ErrorKind::NotFound)
+        );
+    }
+
+    #[test]
+    fn prepare_should_load_auth_profiles_from_config() {
+        let path = std::env::temp_dir().join("aer-context-config-test.toml");
+        std::fs::write(
+            &path,
+            "[profiles.internal]\nfeed = \"https://nexus.example.org/repository/choco/\"\n",
+        )
+        .unwrap();
+        let mut context = Context::default().with_config(&path);
+
+        context.prepare().unwrap();
+
+        assert!(context.request().auth_profiles().get("internal").is_some());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn prepare_should_return_error_on_missing_ca_certificates() {
+        let path = std::env::temp_dir().join("aer-context-ca-test.toml");
+        std::fs::write(&path, "ca_certificates = [\"non-existing.pem\"]\n").unwrap();
+        let mut context = Context::default().with_config(&path);
+
+        let actual = context.prepare().unwrap_err();
+        let _ = std::fs::remove_file(&path);
+
+        assert!(matches!(actual, ConfigError::Parse(_)), "{:?}", actual);
+    }
+
+    #[test]
+    fn prepare_should_create_work_dir() {
+        let path = std::env::temp_dir().join("aer-context-work-dir-test");
+        let _ = std::fs::remove_dir_all(&path);
+        let mut context = Context::default().with_work_dir(&path);
+
+        context.prepare().unwrap();
+
+        assert!(path.is_dir());
+        let _ = std::fs::remove_dir_all(&path);
+    }
+}
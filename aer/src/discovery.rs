@@ -0,0 +1,171 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the functionality for discovering all package files located
+//! below a root directory, used when updating every package of a repository
+//! instead of passing each package file explicitly.
+//!
+//! The discovered package files can be narrowed down using glob filters,
+//! which are matched against the path of the package file relative to the
+//! root directory, and against the name of the package file without the
+//! package file suffix (*ie: `7zip` for `automatic/7zip/7zip.aer.toml`*).
+//!
+//! | Pattern | Matches                                      |
+//! | ------- | -------------------------------------------- |
+//! | `*`     | Any number of characters, except `/`         |
+//! | `**`    | Any number of characters, including `/`      |
+//! | `?`     | A single character, except `/`               |
+
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use regex::Regex;
+
+use crate::check;
+
+/// A glob filter used to select the discovered package files.
+#[derive(Debug, Clone)]
+pub struct PackageFilter {
+    pattern: String,
+    regex: Regex,
+}
+
+impl PackageFilter {
+    /// Returns wether the filter matches the package file, the path is
+    /// expected to be relative to the root directory.
+    pub fn is_match(&self, path: &Path) -> bool {
+        let path = path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        self.regex.is_match(&path) || self.regex.is_match(package_name(&path))
+    }
+}
+
+impl FromStr for PackageFilter {
+    type Err = String;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        if pattern.trim().is_empty() {
+            return Err("The package filter can not be empty!".into());
+        }
+
+        let mut text = String::from("^");
+        let mut chars = pattern.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    text.push_str(".*");
+                }
+                '*' => text.push_str("[^/]*"),
+                '?' => text.push_str("[^/]"),
+                ch => text.push_str(&regex::escape(&ch.to_string())),
+            }
+        }
+        text.push('$');
+
+        let regex = Regex::new(&text)
+            .map_err(|err| format!("'{}' is not a valid package filter: {}", pattern, err))?;
+
+        Ok(PackageFilter {
+            pattern: pattern.to_owned(),
+            regex,
+        })
+    }
+}
+
+impl Display for PackageFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.pattern)
+    }
+}
+
+/// Finds all package files located below the root directory, and returns the
+/// files matching at least one of the filters. All package files are returned
+/// when no filters have been specified.
+pub fn discover(root: &Path, filters: &[PackageFilter]) -> Vec<PathBuf> {
+    check::find_package_files(&[root.to_path_buf()])
+        .into_iter()
+        .filter(|file| {
+            let relative = file.strip_prefix(root).unwrap_or(file);
+            filters.is_empty() || filters.iter().any(|filter| filter.is_match(relative))
+        })
+        .collect()
+}
+
+/// Returns the name of the package file without the directories and the
+/// package file suffix.
+fn package_name(path: &str) -> &str {
+    let name = path.rsplit('/').next().unwrap_or(path);
+
+    match name.find(".aer.") {
+        Some(index) => &name[..index],
+        None => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("7zip", "automatic/7zip/7zip.aer.toml", true)]
+    #[case("7*", "automatic/7zip/7zip.aer.toml", true)]
+    #[case("automatic/*/*.aer.toml", "automatic/7zip/7zip.aer.toml", true)]
+    #[case("**/*.aer.yml", "automatic/tools/7zip/7zip.aer.yml", true)]
+    #[case("automatic/*", "automatic/tools/7zip/7zip.aer.toml", false)]
+    #[case("7zip?", "automatic/7zip/7zip.aer.toml", false)]
+    #[case("codecov", "automatic/7zip/7zip.aer.toml", false)]
+    #[case("7zip.install", "automatic/7zip.install/7zip.install.aer.toml", true)]
+    #[case("7zip.install", "automatic/7zipxinstall/7zipxinstall.aer.toml", false)]
+    fn is_match_should_match_glob_against_path_and_name(
+        #[case] pattern: &str,
+        #[case] path: &str,
+        #[case] expected: bool,
+    ) {
+        let filter: PackageFilter = pattern.parse().unwrap();
+
+        let actual = filter.is_match(Path::new(path));
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn from_str_should_return_error_on_empty_pattern() {
+        let actual = " ".parse::<PackageFilter>();
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn discover_should_find_filtered_package_files_recursively() {
+        let dir = std::env::temp_dir().join("aer-discovery");
+        let _ = std::fs::remove_dir_all(&dir);
+        for name in &["7zip", "codecov", "curl"] {
+            let package_dir = dir.join("automatic").join(name);
+            std::fs::create_dir_all(&package_dir).unwrap();
+            std::fs::write(package_dir.join(format!("{}.aer.toml", name)), "").unwrap();
+            std::fs::write(package_dir.join("README.md"), "").unwrap();
+        }
+        let filters = vec!["c*".parse().unwrap()];
+
+        let all = discover(&dir, &[]);
+        let actual = discover(&dir, &filters);
+
+        assert_eq!(all.len(), 3);
+        assert_eq!(
+            actual,
+            vec![
+                dir.join("automatic/codecov/codecov.aer.toml"),
+                dir.join("automatic/curl/curl.aer.toml")
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,265 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains a simple work-queue executor, used to process several packages
+//! concurrently on a fixed number of threads.
+
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The outcome of processing a single job.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobStatus {
+    /// The job finished without any errors.
+    Succeeded,
+    /// The job was not processed, for instance when a package is not due to
+    /// be checked yet.
+    Skipped,
+    /// The job was not processed, as nothing have changed since the last
+    /// time it was successfully processed.
+    Unchanged,
+    /// The job failed with the specified error message.
+    Failed(String),
+}
+
+/// The name and outcome of a single job.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobResult {
+    /// The name of the job, normally the package identifier or file name.
+    pub name: String,
+    /// The outcome of the job.
+    pub status: JobStatus,
+    /// The time spent processing the job, this is set by the executor when
+    /// the job have finished.
+    pub duration: Duration,
+}
+
+impl JobResult {
+    /// Creates a new result with the specified name and status.
+    pub fn new<N: Display>(name: N, status: JobStatus) -> JobResult {
+        JobResult {
+            name: name.to_string(),
+            status,
+            duration: Duration::default(),
+        }
+    }
+}
+
+/// Aggregates the results of all jobs that was processed.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Summary {
+    results: Vec<JobResult>,
+}
+
+impl Summary {
+    /// Creates a new summary of the specified results.
+    pub fn new(results: Vec<JobResult>) -> Summary {
+        Summary { results }
+    }
+
+    /// Returns all of the results, in the same order as the jobs was queued.
+    pub fn results(&self) -> &[JobResult] {
+        &self.results
+    }
+
+    /// Returns the names of the jobs with the specified status.
+    fn names_with<F: Fn(&JobStatus) -> bool>(&self, filter: F) -> Vec<&str> {
+        self.results
+            .iter()
+            .filter(|result| filter(&result.status))
+            .map(|result| result.name.as_str())
+            .collect()
+    }
+
+    /// Returns the names of the jobs that succeeded.
+    pub fn succeeded(&self) -> Vec<&str> {
+        self.names_with(|status| *status == JobStatus::Succeeded)
+    }
+
+    /// Returns the names of the jobs that was skipped.
+    pub fn skipped(&self) -> Vec<&str> {
+        self.names_with(|status| *status == JobStatus::Skipped)
+    }
+
+    /// Returns the names of the jobs that was unchanged since the last time
+    /// they were processed.
+    pub fn unchanged(&self) -> Vec<&str> {
+        self.names_with(|status| *status == JobStatus::Unchanged)
+    }
+
+    /// Returns the names of the jobs that failed.
+    pub fn failed(&self) -> Vec<&str> {
+        self.names_with(|status| matches!(status, JobStatus::Failed(_)))
+    }
+
+    /// Returns the exit code that should be used by the application, which is
+    /// `1` when any of the jobs failed.
+    pub fn exit_code(&self) -> i32 {
+        if self.failed().is_empty() {
+            0
+        } else {
+            1
+        }
+    }
+}
+
+/// Returns the number of jobs to use, where `0` means one job for each
+/// available processor.
+pub fn resolve_jobs(jobs: usize) -> usize {
+    if jobs > 0 {
+        return jobs;
+    }
+
+    thread::available_parallelism()
+        .map(|count| count.get())
+        .unwrap_or(1)
+}
+
+/// Processes all items using the specified number of worker threads, and
+/// returns the results in the same order as the items was specified.
+pub fn run_jobs<T, F>(items: Vec<T>, jobs: usize, func: F) -> Summary
+where
+    T: Send + 'static,
+    F: Fn(T) -> JobResult + Send + Sync + 'static,
+{
+    let count = items.len();
+    let queue: Arc<Mutex<VecDeque<(usize, T)>>> =
+        Arc::new(Mutex::new(items.into_iter().enumerate().collect()));
+    let func = Arc::new(func);
+    let (sender, receiver) = mpsc::channel();
+
+    let workers: Vec<_> = (0..resolve_jobs(jobs).min(count))
+        .map(|_| {
+            let queue = Arc::clone(&queue);
+            let func = Arc::clone(&func);
+            let sender = sender.clone();
+
+            thread::spawn(move || loop {
+                let next = queue.lock().unwrap().pop_front();
+                match next {
+                    Some((index, item)) => {
+                        let started = Instant::now();
+                        let mut result = panic::catch_unwind(AssertUnwindSafe(|| func(item)))
+                            .unwrap_or_else(|_| {
+                                JobResult::new(
+                                    format!("#{}", index + 1),
+                                    JobStatus::Failed("The job panicked".into()),
+                                )
+                            });
+                        result.duration = started.elapsed();
+                        if sender.send((index, result)).is_err() {
+                            break;
+                        }
+                    }
+                    None => break,
+                }
+            })
+        })
+        .collect();
+    drop(sender);
+
+    let mut results: Vec<Option<JobResult>> = vec![None; count];
+    for (index, result) in receiver {
+        results[index] = Some(result);
+    }
+
+    for worker in workers {
+        if worker.join().is_err() {
+            log::error!("A worker thread panicked while processing packages!");
+        }
+    }
+
+    Summary::new(
+        results
+            .into_iter()
+            .enumerate()
+            .map(|(index, result)| {
+                result.unwrap_or_else(|| {
+                    JobResult::new(
+                        format!("#{}", index + 1),
+                        JobStatus::Failed("The job did not complete".into()),
+                    )
+                })
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest(jobs, case(1), case(3), case(10))]
+    fn run_jobs_should_return_results_in_queued_order(jobs: usize) {
+        let items: Vec<usize> = (0..8).collect();
+
+        let summary = run_jobs(items, jobs, |item| {
+            JobResult::new(
+                item,
+                if item % 2 == 0 {
+                    JobStatus::Succeeded
+                } else {
+                    JobStatus::Failed("odd".into())
+                },
+            )
+        });
+
+        let names: Vec<&str> = summary.results().iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["0", "1", "2", "3", "4", "5", "6", "7"]);
+        assert_eq!(summary.succeeded(), ["0", "2", "4", "6"]);
+        assert_eq!(summary.failed(), ["1", "3", "5", "7"]);
+    }
+
+    #[test]
+    fn run_jobs_should_record_duration_of_jobs() {
+        let summary = run_jobs(vec![20], 1, |item| {
+            thread::sleep(Duration::from_millis(item));
+            JobResult::new(item, JobStatus::Succeeded)
+        });
+
+        assert!(summary.results()[0].duration >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn run_jobs_should_report_panicking_jobs_as_failed() {
+        let summary = run_jobs(vec![1, 2], 1, |item| {
+            if item == 1 {
+                panic!("Job failed");
+            }
+            JobResult::new(item, JobStatus::Succeeded)
+        });
+
+        assert_eq!(summary.exit_code(), 1);
+        assert_eq!(summary.failed(), ["#1"]);
+    }
+
+    #[rstest(
+        statuses,
+        expected,
+        case(vec![JobStatus::Succeeded, JobStatus::Skipped], 0),
+        case(vec![JobStatus::Unchanged, JobStatus::Succeeded], 0),
+        case(vec![JobStatus::Succeeded, JobStatus::Failed("error".into())], 1)
+    )]
+    fn exit_code_should_only_fail_on_failed_jobs(statuses: Vec<JobStatus>, expected: i32) {
+        let summary = Summary::new(
+            statuses
+                .into_iter()
+                .map(|status| JobResult::new("test", status))
+                .collect(),
+        );
+
+        assert_eq!(summary.exit_code(), expected);
+    }
+
+    #[test]
+    fn resolve_jobs_should_use_available_processors_when_zero() {
+        assert!(resolve_jobs(0) >= 1);
+        assert_eq!(resolve_jobs(4), 4);
+    }
+}
@@ -0,0 +1,318 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for updating existing package directories in place,
+//! which is used when migrating packages that are maintained by hand or by
+//! `au` (*Chocolatey Automatic Package Updater Module*).
+//!
+//! Only the managed values are replaced, the version in the nuspec file and
+//! the urls and checksums assigned in the scripts of the tools directory. Every
+//! other part of the files is kept as is.
+
+use std::path::{Path, PathBuf};
+
+use aer_upd::generators::chocolatey::TOOLS_DIR;
+use aer_upd::generators::templates::TemplateValues;
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+
+use crate::codes::{self, CodedError};
+
+lazy_static! {
+    static ref NUSPEC_VERSION: Regex =
+        Regex::new(r"(?i)(<version>)\s*[^<]*?\s*(</version>)").unwrap();
+    static ref SCRIPT_ASSIGNMENT: Regex = Regex::new(
+        r#"(?im)^(\s*\$?(url|url32|url64|url64bit|checksum|checksum32|checksum64|checksumtype|checksumtype32|checksumtype64)\s*=\s*)(['"])([^'"\r\n]*)(['"])"#
+    )
+    .unwrap();
+}
+
+/// A package directory that already exists, containing the nuspec file of the
+/// package and optionally the scripts in the tools directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExistingPackage {
+    package_dir: PathBuf,
+    nuspec: PathBuf,
+}
+
+impl ExistingPackage {
+    /// Locates the existing package directory of the package with the
+    /// specified identifier. The directory is either the specified directory
+    /// itself, or a sub directory named after the identifier (*ie: the layout
+    /// used by `au` repositories*).
+    pub fn locate(dir: &Path, id: &str) -> Result<ExistingPackage, CodedError> {
+        for package_dir in &[dir.join(id), dir.to_path_buf()] {
+            if let Some(nuspec) = find_nuspec(package_dir, id) {
+                return Ok(ExistingPackage {
+                    package_dir: package_dir.clone(),
+                    nuspec,
+                });
+            }
+        }
+
+        Err(CodedError::new(
+            codes::MISSING_EXISTING_PACKAGE,
+            format!(
+                "No nuspec file for the package '{}' was found in '{}'!",
+                id,
+                dir.display()
+            ),
+        ))
+    }
+
+    /// Returns the directory of the existing package.
+    pub fn package_dir(&self) -> &Path {
+        &self.package_dir
+    }
+
+    /// Returns the path to the nuspec file of the existing package.
+    pub fn nuspec(&self) -> &Path {
+        &self.nuspec
+    }
+
+    /// Returns the PowerShell scripts in the tools directory of the package,
+    /// sorted by name.
+    pub fn scripts(&self) -> Vec<PathBuf> {
+        let mut scripts: Vec<_> = std::fs::read_dir(self.package_dir.join(TOOLS_DIR))
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && has_extension(path, "ps1"))
+            .collect();
+        scripts.sort();
+
+        scripts
+    }
+
+    /// Updates the version in the nuspec file, and the urls and checksums in
+    /// the scripts of the package using the specified values. Returns the
+    /// files that was changed.
+    pub fn update(
+        &self,
+        version: &str,
+        values: &TemplateValues,
+    ) -> Result<Vec<PathBuf>, std::io::Error> {
+        let mut changed = vec![];
+
+        if update_file(&self.nuspec, |text| replace_version(text, version))? {
+            changed.push(self.nuspec.clone());
+        }
+
+        for script in self.scripts() {
+            if update_file(&script, |text| replace_assignments(text, values))? {
+                changed.push(script);
+            }
+        }
+
+        Ok(changed)
+    }
+}
+
+/// Replaces the version in the content of a nuspec file.
+pub fn replace_version(nuspec: &str, version: &str) -> String {
+    NUSPEC_VERSION
+        .replace(nuspec, |caps: &Captures| {
+            format!("{}{}{}", &caps[1], version, &caps[2])
+        })
+        .into_owned()
+}
+
+/// Replaces the values assigned to the urls and checksums in the content of a
+/// PowerShell script, both variables (*ie: `$url64 = '...'`*) and keys of
+/// hashtables (*ie: `checksum64 = '...'`*) are replaced. Assignments are only
+/// replaced when a value is available for the architecture.
+pub fn replace_assignments(script: &str, values: &TemplateValues) -> String {
+    SCRIPT_ASSIGNMENT
+        .replace_all(script, |caps: &Captures| {
+            match values.get(value_name(&caps[2])) {
+                Some(value) => format!("{}{}{}{}", &caps[1], &caps[3], value, &caps[5]),
+                None => caps[0].to_owned(),
+            }
+        })
+        .into_owned()
+}
+
+/// Reads the values assigned to the urls and checksums in the content of a
+/// PowerShell script, using the names of the template values (*ie: `url64`
+/// for both `$url64` and `url64bit`*). Empty assignments are ignored, and the
+/// first assignment is used when a value is assigned multiple times.
+pub fn read_assignments(script: &str) -> TemplateValues {
+    let mut values = TemplateValues::new();
+
+    for caps in SCRIPT_ASSIGNMENT.captures_iter(script) {
+        let name = value_name(&caps[2]);
+        if !caps[4].is_empty() && values.get(name).is_none() {
+            values.set(name, &caps[4]);
+        }
+    }
+
+    values
+}
+
+/// Returns the name of the template value used for the specified variable.
+fn value_name(variable: &str) -> &'static str {
+    match variable.to_lowercase().as_str() {
+        "url" | "url32" => "url32",
+        "url64" | "url64bit" => "url64",
+        "checksum" | "checksum32" => "checksum32",
+        "checksum64" => "checksum64",
+        "checksumtype" | "checksumtype32" => "checksumType32",
+        _ => "checksumType64",
+    }
+}
+
+fn find_nuspec(dir: &Path, id: &str) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .find(|path| {
+            path.is_file()
+                && has_extension(path, "nuspec")
+                && matches!(path.file_stem().and_then(|stem| stem.to_str()), Some(stem) if stem.eq_ignore_ascii_case(id))
+        })
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    matches!(path.extension().and_then(|ext| ext.to_str()), Some(ext) if ext.eq_ignore_ascii_case(extension))
+}
+
+fn update_file<F: FnOnce(&str) -> String>(path: &Path, func: F) -> Result<bool, std::io::Error> {
+    let text = std::fs::read_to_string(path)?;
+    let updated = func(&text);
+
+    if updated == text {
+        return Ok(false);
+    }
+
+    std::fs::write(path, updated)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn values() -> TemplateValues {
+        TemplateValues::new()
+            .with_value("url32", "https://example.org/app-2.0.0.exe")
+            .with_value("url64", "https://example.org/app-2.0.0-x64.exe")
+            .with_value("checksum32", "AAAA")
+            .with_value("checksum64", "BBBB")
+            .with_value("checksumType32", "sha256")
+            .with_value("checksumType64", "sha256")
+    }
+
+    #[rstest(
+        nuspec,
+        expected,
+        case(
+            "<metadata>\n  <version>1.0.0</version>\n</metadata>",
+            "<metadata>\n  <version>2.0.0</version>\n</metadata>"
+        ),
+        case(
+            "<metadata><version> 1.0.0 </version><id>app</id></metadata>",
+            "<metadata><version>2.0.0</version><id>app</id></metadata>"
+        )
+    )]
+    fn replace_version_should_replace_version_element(nuspec: &str, expected: &str) {
+        let actual = replace_version(nuspec, "2.0.0");
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn replace_assignments_should_replace_variables_and_hashtable_keys() {
+        let script = "$ErrorActionPreference = 'Stop'\n$url = 'https://example.org/app-1.0.0.exe' \
+                      # Keep me\n$packageArgs = @{\n  packageName = $env:ChocolateyPackageName\n  \
+                      url64bit = \"https://example.org/app-1.0.0-x64.exe\"\n  Checksum64 = \
+                      'OLD'\n  checksumType64 = 'md5'\n}\n";
+
+        let actual = replace_assignments(script, &values());
+
+        assert_eq!(
+            actual,
+            "$ErrorActionPreference = 'Stop'\n$url = 'https://example.org/app-2.0.0.exe' # Keep \
+             me\n$packageArgs = @{\n  packageName = $env:ChocolateyPackageName\n  url64bit = \
+             \"https://example.org/app-2.0.0-x64.exe\"\n  Checksum64 = 'BBBB'\n  checksumType64 = \
+             'sha256'\n}\n"
+        );
+    }
+
+    #[test]
+    fn read_assignments_should_read_variables_and_hashtable_keys() {
+        let script = "$url = 'https://example.org/app-1.0.0.exe'\n$checksum = ''\n$packageArgs = \
+                      @{\n  url64bit = \"https://example.org/app-1.0.0-x64.exe\"\n  Checksum64 = \
+                      'BBBB'\n  checksumType64 = 'sha256'\n}\n";
+
+        let actual = read_assignments(script);
+
+        assert_eq!(
+            actual,
+            TemplateValues::new()
+                .with_value("url32", "https://example.org/app-1.0.0.exe")
+                .with_value("url64", "https://example.org/app-1.0.0-x64.exe")
+                .with_value("checksum64", "BBBB")
+                .with_value("checksumType64", "sha256")
+        );
+    }
+
+    #[test]
+    fn replace_assignments_should_keep_assignments_without_values() {
+        let script = "$url64 = 'https://example.org/app-1.0.0-x64.exe'\n";
+
+        let actual = replace_assignments(script, &TemplateValues::new());
+
+        assert_eq!(actual, script);
+    }
+
+    #[test]
+    fn update_should_update_nuspec_and_scripts_in_sub_directory() {
+        let dir = std::env::temp_dir().join("aer-existing-package-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let package_dir = dir.join("app");
+        std::fs::create_dir_all(package_dir.join(TOOLS_DIR)).unwrap();
+        std::fs::write(
+            package_dir.join("app.nuspec"),
+            "<package><metadata><id>app</id><version>1.0.0</version></metadata></package>",
+        )
+        .unwrap();
+        std::fs::write(
+            package_dir.join(TOOLS_DIR).join("chocolateyInstall.ps1"),
+            "$checksum = 'OLD'\n",
+        )
+        .unwrap();
+        std::fs::write(
+            package_dir.join(TOOLS_DIR).join("chocolateyUninstall.ps1"),
+            "Uninstall-ChocolateyPackage\n",
+        )
+        .unwrap();
+
+        let package = ExistingPackage::locate(&dir, "app").unwrap();
+        let actual = package.update("2.0.0", &values()).unwrap();
+
+        assert_eq!(package.package_dir(), package_dir);
+        assert_eq!(
+            actual,
+            [
+                package_dir.join("app.nuspec"),
+                package_dir.join(TOOLS_DIR).join("chocolateyInstall.ps1")
+            ]
+        );
+        assert_eq!(
+            std::fs::read_to_string(package_dir.join(TOOLS_DIR).join("chocolateyInstall.ps1"))
+                .unwrap(),
+            "$checksum = 'AAAA'\n"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn locate_should_return_error_when_nuspec_is_missing() {
+        let actual =
+            ExistingPackage::locate(Path::new("test-data"), "missing-package").unwrap_err();
+
+        assert_eq!(codes::error_code(&actual), codes::MISSING_EXISTING_PACKAGE);
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the functionality for deciding if a package can be skipped,
+//! because neither the package files nor the upstream location have changed
+//! since the last successful update.
+
+use std::path::{Path, PathBuf};
+
+use aer_upd::data::{Description, PackageData, UpdaterSource, Url};
+use aer_upd::web::sources::github;
+use aer_upd::web::sources::gitlab::GitLabProject;
+use aer_upd::web::sources::sourceforge::SourceForgeProject;
+use sha2::{Digest, Sha256};
+
+use crate::stale;
+use crate::state::PackageHistory;
+
+/// Returns the paths to the files referenced by the package data that
+/// affects the created package, relative paths are resolved against the
+/// specified base directory.
+pub fn referenced_files(data: &PackageData, base_dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = data
+        .updater()
+        .scripts
+        .iter()
+        .map(|script| base_dir.join(script))
+        .collect();
+
+    if let Description::Location { ref from, .. } = *data.metadata().chocolatey().description() {
+        files.push(base_dir.join(from));
+    }
+
+    files
+}
+
+/// Computes the hash of the package file together with every file it
+/// references. Referenced files that do not exist are included by name only,
+/// making sure the hash changes when the file is created.
+pub fn package_hash(file: &Path, data: &PackageData) -> Result<String, std::io::Error> {
+    let base_dir = file.parent().unwrap_or_else(|| Path::new(""));
+    let mut hasher = Sha256::new();
+    hasher.update(std::fs::read(file)?);
+
+    for path in referenced_files(data, base_dir) {
+        hasher.update(path.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        if path.is_file() {
+            hasher.update(std::fs::read(&path)?);
+        }
+        hasher.update([0]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Returns the url used to check if the upstream location have changed, this
+/// is the location that is parsed when finding new versions of the package.
+pub fn upstream_url(data: &PackageData) -> Option<Url> {
+    let updater = data.updater();
+
+    match updater.source {
+        UpdaterSource::Html | UpdaterSource::Json | UpdaterSource::Feed | UpdaterSource::Xml => {
+            stale::parse_url(data)
+        }
+        UpdaterSource::GitHub => github::releases_url(updater.repository.as_deref()?).ok(),
+        UpdaterSource::GitLab => GitLabProject::parse(updater.repository.as_deref()?)
+            .and_then(|project| project.releases_url())
+            .ok(),
+        UpdaterSource::SourceForge => SourceForgeProject::parse(updater.repository.as_deref()?)
+            .and_then(|project| project.feed_url())
+            .ok(),
+    }
+}
+
+/// Returns wether the package is unchanged since the last successful update,
+/// which is only the case when the hash of the package files is the same and
+/// the upstream location responds with the same entity tag.
+pub fn is_unchanged(history: &PackageHistory, hash: &str, etag: Option<&str>) -> bool {
+    match (history.hash.as_deref(), history.etag.as_deref(), etag) {
+        (Some(old_hash), Some(old_etag), Some(etag)) => old_hash == hash && old_etag == etag,
+        _ => false,
+    }
+}
+
+/// Returns wether the upstream location have changed since the last
+/// successful update, the upstream location is always considered changed
+/// when either the previous or the current etag is unknown.
+pub fn is_upstream_changed(history: &PackageHistory, etag: Option<&str>) -> bool {
+    match (history.etag.as_deref(), etag) {
+        (Some(old_etag), Some(etag)) => old_etag != etag,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_upd::data::chocolatey::ChocolateyMetadata;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest(
+        hash,
+        etag,
+        current_hash,
+        current_etag,
+        expected,
+        case(Some("abc"), Some("\"1\""), "abc", Some("\"1\""), true),
+        case(Some("abc"), Some("\"1\""), "def", Some("\"1\""), false),
+        case(Some("abc"), Some("\"1\""), "abc", Some("\"2\""), false),
+        case(Some("abc"), Some("\"1\""), "abc", None, false),
+        case(Some("abc"), None, "abc", None, false),
+        case(None, Some("\"1\""), "abc", Some("\"1\""), false)
+    )]
+    fn is_unchanged_should_compare_hash_and_etag(
+        hash: Option<&str>,
+        etag: Option<&str>,
+        current_hash: &str,
+        current_etag: Option<&str>,
+        expected: bool,
+    ) {
+        let history = PackageHistory {
+            hash: hash.map(String::from),
+            etag: etag.map(String::from),
+            ..PackageHistory::default()
+        };
+
+        assert_eq!(is_unchanged(&history, current_hash, current_etag), expected);
+    }
+
+    #[rstest(
+        etag,
+        current_etag,
+        expected,
+        case(Some("\"1\""), Some("\"1\""), false),
+        case(Some("\"1\""), Some("\"2\""), true),
+        case(Some("\"1\""), None, true),
+        case(None, Some("\"1\""), true)
+    )]
+    fn is_upstream_changed_should_compare_etag(
+        etag: Option<&str>,
+        current_etag: Option<&str>,
+        expected: bool,
+    ) {
+        let history = PackageHistory {
+            etag: etag.map(String::from),
+            ..PackageHistory::default()
+        };
+
+        assert_eq!(is_upstream_changed(&history, current_etag), expected);
+    }
+
+    #[test]
+    fn package_hash_should_change_when_referenced_file_changes() {
+        let dir = std::env::temp_dir().join("aer-fingerprint-hash-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("package.aer.toml");
+        std::fs::write(&file, "[metadata]\nid = \"test-package\"").unwrap();
+        let mut data = PackageData::new("test-package");
+        let mut choco = ChocolateyMetadata::new();
+        choco.set_description(Description::Location {
+            from: PathBuf::from("description.md"),
+            skip_start: 0,
+            skip_end: 0,
+        });
+        data.metadata_mut().set_chocolatey(choco);
+
+        let missing = package_hash(&file, &data).unwrap();
+        std::fs::write(dir.join("description.md"), "First").unwrap();
+        let first = package_hash(&file, &data).unwrap();
+        let same = package_hash(&file, &data).unwrap();
+        std::fs::write(dir.join("description.md"), "Second").unwrap();
+        let second = package_hash(&file, &data).unwrap();
+
+        assert_ne!(missing, first);
+        assert_eq!(first, same);
+        assert_ne!(first, second);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
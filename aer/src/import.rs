@@ -0,0 +1,603 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the import of existing Chocolatey packages into package data
+//! files, used when migrating packages that are maintained by hand or by `au`
+//! (*Chocolatey Automatic Package Updater Module*).
+//!
+//! The metadata of the package is read from the nuspec file, either from a
+//! package directory, the nuspec file itself or a packed package (*ie:
+//! `app.1.0.0.nupkg`*). The urls and checksums assigned in the scripts of the
+//! tools directory are replaced with the tokens of the script templates (*ie:
+//! `{{url64}}`*), and the urls are used to create the regexes finding new
+//! versions where possible. The imported package data should always be
+//! reviewed, as the location the links are found at can not be known.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use aer_archive::{Archive, ArchiveFormat};
+use aer_upd::data::chocolatey::{ChocolateyMetadata, ChocolateyParseUrl, ChocolateyUpdaterData};
+use aer_upd::data::{LicenseType, PackageData, UpdaterSource, Url, Versions};
+use aer_upd::generators::chocolatey::TOOLS_DIR;
+use aer_upd::generators::templates::TemplateValues;
+use aer_upd::parsers;
+use aer_upd::web::xml::{XmlDocument, XmlElement};
+use log::{info, warn};
+
+use crate::codes::{self, CodedError};
+use crate::existing::{read_assignments, replace_assignments};
+
+/// The names of the values read from the scripts, that are replaced with the
+/// tokens of the script templates.
+const TEMPLATE_VALUES: &[&str] = &[
+    "url32",
+    "url64",
+    "checksum32",
+    "checksum64",
+    "checksumType32",
+    "checksumType64",
+];
+
+/// An existing package that have been imported, holding the package data and
+/// the script templates of the package.
+#[derive(Debug)]
+pub struct ImportedPackage {
+    data: PackageData,
+    templates: Vec<(PathBuf, String)>,
+}
+
+impl ImportedPackage {
+    /// Imports the package at the specified path, which can be a package
+    /// directory, a nuspec file or a packed package.
+    pub fn from_path(path: &Path) -> Result<ImportedPackage, CodedError> {
+        let (nuspec, scripts) = if path.is_dir() {
+            read_directory(path)?
+        } else if has_extension(path, "nuspec") {
+            let scripts = match path.parent() {
+                Some(dir) => read_scripts(&dir.join(TOOLS_DIR)),
+                None => vec![],
+            };
+            (read_text(path)?, scripts)
+        } else if has_extension(path, "nupkg") {
+            read_package(path)?
+        } else {
+            return Err(import_error(format!(
+                "'{}' is not a package directory, nuspec file or nupkg file!",
+                path.display()
+            )));
+        };
+
+        ImportedPackage::parse(&nuspec, scripts)
+    }
+
+    /// Imports the package using the content of the nuspec file and the
+    /// scripts of the package, where the scripts are specified as the path
+    /// relative to the package directory together with the content of the
+    /// script.
+    pub fn parse(
+        nuspec: &str,
+        scripts: Vec<(PathBuf, String)>,
+    ) -> Result<ImportedPackage, CodedError> {
+        let document = XmlDocument::parse(nuspec)
+            .map_err(|err| import_error(format!("The nuspec file is not valid: {}", err)))?;
+        let metadata = document
+            .root()
+            .elements()
+            .find(|element| element.name.ends_with("metadata"))
+            .ok_or_else(|| import_error("The nuspec file do not contain any metadata!"))?;
+        let mut data = read_metadata(metadata)?;
+
+        let mut values = TemplateValues::new();
+        let mut templates = vec![];
+        for (path, script) in scripts {
+            let found = read_assignments(&script);
+            let mut tokens = TemplateValues::new();
+            for name in TEMPLATE_VALUES {
+                if let Some(value) = found.get(name) {
+                    tokens.set(name, format!("{{{{{}}}}}", name));
+                    if values.get(name).is_none() {
+                        values.set(name, value);
+                    }
+                }
+            }
+            if tokens.names().next().is_some() {
+                templates.push((path, replace_assignments(&script, &tokens)));
+            }
+        }
+
+        set_updater(&mut data, &values);
+        data.updater_mut().templates = templates.iter().map(|(path, _)| path.clone()).collect();
+
+        Ok(ImportedPackage { data, templates })
+    }
+
+    /// Returns the package data of the imported package.
+    pub fn data(&self) -> &PackageData {
+        &self.data
+    }
+
+    /// Returns the script templates of the imported package, as the path
+    /// relative to the package data file together with the content of the
+    /// template.
+    pub fn templates(&self) -> &[(PathBuf, String)] {
+        &self.templates
+    }
+
+    /// Writes the package data file and the script templates in a directory
+    /// named after the identifier of the package inside the specified
+    /// directory. Returns the path of the package data file, or an error if
+    /// the file already exists and `force` have not been specified.
+    pub fn write(&self, dir: &Path, force: bool) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let id = self.data.metadata().id();
+        let package_dir = dir.join(id);
+        let path = package_dir.join(format!("{}.aer.toml", id));
+        if path.exists() && !force {
+            return Err(import_error(format!(
+                "The package file '{}' already exists!",
+                path.display()
+            ))
+            .into());
+        }
+
+        std::fs::create_dir_all(&package_dir)?;
+        parsers::write_file(&self.data, &path)?;
+        info!("Imported the package data to '{}'", path.display());
+
+        for (template, content) in &self.templates {
+            let target = package_dir.join(template);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&target, content)?;
+            info!("Created the script template '{}'", target.display());
+        }
+
+        Ok(path)
+    }
+}
+
+fn read_metadata(metadata: &XmlElement) -> Result<PackageData, CodedError> {
+    let values: HashMap<String, String> = metadata
+        .elements()
+        .map(|element| (element.name.to_lowercase(), element.text()))
+        .filter(|(_, text)| !text.is_empty())
+        .collect();
+    let value = |name: &str| values.get(name).map(String::as_str);
+
+    let id = value("id").ok_or_else(|| import_error("The nuspec file do not have an id!"))?;
+    let mut data = PackageData::new(id);
+    let mut chocolatey = ChocolateyMetadata::new();
+
+    let package = data.metadata_mut();
+    if let Some(owners) = value("owners") {
+        package.set_maintainers(&split_list(owners, ','));
+    }
+    if let Some(project_url) = value("projecturl").filter(|url| Url::parse(url).is_ok()) {
+        package.set_project_url(project_url);
+    }
+    package.summary = value("summary").unwrap_or_default().to_owned();
+    package.set_license(read_license(metadata, value("licenseurl")));
+
+    if let Some(version) = value("version").and_then(|version| Versions::parse(version).ok()) {
+        chocolatey.version = version;
+    }
+    if let Some(title) = value("title") {
+        chocolatey.set_title(title);
+    }
+    if let Some(authors) = value("authors") {
+        chocolatey.set_authors(&split_list(authors, ','));
+    }
+    if let Some(copyright) = value("copyright") {
+        chocolatey.set_copyright(copyright);
+    }
+    if let Some(tags) = value("tags") {
+        chocolatey.set_tags(&split_list(tags, ' '));
+    }
+    chocolatey.set_description_str(value("description").unwrap_or_default());
+    if let Some(release_notes) = value("releasenotes") {
+        chocolatey.set_release_notes(release_notes);
+    }
+    chocolatey.require_license_acceptance = matches!(value("requirelicenseacceptance"), Some(accept) if accept.eq_ignore_ascii_case("true"));
+    chocolatey.documentation_url = value("docsurl").and_then(|url| Url::parse(url).ok());
+    chocolatey.issues_url = value("bugtrackerurl").and_then(|url| Url::parse(url).ok());
+    chocolatey.set_dependencies(read_dependencies(metadata));
+    package.set_chocolatey(chocolatey);
+
+    Ok(data)
+}
+
+/// Reads the license of the package, using the license expression when the
+/// nuspec file specifies the license as an expression.
+fn read_license(metadata: &XmlElement, license_url: Option<&str>) -> LicenseType {
+    let expression = metadata
+        .elements()
+        .find(|element| {
+            element.name.eq_ignore_ascii_case("license")
+                && matches!(element.attribute("type"), Some("expression"))
+        })
+        .map(XmlElement::text)
+        .filter(|expression| !expression.is_empty());
+    let url = license_url.and_then(|url| Url::parse(url).ok());
+
+    match (expression, url) {
+        (Some(expression), Some(url)) => LicenseType::ExpressionAndLocation { expression, url },
+        (Some(expression), None) => LicenseType::Expression(expression),
+        (None, Some(url)) => LicenseType::Location(url),
+        (None, None) => LicenseType::None,
+    }
+}
+
+/// Reads the dependencies of the package, using the lowest version of
+/// version ranges (*ie: `1.0.0` for `[1.0.0, 2.0.0)`*). Dependencies without
+/// a valid version are skipped.
+fn read_dependencies(metadata: &XmlElement) -> HashMap<String, Versions> {
+    let mut dependencies = HashMap::new();
+
+    for dependencies_element in metadata
+        .elements()
+        .filter(|element| element.name.eq_ignore_ascii_case("dependencies"))
+    {
+        let mut elements: Vec<&XmlElement> = dependencies_element.elements().collect();
+        for group in dependencies_element.elements() {
+            elements.extend(group.elements());
+        }
+
+        for dependency in elements {
+            let (id, version) = match (dependency.attribute("id"), dependency.attribute("version"))
+            {
+                (Some(id), Some(version)) => (id, version),
+                _ => continue,
+            };
+            let version = version
+                .trim_matches(|ch: char| ch == '[' || ch == '(' || ch.is_whitespace())
+                .split(',')
+                .next()
+                .unwrap_or_default()
+                .trim_end_matches(|ch: char| ch == ']' || ch == ')' || ch.is_whitespace());
+            match Versions::parse(version) {
+                Ok(version) => {
+                    dependencies.insert(id.to_owned(), version);
+                }
+                Err(_) => warn!(
+                    "Skipping the dependency '{}', the version could not be read",
+                    id
+                ),
+            }
+        }
+    }
+
+    dependencies
+}
+
+/// Sets the updater data of the package using the urls read from the scripts
+/// of the package. Urls of releases on GitHub uses the releases of the
+/// repository, other urls uses the directory of the url as the parse url.
+fn set_updater(data: &mut PackageData, values: &TemplateValues) {
+    let version = data.metadata().chocolatey().version.to_string();
+    let mut chocolatey = ChocolateyUpdaterData::new();
+    let mut parse_url = None;
+
+    for (arch, name) in &[("arch32", "url32"), ("arch64", "url64")] {
+        let url = match values.get(name).and_then(|url| Url::parse(url).ok()) {
+            Some(url) => url,
+            None => continue,
+        };
+        let file_name = match url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+        {
+            Some(file_name) if !file_name.is_empty() => file_name.to_owned(),
+            _ => continue,
+        };
+        chocolatey.add_regex(arch, &file_regex(&file_name, &version));
+
+        if let Some(repository) = github_repository(&url) {
+            let updater = data.updater_mut();
+            updater.source = UpdaterSource::GitHub;
+            updater.repository = Some(repository);
+        } else if parse_url.is_none() {
+            let mut dir = url.clone();
+            dir.path_segments_mut()
+                .map(|mut segments| {
+                    segments.pop().push("");
+                })
+                .ok();
+            parse_url = Some(dir);
+        }
+    }
+
+    if chocolatey.regexes().is_empty() {
+        warn!(
+            "No urls was found in the scripts of the package, the regexes must be added manually"
+        );
+        return;
+    }
+    if data.updater().source != UpdaterSource::GitHub {
+        if let Some(parse_url) = parse_url {
+            warn!(
+                "Using '{}' as the parse url, verify that the links can be found on this page",
+                parse_url
+            );
+            chocolatey.parse_url = Some(ChocolateyParseUrl::Url(parse_url));
+        }
+    }
+
+    data.updater_mut().set_chocolatey(chocolatey);
+}
+
+/// Creates the regex matching the file name of the url, where the version of
+/// the package is replaced with the `version` group.
+fn file_regex(file_name: &str, version: &str) -> String {
+    let escaped = regex::escape(file_name);
+    let escaped_version = regex::escape(version);
+
+    if version.is_empty() || !escaped.contains(&escaped_version) {
+        format!("{}$", escaped)
+    } else {
+        format!(
+            "{}$",
+            escaped.replacen(&escaped_version, r"(?P<version>[\d\.]+)", 1)
+        )
+    }
+}
+
+/// Returns the repository in the form `owner/repo` when the url points to
+/// the assets of a release on GitHub.
+fn github_repository(url: &Url) -> Option<String> {
+    if url.host_str() != Some("github.com") {
+        return None;
+    }
+
+    let segments: Vec<_> = url.path_segments()?.collect();
+    match segments.as_slice() {
+        [owner, repo, "releases", "download", ..] => Some(format!("{}/{}", owner, repo)),
+        _ => None,
+    }
+}
+
+fn read_directory(dir: &Path) -> Result<(String, Vec<(PathBuf, String)>), CodedError> {
+    let nuspec = std::fs::read_dir(dir)
+        .map_err(|err| import_error(err.to_string()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .find(|path| path.is_file() && has_extension(path, "nuspec"))
+        .ok_or_else(|| import_error(format!("No nuspec file was found in '{}'!", dir.display())))?;
+
+    Ok((read_text(&nuspec)?, read_scripts(&dir.join(TOOLS_DIR))))
+}
+
+fn read_scripts(tools_dir: &Path) -> Vec<(PathBuf, String)> {
+    let mut scripts: Vec<_> = std::fs::read_dir(tools_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && has_extension(path, "ps1"))
+        .filter_map(|path| {
+            let text = std::fs::read_to_string(&path).ok()?;
+            Some((Path::new(TOOLS_DIR).join(path.file_name()?), text))
+        })
+        .collect();
+    scripts.sort();
+
+    scripts
+}
+
+fn read_package(path: &Path) -> Result<(String, Vec<(PathBuf, String)>), CodedError> {
+    let archive = Archive::with_format(path, ArchiveFormat::Zip);
+    let entries = archive
+        .entries()
+        .map_err(|err| import_error(err.to_string()))?;
+    let read = |entry: &Path| -> Result<String, CodedError> {
+        let name = entry.to_string_lossy().replace('\\', "/");
+        let bytes = archive
+            .read_file(&name)
+            .map_err(|err| import_error(err.to_string()))?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    };
+
+    let nuspec = entries
+        .iter()
+        .find(|entry| {
+            !entry.is_dir
+                && entry.path.components().count() == 1
+                && has_extension(&entry.path, "nuspec")
+        })
+        .ok_or_else(|| {
+            import_error(format!("No nuspec file was found in '{}'!", path.display()))
+        })?;
+    let mut scripts = vec![];
+    for entry in &entries {
+        if !entry.is_dir && entry.path.starts_with(TOOLS_DIR) && has_extension(&entry.path, "ps1") {
+            scripts.push((entry.path.clone(), read(&entry.path)?));
+        }
+    }
+    scripts.sort();
+
+    Ok((read(&nuspec.path)?, scripts))
+}
+
+fn read_text(path: &Path) -> Result<String, CodedError> {
+    std::fs::read_to_string(path)
+        .map_err(|err| import_error(format!("Unable to read '{}': {}", path.display(), err)))
+}
+
+fn split_list(value: &str, separator: char) -> Vec<String> {
+    value
+        .split(separator)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    matches!(path.extension().and_then(|ext| ext.to_str()), Some(ext) if ext.eq_ignore_ascii_case(extension))
+}
+
+fn import_error<M: Into<String>>(message: M) -> CodedError {
+    CodedError::new(codes::INVALID_IMPORT, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    const NUSPEC: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<package xmlns="http://schemas.microsoft.com/packaging/2015/06/nuspec.xsd">
+  <metadata>
+    <id>imported-package</id>
+    <version>1.2.3</version>
+    <title>Imported Package</title>
+    <authors>Kim J. Nordmo, WormieCorp</authors>
+    <owners>AdmiringWorm</owners>
+    <projectUrl>https://github.com/WormieCorp/aer</projectUrl>
+    <license type="expression">MIT</license>
+    <licenseUrl>https://github.com/WormieCorp/aer/blob/develop/LICENSE.txt</licenseUrl>
+    <requireLicenseAcceptance>true</requireLicenseAcceptance>
+    <tags>aer import test</tags>
+    <summary>Package imported from &lt;nuspec&gt;</summary>
+    <description>The description of the package</description>
+    <docsUrl>https://github.com/WormieCorp/aer/wiki</docsUrl>
+    <dependencies>
+      <dependency id="chocolatey-core.extension" version="[1.3.3, 2.0.0)" />
+      <dependency id="invalid" version="latest" />
+    </dependencies>
+  </metadata>
+</package>"#;
+
+    const SCRIPT: &str = "$packageArgs = @{\n  url64bit = \
+                          'https://github.com/WormieCorp/aer/releases/download/v1.2.3/aer-1.2.3-x64.exe'\n  \
+                          checksum64 = 'ABCDEF'\n  checksumType64 = 'sha256'\n}\n";
+
+    #[test]
+    fn parse_should_read_metadata_of_nuspec() {
+        let actual = ImportedPackage::parse(NUSPEC, vec![]).unwrap();
+        let metadata = actual.data().metadata();
+        let chocolatey = metadata.chocolatey();
+
+        assert_eq!(metadata.id(), "imported-package");
+        assert_eq!(metadata.maintainers(), ["AdmiringWorm"]);
+        assert_eq!(metadata.summary, "Package imported from <nuspec>");
+        assert_eq!(
+            metadata.project_url().as_str(),
+            "https://github.com/WormieCorp/aer"
+        );
+        assert!(matches!(
+            metadata.license(),
+            LicenseType::ExpressionAndLocation { expression, .. } if expression == "MIT"
+        ));
+        assert_eq!(chocolatey.version, Versions::parse("1.2.3").unwrap());
+        assert_eq!(chocolatey.title.as_deref(), Some("Imported Package"));
+        assert_eq!(chocolatey.authors(), ["Kim J. Nordmo", "WormieCorp"]);
+        assert_eq!(chocolatey.tags(), ["aer", "import", "test"]);
+        assert!(chocolatey.require_license_acceptance);
+        assert_eq!(
+            chocolatey.dependencies().get("chocolatey-core.extension"),
+            Some(&Versions::parse("1.3.3").unwrap())
+        );
+        assert_eq!(chocolatey.dependencies().len(), 1);
+    }
+
+    #[test]
+    fn parse_should_create_templates_and_github_updater_from_scripts() {
+        let actual = ImportedPackage::parse(
+            NUSPEC,
+            vec![(PathBuf::from("tools/chocolateyInstall.ps1"), SCRIPT.into())],
+        )
+        .unwrap();
+        let updater = actual.data().updater();
+
+        assert_eq!(updater.source, UpdaterSource::GitHub);
+        assert_eq!(updater.repository.as_deref(), Some("WormieCorp/aer"));
+        assert_eq!(
+            updater
+                .chocolatey()
+                .regexes()
+                .get("arch64")
+                .map(String::as_str),
+            Some(r"aer\-(?P<version>[\d\.]+)\-x64\.exe$")
+        );
+        assert_eq!(
+            updater.templates,
+            [PathBuf::from("tools/chocolateyInstall.ps1")]
+        );
+        assert_eq!(
+            actual.templates()[0].1,
+            "$packageArgs = @{\n  url64bit = '{{url64}}'\n  checksum64 = '{{checksum64}}'\n  \
+             checksumType64 = '{{checksumType64}}'\n}\n"
+        );
+    }
+
+    #[test]
+    fn parse_should_use_directory_of_url_as_parse_url() {
+        let script = "$url = 'https://example.org/downloads/app-1.2.3.exe'\n";
+
+        let actual = ImportedPackage::parse(
+            NUSPEC,
+            vec![(PathBuf::from("tools/install.ps1"), script.into())],
+        )
+        .unwrap();
+        let chocolatey = actual.data().updater().chocolatey();
+
+        assert_eq!(
+            chocolatey.parse_url,
+            Some(ChocolateyParseUrl::Url(
+                Url::parse("https://example.org/downloads/").unwrap()
+            ))
+        );
+        assert_eq!(
+            chocolatey.regexes().get("arch32").map(String::as_str),
+            Some(r"app\-(?P<version>[\d\.]+)\.exe$")
+        );
+    }
+
+    #[rstest(
+        nuspec,
+        case("<package><metadata><id>app</id></package>"),
+        case("<package><files /></package>"),
+        case("<package><metadata><version>1.0.0</version></metadata></package>")
+    )]
+    fn parse_should_return_error_on_invalid_nuspec(nuspec: &str) {
+        let actual = ImportedPackage::parse(nuspec, vec![]).unwrap_err();
+
+        assert_eq!(codes::error_code(&actual), codes::INVALID_IMPORT);
+    }
+
+    #[test]
+    fn write_should_create_readable_package_data_and_templates() {
+        let dir = std::env::temp_dir().join("aer-import-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("source").join(TOOLS_DIR)).unwrap();
+        std::fs::write(dir.join("source").join("imported-package.nuspec"), NUSPEC).unwrap();
+        std::fs::write(
+            dir.join("source")
+                .join(TOOLS_DIR)
+                .join("chocolateyInstall.ps1"),
+            SCRIPT,
+        )
+        .unwrap();
+
+        let imported = ImportedPackage::from_path(&dir.join("source")).unwrap();
+        let path = imported.write(&dir.join("target"), false).unwrap();
+        let actual = parsers::read_file(&path);
+        let template_exists = dir
+            .join("target")
+            .join("imported-package")
+            .join("tools")
+            .join("chocolateyInstall.ps1")
+            .is_file();
+        let second = imported.write(&dir.join("target"), false);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let actual = actual.unwrap();
+        assert_eq!(actual.metadata().id(), "imported-package");
+        assert_eq!(
+            actual.updater().repository.as_deref(),
+            Some("WormieCorp/aer")
+        );
+        assert!(template_exists);
+        assert!(second.is_err());
+    }
+}
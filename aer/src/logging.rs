@@ -86,6 +86,18 @@ impl Default for Colors {
     }
 }
 
+/// Disables the usage of colors when it have been requested through the
+/// arguments or the `NO_COLOR` environment variable, or when the console do
+/// not support colors.
+pub fn configure_colors(no_color: bool) {
+    let no_color =
+        no_color || std::env::var("NO_COLOR").unwrap_or_default().to_lowercase() == "true";
+
+    if no_color || (cfg!(windows) && !Paint::enable_windows_ascii()) {
+        Paint::disable();
+    }
+}
+
 pub fn setup_logging<T: LogDataTrait>(log: &T) -> Result<(), Box<dyn std::error::Error>> {
     let colors = Colors::default();
 
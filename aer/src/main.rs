@@ -1,144 +1,1411 @@
 // Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
 // Licensed under the MIT license. See LICENSE.txt file in the project
 #![windows_subsystem = "console"]
-use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use aer::{log_data, logging};
+use aer::audit::{self, LinkStatus};
+use aer::build::{self, BuildOptions};
+use aer::check::{self, FileWatcher, OutputFormat, Severity};
+use aer::commands::checksum::{self as checksum_cmd, ChecksumArguments};
+use aer::commands::config::{self as config_cmd, ConfigCommands};
+use aer::commands::history::{self as history_cmd, HistoryArguments};
+use aer::commands::state::{self as state_cmd, StateCommands};
+use aer::commands::ver::{self, VerArguments};
+use aer::commands::web::{self, WebCommands};
+use aer::context::Context;
+use aer::discovery::{self, PackageFilter};
+use aer::executor::{self, JobResult, JobStatus, Summary};
+use aer::import::ImportedPackage;
+use aer::policy::Policy;
+use aer::readiness::{self, CheckStatus};
+use aer::report::{PackageReport, RunReport};
+use aer::scaffold::{self, Scaffold, Target};
+use aer::stale::{self, StaleOptions};
+use aer::state::CheckState;
+use aer::update::{self, FoundLinks};
+use aer::validation::{self, RuleSet};
+use aer::{
+    backfill, codes, fingerprint, log_data, logging, publish, schedule, serve, simulate,
+    ChecksumType,
+};
+use aer_upd::context::UpdateContext;
 use aer_upd::data::*;
-use aer_upd::parsers;
-use aer_upd::web::{WebRequest, WebResponse};
+use aer_upd::runners::{RunnerProtocol, ScriptEnvironment};
+use aer_upd::web::fixtures::{FixtureMode, Fixtures};
+use aer_upd::{features, parsers};
 #[cfg(feature = "human")]
 use human_panic::setup_panic;
-use log::{error, info, trace, warn};
-use regex::Regex;
+use log::{error, info, warn};
 use structopt::StructOpt;
 use yansi::Paint;
 
 log_data! {}
 
 #[derive(StructOpt)]
-#[structopt(author = env!("CARGO_PKG_AUTHORS"))]
-struct Arguments {
+struct UpdateArguments {
     /// The files containing the necessary data (metadata+updater data) that
     /// should be used during the run.
+    #[structopt(required_unless = "all", conflicts_with = "all", parse(from_os_str))]
+    package_files: Vec<PathBuf>,
+
+    /// Update all packages located below the root directory, instead of the
+    /// specified package files. Directories are searched recursively for
+    /// package files (*ie: `*.aer.toml`*).
+    #[structopt(long)]
+    all: bool,
+
+    /// The directory that is searched for package files when updating all
+    /// packages, defaults to the current directory.
+    #[structopt(long = "root", requires = "all", parse(from_os_str))]
+    search_root: Option<PathBuf>,
+
+    /// Only update the discovered packages matching at least one of the glob
+    /// filters, the filters are matched against the path of the package file
+    /// relative to the root directory, and against the name of the package
+    /// file (*ie: `7zip*` or `automatic/**`*).
+    #[structopt(long, requires = "all", number_of_values = 1)]
+    filter: Vec<PackageFilter>,
+
+    /// Only update the packages where the upstream location have changed
+    /// since the last successful update, packages are skipped when the
+    /// upstream location is unchanged even if the package files have changed.
+    /// Packages are also skipped when the version found upstream is the last
+    /// version that was found.
+    #[structopt(long, alias = "changed-only")]
+    changed: bool,
+
+    /// Check all packages, even if the check frequency of the package says it
+    /// is not due yet, or neither the package files nor the upstream location
+    /// have changed since the last successful update.
+    #[structopt(long)]
+    force: bool,
+
+    /// The directory the created packages will be placed in.
+    #[structopt(long, short, default_value = ".", parse(from_os_str))]
+    output: PathBuf,
+
+    /// The number of packages to process concurrently, use `0` to process one
+    /// package for each available processor.
+    #[structopt(long, short, env = "AER_JOBS", default_value = "1")]
+    jobs: usize,
+
+    /// The algorithm to use when generating checksums of the downloaded
+    /// files.
+    #[structopt(long, default_value = "sha256", possible_values = ChecksumType::variants_str())]
+    checksum_type: ChecksumType,
+
+    /// Write the updated package data (*ie: the new version*) back to the
+    /// package files after a successful update. The formatting and comments
+    /// of the package files are preserved where possible.
+    #[structopt(long)]
+    save: bool,
+
+    /// Additional environment variables that are passed through to the
+    /// scripts of the packages. Scripts are run in a scrubbed environment
+    /// with a temporary directory of their own, only the variables needed to
+    /// start a process are passed through by default.
+    #[structopt(long, env = "AER_ENV_PASSTHROUGH", use_delimiter = true)]
+    env_passthrough: Vec<String>,
+
+    /// Re-use the unchanged chunks of previously downloaded files when the
+    /// server supports range requests, and only download the changed chunks.
+    /// Falls back to downloading the whole file when the file changed size.
+    #[structopt(long)]
+    delta: bool,
+
+    /// Do not record the etag and last modified date of downloaded files.
+    /// By default conditional requests are sent on subsequent runs, and the
+    /// previously downloaded file is re-used when it have not changed.
+    #[structopt(long)]
+    no_download_cache: bool,
+
+    /// Push the created packages to the feed configured in the
+    /// `[metadata.chocolatey.publish]` section of the package data. The API
+    /// key is read from the environment variable configured for the feed, or
+    /// from the authentication profile of the feed in the configuration file.
+    #[structopt(long)]
+    publish: bool,
+
+    /// Update existing package directories in place (*ie: packages
+    /// maintained with `au`*), instead of generating the packages from the
+    /// package data. Only the version in the nuspec file and the urls and
+    /// checksums assigned in the scripts of the tools directory are replaced.
+    /// The package is either located directly in the directory, or in a sub
+    /// directory named after the identifier of the package.
+    #[structopt(long, parse(from_os_str))]
+    existing: Option<PathBuf>,
+
+    /// The rule set the package data is validated against before the update
+    /// process is started, packages that do not satisfy the rules of the rule
+    /// set are not updated. Use `none` to skip the validation.
+    #[structopt(
+        long,
+        env = "AER_RULES",
+        default_value = "core",
+        possible_values = RuleSet::variants_str()
+    )]
+    rules: RuleSet,
+
+    /// Exchange data with PowerShell and shell scripts using the legacy
+    /// protocol, where the changed data is written to the output of the
+    /// script between markers, instead of using input and result files.
+    #[structopt(long)]
+    legacy_script_protocol: bool,
+
+    /// Write a JSON report of the run to the specified path, containing the
+    /// old and new version, the urls and checksums, the validation results,
+    /// the errors and the time spent of every package.
+    #[structopt(long, env = "AER_REPORT", parse(from_os_str))]
+    report: Option<PathBuf>,
+}
+
+#[derive(StructOpt)]
+struct ReadinessArguments {
+    /// The files containing the package data that should be checked.
     #[structopt(required = true, parse(from_os_str))]
     package_files: Vec<PathBuf>,
 
+    /// The file containing the validation rules of the organization, which
+    /// are checked in addition to the built-in rules.
+    #[structopt(long, env = "AER_POLICY", parse(from_os_str))]
+    policy: Option<PathBuf>,
+}
+
+#[derive(StructOpt)]
+struct CheckArguments {
+    /// The package files, or directories containing package files that should
+    /// be validated.
+    #[structopt(required = true, parse(from_os_str))]
+    paths: Vec<PathBuf>,
+
+    /// The format to output the diagnostics in.
+    #[structopt(long, default_value = "text", possible_values = OutputFormat::variants_str())]
+    format: OutputFormat,
+
+    /// Keep running, and re-validate the package files whenever they change.
+    #[structopt(long)]
+    watch: bool,
+
+    /// The number of milliseconds to wait between looking for changed files
+    /// when watching.
+    #[structopt(long, default_value = "500")]
+    interval: u64,
+
+    /// The file containing the validation rules of the organization, which
+    /// are checked in addition to the built-in rules.
+    #[structopt(long, env = "AER_POLICY", parse(from_os_str))]
+    policy: Option<PathBuf>,
+
+    /// Validate the package files against the specified rule set, instead of
+    /// the full readiness checklist and the organizational policy.
+    #[structopt(long, possible_values = RuleSet::variants_str())]
+    rules: Option<RuleSet>,
+}
+
+#[derive(StructOpt)]
+struct AuditLinksArguments {
+    /// The package files, or directories containing package files where the
+    /// referenced links should be checked.
+    #[structopt(required = true, parse(from_os_str))]
+    paths: Vec<PathBuf>,
+
+    /// Update the package files with the links that have trivial fixes (*ie:
+    /// links that are also available over https*).
+    #[structopt(long)]
+    fix: bool,
+
+    /// The number of links to check concurrently, use `0` to check one link
+    /// for each available processor.
+    #[structopt(long, short, env = "AER_JOBS", default_value = "4")]
+    jobs: usize,
+}
+
+#[derive(StructOpt)]
+struct StaleArguments {
+    /// The package files, or directories containing package files that should
+    /// be checked.
+    #[structopt(required = true, parse(from_os_str))]
+    paths: Vec<PathBuf>,
+
+    /// The number of months without a new upstream version before a package
+    /// is reported as stale.
+    #[structopt(long, default_value = "6")]
+    months: u32,
+
+    /// The number of failed updates in a row before a package is reported as
+    /// stale, use `0` to not report failed updates.
+    #[structopt(long, default_value = "3")]
+    failures: u32,
+
+    /// The number of packages to check concurrently, use `0` to check one
+    /// package for each available processor.
+    #[structopt(long, short, env = "AER_JOBS", default_value = "4")]
+    jobs: usize,
+}
+
+#[derive(StructOpt)]
+struct BackfillArguments {
+    /// The file containing the package data of the new package.
+    #[structopt(parse(from_os_str))]
+    package_file: PathBuf,
+
+    /// The number of historical versions to generate packages for.
+    #[structopt(long, short, default_value = "5")]
+    count: usize,
+
+    /// The maximum number of release pages, or pages of the releases of the
+    /// repository, to walk through when looking for historical versions.
+    #[structopt(long, default_value = "10")]
+    max_pages: usize,
+
+    /// The directory the created packages will be placed in, the package of
+    /// each version is placed in a directory named after the version.
+    #[structopt(long, short, default_value = ".", parse(from_os_str))]
+    output: PathBuf,
+
+    /// The algorithm to use when generating checksums of the downloaded
+    /// files.
+    #[structopt(long, default_value = "sha256", possible_values = ChecksumType::variants_str())]
+    checksum_type: ChecksumType,
+}
+
+#[derive(StructOpt)]
+struct SimulateArguments {
+    /// The file containing the package data that should be simulated.
+    #[structopt(parse(from_os_str))]
+    package_file: PathBuf,
+
+    /// The directory containing the recorded responses and the expected
+    /// outcome of the update.
+    #[structopt(long, parse(from_os_str))]
+    fixtures: PathBuf,
+
+    /// Record new fixtures and the expected outcome by contacting the remote
+    /// servers, instead of replaying existing fixtures.
+    #[structopt(long)]
+    record: bool,
+}
+
+#[derive(StructOpt)]
+struct ServeArguments {
+    /// The path of the unix socket to listen for requests on, use `-` to read
+    /// requests from the standard input and write responses to the standard
+    /// output.
+    #[structopt(long, parse(from_os_str))]
+    ipc: PathBuf,
+}
+
+#[derive(StructOpt)]
+struct NewArguments {
+    /// The identifier of the new package, the remaining values are prompted
+    /// for when no identifier have been specified.
+    #[structopt(long)]
+    id: Option<String>,
+
+    /// The maintainers of the new package [default: the maintainers in the
+    /// configuration file, or the current user].
+    #[structopt(long = "maintainer")]
+    maintainers: Vec<String>,
+
+    /// The package managers the new package is created for [default:
+    /// chocolatey].
+    #[structopt(long = "target", possible_values = Target::variants_str())]
+    targets: Vec<Target>,
+
+    /// The homepage of the software, repositories on GitHub are probed for
+    /// the description, license and latest release of the software.
+    #[structopt(long)]
+    url: Option<Url>,
+
+    /// Only use the project url as is, without probing the project.
+    #[structopt(long)]
+    no_probe: bool,
+
+    /// Create the script templates of the package (*ie:
+    /// `tools/chocolateyInstall.ps1`*).
+    #[structopt(long)]
+    templates: bool,
+
+    /// The directory the directory of the new package is created in.
+    #[structopt(long, short, default_value = ".", parse(from_os_str))]
+    output: PathBuf,
+
+    /// Overwrite the package files when they already exist.
+    #[structopt(long)]
+    force: bool,
+}
+
+#[derive(StructOpt)]
+struct ImportArguments {
+    /// The existing package to import, either the package directory, the
+    /// nuspec file or the packed package (*ie: `app.1.0.0.nupkg`*).
+    #[structopt(parse(from_os_str))]
+    source: PathBuf,
+
+    /// The directory the directory of the imported package is created in.
+    #[structopt(long, short, default_value = ".", parse(from_os_str))]
+    output: PathBuf,
+
+    /// Overwrite the package files when they already exist.
+    #[structopt(long)]
+    force: bool,
+}
+
+#[derive(StructOpt)]
+struct ExplainArguments {
+    /// The code to explain (*ie: `AER0203`*).
+    code: String,
+}
+
+#[derive(StructOpt)]
+enum Commands {
+    /// Runs the update process for the specified packages.
+    Update(UpdateArguments),
+    /// Outputs a checklist of the requirements and guidelines used during
+    /// moderation on the Chocolatey Community Repository, together with a
+    /// readiness score for each package.
+    Readiness(ReadinessArguments),
+    /// Validates package files and outputs the problems found together with
+    /// the line and column they were found on. Use `--format json --watch` to
+    /// continuously report diagnostics to editor plugins.
+    Check(CheckArguments),
+    /// Checks every link referenced in the package files, and reports dead
+    /// links, links redirecting to new domains and links that are available
+    /// over https. Use `--fix` to update the trivial cases.
+    AuditLinks(AuditLinksArguments),
+    /// Lists the packages where the upstream version have not changed in a
+    /// long time, where the url used to find new versions no longer exists,
+    /// or where the last updates failed.
+    Stale(StaleArguments),
+    /// Finds the last historical versions of a new package by walking the
+    /// upstream release listing, and processes them in order from the oldest
+    /// to the newest version.
+    Backfill(BackfillArguments),
+    /// Runs the update of a package against recorded responses, and verifies
+    /// that the expected version and links are found. Use `--record` to
+    /// create or refresh the fixtures.
+    Simulate(SimulateArguments),
+    /// Allows testing different web related tasks. The currently supported
+    /// tasks included the ability to parse HTML websites, and downloading
+    /// binary files.
+    Web(WebCommands),
+    /// Parses version strings and outputs the converted values to the version
+    /// that will be used by the supported package managers. Additionally
+    /// shows the equivalent Semantic Version (as per Rust specifications) when
+    /// possible.
+    Ver(VerArguments),
+    /// Generates the checksums of a file, or of every file in a directory, and
+    /// outputs them in the format used by `SHA256SUMS` files. Use `--compare`
+    /// to verify the checksum of a file against an expected checksum.
+    Checksum(ChecksumArguments),
+    /// Starts a JSON-RPC server exposing the validate, check-version, update
+    /// and download operations, allowing graphical front-ends and editors to
+    /// integrate with the application.
+    Serve(ServeArguments),
+    /// Exports or imports the state kept between runs (*last checked times,
+    /// versions and etags*), allowing the state to be persisted between CI
+    /// pipelines through artifact caching.
+    State(StateCommands),
+    /// Outputs the state recorded for a single package, including the last
+    /// time it was checked, the last version found upstream and the last
+    /// version pushed to a feed.
+    History(HistoryArguments),
+    /// Reads or changes the settings in the configuration file, which is used
+    /// for the defaults of the options (*ie: the work directory, the rule set
+    /// and the maintainers of new packages*).
+    Config(ConfigCommands),
+    /// Creates a starter package data file for a new package from the
+    /// specified values, or from values prompted for when no identifier have
+    /// been specified.
+    New(NewArguments),
+    /// Imports an existing Chocolatey package into a package data file, the
+    /// urls and checksums in the scripts of the package are replaced with the
+    /// tokens of script templates.
+    Import(ImportArguments),
+    /// Outputs the description of an error or warning code (*ie: `AER0203`*),
+    /// together with the validation rule reporting the code.
+    Explain(ExplainArguments),
+    /// Outputs information about the current installation, including the
+    /// optional features that was enabled when the application was built.
+    Doctor,
+}
+
+#[derive(StructOpt)]
+#[structopt(author = env!("CARGO_PKG_AUTHORS"))]
+struct Arguments {
+    #[structopt(subcommand)]
+    cmd: Commands,
+
     #[structopt(flatten)]
     log: LogData,
+
+    #[structopt(flatten)]
+    context: Context,
+
+    /// Disable the usage of colors when outputting text to the console.
+    #[structopt(long, global = true)]
+    no_color: bool,
 }
 
 fn main() {
     #[cfg(feature = "human")]
     setup_panic!();
-    if cfg!(windows) && !Paint::enable_windows_ascii() {
-        Paint::disable();
+
+    if let Some(path) = aer::config::locate(std::env::args_os().skip(1)) {
+        // Errors are reported when the context is prepared
+        let _ = aer::config::apply_defaults(&path);
     }
 
-    let args = Arguments::from_args();
+    let mut args = Arguments::from_args();
+    logging::configure_colors(args.no_color);
+    args.context
+        .prepare()
+        .expect("Unable to prepare the context of the application!");
+    args.log.path = args.context.data_root().resolve_log_path(&args.log.path);
     logging::setup_logging(&args.log).expect("Unable to configure logging of the application!");
 
-    // TODO: #11 Run updating on several threads
+    match args.cmd {
+        Commands::Update(cmd_args) => update_cmd(cmd_args, &args.context),
+        Commands::Readiness(args) => readiness_cmd(args),
+        Commands::Check(args) => check_cmd(args),
+        Commands::AuditLinks(cmd_args) => audit_links_cmd(cmd_args, &args.context),
+        Commands::Stale(cmd_args) => stale_cmd(cmd_args, &args.context),
+        Commands::Backfill(cmd_args) => {
+            if let Err(err) = backfill_cmd(cmd_args, &args.context) {
+                error!("An error occurred during back-fill process: '{}'", err);
+                std::process::exit(1);
+            }
+        }
+        Commands::Simulate(cmd_args) => match simulate_cmd(cmd_args, &args.context) {
+            Ok(true) => {}
+            Ok(false) => std::process::exit(1),
+            Err(err) => {
+                error!("An error occurred during simulation: '{}'", err);
+                std::process::exit(1);
+            }
+        },
+        Commands::Web(cmd) => web::run(cmd, &args.context),
+        Commands::Ver(cmd_args) => ver::run(cmd_args, &args.context),
+        Commands::Checksum(cmd_args) => checksum_cmd::run(cmd_args),
+        Commands::State(cmd) => state_cmd::run(cmd, &args.context),
+        Commands::History(cmd_args) => history_cmd::run(cmd_args, &args.context),
+        Commands::Config(cmd) => config_cmd::run(cmd, &args.context),
+        Commands::New(cmd_args) => {
+            if let Err(err) = new_cmd(cmd_args, &args.context) {
+                error!("An error occurred while creating the package: '{}'", err);
+                std::process::exit(1);
+            }
+        }
+        Commands::Import(cmd_args) => {
+            let result = ImportedPackage::from_path(&cmd_args.source)
+                .map_err(|err| err.into())
+                .and_then(|imported| imported.write(&cmd_args.output, cmd_args.force));
+            if let Err(err) = result {
+                error!(
+                    "An error occurred while importing the package: '{}'",
+                    codes::describe(err.as_ref())
+                );
+                std::process::exit(1);
+            }
+        }
+        Commands::Explain(cmd_args) => explain_cmd(cmd_args),
+        Commands::Doctor => doctor_cmd(&args.context),
+        Commands::Serve(cmd_args) => {
+            let mut server = serve::Server::new(args.context.clone());
+            if let Err(err) = serve::serve(&mut server, &cmd_args.ipc) {
+                error!("An error occurred while serving requests: '{}'", err);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+fn new_cmd(args: NewArguments, context: &Context) -> Result<(), Box<dyn std::error::Error>> {
+    let NewArguments {
+        id,
+        maintainers,
+        targets,
+        mut url,
+        no_probe,
+        templates,
+        output,
+        force,
+    } = args;
+
+    let mut scaffold = match id {
+        Some(id) => Scaffold::new(&id),
+        None => {
+            let stdin = std::io::stdin();
+            let mut input = stdin.lock();
+            let mut output = std::io::stdout();
+            let id = scaffold::prompt(&mut input, &mut output, "Package identifier", "")?;
+            if id.is_empty() {
+                return Err("An identifier of the package is required!".into());
+            }
+            let mut scaffold = Scaffold::new(&id);
+            if url.is_none() {
+                let answer = scaffold::prompt(&mut input, &mut output, "Project url", "")?;
+                if !answer.is_empty() {
+                    url = Some(Url::parse(&answer)?);
+                }
+            }
+            if maintainers.is_empty() {
+                let answer = scaffold::prompt(
+                    &mut input,
+                    &mut output,
+                    "Maintainers",
+                    &scaffold.maintainers.join(","),
+                )?;
+                scaffold.maintainers = split_answer(&answer);
+            }
+            if targets.is_empty() {
+                let answer = scaffold::prompt(&mut input, &mut output, "Targets", "chocolatey")?;
+                scaffold.targets = split_answer(&answer)
+                    .iter()
+                    .map(|target| target.parse())
+                    .collect::<Result<_, _>>()?;
+            }
+            scaffold
+        }
+    };
+
+    if !maintainers.is_empty() {
+        scaffold.maintainers = maintainers;
+    }
+    if !targets.is_empty() {
+        scaffold.targets = targets;
+    }
+    scaffold.templates = templates;
+    if let Some(url) = url {
+        if no_probe {
+            scaffold.project_url = Some(url);
+        } else if let Err(err) = scaffold.probe(&context.request(), &url) {
+            warn!("Unable to probe '{}', continuing without: '{}'", url, err);
+        }
+    }
+
+    scaffold.create(&output, force)?;
+
+    Ok(())
+}
+
+fn split_answer(answer: &str) -> Vec<String> {
+    answer
+        .split(',')
+        .map(|value| value.trim().to_owned())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+fn update_cmd(args: UpdateArguments, context: &Context) {
+    let clock = context.clock().clone();
+    let virustotal = context.virustotal();
+    let mut update_context = context.update_context();
+    let state = Arc::new(Mutex::new(
+        CheckState::load(&context.data_root().state_dir())
+            .expect("Unable to load the state of previous runs!"),
+    ));
+    let UpdateArguments {
+        package_files,
+        all,
+        search_root,
+        filter,
+        changed,
+        force,
+        output,
+        checksum_type,
+        jobs,
+        save,
+        env_passthrough,
+        delta,
+        no_download_cache,
+        publish,
+        existing,
+        rules,
+        legacy_script_protocol,
+        report,
+    } = args;
+
+    if legacy_script_protocol {
+        update_context = update_context
+            .with_environment(ScriptEnvironment::inherit().with_protocol(RunnerProtocol::Markers));
+    }
+
+    let package_files = if all {
+        let root = search_root.unwrap_or_else(|| PathBuf::from("."));
+        let package_files = discovery::discover(&root, &filter);
+        if package_files.is_empty() {
+            warn!("No package files were found in '{}'!", root.display());
+        } else {
+            info!(
+                "Found {} package files in '{}'",
+                package_files.len(),
+                root.display()
+            );
+        }
+        package_files
+    } else {
+        package_files
+    };
+
+    let started = clock.now();
+    let timer = Instant::now();
+    let reports = Arc::new(Mutex::new(HashMap::new()));
+    let package_reports = Arc::clone(&reports);
+    let summary = executor::run_jobs(package_files, jobs, move |file| {
+        let name = file.display().to_string();
+        let mut data = match parsers::read_file_in(&update_context, &file) {
+            Ok(data) => data,
+            Err(err) => {
+                error!(
+                    "[{}] An error occurred during update process: '{}'",
+                    name,
+                    codes::describe(&err)
+                );
+                return JobResult::new(name, JobStatus::Failed(codes::describe(&err)));
+            }
+        };
+        let id = data.metadata().id().to_owned();
+        let package_context = update_context.for_package(&id);
+        let logger = package_context.logger();
+        let now = clock.now();
+        let mut report = PackageReport::new(&id, &file);
+        report.old_version = Some(data.metadata().chocolatey().version.to_string());
+        package_reports
+            .lock()
+            .unwrap()
+            .insert(id.clone(), report.clone());
+
+        if !force {
+            let last_checked = state.lock().unwrap().last_checked(&id);
+            match schedule::is_due(&id, &data.updater().check_frequency, last_checked, now) {
+                Ok(false) => {
+                    logger.info("Skipping package, it is not due to be checked yet!");
+                    return JobResult::new(id, JobStatus::Skipped);
+                }
+                Err(err) => {
+                    logger.error(format_args!("Invalid check frequency: '{}'", err));
+                    return JobResult::new(id, JobStatus::Failed(err.to_string()));
+                }
+                Ok(true) => {}
+            }
+        }
+
+        let hash = fingerprint::package_hash(&file, &data).ok();
+        let etag = fingerprint::upstream_url(&data)
+            .and_then(|url| package_context.request().check_link(url.as_str()).ok())
+            .and_then(|check| check.etag);
+        if changed {
+            let history = state.lock().unwrap().history(&id);
+            if !fingerprint::is_upstream_changed(&history, etag.as_deref()) {
+                logger.info("Skipping package, the upstream location have not changed!");
+                return JobResult::new(id, JobStatus::Unchanged);
+            }
+        }
+        if !force {
+            let mut state = state.lock().unwrap();
+            if let Some(ref hash) = hash {
+                if fingerprint::is_unchanged(&state.history(&id), hash, etag.as_deref()) {
+                    logger.info(
+                        "Skipping package, neither the package files nor the upstream location \
+                         have changed!",
+                    );
+                    state.record(&id, now);
+                    if let Err(err) = state.save() {
+                        logger.warn(format_args!(
+                            "Unable to save the state of the update process: '{}'",
+                            err
+                        ));
+                    }
+                    return JobResult::new(id, JobStatus::Unchanged);
+                }
+            }
+        }
+
+        let options = BuildOptions {
+            output_dir: output.clone(),
+            base_dir: file.parent().map(PathBuf::from).unwrap_or_default(),
+            checksum_type,
+            delta,
+            download_cache: !no_download_cache,
+            existing_dir: existing.clone(),
+            virustotal: virustotal.clone(),
+        };
+
+        let package_context = match package_context.isolated(&env_passthrough) {
+            Ok(package_context) => {
+                let request = update::with_auth_rules(package_context.request().clone(), &data);
+                package_context.with_request(request)
+            }
+            Err(err) => {
+                logger.error(format_args!(
+                    "Unable to create the work directory of the package: '{}'",
+                    err
+                ));
+                return JobResult::new(id, JobStatus::Failed(err.to_string()));
+            }
+        };
+        let known_version = if changed {
+            state.lock().unwrap().history(&id).version
+        } else {
+            None
+        };
+        let result = run_update(
+            &package_context,
+            &mut data,
+            &options,
+            rules,
+            publish,
+            known_version.as_deref(),
+            &mut report,
+        );
+        let pushed = report.pushed.is_some();
+        package_reports.lock().unwrap().insert(id.clone(), report);
+        let mut state = state.lock().unwrap();
+        let status = match result {
+            Err(err) => {
+                logger.error(format_args!(
+                    "An error occurred during update process: '{}'",
+                    codes::describe(err.as_ref())
+                ));
+                state.record_failure(&id);
+                JobStatus::Failed(codes::describe(err.as_ref()))
+            }
+            Ok(UpdateOutcome::Unchanged) => {
+                state.record(&id, now);
+                state.record_fingerprint(&id, hash.clone(), etag.clone());
+                JobStatus::Unchanged
+            }
+            Ok(UpdateOutcome::Created(version)) => {
+                state.record(&id, now);
+                state.record_success(&id, version.as_deref(), now);
+                if pushed {
+                    state.record_push(&id, version.as_deref(), now);
+                }
+                let mut hash = hash.clone();
+                if save {
+                    if let Err(err) = parsers::write_file(&data, &file) {
+                        logger.warn(format_args!(
+                            "Unable to save the package data to '{}': '{}'",
+                            file.display(),
+                            err
+                        ));
+                    }
+                    hash = fingerprint::package_hash(&file, &data).ok();
+                }
+                state.record_fingerprint(&id, hash, etag.clone());
+                JobStatus::Succeeded
+            }
+        };
+        if let Err(err) = state.save() {
+            logger.warn(format_args!(
+                "Unable to save the state of the update process: '{}'",
+                err
+            ));
+        }
+
+        JobResult::new(id, status)
+    });
+
+    let reports = std::mem::take(&mut *reports.lock().unwrap());
+    let run_report = RunReport::new(started, timer.elapsed(), &summary, reports);
+    for line in run_report.summary_table() {
+        info!("{}", line);
+    }
+    log_summary(&summary);
+    context
+        .notifier()
+        .notify(&context.request(), context.clock(), &run_report);
+    if let Some(report) = report {
+        match run_report.write(&report) {
+            Ok(()) => info!("Wrote the report of the run to '{}'", report.display()),
+            Err(err) => error!(
+                "Unable to write the report of the run to '{}': '{}'",
+                report.display(),
+                err
+            ),
+        }
+    }
+    if summary.exit_code() != 0 {
+        std::process::exit(summary.exit_code());
+    }
+}
+
+fn log_summary(summary: &Summary) {
+    info!(
+        "{} succeeded, {} unchanged, {} skipped, {} failed",
+        Paint::green(summary.succeeded().len()),
+        Paint::cyan(summary.unchanged().len()),
+        Paint::cyan(summary.skipped().len()),
+        Paint::red(summary.failed().len())
+    );
+
+    for result in summary.results() {
+        if let JobStatus::Failed(ref message) = result.status {
+            error!("  {}: {}", result.name, message);
+        }
+    }
+}
+
+fn readiness_cmd(args: ReadinessArguments) {
+    let policy = load_policy(args.policy);
+    let mut all_ready = true;
+
     for file in args.package_files {
-        match run_update(&file) {
-            Err(err) => error!("An error occurred during update process: '{}'", err),
-            _ => {
-                todo!()
+        let data = match parsers::read_file(&file) {
+            Ok(data) => data,
+            Err(err) => {
+                error!(
+                    "Unable to load package data from '{}': '{}'",
+                    file.display(),
+                    err
+                );
+                all_ready = false;
+                continue;
             }
+        };
+
+        let report = readiness::check_readiness_with_policy(&data, &policy);
+        info!(
+            "Moderation readiness for '{}'",
+            Paint::cyan(data.metadata().id())
+        );
+
+        for check in report.checks() {
+            let status = match check.status {
+                CheckStatus::Passed => Paint::green(check.status),
+                CheckStatus::Warning => Paint::yellow(check.status),
+                CheckStatus::Failed => Paint::red(check.status),
+                CheckStatus::Allowed => Paint::cyan(check.status),
+            };
+            if check.message.is_empty() {
+                info!("  [{}] {}", status, check.name);
+            } else {
+                info!("  [{}] {}: {}", status, check.name, check.message);
+            }
+        }
+
+        info!("Readiness score: {}%", Paint::cyan(report.score()));
+        all_ready &= report.is_ready();
+    }
+
+    if !all_ready {
+        std::process::exit(1);
+    }
+}
+
+fn check_cmd(args: CheckArguments) {
+    let policy = load_policy(args.policy);
+    let mut watcher = FileWatcher::new(&args.paths);
+    let mut has_errors = false;
+
+    loop {
+        for file in watcher.changed_files() {
+            let diagnostics = match args.rules {
+                Some(rules) => check::check_file_with_rules(&file, rules),
+                None => check::check_file(&file, &policy),
+            };
+            has_errors |= diagnostics.iter().any(|d| d.severity == Severity::Error);
+            output_diagnostics(&file, &diagnostics, args.format);
+        }
+
+        if !args.watch {
+            break;
+        }
+
+        std::thread::sleep(Duration::from_millis(args.interval));
+    }
+
+    if has_errors {
+        std::process::exit(1);
+    }
+}
+
+fn load_policy(path: Option<PathBuf>) -> Policy {
+    let path = match path {
+        Some(path) => path,
+        None => return Policy::default(),
+    };
+
+    match Policy::load(&path) {
+        Ok(policy) => policy,
+        Err(err) => {
+            error!(
+                "Unable to load the policy file '{}': {}",
+                path.display(),
+                codes::describe(&err)
+            );
+            std::process::exit(1);
         }
     }
 }
 
-fn run_update(package_file: &Path) -> Result<(), Box<dyn std::error::Error>> {
-    info!("Loading package data from '{}'", "yo");
+fn explain_cmd(args: ExplainArguments) {
+    match codes::lookup(&args.code) {
+        Some(info) => {
+            info!("{} ({}): {}", info.code, info.severity, info.description);
+            if let Some(rule) = info.rule {
+                info!("Reported by the validation rule '{}'", rule);
+            }
+        }
+        None => {
+            error!("The code '{}' is not a known code!", args.code);
+            std::process::exit(1);
+        }
+    }
+}
 
-    let data = parsers::read_file(&package_file)?;
+fn doctor_cmd(context: &Context) {
+    info!("aer {}", env!("CARGO_PKG_VERSION"));
     info!(
-        "Successfully loaded package data with identifier '{}'!",
-        data.metadata().id()
+        "Cache directory: {}",
+        context.data_root().cache_dir().display()
     );
+    info!(
+        "State directory: {}",
+        context.data_root().state_dir().display()
+    );
+    info!("Features:");
+    for feature in features::FEATURES {
+        let status = if feature.enabled {
+            Paint::green("enabled")
+        } else {
+            Paint::red("disabled")
+        };
+        info!(
+            "  {:<12} {:<8} {}",
+            feature.name, status, feature.description
+        );
+    }
 
-    // TODO: #12 Validate data according to specified rule set, default would be
-    // Core
+    if features::FEATURES.iter().any(|feature| !feature.enabled) {
+        info!("Disabled features are enabled by building the application with `--features <name>`");
+    }
+}
 
-    // TODO: #13 Run any global before hooks
+fn output_diagnostics(
+    file: &std::path::Path,
+    diagnostics: &[check::Diagnostic],
+    format: OutputFormat,
+) {
+    match format {
+        OutputFormat::Json => println!("{}", check::file_report(file, diagnostics)),
+        OutputFormat::Text => {
+            for diagnostic in diagnostics {
+                let severity = match diagnostic.severity {
+                    Severity::Error => Paint::red(diagnostic.severity),
+                    Severity::Warning => Paint::yellow(diagnostic.severity),
+                    Severity::Allowed => Paint::cyan(diagnostic.severity),
+                };
+                info!(
+                    "{}:{}:{}: {}[{}]: {} ({})",
+                    file.display(),
+                    diagnostic.line,
+                    diagnostic.column,
+                    severity,
+                    diagnostic.code,
+                    diagnostic.message,
+                    diagnostic.rule
+                );
+            }
+        }
+    }
+}
 
-    let request = WebRequest::create();
-
-    if data.updater().has_chocolatey() {
-        let choco = data.updater().chocolatey();
-        let (_, urls) = match &choco.parse_url {
-            Some(chocolatey::ChocolateyParseUrl::Url(url)) => {
-                request.get_html_response(url.as_str())?.read(None)?
-            }
-            Some(chocolatey::ChocolateyParseUrl::UrlWithRegex { url, ref regex }) => {
-                info!("Parsing links on '{}' using regex '{}'", url, regex);
-                let (parent, urls) = request.get_html_response(url.as_str())?.read(Some(regex))?;
-                if !urls.is_empty() {
-                    info!("{} links found, using first one to get links!", urls.len());
-                    let url = urls.get(0).unwrap();
-                    info!("Parsing links on '{}'", url.link);
-                    request.get_html_response(url.link.as_str())?.read(None)?
-                } else {
-                    (parent, urls)
-                }
+fn audit_links_cmd(args: AuditLinksArguments, context: &Context) {
+    let mut has_errors = false;
+    let mut references = vec![];
+
+    for file in check::find_package_files(&args.paths) {
+        match parsers::read_file(&file) {
+            Ok(data) => references.extend(
+                audit::find_links(&data)
+                    .into_iter()
+                    .map(|(key, url)| (file.clone(), key, url)),
+            ),
+            Err(err) => {
+                error!(
+                    "Unable to load package data from '{}': '{}'",
+                    file.display(),
+                    err
+                );
+                has_errors = true;
+            }
+        }
+    }
+
+    let mut urls: Vec<Url> = references.iter().map(|(_, _, url)| url.clone()).collect();
+    urls.sort();
+    urls.dedup();
+
+    let statuses = Arc::new(Mutex::new(HashMap::new()));
+    let request = context.request();
+    let results = Arc::clone(&statuses);
+    executor::run_jobs(urls, args.jobs, move |url| {
+        let status = audit::audit_link(&request, &url);
+        let job_status = match status {
+            LinkStatus::Dead(ref reason) => JobStatus::Failed(reason.clone()),
+            _ => JobStatus::Succeeded,
+        };
+        results.lock().unwrap().insert(url.clone(), status);
+        JobResult::new(url, job_status)
+    });
+
+    let statuses = statuses.lock().unwrap();
+    let mut fixes: BTreeMap<PathBuf, Vec<(Url, Url)>> = BTreeMap::new();
+    let (mut dead, mut moved, mut upgradable) = (0, 0, 0);
+
+    for (file, key, url) in &references {
+        let status = match statuses.get(url) {
+            Some(LinkStatus::Healthy) | None => continue,
+            Some(status) => status,
+        };
+        let label = match status {
+            LinkStatus::Dead(_) => {
+                dead += 1;
+                Paint::red("dead")
+            }
+            LinkStatus::Moved(_) => {
+                moved += 1;
+                Paint::yellow("moved")
             }
             _ => {
-                warn!("No url have been specified to parse!");
-                std::process::exit(5);
+                upgradable += 1;
+                Paint::cyan("https")
             }
         };
+        warn!(
+            "{}: {}[{}]: '{}' is {}",
+            file.display(),
+            label,
+            key,
+            url,
+            status
+        );
 
-        let mut aarch32 = None;
-        let mut aarch64 = None;
-        let mut others = vec![];
-
-        for (key, regex) in choco.regexes() {
-            trace!("Filtering {} urls using {}", key, regex);
-            let re = Regex::new(&regex)?;
-            let mut items = urls.iter().filter_map(|link| {
-                let capture = re.captures(link.link.as_str())?;
-                let mut new_link = link.clone();
-
-                if let Ok(version) =
-                    Versions::parse(capture.name("version").map(|v| v.as_str()).unwrap_or(""))
-                {
-                    new_link.version = Some(version);
-                }
+        if let Some(fixed) = status.fixed_url() {
+            fixes
+                .entry(file.clone())
+                .or_default()
+                .push((url.clone(), fixed.clone()));
+        }
+    }
 
-                Some(new_link)
-            });
-            info!("Parsing urls matching '{}' for {}", regex, key);
+    info!(
+        "{} links checked, {} dead, {} moved, {} available over https",
+        Paint::cyan(references.len()),
+        Paint::red(dead),
+        Paint::yellow(moved),
+        Paint::cyan(upgradable)
+    );
 
-            if key.to_lowercase() == "arch32" {
-                info!("Taking first match if found!!");
-                aarch32 = items.next();
-            } else if key.to_lowercase() == "arch64" {
-                info!("Taking first match if found!!");
-                aarch64 = items.next();
-            } else {
-                for link in items {
-                    others.push(link);
+    if args.fix {
+        for (file, fixes) in fixes {
+            let result = std::fs::read_to_string(&file)
+                .and_then(|text| std::fs::write(&file, audit::fix_links(&text, &fixes)));
+            match result {
+                Ok(()) => info!(
+                    "Fixed {} links in '{}'",
+                    Paint::green(fixes.len()),
+                    file.display()
+                ),
+                Err(err) => {
+                    error!("Unable to fix links in '{}': '{}'", file.display(), err);
+                    has_errors = true;
                 }
             }
-            if let Some(ref aarch32) = aarch32 {
-                info!("Arch 32: {}", aarch32.link);
-            } else {
-                info!("Arch 32: None")
-            }
-            if let Some(ref aarch64) = aarch64 {
-                info!("Arch 64: {}", aarch64.link);
+        }
+    }
+
+    if has_errors || dead > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn stale_cmd(args: StaleArguments, context: &Context) {
+    let state = Arc::new(
+        CheckState::load(&context.data_root().state_dir())
+            .expect("Unable to load the state of previous runs!"),
+    );
+    let options = StaleOptions {
+        months: args.months,
+        failures: args.failures,
+    };
+    let now = context.clock().now();
+    let request = context.request();
+
+    let summary = executor::run_jobs(
+        check::find_package_files(&args.paths),
+        args.jobs,
+        move |file| {
+            let data = match parsers::read_file(&file) {
+                Ok(data) => data,
+                Err(err) => {
+                    return JobResult::new(file.display(), JobStatus::Failed(err.to_string()));
+                }
+            };
+            let id = data.metadata().id();
+            let status = stale::parse_url(&data)
+                .and_then(|url| request.check_link(url.as_str()).ok())
+                .map(|check| check.status);
+
+            let reasons = stale::stale_reasons(&state.history(id), status, now, &options);
+            if reasons.is_empty() {
+                JobResult::new(id, JobStatus::Succeeded)
             } else {
-                info!("Arch 64: None");
-            }
-            {
-                let others: Vec<&str> = others.iter().map(|o| o.link.as_str()).collect();
-                info!("Others: {:?}", others);
+                for reason in &reasons {
+                    warn!("{}: {}", Paint::cyan(id), reason);
+                }
+                // Stale packages are counted as skipped, as the check itself
+                // succeeded
+                JobResult::new(id, JobStatus::Skipped)
             }
+        },
+    );
+
+    for result in summary.results() {
+        if let JobStatus::Failed(ref message) = result.status {
+            error!("Unable to check '{}': '{}'", result.name, message);
+        }
+    }
+    info!(
+        "{} of {} packages are stale",
+        Paint::yellow(summary.skipped().len()),
+        Paint::cyan(summary.results().len())
+    );
+
+    if summary.exit_code() != 0 || !summary.skipped().is_empty() {
+        std::process::exit(1);
+    }
+}
+
+fn backfill_cmd(
+    args: BackfillArguments,
+    context: &Context,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let update_context = context.update_context();
+    let data = parsers::read_file_in(&update_context, &args.package_file)?;
+    let update_context = update_context.for_package(data.metadata().id());
+    let request = update::with_auth_rules(update_context.request().clone(), &data);
+    let update_context = update_context.with_request(request);
+
+    let links = backfill::find_historical_links(update_context.request(), &data, args.max_pages)?;
+    let links = backfill::select_versions(links, args.count);
+
+    if links.is_empty() {
+        warn!(
+            "No historical versions of '{}' was found!",
+            data.metadata().id()
+        );
+        return Ok(());
+    }
+
+    info!(
+        "Found {} historical versions of '{}'",
+        Paint::cyan(links.len()),
+        data.metadata().id()
+    );
+    for found in &links {
+        log_found_links(found);
+    }
+
+    let options = BuildOptions {
+        output_dir: args.output.clone(),
+        base_dir: args
+            .package_file
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_default(),
+        checksum_type: args.checksum_type,
+        virustotal: context.virustotal(),
+        ..BuildOptions::default()
+    };
+    let load_data = || -> Result<PackageData, Box<dyn std::error::Error>> {
+        Ok(parsers::read_file_in(&update_context, &args.package_file)?)
+    };
+    for output in backfill::build_versions(&update_context, load_data, links, &options)? {
+        info!(
+            "Created package '{}' in '{}'",
+            Paint::cyan(data.metadata().id()),
+            output.package_dir.display()
+        );
+        info!("Packed package to '{}'", output.package_file.display());
+    }
+
+    Ok(())
+}
+
+fn simulate_cmd(
+    args: SimulateArguments,
+    context: &Context,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let data = parsers::read_file(&args.package_file)?;
+    let mode = if args.record {
+        FixtureMode::Record
+    } else {
+        FixtureMode::Replay
+    };
+    let request = context
+        .request()
+        .with_fixtures(Fixtures::new(&args.fixtures, mode));
+    let update_context = UpdateContext::new()
+        .with_request(request)
+        .for_package(data.metadata().id());
+
+    let found = update::find_links(&update_context, &data)?;
+    log_found_links(&found);
+    let actual = simulate::outcome_of(&found);
+
+    if args.record {
+        simulate::save_expected(&args.fixtures, &actual)?;
+        info!(
+            "Recorded fixtures and expected outcome to '{}'",
+            args.fixtures.display()
+        );
+        return Ok(true);
+    }
+
+    let expected = simulate::load_expected(&args.fixtures)?;
+    let mismatches = simulate::compare(&expected, &actual);
+
+    if mismatches.is_empty() {
+        info!(
+            "Simulation of '{}' {}!",
+            data.metadata().id(),
+            Paint::green("passed")
+        );
+        Ok(true)
+    } else {
+        for mismatch in mismatches {
+            error!("{}", mismatch);
+        }
+        error!(
+            "Simulation of '{}' {}!",
+            data.metadata().id(),
+            Paint::red("failed")
+        );
+        Ok(false)
+    }
+}
+
+/// The outcome of a successful update of a single package.
+enum UpdateOutcome {
+    /// The package was created using the specified version.
+    Created(Option<String>),
+    /// The package was not created, as the version found upstream is the last
+    /// version that was found.
+    Unchanged,
+}
+
+fn run_update(
+    context: &UpdateContext,
+    data: &mut PackageData,
+    options: &BuildOptions,
+    rules: RuleSet,
+    publish: bool,
+    known_version: Option<&str>,
+    report: &mut PackageReport,
+) -> Result<UpdateOutcome, Box<dyn std::error::Error>> {
+    context.logger().info("Successfully loaded package data!");
+
+    validate_data(context, data, rules, report)?;
+
+    // TODO: #13 Run any global before hooks
+
+    let links = update::find_all_links(context, data)?;
+    for (target, found) in links.iter() {
+        info!("Links found for the {} target:", target);
+        log_found_links(found);
+    }
+    let found = links.primary().cloned().unwrap_or_default();
+    report.record_links(&found);
+    if let (Some(known_version), Some(version)) = (known_version, found.version()) {
+        if version.to_string() == known_version {
+            context.logger().info(format_args!(
+                "Skipping package, the version {} have already been found!",
+                version
+            ));
+            return Ok(UpdateOutcome::Unchanged);
+        }
+    }
+
+    let output = build::build_package(context, data, &links, options)?;
+    report.record_files(&output.files);
+    info!(
+        "Created package '{}' in '{}'",
+        Paint::cyan(data.metadata().id()),
+        output.package_dir.display()
+    );
+    info!("Packed package to '{}'", output.package_file.display());
+    if let Some(ref manifest_dir) = output.manifest_dir {
+        info!("Created winget manifests in '{}'", manifest_dir.display());
+    }
+    if let Some(ref nuget_file) = output.nuget_file {
+        info!("Packed NuGet package to '{}'", nuget_file.display());
+    }
+
+    if publish {
+        if let Some(feed) = publish::publish_package(context, data, &output.package_file)? {
+            info!(
+                "Published package '{}' to '{}'",
+                Paint::cyan(data.metadata().id()),
+                feed
+            );
+            report.pushed = Some(feed.to_string());
         }
+    }
+
+    report.record_resolved(&output.resolved);
+
+    Ok(UpdateOutcome::Created(report.new_version.clone()))
+}
 
-        // TODO: #14 Download architecture files
+/// Validates the package data against the rule set, and returns an error when
+/// any of the rules reported as an error are not satisfied.
+fn validate_data(
+    context: &UpdateContext,
+    data: &PackageData,
+    rules: RuleSet,
+    package_report: &mut PackageReport,
+) -> Result<(), codes::CodedError> {
+    let report = validation::validate(data, rules);
+    let logger = context.logger();
+    package_report.validation = report.results().to_vec();
+
+    for result in report.results() {
+        let message = format!("{}[{}]: {}", result.rule, result.code, result.message);
+        match result.severity {
+            Severity::Error => logger.error(message),
+            Severity::Warning => logger.warn(message),
+            Severity::Allowed => logger.info(message),
+        }
+    }
+
+    if report.has_errors() {
+        return Err(codes::CodedError::new(
+            codes::VALIDATION_FAILED,
+            format!(
+                "The package data do not satisfy the rules of the '{}' rule set",
+                rules
+            ),
+        ));
     }
 
     Ok(())
 }
+
+fn log_found_links(found: &FoundLinks) {
+    if found.architectures.is_empty() {
+        info!("Architectures: None");
+    }
+    for (arch, link) in &found.architectures {
+        info!("Arch {}: {}", arch, link.link);
+    }
+    {
+        let others: Vec<&str> = found.others.iter().map(|o| o.link.as_str()).collect();
+        info!("Others: {:?}", others);
+    }
+}
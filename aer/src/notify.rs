@@ -0,0 +1,614 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the notifications that are sent at the end of an update run,
+//! allowing maintainers to be alerted about failures during scheduled runs on
+//! a server. The summary of the run is either posted to webhooks (*ie: the
+//! incoming webhooks of Slack, Discord or Microsoft Teams*), or sent as an
+//! email through an SMTP server.
+//!
+//! The notifications are configured in the `notifications` table of the
+//! configuration file, and are by default only sent when any of the packages
+//! failed to be updated.
+//!
+//! Emails are by default only encrypted when the SMTP server supports
+//! `STARTTLS`, encryption is always required when the SMTP server needs
+//! credentials so these are never sent in clear text.
+
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::Duration;
+
+use aer_upd::web::WebRequest;
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::transport::smtp::client::{Tls, TlsParameters};
+use lettre::transport::smtp::extension::ClientId;
+use lettre::{Message, SmtpTransport, Transport};
+use log::{info, warn};
+use serde_json::{json, Value};
+
+use crate::clock::Clock;
+use crate::executor::JobStatus;
+use crate::report::RunReport;
+
+/// The maximum number of characters in a message posted to Discord.
+const DISCORD_MAX_LENGTH: usize = 2000;
+
+/// The time to wait on the SMTP server before giving up.
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Decides when the notifications are sent.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NotifyWhen {
+    /// Notifications are sent after every run.
+    Always,
+    /// Notifications are only sent when any package failed to be updated.
+    #[default]
+    Failures,
+}
+
+impl FromStr for NotifyWhen {
+    type Err = &'static str;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val.trim().to_lowercase().as_str() {
+            "always" => Ok(NotifyWhen::Always),
+            "failures" | "failure" => Ok(NotifyWhen::Failures),
+            _ => Err("The value must be either 'always' or 'failures'!"),
+        }
+    }
+}
+
+/// The format of the messages posted to a webhook.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum WebhookFormat {
+    /// Posts the summary as the `text` of the message, which is the format
+    /// used by Slack and compatible services (*ie: Mattermost*).
+    #[default]
+    Slack,
+    /// Posts the summary as the `content` of the message.
+    Discord,
+    /// Posts the summary as a message card to Microsoft Teams.
+    Teams,
+    /// Posts the summary together with the full report of the run.
+    Json,
+}
+
+impl FromStr for WebhookFormat {
+    type Err = &'static str;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val.trim().to_lowercase().as_str() {
+            "slack" => Ok(WebhookFormat::Slack),
+            "discord" => Ok(WebhookFormat::Discord),
+            "teams" => Ok(WebhookFormat::Teams),
+            "json" => Ok(WebhookFormat::Json),
+            _ => Err("The value is not a supported webhook format!"),
+        }
+    }
+}
+
+impl Display for WebhookFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            WebhookFormat::Slack => f.write_str("slack"),
+            WebhookFormat::Discord => f.write_str("discord"),
+            WebhookFormat::Teams => f.write_str("teams"),
+            WebhookFormat::Json => f.write_str("json"),
+        }
+    }
+}
+
+/// A webhook the summary of the run is posted to.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Webhook {
+    /// The url of the webhook.
+    pub url: Option<String>,
+    /// The name of the environment variable holding the url of the webhook,
+    /// used as the url usually contains the secret token of the webhook.
+    pub url_env: Option<String>,
+    /// The format of the posted messages.
+    pub format: WebhookFormat,
+}
+
+impl Webhook {
+    /// Returns the url of the webhook, the environment variable is preferred
+    /// over the url when it have been set.
+    pub fn url(&self) -> Option<String> {
+        self.url_env
+            .as_ref()
+            .and_then(|env| std::env::var(env).ok())
+            .filter(|url| !url.is_empty())
+            .or_else(|| self.url.clone())
+    }
+
+    /// Creates the message posted to the webhook.
+    pub fn message(&self, report: &RunReport) -> Value {
+        let (subject, body) = message_text(report);
+
+        match self.format {
+            WebhookFormat::Slack => json!({ "text": format!("*{}*\n{}", subject, body) }),
+            WebhookFormat::Discord => {
+                let content: String = format!("**{}**\n{}", subject, body)
+                    .chars()
+                    .take(DISCORD_MAX_LENGTH)
+                    .collect();
+                json!({ "content": content })
+            }
+            WebhookFormat::Teams => json!({
+                "@type": "MessageCard",
+                "@context": "https://schema.org/extensions",
+                "summary": subject,
+                "title": subject,
+                "text": body.replace('\n', "\n\n"),
+            }),
+            WebhookFormat::Json => json!({
+                "summary": subject,
+                "report": report.to_json(),
+            }),
+        }
+    }
+}
+
+/// The encryption used when connecting to the SMTP server.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum EmailTls {
+    /// The connection is upgraded with `STARTTLS` when the server supports
+    /// it, the upgrade is required when credentials have been set.
+    #[default]
+    Opportunistic,
+    /// The connection is always upgraded with `STARTTLS`.
+    StartTls,
+    /// The connection uses TLS from the start (*ie: on port 465*).
+    Wrapper,
+    /// The connection is never encrypted, which is not allowed when
+    /// credentials have been set.
+    None,
+}
+
+impl FromStr for EmailTls {
+    type Err = &'static str;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val.trim().to_lowercase().as_str() {
+            "opportunistic" => Ok(EmailTls::Opportunistic),
+            "starttls" => Ok(EmailTls::StartTls),
+            "wrapper" | "tls" => Ok(EmailTls::Wrapper),
+            "none" => Ok(EmailTls::None),
+            _ => Err("The value must be either 'opportunistic', 'starttls', 'wrapper' or 'none'!"),
+        }
+    }
+}
+
+/// The SMTP server and the recipients of the emails.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct EmailSettings {
+    /// The host name of the SMTP server.
+    pub server: String,
+    /// The port of the SMTP server.
+    pub port: u16,
+    /// The address the emails are sent from.
+    pub from: String,
+    /// The addresses the emails are sent to.
+    pub to: Vec<String>,
+    /// The user name used when authenticating with the SMTP server.
+    pub username: Option<String>,
+    /// The name of the environment variable holding the password of the user.
+    pub password_env: Option<String>,
+    /// The encryption used when connecting to the SMTP server.
+    pub tls: EmailTls,
+}
+
+impl EmailSettings {
+    /// Sends the summary of the run as an email to every recipient, the date
+    /// of the email is taken from the specified clock.
+    pub fn send(
+        &self,
+        report: &RunReport,
+        clock: &Clock,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (subject, body) = message_text(report);
+        let mut builder = Message::builder()
+            .from(self.from.parse()?)
+            .subject(subject)
+            .date(clock.now().into())
+            .header(ContentType::TEXT_PLAIN);
+        for to in &self.to {
+            builder = builder.to(to.parse()?);
+        }
+        let message = builder.body(body)?;
+
+        self.transport()?.send(&message)?;
+
+        Ok(())
+    }
+
+    /// Creates the transport used to connect to the SMTP server, an error is
+    /// returned when the credentials would be sent without encryption.
+    fn transport(&self) -> Result<SmtpTransport, Box<dyn std::error::Error>> {
+        let credentials = self.username.as_ref().map(|username| {
+            let password = self
+                .password_env
+                .as_ref()
+                .and_then(|env| std::env::var(env).ok())
+                .unwrap_or_default();
+            Credentials::new(username.clone(), password)
+        });
+        let tls = match self.tls {
+            EmailTls::None if credentials.is_some() => {
+                return Err("The credentials of the SMTP server are never sent without TLS!".into())
+            }
+            EmailTls::None => Tls::None,
+            EmailTls::Opportunistic if credentials.is_none() => {
+                Tls::Opportunistic(TlsParameters::new(self.server.clone())?)
+            }
+            EmailTls::Opportunistic | EmailTls::StartTls => {
+                Tls::Required(TlsParameters::new(self.server.clone())?)
+            }
+            EmailTls::Wrapper => Tls::Wrapper(TlsParameters::new(self.server.clone())?),
+        };
+
+        let mut builder = SmtpTransport::builder_dangerous(self.server.as_str())
+            .port(self.port)
+            .tls(tls)
+            .hello_name(ClientId::Domain("aer".into()))
+            .timeout(Some(SMTP_TIMEOUT));
+        if let Some(credentials) = credentials {
+            builder = builder.credentials(credentials);
+        }
+
+        Ok(builder.build())
+    }
+}
+
+/// The notifications sent at the end of an update run.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Notifier {
+    /// Decides when the notifications are sent.
+    pub when: NotifyWhen,
+    /// The webhooks the summary of the run is posted to.
+    pub webhooks: Vec<Webhook>,
+    /// The SMTP server the summary of the run is sent through.
+    pub email: Option<EmailSettings>,
+}
+
+impl Notifier {
+    /// Returns `true` if no webhooks or email have been configured.
+    pub fn is_empty(&self) -> bool {
+        self.webhooks.is_empty() && self.email.is_none()
+    }
+
+    /// Returns wether notifications should be sent for the run.
+    pub fn should_notify(&self, report: &RunReport) -> bool {
+        !self.is_empty() && (self.when == NotifyWhen::Always || report.has_failures())
+    }
+
+    /// Sends the summary of the run to every webhook and through email,
+    /// failing to send a notification is only logged as a warning.
+    pub fn notify(&self, request: &WebRequest, clock: &Clock, report: &RunReport) {
+        if !self.should_notify(report) {
+            return;
+        }
+
+        for webhook in &self.webhooks {
+            let url = match webhook.url() {
+                Some(url) => url,
+                None => {
+                    warn!(
+                        "The url of the webhook have not been set in the environment variable \
+                         '{}'!",
+                        webhook.url_env.as_deref().unwrap_or_default()
+                    );
+                    continue;
+                }
+            };
+            match request.post_json(&url, &webhook.message(report)) {
+                Ok(_) => info!(
+                    "Posted the summary of the run to the {} webhook",
+                    webhook.format
+                ),
+                Err(err) => warn!("Unable to post the summary of the run: '{}'", err),
+            }
+        }
+
+        if let Some(ref email) = self.email {
+            match email.send(report, clock) {
+                Ok(()) => info!("Sent the summary of the run to {}", email.to.join(", ")),
+                Err(err) => warn!("Unable to send the summary of the run: '{}'", err),
+            }
+        }
+    }
+}
+
+/// Creates the subject and body of the message summarizing the run, listing
+/// the errors of the failed packages and the versions of the updated
+/// packages.
+pub fn message_text(report: &RunReport) -> (String, String) {
+    let subject = format!("aer update run: {}", report.summary());
+    let mut failed = vec![];
+    let mut updated = vec![];
+
+    for package in report.packages() {
+        match package.status {
+            Some(JobStatus::Failed(ref message)) => {
+                failed.push(format!("- {}: {}", package.name, message))
+            }
+            Some(JobStatus::Succeeded) => updated.push(format!(
+                "- {}: {} -> {}",
+                package.name,
+                package.old_version.as_deref().unwrap_or("unknown"),
+                package.new_version.as_deref().unwrap_or("unknown")
+            )),
+            _ => {}
+        }
+    }
+
+    let mut body = vec![];
+    if !failed.is_empty() {
+        body.push("Failed packages:".to_owned());
+        body.extend(failed);
+    }
+    if !updated.is_empty() {
+        if !body.is_empty() {
+            body.push(String::new());
+        }
+        body.push("Updated packages:".to_owned());
+        body.extend(updated);
+    }
+
+    (subject, body.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::path::Path;
+
+    use chrono::{TimeZone, Utc};
+    use rstest::rstest;
+
+    use super::*;
+    use crate::executor::{JobResult, Summary};
+    use crate::report::PackageReport;
+
+    fn create_report(status: JobStatus) -> RunReport {
+        let summary = Summary::new(vec![
+            JobResult::new("test-package", JobStatus::Succeeded),
+            JobResult::new("other-package", status),
+        ]);
+        let mut package = PackageReport::new("test-package", Path::new("test.aer.toml"));
+        package.old_version = Some("1.0.0".into());
+        package.new_version = Some("1.2.0".into());
+        let mut reports = HashMap::new();
+        reports.insert("test-package".to_owned(), package);
+
+        RunReport::new(Utc::now(), Duration::default(), &summary, reports)
+    }
+
+    #[test]
+    fn message_text_should_list_failed_and_updated_packages() {
+        let report = create_report(JobStatus::Failed("Invalid data".into()));
+
+        let (subject, body) = message_text(&report);
+
+        assert_eq!(
+            subject,
+            "aer update run: 1 succeeded, 0 unchanged, 0 skipped, 1 failed"
+        );
+        assert_eq!(
+            body,
+            "Failed packages:\n- other-package: Invalid data\n\nUpdated packages:\n- \
+             test-package: 1.0.0 -> 1.2.0"
+        );
+    }
+
+    #[rstest(
+        format,
+        key,
+        case(WebhookFormat::Slack, "text"),
+        case(WebhookFormat::Discord, "content"),
+        case(WebhookFormat::Teams, "text"),
+        case(WebhookFormat::Json, "report")
+    )]
+    fn message_should_use_format_of_webhook(format: WebhookFormat, key: &str) {
+        let webhook = Webhook {
+            format,
+            ..Webhook::default()
+        };
+        let report = create_report(JobStatus::Failed("Invalid data".into()));
+
+        let actual = webhook.message(&report);
+
+        assert!(!actual[key].is_null());
+    }
+
+    #[rstest(
+        when,
+        status,
+        expected,
+        case(NotifyWhen::Failures, JobStatus::Failed("error".into()), true),
+        case(NotifyWhen::Failures, JobStatus::Skipped, false),
+        case(NotifyWhen::Always, JobStatus::Skipped, true)
+    )]
+    fn should_notify_should_honor_when_to_notify(
+        when: NotifyWhen,
+        status: JobStatus,
+        expected: bool,
+    ) {
+        let notifier = Notifier {
+            when,
+            webhooks: vec![Webhook::default()],
+            email: None,
+        };
+
+        let actual = notifier.should_notify(&create_report(status));
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn should_notify_should_not_notify_without_webhooks_or_email() {
+        let notifier = Notifier {
+            when: NotifyWhen::Always,
+            ..Notifier::default()
+        };
+
+        assert!(!notifier.should_notify(&create_report(JobStatus::Skipped)));
+    }
+
+    /// Starts a SMTP server accepting a single connection, which advertises
+    /// the specified extensions and rejects any recipient named `unknown`.
+    /// The lines received by the server are returned once the connection
+    /// have been closed.
+    fn smtp_server(extensions: &'static str) -> (u16, std::thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            let mut received = vec![];
+            writer.write_all(b"220 localhost ready\r\n").unwrap();
+            let mut in_data = false;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or_default() == 0 {
+                    break;
+                }
+                let line = line.trim_end().to_owned();
+                let reply = if in_data {
+                    if line == "." {
+                        in_data = false;
+                        "250 queued\r\n".to_owned()
+                    } else {
+                        received.push(line);
+                        continue;
+                    }
+                } else if line.starts_with("EHLO") {
+                    format!("250-localhost\r\n250 {}\r\n", extensions)
+                } else if line.starts_with("AUTH") {
+                    "235 authenticated\r\n".to_owned()
+                } else if line.starts_with("RCPT TO:<unknown@") {
+                    "550 unknown user\r\n".to_owned()
+                } else if line == "DATA" {
+                    in_data = true;
+                    "354 start input\r\n".to_owned()
+                } else if line == "QUIT" {
+                    received.push(line);
+                    let _ = writer.write_all(b"221 bye\r\n");
+                    break;
+                } else {
+                    "250 ok\r\n".to_owned()
+                };
+                received.push(line);
+                writer.write_all(reply.as_bytes()).unwrap();
+            }
+            received
+        });
+
+        (port, server)
+    }
+
+    #[test]
+    fn send_should_deliver_email_to_smtp_server() {
+        let (port, server) = smtp_server("AUTH PLAIN");
+        let settings = EmailSettings {
+            server: "127.0.0.1".into(),
+            port,
+            from: "aer@example.org".into(),
+            to: vec!["maintainers@example.org".into()],
+            tls: EmailTls::None,
+            ..EmailSettings::default()
+        };
+        let clock = Clock::frozen(Utc.ymd(2021, 6, 15).and_hms(12, 30, 0));
+
+        settings
+            .send(&create_report(JobStatus::Failed(".hidden".into())), &clock)
+            .unwrap();
+        let received = server.join().unwrap();
+
+        assert_eq!(received[0], "EHLO aer");
+        assert!(!received.iter().any(|line| line.starts_with("AUTH")));
+        assert!(received.contains(&"MAIL FROM:<aer@example.org>".to_owned()));
+        assert!(received.contains(&"RCPT TO:<maintainers@example.org>".to_owned()));
+        assert!(received.contains(
+            &"Subject: aer update run: 1 succeeded, 0 unchanged, 0 skipped, 1 failed".to_owned()
+        ));
+        assert!(received.contains(&"Date: Tue, 15 Jun 2021 12:30:00 +0000".to_owned()));
+        assert!(received.contains(&"- other-package: .hidden".to_owned()));
+        assert_eq!(received.last().unwrap(), "QUIT");
+    }
+
+    #[rstest(tls, case(EmailTls::Opportunistic), case(EmailTls::StartTls))]
+    fn send_should_require_tls_when_credentials_have_been_set(tls: EmailTls) {
+        let (port, server) = smtp_server("AUTH PLAIN");
+        let settings = EmailSettings {
+            server: "127.0.0.1".into(),
+            port,
+            from: "aer@example.org".into(),
+            to: vec!["maintainers@example.org".into()],
+            username: Some("aer".into()),
+            tls,
+            ..EmailSettings::default()
+        };
+
+        let actual = settings.send(&create_report(JobStatus::Skipped), &Clock::default());
+        let received = server.join().unwrap();
+
+        assert!(actual.is_err());
+        assert!(!received.iter().any(|line| line.starts_with("AUTH")));
+    }
+
+    #[test]
+    fn send_should_return_error_on_credentials_without_tls() {
+        let settings = EmailSettings {
+            server: "127.0.0.1".into(),
+            port: 25,
+            from: "aer@example.org".into(),
+            to: vec!["maintainers@example.org".into()],
+            username: Some("aer".into()),
+            tls: EmailTls::None,
+            ..EmailSettings::default()
+        };
+
+        let actual = settings.send(&create_report(JobStatus::Skipped), &Clock::default());
+
+        assert_eq!(
+            actual.unwrap_err().to_string(),
+            "The credentials of the SMTP server are never sent without TLS!"
+        );
+    }
+
+    #[test]
+    fn send_should_return_error_on_rejected_recipient() {
+        let (port, server) = smtp_server("AUTH PLAIN");
+        let settings = EmailSettings {
+            server: "127.0.0.1".into(),
+            port,
+            from: "aer@example.org".into(),
+            to: vec!["unknown@example.org".into()],
+            tls: EmailTls::None,
+            ..EmailSettings::default()
+        };
+
+        let actual = settings.send(&create_report(JobStatus::Skipped), &Clock::default());
+        server.join().unwrap();
+
+        assert!(actual.unwrap_err().to_string().contains("unknown user"));
+    }
+
+    #[rstest(
+        value,
+        expected,
+        case("opportunistic", EmailTls::Opportunistic),
+        case("STARTTLS", EmailTls::StartTls),
+        case("tls", EmailTls::Wrapper),
+        case(" none", EmailTls::None)
+    )]
+    fn email_tls_from_str_should_parse_value(value: &str, expected: EmailTls) {
+        assert_eq!(EmailTls::from_str(value).unwrap(), expected);
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for resolving where the application is allowed to write
+//! its data (cache, logs, state and staging files).
+
+use std::path::{Component, Path, PathBuf};
+
+use aer_upd::web::platform::Platform;
+use structopt::StructOpt;
+
+// Holds the optional data root that all locations written to by the
+// application will be resolved against.
+//
+// When no data root have been specified the application uses the same
+// locations as it always have (the current directory for logs and the
+// temporary directory for downloads). When a data root is specified, no
+// writes will happen outside of this directory, making it easy to run the
+// application in a read-only container with a single mounted volume.
+#[derive(Debug, Default, Clone, PartialEq, StructOpt)]
+pub struct DataRoot {
+    /// The directory where the cache, logs, state and staging files will be
+    /// stored. When specified, relative log paths are resolved inside this
+    /// directory and nothing will be written outside of it.
+    #[structopt(
+        long = "data-root",
+        env = "AER_DATA_ROOT",
+        global = true,
+        parse(from_os_str)
+    )]
+    root: Option<PathBuf>,
+}
+
+impl DataRoot {
+    /// Creates a new data root that will place all data inside the specified
+    /// directory.
+    pub fn new<P: Into<PathBuf>>(root: P) -> DataRoot {
+        DataRoot {
+            root: Some(root.into()),
+        }
+    }
+
+    /// Returns the directory set as the data root, if one have been specified.
+    pub fn root(&self) -> Option<&Path> {
+        self.root.as_deref()
+    }
+
+    /// Returns the directory where cached responses (etags, previous downloads,
+    /// etc.) should be stored.
+    pub fn cache_dir(&self) -> PathBuf {
+        self.join_or("cache", || Platform::current().temp_dir().join("aer-cache"))
+    }
+
+    /// Returns the directory where log files should be written.
+    pub fn logs_dir(&self) -> PathBuf {
+        self.join_or("logs", || PathBuf::from("."))
+    }
+
+    /// Returns the directory where state that should survive between runs is
+    /// stored.
+    pub fn state_dir(&self) -> PathBuf {
+        self.join_or("state", || PathBuf::from(".aer"))
+    }
+
+    /// Returns the directory that should be used when downloading files and
+    /// creating packages.
+    pub fn staging_dir(&self) -> PathBuf {
+        self.join_or("staging", || Platform::current().temp_dir())
+    }
+
+    /// Resolves the specified log path against the data root. Absolute paths,
+    /// or any path when no data root is set, are returned unchanged.
+    pub fn resolve_log_path(&self, path: &Path) -> PathBuf {
+        if self.root.is_none() || path.is_absolute() {
+            return path.to_path_buf();
+        }
+
+        let relative: PathBuf = path
+            .components()
+            .filter(|c| !matches!(c, Component::CurDir))
+            .collect();
+
+        self.logs_dir().join(relative)
+    }
+
+    /// Creates all of the directories inside the data root. Nothing will be
+    /// created when no data root have been specified.
+    pub fn create_dirs(&self) -> Result<(), std::io::Error> {
+        if self.root.is_none() {
+            return Ok(());
+        }
+
+        for dir in &[
+            self.cache_dir(),
+            self.logs_dir(),
+            self.state_dir(),
+            self.staging_dir(),
+        ] {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        Ok(())
+    }
+
+    fn join_or<F: FnOnce() -> PathBuf>(&self, name: &str, default: F) -> PathBuf {
+        if let Some(ref root) = self.root {
+            root.join(name)
+        } else {
+            default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[test]
+    fn default_should_use_existing_locations() {
+        let root = DataRoot::default();
+
+        assert_eq!(root.root(), None);
+        assert_eq!(root.logs_dir(), PathBuf::from("."));
+        assert_eq!(root.staging_dir(), std::env::temp_dir());
+    }
+
+    #[rstest(name, case("cache"), case("logs"), case("state"), case("staging"))]
+    fn dirs_should_be_placed_inside_data_root(name: &str) {
+        let root = DataRoot::new("/data");
+
+        let actual = match name {
+            "cache" => root.cache_dir(),
+            "logs" => root.logs_dir(),
+            "state" => root.state_dir(),
+            _ => root.staging_dir(),
+        };
+
+        assert_eq!(actual, PathBuf::from("/data").join(name));
+    }
+
+    #[test]
+    fn resolve_log_path_should_place_relative_path_in_logs_dir() {
+        let root = DataRoot::new("/data");
+
+        let actual = root.resolve_log_path(Path::new("./aer.log"));
+
+        assert_eq!(actual, PathBuf::from("/data").join("logs").join("aer.log"));
+    }
+
+    #[test]
+    fn resolve_log_path_should_not_change_path_without_data_root() {
+        let root = DataRoot::default();
+
+        let actual = root.resolve_log_path(Path::new("./aer.log"));
+
+        assert_eq!(actual, PathBuf::from("./aer.log"));
+    }
+
+    #[test]
+    fn resolve_log_path_should_not_change_absolute_paths() {
+        let root = DataRoot::new("/data");
+        let expected = std::env::temp_dir().join("aer.log");
+
+        let actual = root.resolve_log_path(&expected);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn create_dirs_should_create_all_directories() {
+        let path = std::env::temp_dir().join("aer-data-root-create-test");
+        let _ = std::fs::remove_dir_all(&path);
+        let root = DataRoot::new(&path);
+
+        root.create_dirs().unwrap();
+
+        assert!(root.cache_dir().is_dir());
+        assert!(root.logs_dir().is_dir());
+        assert!(root.state_dir().is_dir());
+        assert!(root.staging_dir().is_dir());
+
+        let _ = std::fs::remove_dir_all(&path);
+    }
+}
@@ -0,0 +1,424 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the policy engine, allowing organizations to ship their own
+//! validation rules in a policy file that are evaluated alongside the built-in
+//! readiness checks.
+//!
+//! A policy file contains a single rule per line, empty lines and lines
+//! starting with `#` are ignored. Every rule have the following format:
+//!
+//! ```text
+//! <error|warning> <rule-id>: <field> must [not] <condition> [because "<message>"]
+//! ```
+//!
+//! The field is the dotted path of a value in the package data (*ie:
+//! `metadata.project_url`*), the `metadata.`, `metadata.chocolatey.` and
+//! `updater.` prefixes can be left out. The supported conditions are:
+//!
+//! - `be set`: The value is not empty.
+//! - `be <value>`: The value is equal to the specified value.
+//! - `start with <value>`: The value starts with the specified value.
+//! - `end with <value>`: The value ends with the specified value.
+//! - `include <value>`: The list contains, or the text includes, the specified
+//!   value.
+//! - `match <regex>`: The value matches the specified regular expression.
+//!
+//! Values containing spaces must be surrounded by double quotes. When the
+//! field is a list, every item in the list must satisfy the condition, with
+//! the exception of `include` where a single item is enough.
+//!
+//! ```text
+//! # Organizational rules
+//! error https-project-url: project_url must start with "https://"
+//! warning team-maintainer: maintainers must include team-x because "The team must be able to update the package"
+//! ```
+
+use std::error::Error;
+use std::fmt::Display;
+use std::path::Path;
+
+use aer_upd::data::PackageData;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::readiness::CheckStatus;
+
+/// The prefixes tried when the field of a rule is not found at the root of
+/// the package data.
+const FIELD_PREFIXES: &[&str] = &["metadata.", "metadata.chocolatey.", "updater."];
+
+/// The errors that can occur when loading a policy file.
+#[derive(Debug)]
+pub enum PolicyError {
+    /// The policy file could not be read.
+    Io(std::io::Error),
+    /// A rule in the policy file is not valid.
+    Syntax {
+        /// The line of the invalid rule, starting at `1`.
+        line: usize,
+        /// The message describing why the rule is not valid.
+        message: String,
+    },
+}
+
+impl Display for PolicyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PolicyError::Io(err) => err.fmt(f),
+            PolicyError::Syntax { line, message } => {
+                write!(f, "Invalid policy rule on line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl Error for PolicyError {}
+
+impl From<std::io::Error> for PolicyError {
+    fn from(err: std::io::Error) -> Self {
+        PolicyError::Io(err)
+    }
+}
+
+/// The condition a value in the package data must satisfy.
+#[derive(Debug, Clone)]
+pub enum Condition {
+    /// The value must not be empty.
+    Set,
+    /// The value must be equal to the specified value.
+    Equals(String),
+    /// The value must start with the specified value.
+    StartsWith(String),
+    /// The value must end with the specified value.
+    EndsWith(String),
+    /// The list must contain, or the text must include, the specified value.
+    Includes(String),
+    /// The value must match the specified regular expression.
+    Matches(Regex),
+}
+
+impl Condition {
+    fn is_satisfied(&self, values: &[String]) -> bool {
+        let all = |check: &dyn Fn(&str) -> bool| {
+            !values.is_empty() && values.iter().all(|val| check(val))
+        };
+
+        match self {
+            Condition::Set => values.iter().any(|val| !val.trim().is_empty()),
+            Condition::Equals(expected) => all(&|val| val == expected),
+            Condition::StartsWith(expected) => all(&|val| val.starts_with(expected.as_str())),
+            Condition::EndsWith(expected) => all(&|val| val.ends_with(expected.as_str())),
+            Condition::Includes(expected) => {
+                values.iter().any(|val| val.contains(expected.as_str()))
+            }
+            Condition::Matches(regex) => all(&|val| regex.is_match(val)),
+        }
+    }
+}
+
+/// A single rule in the policy file.
+#[derive(Debug, Clone)]
+pub struct PolicyRule {
+    /// The identifier of the rule (*ie: `https-project-url`*).
+    pub id: String,
+    /// The status reported when the package do not satisfy the rule, this is
+    /// either [CheckStatus::Failed] or [CheckStatus::Warning].
+    pub status: CheckStatus,
+    /// The dotted path of the value in the package data that is validated.
+    pub field: String,
+    /// Wether the condition must not be satisfied.
+    pub negated: bool,
+    /// The condition the value must satisfy.
+    pub condition: Condition,
+    /// The message reported when the package do not satisfy the rule.
+    pub message: String,
+}
+
+impl PolicyRule {
+    /// Evaluates the rule against the package data (*serialized as a JSON
+    /// value*), and returns `true` if the package satisfies the rule.
+    pub fn evaluate(&self, data: &Value) -> bool {
+        let values = field_values(data, &self.field);
+
+        self.condition.is_satisfied(&values) != self.negated
+    }
+}
+
+/// The custom validation rules of an organization.
+///
+/// The policy is `Send` and `Sync`, and is loaded once and shared by every
+/// thread validating packages.
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    rules: Vec<PolicyRule>,
+}
+
+impl Policy {
+    /// Loads the policy rules from the specified file.
+    pub fn load(path: &Path) -> Result<Policy, PolicyError> {
+        let text = std::fs::read_to_string(path)?;
+
+        Policy::parse(&text)
+    }
+
+    /// Parses the policy rules from the specified text.
+    pub fn parse(text: &str) -> Result<Policy, PolicyError> {
+        let mut rules = vec![];
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let rule = parse_rule(line).map_err(|message| PolicyError::Syntax {
+                line: i + 1,
+                message,
+            })?;
+            rules.push(rule);
+        }
+
+        Ok(Policy { rules })
+    }
+
+    /// Returns all rules of the policy.
+    pub fn rules(&self) -> &[PolicyRule] {
+        self.rules.as_slice()
+    }
+
+    /// Returns `true` if the policy do not contain any rules.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Evaluates all rules against the specified package data, and returns
+    /// every rule together with wether the package satisfies the rule.
+    pub fn evaluate<'a>(&'a self, data: &PackageData) -> Vec<(&'a PolicyRule, bool)> {
+        if self.rules.is_empty() {
+            return vec![];
+        }
+
+        let data = serde_json::to_value(data).unwrap_or_default();
+
+        self.rules
+            .iter()
+            .map(|rule| (rule, rule.evaluate(&data)))
+            .collect()
+    }
+}
+
+fn parse_rule(line: &str) -> Result<PolicyRule, String> {
+    let (head, expression) = line
+        .split_once(':')
+        .ok_or_else(|| "Expected a ':' after the rule identifier".to_string())?;
+
+    let mut head = head.split_whitespace();
+    let status = match head.next() {
+        Some("error") => CheckStatus::Failed,
+        Some("warning") => CheckStatus::Warning,
+        Some(val) => return Err(format!("Unknown severity '{}'", val)),
+        None => return Err("Expected a severity".into()),
+    };
+    let id = head
+        .next()
+        .ok_or_else(|| "Expected a rule identifier".to_string())?;
+    if head.next().is_some() {
+        return Err("The rule identifier can not contain spaces".into());
+    }
+
+    let tokens = tokenize(expression)?;
+    let mut tokens = tokens.iter().map(String::as_str).peekable();
+
+    let field = tokens
+        .next()
+        .ok_or_else(|| "Expected a field".to_string())?;
+    if tokens.next() != Some("must") {
+        return Err(format!("Expected 'must' after the field '{}'", field));
+    }
+    let negated = tokens.peek() == Some(&"not");
+    if negated {
+        tokens.next();
+    }
+
+    let mut value = |name: &str| {
+        tokens
+            .next()
+            .map(String::from)
+            .ok_or_else(|| format!("Expected a value after '{}'", name))
+    };
+
+    let condition = match value("must")?.as_str() {
+        "be" => match value("be")?.as_str() {
+            "set" => Condition::Set,
+            val => Condition::Equals(val.into()),
+        },
+        "start" if value("start")? == "with" => Condition::StartsWith(value("start with")?),
+        "end" if value("end")? == "with" => Condition::EndsWith(value("end with")?),
+        "include" => Condition::Includes(value("include")?),
+        "match" => {
+            let regex = value("match")?;
+            Condition::Matches(Regex::new(&regex).map_err(|err| err.to_string())?)
+        }
+        val => return Err(format!("Unknown condition '{}'", val)),
+    };
+
+    let message = match (tokens.next(), tokens.next()) {
+        (None, _) => format!(
+            "The policy requires that {}",
+            expression.trim().replace('"', "'")
+        ),
+        (Some("because"), Some(message)) => message.into(),
+        (Some(val), _) => return Err(format!("Unexpected '{}' after the condition", val)),
+    };
+    if let Some(val) = tokens.next() {
+        return Err(format!("Unexpected '{}' after the message", val));
+    }
+
+    Ok(PolicyRule {
+        id: id.into(),
+        status,
+        field: field.into(),
+        negated,
+        condition,
+        message,
+    })
+}
+
+/// Splits the expression on whitespace, while keeping values surrounded by
+/// double quotes together.
+fn tokenize(expression: &str) -> Result<Vec<String>, String> {
+    let mut tokens = vec![];
+    let mut chars = expression.trim().chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+
+        let mut token = String::new();
+        if c == '"' {
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(c) => token.push(c),
+                    None => return Err("Missing closing '\"' of value".into()),
+                }
+            }
+        } else {
+            token.push(c);
+            while let Some(c) = chars.peek().filter(|c| !c.is_whitespace()) {
+                token.push(*c);
+                chars.next();
+            }
+        }
+
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Returns the values of the specified field, lists are returned as one value
+/// per item and missing fields as no values.
+fn field_values(data: &Value, field: &str) -> Vec<String> {
+    let value = std::iter::once("")
+        .chain(FIELD_PREFIXES.iter().copied())
+        .find_map(|prefix| {
+            let path = format!("/{}{}", prefix, field).replace('.', "/");
+            data.pointer(&path)
+        });
+
+    match value {
+        Some(Value::Array(items)) => items.iter().filter_map(value_text).collect(),
+        Some(value) => value_text(value).into_iter().collect(),
+        None => vec![],
+    }
+}
+
+fn value_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null | Value::Array(_) | Value::Object(_) => None,
+        Value::String(val) => Some(val.clone()),
+        val => Some(val.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_upd::data::chocolatey::ChocolateyMetadata;
+    use aer_upd::data::testing::PackageDataFixture;
+    use rstest::rstest;
+    use static_assertions::assert_impl_all;
+
+    use super::*;
+
+    assert_impl_all!(Policy: Send, Sync);
+
+    fn test_package() -> PackageData {
+        PackageDataFixture::new("test-package")
+            .with_maintainers(&["AdmiringWorm", "team-x"])
+            .with_project_url("http://example.org/project")
+            .with_choco(|choco| *choco = ChocolateyMetadata::with_authors(&["WormieCorp"]))
+            .build()
+    }
+
+    #[rstest(
+        rule,
+        expected,
+        case("error rule: project_url must start with \"https://\"", false),
+        case("error rule: project_url must not start with https://", true),
+        case("error rule: metadata.project_url must end with /project", true),
+        case("error rule: maintainers must include team-x", true),
+        case("error rule: maintainers must include team-y", false),
+        case("error rule: authors must be WormieCorp", true),
+        case("error rule: id must match ^[a-z-]+$", true),
+        case("error rule: summary must be set", false),
+        case("error rule: copyright must not be set", true),
+        case("error rule: chocolatey.lowercase_id must be true", true)
+    )]
+    fn evaluate_should_validate_package_data(rule: &str, expected: bool) {
+        let policy = Policy::parse(rule).unwrap();
+
+        let actual = policy.evaluate(&test_package());
+
+        assert_eq!(actual.len(), 1);
+        assert_eq!(actual[0].1, expected);
+    }
+
+    #[test]
+    fn parse_should_read_all_rules() {
+        let policy = Policy::parse(
+            "# Organizational rules\n\nerror https-project-url: project_url must start with \
+             \"https://\"\nwarning team-maintainer: maintainers must include team-x because \"The \
+             team must be a maintainer\"\n",
+        )
+        .unwrap();
+
+        let rules = policy.rules();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].id, "https-project-url");
+        assert_eq!(rules[0].status, CheckStatus::Failed);
+        assert_eq!(
+            rules[0].message,
+            "The policy requires that project_url must start with 'https://'"
+        );
+        assert_eq!(rules[1].status, CheckStatus::Warning);
+        assert_eq!(rules[1].message, "The team must be a maintainer");
+    }
+
+    #[rstest(
+        text,
+        case("error rule project_url must be set"),
+        case("fatal rule: project_url must be set"),
+        case("error rule: project_url should be set"),
+        case("error rule: project_url must look nice"),
+        case("error rule: project_url must match \"[a-z\""),
+        case("error rule: project_url must be \"set")
+    )]
+    fn parse_should_report_line_of_invalid_rules(text: &str) {
+        let actual = Policy::parse(&format!("# Rules\n{}", text));
+
+        assert!(matches!(actual, Err(PolicyError::Syntax { line: 2, .. })));
+    }
+}
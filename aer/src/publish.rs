@@ -0,0 +1,206 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the publish step of the update process, responsible for pushing
+//! the created package archive to the feed configured in the package data.
+//! The feed and API key can also be taken from a named authentication profile
+//! in the global configuration file.
+
+use std::path::Path;
+
+use aer_upd::context::UpdateContext;
+use aer_upd::data::chocolatey::ChocolateyPublish;
+use aer_upd::data::{PackageData, Url};
+use aer_upd::observer::Stage;
+use aer_upd::web::auth::AuthProfile;
+
+use crate::codes::{self, CodedError};
+
+/// Pushes the package archive to the feed configured in the
+/// `[metadata.chocolatey.publish]` section of the package data, and returns
+/// the url of the feed the package was pushed to. Nothing is pushed when the
+/// package data do not have a publish section.
+///
+/// When the publish section references an authentication profile, the feed
+/// and API key of the profile are used. Otherwise the API key of a profile
+/// matching the feed is used when the configured environment variable have not
+/// been set.
+pub fn publish_package(
+    context: &UpdateContext,
+    data: &PackageData,
+    package_file: &Path,
+) -> Result<Option<Url>, Box<dyn std::error::Error>> {
+    let publish = match data.metadata().chocolatey().publish {
+        Some(ref publish) => publish.clone(),
+        None => {
+            context
+                .logger()
+                .info("No feed have been configured, the package will not be published");
+            return Ok(None);
+        }
+    };
+
+    let profile = find_profile(context, &publish)?;
+    let feed = match (&publish.profile, profile) {
+        (Some(_), Some(profile)) => profile.feed().clone(),
+        _ => publish.feed.clone(),
+    };
+
+    context.stage(Stage::Publish, || {
+        push(context, &publish, profile, &feed, package_file)
+    })?;
+
+    Ok(Some(feed))
+}
+
+/// Returns the authentication profile referenced by the publish section, or
+/// the profile matching the configured feed when no profile is referenced.
+fn find_profile<'a>(
+    context: &'a UpdateContext,
+    publish: &ChocolateyPublish,
+) -> Result<Option<&'a AuthProfile>, CodedError> {
+    let profiles = context.request().auth_profiles();
+
+    match publish.profile {
+        Some(ref name) => profiles.get(name).map(Some).ok_or_else(|| {
+            CodedError::new(
+                codes::MISSING_AUTH_PROFILE,
+                format!(
+                    "The authentication profile '{}' have not been configured in the \
+                     configuration file!",
+                    name
+                ),
+            )
+        }),
+        None => Ok(profiles.find(&publish.feed)),
+    }
+}
+
+fn push(
+    context: &UpdateContext,
+    publish: &ChocolateyPublish,
+    profile: Option<&AuthProfile>,
+    feed: &Url,
+    package_file: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let api_key = match (&publish.profile, profile) {
+        (Some(_), Some(profile)) => profile.api_key(),
+        (_, profile) => publish
+            .api_key()
+            .or_else(|| profile.and_then(|profile| profile.api_key())),
+    };
+    let api_key = api_key.ok_or_else(|| {
+        let api_key_env = match (&publish.profile, profile) {
+            (Some(_), Some(profile)) => profile.api_key_env.as_deref().unwrap_or_default(),
+            _ => &publish.api_key_env,
+        };
+        CodedError::new(
+            codes::MISSING_API_KEY,
+            format!(
+                "The API key used when pushing to '{}' have not been set in the environment \
+                 variable '{}'!",
+                feed, api_key_env
+            ),
+        )
+    })?;
+
+    context
+        .request()
+        .push_package(feed.as_str(), &api_key, package_file)?;
+    context
+        .logger()
+        .info(format!("Pushed '{}' to '{}'", package_file.display(), feed));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_upd::data::testing::PackageDataFixture;
+    use aer_upd::web::auth::AuthProfiles;
+    use aer_upd::web::WebRequest;
+
+    use super::*;
+
+    #[test]
+    fn publish_package_should_skip_packages_without_feed() {
+        let data = PackageData::new("publish-test");
+
+        let actual = publish_package(
+            &UpdateContext::new().for_package("publish-test"),
+            &data,
+            Path::new("publish-test.1.0.0.nupkg"),
+        )
+        .unwrap();
+
+        assert_eq!(actual, None);
+    }
+
+    #[test]
+    fn publish_package_should_fail_without_api_key() {
+        let data = PackageDataFixture::new("publish-test")
+            .with_choco_defaults()
+            .with_choco_publish(
+                ChocolateyPublish::new().with_api_key_env("AER_TEST_MISSING_PUBLISH_KEY"),
+            )
+            .build();
+
+        let actual = publish_package(
+            &UpdateContext::new().for_package("publish-test"),
+            &data,
+            Path::new("publish-test.1.0.0.nupkg"),
+        )
+        .unwrap_err();
+
+        assert_eq!(codes::error_code(actual.as_ref()), codes::MISSING_API_KEY);
+    }
+
+    #[test]
+    fn publish_package_should_fail_with_unknown_profile() {
+        let data = PackageDataFixture::new("publish-test")
+            .with_choco_defaults()
+            .with_choco_publish(ChocolateyPublish::new().with_profile("internal"))
+            .build();
+
+        let actual = publish_package(
+            &UpdateContext::new().for_package("publish-test"),
+            &data,
+            Path::new("publish-test.1.0.0.nupkg"),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            codes::error_code(actual.as_ref()),
+            codes::MISSING_AUTH_PROFILE
+        );
+    }
+
+    #[test]
+    fn publish_package_should_use_api_key_env_of_profile() {
+        let profiles = AuthProfiles::new().with_profile(
+            AuthProfile::new(
+                "internal",
+                Url::parse("https://nexus.example.org/repository/choco/").unwrap(),
+            )
+            .with_api_key_env("AER_TEST_MISSING_PROFILE_KEY"),
+        );
+        let context = UpdateContext::new()
+            .with_request(WebRequest::create().with_auth_profiles(profiles))
+            .for_package("publish-test");
+        let data = PackageDataFixture::new("publish-test")
+            .with_choco_defaults()
+            .with_choco_publish(ChocolateyPublish::new().with_profile("internal"))
+            .build();
+
+        let actual =
+            publish_package(&context, &data, Path::new("publish-test.1.0.0.nupkg")).unwrap_err();
+
+        assert_eq!(codes::error_code(actual.as_ref()), codes::MISSING_API_KEY);
+        assert!(actual
+            .to_string()
+            .contains("'https://nexus.example.org/repository/choco/'"));
+        assert!(actual
+            .to_string()
+            .contains("'AER_TEST_MISSING_PROFILE_KEY'"));
+    }
+}
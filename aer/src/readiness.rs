@@ -0,0 +1,455 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the checks used to figure out how ready a package is to be
+//! submitted to the Chocolatey Community Repository, based on the requirements
+//! and guidelines that are used during moderation.
+
+use std::fmt::Display;
+
+use aer_upd::data::{PackageData, PackageLints};
+
+use crate::check::Severity;
+use crate::codes;
+use crate::policy::Policy;
+use crate::validation::{Rule, RuleSet};
+
+/// The outcome of a single readiness check.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CheckStatus {
+    /// The package satisfies the check.
+    Passed,
+    /// The package breaks a guideline, the package will be flagged but not
+    /// necessarily rejected.
+    Warning,
+    /// The package breaks a requirement, and will be rejected during
+    /// moderation.
+    Failed,
+    /// The package breaks a requirement or guideline, but the finding have
+    /// been explicitly allowed in the lint settings of the package.
+    Allowed,
+}
+
+impl Display for CheckStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            CheckStatus::Passed => f.write_str("PASS"),
+            CheckStatus::Warning => f.write_str("WARN"),
+            CheckStatus::Failed => f.write_str("FAIL"),
+            CheckStatus::Allowed => f.write_str("ALLOW"),
+        }
+    }
+}
+
+/// A single item in the readiness checklist.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadinessCheck {
+    /// The name of the check, as it is shown in the checklist.
+    pub name: String,
+    /// The dotted path of the key in the package data that the check
+    /// validates, i.e. `metadata.summary`.
+    pub key: String,
+    /// The outcome of the check.
+    pub status: CheckStatus,
+    /// A message describing why the check passed or failed.
+    pub message: String,
+    /// The stable code of the rule that was checked (*ie: `AER0203`*).
+    pub code: &'static str,
+}
+
+impl ReadinessCheck {
+    /// Returns the identifier of the rule that was checked, which is the name
+    /// of the check in kebab-case (i.e. `project-url`).
+    pub fn rule_id(&self) -> String {
+        rule_id(&self.name)
+    }
+}
+
+fn rule_id(name: &str) -> String {
+    name.to_lowercase().replace(' ', "-")
+}
+
+/// Returns the name of the check shown in the checklist for the identifier of
+/// a validation rule (*ie: `Project Url` for `project-url`*).
+fn check_name(rule_id: &str) -> String {
+    rule_id
+        .split('-')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// The checklist and resulting score of a single package.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadinessReport {
+    checks: Vec<ReadinessCheck>,
+}
+
+impl ReadinessReport {
+    /// Marks the checks that did not pass, but where the code of the check
+    /// have been allowed in the lint settings of the package. The note
+    /// justifying the exception is appended to the message of the check.
+    fn apply_lints(&mut self, lints: &PackageLints) {
+        for check in &mut self.checks {
+            if check.status == CheckStatus::Passed || !lints.is_allowed(check.code) {
+                continue;
+            }
+
+            check.status = CheckStatus::Allowed;
+            check.message = match lints.note(check.code) {
+                Some(note) => format!("{} (allowed: {})", check.message, note),
+                None => format!("{} (allowed without a justification)", check.message),
+            };
+        }
+    }
+
+    /// Returns all checks that was run against the package.
+    pub fn checks(&self) -> &[ReadinessCheck] {
+        self.checks.as_slice()
+    }
+
+    /// Returns the readiness score as a percentage between `0` and `100`.
+    /// Passed checks counts fully, while warnings only counts half.
+    pub fn score(&self) -> u8 {
+        if self.checks.is_empty() {
+            return 100;
+        }
+
+        let points: usize = self
+            .checks
+            .iter()
+            .map(|check| match check.status {
+                CheckStatus::Passed | CheckStatus::Allowed => 2,
+                CheckStatus::Warning => 1,
+                CheckStatus::Failed => 0,
+            })
+            .sum();
+
+        (points * 100 / (self.checks.len() * 2)) as u8
+    }
+
+    /// Returns `true` if none of the requirements failed, warnings are
+    /// allowed.
+    pub fn is_ready(&self) -> bool {
+        !self
+            .checks
+            .iter()
+            .any(|check| check.status == CheckStatus::Failed)
+    }
+
+    fn add<F: FnOnce() -> String>(
+        &mut self,
+        name: &'static str,
+        key: &'static str,
+        passed: bool,
+        failure_status: CheckStatus,
+        message: F,
+    ) {
+        let (status, message) = if passed {
+            (CheckStatus::Passed, String::new())
+        } else {
+            (failure_status, message())
+        };
+
+        self.checks.push(ReadinessCheck {
+            name: name.into(),
+            key: key.into(),
+            status,
+            message,
+            code: codes::rule_code(&rule_id(name)),
+        });
+    }
+
+    /// Adds a check for every validation rule, where rules reported as errors
+    /// are failed checks and the remaining rules are warnings.
+    fn apply_rules(&mut self, data: &PackageData, rules: &[Box<dyn Rule>]) {
+        for rule in rules {
+            let (status, message) = match rule.validate(data) {
+                None => (CheckStatus::Passed, String::new()),
+                Some(message) if rule.severity() == Severity::Error => {
+                    (CheckStatus::Failed, message)
+                }
+                Some(message) => (CheckStatus::Warning, message),
+            };
+
+            self.checks.push(ReadinessCheck {
+                name: check_name(rule.id()),
+                key: rule.key().into(),
+                status,
+                message,
+                code: codes::rule_code(rule.id()),
+            });
+        }
+    }
+
+    /// Adds a check for every rule in the policy of the organization.
+    fn apply_policy(&mut self, data: &PackageData, policy: &Policy) {
+        for (rule, passed) in policy.evaluate(data) {
+            let (status, message) = if passed {
+                (CheckStatus::Passed, String::new())
+            } else {
+                (rule.status, rule.message.clone())
+            };
+
+            self.checks.push(ReadinessCheck {
+                name: rule.id.clone(),
+                key: rule.field.clone(),
+                status,
+                message,
+                code: codes::POLICY_RULE,
+            });
+        }
+    }
+}
+
+/// Runs all of the moderation readiness checks against the specified package
+/// data, which are the rules of the [RuleSet::ChocolateyCommunity] rule set
+/// together with the guidelines used during moderation.
+pub fn check_readiness(data: &PackageData) -> ReadinessReport {
+    check_readiness_with_policy(data, &Policy::default())
+}
+
+/// Runs all of the moderation readiness checks, together with the rules of
+/// the specified organizational policy against the package data.
+pub fn check_readiness_with_policy(data: &PackageData, policy: &Policy) -> ReadinessReport {
+    let mut report = ReadinessReport { checks: vec![] };
+    let metadata = data.metadata();
+    let choco = metadata.chocolatey();
+
+    report.apply_rules(data, &RuleSet::ChocolateyCommunity.rules());
+    report.add(
+        "Chocolatey Tag",
+        "metadata.chocolatey.tags",
+        !choco
+            .tags()
+            .iter()
+            .any(|tag| tag.eq_ignore_ascii_case("chocolatey")),
+        CheckStatus::Warning,
+        || "The tag 'chocolatey' should only be used for chocolatey related software".into(),
+    );
+    report.add(
+        "Title",
+        "metadata.chocolatey.title",
+        !choco.title.as_deref().unwrap_or_default().trim().is_empty(),
+        CheckStatus::Warning,
+        || "A title for the software should be specified".into(),
+    );
+    report.add(
+        "Copyright",
+        "metadata.chocolatey.copyright",
+        choco.copyright.is_some(),
+        CheckStatus::Warning,
+        || "The copyright of the software should be specified".into(),
+    );
+    report.add(
+        "Documentation Url",
+        "metadata.chocolatey.documentation_url",
+        choco.documentation_url.is_some(),
+        CheckStatus::Warning,
+        || "A documentation url should be specified when available".into(),
+    );
+    report.add(
+        "Issues Url",
+        "metadata.chocolatey.issues_url",
+        choco.issues_url.is_some(),
+        CheckStatus::Warning,
+        || "An issues url should be specified when available".into(),
+    );
+    report.add(
+        "Release Notes",
+        "metadata.chocolatey.release_notes",
+        choco.release_notes().is_some(),
+        CheckStatus::Warning,
+        || "Release notes should be included in the package".into(),
+    );
+
+    report.apply_policy(data, policy);
+    report.apply_lints(data.lints());
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_upd::data::testing::PackageDataFixture;
+    use aer_upd::data::Url;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn ready_package() -> PackageData {
+        PackageDataFixture::minimal()
+            .with_summary("Some kind of summary")
+            .with_choco_defaults()
+            .with_choco(|choco| {
+                choco.set_copyright("Copyright (c) 2021 Kim J. Nordmo and WormieCorp.");
+                choco.set_description_str(
+                    "This is a description that is long enough to be accepted",
+                );
+                choco.set_release_notes("https://github.com/WormieCorp/aer/releases");
+                choco.set_tags(&["aer", "updater"]);
+                choco.documentation_url =
+                    Some(Url::parse("https://github.com/WormieCorp/aer").unwrap());
+                choco.issues_url =
+                    Some(Url::parse("https://github.com/WormieCorp/aer/issues").unwrap());
+            })
+            .build()
+    }
+
+    fn status_of(report: &ReadinessReport, name: &str) -> CheckStatus {
+        report
+            .checks()
+            .iter()
+            .find(|check| check.name == name)
+            .unwrap()
+            .status
+    }
+
+    #[test]
+    fn check_readiness_should_pass_all_checks_on_complete_package() {
+        let report = check_readiness(&ready_package());
+
+        assert!(report.is_ready());
+        assert_eq!(report.score(), 100);
+    }
+
+    #[test]
+    fn check_readiness_should_fail_on_empty_package() {
+        let report = check_readiness(&PackageData::new("test-package"));
+
+        assert!(!report.is_ready());
+        assert_eq!(status_of(&report, "Summary"), CheckStatus::Failed);
+        assert_eq!(status_of(&report, "Project Url"), CheckStatus::Failed);
+        assert_eq!(status_of(&report, "Authors"), CheckStatus::Failed);
+        assert_eq!(status_of(&report, "Description"), CheckStatus::Failed);
+        assert_eq!(status_of(&report, "Tags"), CheckStatus::Warning);
+    }
+
+    #[test]
+    fn check_readiness_should_include_rules_of_community_rule_set() {
+        let report = check_readiness(&PackageData::new("test-package"));
+
+        for rule in RuleSet::ChocolateyCommunity.rules() {
+            let check = report
+                .checks()
+                .iter()
+                .find(|check| check.rule_id() == rule.id())
+                .unwrap();
+            assert_eq!(check.key, rule.key());
+            assert_eq!(check.code, codes::rule_code(rule.id()));
+        }
+    }
+
+    #[rstest(
+        description,
+        expected,
+        case("Too short", CheckStatus::Failed),
+        case(
+            "This is a description that is long enough to be accepted",
+            CheckStatus::Passed
+        )
+    )]
+    fn check_readiness_should_validate_description_length(
+        description: &str,
+        expected: CheckStatus,
+    ) {
+        let mut data = ready_package();
+        let mut choco = data.metadata().chocolatey().into_owned();
+        choco.set_description_str(description);
+        data.metadata_mut().set_chocolatey(choco);
+
+        let report = check_readiness(&data);
+
+        assert_eq!(status_of(&report, "Description"), expected);
+    }
+
+    #[rstest(
+        tags,
+        name,
+        case(&["aer,updater"], "Tag Format"),
+        case(&["aer", "Chocolatey"], "Chocolatey Tag")
+    )]
+    fn check_readiness_should_flag_invalid_tags(tags: &[&str], name: &str) {
+        let mut data = ready_package();
+        let mut choco = data.metadata().chocolatey().into_owned();
+        choco.set_tags(tags);
+        data.metadata_mut().set_chocolatey(choco);
+
+        let report = check_readiness(&data);
+
+        assert_ne!(status_of(&report, name), CheckStatus::Passed);
+    }
+
+    #[test]
+    fn score_should_count_warnings_as_half() {
+        let report = ReadinessReport {
+            checks: vec![
+                ReadinessCheck {
+                    name: "First".into(),
+                    key: "metadata.id".into(),
+                    status: CheckStatus::Passed,
+                    message: String::new(),
+                    code: codes::UNKNOWN,
+                },
+                ReadinessCheck {
+                    name: "Second".into(),
+                    key: "metadata.summary".into(),
+                    status: CheckStatus::Warning,
+                    message: String::new(),
+                    code: codes::UNKNOWN,
+                },
+            ],
+        };
+
+        assert_eq!(report.score(), 75);
+        assert!(report.is_ready());
+    }
+
+    #[test]
+    fn check_readiness_should_allow_suppressed_findings() {
+        let mut data = ready_package();
+        data.metadata_mut().summary = String::new();
+        data.lints_mut()
+            .add_allow("AER0203", Some("The summary is generated"));
+
+        let report = check_readiness(&data);
+
+        assert_eq!(status_of(&report, "Summary"), CheckStatus::Allowed);
+        assert!(report.is_ready());
+        let summary = report
+            .checks()
+            .iter()
+            .find(|check| check.name == "Summary")
+            .unwrap();
+        assert!(summary
+            .message
+            .ends_with("(allowed: The summary is generated)"));
+    }
+
+    #[test]
+    fn check_readiness_with_policy_should_add_policy_rules() {
+        let policy = Policy::parse(
+            "error https-project-url: project_url must start with https://\nwarning \
+             team-maintainer: maintainers must include team-x",
+        )
+        .unwrap();
+
+        let report = check_readiness_with_policy(&ready_package(), &policy);
+
+        assert_eq!(status_of(&report, "https-project-url"), CheckStatus::Passed);
+        assert_eq!(status_of(&report, "team-maintainer"), CheckStatus::Warning);
+        let check = report
+            .checks()
+            .iter()
+            .find(|check| check.name == "team-maintainer")
+            .unwrap();
+        assert_eq!(check.code, codes::POLICY_RULE);
+        assert_eq!(check.key, "maintainers");
+    }
+}
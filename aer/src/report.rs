@@ -0,0 +1,406 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the report of an update run, which can be written as a JSON file
+//! for consumption by continuous integration services, and summarized as a
+//! table readable by humans at the end of the run.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use aer_upd::data::Url;
+use aer_upd::web::checksum::ChecksumType;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+use crate::build::DownloadedFile;
+use crate::executor::{JobStatus, Summary};
+use crate::resolved::ResolvedUpdate;
+use crate::update::FoundLinks;
+use crate::validation::ValidationResult;
+
+/// The report of updating a single package.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PackageReport {
+    /// The identifier of the package, or the name of the package file when
+    /// the package data could not be read.
+    pub name: String,
+    /// The package file the package data was read from.
+    pub file: Option<PathBuf>,
+    /// The outcome of updating the package.
+    pub status: Option<JobStatus>,
+    /// The version of the package before the update.
+    pub old_version: Option<String>,
+    /// The version of the package after the update, only set when the
+    /// package was successfully updated.
+    pub new_version: Option<String>,
+    /// The links that was found upstream, and the architecture they are used
+    /// for (*ie: `arch32`*).
+    pub urls: Vec<(&'static str, Url)>,
+    /// The url, checksum and checksum type of every downloaded file.
+    pub files: Vec<(Url, String, ChecksumType)>,
+    /// The resolved result of the update, only set when the package was
+    /// built.
+    pub resolved: Option<ResolvedUpdate>,
+    /// The validation rules that the package data did not satisfy.
+    pub validation: Vec<ValidationResult>,
+    /// The feed the package was pushed to.
+    pub pushed: Option<String>,
+    /// The time spent updating the package.
+    pub duration: Duration,
+}
+
+impl PackageReport {
+    /// Creates a new report for the package read from the specified package
+    /// file.
+    pub fn new(name: &str, file: &Path) -> PackageReport {
+        PackageReport {
+            name: name.into(),
+            file: Some(file.to_path_buf()),
+            ..PackageReport::default()
+        }
+    }
+
+    /// Records the links that was found upstream.
+    pub fn record_links(&mut self, found: &FoundLinks) {
+        self.urls = found
+            .release
+            .iter()
+            .map(|link| ("release", link.link.clone()))
+            .chain(
+                found
+                    .architectures
+                    .iter()
+                    .map(|(arch, link)| (arch.key(), link.link.clone())),
+            )
+            .chain(
+                found
+                    .checksums
+                    .iter()
+                    .map(|link| ("checksums", link.link.clone())),
+            )
+            .chain(found.others.iter().map(|link| ("other", link.link.clone())))
+            .collect();
+    }
+
+    /// Records the checksums of the downloaded files.
+    pub fn record_files(&mut self, files: &[DownloadedFile]) {
+        self.files = files
+            .iter()
+            .map(|file| {
+                (
+                    file.link.link.clone(),
+                    file.checksum.clone(),
+                    file.checksum_type,
+                )
+            })
+            .collect();
+    }
+
+    /// Records the resolved result of the update, and the version the
+    /// package was updated to.
+    pub fn record_resolved(&mut self, resolved: &ResolvedUpdate) {
+        self.new_version = resolved.version.as_ref().map(|version| version.to_string());
+        self.resolved = Some(resolved.clone());
+    }
+
+    /// Converts the report to a JSON value.
+    pub fn to_json(&self) -> Value {
+        let (status, error) = match self.status {
+            Some(ref status) => status_parts(status),
+            None => ("unknown", None),
+        };
+
+        json!({
+            "name": self.name,
+            "file": self.file,
+            "status": status,
+            "error": error,
+            "old_version": self.old_version,
+            "new_version": self.new_version,
+            "urls": self.urls.iter().map(|(kind, url)| json!({
+                "type": kind,
+                "url": url.as_str(),
+            })).collect::<Vec<_>>(),
+            "files": self.files.iter().map(|(url, checksum, checksum_type)| json!({
+                "url": url.as_str(),
+                "checksum": checksum,
+                "checksum_type": checksum_type.to_string(),
+            })).collect::<Vec<_>>(),
+            "resolved": self.resolved.as_ref().map(ResolvedUpdate::to_json),
+            "validation": self.validation.iter().map(ValidationResult::to_json).collect::<Vec<_>>(),
+            "pushed": self.pushed,
+            "duration": self.duration.as_secs_f64(),
+        })
+    }
+}
+
+/// The report of a complete update run, containing the report of every
+/// package in the order the packages was queued.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunReport {
+    started: DateTime<Utc>,
+    duration: Duration,
+    packages: Vec<PackageReport>,
+}
+
+impl RunReport {
+    /// Creates a new report from the summary of the run, using the package
+    /// reports recorded during the run. Packages without a recorded report
+    /// (*ie: packages where the package data could not be read*) are only
+    /// reported with the outcome of the job.
+    pub fn new(
+        started: DateTime<Utc>,
+        duration: Duration,
+        summary: &Summary,
+        mut reports: HashMap<String, PackageReport>,
+    ) -> RunReport {
+        let packages = summary
+            .results()
+            .iter()
+            .map(|result| {
+                let mut report = reports
+                    .remove(&result.name)
+                    .unwrap_or_else(|| PackageReport {
+                        name: result.name.clone(),
+                        ..PackageReport::default()
+                    });
+                report.status = Some(result.status.clone());
+                report.duration = result.duration;
+                report
+            })
+            .collect();
+
+        RunReport {
+            started,
+            duration,
+            packages,
+        }
+    }
+
+    /// Returns the reports of the packages.
+    pub fn packages(&self) -> &[PackageReport] {
+        &self.packages
+    }
+
+    /// Returns `true` if any of the packages failed to be updated.
+    pub fn has_failures(&self) -> bool {
+        self.count("failed") > 0
+    }
+
+    /// Returns the number of packages with each outcome, in a format readable
+    /// by humans.
+    pub fn summary(&self) -> String {
+        format!(
+            "{} succeeded, {} unchanged, {} skipped, {} failed",
+            self.count("succeeded"),
+            self.count("unchanged"),
+            self.count("skipped"),
+            self.count("failed")
+        )
+    }
+
+    fn count(&self, name: &str) -> usize {
+        self.packages
+            .iter()
+            .filter(|package| {
+                package
+                    .status
+                    .as_ref()
+                    .map(|status| status_parts(status).0 == name)
+                    .unwrap_or_default()
+            })
+            .count()
+    }
+
+    /// Converts the report to a JSON value.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "started": self.started.to_rfc3339(),
+            "duration": self.duration.as_secs_f64(),
+            "summary": {
+                "succeeded": self.count("succeeded"),
+                "unchanged": self.count("unchanged"),
+                "skipped": self.count("skipped"),
+                "failed": self.count("failed"),
+            },
+            "packages": self.packages.iter().map(PackageReport::to_json).collect::<Vec<_>>(),
+        })
+    }
+
+    /// Writes the report as a JSON file to the specified path, the parent
+    /// directory is created when it do not exist.
+    pub fn write(&self, path: &Path) -> Result<(), std::io::Error> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        std::fs::write(path, format!("{:#}\n", self.to_json()))
+    }
+
+    /// Returns the lines of a table summarizing the outcome, versions and
+    /// duration of every package.
+    pub fn summary_table(&self) -> Vec<String> {
+        let header = [
+            "Package",
+            "Status",
+            "Old version",
+            "New version",
+            "Duration",
+        ];
+        let rows: Vec<[String; 5]> = self
+            .packages
+            .iter()
+            .map(|package| {
+                [
+                    package.name.clone(),
+                    package
+                        .status
+                        .as_ref()
+                        .map(|status| status_parts(status).0)
+                        .unwrap_or("unknown")
+                        .to_owned(),
+                    package.old_version.clone().unwrap_or_default(),
+                    package.new_version.clone().unwrap_or_default(),
+                    format!("{:.2}s", package.duration.as_secs_f64()),
+                ]
+            })
+            .collect();
+
+        let mut widths: Vec<usize> = header.iter().map(|name| name.len()).collect();
+        for row in &rows {
+            for (width, value) in widths.iter_mut().zip(row.iter()) {
+                *width = (*width).max(value.chars().count());
+            }
+        }
+
+        let format_row = |values: Vec<&str>| {
+            values
+                .iter()
+                .zip(widths.iter())
+                .map(|(value, width)| format!("{:width$}", value, width = width))
+                .collect::<Vec<_>>()
+                .join(" | ")
+                .trim_end()
+                .to_owned()
+        };
+
+        let mut lines = vec![
+            format_row(header.to_vec()),
+            widths
+                .iter()
+                .map(|width| "-".repeat(*width))
+                .collect::<Vec<_>>()
+                .join("-|-"),
+        ];
+        lines.extend(
+            rows.iter()
+                .map(|row| format_row(row.iter().map(String::as_str).collect())),
+        );
+
+        lines
+    }
+}
+
+/// Returns the name of the status, and the error message of failed jobs.
+fn status_parts(status: &JobStatus) -> (&'static str, Option<&str>) {
+    match status {
+        JobStatus::Succeeded => ("succeeded", None),
+        JobStatus::Skipped => ("skipped", None),
+        JobStatus::Unchanged => ("unchanged", None),
+        JobStatus::Failed(message) => ("failed", Some(message.as_str())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_upd::data::{Architecture, Versions};
+    use aer_upd::web::{LinkElement, LinkType};
+
+    use super::*;
+    use crate::executor::JobResult;
+
+    fn create_report() -> RunReport {
+        let summary = Summary::new(vec![
+            JobResult::new("test-package", JobStatus::Succeeded),
+            JobResult::new("broken.aer.toml", JobStatus::Failed("Invalid data".into())),
+        ]);
+        let mut package = PackageReport::new("test-package", Path::new("test.aer.toml"));
+        package.old_version = Some("1.0.0".into());
+        package.record_resolved(&ResolvedUpdate {
+            version: Some(Versions::parse("1.2.0").unwrap()),
+            ..ResolvedUpdate::default()
+        });
+        package.record_links(&FoundLinks::default().with_arch(
+            Architecture::X64,
+            LinkElement::new(
+                Url::parse("https://example.org/test-x64.exe").unwrap(),
+                LinkType::Binary,
+            ),
+        ));
+        let mut reports = HashMap::new();
+        reports.insert("test-package".to_owned(), package);
+
+        RunReport::new(
+            DateTime::parse_from_rfc3339("2021-06-15T10:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+            Duration::from_secs(3),
+            &summary,
+            reports,
+        )
+    }
+
+    #[test]
+    fn to_json_should_include_packages_in_queued_order() {
+        let report = create_report();
+
+        let actual = report.to_json();
+
+        assert_eq!(actual["started"], "2021-06-15T10:00:00+00:00");
+        assert_eq!(actual["summary"]["succeeded"], 1);
+        assert_eq!(actual["summary"]["failed"], 1);
+        assert_eq!(actual["packages"][0]["name"], "test-package");
+        assert_eq!(actual["packages"][0]["old_version"], "1.0.0");
+        assert_eq!(actual["packages"][0]["new_version"], "1.2.0");
+        assert_eq!(actual["packages"][0]["resolved"]["version"], "1.2.0");
+        assert_eq!(actual["packages"][0]["urls"][0]["type"], "arch64");
+        assert_eq!(
+            actual["packages"][0]["urls"][0]["url"],
+            "https://example.org/test-x64.exe"
+        );
+        assert_eq!(actual["packages"][1]["name"], "broken.aer.toml");
+        assert_eq!(actual["packages"][1]["status"], "failed");
+        assert_eq!(actual["packages"][1]["error"], "Invalid data");
+        assert_eq!(actual["packages"][1]["resolved"], Value::Null);
+    }
+
+    #[test]
+    fn summary_should_count_outcomes() {
+        let report = create_report();
+
+        assert_eq!(
+            report.summary(),
+            "1 succeeded, 0 unchanged, 0 skipped, 1 failed"
+        );
+        assert!(report.has_failures());
+    }
+
+    #[test]
+    fn summary_table_should_align_columns() {
+        let report = create_report();
+
+        let actual = report.summary_table();
+
+        assert_eq!(
+            actual,
+            vec![
+                "Package         | Status    | Old version | New version | Duration",
+                "----------------|-----------|-------------|-------------|---------",
+                "test-package    | succeeded | 1.0.0       | 1.2.0       | 0.00s",
+                "broken.aer.toml | failed    |             |             | 0.00s",
+            ]
+        );
+    }
+}
@@ -0,0 +1,249 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the lookup of the reputation of downloaded files on VirusTotal,
+//! configured in the `[virustotal]` section of the configuration file.
+//!
+//! The files are looked up by their checksum once they have been downloaded,
+//! and the update of the package fails when a file have been flagged by more
+//! anti virus engines than the configured threshold allows. Files that have
+//! never been scanned by VirusTotal, or that could not be looked up, are only
+//! reported with a warning.
+
+use std::path::Path;
+
+use aer_upd::context::UpdateContext;
+use aer_upd::web::virustotal::DetectionRatio;
+
+use crate::codes::{self, CodedError};
+use crate::ChecksumType;
+
+/// The environment variable holding the VirusTotal API key, unless another
+/// variable have been named in the configuration file.
+pub const DEFAULT_API_KEY_ENV: &str = "AER_VIRUSTOTAL_KEY";
+
+/// The settings used when looking up the downloaded files on VirusTotal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VirusTotalSettings {
+    /// The name of the environment variable holding the API key, no lookups
+    /// are done when the variable have not been set.
+    pub api_key_env: String,
+    /// The number of anti virus engines that are allowed to flag a downloaded
+    /// file before the update of the package fails.
+    pub threshold: u32,
+}
+
+impl Default for VirusTotalSettings {
+    fn default() -> Self {
+        Self {
+            api_key_env: DEFAULT_API_KEY_ENV.into(),
+            threshold: 0,
+        }
+    }
+}
+
+impl VirusTotalSettings {
+    /// Returns the API key read from the environment variable of the
+    /// settings, if the variable have been set.
+    pub fn api_key(&self) -> Option<String> {
+        std::env::var(&self.api_key_env)
+            .ok()
+            .filter(|key| !key.trim().is_empty())
+    }
+}
+
+/// Looks up the downloaded file on VirusTotal, and returns the detection
+/// ratio of the file when the file have been scanned. An error is returned
+/// when the file have been flagged by more anti virus engines than the
+/// threshold of the settings allows.
+pub fn check_file(
+    context: &UpdateContext,
+    settings: &VirusTotalSettings,
+    api_key: &str,
+    path: &Path,
+    checksum: (&str, ChecksumType),
+) -> Result<Option<DetectionRatio>, CodedError> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    // VirusTotal only knows the files by their MD5, SHA-1 or SHA-256 checksum.
+    let hash = match checksum {
+        (checksum, ChecksumType::Md5)
+        | (checksum, ChecksumType::Sha1)
+        | (checksum, ChecksumType::Sha256) => checksum.to_owned(),
+        _ => match ChecksumType::Sha256.generate(path) {
+            Ok(checksum) => checksum,
+            Err(err) => {
+                context.logger().warn(format_args!(
+                    "Unable to look up '{}' on VirusTotal: {}",
+                    name, err
+                ));
+                return Ok(None);
+            }
+        },
+    };
+
+    let ratio = match context.request().get_virustotal_report(&hash, api_key) {
+        Ok(Some(ratio)) => ratio,
+        Ok(None) => {
+            context.logger().warn(format_args!(
+                "The file '{}' have not been scanned by VirusTotal!",
+                name
+            ));
+            return Ok(None);
+        }
+        Err(err) => {
+            context.logger().warn(format_args!(
+                "Unable to look up '{}' on VirusTotal: {}",
+                name, err
+            ));
+            return Ok(None);
+        }
+    };
+
+    if ratio.exceeds(settings.threshold) {
+        return Err(CodedError::new(
+            codes::VIRUSTOTAL_DETECTION,
+            format!(
+                "The file '{}' was flagged by {} anti virus engines, which is above the threshold \
+                 of {}!",
+                name, ratio, settings.threshold
+            ),
+        ));
+    }
+
+    context.logger().info(format_args!(
+        "The file '{}' was flagged by {} anti virus engines on VirusTotal",
+        name, ratio
+    ));
+
+    Ok(Some(ratio))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use aer_upd::data::Url;
+    use aer_upd::web::fixtures::{FixtureMode, Fixtures};
+    use aer_upd::web::WebRequest;
+    use serde_json::json;
+
+    use super::*;
+
+    const HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    fn create_context(name: &str, status: u16, malicious: u32) -> (UpdateContext, PathBuf) {
+        let dir = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let fixtures = Fixtures::new(&dir, FixtureMode::Replay);
+        let url = Url::parse("https://www.virustotal.com/api/v3/files/")
+            .unwrap()
+            .join(HELLO_SHA256)
+            .unwrap();
+        let body = json!({
+            "data": { "attributes": { "last_analysis_stats": {
+                "harmless": 0,
+                "malicious": malicious,
+                "suspicious": 0,
+                "undetected": 70 - malicious,
+            } } }
+        });
+        let recorded = json!({
+            "url": url.as_str(),
+            "final_url": url.as_str(),
+            "status": status,
+            "headers": { "content-type": "application/json" },
+            "body": body.to_string(),
+        });
+        std::fs::write(fixtures.path_for(&url), recorded.to_string()).unwrap();
+        std::fs::write(dir.join("setup.exe"), "hello").unwrap();
+        let request = WebRequest::create().with_fixtures(fixtures);
+
+        (UpdateContext::new().with_request(request), dir)
+    }
+
+    fn settings(threshold: u32) -> VirusTotalSettings {
+        VirusTotalSettings {
+            threshold,
+            ..VirusTotalSettings::default()
+        }
+    }
+
+    #[test]
+    fn check_file_should_return_detection_ratio() {
+        let (context, dir) = create_context("aer-virustotal-ratio-test", 200, 1);
+
+        let actual = check_file(
+            &context,
+            &settings(1),
+            "key",
+            &dir.join("setup.exe"),
+            (HELLO_SHA256, ChecksumType::Sha256),
+        );
+
+        assert_eq!(
+            actual.unwrap(),
+            Some(DetectionRatio {
+                positives: 1,
+                total: 70
+            })
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn check_file_should_fail_when_threshold_is_exceeded() {
+        let (context, dir) = create_context("aer-virustotal-threshold-test", 200, 3);
+
+        let actual = check_file(
+            &context,
+            &settings(2),
+            "key",
+            &dir.join("setup.exe"),
+            (HELLO_SHA256, ChecksumType::Sha256),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            codes::describe(&actual),
+            "AER0112: The file 'setup.exe' was flagged by 3/70 anti virus engines, which is above \
+             the threshold of 2!"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn check_file_should_look_up_sha256_checksum_of_other_checksum_types() {
+        let (context, dir) = create_context("aer-virustotal-sha512-test", 200, 0);
+
+        let actual = check_file(
+            &context,
+            &settings(0),
+            "key",
+            &dir.join("setup.exe"),
+            ("abcdef", ChecksumType::Sha512),
+        );
+
+        assert!(matches!(actual, Ok(Some(_))));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn check_file_should_return_nothing_when_file_have_not_been_scanned() {
+        let (context, dir) = create_context("aer-virustotal-not-found-test", 404, 0);
+
+        let actual = check_file(
+            &context,
+            &settings(0),
+            "key",
+            &dir.join("setup.exe"),
+            (HELLO_SHA256, ChecksumType::Sha256),
+        );
+
+        assert_eq!(actual.unwrap(), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
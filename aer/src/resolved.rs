@@ -0,0 +1,207 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the resolved result of an update, holding the version and the
+//! links, checksums and names of the files that was downloaded for each
+//! architecture.
+//!
+//! The resolved update is created once the files have been downloaded, and
+//! is the single source of the values passed on to the script templates, the
+//! scripts of the package and the report of the run.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use aer_upd::data::{Architecture, Url, Versions};
+use aer_upd::web::virustotal::DetectionRatio;
+use serde_json::{json, Value};
+
+use crate::build::DownloadedFile;
+use crate::update::FoundLinks;
+use crate::ChecksumType;
+
+/// The link and downloaded file of a single architecture.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedFile {
+    /// The url the file is downloaded from.
+    pub url: Url,
+    /// The checksum of the downloaded file, only set when the file have been
+    /// downloaded.
+    pub checksum: Option<String>,
+    /// The algorithm used when generating the checksum.
+    pub checksum_type: Option<ChecksumType>,
+    /// The name of the downloaded file.
+    pub file_name: Option<String>,
+    /// The directory the file was extracted to, this is only set for archives
+    /// of packages using the `Archive` updater type.
+    pub extracted: Option<PathBuf>,
+    /// The number of anti virus engines on VirusTotal that flagged the file,
+    /// only set when the file have been looked up on VirusTotal.
+    pub detection: Option<DetectionRatio>,
+}
+
+/// The resolved result of an update.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ResolvedUpdate {
+    /// The version the package is updated to, either found in the links or
+    /// read from the downloaded files.
+    pub version: Option<Versions>,
+    /// The link and downloaded file of each architecture that was found.
+    pub files: BTreeMap<Architecture, ResolvedFile>,
+}
+
+impl ResolvedUpdate {
+    /// Creates the resolved update from the links that was found, and the
+    /// files that was downloaded from these links.
+    pub fn new(
+        found: &FoundLinks,
+        files: &[DownloadedFile],
+        version: Option<Versions>,
+    ) -> ResolvedUpdate {
+        let files = found
+            .architectures
+            .iter()
+            .map(|(arch, link)| {
+                let file = files.iter().find(|file| file.link == *link);
+                let resolved = ResolvedFile {
+                    url: link.link.clone(),
+                    checksum: file.map(|file| file.checksum.clone()),
+                    checksum_type: file.map(|file| file.checksum_type),
+                    file_name: file.and_then(|file| {
+                        file.path
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                    }),
+                    extracted: file.and_then(|file| file.extracted.clone()),
+                    detection: file.and_then(|file| file.detection),
+                };
+
+                (*arch, resolved)
+            })
+            .collect();
+
+        ResolvedUpdate { version, files }
+    }
+
+    /// Returns the values of the resolved files, named after the suffix of
+    /// the architecture (*ie: `url64`, `checksum64` and `checksumType64`*).
+    /// The checksums are only available for the files that was downloaded.
+    pub fn values(&self) -> Vec<(String, String)> {
+        let mut values = vec![];
+
+        for (arch, file) in &self.files {
+            let suffix = arch.suffix();
+            values.push((format!("url{}", suffix), file.url.to_string()));
+            if let Some(ref checksum) = file.checksum {
+                values.push((format!("checksum{}", suffix), checksum.clone()));
+            }
+            if let Some(checksum_type) = file.checksum_type {
+                values.push((format!("checksumType{}", suffix), checksum_type.to_string()));
+            }
+        }
+
+        values
+    }
+
+    /// Converts the resolved update to a JSON value.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "version": self.version.as_ref().map(|version| version.to_string()),
+            "files": self.files.iter().map(|(arch, file)| json!({
+                "architecture": arch.to_string(),
+                "url": file.url.as_str(),
+                "checksum": file.checksum,
+                "checksum_type": file.checksum_type.map(|checksum_type| checksum_type.to_string()),
+                "file_name": file.file_name,
+                "virustotal": file.detection.map(|detection| detection.to_string()),
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_upd::web::{LinkElement, LinkType};
+
+    use super::*;
+
+    fn resolved() -> ResolvedUpdate {
+        let x64 = LinkElement::new(
+            Url::parse("https://example.org/app-1.0.0-x64.exe").unwrap(),
+            LinkType::Binary,
+        );
+        let arm64 = LinkElement::new(
+            Url::parse("https://example.org/app-1.0.0-arm64.exe").unwrap(),
+            LinkType::Binary,
+        );
+        let found = FoundLinks::default()
+            .with_arch(Architecture::X64, x64.clone())
+            .with_arch(Architecture::Arm64, arm64);
+        let files = vec![DownloadedFile {
+            link: x64,
+            path: PathBuf::from("downloads").join("app-1.0.0-x64.exe"),
+            checksum: "ABCDEF".into(),
+            checksum_type: ChecksumType::Sha256,
+            signatures: vec![],
+            extracted: None,
+            detection: Some(DetectionRatio {
+                positives: 1,
+                total: 70,
+            }),
+        }];
+
+        ResolvedUpdate::new(&found, &files, Some(Versions::parse("1.0.0").unwrap()))
+    }
+
+    #[test]
+    fn new_should_resolve_downloaded_files_for_each_architecture() {
+        let actual = resolved();
+
+        let x64 = &actual.files[&Architecture::X64];
+        assert_eq!(x64.checksum.as_deref(), Some("ABCDEF"));
+        assert_eq!(x64.checksum_type, Some(ChecksumType::Sha256));
+        assert_eq!(x64.file_name.as_deref(), Some("app-1.0.0-x64.exe"));
+        let arm64 = &actual.files[&Architecture::Arm64];
+        assert_eq!(
+            arm64.url.as_str(),
+            "https://example.org/app-1.0.0-arm64.exe"
+        );
+        assert_eq!(arm64.checksum, None);
+        assert_eq!(arm64.file_name, None);
+    }
+
+    #[test]
+    fn values_should_use_architecture_suffixes() {
+        let actual = resolved().values();
+
+        assert_eq!(
+            actual,
+            vec![
+                (
+                    "url64".to_owned(),
+                    "https://example.org/app-1.0.0-x64.exe".to_owned()
+                ),
+                ("checksum64".to_owned(), "ABCDEF".to_owned()),
+                ("checksumType64".to_owned(), "sha256".to_owned()),
+                (
+                    "urlArm64".to_owned(),
+                    "https://example.org/app-1.0.0-arm64.exe".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn to_json_should_include_version_and_files() {
+        let actual = resolved().to_json();
+
+        assert_eq!(actual["version"], "1.0.0");
+        assert_eq!(actual["files"][0]["architecture"], "x64");
+        assert_eq!(actual["files"][0]["checksum_type"], "sha256");
+        assert_eq!(actual["files"][0]["file_name"], "app-1.0.0-x64.exe");
+        assert_eq!(actual["files"][0]["virustotal"], "1/70");
+        assert_eq!(actual["files"][1]["architecture"], "arm64");
+        assert_eq!(actual["files"][1]["checksum"], Value::Null);
+        assert_eq!(actual["files"][1]["virustotal"], Value::Null);
+    }
+}
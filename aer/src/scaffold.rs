@@ -0,0 +1,437 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the scaffolding of new package data files, used when starting on
+//! a new package instead of writing the package data by hand.
+//!
+//! The package data file is created in a directory named after the
+//! identifier of the package, together with the script templates of the
+//! package when requested. When the project url points to a repository on
+//! GitHub, the description, license and latest release of the repository are
+//! used to pre-fill the package data, and the releases of the repository are
+//! used to find new versions.
+
+use std::fmt::Display;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use aer_upd::data::{PackageMetadata, Url, Versions};
+use aer_upd::web::errors::WebError;
+use aer_upd::web::sources::github::{self, GitHubRepository};
+use aer_upd::web::WebRequest;
+use log::info;
+use toml_edit::{value, Array, Document, InlineTable, Item, Table};
+
+use crate::validation::DEFAULT_PROJECT_HOST;
+
+/// The path of the install script template, relative to the package data
+/// file.
+const INSTALL_TEMPLATE: &str = "tools/chocolateyInstall.ps1";
+
+/// The content of the install script template of Chocolatey packages.
+const INSTALL_SCRIPT: &str = r#"$ErrorActionPreference = 'Stop'
+
+$packageArgs = @{
+  packageName    = '{{id}}'
+  fileType       = 'exe'
+  url64bit       = '{{url64}}'
+  checksum64     = '{{checksum64}}'
+  checksumType64 = '{{checksumType64}}'
+  silentArgs     = '/S'
+  validExitCodes = @(0)
+}
+
+Install-ChocolateyPackage @packageArgs
+"#;
+
+/// The package managers a new package can be created for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    Chocolatey,
+    Nuget,
+    Winget,
+}
+
+impl Target {
+    pub fn variants_str() -> &'static [&'static str] {
+        static VARIANTS: &[&str] = &["chocolatey", "nuget", "winget"];
+
+        VARIANTS
+    }
+}
+
+impl FromStr for Target {
+    type Err = &'static str;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val.trim().to_lowercase().as_str() {
+            "chocolatey" | "choco" => Ok(Target::Chocolatey),
+            "nuget" => Ok(Target::Nuget),
+            "winget" => Ok(Target::Winget),
+            _ => Err("The value is not a supported target!"),
+        }
+    }
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            Target::Chocolatey => f.write_str("chocolatey"),
+            Target::Nuget => f.write_str("nuget"),
+            Target::Winget => f.write_str("winget"),
+        }
+    }
+}
+
+/// The values used when creating a new package data file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scaffold {
+    /// The identifier of the package.
+    pub id: String,
+    /// The maintainers of the package.
+    pub maintainers: Vec<String>,
+    /// The package managers the package is created for.
+    pub targets: Vec<Target>,
+    /// The homepage of the software.
+    pub project_url: Option<Url>,
+    /// The short summary of the software.
+    pub summary: Option<String>,
+    /// The license expression of the software (*ie: `MIT`*).
+    pub license: Option<String>,
+    /// The authors of the software.
+    pub authors: Vec<String>,
+    /// The tags of the package.
+    pub tags: Vec<String>,
+    /// The GitHub repository in the form `owner/repo`, used to find new
+    /// versions of the software.
+    pub repository: Option<String>,
+    /// The latest version of the software.
+    pub version: Option<Versions>,
+    /// Wether the script templates of the package are created.
+    pub templates: bool,
+}
+
+impl Scaffold {
+    /// Creates the values of a new Chocolatey package with the specified
+    /// identifier, maintained by the default maintainers.
+    pub fn new(id: &str) -> Scaffold {
+        Scaffold {
+            id: id.to_owned(),
+            maintainers: PackageMetadata::new(id).maintainers().to_vec(),
+            targets: vec![Target::Chocolatey],
+            project_url: None,
+            summary: None,
+            license: None,
+            authors: vec![],
+            tags: vec![],
+            repository: None,
+            version: None,
+            templates: false,
+        }
+    }
+
+    /// Reads the information of the project at the specified url, and uses
+    /// it for the values that have not already been set. Only repositories
+    /// hosted on GitHub can be probed, other urls are only used as the
+    /// project url.
+    pub fn probe(&mut self, request: &WebRequest, url: &Url) -> Result<(), WebError> {
+        if self.project_url.is_none() {
+            self.project_url = Some(url.clone());
+        }
+
+        let repository = match github::repository_from_url(url) {
+            Some(repository) => repository,
+            None => return Ok(()),
+        };
+        let info = request.get_github_repository(&repository)?;
+        let releases = request.get_github_releases(&repository)?;
+        let version = releases
+            .iter()
+            .filter(|release| !release.prerelease)
+            .find_map(|release| release.version.as_ref());
+        self.use_repository(&info, version);
+
+        Ok(())
+    }
+
+    /// Uses the information of a repository on GitHub, together with the
+    /// version of the latest release of the repository, for the values that
+    /// have not already been set.
+    pub fn use_repository(&mut self, repository: &GitHubRepository, version: Option<&Versions>) {
+        if self.repository.is_none() {
+            self.repository = Some(repository.name.clone());
+        }
+        if self.summary.is_none() {
+            self.summary = repository.description.clone();
+        }
+        if self.license.is_none() {
+            self.license = repository.license.clone();
+        }
+        if self.authors.is_empty() {
+            if let Some((owner, _)) = repository.name.split_once('/') {
+                self.authors.push(owner.to_owned());
+            }
+        }
+        if self.tags.is_empty() {
+            self.tags = repository.topics.clone();
+        }
+        if let Some(ref homepage) = repository.homepage {
+            self.project_url = Some(homepage.clone());
+        }
+        if self.version.is_none() {
+            self.version = version.cloned();
+        }
+    }
+
+    /// Returns the content of the package data file.
+    pub fn to_toml(&self) -> String {
+        let mut document = Document::new();
+        let summary = self.summary.clone().unwrap_or_default();
+        let project_url = self
+            .project_url
+            .as_ref()
+            .map(Url::to_string)
+            .unwrap_or_else(|| format!("https://{}/", DEFAULT_PROJECT_HOST));
+        let authors = if self.authors.is_empty() {
+            &self.maintainers
+        } else {
+            &self.authors
+        };
+
+        let mut metadata = Table::new();
+        metadata["id"] = value(self.id.as_str());
+        metadata["maintainers"] = value(array(&self.maintainers));
+        metadata["summary"] = value(summary.as_str());
+        metadata["project_url"] = value(project_url);
+        if let Some(ref license) = self.license {
+            metadata["license"] = value(license.as_str());
+        }
+
+        for target in &self.targets {
+            let mut table = Table::new();
+            match target {
+                Target::Chocolatey => {
+                    table["authors"] = value(array(authors));
+                    table["description"] = value(summary.as_str());
+                    if !self.tags.is_empty() {
+                        table["tags"] = value(array(&self.tags));
+                    }
+                }
+                Target::Nuget => {
+                    table["authors"] = value(array(authors));
+                    table["description"] = value(summary.as_str());
+                }
+                Target::Winget => {
+                    let publisher = authors.first().map(String::as_str).unwrap_or_default();
+                    table["package_identifier"] = value(format!("{}.{}", publisher, self.id));
+                    table["publisher"] = value(publisher);
+                }
+            }
+            if let Some(ref version) = self.version {
+                table["version"] = value(version.to_string());
+            }
+            metadata[&target.to_string()] = Item::Table(table);
+        }
+        document["metadata"] = Item::Table(metadata);
+
+        let mut updater = Table::new();
+        if let Some(ref repository) = self.repository {
+            updater["source"] = value("github");
+            updater["repository"] = value(repository.as_str());
+        }
+        if self.has_templates() {
+            updater["templates"] = value(array(&[INSTALL_TEMPLATE.to_owned()]));
+        }
+        if self.targets.contains(&Target::Chocolatey) {
+            let mut chocolatey = Table::new();
+            if self.repository.is_none() {
+                if let Some(ref url) = self.project_url {
+                    chocolatey["parse_url"] = value(url.as_str());
+                }
+            }
+            let mut regexes = InlineTable::new();
+            regexes.insert("arch64", r"\.exe$".into());
+            chocolatey["regexes"] = value(regexes);
+            updater["chocolatey"] = Item::Table(chocolatey);
+        }
+        document["updater"] = Item::Table(updater);
+
+        document.to_string()
+    }
+
+    /// Creates the package data file, and the script templates when
+    /// requested, in a directory named after the identifier of the package
+    /// inside the specified directory. Returns the path of the created
+    /// package data file, or an error if the file already exists and `force`
+    /// have not been specified.
+    pub fn create(&self, dir: &Path, force: bool) -> std::io::Result<PathBuf> {
+        let package_dir = dir.join(&self.id);
+        let path = package_dir.join(format!("{}.aer.toml", self.id));
+        if path.exists() && !force {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("The package file '{}' already exists!", path.display()),
+            ));
+        }
+
+        std::fs::create_dir_all(&package_dir)?;
+        std::fs::write(&path, self.to_toml())?;
+        info!("Created the package file '{}'", path.display());
+
+        if self.has_templates() {
+            let template = package_dir.join(INSTALL_TEMPLATE);
+            if !template.exists() || force {
+                if let Some(parent) = template.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&template, INSTALL_SCRIPT)?;
+                info!("Created the script template '{}'", template.display());
+            }
+        }
+
+        Ok(path)
+    }
+
+    fn has_templates(&self) -> bool {
+        self.templates && self.targets.contains(&Target::Chocolatey)
+    }
+}
+
+/// Asks the question on the output, and returns the answer read from the
+/// input. The default value is returned when the answer is empty, or when no
+/// more input is available.
+pub fn prompt<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    question: &str,
+    default: &str,
+) -> std::io::Result<String> {
+    if default.is_empty() {
+        write!(output, "{}: ", question)?;
+    } else {
+        write!(output, "{} [{}]: ", question, default)?;
+    }
+    output.flush()?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    let answer = answer.trim();
+
+    Ok(if answer.is_empty() {
+        default.to_owned()
+    } else {
+        answer.to_owned()
+    })
+}
+
+fn array(values: &[String]) -> Array {
+    values.iter().map(String::as_str).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_upd::parsers;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn repository() -> GitHubRepository {
+        GitHubRepository {
+            name: "owner/repo".into(),
+            description: Some("A tool for testing".into()),
+            homepage: None,
+            html_url: Url::parse("https://github.com/owner/repo").unwrap(),
+            license: Some("MIT".into()),
+            topics: vec!["cli".into()],
+        }
+    }
+
+    #[rstest(
+        value,
+        expected,
+        case("chocolatey", Target::Chocolatey),
+        case("Choco", Target::Chocolatey),
+        case("nuget", Target::Nuget),
+        case("winget", Target::Winget)
+    )]
+    fn from_str_should_parse_target(value: &str, expected: Target) {
+        let actual: Target = value.parse().unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest(
+        input,
+        default,
+        expected,
+        case("my-package\n", "", "my-package"),
+        case("  \n", "chocolatey", "chocolatey"),
+        case("", "AdmiringWorm", "AdmiringWorm")
+    )]
+    fn prompt_should_return_answer_or_default(input: &str, default: &str, expected: &str) {
+        let mut output = vec![];
+
+        let actual = prompt(&mut input.as_bytes(), &mut output, "Question", default).unwrap();
+
+        assert_eq!(actual, expected);
+        assert!(String::from_utf8(output).unwrap().starts_with("Question"));
+    }
+
+    #[test]
+    fn use_repository_should_fill_missing_values() {
+        let mut scaffold = Scaffold::new("repo");
+        scaffold.license = Some("Apache-2.0".into());
+        let version = Versions::parse("1.2.3").unwrap();
+
+        scaffold.use_repository(&repository(), Some(&version));
+
+        assert_eq!(scaffold.repository.as_deref(), Some("owner/repo"));
+        assert_eq!(scaffold.summary.as_deref(), Some("A tool for testing"));
+        assert_eq!(scaffold.license.as_deref(), Some("Apache-2.0"));
+        assert_eq!(scaffold.authors, ["owner"]);
+        assert_eq!(scaffold.tags, ["cli"]);
+        assert_eq!(scaffold.version, Versions::parse("1.2.3").ok());
+    }
+
+    #[test]
+    fn create_should_write_readable_package_data() {
+        let dir = std::env::temp_dir().join("aer-scaffold-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut scaffold = Scaffold::new("test-package");
+        scaffold.maintainers = vec!["AdmiringWorm".into()];
+        scaffold.targets = vec![Target::Chocolatey, Target::Nuget, Target::Winget];
+        scaffold.templates = true;
+        scaffold.use_repository(&repository(), None);
+
+        let path = scaffold.create(&dir, false).unwrap();
+        let actual = parsers::read_file(&path);
+        let template_exists = dir.join("test-package").join(INSTALL_TEMPLATE).is_file();
+        let second = scaffold.create(&dir, false);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let actual = actual.unwrap();
+        assert_eq!(actual.metadata().id(), "test-package");
+        assert_eq!(actual.metadata().maintainers(), ["AdmiringWorm"]);
+        assert_eq!(actual.metadata().summary, "A tool for testing");
+        assert_eq!(actual.updater().repository.as_deref(), Some("owner/repo"));
+        assert_eq!(
+            actual.updater().templates,
+            [PathBuf::from(INSTALL_TEMPLATE)]
+        );
+        assert!(template_exists);
+        assert!(second.is_err());
+    }
+
+    #[test]
+    fn to_toml_should_use_project_url_when_not_on_github() {
+        let mut scaffold = Scaffold::new("test-package");
+        scaffold.project_url = Some(Url::parse("https://example.org/downloads").unwrap());
+
+        let actual = scaffold.to_toml();
+
+        assert!(actual.contains("project_url = \"https://example.org/downloads\""));
+        assert!(actual.contains("parse_url = \"https://example.org/downloads\""));
+        assert!(!actual.contains("source = "));
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for deciding if a package is due to be checked for
+//! updates, based on the check frequency declared in the updater data and the
+//! last time the package was checked.
+
+use std::str::FromStr;
+
+use aer_upd::data::CheckFrequency;
+use chrono::{DateTime, Duration, Utc};
+use cron::Schedule;
+
+/// The jitter subtracted from a fixed interval, in percent of the interval.
+const JITTER_PERCENT: u32 = 10;
+
+/// The maximum jitter subtracted from the next time of a cron expression.
+const MAX_CRON_JITTER_SECS: u64 = 15 * 60;
+
+/// Returns `true` if the package with the specified identifier should be
+/// checked at the time of `now`.
+///
+/// A small jitter, derived from the package identifier, is subtracted from the
+/// next time a package is due. This spreads out the checks of packages using
+/// the same frequency, instead of every package hitting vendors at the same
+/// time, while a package is never checked later than its frequency allows
+/// (*ie: a daily package is always due when the next daily run happens*).
+pub fn is_due(
+    id: &str,
+    frequency: &CheckFrequency,
+    last_checked: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Result<bool, cron::error::Error> {
+    let last_checked = match last_checked {
+        Some(last_checked) if *frequency != CheckFrequency::Always => last_checked,
+        _ => return Ok(true),
+    };
+
+    let next = if let Some(interval) = frequency.interval() {
+        let jitter = jitter(id, interval.as_secs() * JITTER_PERCENT as u64 / 100);
+        last_checked
+            + Duration::from_std(interval.saturating_sub(jitter))
+                .unwrap_or_else(|_| Duration::zero())
+    } else if let CheckFrequency::Cron(expression) = frequency {
+        let schedule = parse_cron(expression)?;
+        match schedule.after(&last_checked).next() {
+            Some(next) => {
+                let next = next
+                    - Duration::from_std(jitter(id, MAX_CRON_JITTER_SECS))
+                        .unwrap_or_else(|_| Duration::zero());
+                // The jitter may never make the package due before it was last checked.
+                next.max(last_checked + Duration::seconds(1))
+            }
+            None => return Ok(false),
+        }
+    } else {
+        return Ok(true);
+    };
+
+    Ok(now >= next)
+}
+
+/// Returns a stable jitter between zero and `max_secs` for the specified
+/// package identifier. The same identifier always gets the same jitter.
+pub fn jitter(id: &str, max_secs: u64) -> std::time::Duration {
+    if max_secs == 0 {
+        return std::time::Duration::from_secs(0);
+    }
+
+    // FNV-1a, used as the hash needs to be stable between runs.
+    let hash = id.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+
+    std::time::Duration::from_secs(hash % (max_secs + 1))
+}
+
+/// Parses the cron expression, allowing the common five field syntax in
+/// addition to the six and seven field syntax that includes seconds.
+fn parse_cron(expression: &str) -> Result<Schedule, cron::error::Error> {
+    if expression.split_whitespace().count() == 5 {
+        Schedule::from_str(&format!("0 {}", expression))
+    } else {
+        Schedule::from_str(expression)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn date(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.ymd(2021, 6, day).and_hms(hour, 0, 0)
+    }
+
+    #[test]
+    fn is_due_should_be_true_when_never_checked() {
+        let actual = is_due("test", &CheckFrequency::Weekly, None, date(1, 0)).unwrap();
+
+        assert!(actual);
+    }
+
+    #[test]
+    fn is_due_should_always_be_true_for_always_frequency() {
+        let actual = is_due(
+            "test",
+            &CheckFrequency::Always,
+            Some(date(1, 0)),
+            date(1, 0),
+        )
+        .unwrap();
+
+        assert!(actual);
+    }
+
+    #[rstest(
+        frequency,
+        now,
+        expected,
+        case(CheckFrequency::Daily, date(1, 12), false),
+        case(CheckFrequency::Daily, date(1, 21), false),
+        case(CheckFrequency::Daily, date(2, 0), true),
+        case(CheckFrequency::Weekly, date(5, 0), false),
+        case(CheckFrequency::Weekly, date(8, 0), true),
+        case(CheckFrequency::Cron("0 4 * * *".into()), date(1, 2), false),
+        case(CheckFrequency::Cron("0 4 * * *".into()), date(1, 6), true)
+    )]
+    fn is_due_should_compare_against_last_checked(
+        frequency: CheckFrequency,
+        now: DateTime<Utc>,
+        expected: bool,
+    ) {
+        let actual = is_due("test", &frequency, Some(date(1, 0)), now).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest(
+        id,
+        case("7zip"),
+        case("chocolatey"),
+        case("notepadplusplus"),
+        case("some-package")
+    )]
+    fn is_due_should_be_true_once_daily_interval_have_passed(id: &str) {
+        let last_checked = date(1, 0);
+
+        let before = is_due(
+            id,
+            &CheckFrequency::Daily,
+            Some(last_checked),
+            last_checked + Duration::hours(21),
+        )
+        .unwrap();
+        let after = is_due(
+            id,
+            &CheckFrequency::Daily,
+            Some(last_checked),
+            last_checked + Duration::hours(24),
+        )
+        .unwrap();
+
+        assert!(!before);
+        assert!(after);
+    }
+
+    #[test]
+    fn is_due_should_be_true_at_next_cron_time() {
+        let actual = is_due(
+            "test",
+            &CheckFrequency::Cron("0 4 * * *".into()),
+            Some(date(1, 4)),
+            date(2, 4),
+        )
+        .unwrap();
+
+        assert!(actual);
+    }
+
+    #[test]
+    fn is_due_should_return_error_on_invalid_cron_expression() {
+        let actual = is_due(
+            "test",
+            &CheckFrequency::Cron("not a cron".into()),
+            Some(date(1, 0)),
+            date(2, 0),
+        );
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn jitter_should_be_stable_and_within_max() {
+        let first = jitter("some-package", 60);
+        let second = jitter("some-package", 60);
+
+        assert_eq!(first, second);
+        assert!(first.as_secs() <= 60);
+    }
+}
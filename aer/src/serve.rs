@@ -0,0 +1,395 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains a simple JSON-RPC server exposing the operations of the update
+//! pipeline, allowing graphical front-ends and editors to integrate with the
+//! application without parsing the log output.
+//!
+//! Every request and response is a single JSON-RPC 2.0 object written on its
+//! own line. The following methods are available:
+//!
+//! - `validate` (`{ "path": "<file>" }`): Checks the readiness of a package.
+//! - `check-version` (`{ "path": "<file>" }`): Finds the newest version and
+//!   links of a package.
+//! - `update` (`{ "path": "<file>" }`): Same as `check-version`, but also
+//!   records the check in the state directory.
+//! - `download` (`{ "url": "<url>" }`): Downloads a file to the work directory,
+//!   and returns the path and checksum of the file.
+//! - `shutdown`: Stops the server after the response have been written.
+
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use aer_upd::parsers;
+use aer_upd::web::{ResponseType, WebResponse};
+use log::{debug, info};
+use serde_json::{json, Value};
+
+use crate::context::Context;
+use crate::state::CheckState;
+use crate::{readiness, simulate, update, ChecksumType};
+
+/// The error code used when the request is not valid JSON.
+pub const PARSE_ERROR: i64 = -32700;
+/// The error code used when the request is not a valid JSON-RPC request.
+pub const INVALID_REQUEST: i64 = -32600;
+/// The error code used when the requested method do not exist.
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// The error code used when the parameters of the request are not valid.
+pub const INVALID_PARAMS: i64 = -32602;
+/// The error code used when the operation itself failed.
+pub const OPERATION_FAILED: i64 = -32000;
+
+/// The value that can be used instead of a socket path to serve requests on
+/// the standard input and output.
+pub const STDIO: &str = "-";
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new<M: ToString>(code: i64, message: M) -> RpcError {
+        RpcError {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Handles the JSON-RPC requests sent to the server.
+pub struct Server {
+    context: Context,
+    stopped: bool,
+}
+
+impl Server {
+    /// Creates a new server that will use the specified context for every
+    /// operation.
+    pub fn new(context: Context) -> Server {
+        Server {
+            context,
+            stopped: false,
+        }
+    }
+
+    /// Returns wether a `shutdown` request have been handled.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Handles a single line containing a JSON-RPC request, and returns the
+    /// response that should be written back. No response is returned for
+    /// notifications (requests without an id).
+    pub fn handle(&mut self, line: &str) -> Option<String> {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(err) => return Some(error_response(Value::Null, RpcError::new(PARSE_ERROR, err))),
+        };
+
+        let id = request.get("id").cloned();
+        let method = match request.get("method").and_then(Value::as_str) {
+            Some(method) if request.get("jsonrpc") == Some(&json!("2.0")) => method,
+            _ => {
+                return Some(error_response(
+                    id.unwrap_or(Value::Null),
+                    RpcError::new(
+                        INVALID_REQUEST,
+                        "The request is not a JSON-RPC 2.0 request!",
+                    ),
+                ))
+            }
+        };
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+        debug!("Handling '{}' request", method);
+        let result = self.call(method, &params);
+
+        let id = id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }).to_string(),
+            Err(err) => error_response(id, err),
+        })
+    }
+
+    fn call(&mut self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        match method {
+            "validate" => self.validate(&path_param(params)?),
+            "check-version" => self.check_version(&path_param(params)?, false),
+            "update" => self.check_version(&path_param(params)?, true),
+            "download" => self.download(&string_param(params, "url")?),
+            "shutdown" => {
+                self.stopped = true;
+                Ok(Value::Null)
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("The method '{}' do not exist!", method),
+            )),
+        }
+    }
+
+    fn validate(&self, path: &Path) -> Result<Value, RpcError> {
+        let data = parsers::read_file(path).map_err(|err| RpcError::new(OPERATION_FAILED, err))?;
+        let report = readiness::check_readiness(&data);
+
+        let checks: Vec<Value> = report
+            .checks()
+            .iter()
+            .map(|check| {
+                json!({
+                    "name": check.name,
+                    "status": check.status.to_string(),
+                    "message": check.message,
+                })
+            })
+            .collect();
+
+        Ok(json!({
+            "id": data.metadata().id(),
+            "ready": report.is_ready(),
+            "score": report.score(),
+            "checks": checks,
+        }))
+    }
+
+    fn check_version(&self, path: &Path, record: bool) -> Result<Value, RpcError> {
+        let update_context = self.context.update_context();
+        let data = parsers::read_file_in(&update_context, path)
+            .map_err(|err| RpcError::new(OPERATION_FAILED, err))?;
+        let id = data.metadata().id();
+
+        let found = update::find_links(&update_context.for_package(id), &data)
+            .map_err(|err| RpcError::new(OPERATION_FAILED, err))?;
+
+        let version = found.version().map(|v| v.to_string());
+
+        if record {
+            let now = self.context.clock().now();
+            let mut state = CheckState::load(&self.context.data_root().state_dir())
+                .map_err(|err| RpcError::new(OPERATION_FAILED, err))?;
+            state.record(id, now);
+            state.record_success(id, version.as_deref(), now);
+            state
+                .save()
+                .map_err(|err| RpcError::new(OPERATION_FAILED, err))?;
+        }
+
+        Ok(json!({
+            "id": id,
+            "version": version,
+            "links": simulate::outcome_of(&found),
+        }))
+    }
+
+    fn download(&self, url: &str) -> Result<Value, RpcError> {
+        let update_context = self.context.update_context();
+        let response = update_context
+            .request()
+            .get_binary_response(url, None, None)
+            .map_err(|err| RpcError::new(OPERATION_FAILED, err))?;
+
+        match response {
+            ResponseType::Updated(status) => Ok(json!({ "status": status })),
+            ResponseType::New(mut response, status) => {
+                response.set_work_dir(update_context.work_dir());
+                let path = response
+                    .read(None)
+                    .map_err(|err| RpcError::new(OPERATION_FAILED, err))?;
+                let checksum_type = ChecksumType::default();
+                let checksum = checksum_type
+                    .generate(&path)
+                    .map_err(|err| RpcError::new(OPERATION_FAILED, err))?;
+
+                Ok(json!({
+                    "status": status,
+                    "path": path,
+                    "checksum": checksum,
+                    "checksum_type": checksum_type.to_string(),
+                }))
+            }
+        }
+    }
+}
+
+/// Reads requests from the reader, and writes the responses to the writer
+/// until the reader is closed or a `shutdown` request have been handled.
+pub fn serve_stream<R: BufRead, W: Write>(
+    server: &mut Server,
+    reader: R,
+    mut writer: W,
+) -> Result<(), std::io::Error> {
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(response) = server.handle(&line) {
+            writeln!(writer, "{}", response)?;
+            writer.flush()?;
+        }
+
+        if server.is_stopped() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Serves requests on the specified unix socket, or on the standard input
+/// and output when the path is `-`. Connections are handled one at a time.
+pub fn serve(server: &mut Server, ipc: &Path) -> Result<(), std::io::Error> {
+    if ipc == Path::new(STDIO) {
+        let stdin = std::io::stdin();
+        return serve_stream(server, stdin.lock(), std::io::stdout());
+    }
+
+    serve_socket(server, ipc)
+}
+
+#[cfg(unix)]
+fn serve_socket(server: &mut Server, ipc: &Path) -> Result<(), std::io::Error> {
+    use std::os::unix::net::UnixListener;
+
+    if ipc.exists() {
+        std::fs::remove_file(ipc)?;
+    }
+    let listener = UnixListener::bind(ipc)?;
+    info!("Listening for requests on '{}'", ipc.display());
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        serve_stream(server, BufReader::new(stream.try_clone()?), stream)?;
+
+        if server.is_stopped() {
+            break;
+        }
+    }
+
+    let _ = std::fs::remove_file(ipc);
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn serve_socket(_server: &mut Server, ipc: &Path) -> Result<(), std::io::Error> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        format!(
+            "Serving on the socket '{}' is not supported on this platform, use '{}' to serve on \
+             the standard input and output instead!",
+            ipc.display(),
+            STDIO
+        ),
+    ))
+}
+
+fn error_response(id: Value, error: RpcError) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+    .to_string()
+}
+
+fn string_param(params: &Value, name: &str) -> Result<String, RpcError> {
+    params
+        .get(name)
+        .and_then(Value::as_str)
+        .map(String::from)
+        .ok_or_else(|| {
+            RpcError::new(
+                INVALID_PARAMS,
+                format!("The parameter '{}' is required!", name),
+            )
+        })
+}
+
+fn path_param(params: &Value) -> Result<PathBuf, RpcError> {
+    string_param(params, "path").map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(server: &mut Server, request: &str) -> Value {
+        serde_json::from_str(&server.handle(request).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn handle_should_return_parse_error_on_invalid_json() {
+        let mut server = Server::new(Context::default());
+
+        let actual = response(&mut server, "{ invalid");
+
+        assert_eq!(actual["error"]["code"], PARSE_ERROR);
+        assert_eq!(actual["id"], Value::Null);
+    }
+
+    #[test]
+    fn handle_should_return_error_on_unknown_method() {
+        let mut server = Server::new(Context::default());
+
+        let actual = response(
+            &mut server,
+            r#"{"jsonrpc":"2.0","id":1,"method":"unknown"}"#,
+        );
+
+        assert_eq!(actual["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(actual["id"], 1);
+    }
+
+    #[test]
+    fn handle_should_return_error_on_missing_params() {
+        let mut server = Server::new(Context::default());
+
+        let actual = response(
+            &mut server,
+            r#"{"jsonrpc":"2.0","id":2,"method":"validate"}"#,
+        );
+
+        assert_eq!(actual["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[test]
+    fn handle_should_not_respond_to_notifications() {
+        let mut server = Server::new(Context::default());
+
+        let actual = server.handle(r#"{"jsonrpc":"2.0","method":"shutdown"}"#);
+
+        assert_eq!(actual, None);
+        assert!(server.is_stopped());
+    }
+
+    #[test]
+    fn handle_should_validate_package() {
+        let mut server = Server::new(Context::default());
+
+        let actual = response(
+            &mut server,
+            r#"{"jsonrpc":"2.0","id":"a","method":"validate","params":{"path":"test-data/simulate/package.aer.toml"}}"#,
+        );
+
+        assert_eq!(actual["id"], "a");
+        assert_eq!(actual["result"]["id"], "simulate-test");
+        assert!(actual["result"]["checks"].is_array());
+    }
+
+    #[test]
+    fn serve_stream_should_stop_on_shutdown() {
+        let mut server = Server::new(Context::default());
+        let input = "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"shutdown\"}\n{\"jsonrpc\":\"2.0\",\
+                     \"id\":2,\"method\":\"shutdown\"}\n";
+        let mut output = vec![];
+
+        serve_stream(&mut server, input.as_bytes(), &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output, "{\"id\":1,\"jsonrpc\":\"2.0\",\"result\":null}\n");
+    }
+}
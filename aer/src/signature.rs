@@ -0,0 +1,473 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the verification of the signatures of downloaded files, as
+//! configured in the `[updater.signature]` section of the package data.
+//!
+//! Detached GPG signatures are downloaded from the url of the file with the
+//! configured extension appended (*ie: `setup.exe.asc`*), and are verified
+//! using the `gpg` executable against the public keys of the package only.
+//! The Authenticode signatures of installers are verified using the
+//! `Get-AuthenticodeSignature` cmdlet, which is only available on Windows.
+
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use aer_upd::context::UpdateContext;
+use aer_upd::data::SignatureData;
+use aer_upd::web::{LinkElement, ResponseType, WebResponse};
+
+use crate::codes::{self, CodedError};
+
+/// The executable used when verifying GPG signatures.
+const GPG_EXEC: &str = "gpg";
+
+/// The extensions of the files that can contain an Authenticode signature.
+const AUTHENTICODE_EXTENSIONS: &[&str] = &["exe", "msi", "msix", "dll"];
+
+/// The kind of signature that was verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureKind {
+    /// A detached GPG signature.
+    Gpg,
+    /// An Authenticode signature embedded in the file.
+    Authenticode,
+}
+
+impl Display for SignatureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureKind::Gpg => f.write_str("GPG"),
+            SignatureKind::Authenticode => f.write_str("Authenticode"),
+        }
+    }
+}
+
+/// The outcome of verifying a signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureStatus {
+    /// The signature is valid, holding the signer of the file when known.
+    Valid(Option<String>),
+    /// The signature is invalid (*ie: the file have been changed after it was
+    /// signed, or it was not signed by any of the trusted keys*).
+    Invalid(String),
+    /// The signature could not be verified (*ie: the signature could not be
+    /// downloaded, or the tools needed are not available*).
+    Unverified(String),
+}
+
+/// The result of verifying a single signature of a downloaded file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureCheck {
+    /// The kind of signature that was verified.
+    pub kind: SignatureKind,
+    /// The outcome of the verification.
+    pub status: SignatureStatus,
+}
+
+impl SignatureCheck {
+    /// Returns wether the signature was successfully verified.
+    pub fn is_valid(&self) -> bool {
+        matches!(self.status, SignatureStatus::Valid(_))
+    }
+}
+
+impl Display for SignatureCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.status {
+            SignatureStatus::Valid(Some(ref signer)) => {
+                write!(f, "Valid {} signature from '{}'", self.kind, signer)
+            }
+            SignatureStatus::Valid(None) => write!(f, "Valid {} signature", self.kind),
+            SignatureStatus::Invalid(ref reason) => {
+                write!(f, "Invalid {} signature: {}", self.kind, reason)
+            }
+            SignatureStatus::Unverified(ref reason) => {
+                write!(f, "Unable to verify {} signature: {}", self.kind, reason)
+            }
+        }
+    }
+}
+
+/// Verifies the signatures of the downloaded file using the specified
+/// settings, relative key paths are resolved against the base directory. The
+/// result of every check is reported to the logger of the context, and an
+/// error is returned when signatures are required and any of the signatures
+/// are not valid.
+pub fn verify_file(
+    context: &UpdateContext,
+    settings: &SignatureData,
+    base_dir: &Path,
+    link: &LinkElement,
+    path: &Path,
+) -> Result<Vec<SignatureCheck>, CodedError> {
+    let mut checks = vec![];
+
+    if !settings.gpg_keys.is_empty() {
+        let keys: Vec<PathBuf> = settings
+            .gpg_keys
+            .iter()
+            .map(|key| base_dir.join(key))
+            .collect();
+        let status = match download_signature(context, link, path, &settings.extension) {
+            Ok(signature) => {
+                let status = verify_gpg(path, &signature, &keys);
+                let _ = std::fs::remove_file(&signature);
+                status
+            }
+            Err(err) => SignatureStatus::Unverified(err),
+        };
+        checks.push(SignatureCheck {
+            kind: SignatureKind::Gpg,
+            status,
+        });
+    }
+
+    if settings.authenticode && has_authenticode_extension(path) {
+        checks.push(SignatureCheck {
+            kind: SignatureKind::Authenticode,
+            status: verify_authenticode(path),
+        });
+    }
+
+    let name = path
+        .file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy();
+    for check in &checks {
+        if check.is_valid() {
+            context
+                .logger()
+                .info(format_args!("{} ('{}')", check, name));
+        } else {
+            context
+                .logger()
+                .warn(format_args!("{} ('{}')", check, name));
+        }
+    }
+
+    match checks.iter().find(|check| !check.is_valid()) {
+        Some(check) if settings.required => Err(CodedError::new(
+            codes::INVALID_SIGNATURE,
+            format!("{} ('{}')", check, name),
+        )),
+        _ => Ok(checks),
+    }
+}
+
+/// Downloads the detached signature of the file to the work directory.
+fn download_signature(
+    context: &UpdateContext,
+    link: &LinkElement,
+    path: &Path,
+    extension: &str,
+) -> Result<PathBuf, String> {
+    let url = format!("{}{}", link.link, extension);
+    let name = format!(
+        "{}{}",
+        path.file_name()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy(),
+        extension
+    );
+
+    match context.request().get_binary_response(&url, None, None) {
+        Ok(ResponseType::New(mut response, _)) => {
+            response.set_work_dir(context.work_dir());
+            response
+                .read(Some(&name))
+                .map_err(|err| format!("The signature '{}' could not be downloaded: {}", url, err))
+        }
+        Ok(ResponseType::Updated(status)) => Err(format!(
+            "The server responded with status {} when downloading '{}'",
+            status, url
+        )),
+        Err(err) => Err(format!(
+            "The signature '{}' could not be downloaded: {}",
+            url, err
+        )),
+    }
+}
+
+/// Verifies the detached GPG signature of the file, only the specified public
+/// keys are trusted. The keys are imported into a temporary home directory,
+/// leaving the keyrings of the user untouched.
+pub fn verify_gpg(file: &Path, signature: &Path, keys: &[PathBuf]) -> SignatureStatus {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let home = std::env::temp_dir().join(format!(
+        "aer-gpg-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let status = verify_gpg_in(&home, file, signature, keys);
+    let _ = std::fs::remove_dir_all(&home);
+
+    status
+}
+
+fn verify_gpg_in(home: &Path, file: &Path, signature: &Path, keys: &[PathBuf]) -> SignatureStatus {
+    if let Err(err) = std::fs::create_dir_all(home) {
+        return SignatureStatus::Unverified(format!(
+            "The temporary GPG home directory could not be created: {}",
+            err
+        ));
+    }
+
+    let output = gpg_command(home).arg("--import").args(keys).output();
+    match output {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => {
+            return SignatureStatus::Unverified(format!(
+                "The public keys could not be imported: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+        }
+        Err(err) => {
+            return SignatureStatus::Unverified(format!("No gpg executable was found! ({})", err))
+        }
+    }
+
+    let output = gpg_command(home)
+        .args(["--status-fd", "1", "--verify"])
+        .arg(signature)
+        .arg(file)
+        .output();
+
+    match output {
+        Ok(output) => parse_gpg_status(
+            &String::from_utf8_lossy(&output.stdout),
+            &String::from_utf8_lossy(&output.stderr),
+        ),
+        Err(err) => SignatureStatus::Unverified(format!("No gpg executable was found! ({})", err)),
+    }
+}
+
+fn gpg_command(home: &Path) -> Command {
+    let mut cmd = Command::new(GPG_EXEC);
+    cmd.arg("--homedir").arg(home).args(["--batch", "--no-tty"]);
+    cmd
+}
+
+/// Parses the status lines written by `gpg --status-fd`, and returns the
+/// outcome of the verification.
+fn parse_gpg_status(status: &str, stderr: &str) -> SignatureStatus {
+    for line in status.lines() {
+        let mut parts = line.trim().splitn(4, ' ');
+        if parts.next() != Some("[GNUPG:]") {
+            continue;
+        }
+        let keyword = parts.next().unwrap_or_default();
+        let _key_id = parts.next();
+        let user = parts.next().map(String::from);
+
+        match keyword {
+            "GOODSIG" => return SignatureStatus::Valid(user),
+            "BADSIG" => {
+                return SignatureStatus::Invalid(format!(
+                    "The file have been changed after it was signed by '{}'",
+                    user.unwrap_or_default()
+                ))
+            }
+            "EXPKEYSIG" | "REVKEYSIG" => {
+                return SignatureStatus::Invalid(format!(
+                    "The key of '{}' have expired or been revoked",
+                    user.unwrap_or_default()
+                ))
+            }
+            "NO_PUBKEY" => {
+                return SignatureStatus::Invalid(
+                    "The file was not signed by any of the configured keys".into(),
+                )
+            }
+            _ => {}
+        }
+    }
+
+    SignatureStatus::Unverified(stderr.trim().to_string())
+}
+
+fn has_authenticode_extension(path: &Path) -> bool {
+    path.extension()
+        .map(|extension| {
+            AUTHENTICODE_EXTENSIONS
+                .iter()
+                .any(|&ext| extension.eq_ignore_ascii_case(ext))
+        })
+        .unwrap_or(false)
+}
+
+/// Verifies the Authenticode signature of the file, this is only supported on
+/// Windows.
+pub fn verify_authenticode(file: &Path) -> SignatureStatus {
+    if !cfg!(windows) {
+        return SignatureStatus::Unverified(
+            "Authenticode signatures can only be verified on Windows".into(),
+        );
+    }
+
+    let output = Command::new("powershell")
+        .env("AER_SIGNED_FILE", file)
+        .args([
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            "$signature = Get-AuthenticodeSignature -LiteralPath $env:AER_SIGNED_FILE; \
+             Write-Output $signature.Status; Write-Output $signature.StatusMessage; Write-Output \
+             $signature.SignerCertificate.Subject",
+        ])
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_authenticode_output(&String::from_utf8_lossy(&output.stdout))
+        }
+        Ok(output) => {
+            SignatureStatus::Unverified(String::from_utf8_lossy(&output.stderr).trim().to_string())
+        }
+        Err(err) => {
+            SignatureStatus::Unverified(format!("No powershell executable was found! ({})", err))
+        }
+    }
+}
+
+/// Parses the status, the status message and the subject of the signer that
+/// is written when verifying an Authenticode signature.
+fn parse_authenticode_output(output: &str) -> SignatureStatus {
+    let mut lines = output.lines().map(str::trim);
+    let status = lines.next().unwrap_or_default();
+    let message = lines.next().unwrap_or_default();
+    let signer = lines.next().filter(|signer| !signer.is_empty());
+
+    match status {
+        "Valid" => SignatureStatus::Valid(signer.map(String::from)),
+        "" => SignatureStatus::Unverified("No signature status was returned".into()),
+        status if message.is_empty() => SignatureStatus::Invalid(status.to_string()),
+        status => SignatureStatus::Invalid(format!("{} ({})", message, status)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    const TEST_DIR: &str = "test-data/signature";
+
+    fn test_path(name: &str) -> PathBuf {
+        PathBuf::from(TEST_DIR).join(name)
+    }
+
+    #[test]
+    fn verify_gpg_should_accept_valid_signature() {
+        let actual = verify_gpg(
+            &test_path("signed.txt"),
+            &test_path("signed.txt.asc"),
+            &[test_path("release-key.asc")],
+        );
+
+        assert_eq!(
+            actual,
+            SignatureStatus::Valid(Some("aer test <test@example.org>".into()))
+        );
+    }
+
+    #[rstest(
+        file,
+        key,
+        case("tampered.txt", "release-key.asc"),
+        case("signed.txt", "other-key.asc")
+    )]
+    fn verify_gpg_should_reject_invalid_signatures(file: &str, key: &str) {
+        let actual = verify_gpg(
+            &test_path(file),
+            &test_path(&format!("{}.asc", file)),
+            &[test_path(key)],
+        );
+
+        assert!(
+            matches!(actual, SignatureStatus::Invalid(_)),
+            "{:?}",
+            actual
+        );
+    }
+
+    #[rstest(
+        status,
+        expected,
+        case(
+            "[GNUPG:] NEWSIG\n[GNUPG:] GOODSIG 1F322C0A20F73671 aer test <test@example.org>",
+            SignatureStatus::Valid(Some("aer test <test@example.org>".into()))
+        ),
+        case(
+            "[GNUPG:] EXPKEYSIG 1F322C0A20F73671 aer test",
+            SignatureStatus::Invalid("The key of 'aer test' have expired or been revoked".into())
+        ),
+        case("", SignatureStatus::Unverified("gpg: no valid OpenPGP data found.".into()))
+    )]
+    fn parse_gpg_status_should_return_expected_status(status: &str, expected: SignatureStatus) {
+        let actual = parse_gpg_status(status, "gpg: no valid OpenPGP data found.\n");
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest(
+        output,
+        expected,
+        case(
+            "Valid\nSignature verified.\nCN=WormieCorp\n",
+            SignatureStatus::Valid(Some("CN=WormieCorp".into()))
+        ),
+        case(
+            "NotSigned\nThe file is not digitally signed.\n\n",
+            SignatureStatus::Invalid("The file is not digitally signed. (NotSigned)".into())
+        ),
+        case("", SignatureStatus::Unverified("No signature status was returned".into()))
+    )]
+    fn parse_authenticode_output_should_return_expected_status(
+        output: &str,
+        expected: SignatureStatus,
+    ) {
+        assert_eq!(parse_authenticode_output(output), expected);
+    }
+
+    #[rstest(
+        name,
+        expected,
+        case("setup.exe", true),
+        case("SETUP.MSI", true),
+        case("archive.zip", false),
+        case("README", false)
+    )]
+    fn has_authenticode_extension_should_only_accept_signable_files(name: &str, expected: bool) {
+        assert_eq!(has_authenticode_extension(Path::new(name)), expected);
+    }
+
+    #[test]
+    fn verify_file_should_return_error_when_required_signature_is_invalid() {
+        let mut settings = SignatureData::new();
+        settings.authenticode = true;
+        settings.required = true;
+        let link = LinkElement::new(
+            aer_upd::data::Url::parse("https://example.org/setup.exe").unwrap(),
+            aer_upd::web::LinkType::Binary,
+        );
+        let context = UpdateContext::new();
+        let path = Path::new("setup.exe");
+
+        let actual = verify_file(&context, &settings, Path::new("."), &link, path);
+
+        if cfg!(windows) {
+            assert!(actual.is_err());
+        } else {
+            assert_eq!(
+                actual.unwrap_err().to_string(),
+                "Unable to verify Authenticode signature: Authenticode signatures can only be \
+                 verified on Windows ('setup.exe')"
+            );
+        }
+    }
+}
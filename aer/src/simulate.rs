@@ -0,0 +1,139 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for comparing the outcome of an update run against
+//! recorded fixtures, with the outcome that is expected for the package.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::update::FoundLinks;
+
+/// The name of the file in the fixture directory that holds the expected
+/// outcome.
+pub const EXPECTED_FILE_NAME: &str = "expected.json";
+
+/// The outcome of an update run, stored as a flat map to make the expected
+/// file easy to read and edit by hand.
+pub type Outcome = BTreeMap<String, String>;
+
+/// Creates the outcome based on the links that was found during an update
+/// run.
+pub fn outcome_of(found: &FoundLinks) -> Outcome {
+    let mut outcome = Outcome::new();
+
+    if let Some(version) = found.version() {
+        outcome.insert("version".into(), version.to_string());
+    }
+    if let Some(ref release) = found.release {
+        outcome.insert("release".into(), release.link.to_string());
+    }
+    for (arch, link) in &found.architectures {
+        outcome.insert(arch.key().into(), link.link.to_string());
+    }
+
+    outcome
+}
+
+/// Compares the actual outcome against the expected outcome, and returns a
+/// message for every value that do not match. Only the values available in
+/// the expected outcome is compared.
+pub fn compare(expected: &Outcome, actual: &Outcome) -> Vec<String> {
+    expected
+        .iter()
+        .filter_map(|(key, expected)| match actual.get(key) {
+            Some(actual) if actual == expected => None,
+            Some(actual) => Some(format!(
+                "{}: expected '{}', but found '{}'",
+                key, expected, actual
+            )),
+            None => Some(format!(
+                "{}: expected '{}', but found nothing",
+                key, expected
+            )),
+        })
+        .collect()
+}
+
+/// Loads the expected outcome from the fixture directory.
+pub fn load_expected(dir: &Path) -> Result<Outcome, Box<dyn std::error::Error>> {
+    let text = std::fs::read_to_string(dir.join(EXPECTED_FILE_NAME))?;
+
+    Ok(serde_json::from_str(&text)?)
+}
+
+/// Saves the specified outcome as the expected outcome in the fixture
+/// directory.
+pub fn save_expected(dir: &Path, outcome: &Outcome) -> Result<(), Box<dyn std::error::Error>> {
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(
+        dir.join(EXPECTED_FILE_NAME),
+        serde_json::to_string_pretty(outcome)?,
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_upd::data::{Architecture, Url, Versions};
+    use aer_upd::web::{LinkElement, LinkType};
+
+    use super::*;
+
+    fn outcome(values: &[(&str, &str)]) -> Outcome {
+        values
+            .iter()
+            .map(|(key, val)| (key.to_string(), val.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn outcome_of_should_include_version_and_links() {
+        let mut arch64 = LinkElement::new(
+            Url::parse("https://test.com/1.2.0/x64.zip").unwrap(),
+            LinkType::Binary,
+        );
+        arch64.version = Some(Versions::parse("1.2.0").unwrap());
+        let found = FoundLinks::default().with_arch(Architecture::X64, arch64);
+
+        let actual = outcome_of(&found);
+
+        assert_eq!(
+            actual,
+            outcome(&[
+                ("arch64", "https://test.com/1.2.0/x64.zip"),
+                ("version", "1.2.0")
+            ])
+        );
+    }
+
+    #[test]
+    fn compare_should_report_mismatched_and_missing_values() {
+        let expected = outcome(&[("version", "1.2.0"), ("arch32", "https://test.com/x86.zip")]);
+        let actual = outcome(&[("version", "1.3.0"), ("arch64", "https://test.com/x64.zip")]);
+
+        let actual = compare(&expected, &actual);
+
+        assert_eq!(
+            actual,
+            [
+                "arch32: expected 'https://test.com/x86.zip', but found nothing",
+                "version: expected '1.2.0', but found '1.3.0'"
+            ]
+        );
+    }
+
+    #[test]
+    fn save_expected_should_be_loadable() {
+        let dir = std::env::temp_dir().join("aer-simulate-expected-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let expected = outcome(&[("version", "1.2.0")]);
+
+        save_expected(&dir, &expected).unwrap();
+        let actual = load_expected(&dir).unwrap();
+
+        assert_eq!(actual, expected);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,157 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the functionality for detecting packages where the automatic
+//! updating is likely to be broken, or where the upstream software is no
+//! longer maintained.
+
+use std::fmt::Display;
+
+use aer_upd::data::{PackageData, Url};
+use chrono::{DateTime, Duration, Utc};
+
+use crate::state::PackageHistory;
+
+/// The thresholds used when deciding if a package is stale.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StaleOptions {
+    /// The number of months without a new upstream version before a package
+    /// is considered stale.
+    pub months: u32,
+    /// The number of failed updates in a row before a package is considered
+    /// stale.
+    pub failures: u32,
+}
+
+impl Default for StaleOptions {
+    fn default() -> Self {
+        Self {
+            months: 6,
+            failures: 3,
+        }
+    }
+}
+
+/// The reason a package is considered stale.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StaleReason {
+    /// The upstream version have not changed since the specified time.
+    Unchanged(DateTime<Utc>),
+    /// The url used to find new versions responded with the specified status
+    /// code, signaling that the location no longer exists.
+    ParseUrlGone(u16),
+    /// The update of the package have failed the specified number of times in
+    /// a row.
+    RepeatedFailures(u32),
+}
+
+impl Display for StaleReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            StaleReason::Unchanged(changed) => write!(
+                f,
+                "The upstream version have not changed since {}",
+                changed.format("%Y-%m-%d")
+            ),
+            StaleReason::ParseUrlGone(status) => {
+                write!(f, "The parse url responded with status {}", status)
+            }
+            StaleReason::RepeatedFailures(failures) => {
+                write!(f, "The last {} updates failed", failures)
+            }
+        }
+    }
+}
+
+/// Returns the url used when finding new versions of the primary target of
+/// the package, if one have been specified.
+pub fn parse_url(data: &PackageData) -> Option<Url> {
+    data.updater()
+        .primary_urls()
+        .and_then(|urls| urls.parse_url)
+        .map(|parse_url| parse_url.url().clone())
+}
+
+/// Returns the reasons the package with the specified history is considered
+/// stale, an empty list is returned when the package is healthy. The
+/// `parse_url_status` is the status code returned when the parse url of the
+/// package was checked, if it was checked.
+pub fn stale_reasons(
+    history: &PackageHistory,
+    parse_url_status: Option<u16>,
+    now: DateTime<Utc>,
+    options: &StaleOptions,
+) -> Vec<StaleReason> {
+    let mut reasons = vec![];
+
+    if let Some(changed) = history.changed {
+        if now - changed >= Duration::days(30 * i64::from(options.months)) {
+            reasons.push(StaleReason::Unchanged(changed));
+        }
+    }
+
+    if let Some(status) = parse_url_status {
+        if status == 404 || status == 410 {
+            reasons.push(StaleReason::ParseUrlGone(status));
+        }
+    }
+
+    if options.failures > 0 && history.failures >= options.failures {
+        reasons.push(StaleReason::RepeatedFailures(history.failures));
+    }
+
+    reasons
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest(
+        changed,
+        status,
+        failures,
+        expected,
+        case(Some((2021, 5, 1)), Some(200), 0, vec![]),
+        case(None, None, 2, vec![]),
+        case(
+            Some((2020, 12, 1)),
+            None,
+            0,
+            vec![StaleReason::Unchanged(Utc.ymd(2020, 12, 1).and_hms(0, 0, 0))]
+        ),
+        case(None, Some(404), 0, vec![StaleReason::ParseUrlGone(404)]),
+        case(None, Some(500), 3, vec![StaleReason::RepeatedFailures(3)])
+    )]
+    fn stale_reasons_should_return_expected_reasons(
+        changed: Option<(i32, u32, u32)>,
+        status: Option<u16>,
+        failures: u32,
+        expected: Vec<StaleReason>,
+    ) {
+        let history = PackageHistory {
+            version: Some("1.0.0".into()),
+            changed: changed.map(|(y, m, d)| Utc.ymd(y, m, d).and_hms(0, 0, 0)),
+            failures,
+            ..PackageHistory::default()
+        };
+        let now = Utc.ymd(2021, 7, 1).and_hms(0, 0, 0);
+
+        let actual = stale_reasons(&history, status, now, &StaleOptions::default());
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn fmt_should_output_date_of_last_change() {
+        let reason = StaleReason::Unchanged(Utc.ymd(2020, 12, 1).and_hms(10, 0, 0));
+
+        assert_eq!(
+            reason.to_string(),
+            "The upstream version have not changed since 2020-12-01"
+        );
+    }
+}
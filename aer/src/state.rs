@@ -0,0 +1,542 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the state that is kept between different runs of the application.
+//!
+//! The state is stored as JSON files in the state directory. The files are
+//! first written to a temporary file next to the state file, which then
+//! replaces the state file. This way an interrupted run (*ie: a crash or a
+//! full disk*) never leaves a partially written state file behind.
+//!
+//! When the `state_db` feature is enabled, the state is instead stored in an
+//! embedded database in the state directory. The JSON files are only read
+//! when no database have been created yet, which migrates the state of
+//! earlier runs to the database on the next save.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Map, Value};
+
+#[cfg(feature = "state_db")]
+mod database;
+
+/// The name of the file holding the last time each package was checked.
+const CHECKS_FILE_NAME: &str = "last-checked.json";
+
+/// The name of the file holding the update history of each package.
+const HISTORY_FILE_NAME: &str = "history.json";
+
+/// The version of the format used when exporting the state to a single file.
+const EXPORT_VERSION: u64 = 1;
+
+/// The update history of a single package.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PackageHistory {
+    /// The last version that was found upstream.
+    pub version: Option<String>,
+    /// The time the upstream version was last seen changing.
+    pub changed: Option<DateTime<Utc>>,
+    /// The number of times the update of the package have failed since the
+    /// last successful update.
+    pub failures: u32,
+    /// The hash of the package file and the files it references, recorded on
+    /// the last successful update.
+    pub hash: Option<String>,
+    /// The entity tag of the upstream location, recorded on the last
+    /// successful update.
+    pub etag: Option<String>,
+    /// The time the package was last pushed to a feed.
+    pub pushed: Option<DateTime<Utc>>,
+    /// The version of the package that was last pushed to a feed.
+    pub pushed_version: Option<String>,
+}
+
+impl PackageHistory {
+    fn from_json(value: &Value) -> Result<PackageHistory, Box<dyn std::error::Error>> {
+        let parse_date = |key: &str| match value[key].as_str() {
+            Some(date) => {
+                DateTime::parse_from_rfc3339(date).map(|date| Some(date.with_timezone(&Utc)))
+            }
+            None => Ok(None),
+        };
+
+        Ok(PackageHistory {
+            version: value["version"].as_str().map(String::from),
+            changed: parse_date("changed")?,
+            failures: value["failures"].as_u64().unwrap_or_default() as u32,
+            hash: value["hash"].as_str().map(String::from),
+            etag: value["etag"].as_str().map(String::from),
+            pushed: parse_date("pushed")?,
+            pushed_version: value["pushed_version"].as_str().map(String::from),
+        })
+    }
+
+    /// Converts the history to a JSON value.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "version": self.version,
+            "changed": self.changed.map(|date| date.to_rfc3339()),
+            "failures": self.failures,
+            "hash": self.hash,
+            "etag": self.etag,
+            "pushed": self.pushed.map(|date| date.to_rfc3339()),
+            "pushed_version": self.pushed_version,
+        })
+    }
+}
+
+/// Holds the last time each package was checked for updates together with
+/// the update history of the packages, and is persisted as files in the state
+/// directory.
+#[derive(Debug, Default, PartialEq)]
+pub struct CheckState {
+    path: PathBuf,
+    checks: HashMap<String, DateTime<Utc>>,
+    history: HashMap<String, PackageHistory>,
+}
+
+impl CheckState {
+    /// Loads the state from the specified state directory, an empty state is
+    /// returned if no state have been saved before.
+    pub fn load(state_dir: &Path) -> Result<CheckState, Box<dyn std::error::Error>> {
+        let path = state_dir.join(CHECKS_FILE_NAME);
+        let mut state = CheckState {
+            path,
+            ..CheckState::default()
+        };
+
+        #[cfg(feature = "state_db")]
+        if database::load(state_dir, &mut state)? {
+            return Ok(state);
+        }
+
+        if state.path.exists() {
+            let text = std::fs::read_to_string(&state.path)?;
+            state.checks = parse_checks(serde_json::from_str(&text)?)?;
+        }
+
+        let history_path = state_dir.join(HISTORY_FILE_NAME);
+
+        if history_path.exists() {
+            let text = std::fs::read_to_string(&history_path)?;
+            state.history = parse_history(serde_json::from_str(&text)?)?;
+        }
+
+        Ok(state)
+    }
+
+    /// Returns the last time the package with the specified identifier was
+    /// checked, if it have been checked before.
+    pub fn last_checked(&self, id: &str) -> Option<DateTime<Utc>> {
+        self.checks.get(id).copied()
+    }
+
+    /// Records that the package with the specified identifier was checked at
+    /// the specified time.
+    pub fn record(&mut self, id: &str, checked: DateTime<Utc>) {
+        self.checks.insert(id.into(), checked);
+    }
+
+    /// Returns the identifiers of all packages that have been checked before.
+    pub fn packages(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self
+            .checks
+            .keys()
+            .chain(self.history.keys())
+            .map(String::as_str)
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+
+        ids
+    }
+
+    /// Returns the update history of the package with the specified
+    /// identifier, an empty history is returned if no update have been
+    /// recorded.
+    pub fn history(&self, id: &str) -> PackageHistory {
+        self.history.get(id).cloned().unwrap_or_default()
+    }
+
+    /// Records that the package with the specified identifier was updated
+    /// successfully, and that the specified version was found upstream. The
+    /// time the version changed is only updated when the version differs from
+    /// the previously found version.
+    pub fn record_success(&mut self, id: &str, version: Option<&str>, checked: DateTime<Utc>) {
+        let history = self.history.entry(id.into()).or_default();
+        history.failures = 0;
+
+        if let Some(version) = version {
+            if history.version.as_deref() != Some(version) {
+                history.version = Some(version.into());
+                history.changed = Some(checked);
+            }
+        }
+    }
+
+    /// Records the hash of the package files and the entity tag of the
+    /// upstream location, used to skip the package when neither have changed.
+    pub fn record_fingerprint(&mut self, id: &str, hash: Option<String>, etag: Option<String>) {
+        let history = self.history.entry(id.into()).or_default();
+        history.hash = hash;
+        history.etag = etag;
+    }
+
+    /// Records that the specified version of the package with the specified
+    /// identifier was pushed to a feed at the specified time.
+    pub fn record_push(&mut self, id: &str, version: Option<&str>, pushed: DateTime<Utc>) {
+        let history = self.history.entry(id.into()).or_default();
+        history.pushed = Some(pushed);
+        history.pushed_version = version.map(String::from);
+    }
+
+    /// Records that the update of the package with the specified identifier
+    /// failed, the recorded fingerprint is cleared to make sure the package is
+    /// processed again on the next run.
+    pub fn record_failure(&mut self, id: &str) {
+        let history = self.history.entry(id.into()).or_default();
+        history.failures += 1;
+        history.hash = None;
+        history.etag = None;
+    }
+
+    /// Saves the current state to the state directory, creating the directory
+    /// if it do not already exist.
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let state_dir = self.path.parent().unwrap_or_else(|| Path::new(""));
+        std::fs::create_dir_all(state_dir)?;
+
+        #[cfg(feature = "state_db")]
+        {
+            database::save(state_dir, self)
+        }
+
+        #[cfg(not(feature = "state_db"))]
+        {
+            write_atomic(
+                &self.path,
+                &serde_json::to_string_pretty(&self.checks_to_json())?,
+            )?;
+            write_atomic(
+                &self.path.with_file_name(HISTORY_FILE_NAME),
+                &serde_json::to_string_pretty(&self.history_to_json())?,
+            )?;
+
+            Ok(())
+        }
+    }
+
+    /// Exports the checks and the update history of all packages to a single
+    /// file, allowing the state to be cached between runs on machines that do
+    /// not keep the state directory (*ie: ephemeral CI runners*).
+    pub fn export(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let value = json!({
+            "version": EXPORT_VERSION,
+            "checks": self.checks_to_json(),
+            "history": self.history_to_json(),
+        });
+        write_atomic(path, &serde_json::to_string_pretty(&value)?)?;
+
+        Ok(())
+    }
+
+    /// Imports a state file previously created by [export](CheckState::export)
+    /// and merges it into the current state. Returns the number of packages
+    /// that were updated by the import.
+    pub fn import(&mut self, path: &Path) -> Result<usize, Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(path)?;
+        let mut value: Value = serde_json::from_str(&text)?;
+
+        match value["version"].as_u64() {
+            Some(EXPORT_VERSION) => {}
+            Some(version) => {
+                return Err(
+                    format!("The state format version {} is not supported!", version).into(),
+                )
+            }
+            None => return Err("The file is not an exported state file!".into()),
+        }
+
+        let checks = parse_checks(serde_json::from_value(value["checks"].take())?)?;
+        let history = parse_history(serde_json::from_value(value["history"].take())?)?;
+
+        Ok(self.merge(checks, history))
+    }
+
+    /// Merges the specified checks and history into the current state. The
+    /// state of a package is replaced when it was checked more recently than
+    /// the package in the current state, or when the current state do not
+    /// know of the package.
+    fn merge(
+        &mut self,
+        mut checks: HashMap<String, DateTime<Utc>>,
+        mut history: HashMap<String, PackageHistory>,
+    ) -> usize {
+        let mut ids: Vec<String> = checks.keys().chain(history.keys()).cloned().collect();
+        ids.sort_unstable();
+        ids.dedup();
+        let mut merged = 0;
+
+        for id in ids {
+            let checked = checks.remove(&id);
+            let package_history = history.remove(&id);
+
+            if checked > self.last_checked(&id) {
+                self.checks.insert(id.clone(), checked.unwrap());
+                if let Some(package_history) = package_history {
+                    self.history.insert(id, package_history);
+                }
+                merged += 1;
+            } else if let Some(package_history) = package_history {
+                if let Entry::Vacant(entry) = self.history.entry(id) {
+                    entry.insert(package_history);
+                    merged += 1;
+                }
+            }
+        }
+
+        merged
+    }
+
+    fn checks_to_json(&self) -> Map<String, Value> {
+        self.checks
+            .iter()
+            .map(|(id, date)| (id.clone(), Value::String(date.to_rfc3339())))
+            .collect()
+    }
+
+    fn history_to_json(&self) -> Map<String, Value> {
+        self.history
+            .iter()
+            .map(|(id, history)| (id.clone(), history.to_json()))
+            .collect()
+    }
+}
+
+/// Writes the content to a temporary file in the same directory as the path,
+/// and replaces the file at the path with the temporary file once the content
+/// have been flushed to the disk.
+fn write_atomic(path: &Path, content: &str) -> std::io::Result<()> {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".tmp");
+    let temp_path = path.with_file_name(file_name);
+
+    let result = std::fs::File::create(&temp_path).and_then(|mut file| {
+        file.write_all(content.as_bytes())?;
+        file.sync_all()
+    });
+    if let Err(err) = result.and_then(|_| std::fs::rename(&temp_path, path)) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+fn parse_checks(
+    values: HashMap<String, String>,
+) -> Result<HashMap<String, DateTime<Utc>>, Box<dyn std::error::Error>> {
+    let mut checks = HashMap::new();
+
+    for (id, date) in values {
+        checks.insert(id, DateTime::parse_from_rfc3339(&date)?.with_timezone(&Utc));
+    }
+
+    Ok(checks)
+}
+
+fn parse_history(
+    values: Map<String, Value>,
+) -> Result<HashMap<String, PackageHistory>, Box<dyn std::error::Error>> {
+    let mut history = HashMap::new();
+
+    for (id, value) in values {
+        history.insert(id, PackageHistory::from_json(&value)?);
+    }
+
+    Ok(history)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn load_should_return_empty_state_when_not_saved() {
+        let dir = std::env::temp_dir().join("aer-state-empty-test");
+
+        let state = CheckState::load(&dir).unwrap();
+
+        assert_eq!(state.last_checked("test-package"), None);
+    }
+
+    #[test]
+    fn save_should_persist_recorded_checks() {
+        let dir = std::env::temp_dir().join("aer-state-save-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let expected = Utc.ymd(2021, 6, 1).and_hms(12, 30, 0);
+        let mut state = CheckState::load(&dir).unwrap();
+        state.record("test-package", expected);
+
+        state.save().unwrap();
+        let actual = CheckState::load(&dir).unwrap();
+
+        assert_eq!(actual.last_checked("test-package"), Some(expected));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    #[cfg(not(feature = "state_db"))]
+    fn save_should_replace_existing_state_without_leaving_temporary_files() {
+        let dir = std::env::temp_dir().join("aer-state-replace-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let expected = Utc.ymd(2021, 7, 1).and_hms(12, 30, 0);
+        let mut state = CheckState::load(&dir).unwrap();
+        state.record("test-package", Utc.ymd(2021, 6, 1).and_hms(12, 30, 0));
+        state.save().unwrap();
+        state.record("test-package", expected);
+
+        state.save().unwrap();
+        let actual = CheckState::load(&dir).unwrap();
+        let mut files: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+
+        assert_eq!(actual.last_checked("test-package"), Some(expected));
+        assert_eq!(files, [HISTORY_FILE_NAME, CHECKS_FILE_NAME]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    #[cfg(feature = "state_db")]
+    fn save_should_migrate_state_files_to_database() {
+        let dir = std::env::temp_dir().join("aer-state-database-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join(CHECKS_FILE_NAME),
+            r#"{ "test-package": "2021-06-01T12:30:00+00:00" }"#,
+        )
+        .unwrap();
+        let expected = Utc.ymd(2021, 7, 1).and_hms(12, 30, 0);
+        let mut state = CheckState::load(&dir).unwrap();
+        state.record("other-package", expected);
+
+        state.save().unwrap();
+        std::fs::remove_file(dir.join(CHECKS_FILE_NAME)).unwrap();
+        let actual = CheckState::load(&dir).unwrap();
+
+        assert!(dir.join(database::DATABASE_NAME).is_dir());
+        assert_eq!(
+            actual.last_checked("test-package"),
+            Some(Utc.ymd(2021, 6, 1).and_hms(12, 30, 0))
+        );
+        assert_eq!(actual.last_checked("other-package"), Some(expected));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn record_success_should_only_update_changed_time_on_new_versions() {
+        let first = Utc.ymd(2021, 6, 1).and_hms(12, 30, 0);
+        let second = Utc.ymd(2021, 7, 1).and_hms(12, 30, 0);
+        let mut state = CheckState::default();
+        state.record_failure("test-package");
+
+        state.record_success("test-package", Some("1.0.0"), first);
+        state.record_success("test-package", Some("1.0.0"), second);
+
+        assert_eq!(
+            state.history("test-package"),
+            PackageHistory {
+                version: Some("1.0.0".into()),
+                changed: Some(first),
+                failures: 0,
+                ..PackageHistory::default()
+            }
+        );
+    }
+
+    #[test]
+    fn save_should_persist_package_history() {
+        let dir = std::env::temp_dir().join("aer-state-history-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let changed = Utc.ymd(2021, 6, 1).and_hms(12, 30, 0);
+        let mut state = CheckState::load(&dir).unwrap();
+        state.record_success("test-package", Some("2.0.0"), changed);
+        state.record_failure("test-package");
+        state.record_fingerprint("test-package", Some("abc".into()), Some("\"1\"".into()));
+        state.record_push("test-package", Some("2.0.0"), changed);
+
+        state.save().unwrap();
+        let actual = CheckState::load(&dir).unwrap();
+
+        assert_eq!(
+            actual.history("test-package"),
+            PackageHistory {
+                version: Some("2.0.0".into()),
+                changed: Some(changed),
+                failures: 1,
+                hash: Some("abc".into()),
+                etag: Some("\"1\"".into()),
+                pushed: Some(changed),
+                pushed_version: Some("2.0.0".into()),
+            }
+        );
+        assert_eq!(actual.packages(), ["test-package"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn import_should_merge_exported_state() {
+        let dir = std::env::temp_dir().join("aer-state-export-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let older = Utc.ymd(2021, 6, 1).and_hms(12, 30, 0);
+        let newer = Utc.ymd(2021, 7, 1).and_hms(12, 30, 0);
+        let mut exported = CheckState::load(&dir).unwrap();
+        exported.record("newer-package", newer);
+        exported.record_success("newer-package", Some("2.0.0"), newer);
+        exported.record("older-package", older);
+        exported.record_success("older-package", Some("1.0.0"), older);
+        exported.export(&dir.join("export.json")).unwrap();
+        let mut state = CheckState::default();
+        state.record("newer-package", older);
+        state.record_success("newer-package", Some("1.0.0"), older);
+        state.record("older-package", newer);
+        state.record_success("older-package", Some("1.1.0"), newer);
+
+        let merged = state.import(&dir.join("export.json")).unwrap();
+
+        assert_eq!(merged, 1);
+        assert_eq!(state.last_checked("newer-package"), Some(newer));
+        assert_eq!(state.history("newer-package").version, Some("2.0.0".into()));
+        assert_eq!(state.last_checked("older-package"), Some(newer));
+        assert_eq!(state.history("older-package").version, Some("1.1.0".into()));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn import_should_fail_on_files_not_exported() {
+        let dir = std::env::temp_dir().join("aer-state-import-invalid-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("history.json"), "{}").unwrap();
+        let mut state = CheckState::default();
+
+        let result = state.import(&dir.join("history.json"));
+
+        assert!(result.is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
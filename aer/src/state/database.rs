@@ -0,0 +1,83 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the embedded database the state is stored in when the `state_db`
+//! feature is enabled. The last time each package was checked and the update
+//! history of the packages are kept in separate trees of the database, and
+//! both trees are updated in a single transaction when the state is saved.
+
+use std::error::Error;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use sled::transaction::{ConflictableTransactionError, Transactional};
+
+use super::{CheckState, PackageHistory};
+
+/// The name of the directory holding the database, relative to the state
+/// directory.
+pub(super) const DATABASE_NAME: &str = "state.db";
+
+/// The name of the tree holding the last time each package was checked.
+const CHECKS_TREE: &str = "last-checked";
+
+/// The name of the tree holding the update history of each package.
+const HISTORY_TREE: &str = "history";
+
+/// Loads the state from the database in the state directory. Returns `false`
+/// when the database have not been created yet, in which case the state is
+/// left unchanged.
+pub(super) fn load(state_dir: &Path, state: &mut CheckState) -> Result<bool, Box<dyn Error>> {
+    let path = state_dir.join(DATABASE_NAME);
+
+    if !path.exists() {
+        return Ok(false);
+    }
+
+    let db = sled::open(&path)?;
+
+    for entry in db.open_tree(CHECKS_TREE)?.iter() {
+        let (id, date) = entry?;
+        let date = DateTime::parse_from_rfc3339(std::str::from_utf8(&date)?)?;
+        state
+            .checks
+            .insert(String::from_utf8(id.to_vec())?, date.with_timezone(&Utc));
+    }
+
+    for entry in db.open_tree(HISTORY_TREE)?.iter() {
+        let (id, value) = entry?;
+        let history = PackageHistory::from_json(&serde_json::from_slice(&value)?)?;
+        state
+            .history
+            .insert(String::from_utf8(id.to_vec())?, history);
+    }
+
+    Ok(true)
+}
+
+/// Saves the state to the database in the state directory, creating the
+/// database if it do not already exist.
+pub(super) fn save(state_dir: &Path, state: &CheckState) -> Result<(), Box<dyn Error>> {
+    let db = sled::open(state_dir.join(DATABASE_NAME))?;
+    let checks = db.open_tree(CHECKS_TREE)?;
+    let history = db.open_tree(HISTORY_TREE)?;
+    let values = state
+        .history
+        .iter()
+        .map(|(id, history)| Ok((id.as_str(), serde_json::to_vec(&history.to_json())?)))
+        .collect::<Result<Vec<_>, serde_json::Error>>()?;
+
+    (&checks, &history).transaction(|(checks, history)| {
+        for (id, date) in &state.checks {
+            checks.insert(id.as_str(), date.to_rfc3339().as_str())?;
+        }
+        for (id, value) in &values {
+            history.insert(*id, value.as_slice())?;
+        }
+
+        Ok::<_, ConflictableTransactionError>(())
+    })?;
+    db.flush()?;
+
+    Ok(())
+}
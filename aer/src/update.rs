@@ -0,0 +1,1016 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for finding the links (and the version) of the newest
+//! release of a package, based on the updater data of the package.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use aer_upd::context::UpdateContext;
+use aer_upd::data::{
+    self, Architecture, LinkSelection, PackageData, ParseUrl, UpdaterSource, UpdaterTarget,
+    UpdaterUrls, VersionTransform, Versions,
+};
+use aer_upd::observer::Stage;
+use aer_upd::web::errors::WebError;
+#[cfg(feature = "render")]
+use aer_upd::web::render::BrowserRenderer;
+use aer_upd::web::selector::HtmlSelector;
+use aer_upd::web::sources::SourceRelease;
+use aer_upd::web::{
+    HtmlResponse, LinkElement, LinkSource, LinkType, NextPage, Pagination, WebRequest, WebResponse,
+};
+use aer_version::transform::transform;
+use aer_version::SemanticVersionError;
+use chrono::{DateTime, Utc};
+use log::{info, trace, warn};
+use regex::Regex;
+
+use crate::clock;
+
+/// Holds the links that was found when parsing the upstream location of a
+/// package.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FoundLinks {
+    /// The release page that was used when finding the links, this is only
+    /// set when a regex was used for the url to parse, or when the links are
+    /// read from the releases of a repository.
+    pub release: Option<LinkElement>,
+    /// The links matching the regexes of each architecture, selected using
+    /// the selection policy of the package.
+    pub architectures: BTreeMap<Architecture, LinkElement>,
+    /// All links matching any other regex.
+    pub others: Vec<LinkElement>,
+    /// The checksum file the downloaded files are verified against, either
+    /// the first link matching the checksum regex or the checksum url.
+    pub checksums: Option<LinkElement>,
+}
+
+impl FoundLinks {
+    /// Sets the link that was found for the specified architecture.
+    pub fn with_arch(mut self, arch: Architecture, link: LinkElement) -> FoundLinks {
+        self.architectures.insert(arch, link);
+        self
+    }
+
+    /// Returns the link that was found for the specified architecture.
+    pub fn arch(&self, arch: Architecture) -> Option<&LinkElement> {
+        self.architectures.get(&arch)
+    }
+
+    /// Returns the version that was found, the version of the architecture
+    /// specific links are preferred over the version of the release page.
+    pub fn version(&self) -> Option<&Versions> {
+        Architecture::PREFERRED
+            .iter()
+            .filter_map(|arch| self.arch(*arch))
+            .chain(self.others.iter())
+            .chain(self.release.iter())
+            .find_map(|link| link.version.as_ref())
+    }
+}
+
+/// Holds the links that was found for every target of a package, in the order
+/// the targets are returned by the updater data of the package.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TargetLinks {
+    targets: Vec<(UpdaterTarget, FoundLinks)>,
+}
+
+impl TargetLinks {
+    /// Returns the links of the primary target, which is the target the
+    /// package is created from.
+    pub fn primary(&self) -> Option<&FoundLinks> {
+        self.targets.first().map(|(_, found)| found)
+    }
+
+    /// Returns the links that was found for the specified target.
+    pub fn get(&self, target: UpdaterTarget) -> Option<&FoundLinks> {
+        self.targets
+            .iter()
+            .find(|(current, _)| *current == target)
+            .map(|(_, found)| found)
+    }
+
+    /// Returns an iterator over the targets and the links found for each
+    /// target.
+    pub fn iter(&self) -> impl Iterator<Item = &(UpdaterTarget, FoundLinks)> {
+        self.targets.iter()
+    }
+}
+
+impl From<FoundLinks> for TargetLinks {
+    /// Creates the links of a package where the links was only found for the
+    /// Chocolatey target.
+    fn from(found: FoundLinks) -> TargetLinks {
+        TargetLinks {
+            targets: vec![(UpdaterTarget::Chocolatey, found)],
+        }
+    }
+}
+
+/// Parses the upstream location specified in the updater data of the package,
+/// and returns the links matching the regexes of the primary target of the
+/// package.
+pub fn find_links(
+    context: &UpdateContext,
+    data: &PackageData,
+) -> Result<FoundLinks, Box<dyn std::error::Error>> {
+    match data.updater().primary_urls() {
+        Some(urls) => find_target_links(context, data, &urls),
+        None => Ok(FoundLinks::default()),
+    }
+}
+
+/// Parses the upstream location of every target of the package, and returns
+/// the links matching the regexes of each target. Targets sharing the same
+/// location and regexes re-uses the links found for the first of the targets.
+pub fn find_all_links(
+    context: &UpdateContext,
+    data: &PackageData,
+) -> Result<TargetLinks, Box<dyn std::error::Error>> {
+    let mut links = TargetLinks::default();
+    let mut found_urls: Vec<(UpdaterUrls, FoundLinks)> = vec![];
+
+    for (target, urls) in data.updater().targets() {
+        let found = match found_urls.iter().find(|(existing, _)| *existing == urls) {
+            Some((_, found)) => found.clone(),
+            None => {
+                trace!("Finding links for the {} target", target);
+                let found = find_target_links(context, data, &urls)?;
+                found_urls.push((urls, found.clone()));
+                found
+            }
+        };
+        links.targets.push((target, found));
+    }
+
+    Ok(links)
+}
+
+/// Parses the specified upstream location, and returns the links matching the
+/// specified regexes.
+pub fn find_target_links(
+    context: &UpdateContext,
+    data: &PackageData,
+    urls: &UpdaterUrls,
+) -> Result<FoundLinks, Box<dyn std::error::Error>> {
+    context.stage(Stage::FindLinks, || {
+        find_links_in_stage(context, data, urls)
+    })
+}
+
+fn find_links_in_stage(
+    context: &UpdateContext,
+    data: &PackageData,
+    target_urls: &UpdaterUrls,
+) -> Result<FoundLinks, Box<dyn std::error::Error>> {
+    let mut found = FoundLinks::default();
+
+    context.check_cancelled()?;
+    let request = package_request(context.request(), data);
+    let request = request.as_ref();
+
+    let urls = match data.updater().source {
+        UpdaterSource::GitHub | UpdaterSource::GitLab | UpdaterSource::SourceForge => {
+            find_release_links(request, data, &mut found)?
+        }
+        UpdaterSource::Html => find_html_links(context, request, data, target_urls, &mut found)?,
+        UpdaterSource::Json => find_json_links(request, data, target_urls, &mut found)?,
+        UpdaterSource::Feed => find_feed_links(request, data, target_urls, &mut found)?,
+        UpdaterSource::Xml => find_xml_links(request, data, target_urls, &mut found)?,
+    };
+
+    match_links(data, target_urls, found, &urls)
+}
+
+/// Filters the specified links using the regexes of the target, and returns
+/// the links that was found together with the links already found. The
+/// version transformations of the package are applied to every matching link,
+/// and the link of each architecture is selected using the selection policy
+/// of the package.
+pub fn match_links(
+    data: &PackageData,
+    target_urls: &UpdaterUrls,
+    mut found: FoundLinks,
+    urls: &[LinkElement],
+) -> Result<FoundLinks, Box<dyn std::error::Error>> {
+    let selection = data.updater().selection;
+    let transforms = &data.updater().version_transforms;
+
+    // The regexes are sorted to make sure the links are always processed in the
+    // same order.
+    let mut regexes: Vec<_> = target_urls.regexes().iter().collect();
+    regexes.sort();
+
+    for (key, regex) in regexes {
+        trace!("Filtering {} urls using {}", key, regex);
+        let re = Regex::new(regex)?;
+        let mut items = vec![];
+        for link in urls {
+            let capture = match re.captures(link.link.as_str()) {
+                Some(capture) => capture,
+                None => continue,
+            };
+            let mut new_link = link.clone();
+            new_link.version = transformed_version(
+                capture.name("version").map(|v| v.as_str()),
+                link,
+                transforms,
+            )?;
+
+            if allows_link(&new_link, data) {
+                items.push(new_link);
+            } else {
+                trace!(
+                    "Skipping '{}', the pre-release is not allowed",
+                    new_link.link
+                );
+            }
+        }
+        info!("Parsing urls matching '{}' for {}", regex, key);
+
+        match data.updater().architecture_of(key) {
+            Some(arch) => {
+                if let Some(link) = select_link(items.into_iter(), selection) {
+                    found.architectures.insert(arch, link);
+                }
+            }
+            None => found.others.extend(items),
+        }
+    }
+
+    if let Some(ref mut release) = found.release {
+        release.version = transformed_version(None, release, transforms)?;
+    }
+
+    found.checksums = find_checksum_link(data, urls)?;
+
+    Ok(found)
+}
+
+/// Returns the version of the link after the version transformations of the
+/// package have been applied, the version captured by the regex of the link
+/// is preferred over the version the link already have. The version the link
+/// already have is kept when the transformed version is not a valid version.
+fn transformed_version(
+    captured: Option<&str>,
+    link: &LinkElement,
+    transforms: &[VersionTransform],
+) -> Result<Option<Versions>, SemanticVersionError> {
+    if let Some(captured) = captured {
+        if let Ok(version) = Versions::parse(&transform(captured, transforms)?) {
+            return Ok(Some(version));
+        }
+    }
+
+    match link.version {
+        Some(ref version) if !transforms.is_empty() => {
+            let transformed = transform(&version.to_string(), transforms)?;
+            Ok(Versions::parse(&transformed)
+                .ok()
+                .or_else(|| link.version.clone()))
+        }
+        ref version => Ok(version.clone()),
+    }
+}
+
+/// Selects the link of an architecture out of the links matching the regex of
+/// the architecture, using the specified selection policy. The first link is
+/// used when several links are considered equal.
+fn select_link<I: Iterator<Item = LinkElement>>(
+    mut links: I,
+    selection: LinkSelection,
+) -> Option<LinkElement> {
+    match selection {
+        LinkSelection::FirstMatch => {
+            info!("Taking first match if found!!");
+            links.next()
+        }
+        LinkSelection::HighestVersion => {
+            info!("Taking match with the highest version if found!!");
+            select_max(links, |link| link.version.clone())
+        }
+        LinkSelection::LatestByDate => {
+            info!("Taking latest published match if found!!");
+            select_max(links, published_at)
+        }
+    }
+}
+
+/// Returns the first link with the highest key.
+fn select_max<I, K, F>(links: I, key: F) -> Option<LinkElement>
+where
+    I: Iterator<Item = LinkElement>,
+    K: Ord,
+    F: Fn(&LinkElement) -> K,
+{
+    links
+        .fold(None, |selected: Option<(K, LinkElement)>, link| {
+            let link_key = key(&link);
+            match selected {
+                Some((ref selected_key, _)) if *selected_key >= link_key => selected,
+                _ => Some((link_key, link)),
+            }
+        })
+        .map(|(_, link)| link)
+}
+
+/// Returns the date the link was published, read from the `published-at`
+/// attribute as either an RFC 3339 or an RFC 2822 timestamp.
+fn published_at(link: &LinkElement) -> Option<DateTime<Utc>> {
+    let value = link.attributes.get("published-at")?;
+
+    clock::parse_time(value)
+        .or_else(|_| DateTime::parse_from_rfc2822(value).map(|time| time.with_timezone(&Utc)))
+        .ok()
+}
+
+/// Returns wether the version of the link is allowed by the pre-release
+/// settings of the package, links without a version are always allowed.
+fn allows_link(link: &LinkElement, data: &PackageData) -> bool {
+    match link.version {
+        Some(ref version) => data.updater().allows_version(version),
+        None => true,
+    }
+}
+
+/// Returns wether the release is allowed by the pre-release settings of the
+/// package. Releases marked as pre-releases without a pre-release version are
+/// only allowed when no channels have been specified.
+pub fn allows_release(release: &SourceRelease, data: &PackageData) -> bool {
+    let updater = data.updater();
+    match release.version {
+        Some(ref version) if version.is_prerelease() || !release.prerelease => {
+            updater.allows_version(version)
+        }
+        _ => !release.prerelease || (updater.allow_prerelease && updater.channels.is_empty()),
+    }
+}
+
+/// Returns the request used for the package, using the languages and the
+/// authentication rules of the package when these have been specified.
+pub fn package_request<'a>(request: &'a WebRequest, data: &PackageData) -> Cow<'a, WebRequest> {
+    let updater = data.updater();
+    if updater.accept_language.is_none() && updater.auth.is_empty() {
+        return Cow::Borrowed(request);
+    }
+
+    let request = match updater.accept_language {
+        Some(ref accept_language) => request.clone().with_accept_language(accept_language),
+        None => request.clone(),
+    };
+
+    Cow::Owned(with_auth_rules(request, data))
+}
+
+/// Adds the authentication rules of the package to the request, the rules
+/// selects the profile used when requesting the urls matching the rules.
+pub fn with_auth_rules(request: WebRequest, data: &PackageData) -> WebRequest {
+    if data.updater().auth.is_empty() {
+        return request;
+    }
+
+    let profiles = data
+        .updater()
+        .auth
+        .iter()
+        .fold(request.auth_profiles().clone(), |profiles, rule| {
+            profiles.with_rule(&rule.pattern, &rule.profile)
+        });
+
+    request.with_auth_profiles(profiles)
+}
+
+/// Returns the link to the checksum file of the package, the first link
+/// matching the checksum regex is preferred over the checksum url.
+fn find_checksum_link(
+    data: &PackageData,
+    urls: &[LinkElement],
+) -> Result<Option<LinkElement>, regex::Error> {
+    let updater = data.updater();
+    let link = match updater.checksum_regex {
+        Some(ref regex) => {
+            let re = Regex::new(regex)?;
+            urls.iter()
+                .find(|link| re.is_match(link.link.as_str()))
+                .cloned()
+        }
+        None => None,
+    };
+
+    Ok(link.or_else(|| {
+        updater
+            .checksum_url
+            .clone()
+            .map(|url| LinkElement::new(url, LinkType::Text))
+    }))
+}
+
+fn find_html_links(
+    context: &UpdateContext,
+    request: &WebRequest,
+    data: &PackageData,
+    urls: &UpdaterUrls,
+    found: &mut FoundLinks,
+) -> Result<Vec<LinkElement>, Box<dyn std::error::Error>> {
+    let sources = link_sources(data);
+    let (_, urls) = match &urls.parse_url {
+        Some(ParseUrl::Url(url)) => with_selectors(
+            get_html_response(request, url.as_str(), data)?.with_link_sources(&sources),
+            data,
+        )?
+        .read(None)?,
+        Some(ParseUrl::UrlWithRegex { url, ref regex }) => {
+            info!("Parsing links on '{}' using regex '{}'", url, regex);
+            let (parent, urls) = get_html_response(request, url.as_str(), data)?
+                .with_link_sources(&sources)
+                .read(Some(regex))?;
+            if let Some(url) = urls.iter().find(|link| allows_link(link, data)) {
+                info!(
+                    "{} links found, using first allowed one to get links!",
+                    urls.len()
+                );
+                info!("Parsing links on '{}'", url.link);
+                found.release = Some(url.clone());
+                context.check_cancelled()?;
+                with_selectors(
+                    get_html_response(request, url.link.as_str(), data)?
+                        .with_link_sources(&sources),
+                    data,
+                )?
+                .read(None)?
+            } else {
+                (parent, urls)
+            }
+        }
+        None => {
+            return Err(Box::new(WebError::Other(
+                "No url have been specified to parse!".into(),
+            )))
+        }
+    };
+
+    Ok(urls)
+}
+
+/// Requests the html page at the url, the page is rendered using a headless
+/// browser when this have been enabled for the package.
+fn get_html_response(
+    request: &WebRequest,
+    url: &str,
+    data: &PackageData,
+) -> Result<HtmlResponse, WebError> {
+    if !data.updater().render {
+        return request.get_html_response(url);
+    }
+
+    #[cfg(feature = "render")]
+    {
+        request.get_rendered_html_response(url, &BrowserRenderer::new())
+    }
+    #[cfg(not(feature = "render"))]
+    {
+        Err(WebError::Other(format!(
+            "Unable to render '{}', aer have been built without the 'render' feature!",
+            url
+        )))
+    }
+}
+
+/// Applies the link and version selectors, and the pagination, configured in
+/// the updater data of the package to the html response.
+fn with_selectors(response: HtmlResponse, data: &PackageData) -> Result<HtmlResponse, WebError> {
+    let updater = data.updater();
+    let response = match updater.link_selector {
+        Some(ref selector) => response.with_link_selector(HtmlSelector::parse(selector)?),
+        None => response,
+    };
+    let response = match updater.pagination {
+        Some(ref pagination) => response.with_pagination(Pagination::new(
+            match pagination.next {
+                Some(ref re) => NextPage::Regex(re.clone()),
+                None => NextPage::Rel,
+            },
+            pagination.max_pages,
+        )),
+        None => response,
+    };
+
+    Ok(match updater.version_selector {
+        Some(ref selector) => response.with_version_selector(HtmlSelector::parse(selector)?),
+        None => response,
+    })
+}
+
+/// Requests the JSON document at the parse url, and returns the links selected
+/// by the url expression of the package. The version selected by the version
+/// expression is set on the release and on every link.
+fn find_json_links(
+    request: &WebRequest,
+    data: &PackageData,
+    urls: &UpdaterUrls,
+    found: &mut FoundLinks,
+) -> Result<Vec<LinkElement>, Box<dyn std::error::Error>> {
+    let json = match data.updater().json {
+        Some(ref json) => json,
+        None => {
+            return Err(Box::new(WebError::Other(
+                "No json expressions have been specified to extract links with!".into(),
+            )))
+        }
+    };
+    let url = structured_parse_url(urls)?;
+
+    info!("Reading links from '{}' using '{}'", url, json.urls);
+    let (parent, urls) = request
+        .get_json_response(url.as_str())?
+        .read_links(&json.urls, json.version.as_deref())?;
+    if json.version.is_some() {
+        found.release = Some(parent);
+    }
+
+    Ok(urls)
+}
+
+/// Requests the XML document at the parse url, and returns the link and
+/// version pairs selected by the queries of the package. The version of the
+/// first link is used as the version of the release.
+fn find_xml_links(
+    request: &WebRequest,
+    data: &PackageData,
+    urls: &UpdaterUrls,
+    found: &mut FoundLinks,
+) -> Result<Vec<LinkElement>, Box<dyn std::error::Error>> {
+    let xml = match data.updater().xml {
+        Some(ref xml) => xml,
+        None => {
+            return Err(Box::new(WebError::Other(
+                "No xml queries have been specified to extract links with!".into(),
+            )))
+        }
+    };
+    let url = structured_parse_url(urls)?;
+
+    info!("Reading links from '{}' using '{}'", url, xml.items);
+    let (parent, urls) = request.get_xml_response(url.as_str())?.read_links(
+        &xml.items,
+        &xml.url,
+        xml.version.as_deref(),
+    )?;
+    if xml.version.is_some() {
+        found.release = Some(parent);
+    }
+
+    Ok(urls)
+}
+
+/// Returns the parse url of a package reading the links from a structured
+/// document, where the regex of the parse url is not used.
+fn structured_parse_url(urls: &UpdaterUrls) -> Result<data::Url, WebError> {
+    match urls.parse_url {
+        Some(ParseUrl::Url(ref url)) => Ok(url.clone()),
+        Some(ParseUrl::UrlWithRegex { ref url, ref regex }) => {
+            warn!(
+                "The regex '{}' is not used when reading links from the document at '{}'!",
+                regex, url
+            );
+            Ok(url.clone())
+        }
+        None => Err(WebError::Other(
+            "No url have been specified to parse!".into(),
+        )),
+    }
+}
+
+/// Reads the entries of the feed at the parse url, and returns the links of
+/// every entry. The first entry of the feed allowed by the pre-release
+/// settings is used as the release, as feeds lists the newest entries first.
+fn find_feed_links(
+    request: &WebRequest,
+    data: &PackageData,
+    urls: &UpdaterUrls,
+    found: &mut FoundLinks,
+) -> Result<Vec<LinkElement>, Box<dyn std::error::Error>> {
+    let (url, regex) = match urls.parse_url {
+        Some(ParseUrl::Url(ref url)) => (url.clone(), None),
+        Some(ParseUrl::UrlWithRegex { ref url, ref regex }) => (url.clone(), Some(regex.clone())),
+        None => {
+            return Err(Box::new(WebError::Other(
+                "No url have been specified to parse!".into(),
+            )))
+        }
+    };
+
+    info!("Reading the entries of the feed at '{}'", url);
+    let (_, urls) = request
+        .get_feed_response(url.as_str())?
+        .read(regex.as_deref())?;
+    found.release = urls.iter().find(|link| allows_link(link, data)).cloned();
+
+    Ok(urls)
+}
+
+/// Reads the releases of the repository specified in the updater data, and
+/// returns the assets of the newest release allowed by the pre-release
+/// settings of the package.
+fn find_release_links(
+    request: &WebRequest,
+    data: &PackageData,
+    found: &mut FoundLinks,
+) -> Result<Vec<LinkElement>, Box<dyn std::error::Error>> {
+    let repository = match data.updater().repository {
+        Some(ref repository) => repository,
+        None => {
+            return Err(Box::new(WebError::Other(
+                "No repository have been specified to read releases from!".into(),
+            )))
+        }
+    };
+
+    let releases = match data.updater().source {
+        UpdaterSource::GitLab => request.get_gitlab_releases(repository)?,
+        UpdaterSource::SourceForge => request.get_sourceforge_releases(repository)?,
+        _ => request.get_github_releases(repository)?,
+    };
+    match releases
+        .into_iter()
+        .find(|release| allows_release(release, data))
+    {
+        Some(release) => {
+            info!(
+                "Using release '{}' with {} assets",
+                release.tag,
+                release.assets.len()
+            );
+            found.release = Some(release.link);
+            Ok(release.assets)
+        }
+        None => {
+            warn!("No allowed releases was found for '{}'!", repository);
+            Ok(vec![])
+        }
+    }
+}
+
+/// Returns the additional sources that links should be extracted from, as
+/// configured in the updater data of the package.
+fn link_sources(data: &PackageData) -> Vec<LinkSource> {
+    data.updater()
+        .link_sources
+        .iter()
+        .map(|source| match source {
+            data::LinkSource::MetaRefresh => LinkSource::MetaRefresh,
+            data::LinkSource::ScriptVariables => LinkSource::ScriptVariables,
+            data::LinkSource::ScriptJson => LinkSource::ScriptJson,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_upd::data::Url;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn link(url: &str, version: Option<&str>) -> LinkElement {
+        let mut link = LinkElement::new(Url::parse(url).unwrap(), LinkType::Unknown);
+        link.version = version.map(|v| Versions::parse(v).unwrap());
+        link
+    }
+
+    fn published_link(url: &str, published_at: Option<&str>) -> LinkElement {
+        let mut link = link(url, None);
+        if let Some(published_at) = published_at {
+            link.attributes
+                .insert("published-at".into(), published_at.into());
+        }
+        link
+    }
+
+    #[rstest(
+        captured,
+        version,
+        transforms,
+        expected,
+        case(Some("1.2.3"), None, vec![], Some("1.2.3")),
+        case(Some("v1.2.3"), Some("1.0.0"), vec![], Some("1.0.0")),
+        case(Some("v1.2.3"), None, vec!["strip_prefix:v"], Some("1.2.3")),
+        case(None, Some("1.2.3.4"), vec!["take_parts:3", "append_revision:1"], Some("1.2.3.1")),
+        case(None, Some("1.2.3"), vec!["replace:/^/v/"], Some("1.2.3")),
+        case(None, None, vec!["pad_parts:3"], None)
+    )]
+    fn transformed_version_should_apply_transforms(
+        captured: Option<&str>,
+        version: Option<&str>,
+        transforms: Vec<&str>,
+        expected: Option<&str>,
+    ) {
+        let link = link("https://test.com/x64.zip", version);
+        let transforms: Vec<VersionTransform> =
+            transforms.iter().map(|t| t.parse().unwrap()).collect();
+
+        let actual = transformed_version(captured, &link, &transforms).unwrap();
+
+        assert_eq!(actual, expected.map(|v| Versions::parse(v).unwrap()));
+    }
+
+    #[rstest(
+        selection,
+        expected,
+        case(LinkSelection::FirstMatch, "https://test.com/1.9.0/x64.zip"),
+        case(LinkSelection::HighestVersion, "https://test.com/2.10.0/x64.zip"),
+        case(LinkSelection::LatestByDate, "https://test.com/1.9.0/x64.zip")
+    )]
+    fn select_link_should_use_selection_policy(selection: LinkSelection, expected: &str) {
+        let links = vec![
+            link("https://test.com/1.9.0/x64.zip", Some("1.9.0")),
+            link("https://test.com/x64.zip", None),
+            link("https://test.com/2.10.0/x64.zip", Some("2.10.0")),
+            link("https://test.com/2.2.0/x64.zip", Some("2.2.0")),
+        ];
+
+        let actual = select_link(links.into_iter(), selection).unwrap();
+
+        assert_eq!(actual.link.as_str(), expected);
+    }
+
+    #[test]
+    fn select_link_should_take_latest_published_link() {
+        let links = vec![
+            published_link("https://test.com/old.zip", Some("2021-05-01T10:00:00Z")),
+            published_link("https://test.com/unknown.zip", None),
+            published_link(
+                "https://test.com/new.zip",
+                Some("Tue, 01 Jun 2021 10:00:00 GMT"),
+            ),
+            published_link(
+                "https://test.com/same.zip",
+                Some("2021-06-01T12:00:00+02:00"),
+            ),
+        ];
+
+        let actual = select_link(links.into_iter(), LinkSelection::LatestByDate).unwrap();
+
+        assert_eq!(actual.link.as_str(), "https://test.com/new.zip");
+    }
+
+    #[test]
+    fn select_link_should_return_none_when_no_links_match() {
+        let actual = select_link(std::iter::empty(), LinkSelection::HighestVersion);
+
+        assert_eq!(actual, None);
+    }
+
+    #[test]
+    fn version_should_prefer_architecture_links() {
+        let found = FoundLinks {
+            release: Some(link("https://test.com/v1.0.0", Some("1.0.0"))),
+            ..FoundLinks::default()
+        }
+        .with_arch(
+            Architecture::X86,
+            link("https://test.com/1.0.1/x86.zip", Some("1.0.1")),
+        )
+        .with_arch(Architecture::X64, link("https://test.com/x64.zip", None));
+
+        let actual = found.version();
+
+        assert_eq!(actual, Some(&Versions::parse("1.0.1").unwrap()));
+    }
+
+    #[test]
+    fn version_should_fall_back_to_release_version() {
+        let found = FoundLinks {
+            release: Some(link("https://test.com/v1.0.0", Some("1.0.0"))),
+            ..Default::default()
+        };
+
+        let actual = found.version();
+
+        assert_eq!(actual, Some(&Versions::parse("1.0.0").unwrap()));
+    }
+
+    #[rstest(
+        regex,
+        url,
+        expected,
+        case(Some("SHA256SUMS$"), None, Some("https://test.com/1.0.0/SHA256SUMS")),
+        case(
+            Some("checksums\\.txt$"),
+            Some("https://test.com/SHA256SUMS"),
+            Some("https://test.com/SHA256SUMS")
+        ),
+        case(None, None, None)
+    )]
+    fn find_checksum_link_should_prefer_link_matching_regex(
+        regex: Option<&str>,
+        url: Option<&str>,
+        expected: Option<&str>,
+    ) {
+        let mut data = PackageData::new("test-package");
+        data.updater_mut().checksum_regex = regex.map(String::from);
+        data.updater_mut().checksum_url = url.map(|url| Url::parse(url).unwrap());
+        let urls = [
+            link("https://test.com/1.0.0/x64.zip", None),
+            link("https://test.com/1.0.0/SHA256SUMS", None),
+        ];
+
+        let actual = find_checksum_link(&data, &urls).unwrap();
+
+        assert_eq!(
+            actual.map(|link| link.link.to_string()).as_deref(),
+            expected
+        );
+    }
+
+    #[test]
+    fn find_all_links_should_return_no_targets_without_urls() {
+        let data = PackageData::new("test-package");
+
+        let actual = find_all_links(&UpdateContext::new(), &data).unwrap();
+
+        assert_eq!(actual, TargetLinks::default());
+        assert_eq!(actual.primary(), None);
+    }
+
+    #[test]
+    fn find_all_links_should_use_shared_urls_for_every_target() {
+        let mut data = PackageData::new("test-package");
+        data.updater_mut().source = UpdaterSource::Json;
+        data.updater_mut().urls.add_regex("arch64", "x64");
+        data.updater_mut().set_winget(UpdaterUrls::new());
+
+        let actual = find_all_links(&UpdateContext::new(), &data).unwrap_err();
+
+        assert_eq!(
+            actual.to_string(),
+            "No json expressions have been specified to extract links with!"
+        );
+    }
+
+    #[test]
+    fn target_links_should_return_links_of_target() {
+        let found = FoundLinks::default().with_arch(
+            Architecture::X64,
+            link("https://test.com/1.0.0/x64.zip", None),
+        );
+
+        let actual = TargetLinks::from(found.clone());
+
+        assert_eq!(actual.primary(), Some(&found));
+        assert_eq!(actual.get(UpdaterTarget::Chocolatey), Some(&found));
+        assert_eq!(actual.get(UpdaterTarget::Winget), None);
+    }
+
+    #[test]
+    fn find_links_should_return_error_when_no_url_to_parse() {
+        let mut data = PackageData::new("test-package");
+        data.updater_mut()
+            .set_chocolatey(aer_upd::data::chocolatey::ChocolateyUpdaterData::new());
+
+        let actual = find_links(&UpdateContext::new(), &data);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn find_links_should_return_error_when_no_json_expressions() {
+        let mut data = PackageData::new("test-package");
+        data.updater_mut().source = UpdaterSource::Json;
+        data.updater_mut()
+            .set_chocolatey(aer_upd::data::chocolatey::ChocolateyUpdaterData::new());
+
+        let actual = find_links(&UpdateContext::new(), &data).unwrap_err();
+
+        assert_eq!(
+            actual.to_string(),
+            "No json expressions have been specified to extract links with!"
+        );
+    }
+
+    #[cfg(not(feature = "render"))]
+    #[test]
+    fn find_links_should_return_error_when_rendering_is_not_available() {
+        let mut data = PackageData::new("test-package");
+        data.updater_mut().render = true;
+        let mut choco = aer_upd::data::chocolatey::ChocolateyUpdaterData::new();
+        choco.parse_url = Some(ParseUrl::Url(
+            Url::parse("https://example.org/downloads").unwrap(),
+        ));
+        data.updater_mut().set_chocolatey(choco);
+
+        let actual = find_links(&UpdateContext::new(), &data).unwrap_err();
+
+        assert_eq!(
+            actual.to_string(),
+            "Unable to render 'https://example.org/downloads', aer have been built without the \
+             'render' feature!"
+        );
+    }
+
+    #[test]
+    fn find_links_should_return_error_when_no_xml_queries() {
+        let mut data = PackageData::new("test-package");
+        data.updater_mut().source = UpdaterSource::Xml;
+        data.updater_mut()
+            .set_chocolatey(aer_upd::data::chocolatey::ChocolateyUpdaterData::new());
+
+        let actual = find_links(&UpdateContext::new(), &data).unwrap_err();
+
+        assert_eq!(
+            actual.to_string(),
+            "No xml queries have been specified to extract links with!"
+        );
+    }
+
+    #[rstest(
+        version,
+        prerelease,
+        allow_prerelease,
+        channels,
+        expected,
+        case(Some("1.0.0"), false, false, vec![], true),
+        case(Some("1.0.0-beta.1"), false, false, vec![], false),
+        case(Some("1.0.0-beta.1"), true, true, vec!["beta"], true),
+        case(Some("1.0.0-rc.1"), true, true, vec!["beta"], false),
+        case(Some("1.0.0"), true, false, vec![], false),
+        case(Some("1.0.0"), true, true, vec![], true),
+        case(None, true, true, vec!["beta"], false),
+        case(None, false, false, vec![], true)
+    )]
+    fn allows_release_should_use_prerelease_settings(
+        version: Option<&str>,
+        prerelease: bool,
+        allow_prerelease: bool,
+        channels: Vec<&str>,
+        expected: bool,
+    ) {
+        let mut data = PackageData::new("test-package");
+        data.updater_mut().allow_prerelease = allow_prerelease;
+        data.updater_mut().channels = channels.into_iter().map(String::from).collect();
+        let release = SourceRelease {
+            tag: "v1.0.0".into(),
+            version: version.map(|v| Versions::parse(v).unwrap()),
+            prerelease,
+            link: link("https://test.com/releases/v1.0.0", version),
+            assets: vec![],
+        };
+
+        assert_eq!(allows_release(&release, &data), expected);
+    }
+
+    #[test]
+    fn allows_link_should_skip_prerelease_links_by_default() {
+        let data = PackageData::new("test-package");
+
+        assert!(allows_link(&link("https://test.com/x64.zip", None), &data));
+        assert!(allows_link(
+            &link("https://test.com/1.0.0/x64.zip", Some("1.0.0")),
+            &data
+        ));
+        assert!(!allows_link(
+            &link("https://test.com/1.1.0-rc.1/x64.zip", Some("1.1.0-rc.1")),
+            &data
+        ));
+    }
+
+    #[test]
+    fn find_links_should_return_error_when_cancelled() {
+        let mut data = PackageData::new("test-package");
+        data.updater_mut()
+            .set_chocolatey(aer_upd::data::chocolatey::ChocolateyUpdaterData::new());
+        let context = UpdateContext::new();
+        context.cancellation().cancel();
+
+        let actual = find_links(&context, &data).unwrap_err();
+
+        assert_eq!(actual.to_string(), "The update process was cancelled!");
+    }
+
+    #[test]
+    fn with_auth_rules_should_select_profile_of_package() {
+        let mut data = PackageData::new("test-package");
+        data.updater_mut().auth.push(data::AuthRule::new(
+            "https://downloads.example.org/private/*",
+            "releases",
+        ));
+        let profiles = aer_upd::web::auth::AuthProfiles::new().with_profile(
+            aer_upd::web::auth::AuthProfile::new(
+                "releases",
+                Url::parse("https://releases.example.org/").unwrap(),
+            )
+            .with_hosts(vec!["downloads.example.org"]),
+        );
+        let request = WebRequest::create().with_auth_profiles(profiles);
+
+        let actual = with_auth_rules(request, &data);
+
+        let profile = actual
+            .auth_profiles()
+            .find(&Url::parse("https://downloads.example.org/private/app.exe").unwrap());
+        assert_eq!(profile.map(|profile| profile.name()), Some("releases"));
+    }
+}
@@ -0,0 +1,556 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the rule sets the package data is validated against before the
+//! update process is started.
+//!
+//! Every rule set is a collection of built-in [Rule] implementations, the
+//! `Core` rule set only contains the rules that every package must satisfy,
+//! while the `ChocolateyCommunity` rule set additionally contains the rules
+//! enforced during moderation on the Chocolatey Community Repository. Rules
+//! that have been allowed in the lint settings of the package are reported
+//! with the [Severity::Allowed] severity.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use aer_upd::data::{Description, PackageData};
+use serde_json::{json, Value};
+
+use crate::check::Severity;
+use crate::codes;
+
+/// The minimum length a description must have before it is not considered to
+/// be a placeholder.
+pub(crate) const MIN_DESCRIPTION_LENGTH: usize = 30;
+
+/// The maximum length of a description allowed in a nuspec file.
+pub(crate) const MAX_DESCRIPTION_LENGTH: usize = 4000;
+
+/// The default project url used when no url have been specified by the user.
+pub(crate) const DEFAULT_PROJECT_HOST: &str = "example-repo.org";
+
+/// A single validation rule, checking one aspect of the package data.
+pub trait Rule: Send + Sync {
+    /// The identifier of the rule (*ie: `lowercase-identifier`*).
+    fn id(&self) -> &'static str;
+
+    /// The dotted path of the key in the package data that the rule
+    /// validates (*ie: `metadata.id`*).
+    fn key(&self) -> &'static str;
+
+    /// The severity reported when the package data do not satisfy the rule.
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    /// Validates the package data, and returns the message describing the
+    /// problem when the package data do not satisfy the rule.
+    fn validate(&self, data: &PackageData) -> Option<String>;
+}
+
+/// The built-in rule sets the package data can be validated against.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RuleSet {
+    /// No validation is done.
+    None,
+    /// The rules every package must satisfy.
+    #[default]
+    Core,
+    /// The rules of the core rule set, together with the rules used during
+    /// moderation on the Chocolatey Community Repository.
+    ChocolateyCommunity,
+}
+
+impl FromStr for RuleSet {
+    type Err = &'static str;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val.trim().to_lowercase().as_str() {
+            "none" => Ok(RuleSet::None),
+            "core" => Ok(RuleSet::Core),
+            "chocolatey-community" | "community" => Ok(RuleSet::ChocolateyCommunity),
+            _ => Err("The value is not a supported rule set!"),
+        }
+    }
+}
+
+impl Display for RuleSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            RuleSet::None => f.write_str("none"),
+            RuleSet::Core => f.write_str("core"),
+            RuleSet::ChocolateyCommunity => f.write_str("chocolatey-community"),
+        }
+    }
+}
+
+impl RuleSet {
+    pub fn variants_str() -> &'static [&'static str] {
+        static VARIANTS: &[&str] = &["none", "core", "chocolatey-community", "community"];
+
+        VARIANTS
+    }
+
+    /// Returns the rules that are part of the rule set.
+    pub fn rules(&self) -> Vec<Box<dyn Rule>> {
+        let mut rules: Vec<Box<dyn Rule>> = vec![];
+
+        if *self == RuleSet::None {
+            return rules;
+        }
+
+        rules.push(Box::new(IdentifierRule));
+        rules.push(Box::new(SummaryRule));
+        rules.push(Box::new(ProjectUrlRule));
+        rules.push(Box::new(LicenseUrlRule));
+
+        if *self == RuleSet::ChocolateyCommunity {
+            rules.push(Box::new(LowercaseIdentifierRule));
+            rules.push(Box::new(AuthorsRule));
+            rules.push(Box::new(DescriptionRule));
+            rules.push(Box::new(TagsRule));
+            rules.push(Box::new(TagFormatRule));
+        }
+
+        rules
+    }
+}
+
+/// A single rule that the package data did not satisfy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationResult {
+    /// The identifier of the rule that was not satisfied.
+    pub rule: &'static str,
+    /// The dotted path of the key in the package data the rule validates.
+    pub key: &'static str,
+    /// The severity of the problem.
+    pub severity: Severity,
+    /// The message describing the problem.
+    pub message: String,
+    /// The stable code of the rule (*ie: `AER0202`*).
+    pub code: &'static str,
+}
+
+impl ValidationResult {
+    /// Converts the result to a JSON value.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "rule": self.rule,
+            "key": self.key,
+            "severity": self.severity.to_string(),
+            "message": self.message,
+            "code": self.code,
+        })
+    }
+}
+
+/// The outcome of validating the package data against a rule set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationReport {
+    rule_set: RuleSet,
+    results: Vec<ValidationResult>,
+}
+
+impl ValidationReport {
+    /// Returns the rule set the package data was validated against.
+    pub fn rule_set(&self) -> RuleSet {
+        self.rule_set
+    }
+
+    /// Returns the rules that the package data did not satisfy.
+    pub fn results(&self) -> &[ValidationResult] {
+        self.results.as_slice()
+    }
+
+    /// Returns `true` if any of the rules not satisfied are reported as an
+    /// error.
+    pub fn has_errors(&self) -> bool {
+        self.results
+            .iter()
+            .any(|result| result.severity == Severity::Error)
+    }
+
+    /// Converts the report to a JSON value.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "rules": self.rule_set.to_string(),
+            "results": self.results.iter().map(ValidationResult::to_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// Validates the package data against every rule in the specified rule set.
+pub fn validate(data: &PackageData, rule_set: RuleSet) -> ValidationReport {
+    let lints = data.lints();
+    let results = rule_set
+        .rules()
+        .iter()
+        .filter_map(|rule| {
+            let message = rule.validate(data)?;
+            let code = codes::rule_code(rule.id());
+
+            if lints.is_allowed(code) {
+                let message = match lints.note(code) {
+                    Some(note) => format!("{} (allowed: {})", message, note),
+                    None => format!("{} (allowed without a justification)", message),
+                };
+
+                return Some(ValidationResult {
+                    rule: rule.id(),
+                    key: rule.key(),
+                    severity: Severity::Allowed,
+                    message,
+                    code,
+                });
+            }
+
+            Some(ValidationResult {
+                rule: rule.id(),
+                key: rule.key(),
+                severity: rule.severity(),
+                message,
+                code,
+            })
+        })
+        .collect();
+
+    ValidationReport { rule_set, results }
+}
+
+/// Validates that the identifier is not empty, and do not contain any spaces.
+pub struct IdentifierRule;
+
+impl Rule for IdentifierRule {
+    fn id(&self) -> &'static str {
+        "identifier"
+    }
+
+    fn key(&self) -> &'static str {
+        "metadata.id"
+    }
+
+    fn validate(&self, data: &PackageData) -> Option<String> {
+        let id = data.metadata().id();
+
+        if id.trim().is_empty() || id.contains(char::is_whitespace) {
+            Some("The identifier can not be empty or contain spaces".into())
+        } else {
+            None
+        }
+    }
+}
+
+/// Validates that the identifier is lowercase, or that the identifier will be
+/// converted to lowercase when the package is created.
+pub struct LowercaseIdentifierRule;
+
+impl Rule for LowercaseIdentifierRule {
+    fn id(&self) -> &'static str {
+        "lowercase-identifier"
+    }
+
+    fn key(&self) -> &'static str {
+        "metadata.id"
+    }
+
+    fn validate(&self, data: &PackageData) -> Option<String> {
+        let id = data.metadata().id();
+
+        if data.metadata().chocolatey().lowercase_id() || id == id.to_lowercase() {
+            None
+        } else {
+            Some(format!("The identifier '{}' must be lowercase", id))
+        }
+    }
+}
+
+/// Validates that a summary of the software have been specified.
+pub struct SummaryRule;
+
+impl Rule for SummaryRule {
+    fn id(&self) -> &'static str {
+        "summary"
+    }
+
+    fn key(&self) -> &'static str {
+        "metadata.summary"
+    }
+
+    fn validate(&self, data: &PackageData) -> Option<String> {
+        if data.metadata().summary.trim().is_empty() {
+            Some("A summary of the software is required".into())
+        } else {
+            None
+        }
+    }
+}
+
+/// Validates that the project url have been changed from the default url.
+pub struct ProjectUrlRule;
+
+impl Rule for ProjectUrlRule {
+    fn id(&self) -> &'static str {
+        "project-url"
+    }
+
+    fn key(&self) -> &'static str {
+        "metadata.project_url"
+    }
+
+    fn validate(&self, data: &PackageData) -> Option<String> {
+        if data.metadata().project_url().host_str() == Some(DEFAULT_PROJECT_HOST) {
+            Some("The project url have not been specified".into())
+        } else {
+            None
+        }
+    }
+}
+
+/// Validates that a license url have been specified, or can be resolved from
+/// the license expression.
+pub struct LicenseUrlRule;
+
+impl Rule for LicenseUrlRule {
+    fn id(&self) -> &'static str {
+        "license-url"
+    }
+
+    fn key(&self) -> &'static str {
+        "metadata.license"
+    }
+
+    fn validate(&self, data: &PackageData) -> Option<String> {
+        if data.metadata().license().license_url().is_some() {
+            None
+        } else {
+            Some("No license url have been specified or could be resolved".into())
+        }
+    }
+}
+
+/// Validates that the authors of the software have been specified.
+pub struct AuthorsRule;
+
+impl Rule for AuthorsRule {
+    fn id(&self) -> &'static str {
+        "authors"
+    }
+
+    fn key(&self) -> &'static str {
+        "metadata.chocolatey.authors"
+    }
+
+    fn validate(&self, data: &PackageData) -> Option<String> {
+        if data.metadata().chocolatey().authors().is_empty() {
+            Some("The authors of the software must be specified".into())
+        } else {
+            None
+        }
+    }
+}
+
+/// Validates that the description is neither too short to be a placeholder,
+/// nor too long to be used in a nuspec file.
+pub struct DescriptionRule;
+
+impl Rule for DescriptionRule {
+    fn id(&self) -> &'static str {
+        "description"
+    }
+
+    fn key(&self) -> &'static str {
+        "metadata.chocolatey.description"
+    }
+
+    fn validate(&self, data: &PackageData) -> Option<String> {
+        match data.metadata().chocolatey().description() {
+            Description::Text(text) => {
+                let len = text.trim().chars().count();
+
+                if (MIN_DESCRIPTION_LENGTH..=MAX_DESCRIPTION_LENGTH).contains(&len) {
+                    None
+                } else {
+                    Some(format!(
+                        "The description must be between {} and {} characters long (was {})",
+                        MIN_DESCRIPTION_LENGTH, MAX_DESCRIPTION_LENGTH, len
+                    ))
+                }
+            }
+            // The description is only read from the file when the package is
+            // created, and can not be validated beforehand.
+            Description::Location { .. } => None,
+            Description::None => Some("A description of the software is required".into()),
+        }
+    }
+}
+
+/// Validates that at least one tag have been specified.
+pub struct TagsRule;
+
+impl Rule for TagsRule {
+    fn id(&self) -> &'static str {
+        "tags"
+    }
+
+    fn key(&self) -> &'static str {
+        "metadata.chocolatey.tags"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn validate(&self, data: &PackageData) -> Option<String> {
+        if data.metadata().chocolatey().tags().is_empty() {
+            Some("At least one tag should be specified".into())
+        } else {
+            None
+        }
+    }
+}
+
+/// Validates that the tags are not comma separated.
+pub struct TagFormatRule;
+
+impl Rule for TagFormatRule {
+    fn id(&self) -> &'static str {
+        "tag-format"
+    }
+
+    fn key(&self) -> &'static str {
+        "metadata.chocolatey.tags"
+    }
+
+    fn validate(&self, data: &PackageData) -> Option<String> {
+        if data
+            .metadata()
+            .chocolatey()
+            .tags()
+            .iter()
+            .any(|tag| tag.contains(','))
+        {
+            Some("Tags must be space separated, not comma separated".into())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_upd::data::testing::{PackageDataFixture, FIXTURE_LICENSE_URL, FIXTURE_PROJECT_URL};
+    use aer_upd::data::{LicenseType, Url};
+    use rstest::rstest;
+
+    use super::*;
+
+    fn rules_of(report: &ValidationReport) -> Vec<&'static str> {
+        report.results().iter().map(|result| result.rule).collect()
+    }
+
+    #[rstest(
+        test,
+        expected,
+        case("none", RuleSet::None),
+        case("core", RuleSet::Core),
+        case("Chocolatey-Community", RuleSet::ChocolateyCommunity),
+        case("community", RuleSet::ChocolateyCommunity)
+    )]
+    fn from_str_should_create_expected_rule_set(test: &str, expected: RuleSet) {
+        assert_eq!(RuleSet::from_str(test), Ok(expected));
+    }
+
+    #[test]
+    fn validate_should_only_use_core_rules() {
+        let data = PackageDataFixture::new("Test Package").build();
+
+        let actual = validate(&data, RuleSet::Core);
+
+        assert_eq!(
+            rules_of(&actual),
+            ["identifier", "summary", "project-url", "license-url"]
+        );
+        assert!(actual.has_errors());
+    }
+
+    #[test]
+    fn validate_should_use_community_rules() {
+        let data = PackageDataFixture::new("Test-Package")
+            .with_summary("Package used when testing validation")
+            .with_project_url(FIXTURE_PROJECT_URL)
+            .with_license(LicenseType::Location(
+                Url::parse(FIXTURE_LICENSE_URL).unwrap(),
+            ))
+            .with_choco(|choco| {
+                choco.set_tags(&["aer,updater"]);
+            })
+            .build();
+
+        let actual = validate(&data, RuleSet::ChocolateyCommunity);
+
+        assert_eq!(rules_of(&actual), ["authors", "description", "tag-format"]);
+    }
+
+    #[test]
+    fn validate_should_not_use_any_rules_when_disabled() {
+        let data = PackageDataFixture::new("Test Package").build();
+
+        let actual = validate(&data, RuleSet::None);
+
+        assert!(actual.results().is_empty());
+        assert!(!actual.has_errors());
+    }
+
+    #[test]
+    fn validate_should_pass_valid_package() {
+        let data = PackageDataFixture::minimal()
+            .with_summary("Package used when testing validation")
+            .with_choco_defaults()
+            .with_choco(|choco| {
+                choco.set_tags(&["aer"]);
+            })
+            .build();
+
+        let actual = validate(&data, RuleSet::ChocolateyCommunity);
+
+        assert_eq!(actual.results(), []);
+    }
+
+    #[test]
+    fn validate_should_report_allowed_rules() {
+        let data = PackageDataFixture::minimal()
+            .with_lint_allowed("AER0203", Some("The summary is not available"))
+            .build();
+
+        let actual = validate(&data, RuleSet::Core);
+
+        assert_eq!(actual.results()[0].severity, Severity::Allowed);
+        assert_eq!(
+            actual.results()[0].message,
+            "A summary of the software is required (allowed: The summary is not available)"
+        );
+        assert!(!actual.has_errors());
+    }
+
+    #[test]
+    fn to_json_should_include_results() {
+        let data = PackageDataFixture::minimal().build();
+
+        let actual = validate(&data, RuleSet::Core).to_json();
+
+        assert_eq!(
+            actual,
+            json!({
+                "rules": "core",
+                "results": [{
+                    "rule": "summary",
+                    "key": "metadata.summary",
+                    "severity": "error",
+                    "message": "A summary of the software is required",
+                    "code": "AER0203",
+                }],
+            })
+        );
+    }
+}
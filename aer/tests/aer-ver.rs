@@ -11,6 +11,23 @@ lazy_static! {
     static ref LOG_DIR: PathBuf = std::env::temp_dir();
 }
 
+#[test]
+fn help_should_output_description_of_application() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer-ver")?;
+
+    cmd.arg("--help").env("NO_COLOR", "true");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with(format!(
+            "aer-ver {}\n{}\nParses version strings and outputs the converted values",
+            env!("CARGO_PKG_VERSION"),
+            env!("CARGO_PKG_AUTHORS")
+        )));
+
+    Ok(())
+}
+
 #[test]
 fn testing_single_item_with_valid_version() -> Result<(), Box<dyn std::error::Error>> {
     let mut cmd = Command::cargo_bin("aer-ver")?;
@@ -121,3 +138,81 @@ fn testing_with_trace_logging() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[test]
+fn testing_with_data_root_should_write_log_inside_data_root(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer-ver")?;
+    let data_root = LOG_DIR.join("aer-ver-tests-data-root");
+    let _ = std::fs::remove_dir_all(&data_root);
+
+    cmd.args(&["1.0.0", "--data-root", data_root.to_str().unwrap()])
+        .env("NO_COLOR", "true")
+        .env_remove("AER_LOG_PATH");
+
+    cmd.assert().success();
+
+    assert!(data_root.join("logs").join("aer-ver.log").is_file());
+    assert!(data_root.join("staging").is_dir());
+
+    let _ = std::fs::remove_dir_all(&data_root);
+
+    Ok(())
+}
+
+#[test]
+fn testing_with_frozen_time_should_create_fix_version_for_frozen_date(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer-ver")?;
+    let log_path = LOG_DIR.join("aer-ver-tests-frozen-time.log");
+
+    cmd.args(&[
+        "1.2.0",
+        "--with-fix-version",
+        "--frozen-time",
+        "2021-06-15T23:30:00Z",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().success().stdout(predicate::eq(
+        "Checking 1 version...
+
+       Raw Version : 1.2.0
+
+        Chocolatey : 1.2.0
+ SemVer from Choco : 1.2.0
+    Chocolatey Fix : 1.2.0.20210615
+
+            SemVer : 1.2.0
+ Choco from SemVer : 1.2.0
+    Chocolatey Fix : 1.2.0.20210615
+",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn testing_with_revision_fix_scheme_should_increment_revision(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer-ver")?;
+    let log_path = LOG_DIR.join("aer-ver-tests-revision-fix.log");
+
+    cmd.args(&[
+        "1.2.0.4",
+        "--with-fix-version",
+        "--fix-scheme",
+        "revision",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("    Chocolatey Fix : 1.2.0.5"));
+
+    Ok(())
+}
@@ -22,6 +22,23 @@ lazy_static! {
     static ref LOG_DIR: PathBuf = std::env::temp_dir();
 }
 
+#[test]
+fn help_should_output_description_of_application() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer-web")?;
+
+    cmd.arg("--help").env("NO_COLOR", "true");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with(format!(
+            "aer-web {}\n{}\nAllows testing different web related tasks",
+            env!("CARGO_PKG_VERSION"),
+            env!("CARGO_PKG_AUTHORS")
+        )));
+
+    Ok(())
+}
+
 #[test]
 fn should_parse_with_correct_information_command() -> Result<(), Box<dyn std::error::Error>> {
     let mut cmd = Command::cargo_bin("aer-web")?;
@@ -139,6 +156,34 @@ fn should_not_download_up_to_date_file() -> Result<(), Box<dyn std::error::Error
     Ok(())
 }
 
+#[test]
+fn should_output_metadata_without_downloading_file() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer-web")?;
+    let log_path = LOG_DIR.join("aer-web-tests-head.log");
+
+    cmd.args(&[
+        "head",
+        "https://github.com/chocolatey/ChocolateyGUI/releases/download/0.18.1/ChocolateyGui.Common.0.18.1.nupkg",
+        "--etag",
+        "f0e303406002b7449f3a92d94761fea6",
+        "--log",
+        log_path.to_str().unwrap()
+    ])
+        .env("NO_COLOR", "true");
+
+    cmd.assert().success().stdout(
+        predicate::str::contains("Status : 304")
+            .and(predicate::str::contains(
+                "ETag : f0e303406002b7449f3a92d94761fea6",
+            ))
+            .and(predicate::str::contains(
+                "The file have not been modified, no download is necessary!",
+            )),
+    );
+
+    Ok(())
+}
+
 #[test]
 fn should_keep_downloaded_files() -> Result<(), Box<dyn std::error::Error>> {
     let mut cmd = Command::cargo_bin("aer-web")?;
@@ -0,0 +1,629 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+use std::path::PathBuf;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use lazy_static::lazy_static;
+use predicates::prelude::*;
+
+lazy_static! {
+    static ref LOG_DIR: PathBuf = std::env::temp_dir();
+}
+
+#[test]
+fn simulate_should_succeed_when_outcome_matches_fixtures() -> Result<(), Box<dyn std::error::Error>>
+{
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-simulate-matching.log");
+
+    cmd.args(&[
+        "simulate",
+        "test-data/simulate/package.aer.toml",
+        "--fixtures",
+        "test-data/simulate",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().success();
+
+    Ok(())
+}
+
+#[test]
+fn simulate_should_fail_when_fixture_is_missing() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-simulate-missing.log");
+
+    cmd.args(&[
+        "simulate",
+        "test-data/simulate/package.aer.toml",
+        "--fixtures",
+        "test-data/non-existing",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn ver_should_output_transformed_versions() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-ver.log");
+
+    cmd.args(&["ver", "4.5.1", "--log", log_path.to_str().unwrap()])
+        .env("NO_COLOR", "true");
+
+    cmd.assert().success().stdout(predicate::eq(
+        "Checking 1 version...
+
+       Raw Version : 4.5.1
+
+        Chocolatey : 4.5.1
+ SemVer from Choco : 4.5.1
+
+            SemVer : 4.5.1
+ Choco from SemVer : 4.5.1
+",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn help_should_not_include_description_of_global_options() -> Result<(), Box<dyn std::error::Error>>
+{
+    let mut cmd = Command::cargo_bin("aer")?;
+
+    cmd.arg("--help").env("NO_COLOR", "true");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with(format!(
+            "aer {}\n{}\n\nUSAGE:",
+            env!("CARGO_PKG_VERSION"),
+            env!("CARGO_PKG_AUTHORS")
+        )));
+
+    Ok(())
+}
+
+#[test]
+fn web_should_require_a_sub_command() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-web-no-command.log");
+
+    cmd.args(&["web", "--log", log_path.to_str().unwrap()])
+        .env("NO_COLOR", "true");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("parse").and(predicate::str::contains("download")));
+
+    Ok(())
+}
+
+#[test]
+fn should_fail_when_config_file_does_not_exist() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-missing-config.log");
+
+    cmd.args(&[
+        "ver",
+        "1.0.0",
+        "--config",
+        "non-existing.toml",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("non-existing.toml"));
+
+    Ok(())
+}
+
+#[test]
+fn serve_should_respond_to_requests_on_stdio() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = assert_cmd::Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-serve-stdio.log");
+
+    cmd.args(&["serve", "--ipc", "-", "--log", log_path.to_str().unwrap()])
+        .env("NO_COLOR", "true")
+        .write_stdin(
+            "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"validate\",\"params\":{\"path\":\"\
+             test-data/simulate/package.aer.toml\"}}
+{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"shutdown\"}
+",
+        );
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\"id\":\"simulate-test\"").and(
+            predicate::str::contains("{\"id\":2,\"jsonrpc\":\"2.0\",\"result\":null}"),
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn check_should_output_json_diagnostics() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-check-json.log");
+
+    cmd.args(&[
+        "check",
+        "test-data/simulate",
+        "--format",
+        "json",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().failure().stdout(
+        predicate::str::contains("\"file\":\"test-data/simulate/package.aer.toml\"")
+            .and(predicate::str::contains("\"rule\":\"authors\"")),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn check_should_report_policy_rules() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-check-policy.log");
+
+    cmd.args(&[
+        "check",
+        "test-data/simulate",
+        "--format",
+        "json",
+        "--policy",
+        "test-data/organization.policy",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().failure().stdout(
+        predicate::str::contains("\"rule\":\"team-maintainer\"")
+            .and(predicate::str::contains("\"code\":\"AER0290\"")),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn check_should_only_report_rules_of_rule_set() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-check-rules.log");
+
+    cmd.args(&[
+        "check",
+        "test-data/simulate",
+        "--format",
+        "json",
+        "--rules",
+        "core",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().failure().stdout(
+        predicate::str::contains("\"rule\":\"license-url\"")
+            .and(predicate::str::contains("\"rule\":\"authors\"").not()),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn stale_should_report_packages_with_repeated_failures() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-stale.log");
+    let root = std::env::temp_dir().join("aer-tests-stale");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("state"))?;
+    std::fs::write(
+        root.join("state").join("history.json"),
+        r#"{ "stale-test": { "version": "1.0.0", "changed": null, "failures": 5 } }"#,
+    )?;
+    std::fs::write(
+        root.join("stale-test.aer.toml"),
+        "[metadata]\nid = \"stale-test\"\nproject_url = \"https://example.org\"\nsummary = \"\"\n",
+    )?;
+
+    cmd.args(&[
+        "stale",
+        root.to_str().unwrap(),
+        "--data-root",
+        root.to_str().unwrap(),
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("1 of 1 packages are stale"))
+        .stderr(predicate::str::contains(
+            "stale-test: The last 5 updates failed",
+        ));
+    let _ = std::fs::remove_dir_all(&root);
+
+    Ok(())
+}
+
+#[test]
+fn update_should_discover_filtered_package_files() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-update-all.log");
+    let root = std::env::temp_dir().join("aer-tests-update-all");
+    let _ = std::fs::remove_dir_all(&root);
+    for name in &["first-test", "second-test"] {
+        std::fs::create_dir_all(root.join("automatic").join(name))?;
+        std::fs::write(
+            root.join("automatic")
+                .join(name)
+                .join(format!("{}.aer.toml", name)),
+            "[metadata",
+        )?;
+    }
+
+    cmd.args(&[
+        "update",
+        "--all",
+        "--root",
+        root.join("automatic").to_str().unwrap(),
+        "--filter",
+        "first-*",
+        "--data-root",
+        root.to_str().unwrap(),
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("Found 1 package files"))
+        .stdout(predicate::str::contains(
+            "0 succeeded, 0 unchanged, 0 skipped, 1 failed",
+        ))
+        .stderr(predicate::str::contains("first-test.aer.toml"))
+        .stderr(predicate::str::contains("second-test").not());
+    let _ = std::fs::remove_dir_all(&root);
+
+    Ok(())
+}
+
+#[test]
+fn update_should_write_json_report() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-update-report.log");
+    let root = std::env::temp_dir().join("aer-tests-update-report");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root)?;
+    let package_file = root.join("broken.aer.toml");
+    std::fs::write(&package_file, "[metadata")?;
+    let report_path = root.join("reports").join("report.json");
+
+    cmd.args(&[
+        "update",
+        package_file.to_str().unwrap(),
+        "--report",
+        report_path.to_str().unwrap(),
+        "--data-root",
+        root.to_str().unwrap(),
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("Package").and(predicate::str::contains("| failed")));
+    let report: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&report_path)?)?;
+    assert_eq!(report["summary"]["failed"], 1);
+    assert_eq!(report["packages"][0]["status"], "failed");
+    assert!(report["packages"][0]["error"].is_string());
+    let _ = std::fs::remove_dir_all(&root);
+
+    Ok(())
+}
+
+#[test]
+fn state_should_import_exported_state() -> Result<(), Box<dyn std::error::Error>> {
+    let log_path = LOG_DIR.join("aer-tests-state.log");
+    let root = std::env::temp_dir().join("aer-tests-state");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("source").join("state"))?;
+    std::fs::write(
+        root.join("source").join("state").join("last-checked.json"),
+        r#"{ "state-test": "2021-06-01T12:30:00+00:00" }"#,
+    )?;
+    let export_path = root.join("state-export.json");
+
+    Command::cargo_bin("aer")?
+        .args(&[
+            "state",
+            "export",
+            export_path.to_str().unwrap(),
+            "--data-root",
+            root.join("source").to_str().unwrap(),
+            "--log",
+            log_path.to_str().unwrap(),
+        ])
+        .env("NO_COLOR", "true")
+        .assert()
+        .success();
+    Command::cargo_bin("aer")?
+        .args(&[
+            "state",
+            "import",
+            export_path.to_str().unwrap(),
+            "--data-root",
+            root.join("target").to_str().unwrap(),
+            "--log",
+            log_path.to_str().unwrap(),
+        ])
+        .env("NO_COLOR", "true")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported the state of 1 packages"));
+
+    Command::cargo_bin("aer")?
+        .args(&[
+            "state",
+            "export",
+            root.join("target-export.json").to_str().unwrap(),
+            "--data-root",
+            root.join("target").to_str().unwrap(),
+            "--log",
+            log_path.to_str().unwrap(),
+        ])
+        .env("NO_COLOR", "true")
+        .assert()
+        .success();
+    let imported = std::fs::read_to_string(root.join("target-export.json"))?;
+    assert!(imported.contains("state-test"));
+    let _ = std::fs::remove_dir_all(&root);
+
+    Ok(())
+}
+
+#[test]
+fn history_should_output_recorded_state_of_package() -> Result<(), Box<dyn std::error::Error>> {
+    let log_path = LOG_DIR.join("aer-tests-history.log");
+    let root = std::env::temp_dir().join("aer-tests-history");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("state"))?;
+    std::fs::write(
+        root.join("state").join("last-checked.json"),
+        r#"{ "history-test": "2021-06-01T12:30:00+00:00" }"#,
+    )?;
+    std::fs::write(
+        root.join("state").join("history.json"),
+        r#"{ "history-test": { "version": "2.0.0", "changed": null, "failures": 0, "pushed": "2021-06-01T12:30:00+00:00", "pushed_version": "2.0.0" } }"#,
+    )?;
+
+    Command::cargo_bin("aer")?
+        .args(&[
+            "history",
+            "history-test",
+            "--data-root",
+            root.to_str().unwrap(),
+            "--log",
+            log_path.to_str().unwrap(),
+        ])
+        .env("NO_COLOR", "true")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Upstream version : 2.0.0"))
+        .stdout(predicate::str::contains(
+            "Last pushed : 2021-06-01T12:30:00+00:00",
+        ));
+    Command::cargo_bin("aer")?
+        .args(&[
+            "history",
+            "unknown-test",
+            "--data-root",
+            root.to_str().unwrap(),
+            "--log",
+            log_path.to_str().unwrap(),
+        ])
+        .env("NO_COLOR", "true")
+        .assert()
+        .failure();
+    let _ = std::fs::remove_dir_all(&root);
+
+    Ok(())
+}
+
+#[test]
+fn config_should_change_user_configuration() -> Result<(), Box<dyn std::error::Error>> {
+    let log_path = LOG_DIR.join("aer-tests-config.log");
+    let root = std::env::temp_dir().join("aer-tests-config");
+    let _ = std::fs::remove_dir_all(&root);
+
+    Command::cargo_bin("aer")?
+        .args(&[
+            "config",
+            "set",
+            "maintainers",
+            r#"["AdmiringWorm"]"#,
+            "--log",
+            log_path.to_str().unwrap(),
+        ])
+        .env("NO_COLOR", "true")
+        .env("XDG_CONFIG_HOME", &root)
+        .env("APPDATA", &root)
+        .env_remove("AER_CONFIG")
+        .assert()
+        .success();
+    Command::cargo_bin("aer")?
+        .args(&[
+            "config",
+            "get",
+            "maintainers",
+            "--log",
+            log_path.to_str().unwrap(),
+        ])
+        .env("NO_COLOR", "true")
+        .env("XDG_CONFIG_HOME", &root)
+        .env("APPDATA", &root)
+        .env_remove("AER_CONFIG")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#"["AdmiringWorm"]"#));
+
+    assert!(root.join("aer").join("config.toml").is_file());
+    let _ = std::fs::remove_dir_all(&root);
+
+    Ok(())
+}
+
+#[test]
+fn new_should_create_package_data_file() -> Result<(), Box<dyn std::error::Error>> {
+    let log_path = LOG_DIR.join("aer-tests-new.log");
+    let root = std::env::temp_dir().join("aer-tests-new");
+    let _ = std::fs::remove_dir_all(&root);
+
+    Command::cargo_bin("aer")?
+        .args(&[
+            "new",
+            "--id",
+            "new-package",
+            "--maintainer",
+            "AdmiringWorm",
+            "--target",
+            "chocolatey",
+            "--url",
+            "https://example.org/new-package",
+            "--no-probe",
+            "--templates",
+            "--output",
+            root.to_str().unwrap(),
+            "--log",
+            log_path.to_str().unwrap(),
+        ])
+        .env("NO_COLOR", "true")
+        .assert()
+        .success();
+
+    let package_dir = root.join("new-package");
+    let text = std::fs::read_to_string(package_dir.join("new-package.aer.toml"))?;
+    assert!(text.contains("maintainers = [\"AdmiringWorm\"]"));
+    assert!(text.contains("project_url = \"https://example.org/new-package\""));
+    assert!(package_dir
+        .join("tools")
+        .join("chocolateyInstall.ps1")
+        .is_file());
+    let _ = std::fs::remove_dir_all(&root);
+
+    Ok(())
+}
+
+#[test]
+fn import_should_create_package_data_from_nuspec() -> Result<(), Box<dyn std::error::Error>> {
+    let log_path = LOG_DIR.join("aer-tests-import.log");
+    let root = std::env::temp_dir().join("aer-tests-import");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root)?;
+    std::fs::write(
+        root.join("import-test.nuspec"),
+        "<package><metadata><id>import-test</id><version>1.0.0</version><authors>WormieCorp</authors>\
+         <projectUrl>https://example.org/</projectUrl><summary>Summary</summary>\
+         <description>Description</description></metadata></package>",
+    )?;
+
+    Command::cargo_bin("aer")?
+        .args(&[
+            "import",
+            root.join("import-test.nuspec").to_str().unwrap(),
+            "--output",
+            root.join("output").to_str().unwrap(),
+            "--log",
+            log_path.to_str().unwrap(),
+        ])
+        .env("NO_COLOR", "true")
+        .assert()
+        .success();
+
+    let text = std::fs::read_to_string(
+        root.join("output")
+            .join("import-test")
+            .join("import-test.aer.toml"),
+    )?;
+    assert!(text.contains("id = \"import-test\""));
+    assert!(text.contains("version = \"1.0.0\""));
+    let _ = std::fs::remove_dir_all(&root);
+
+    Ok(())
+}
+
+#[test]
+fn explain_should_output_description_of_code() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-explain.log");
+
+    cmd.args(&["explain", "aer0203", "--log", log_path.to_str().unwrap()])
+        .env("NO_COLOR", "true");
+
+    cmd.assert().success().stdout(
+        predicate::str::contains("AER0203 (error)").and(predicate::str::contains("'summary'")),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn checksum_should_fail_when_checksum_do_not_match() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-checksum.log");
+    let file = LOG_DIR.join("aer-tests-checksum.txt");
+    std::fs::write(&file, "hello")?;
+
+    cmd.args(&[
+        "checksum",
+        file.to_str().unwrap(),
+        "--compare",
+        "abcdef",
+        "--log",
+        log_path.to_str().unwrap(),
+    ])
+    .env("NO_COLOR", "true");
+
+    cmd.assert().failure().stdout(predicate::str::contains(
+        "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824  aer-tests-checksum.txt",
+    ));
+    let _ = std::fs::remove_file(file);
+
+    Ok(())
+}
+
+#[test]
+fn doctor_should_output_enabled_features() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("aer")?;
+    let log_path = LOG_DIR.join("aer-tests-doctor.log");
+
+    cmd.args(&["doctor", "--log", log_path.to_str().unwrap()])
+        .env("NO_COLOR", "true");
+
+    cmd.assert().success().stdout(
+        predicate::str::is_match(r"powershell\s+enabled")?
+            .and(predicate::str::is_match(r"toml_data\s+enabled")?),
+    );
+
+    Ok(())
+}
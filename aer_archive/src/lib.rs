@@ -0,0 +1,455 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+#![deny(missing_docs)]
+
+//! This crate is responsible for inspecting and extracting the archives that
+//! are downloaded when updating a package (*ie: `zip`, `tar.gz` and `7z`
+//! files*), allowing embedded packages to include the extracted files and
+//! scripts to read information from files inside the archive.
+//!
+//! Zip and tar archives are handled natively, while 7z archives requires the
+//! `7z` executable to be available on the path.
+//!
+//! ### Examples
+//!
+//! ```no_run
+//! use aer_archive::Archive;
+//!
+//! let archive = Archive::open("app-1.2.3.zip").unwrap();
+//! for entry in archive.entries().unwrap() {
+//!     println!("{}", entry.path.display());
+//! }
+//! let version = archive.read_file("app/version.txt").unwrap();
+//! archive.extract("tools").unwrap();
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+mod sevenz;
+mod tar;
+mod zip;
+
+/// The formats of the archives that can be handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// A zip archive (*ie: `app.zip`*).
+    Zip,
+    /// An uncompressed tar archive (*ie: `app.tar`*).
+    Tar,
+    /// A gzip compressed tar archive (*ie: `app.tar.gz` or `app.tgz`*).
+    TarGz,
+    /// A 7-Zip archive (*ie: `app.7z`*), handled using the `7z` executable.
+    SevenZip,
+}
+
+impl ArchiveFormat {
+    /// Returns the format of the archive based on the file name of the path,
+    /// nothing is returned when the extension is not a known archive
+    /// extension.
+    pub fn from_path(path: &Path) -> Option<ArchiveFormat> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+
+        if name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if name.ends_with(".7z") {
+            Some(ArchiveFormat::SevenZip)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the format of the archive based on the first bytes of the
+    /// archive (*the magic number*), nothing is returned when the bytes do not
+    /// match any of the known formats.
+    pub fn from_bytes(bytes: &[u8]) -> Option<ArchiveFormat> {
+        if bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"PK\x05\x06") {
+            Some(ArchiveFormat::Zip)
+        } else if bytes.starts_with(&[0x1f, 0x8b]) {
+            Some(ArchiveFormat::TarGz)
+        } else if bytes.starts_with(b"7z\xbc\xaf\x27\x1c") {
+            Some(ArchiveFormat::SevenZip)
+        } else if bytes.len() >= 262 && &bytes[257..262] == b"ustar" {
+            Some(ArchiveFormat::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for ArchiveFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArchiveFormat::Zip => f.write_str("zip"),
+            ArchiveFormat::Tar => f.write_str("tar"),
+            ArchiveFormat::TarGz => f.write_str("tar.gz"),
+            ArchiveFormat::SevenZip => f.write_str("7z"),
+        }
+    }
+}
+
+/// A single file or directory stored in an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// The relative path of the entry inside the archive.
+    pub path: PathBuf,
+    /// The uncompressed size of the entry, directories always have a size of
+    /// `0`.
+    pub size: u64,
+    /// Wether the entry is a directory.
+    pub is_dir: bool,
+}
+
+/// The errors that can occur when handling an archive.
+#[derive(Debug)]
+pub enum ArchiveError {
+    /// The format of the archive could not be determined, or is not
+    /// supported.
+    UnsupportedFormat(PathBuf),
+    /// Reading or writing a file failed.
+    Io(std::io::Error),
+    /// The archive is corrupt or could not be read.
+    Corrupt(String),
+    /// An entry of the archive would be extracted outside of the target
+    /// directory (*ie: the path contains `..` or is absolute*).
+    UnsafePath(String),
+    /// The requested file do not exist in the archive.
+    MissingEntry(String),
+    /// The external tool used to handle the archive failed, or could not be
+    /// found.
+    Tool(String),
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArchiveError::UnsupportedFormat(path) => write!(
+                f,
+                "The format of the archive '{}' is not supported!",
+                path.display()
+            ),
+            ArchiveError::Io(err) => err.fmt(f),
+            ArchiveError::Corrupt(msg) => write!(f, "The archive could not be read: {}", msg),
+            ArchiveError::UnsafePath(path) => write!(
+                f,
+                "The archive entry '{}' would be extracted outside of the target directory!",
+                path
+            ),
+            ArchiveError::MissingEntry(name) => {
+                write!(f, "The file '{}' do not exist in the archive!", name)
+            }
+            ArchiveError::Tool(msg) => msg.fmt(f),
+        }
+    }
+}
+
+impl Error for ArchiveError {}
+
+impl ArchiveError {
+    /// Returns the stable code identifying the kind of error (*ie:
+    /// `AER0601`*), which can be used when searching the documentation.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ArchiveError::UnsupportedFormat(_) => "AER0601",
+            ArchiveError::Io(_) => "AER0602",
+            ArchiveError::Corrupt(_) => "AER0603",
+            ArchiveError::UnsafePath(_) => "AER0604",
+            ArchiveError::MissingEntry(_) => "AER0605",
+            ArchiveError::Tool(_) => "AER0606",
+        }
+    }
+}
+
+impl From<std::io::Error> for ArchiveError {
+    fn from(err: std::io::Error) -> Self {
+        ArchiveError::Io(err)
+    }
+}
+
+/// An archive on the file system that can be inspected and extracted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Archive {
+    path: PathBuf,
+    format: ArchiveFormat,
+}
+
+impl Archive {
+    /// Opens the archive at the specified path, the format is determined from
+    /// the extension of the file and falls back to reading the first bytes of
+    /// the file when the extension is unknown.
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<Archive, ArchiveError> {
+        let path = path.into();
+        let format = match ArchiveFormat::from_path(&path) {
+            Some(format) => format,
+            None => {
+                let mut bytes = Vec::with_capacity(512);
+                File::open(&path)?.take(512).read_to_end(&mut bytes)?;
+                ArchiveFormat::from_bytes(&bytes)
+                    .ok_or_else(|| ArchiveError::UnsupportedFormat(path.clone()))?
+            }
+        };
+
+        Ok(Archive::with_format(path, format))
+    }
+
+    /// Creates a new archive of the specified format, without inspecting the
+    /// file.
+    pub fn with_format<P: Into<PathBuf>>(path: P, format: ArchiveFormat) -> Archive {
+        Archive {
+            path: path.into(),
+            format,
+        }
+    }
+
+    /// Returns wether the specified path is a file that can be handled as an
+    /// archive, based on the extension of the file.
+    pub fn is_archive(path: &Path) -> bool {
+        ArchiveFormat::from_path(path).is_some()
+    }
+
+    /// Returns the path of the archive.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the format of the archive.
+    pub fn format(&self) -> ArchiveFormat {
+        self.format
+    }
+
+    /// Returns all files and directories stored in the archive.
+    pub fn entries(&self) -> Result<Vec<ArchiveEntry>, ArchiveError> {
+        match self.format {
+            ArchiveFormat::Zip => zip::entries(&self.path),
+            ArchiveFormat::Tar => tar::entries(File::open(&self.path)?),
+            ArchiveFormat::TarGz => tar::entries(gz_reader(&self.path)?),
+            ArchiveFormat::SevenZip => sevenz::entries(&self.path),
+        }
+    }
+
+    /// Reads the content of a single file in the archive, the name is the
+    /// relative path of the file inside the archive (*ie:
+    /// `app/version.txt`*).
+    pub fn read_file(&self, name: &str) -> Result<Vec<u8>, ArchiveError> {
+        let content = match self.format {
+            ArchiveFormat::Zip => zip::read_file(&self.path, name)?,
+            ArchiveFormat::Tar => tar::read_file(File::open(&self.path)?, name)?,
+            ArchiveFormat::TarGz => tar::read_file(gz_reader(&self.path)?, name)?,
+            ArchiveFormat::SevenZip => sevenz::read_file(&self.path, name)?,
+        };
+
+        content.ok_or_else(|| ArchiveError::MissingEntry(name.into()))
+    }
+
+    /// Extracts all files in the archive to the specified directory, which is
+    /// created when it do not exist. Returns the paths of the files that was
+    /// extracted, nothing is extracted when any of the entries would be
+    /// written outside of the directory.
+    pub fn extract<P: AsRef<Path>>(&self, target: P) -> Result<Vec<PathBuf>, ArchiveError> {
+        let target = target.as_ref();
+        // Validate every entry before anything is written, so a malicious
+        // archive does not leave partially extracted files behind.
+        let entries = self.entries()?;
+        std::fs::create_dir_all(target)?;
+        log::debug!(
+            "Extracting '{}' to '{}'",
+            self.path.display(),
+            target.display()
+        );
+
+        match self.format {
+            ArchiveFormat::Zip => zip::extract(&self.path, entries, target),
+            ArchiveFormat::Tar => tar::extract(File::open(&self.path)?, target),
+            ArchiveFormat::TarGz => tar::extract(gz_reader(&self.path)?, target),
+            ArchiveFormat::SevenZip => sevenz::extract(&self.path, entries, target),
+        }
+    }
+}
+
+fn gz_reader(path: &Path) -> Result<flate2::read::GzDecoder<File>, ArchiveError> {
+    Ok(flate2::read::GzDecoder::new(File::open(path)?))
+}
+
+/// Returns the relative path of the entry with the specified name, an error
+/// is returned when the path would escape the directory it is extracted to.
+fn safe_path(name: &str) -> Result<PathBuf, ArchiveError> {
+    let mut path = PathBuf::new();
+
+    for component in Path::new(&name.replace('\\', "/")).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(ArchiveError::UnsafePath(name.into()))
+            }
+        }
+    }
+
+    Ok(path)
+}
+
+/// Returns wether the name of the entry refers to the same path as the
+/// requested name, ignoring differences in the path separators.
+fn is_same_entry(entry: &Path, name: &str) -> bool {
+    safe_path(name).map(|name| name == entry).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn test_path(name: &str) -> PathBuf {
+        PathBuf::from("test-data").join(name)
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aer-archive-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[rstest(
+        name,
+        expected,
+        case("app.zip", Some(ArchiveFormat::Zip)),
+        case("APP.ZIP", Some(ArchiveFormat::Zip)),
+        case("app.tar", Some(ArchiveFormat::Tar)),
+        case("app.tar.gz", Some(ArchiveFormat::TarGz)),
+        case("app.tgz", Some(ArchiveFormat::TarGz)),
+        case("app.7z", Some(ArchiveFormat::SevenZip)),
+        case("app.exe", None)
+    )]
+    fn from_path_should_return_expected_format(name: &str, expected: Option<ArchiveFormat>) {
+        assert_eq!(ArchiveFormat::from_path(Path::new(name)), expected);
+    }
+
+    #[rstest(
+        name,
+        expected,
+        case("sample.zip", ArchiveFormat::Zip),
+        case("sample.tar", ArchiveFormat::Tar),
+        case("sample.tar.gz", ArchiveFormat::TarGz)
+    )]
+    fn from_bytes_should_detect_format_of_archives(name: &str, expected: ArchiveFormat) {
+        let bytes = std::fs::read(test_path(name)).unwrap();
+
+        assert_eq!(ArchiveFormat::from_bytes(&bytes), Some(expected));
+    }
+
+    #[test]
+    fn open_should_detect_format_from_content_when_extension_is_unknown() {
+        let dir = temp_dir("detect");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("download");
+        std::fs::copy(test_path("sample.tar.gz"), &path).unwrap();
+
+        let archive = Archive::open(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(archive.format(), ArchiveFormat::TarGz);
+    }
+
+    #[rstest(name, case("sample.zip"), case("sample.tar"), case("sample.tar.gz"))]
+    fn entries_should_list_all_entries(name: &str) {
+        let archive = Archive::open(test_path(name)).unwrap();
+
+        let entries = archive.entries().unwrap();
+
+        assert_eq!(
+            entries,
+            [
+                ArchiveEntry {
+                    path: PathBuf::from("app"),
+                    size: 0,
+                    is_dir: true
+                },
+                ArchiveEntry {
+                    path: PathBuf::from("app/app.exe"),
+                    size: 28,
+                    is_dir: false
+                },
+                ArchiveEntry {
+                    path: PathBuf::from("app/version.txt"),
+                    size: 6,
+                    is_dir: false
+                }
+            ]
+        );
+    }
+
+    #[rstest(name, case("sample.zip"), case("sample.tar"), case("sample.tar.gz"))]
+    fn read_file_should_return_content_of_file(name: &str) {
+        let archive = Archive::open(test_path(name)).unwrap();
+
+        let content = archive.read_file("app/version.txt").unwrap();
+
+        assert_eq!(content, b"1.2.3\n");
+    }
+
+    #[rstest(name, case("sample.zip"), case("sample.tar.gz"))]
+    fn read_file_should_return_error_on_missing_file(name: &str) {
+        let archive = Archive::open(test_path(name)).unwrap();
+
+        let err = archive.read_file("app/missing.txt").unwrap_err();
+
+        assert_eq!(err.code(), "AER0605");
+    }
+
+    #[rstest(name, case("sample.zip"), case("sample.tar"), case("sample.tar.gz"))]
+    fn extract_should_write_files_to_target(name: &str) {
+        let dir = temp_dir(name);
+        let archive = Archive::open(test_path(name)).unwrap();
+
+        let files = archive.extract(&dir).unwrap();
+        let version = std::fs::read_to_string(dir.join("app").join("version.txt")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            files,
+            [
+                dir.join("app").join("app.exe"),
+                dir.join("app").join("version.txt")
+            ]
+        );
+        assert_eq!(version, "1.2.3\n");
+    }
+
+    #[test]
+    fn extract_should_reject_entries_outside_of_target() {
+        let dir = temp_dir("traversal");
+        let archive = Archive::open(test_path("traversal.zip")).unwrap();
+
+        let err = archive.extract(dir.join("inner")).unwrap_err();
+        let escaped = dir.join("evil.txt").exists();
+        let created = dir.join("inner").exists();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(err.code(), "AER0604");
+        assert!(!escaped);
+        assert!(!created);
+    }
+
+    #[rstest(
+        name,
+        expected,
+        case("app/app.exe", Some("app/app.exe")),
+        case("./app\\app.exe", Some("app/app.exe")),
+        case("../app.exe", None),
+        case("/etc/passwd", None),
+        case("app/../../app.exe", None)
+    )]
+    fn safe_path_should_reject_paths_escaping_target(name: &str, expected: Option<&str>) {
+        let actual = safe_path(name).ok();
+
+        assert_eq!(actual, expected.map(PathBuf::from));
+    }
+}
@@ -0,0 +1,199 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Handles 7z archives using the `7z` executable, which needs to be available
+//! on the path.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use crate::{is_same_entry, safe_path, ArchiveEntry, ArchiveError};
+
+/// The executable used when handling 7z archives.
+const SEVENZIP_EXEC: &str = "7z";
+
+fn run(args: &[&str], path: &Path) -> Result<Output, ArchiveError> {
+    let output = Command::new(SEVENZIP_EXEC)
+        .args(args)
+        .arg(path)
+        .output()
+        .map_err(|err| {
+            ArchiveError::Tool(format!(
+                "The '{}' executable is required to handle 7z archives! ({})",
+                SEVENZIP_EXEC, err
+            ))
+        })?;
+
+    if output.status.success() {
+        Ok(output)
+    } else {
+        Err(ArchiveError::Tool(format!(
+            "'{}' failed to handle the archive '{}': {}",
+            SEVENZIP_EXEC,
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+pub(crate) fn entries(path: &Path) -> Result<Vec<ArchiveEntry>, ArchiveError> {
+    let output = run(&["l", "-slt"], path)?;
+
+    parse_listing(&String::from_utf8_lossy(&output.stdout))
+}
+
+pub(crate) fn read_file(path: &Path, name: &str) -> Result<Option<Vec<u8>>, ArchiveError> {
+    let entry = entries(path)?
+        .into_iter()
+        .find(|entry| !entry.is_dir && is_same_entry(&entry.path, name));
+
+    match entry {
+        Some(entry) => {
+            let include = format!("-i!{}", entry.path.display());
+            let output = run(&["e", "-so", &include], path)?;
+            Ok(Some(output.stdout))
+        }
+        None => Ok(None),
+    }
+}
+
+pub(crate) fn extract(
+    path: &Path,
+    entries: Vec<ArchiveEntry>,
+    target: &Path,
+) -> Result<Vec<PathBuf>, ArchiveError> {
+    let output_dir = format!("-o{}", target.display());
+    run(&["x", "-y", &output_dir], path)?;
+
+    Ok(entries
+        .into_iter()
+        .filter(|entry| !entry.is_dir)
+        .map(|entry| target.join(entry.path))
+        .collect())
+}
+
+/// Parses the technical listing (`7z l -slt`) of the archive, where every
+/// entry is written as a block of `Key = Value` lines after a line of dashes.
+fn parse_listing(listing: &str) -> Result<Vec<ArchiveEntry>, ArchiveError> {
+    let mut entries = vec![];
+    let mut lines = listing
+        .lines()
+        .skip_while(|line| !line.starts_with("----------"));
+    let mut entry: Option<ArchiveEntry> = None;
+
+    lines.next();
+    for line in lines {
+        let (key, value) = match line.split_once(" = ") {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => {
+                entries.extend(entry.take());
+                continue;
+            }
+        };
+
+        match key {
+            "Path" => {
+                entries.extend(entry.take());
+                entry = Some(ArchiveEntry {
+                    path: safe_path(value)?,
+                    size: 0,
+                    is_dir: false,
+                });
+            }
+            "Size" => {
+                if let Some(ref mut entry) = entry {
+                    entry.size = value.parse().unwrap_or_default();
+                }
+            }
+            "Folder" => {
+                if let Some(ref mut entry) = entry {
+                    entry.is_dir = value == "+";
+                }
+            }
+            "Attributes" => {
+                if let Some(ref mut entry) = entry {
+                    entry.is_dir |= value.starts_with('D');
+                }
+            }
+            _ => {}
+        }
+    }
+    entries.extend(entry);
+
+    for entry in entries.iter_mut().filter(|entry| entry.is_dir) {
+        entry.size = 0;
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LISTING: &str = "
+7-Zip [64] 16.02 : Copyright (c) 1999-2016 Igor Pavlov : 2016-05-21
+
+Scanning the drive for archives:
+1 file, 213 bytes (1 KiB)
+
+Listing archive: app.7z
+
+--
+Path = app.7z
+Type = 7z
+Physical Size = 213
+
+----------
+Path = app
+Size = 0
+Folder = +
+Attributes = D_ drwxr-xr-x
+
+Path = app/app.exe
+Size = 28
+Packed Size = 40
+Folder = -
+Attributes = A_ -rw-r--r--
+
+Path = app/version.txt
+Size = 6
+Folder = -
+Attributes = A_ -rw-r--r--
+";
+
+    #[test]
+    fn parse_listing_should_return_entries_after_archive_information() {
+        let entries = parse_listing(LISTING).unwrap();
+
+        assert_eq!(
+            entries,
+            [
+                ArchiveEntry {
+                    path: PathBuf::from("app"),
+                    size: 0,
+                    is_dir: true
+                },
+                ArchiveEntry {
+                    path: PathBuf::from("app/app.exe"),
+                    size: 28,
+                    is_dir: false
+                },
+                ArchiveEntry {
+                    path: PathBuf::from("app/version.txt"),
+                    size: 6,
+                    is_dir: false
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_listing_should_reject_unsafe_paths() {
+        let listing = "----------\nPath = ../evil.txt\nSize = 5\n";
+
+        let err = parse_listing(listing).unwrap_err();
+
+        assert_eq!(err.code(), "AER0604");
+    }
+}
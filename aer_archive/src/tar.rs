@@ -0,0 +1,287 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Handles uncompressed and gzip compressed tar archives. Only the parts of
+//! the format needed to read regular files and directories are implemented,
+//! which includes the ustar path prefix, GNU long names and pax paths.
+//! Links and special files are skipped.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use crate::{is_same_entry, safe_path, ArchiveEntry, ArchiveError};
+
+const BLOCK_SIZE: usize = 512;
+
+pub(crate) fn entries<R: Read>(reader: R) -> Result<Vec<ArchiveEntry>, ArchiveError> {
+    let mut entries = vec![];
+    walk(reader, |entry, _| {
+        entries.push(entry);
+        Ok(false)
+    })?;
+
+    Ok(entries)
+}
+
+pub(crate) fn read_file<R: Read>(reader: R, name: &str) -> Result<Option<Vec<u8>>, ArchiveError> {
+    let mut content = None;
+    walk(reader, |entry, data| {
+        if entry.is_dir || !is_same_entry(&entry.path, name) {
+            return Ok(false);
+        }
+
+        // The size in the header is not trusted, the buffer only grows with
+        // the data that is actually read from the archive.
+        let mut buffer = vec![];
+        data.read_to_end(&mut buffer)?;
+        if (buffer.len() as u64) < entry.size {
+            return Err(ArchiveError::Corrupt(format!(
+                "The entry '{}' is truncated",
+                entry.path.display()
+            )));
+        }
+        content = Some(buffer);
+        Ok(true)
+    })?;
+
+    Ok(content)
+}
+
+pub(crate) fn extract<R: Read>(reader: R, target: &Path) -> Result<Vec<PathBuf>, ArchiveError> {
+    let mut files = vec![];
+
+    walk(reader, |entry, data| {
+        let output = target.join(&entry.path);
+        if entry.is_dir {
+            std::fs::create_dir_all(&output)?;
+            return Ok(false);
+        }
+        if let Some(parent) = output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        io::copy(data, &mut File::create(&output)?)?;
+        files.push(output);
+        Ok(false)
+    })?;
+
+    Ok(files)
+}
+
+/// Visits every file and directory in the archive, the visitor returns `true`
+/// when no further entries should be visited.
+fn walk<R, F>(mut reader: R, mut visit: F) -> Result<(), ArchiveError>
+where
+    R: Read,
+    F: FnMut(ArchiveEntry, &mut dyn Read) -> Result<bool, ArchiveError>,
+{
+    let mut block = [0; BLOCK_SIZE];
+    let mut long_name = None;
+
+    while read_block(&mut reader, &mut block)? {
+        if block.iter().all(|&b| b == 0) {
+            break;
+        }
+        verify_checksum(&block)?;
+
+        let size = parse_number(&block[124..136])?;
+        let kind = block[156];
+        let name = long_name.take().unwrap_or_else(|| header_name(&block));
+        let mut data = (&mut reader).take(size);
+        let mut stop = false;
+
+        match kind {
+            b'L' => long_name = Some(read_string(&mut data)?),
+            b'x' => long_name = pax_path(&read_string(&mut data)?),
+            b'0' | b'\0' | b'5' => {
+                let is_dir = kind == b'5' || name.ends_with('/');
+                let entry = ArchiveEntry {
+                    path: safe_path(&name)?,
+                    size: if is_dir { 0 } else { size },
+                    is_dir,
+                };
+                stop = visit(entry, &mut data)?;
+            }
+            _ => {}
+        }
+
+        io::copy(&mut data, &mut io::sink())?;
+        let padding = (BLOCK_SIZE as u64 - size % BLOCK_SIZE as u64) % BLOCK_SIZE as u64;
+        io::copy(&mut (&mut reader).take(padding), &mut io::sink())?;
+
+        if stop {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a single block, `false` is returned when the end of the archive was
+/// reached before any data was read.
+fn read_block<R: Read>(reader: &mut R, block: &mut [u8; BLOCK_SIZE]) -> Result<bool, ArchiveError> {
+    let mut read = 0;
+
+    while read < BLOCK_SIZE {
+        match reader.read(&mut block[read..]) {
+            Ok(0) if read == 0 => return Ok(false),
+            Ok(0) => {
+                return Err(ArchiveError::Corrupt(
+                    "Unexpected end of the archive".into(),
+                ))
+            }
+            Ok(count) => read += count,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) if err.kind() == io::ErrorKind::InvalidInput => {
+                return Err(ArchiveError::Corrupt(err.to_string()))
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Ok(true)
+}
+
+fn verify_checksum(block: &[u8; BLOCK_SIZE]) -> Result<(), ArchiveError> {
+    let expected = parse_number(&block[148..156])?;
+    let actual: u64 = block
+        .iter()
+        .enumerate()
+        .map(|(index, &b)| {
+            if (148..156).contains(&index) {
+                u64::from(b' ')
+            } else {
+                u64::from(b)
+            }
+        })
+        .sum();
+
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(ArchiveError::Corrupt(
+            "Invalid checksum of tar header".into(),
+        ))
+    }
+}
+
+/// Parses a numeric field of the header, which is either an octal string or
+/// a big-endian binary number when the high bit of the first byte is set.
+fn parse_number(field: &[u8]) -> Result<u64, ArchiveError> {
+    if field[0] & 0x80 != 0 {
+        return Ok(field[1..]
+            .iter()
+            .fold(u64::from(field[0] & 0x7f), |acc, &b| {
+                (acc << 8) | u64::from(b)
+            }));
+    }
+
+    let value = String::from_utf8_lossy(field);
+    let value = value.trim_matches(|c: char| c == '\0' || c == ' ');
+    if value.is_empty() {
+        return Ok(0);
+    }
+
+    u64::from_str_radix(value, 8)
+        .map_err(|_| ArchiveError::Corrupt(format!("Invalid number '{}' in tar header", value)))
+}
+
+fn field_string(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+fn header_name(block: &[u8; BLOCK_SIZE]) -> String {
+    let name = field_string(&block[..100]);
+    if &block[257..262] != b"ustar" {
+        return name;
+    }
+
+    let prefix = field_string(&block[345..500]);
+    if prefix.is_empty() {
+        name
+    } else {
+        format!("{}/{}", prefix, name)
+    }
+}
+
+fn read_string<R: Read>(reader: &mut R) -> Result<String, ArchiveError> {
+    let mut buffer = vec![];
+    reader.read_to_end(&mut buffer)?;
+
+    Ok(field_string(&buffer))
+}
+
+/// Returns the path stored in the extended pax header, the records have the
+/// format `<length> <key>=<value>\n`.
+fn pax_path(records: &str) -> Option<String> {
+    records.lines().find_map(|record| {
+        let (_, pair) = record.split_once(' ')?;
+        let (key, value) = pair.split_once('=')?;
+
+        if key == "path" {
+            Some(value.to_string())
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest(
+        field,
+        expected,
+        case(b"0000000034\0", 28),
+        case(b"     755 \0", 493),
+        case(b"\0\0\0\0", 0),
+        case(&[0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0x02, 0], 512)
+    )]
+    fn parse_number_should_parse_octal_and_binary_numbers(field: &[u8], expected: u64) {
+        assert_eq!(parse_number(field).unwrap(), expected);
+    }
+
+    #[test]
+    fn pax_path_should_return_path_record() {
+        let records = "30 mtime=1600000000.123456789\n30 path=app/long/version.txt\n";
+
+        assert_eq!(pax_path(records), Some("app/long/version.txt".into()));
+    }
+
+    fn header(name: &str, size: u64) -> [u8; BLOCK_SIZE] {
+        let mut block = [0; BLOCK_SIZE];
+        block[..name.len()].copy_from_slice(name.as_bytes());
+        block[124] = 0x80;
+        block[128..136].copy_from_slice(&size.to_be_bytes());
+        block[156] = b'0';
+        block[148..156].copy_from_slice(b"        ");
+        let checksum: u64 = block.iter().map(|&b| u64::from(b)).sum();
+        block[148..155].copy_from_slice(format!("{:06o}\0", checksum).as_bytes());
+
+        block
+    }
+
+    #[test]
+    fn read_file_should_return_error_on_entry_larger_than_archive() {
+        let mut bytes = header("app/version.txt", 1 << 40).to_vec();
+        bytes.extend_from_slice(b"1.2.3");
+
+        let err = read_file(&bytes[..], "app/version.txt").unwrap_err();
+
+        assert_eq!(err.code(), "AER0603");
+    }
+
+    #[test]
+    fn entries_should_return_error_on_truncated_archive() {
+        let bytes = std::fs::read("test-data/sample.tar").unwrap();
+
+        let err = entries(&bytes[..700]).unwrap_err();
+
+        assert_eq!(err.code(), "AER0603");
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Handles zip archives using the `zip` crate.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+use crate::{is_same_entry, safe_path, ArchiveEntry, ArchiveError};
+
+impl From<ZipError> for ArchiveError {
+    fn from(err: ZipError) -> Self {
+        match err {
+            ZipError::Io(err) => ArchiveError::Io(err),
+            err => ArchiveError::Corrupt(err.to_string()),
+        }
+    }
+}
+
+fn open(path: &Path) -> Result<ZipArchive<File>, ArchiveError> {
+    Ok(ZipArchive::new(File::open(path)?)?)
+}
+
+pub(crate) fn entries(path: &Path) -> Result<Vec<ArchiveEntry>, ArchiveError> {
+    let mut archive = open(path)?;
+    let mut entries = Vec::with_capacity(archive.len());
+
+    for index in 0..archive.len() {
+        let file = archive.by_index(index)?;
+        let is_dir = file.is_dir();
+        entries.push(ArchiveEntry {
+            path: safe_path(file.name())?,
+            size: if is_dir { 0 } else { file.size() },
+            is_dir,
+        });
+    }
+
+    Ok(entries)
+}
+
+pub(crate) fn read_file(path: &Path, name: &str) -> Result<Option<Vec<u8>>, ArchiveError> {
+    let mut archive = open(path)?;
+
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        if file.is_dir() || !is_same_entry(&safe_path(file.name())?, name) {
+            continue;
+        }
+
+        let mut content = vec![];
+        file.read_to_end(&mut content)?;
+        return Ok(Some(content));
+    }
+
+    Ok(None)
+}
+
+pub(crate) fn extract(
+    path: &Path,
+    entries: Vec<ArchiveEntry>,
+    target: &Path,
+) -> Result<Vec<PathBuf>, ArchiveError> {
+    let mut archive = open(path)?;
+    let mut files = vec![];
+
+    for (index, entry) in entries.into_iter().enumerate() {
+        let output = target.join(&entry.path);
+        if entry.is_dir {
+            std::fs::create_dir_all(&output)?;
+            continue;
+        }
+        if let Some(parent) = output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut file = archive.by_index(index)?;
+        std::io::copy(&mut file, &mut File::create(&output)?)?;
+        files.push(output);
+    }
+
+    Ok(files)
+}
@@ -1,22 +1,46 @@
 // Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
 // Licensed under the MIT license. See LICENSE.txt file in the project
 
-#[cfg(feature = "chocolatey")]
+#[cfg(any(feature = "chocolatey", feature = "nuget", feature = "winget"))]
 use aer_version::{SemVersion, Versions};
+#[cfg(feature = "chocolatey")]
+use url::Url;
 
 #[cfg(feature = "chocolatey")]
 pub fn boolean_true() -> bool {
     true
 }
 
-#[cfg(feature = "chocolatey")]
+#[cfg(any(feature = "chocolatey", feature = "nuget", feature = "winget"))]
 pub fn empty_version() -> Versions {
     Versions::SemVer(SemVersion::new(0, 0, 0))
 }
 
+#[cfg(feature = "chocolatey")]
+pub fn publish_feed() -> Url {
+    Url::parse("https://push.chocolatey.org/").unwrap()
+}
+
+#[cfg(feature = "chocolatey")]
+pub fn publish_api_key_env() -> String {
+    "CHOCOLATEY_API_KEY".into()
+}
+
+pub fn signature_extension() -> String {
+    ".asc".into()
+}
+
+pub fn max_pages() -> usize {
+    5
+}
+
 pub fn maintainer() -> Vec<String> {
-    vec![match std::env::var("AER_MAINTAINER") {
-        Ok(maintainer) => maintainer,
-        Err(_) => whoami::username(),
-    }]
+    match std::env::var("AER_MAINTAINER") {
+        Ok(maintainers) => maintainers
+            .split(',')
+            .map(|maintainer| maintainer.trim().to_owned())
+            .filter(|maintainer| !maintainer.is_empty())
+            .collect(),
+        Err(_) => vec![whoami::username()],
+    }
 }
@@ -31,8 +31,11 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
 mod defaults;
+pub mod lints;
 pub mod metadata;
 pub mod prelude;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod updater;
 
 #[cfg(feature = "serialize")]
@@ -40,6 +43,13 @@ use serde::{Deserialize, Serialize};
 
 /// Structure for holding all available data that a user can specify for a
 /// package.
+///
+/// ## Thread safety
+///
+/// The package data is `Send` and `Sync`, and only contains owned values. It
+/// can be moved to the thread processing the package, or shared between
+/// threads for reading. Modifications requires exclusive access, and each
+/// worker is expected to own the package data it modifies.
 #[derive(Debug, PartialEq)]
 #[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
 #[non_exhaustive]
@@ -49,6 +59,13 @@ pub struct PackageData {
 
     #[cfg_attr(feature = "serialize", serde(default))]
     updater: updater::PackageUpdateData,
+
+    /// The findings of the validation rules that are allowed for the package.
+    #[cfg_attr(
+        feature = "serialize",
+        serde(default, skip_serializing_if = "lints::PackageLints::is_empty")
+    )]
+    lints: lints::PackageLints,
 }
 
 impl PackageData {
@@ -57,6 +74,7 @@ impl PackageData {
         PackageData {
             metadata: metadata::PackageMetadata::new(id),
             updater: updater::PackageUpdateData::new(),
+            lints: lints::PackageLints::new(),
         }
     }
 
@@ -80,17 +98,34 @@ impl PackageData {
     pub fn updater_mut(&mut self) -> &mut updater::PackageUpdateData {
         &mut self.updater
     }
+
+    /// Returns the findings of the validation rules that are allowed for this
+    /// package.
+    pub fn lints(&self) -> &lints::PackageLints {
+        &self.lints
+    }
+
+    /// Returns the findings of the validation rules that are allowed for this
+    /// package as a mutable reference.
+    pub fn lints_mut(&mut self) -> &mut lints::PackageLints {
+        &mut self.lints
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use static_assertions::assert_impl_all;
+
     use super::*;
 
+    assert_impl_all!(PackageData: Send, Sync);
+
     #[test]
     fn new_should_set_expected_values() {
         let expected = PackageData {
             metadata: metadata::PackageMetadata::new("test-id"),
             updater: updater::PackageUpdateData::new(),
+            lints: lints::PackageLints::new(),
         };
 
         let actual = PackageData::new("test-id");
@@ -108,6 +143,7 @@ mod tests {
         let pkg = PackageData {
             metadata: pkg_create(),
             updater: updater::PackageUpdateData::new(),
+            lints: lints::PackageLints::new(),
         };
 
         let actual = pkg.metadata();
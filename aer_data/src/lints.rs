@@ -0,0 +1,106 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the lint settings of a package, allowing specific findings of the
+//! validation rules to be suppressed together with a note justifying why the
+//! finding is allowed.
+//!
+//! ### Examples
+//!
+//! ```toml
+//! [lints]
+//! allow = ["AER0212"]
+//!
+//! [lints.notes]
+//! AER0212 = "The software do not state any copyright"
+//! ```
+
+use std::collections::BTreeMap;
+
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+/// The findings that are allowed for a single package.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
+#[non_exhaustive]
+pub struct PackageLints {
+    /// The codes of the findings that are allowed for the package (*ie:
+    /// `AER0203`*).
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub allow: Vec<String>,
+
+    /// Notes justifying why the findings are allowed, keyed by the code of
+    /// the finding.
+    #[cfg_attr(
+        feature = "serialize",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    pub notes: BTreeMap<String, String>,
+}
+
+impl PackageLints {
+    /// Creates a new instance of the lint settings, where no findings are
+    /// allowed.
+    pub fn new() -> PackageLints {
+        PackageLints::default()
+    }
+
+    /// Returns `true` when no findings are allowed, and no notes have been
+    /// added.
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.notes.is_empty()
+    }
+
+    /// Allows the finding with the specified code, with an optional note
+    /// justifying why the finding is allowed.
+    pub fn add_allow(&mut self, code: &str, note: Option<&str>) {
+        if !self.is_allowed(code) {
+            self.allow.push(code.to_uppercase());
+        }
+        if let Some(note) = note {
+            self.notes.insert(code.to_uppercase(), note.into());
+        }
+    }
+
+    /// Returns wether the finding with the specified code is allowed, codes
+    /// are compared without considering the case.
+    pub fn is_allowed(&self, code: &str) -> bool {
+        self.allow
+            .iter()
+            .any(|allowed| allowed.trim().eq_ignore_ascii_case(code))
+    }
+
+    /// Returns the note justifying why the finding with the specified code
+    /// is allowed, if one have been added.
+    pub fn note(&self, code: &str) -> Option<&str> {
+        self.notes
+            .iter()
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case(code))
+            .map(|(_, note)| note.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_allow_should_allow_code_with_note() {
+        let mut lints = PackageLints::new();
+
+        lints.add_allow("aer0203", Some("The summary is intentionally empty"));
+
+        assert!(lints.is_allowed("AER0203"));
+        assert_eq!(
+            lints.note("AER0203"),
+            Some("The summary is intentionally empty")
+        );
+        assert!(!lints.is_allowed("AER0204"));
+    }
+
+    #[test]
+    fn is_empty_should_be_true_for_new_lints() {
+        assert!(PackageLints::new().is_empty());
+    }
+}
@@ -3,6 +3,10 @@
 
 #[cfg(feature = "chocolatey")]
 pub mod chocolatey;
+#[cfg(feature = "nuget")]
+pub mod nuget;
+#[cfg(feature = "winget")]
+pub mod winget;
 
 use std::borrow::Cow;
 use std::fmt::Display;
@@ -85,12 +89,31 @@ pub struct PackageMetadata {
     ///
     /// If creating a chocolatey package, a license url is usually necessary
     /// when pushing to the chocolatey repository.
-    #[cfg_attr(feature = "serialize", serde(default))]
+    #[cfg_attr(
+        feature = "serialize",
+        serde(default, skip_serializing_if = "LicenseType::is_none")
+    )]
     license: LicenseType,
 
     #[cfg(feature = "chocolatey")]
     #[cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
     chocolatey: Option<chocolatey::ChocolateyMetadata>,
+
+    #[cfg(feature = "winget")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "winget")))]
+    #[cfg_attr(
+        feature = "serialize",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    winget: Option<winget::WingetMetadata>,
+
+    #[cfg(feature = "nuget")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "nuget")))]
+    #[cfg_attr(
+        feature = "serialize",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    nuget: Option<nuget::NugetMetadata>,
 }
 
 impl PackageMetadata {
@@ -105,6 +128,10 @@ impl PackageMetadata {
             license: LicenseType::None,
             #[cfg(feature = "chocolatey")]
             chocolatey: None,
+            #[cfg(feature = "winget")]
+            winget: None,
+            #[cfg(feature = "nuget")]
+            nuget: None,
         }
     }
 
@@ -113,6 +140,11 @@ impl PackageMetadata {
         &self.id
     }
 
+    /// Sets the main identifier for the package.
+    pub fn set_id(&mut self, id: &str) {
+        self.id = id.to_owned();
+    }
+
     /// Returns wether metadata regarding chocolatey is already set or not.
     #[cfg(feature = "chocolatey")]
     #[cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
@@ -132,6 +164,42 @@ impl PackageMetadata {
         }
     }
 
+    /// Returns wether metadata regarding winget is already set or not.
+    #[cfg(feature = "winget")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "winget")))]
+    pub fn has_winget(&self) -> bool {
+        self.winget.is_some()
+    }
+
+    /// Returns the set winget metadata, or a new instance if no data is set.
+    #[cfg(feature = "winget")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "winget")))]
+    pub fn winget(&self) -> Cow<winget::WingetMetadata> {
+        if let Some(ref winget) = self.winget {
+            Cow::Borrowed(winget)
+        } else {
+            Cow::Owned(winget::WingetMetadata::default())
+        }
+    }
+
+    /// Returns wether metadata regarding NuGet is already set or not.
+    #[cfg(feature = "nuget")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "nuget")))]
+    pub fn has_nuget(&self) -> bool {
+        self.nuget.is_some()
+    }
+
+    /// Returns the set NuGet metadata, or a new instance if no data is set.
+    #[cfg(feature = "nuget")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "nuget")))]
+    pub fn nuget(&self) -> Cow<nuget::NugetMetadata> {
+        if let Some(ref nuget) = self.nuget {
+            Cow::Borrowed(nuget)
+        } else {
+            Cow::Owned(nuget::NugetMetadata::new())
+        }
+    }
+
     /// Returns the people responsible for creating and updating the package.
     pub fn maintainers(&self) -> &[String] {
         self.maintainers.as_slice()
@@ -155,6 +223,22 @@ impl PackageMetadata {
         self.chocolatey = Some(choco);
     }
 
+    /// Allows setting a new instance of winget metadata and associate it with
+    /// the current metadata instance.
+    #[cfg(feature = "winget")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "winget")))]
+    pub fn set_winget(&mut self, winget: winget::WingetMetadata) {
+        self.winget = Some(winget);
+    }
+
+    /// Allows setting a new instance of NuGet metadata and associate it with
+    /// the current metadata instance.
+    #[cfg(feature = "nuget")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "nuget")))]
+    pub fn set_nuget(&mut self, nuget: nuget::NugetMetadata) {
+        self.nuget = Some(nuget);
+    }
+
     pub fn set_maintainers<T>(&mut self, vals: &[T])
     where
         T: Display,
@@ -198,6 +282,10 @@ mod tests {
             summary: String::new(),
             #[cfg(feature = "chocolatey")]
             chocolatey: None,
+            #[cfg(feature = "winget")]
+            winget: None,
+            #[cfg(feature = "nuget")]
+            nuget: None,
         };
 
         let actual = PackageMetadata::new("test-package");
@@ -223,6 +311,15 @@ mod tests {
         assert_eq!(pkg.id(), EXPECTED);
     }
 
+    #[test]
+    fn set_id_should_replace_identifier() {
+        let mut pkg = PackageMetadata::new("test-package");
+
+        pkg.set_id("test-package.beta");
+
+        assert_eq!(pkg.id(), "test-package.beta");
+    }
+
     #[test]
     fn maintainers_should_return_set_maintainers() {
         let expected = [
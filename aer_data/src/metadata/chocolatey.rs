@@ -96,6 +96,108 @@ pub struct ChocolateyMetadata {
 
     #[cfg_attr(feature = "serialize", serde(default))]
     dependencies: HashMap<String, Versions>,
+
+    /// The feed the package should be pushed to when the package is
+    /// published, packages are not published when this is not set.
+    #[cfg_attr(
+        feature = "serialize",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub publish: Option<ChocolateyPublish>,
+}
+
+/// Holds the information needed to push a Chocolatey package to a NuGet v2
+/// feed, either the Chocolatey Community Repository or an internal feed.
+///
+/// ### Examples
+///
+/// A `TOML` edition of pushing the package to an internal feed.
+/// ```toml
+/// [metadata.chocolatey.publish]
+/// feed = "https://nexus.example.org/repository/choco-internal/"
+/// api_key_env = "NEXUS_API_KEY"
+/// ```
+///
+/// A `TOML` edition of pushing the package using the feed and credentials of
+/// an authentication profile in the global configuration file.
+/// ```toml
+/// [metadata.chocolatey.publish]
+/// profile = "internal"
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
+#[non_exhaustive]
+pub struct ChocolateyPublish {
+    /// The url of the feed the package is pushed to, defaults to the
+    /// Chocolatey Community Repository.
+    #[cfg_attr(
+        feature = "serialize",
+        serde(default = "crate::defaults::publish_feed")
+    )]
+    pub feed: Url,
+
+    /// The name of the environment variable holding the API key used when
+    /// pushing the package, the key itself should never be stored in the
+    /// package data.
+    #[cfg_attr(
+        feature = "serialize",
+        serde(default = "crate::defaults::publish_api_key_env")
+    )]
+    pub api_key_env: String,
+
+    /// The name of the authentication profile in the global configuration
+    /// file to use, the feed and API key of the profile are used instead of
+    /// the values in this section.
+    #[cfg_attr(
+        feature = "serialize",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub profile: Option<String>,
+}
+
+impl ChocolateyPublish {
+    /// Creates new publish settings pushing to the Chocolatey Community
+    /// Repository, using the API key in the `CHOCOLATEY_API_KEY` environment
+    /// variable.
+    pub fn new() -> ChocolateyPublish {
+        ChocolateyPublish {
+            feed: crate::defaults::publish_feed(),
+            api_key_env: crate::defaults::publish_api_key_env(),
+            profile: None,
+        }
+    }
+
+    /// Sets the url of the feed the package is pushed to.
+    pub fn with_feed(mut self, feed: Url) -> ChocolateyPublish {
+        self.feed = feed;
+        self
+    }
+
+    /// Sets the name of the environment variable holding the API key.
+    pub fn with_api_key_env<T: Into<String>>(mut self, api_key_env: T) -> ChocolateyPublish {
+        self.api_key_env = api_key_env.into();
+        self
+    }
+
+    /// Sets the name of the authentication profile to use.
+    pub fn with_profile<T: Into<String>>(mut self, profile: T) -> ChocolateyPublish {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    /// Returns the API key stored in the configured environment variable, if
+    /// the variable have been set.
+    pub fn api_key(&self) -> Option<String> {
+        std::env::var(&self.api_key_env)
+            .ok()
+            .filter(|key| !key.trim().is_empty())
+    }
+}
+
+impl Default for ChocolateyPublish {
+    fn default() -> ChocolateyPublish {
+        ChocolateyPublish::new()
+    }
 }
 
 impl ChocolateyMetadata {
@@ -114,6 +216,7 @@ impl ChocolateyMetadata {
             tags: vec![],
             release_notes: None,
             dependencies: HashMap::new(),
+            publish: None,
         }
     }
 
@@ -134,6 +237,22 @@ impl ChocolateyMetadata {
         &self.description
     }
 
+    /// Returns the tags that will be used to make the package easier to find.
+    pub fn tags(&self) -> &[String] {
+        self.tags.as_slice()
+    }
+
+    /// Returns the release notes of the software, if any have been set.
+    pub fn release_notes(&self) -> Option<&str> {
+        self.release_notes.as_deref()
+    }
+
+    /// Returns the identifiers and minimum versions of the packages this
+    /// package depends on.
+    pub fn dependencies(&self) -> &HashMap<String, Versions> {
+        &self.dependencies
+    }
+
     /// Sets the description of the package
     pub fn set_description(&mut self, description: Description) {
         self.description = description;
@@ -179,6 +298,19 @@ impl ChocolateyMetadata {
         self.dependencies = dependencies;
     }
 
+    /// Sets the authors/developers of the software, the authors are left
+    /// unchanged when no authors are specified.
+    pub fn set_authors<T>(&mut self, authors: &[T])
+    where
+        T: Display,
+    {
+        if authors.is_empty() {
+            return;
+        }
+
+        self.authors = authors.iter().map(|author| author.to_string()).collect();
+    }
+
     pub fn set_tags<T>(&mut self, tags: &[T]) -> &Self
     where
         T: Display,
@@ -241,6 +373,7 @@ mod tests {
             tags: vec![],
             release_notes: None,
             dependencies: HashMap::new(),
+            publish: None,
         };
 
         let actual = ChocolateyMetadata::new();
@@ -263,6 +396,7 @@ mod tests {
             tags: vec![],
             release_notes: None,
             dependencies: HashMap::new(),
+            publish: None,
         };
 
         let actual = ChocolateyMetadata::default();
@@ -307,6 +441,17 @@ mod tests {
         ChocolateyMetadata::with_authors(&val);
     }
 
+    #[test]
+    fn set_authors_should_not_remove_authors_when_empty() {
+        let mut data = ChocolateyMetadata::with_authors(&["AdmiringWorm"]);
+        let val: [&str; 0] = [];
+
+        data.set_authors(&val);
+        assert_eq!(data.authors(), ["AdmiringWorm"]);
+        data.set_authors(&["WormieCorp"]);
+        assert_eq!(data.authors(), ["WormieCorp"]);
+    }
+
     #[test]
     fn lowercase_id_should_return_set_values() {
         let mut data = ChocolateyMetadata::new();
@@ -336,4 +481,56 @@ mod tests {
 
         assert_eq!(data.description(), "My awesome description");
     }
+
+    #[test]
+    fn tags_should_return_set_values() {
+        let mut data = ChocolateyMetadata::new();
+        data.set_tags(&["aer", "updater"]);
+
+        let actual = data.tags();
+
+        assert_eq!(actual, ["aer", "updater"]);
+    }
+
+    #[test]
+    fn release_notes_should_return_set_values() {
+        let mut data = ChocolateyMetadata::new();
+        assert_eq!(data.release_notes(), None);
+        data.set_release_notes("Some release notes");
+
+        let actual = data.release_notes();
+
+        assert_eq!(actual, Some("Some release notes"));
+    }
+
+    #[test]
+    fn dependencies_should_return_set_values() {
+        let mut data = ChocolateyMetadata::new();
+        data.add_dependencies("chocolatey-core.extension", "1.3.3");
+
+        let actual = data.dependencies();
+
+        assert_eq!(
+            actual.get("chocolatey-core.extension"),
+            Some(&Versions::parse("1.3.3").unwrap())
+        );
+    }
+
+    #[test]
+    fn publish_new_should_push_to_community_repository() {
+        let actual = ChocolateyPublish::new();
+
+        assert_eq!(actual.feed.as_str(), "https://push.chocolatey.org/");
+        assert_eq!(actual.api_key_env, "CHOCOLATEY_API_KEY");
+    }
+
+    #[test]
+    fn publish_api_key_should_read_configured_environment_variable() {
+        std::env::set_var("AER_TEST_PUBLISH_API_KEY", "secret-key");
+        let publish = ChocolateyPublish::new().with_api_key_env("AER_TEST_PUBLISH_API_KEY");
+
+        let actual = publish.api_key();
+
+        assert_eq!(actual.as_deref(), Some("secret-key"));
+    }
 }
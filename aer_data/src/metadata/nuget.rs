@@ -0,0 +1,157 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains all data that can be used that are specific to plain NuGet
+//! packages (*ie: packages pushed to nuget.org*). Variables that are common
+//! between different packages managers are located in the default package
+//! data section.
+
+#![cfg_attr(docsrs, doc(cfg(feature = "nuget")))]
+
+use std::fmt::Display;
+
+use aer_version::Versions;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+/// The type of the NuGet package, which decides how the package can be
+/// consumed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(Deserialize, Serialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum NugetPackageType {
+    /// A package that is referenced as a dependency by other projects.
+    #[default]
+    Dependency,
+    /// A package containing a tool that is installed with `dotnet tool
+    /// install`.
+    DotnetTool,
+}
+
+impl Display for NugetPackageType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            NugetPackageType::Dependency => "Dependency",
+            NugetPackageType::DotnetTool => "DotnetTool",
+        })
+    }
+}
+
+/// Basic structure to hold information regarding a package that are only
+/// specific to creating plain NuGet packages.
+///
+/// ### Examples
+///
+/// A `TOML` edition of creating a NuGet package of a .NET tool in addition to
+/// the Chocolatey package.
+/// ```toml
+/// [metadata.nuget]
+/// authors = ["WormieCorp"]
+/// package_type = "dotnet-tool"
+/// tags = ["packaging", "updater"]
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
+#[non_exhaustive]
+pub struct NugetMetadata {
+    /// The version of the package, can be automatically updated and is not
+    /// necessary to initially be set.
+    #[cfg_attr(
+        feature = "serialize",
+        serde(default = "crate::defaults::empty_version")
+    )]
+    pub version: Versions,
+
+    /// The authors of the software, defaults to the maintainers of the
+    /// package when not set.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    authors: Vec<String>,
+
+    /// The description of the software, defaults to the summary of the
+    /// software when not set.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub description: Option<String>,
+
+    /// The release notes of the current version of the software.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub release_notes: Option<String>,
+
+    /// The type of the package.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub package_type: NugetPackageType,
+
+    #[cfg_attr(feature = "serialize", serde(default))]
+    tags: Vec<String>,
+}
+
+impl NugetMetadata {
+    /// Creates a new empty structure of NuGet metadata.
+    pub fn new() -> NugetMetadata {
+        NugetMetadata {
+            version: crate::defaults::empty_version(),
+            authors: vec![],
+            description: None,
+            release_notes: None,
+            package_type: NugetPackageType::default(),
+            tags: vec![],
+        }
+    }
+
+    /// Returns the authors of the software, an empty slice is returned when
+    /// the maintainers of the package should be used.
+    pub fn authors(&self) -> &[String] {
+        self.authors.as_slice()
+    }
+
+    /// Returns the tags that will be used to make the package easier to find.
+    pub fn tags(&self) -> &[String] {
+        self.tags.as_slice()
+    }
+
+    pub fn set_authors<T>(&mut self, authors: &[T])
+    where
+        T: Display,
+    {
+        self.authors = authors.iter().map(|author| author.to_string()).collect();
+    }
+
+    pub fn set_tags<T>(&mut self, tags: &[T])
+    where
+        T: Display,
+    {
+        self.tags = tags.iter().map(|tag| tag.to_string()).collect();
+    }
+}
+
+impl Default for NugetMetadata {
+    fn default() -> NugetMetadata {
+        NugetMetadata::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_should_create_with_expected_values() {
+        let actual = NugetMetadata::new();
+
+        assert_eq!(actual.version, crate::defaults::empty_version());
+        assert_eq!(actual.package_type, NugetPackageType::Dependency);
+        assert!(actual.authors().is_empty());
+        assert!(actual.tags().is_empty());
+    }
+
+    #[test]
+    fn set_authors_should_replace_authors() {
+        let mut data = NugetMetadata::new();
+        data.set_authors(&["AdmiringWorm"]);
+        data.set_authors(&["WormieCorp"]);
+
+        assert_eq!(data.authors(), ["WormieCorp"]);
+    }
+}
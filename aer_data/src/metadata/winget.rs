@@ -0,0 +1,187 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains all data that can be used that are specific to winget manifests.
+//! Variables that are common between different packages managers are located in
+//! the default package data section.
+
+#![cfg_attr(docsrs, doc(cfg(feature = "winget")))]
+
+use std::fmt::Display;
+
+use aer_version::Versions;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+/// The type of installer used by the software, which decides how winget runs
+/// the installer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(Deserialize, Serialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum WingetInstallerType {
+    /// An executable installer, the silent switches must be specified.
+    #[default]
+    Exe,
+    /// A Windows Installer package.
+    Msi,
+    /// A MSIX or APPX package.
+    Msix,
+    /// An installer created with Inno Setup.
+    Inno,
+    /// An installer created with the Nullsoft Scriptable Install System.
+    Nullsoft,
+    /// A Windows Installer package created with the WiX toolset.
+    Wix,
+    /// A bundle created with the WiX toolset.
+    Burn,
+    /// A standalone executable that do not need to be installed.
+    Portable,
+}
+
+impl Display for WingetInstallerType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            WingetInstallerType::Exe => "exe",
+            WingetInstallerType::Msi => "msi",
+            WingetInstallerType::Msix => "msix",
+            WingetInstallerType::Inno => "inno",
+            WingetInstallerType::Nullsoft => "nullsoft",
+            WingetInstallerType::Wix => "wix",
+            WingetInstallerType::Burn => "burn",
+            WingetInstallerType::Portable => "portable",
+        })
+    }
+}
+
+/// Basic structure to hold information regarding a package that are only
+/// specific to creating winget manifests.
+///
+/// ### Examples
+///
+/// A `TOML` edition of creating winget manifests in addition to the
+/// Chocolatey package.
+/// ```toml
+/// [metadata.winget]
+/// package_identifier = "WormieCorp.Aer"
+/// publisher = "WormieCorp"
+/// installer_type = "inno"
+/// tags = ["packaging", "updater"]
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
+#[non_exhaustive]
+pub struct WingetMetadata {
+    /// The identifier of the package in the winget repository, in the form
+    /// `Publisher.Name` (*ie: `WormieCorp.Aer`*).
+    package_identifier: String,
+
+    /// The publisher of the software.
+    publisher: String,
+
+    /// The name of the software, defaults to the identifier of the package
+    /// when not set.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub package_name: Option<String>,
+
+    /// The version of the software, can be automatically updated and is not
+    /// necessary to initially be set.
+    #[cfg_attr(
+        feature = "serialize",
+        serde(default = "crate::defaults::empty_version")
+    )]
+    pub version: Versions,
+
+    /// The short description of the software, defaults to the summary of the
+    /// software when not set.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub short_description: Option<String>,
+
+    /// The common name the software is installed by (*ie: `vscode`*).
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub moniker: Option<String>,
+
+    /// The type of the installer of the software.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub installer_type: WingetInstallerType,
+
+    /// The arguments used to install the software silently, only needed for
+    /// executable installers.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub silent_switches: Option<String>,
+
+    #[cfg_attr(feature = "serialize", serde(default))]
+    tags: Vec<String>,
+}
+
+impl WingetMetadata {
+    /// Creates a new structure with the specified identifier in the winget
+    /// repository and the publisher of the software.
+    pub fn new(package_identifier: &str, publisher: &str) -> WingetMetadata {
+        WingetMetadata {
+            package_identifier: package_identifier.into(),
+            publisher: publisher.into(),
+            package_name: None,
+            version: crate::defaults::empty_version(),
+            short_description: None,
+            moniker: None,
+            installer_type: WingetInstallerType::default(),
+            silent_switches: None,
+            tags: vec![],
+        }
+    }
+
+    /// Returns the identifier of the package in the winget repository.
+    pub fn package_identifier(&self) -> &str {
+        &self.package_identifier
+    }
+
+    /// Returns the publisher of the software.
+    pub fn publisher(&self) -> &str {
+        &self.publisher
+    }
+
+    /// Returns the tags that will be used to make the package easier to find.
+    pub fn tags(&self) -> &[String] {
+        self.tags.as_slice()
+    }
+
+    pub fn set_tags<T>(&mut self, tags: &[T])
+    where
+        T: Display,
+    {
+        self.tags = tags.iter().map(|tag| tag.to_string()).collect();
+    }
+}
+
+impl Default for WingetMetadata {
+    fn default() -> WingetMetadata {
+        WingetMetadata::new("", "")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_should_create_with_expected_values() {
+        let actual = WingetMetadata::new("WormieCorp.Aer", "WormieCorp");
+
+        assert_eq!(actual.package_identifier(), "WormieCorp.Aer");
+        assert_eq!(actual.publisher(), "WormieCorp");
+        assert_eq!(actual.installer_type, WingetInstallerType::Exe);
+        assert!(actual.tags().is_empty());
+    }
+
+    #[test]
+    fn set_tags_should_replace_tags() {
+        let mut data = WingetMetadata::new("WormieCorp.Aer", "WormieCorp");
+        data.set_tags(&["packaging"]);
+        data.set_tags(&["updater", "cli"]);
+
+        assert_eq!(data.tags(), ["updater", "cli"]);
+    }
+}
@@ -2,21 +2,40 @@
 // Licensed under the MIT license. See LICENSE.txt file in the project
 
 pub use aer_license::LicenseType;
-pub use aer_version::{FixVersion, SemVersion, Versions};
+pub use aer_version::{FixVersion, SemVersion, VersionTransform, Versions};
 pub use url::Url;
 
+pub use crate::lints::PackageLints;
 pub use crate::metadata::{Description, PackageMetadata};
-pub use crate::updater::PackageUpdateData;
+pub use crate::updater::{
+    Architecture, AuthRule, CheckFrequency, JsonSourceData, LinkSelection, LinkSource,
+    PackageUpdateData, PaginationData, ParseUrl, SignatureData, UpdaterSource, UpdaterTarget,
+    UpdaterUrls, VersionSource, XmlSourceData,
+};
 pub use crate::PackageData;
 
 /// Re-Exports of usable chocolatey types.
 #[cfg(feature = "chocolatey")]
 #[cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
 pub mod chocolatey {
-    pub use aer_version::chocolatey::ChocoVersion;
+    pub use aer_version::chocolatey::{ChocoVersion, FixScheme, FixTimeZone};
 
-    pub use crate::metadata::chocolatey::ChocolateyMetadata;
+    pub use crate::metadata::chocolatey::{ChocolateyMetadata, ChocolateyPublish};
     pub use crate::updater::chocolatey::{
         ChocolateyParseUrl, ChocolateyUpdaterData, ChocolateyUpdaterType,
     };
 }
+
+/// Re-Exports of usable NuGet types.
+#[cfg(feature = "nuget")]
+#[cfg_attr(docsrs, doc(cfg(feature = "nuget")))]
+pub mod nuget {
+    pub use crate::metadata::nuget::{NugetMetadata, NugetPackageType};
+}
+
+/// Re-Exports of usable winget types.
+#[cfg(feature = "winget")]
+#[cfg_attr(docsrs, doc(cfg(feature = "winget")))]
+pub mod winget {
+    pub use crate::metadata::winget::{WingetInstallerType, WingetMetadata};
+}
@@ -0,0 +1,289 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains fluent builders that makes it easier to construct package data
+//! when writing tests, without needing to assemble the nested structures by
+//! hand.
+//!
+//! ### Examples
+//!
+//! Creating package data ready to be used when creating a Chocolatey package,
+//! using GitHub releases as the updater source.
+//! ```
+//! use aer_data::testing::PackageDataFixture;
+//!
+//! let data = PackageDataFixture::minimal()
+//!     .with_choco_defaults()
+//!     .with_updater_github("WormieCorp/aer")
+//!     .build();
+//!
+//! assert_eq!(data.metadata().id(), "test-package");
+//! assert_eq!(data.updater().repository.as_deref(), Some("WormieCorp/aer"));
+//! ```
+
+#![cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+
+use aer_license::LicenseType;
+use url::Url;
+
+#[cfg(feature = "chocolatey")]
+use crate::metadata::chocolatey::{ChocolateyMetadata, ChocolateyPublish};
+#[cfg(feature = "nuget")]
+use crate::metadata::nuget::NugetMetadata;
+#[cfg(feature = "winget")]
+use crate::metadata::winget::WingetMetadata;
+#[cfg(feature = "chocolatey")]
+use crate::updater::chocolatey::{ChocolateyParseUrl, ChocolateyUpdaterData};
+use crate::updater::UpdaterSource;
+use crate::PackageData;
+
+/// The identifier used by the minimal package data.
+pub const FIXTURE_ID: &str = "test-package";
+
+/// The maintainer used by the fixtures, used instead of the current user to
+/// keep the package data the same on every machine.
+pub const FIXTURE_MAINTAINER: &str = "AdmiringWorm";
+
+/// The project url used by the minimal package data.
+pub const FIXTURE_PROJECT_URL: &str = "https://github.com/WormieCorp/aer";
+
+/// The license url used by the minimal package data.
+pub const FIXTURE_LICENSE_URL: &str = "https://github.com/WormieCorp/aer/blob/develop/LICENSE.txt";
+
+/// Fluent builder for creating package data used in tests.
+#[derive(Debug)]
+pub struct PackageDataFixture {
+    data: PackageData,
+}
+
+impl PackageDataFixture {
+    /// Creates a new fixture with the specified identifier, and the maintainer
+    /// used by all fixtures. All other values are left at their defaults.
+    pub fn new(id: &str) -> PackageDataFixture {
+        let mut data = PackageData::new(id);
+        data.metadata_mut().set_maintainers(&[FIXTURE_MAINTAINER]);
+
+        PackageDataFixture { data }
+    }
+
+    /// Creates a new fixture with the identifier `test-package`, with the
+    /// project url and license url of this repository.
+    pub fn minimal() -> PackageDataFixture {
+        PackageDataFixture::new(FIXTURE_ID)
+            .with_project_url(FIXTURE_PROJECT_URL)
+            .with_license(LicenseType::Location(
+                Url::parse(FIXTURE_LICENSE_URL).unwrap(),
+            ))
+    }
+
+    /// Sets the summary of the software.
+    pub fn with_summary(mut self, summary: &str) -> PackageDataFixture {
+        self.data.metadata_mut().summary = summary.into();
+        self
+    }
+
+    /// Sets the maintainers of the package.
+    pub fn with_maintainers(mut self, maintainers: &[&str]) -> PackageDataFixture {
+        self.data.metadata_mut().set_maintainers(maintainers);
+        self
+    }
+
+    /// Sets the project url of the software.
+    pub fn with_project_url(mut self, url: &str) -> PackageDataFixture {
+        self.data.metadata_mut().set_project_url(url);
+        self
+    }
+
+    /// Sets the license of the software.
+    pub fn with_license(mut self, license: LicenseType) -> PackageDataFixture {
+        self.data.metadata_mut().set_license(license);
+        self
+    }
+
+    /// Sets the Chocolatey metadata to values that are accepted when creating
+    /// a Chocolatey package, the authors, a description, the version `1.0.0`
+    /// and a title.
+    #[cfg(feature = "chocolatey")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
+    pub fn with_choco_defaults(self) -> PackageDataFixture {
+        self.with_choco(|choco| {
+            *choco = ChocolateyMetadata::with_authors(&[FIXTURE_MAINTAINER]);
+            choco.version = crate::prelude::Versions::parse("1.0.0").unwrap();
+            choco.set_title("Test Package");
+            choco.set_description_str("Package data created by the testing fixtures");
+        })
+    }
+
+    /// Modifies the Chocolatey metadata using the specified function, new
+    /// metadata is created when the package do not have any.
+    #[cfg(feature = "chocolatey")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
+    pub fn with_choco<F: FnOnce(&mut ChocolateyMetadata)>(mut self, func: F) -> PackageDataFixture {
+        let mut choco = self.data.metadata().chocolatey().into_owned();
+        func(&mut choco);
+        self.data.metadata_mut().set_chocolatey(choco);
+        self
+    }
+
+    /// Sets the feed the Chocolatey package is published to.
+    #[cfg(feature = "chocolatey")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
+    pub fn with_choco_publish(self, publish: ChocolateyPublish) -> PackageDataFixture {
+        self.with_choco(|choco| choco.publish = Some(publish))
+    }
+
+    /// Modifies the winget metadata using the specified function, new metadata
+    /// is created when the package do not have any.
+    #[cfg(feature = "winget")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "winget")))]
+    pub fn with_winget<F: FnOnce(&mut WingetMetadata)>(mut self, func: F) -> PackageDataFixture {
+        let mut winget = self.data.metadata().winget().into_owned();
+        func(&mut winget);
+        self.data.metadata_mut().set_winget(winget);
+        self
+    }
+
+    /// Modifies the NuGet metadata using the specified function, new metadata
+    /// is created when the package do not have any.
+    #[cfg(feature = "nuget")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "nuget")))]
+    pub fn with_nuget<F: FnOnce(&mut NugetMetadata)>(mut self, func: F) -> PackageDataFixture {
+        let mut nuget = self.data.metadata().nuget().into_owned();
+        func(&mut nuget);
+        self.data.metadata_mut().set_nuget(nuget);
+        self
+    }
+
+    /// Finds new releases using the GitHub releases of the specified
+    /// repository (*ie: `WormieCorp/aer`*).
+    pub fn with_updater_github(self, repository: &str) -> PackageDataFixture {
+        self.with_updater_source(UpdaterSource::GitHub, repository)
+    }
+
+    /// Finds new releases using the GitLab releases of the specified project.
+    pub fn with_updater_gitlab(self, repository: &str) -> PackageDataFixture {
+        self.with_updater_source(UpdaterSource::GitLab, repository)
+    }
+
+    /// Finds new releases using the files of the specified SourceForge
+    /// project.
+    pub fn with_updater_sourceforge(self, repository: &str) -> PackageDataFixture {
+        self.with_updater_source(UpdaterSource::SourceForge, repository)
+    }
+
+    fn with_updater_source(
+        mut self,
+        source: UpdaterSource,
+        repository: &str,
+    ) -> PackageDataFixture {
+        let updater = self.data.updater_mut();
+        updater.source = source;
+        updater.repository = Some(repository.into());
+        self
+    }
+
+    /// Finds new releases by parsing the html page at the specified url.
+    #[cfg(feature = "chocolatey")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
+    pub fn with_updater_html(self, parse_url: &str) -> PackageDataFixture {
+        let parse_url = ChocolateyParseUrl::Url(Url::parse(parse_url).unwrap());
+
+        self.with_choco_updater(|updater| updater.parse_url = Some(parse_url))
+    }
+
+    /// Adds a regex used when finding the links of new releases (*ie:
+    /// `arch64`*).
+    #[cfg(feature = "chocolatey")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
+    pub fn with_updater_regex(self, name: &str, regex: &str) -> PackageDataFixture {
+        self.with_choco_updater(|updater| updater.add_regex(name, regex))
+    }
+
+    /// Modifies the Chocolatey updater data using the specified function, new
+    /// updater data is created when the package do not have any.
+    #[cfg(feature = "chocolatey")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
+    pub fn with_choco_updater<F: FnOnce(&mut ChocolateyUpdaterData)>(
+        mut self,
+        func: F,
+    ) -> PackageDataFixture {
+        let mut updater = self.data.updater().chocolatey().into_owned();
+        func(&mut updater);
+        self.data.updater_mut().set_chocolatey(updater);
+        self
+    }
+
+    /// Allows the findings with the specified code, with an optional note
+    /// justifying the exception.
+    pub fn with_lint_allowed(mut self, code: &str, note: Option<&str>) -> PackageDataFixture {
+        self.data.lints_mut().add_allow(code, note);
+        self
+    }
+
+    /// Returns the package data created by the fixture.
+    pub fn build(self) -> PackageData {
+        self.data
+    }
+}
+
+impl From<PackageDataFixture> for PackageData {
+    fn from(fixture: PackageDataFixture) -> Self {
+        fixture.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimal_should_create_package_with_project_and_license() {
+        let actual = PackageDataFixture::minimal().build();
+
+        assert_eq!(actual.metadata().id(), FIXTURE_ID);
+        assert_eq!(actual.metadata().maintainers(), [FIXTURE_MAINTAINER]);
+        assert_eq!(
+            actual.metadata().project_url().as_str(),
+            FIXTURE_PROJECT_URL
+        );
+        assert_eq!(
+            actual.metadata().license().license_url(),
+            Some(FIXTURE_LICENSE_URL)
+        );
+    }
+
+    #[cfg(feature = "chocolatey")]
+    #[test]
+    fn with_choco_defaults_should_set_chocolatey_metadata() {
+        let actual = PackageDataFixture::minimal()
+            .with_choco_defaults()
+            .with_choco(|choco| {
+                choco.set_tags(&["aer"]);
+            })
+            .build();
+
+        let choco = actual.metadata().chocolatey();
+        assert_eq!(choco.authors(), [FIXTURE_MAINTAINER]);
+        assert_eq!(choco.version.to_string(), "1.0.0");
+        assert_eq!(choco.tags(), ["aer"]);
+    }
+
+    #[cfg(feature = "chocolatey")]
+    #[test]
+    fn with_updater_github_should_set_source_and_regexes() {
+        let actual = PackageDataFixture::minimal()
+            .with_updater_github("WormieCorp/aer")
+            .with_updater_regex("arch64", r"\.exe$")
+            .build();
+
+        assert_eq!(actual.updater().source, UpdaterSource::GitHub);
+        assert_eq!(
+            actual.updater().repository.as_deref(),
+            Some("WormieCorp/aer")
+        );
+        assert_eq!(
+            actual.updater().chocolatey().regexes().get("arch64"),
+            Some(&r"\.exe$".to_string())
+        );
+    }
+}
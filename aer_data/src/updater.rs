@@ -1,30 +1,645 @@
 // Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
 // Licensed under the MIT license. See LICENSE.txt file in the project
 
+mod architecture;
 pub mod chocolatey;
+mod urls;
 
 use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::time::Duration;
 
+use aer_version::{VersionTransform, Versions};
+pub use architecture::Architecture;
 #[cfg(feature = "serialize")]
 use serde::{Deserialize, Serialize};
+use url::Url;
+pub use urls::{ParseUrl, UpdaterTarget, UpdaterUrls};
+
+/// How often a package should be checked for updates when running several
+/// packages at once.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(Deserialize, Serialize),
+    serde(from = "String", into = "String")
+)]
+pub enum CheckFrequency {
+    /// The package is checked every time the updater runs.
+    #[default]
+    Always,
+    /// The package is checked at most once every hour.
+    Hourly,
+    /// The package is checked at most once every day.
+    Daily,
+    /// The package is checked at most once every week.
+    Weekly,
+    /// The package is checked according to the specified cron expression.
+    Cron(String),
+}
+
+impl CheckFrequency {
+    /// Returns the fixed interval between each check, or `None` if the
+    /// package should always be checked or uses a cron expression.
+    pub fn interval(&self) -> Option<Duration> {
+        match self {
+            CheckFrequency::Hourly => Some(Duration::from_secs(60 * 60)),
+            CheckFrequency::Daily => Some(Duration::from_secs(24 * 60 * 60)),
+            CheckFrequency::Weekly => Some(Duration::from_secs(7 * 24 * 60 * 60)),
+            _ => None,
+        }
+    }
+}
+
+impl From<String> for CheckFrequency {
+    fn from(val: String) -> Self {
+        match val.trim().to_lowercase().as_str() {
+            "always" => CheckFrequency::Always,
+            "hourly" => CheckFrequency::Hourly,
+            "daily" => CheckFrequency::Daily,
+            "weekly" => CheckFrequency::Weekly,
+            _ => CheckFrequency::Cron(val.trim().into()),
+        }
+    }
+}
+
+impl From<CheckFrequency> for String {
+    fn from(val: CheckFrequency) -> Self {
+        val.to_string()
+    }
+}
+
+impl Display for CheckFrequency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            CheckFrequency::Always => f.write_str("always"),
+            CheckFrequency::Hourly => f.write_str("hourly"),
+            CheckFrequency::Daily => f.write_str("daily"),
+            CheckFrequency::Weekly => f.write_str("weekly"),
+            CheckFrequency::Cron(expression) => f.write_str(expression),
+        }
+    }
+}
+
+/// Additional places in an html page where download links should be extracted
+/// from, for pages where the links are not available as plain anchors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(Deserialize, Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum LinkSource {
+    /// Links in `<meta http-equiv="refresh">` elements.
+    MetaRefresh,
+    /// Links assigned to variables in script elements (*ie: `var downloadUrl =
+    /// "..."`*).
+    ScriptVariables,
+    /// Links in JSON embedded in `<script type="application/json">` elements.
+    ScriptJson,
+}
+
+/// The source that is used when finding new releases of the software.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(Deserialize, Serialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum UpdaterSource {
+    /// The links are extracted from the html page specified as the parse url.
+    #[default]
+    Html,
+    /// The links are read from the releases of a repository hosted on
+    /// [GitHub](https://github.com), using the GitHub Releases API.
+    GitHub,
+    /// The links are read from the releases of a project hosted on
+    /// [GitLab](https://gitlab.com) or a self-hosted GitLab instance.
+    GitLab,
+    /// The links are read from the RSS feed of the files of a project hosted
+    /// on [SourceForge](https://sourceforge.net).
+    SourceForge,
+    /// The links and the version are extracted from the JSON document
+    /// returned by the parse url (*ie: the API of a vendor*), using the
+    /// expressions specified in the json settings of the updater.
+    Json,
+    /// The links are read from the entries of the RSS or Atom feed specified
+    /// as the parse url. When the parse url have a regex, only entries with a
+    /// title matching the regex are used and the version is read from the
+    /// `version` group of the regex.
+    Feed,
+    /// The links and the versions are extracted from the XML document
+    /// returned by the parse url (*ie: an appcast feed or an update
+    /// manifest*), using the queries specified in the xml settings of the
+    /// updater.
+    Xml,
+}
+
+/// The source the version of the package is read from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(Deserialize, Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum VersionSource {
+    /// The version is parsed from the links that was found, preferring the
+    /// architecture specific links over the release page.
+    #[default]
+    Link,
+    /// The version is read from the product version of the downloaded
+    /// installers (*ie: `.exe` and `.msi` files*).
+    ProductVersion,
+    /// The version is read from the file version of the downloaded
+    /// executables, installers without a file version uses the product
+    /// version.
+    FileVersion,
+}
+
+/// The policy used when selecting the link of an architecture, when more than
+/// one link matches the regex of the architecture.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(Deserialize, Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum LinkSelection {
+    /// The first matching link is used, in the order the links were found.
+    #[default]
+    FirstMatch,
+    /// The matching link with the highest version is used, links without a
+    /// version are only used when no other links have a version.
+    HighestVersion,
+    /// The matching link with the latest publish date (*ie: the
+    /// `published-at` attribute of releases and feed entries*) is used, links
+    /// without a date are only used when no other links have a date.
+    LatestByDate,
+}
+
+/// A rule selecting the authentication profile used when requesting the urls
+/// matching a pattern, the profiles themselves are defined in the global
+/// configuration file. The rule is only used for the host of the feed of the
+/// profile, or for the hosts listed in the `hosts` of the profile.
+///
+/// ### Examples
+///
+/// ```toml
+/// [[updater.auth]]
+/// pattern = "https://downloads.example.org/private/*"
+/// profile = "internal"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
+pub struct AuthRule {
+    /// The pattern the urls are matched against, where `*` matches any
+    /// number of characters.
+    pub pattern: String,
+    /// The name of the authentication profile used for the matching urls.
+    pub profile: String,
+}
+
+impl AuthRule {
+    /// Creates a new rule using the specified profile for the urls matching
+    /// the pattern.
+    pub fn new<P: Into<String>, N: Into<String>>(pattern: P, profile: N) -> AuthRule {
+        AuthRule {
+            pattern: pattern.into(),
+            profile: profile.into(),
+        }
+    }
+}
+
+/// The expressions used to extract the links and the version from the JSON
+/// document returned by the parse url, when using the json source. The
+/// expressions are either JSONPath expressions (*ie: `$.assets[*].url`*),
+/// JSON pointers (*ie: `/assets/0/url`*) or dotted paths (*ie:
+/// `assets.0.url`*).
+///
+/// ### Examples
+///
+/// ```toml
+/// [updater.json]
+/// urls = "$.assets[*].browser_download_url"
+/// version = "$.tag_name"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
+pub struct JsonSourceData {
+    /// The expression selecting the urls of the upstream files.
+    pub urls: String,
+    /// The expression selecting the version of the release, the version is
+    /// parsed from the links when no expression have been specified.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub version: Option<String>,
+}
+
+impl JsonSourceData {
+    /// Creates new settings selecting the urls using the specified
+    /// expression.
+    pub fn new<U: Into<String>>(urls: U) -> JsonSourceData {
+        JsonSourceData {
+            urls: urls.into(),
+            version: None,
+        }
+    }
+}
+
+/// The XPath-like queries used to extract the links and the versions from
+/// the XML document returned by the parse url, when using the xml source. The
+/// url and version queries are evaluated against each of the elements
+/// selected by the items query, unless the query starts with `/`.
+///
+/// ### Examples
+///
+/// ```toml
+/// [updater.xml]
+/// items = "//item"
+/// url = "enclosure/@url"
+/// version = "enclosure/@sparkle:version"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
+pub struct XmlSourceData {
+    /// The query selecting the elements holding the links and versions.
+    pub items: String,
+    /// The query selecting the urls of the upstream files.
+    pub url: String,
+    /// The query selecting the version of the upstream files, the version is
+    /// parsed from the links when no query have been specified.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub version: Option<String>,
+}
+
+impl XmlSourceData {
+    /// Creates new settings selecting the urls of the specified items.
+    pub fn new<I: Into<String>, U: Into<String>>(items: I, url: U) -> XmlSourceData {
+        XmlSourceData {
+            items: items.into(),
+            url: url.into(),
+            version: None,
+        }
+    }
+}
+
+/// The settings used when following the pages of a paginated html page (*ie:
+/// a release listing*), the links of every page are used when finding the
+/// newest release.
+///
+/// ### Examples
+///
+/// ```toml
+/// [updater.pagination]
+/// next = "^Older releases"
+/// max_pages = 3
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
+pub struct PaginationData {
+    /// The regex matched against the link and the text of the anchor elements
+    /// to find the next page, the element with the `rel="next"` relation is
+    /// used when no regex have been specified.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub next: Option<String>,
+
+    /// The maximum number of pages to read, including the first page,
+    /// defaults to `5`.
+    #[cfg_attr(feature = "serialize", serde(default = "crate::defaults::max_pages"))]
+    pub max_pages: usize,
+}
+
+impl PaginationData {
+    /// Creates new settings following the `rel="next"` links, reading up to 5
+    /// pages.
+    pub fn new() -> PaginationData {
+        PaginationData {
+            next: None,
+            max_pages: crate::defaults::max_pages(),
+        }
+    }
+}
+
+impl Default for PaginationData {
+    fn default() -> PaginationData {
+        PaginationData::new()
+    }
+}
+
+/// The settings used when verifying the signatures of the downloaded files.
+///
+/// ### Examples
+///
+/// ```toml
+/// [updater.signature]
+/// gpg_keys = ["keys/release-key.asc"]
+/// authenticode = true
+/// required = true
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
+#[non_exhaustive]
+pub struct SignatureData {
+    /// The public keys (*armored or binary*) the detached GPG signatures of
+    /// the downloaded files are verified against. Relative paths are resolved
+    /// against the directory of the package data file, and no GPG signatures
+    /// are verified when no keys have been specified.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub gpg_keys: Vec<PathBuf>,
+
+    /// The extension appended to the url of a downloaded file to get the url
+    /// of its detached signature, defaults to `.asc`.
+    #[cfg_attr(
+        feature = "serialize",
+        serde(default = "crate::defaults::signature_extension")
+    )]
+    pub extension: String,
+
+    /// Wether the Authenticode signatures of downloaded installers (*ie:
+    /// `.exe` and `.msi` files*) should be checked, this is only supported on
+    /// Windows.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub authenticode: bool,
+
+    /// Wether the package should fail when a signature is invalid or could
+    /// not be verified, instead of only reporting a warning.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub required: bool,
+}
+
+impl SignatureData {
+    /// Creates new signature settings where no signatures are verified.
+    pub fn new() -> SignatureData {
+        SignatureData {
+            gpg_keys: vec![],
+            extension: crate::defaults::signature_extension(),
+            authenticode: false,
+            required: false,
+        }
+    }
+
+    /// Returns wether any signatures should be verified.
+    pub fn is_enabled(&self) -> bool {
+        !self.gpg_keys.is_empty() || self.authenticode
+    }
+}
+
+impl Default for SignatureData {
+    fn default() -> SignatureData {
+        SignatureData::new()
+    }
+}
 
 #[derive(Debug, Default, PartialEq)]
 #[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
 #[non_exhaustive]
 pub struct PackageUpdateData {
+    /// The source used when finding new releases of the software, defaults to
+    /// parsing the html page specified as the parse url.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub source: UpdaterSource,
+
+    /// The repository the releases are read from when using a source other
+    /// than html, specified in the form `owner/repo`. Projects on self-hosted
+    /// GitLab instances are specified using the full url to the project, and
+    /// projects on SourceForge using the name of the project optionally
+    /// followed by the folder to read files from (*ie: `astyle/astyle`*).
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub repository: Option<String>,
+
+    /// How often the package should be checked for updates, defaults to
+    /// always checking the package.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub check_frequency: CheckFrequency,
+
+    /// The expressions used to extract the links and the version when using
+    /// the json source.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub json: Option<JsonSourceData>,
+
+    /// The queries used to extract the links and the versions when using the
+    /// xml source.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub xml: Option<XmlSourceData>,
+
+    /// Additional sources that links should be extracted from when parsing
+    /// html pages, anchor elements are always used.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub link_sources: Vec<LinkSource>,
+
+    /// The CSS selector, or XPath-like query when starting with `/`, matching
+    /// the anchor elements or the sections of the html page the links should
+    /// be extracted from (*ie: `table#downloads`*). Applies to the page the
+    /// download links are read from.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub link_selector: Option<String>,
+
+    /// The CSS selector, or XPath-like query when starting with `/` or `./`,
+    /// matching the element the version of a link is read from (*ie: the
+    /// `td.version` cell in the same table row as the link*). Takes
+    /// precedence over the version captured by the regexes.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub version_selector: Option<String>,
+
+    /// The settings used when following the next pages of the html page the
+    /// download links are read from, only the first page is read when not
+    /// set.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub pagination: Option<PaginationData>,
+
+    /// Wether the html pages of the package should be rendered using a
+    /// headless browser before the links are extracted, used for download
+    /// pages that are rendered by JavaScript. This requires a Chromium based
+    /// browser to be installed.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub render: bool,
+
+    /// The scripts that should be run after the upstream files have been
+    /// downloaded, before the package is created. Relative paths are resolved
+    /// against the directory of the package data file.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub scripts: Vec<PathBuf>,
+
+    /// The script templates that are rendered into the package directory when
+    /// the package is created, replacing tokens like `{{url64}}` and
+    /// `{{checksum64}}` with the values found during the update. Relative
+    /// paths are resolved against the directory of the package data file, and
+    /// keeps the same relative path inside the package directory.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub templates: Vec<PathBuf>,
+
+    /// The languages that should be requested when parsing the upstream
+    /// location of the package (*ie: `de-DE, de;q=0.9`*), overriding the
+    /// globally configured languages.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub accept_language: Option<String>,
+
+    /// The url of the checksum file published next to the upstream files
+    /// (*ie: `SHA256SUMS`*), the downloaded files are verified against the
+    /// checksums in the file.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub checksum_url: Option<Url>,
+
+    /// The regex used to find the checksum file among the links of the parsed
+    /// page or release, used instead of the checksum url when the location of
+    /// the checksum file changes between releases.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub checksum_regex: Option<String>,
+
+    /// The settings used when verifying the signatures of the downloaded
+    /// files.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub signature: SignatureData,
+
+    /// The source the version of the package is read from, used for software
+    /// where the version is not part of the download url.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub version_source: VersionSource,
+
+    /// The transformations applied in order to the upstream versions before
+    /// these are used (*ie: `["strip_prefix:v", "take_parts:3"]`*), see
+    /// [aer_version::transform] for the available operations.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub version_transforms: Vec<VersionTransform>,
+
+    /// The policy used when selecting the 32bit and 64bit links, when more
+    /// than one link matches the regex of the architecture. Defaults to using
+    /// the first matching link.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub selection: LinkSelection,
+
+    /// Wether pre-release versions (*ie: `1.0.0-beta.1`*) should be used when
+    /// finding new releases, only stable releases are used by default.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub allow_prerelease: bool,
+
+    /// The pre-release channels that should be used when pre-releases are
+    /// allowed (*ie: `beta`*), any channel is used when no channels have been
+    /// specified. Stable releases are always used.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub channels: Vec<String>,
+
+    /// The suffix appended to the identifier of the package when the version
+    /// found is a pre-release (*ie: `.beta`*), used to maintain the
+    /// pre-releases as a separate package.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub prerelease_suffix: Option<String>,
+
+    /// The rules selecting the authentication profiles used when requesting
+    /// the upstream locations and files of the package, these takes
+    /// precedence over the feeds of the profiles.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub auth: Vec<AuthRule>,
+
+    /// The architectures of the links matched by the regexes with the
+    /// specified names, used for regexes not named after the architecture
+    /// (*ie: `arch32`, `arch64`, `arm64` or `universal`*).
+    ///
+    /// ### Examples
+    ///
+    /// ```toml
+    /// [updater.architectures]
+    /// installer_arm = "arm64"
+    /// ```
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub architectures: HashMap<String, Architecture>,
+
+    /// The upstream location and regexes shared between all targets, used
+    /// by the targets that do not specify their own location or regexes.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub urls: UpdaterUrls,
+
     #[cfg(feature = "chocolatey")]
     #[cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
     chocolatey: Option<chocolatey::ChocolateyUpdaterData>,
+
+    #[cfg(feature = "winget")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "winget")))]
+    #[cfg_attr(feature = "serialize", serde(default))]
+    winget: Option<UpdaterUrls>,
+
+    #[cfg(feature = "nuget")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "nuget")))]
+    #[cfg_attr(feature = "serialize", serde(default))]
+    nuget: Option<UpdaterUrls>,
 }
 
 impl PackageUpdateData {
     pub fn new() -> PackageUpdateData {
         PackageUpdateData {
+            source: UpdaterSource::default(),
+            repository: None,
+            check_frequency: CheckFrequency::default(),
+            json: None,
+            xml: None,
+            link_sources: vec![],
+            link_selector: None,
+            version_selector: None,
+            pagination: None,
+            render: false,
+            scripts: vec![],
+            templates: vec![],
+            accept_language: None,
+            checksum_url: None,
+            checksum_regex: None,
+            signature: SignatureData::new(),
+            version_source: VersionSource::default(),
+            version_transforms: vec![],
+            selection: LinkSelection::default(),
+            allow_prerelease: false,
+            channels: vec![],
+            prerelease_suffix: None,
+            auth: vec![],
+            architectures: HashMap::new(),
+            urls: UpdaterUrls::new(),
             #[cfg(feature = "chocolatey")]
             chocolatey: None,
+            #[cfg(feature = "winget")]
+            winget: None,
+            #[cfg(feature = "nuget")]
+            nuget: None,
+        }
+    }
+
+    /// Returns wether the version can be used as a new release of the
+    /// package, stable versions are always used while pre-release versions
+    /// are only used when pre-releases are allowed and the channel of the
+    /// version is one of the configured channels.
+    pub fn allows_version(&self, version: &Versions) -> bool {
+        match version.channel() {
+            None => true,
+            Some(_) if !self.allow_prerelease => false,
+            Some(_) if self.channels.is_empty() => true,
+            Some(channel) => self
+                .channels
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(&channel)),
         }
     }
 
+    /// Returns the identifier the package should use for the version, this is
+    /// the identifier with the pre-release suffix appended when the version
+    /// is a pre-release and a suffix have been specified.
+    pub fn package_id<'a>(&self, id: &'a str, version: &Versions) -> Cow<'a, str> {
+        match self.prerelease_suffix {
+            Some(ref suffix) if version.is_prerelease() && !id.ends_with(suffix.as_str()) => {
+                Cow::Owned(format!("{}{}", id, suffix))
+            }
+            _ => Cow::Borrowed(id),
+        }
+    }
+
+    /// Returns the architecture of the links matched by the regex with the
+    /// specified name, the architectures specified in the updater data takes
+    /// precedence over the well-known names of the regexes.
+    pub fn architecture_of(&self, key: &str) -> Option<Architecture> {
+        self.architectures
+            .get(key)
+            .copied()
+            .or_else(|| Architecture::from_key(key))
+    }
+
     /// Returns wether data regarding chocolatey is already set for the updater.
     #[cfg(feature = "chocolatey")]
     #[cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
@@ -50,12 +665,196 @@ impl PackageUpdateData {
     pub fn set_chocolatey(&mut self, choco: chocolatey::ChocolateyUpdaterData) {
         self.chocolatey = Some(choco);
     }
+
+    /// Returns wether data regarding winget is already set for the updater.
+    #[cfg(feature = "winget")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "winget")))]
+    pub fn has_winget(&self) -> bool {
+        self.winget.is_some()
+    }
+
+    /// Returns the current set winget updater data, or a new instance if no
+    /// data is already set.
+    #[cfg(feature = "winget")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "winget")))]
+    pub fn winget(&self) -> Cow<UpdaterUrls> {
+        match self.winget {
+            Some(ref winget) => Cow::Borrowed(winget),
+            None => Cow::Owned(UpdaterUrls::new()),
+        }
+    }
+
+    /// Allows associating new winget updater data with the current instance.
+    #[cfg(feature = "winget")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "winget")))]
+    pub fn set_winget(&mut self, winget: UpdaterUrls) {
+        self.winget = Some(winget);
+    }
+
+    /// Returns wether data regarding NuGet is already set for the updater.
+    #[cfg(feature = "nuget")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "nuget")))]
+    pub fn has_nuget(&self) -> bool {
+        self.nuget.is_some()
+    }
+
+    /// Returns the current set NuGet updater data, or a new instance if no
+    /// data is already set.
+    #[cfg(feature = "nuget")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "nuget")))]
+    pub fn nuget(&self) -> Cow<UpdaterUrls> {
+        match self.nuget {
+            Some(ref nuget) => Cow::Borrowed(nuget),
+            None => Cow::Owned(UpdaterUrls::new()),
+        }
+    }
+
+    /// Allows associating new NuGet updater data with the current instance.
+    #[cfg(feature = "nuget")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "nuget")))]
+    pub fn set_nuget(&mut self, nuget: UpdaterUrls) {
+        self.nuget = Some(nuget);
+    }
+
+    /// Returns the targets that have updater data, together with the upstream
+    /// location and regexes of each target after applying the shared values.
+    /// The targets are returned in the order Chocolatey, winget and NuGet,
+    /// where the first target is considered the primary target of the
+    /// package. When no target have been specified, the shared values are
+    /// used for the Chocolatey target.
+    pub fn targets(&self) -> Vec<(UpdaterTarget, UpdaterUrls)> {
+        #[allow(unused_mut)]
+        let mut targets = vec![];
+
+        #[cfg(feature = "chocolatey")]
+        if let Some(ref chocolatey) = self.chocolatey {
+            targets.push((
+                UpdaterTarget::Chocolatey,
+                chocolatey.urls().or_shared(&self.urls),
+            ));
+        }
+        #[cfg(feature = "winget")]
+        if let Some(ref winget) = self.winget {
+            targets.push((UpdaterTarget::Winget, winget.or_shared(&self.urls)));
+        }
+        #[cfg(feature = "nuget")]
+        if let Some(ref nuget) = self.nuget {
+            targets.push((UpdaterTarget::Nuget, nuget.or_shared(&self.urls)));
+        }
+
+        if targets.is_empty() && !self.urls.is_empty() {
+            targets.push((UpdaterTarget::Chocolatey, self.urls.clone()));
+        }
+
+        targets
+    }
+
+    /// Returns the upstream location and regexes of the primary target, if
+    /// any target have been specified.
+    pub fn primary_urls(&self) -> Option<UpdaterUrls> {
+        self.targets().into_iter().next().map(|(_, urls)| urls)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use rstest::rstest;
+
     use super::*;
 
+    #[rstest(
+        value,
+        expected,
+        case("always", CheckFrequency::Always),
+        case("Hourly", CheckFrequency::Hourly),
+        case("daily", CheckFrequency::Daily),
+        case(" WEEKLY ", CheckFrequency::Weekly),
+        case("0 4 * * 1", CheckFrequency::Cron("0 4 * * 1".into()))
+    )]
+    fn from_should_parse_check_frequency(value: &str, expected: CheckFrequency) {
+        let actual = CheckFrequency::from(value.to_string());
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest(
+        frequency,
+        expected,
+        case(CheckFrequency::Always, None),
+        case(CheckFrequency::Daily, Some(Duration::from_secs(86400))),
+        case(CheckFrequency::Cron("0 4 * * *".into()), None)
+    )]
+    fn interval_should_return_expected_duration(
+        frequency: CheckFrequency,
+        expected: Option<Duration>,
+    ) {
+        assert_eq!(frequency.interval(), expected);
+    }
+
+    #[rstest(
+        keys,
+        authenticode,
+        expected,
+        case(vec![], false, false),
+        case(vec!["release-key.asc"], false, true),
+        case(vec![], true, true)
+    )]
+    fn is_enabled_should_require_keys_or_authenticode(
+        keys: Vec<&str>,
+        authenticode: bool,
+        expected: bool,
+    ) {
+        let mut data = SignatureData::new();
+        data.gpg_keys = keys.into_iter().map(PathBuf::from).collect();
+        data.authenticode = authenticode;
+
+        assert_eq!(data.is_enabled(), expected);
+    }
+
+    #[rstest(
+        version,
+        allow_prerelease,
+        channels,
+        expected,
+        case("1.0.0", false, vec![], true),
+        case("1.0.0-beta.1", false, vec![], false),
+        case("1.0.0-beta.1", true, vec![], true),
+        case("1.0.0-beta.1", true, vec!["Beta"], true),
+        case("1.0.0-alpha.1", true, vec!["beta"], false),
+        case("1.0.0", true, vec!["beta"], true)
+    )]
+    fn allows_version_should_filter_prerelease_channels(
+        version: &str,
+        allow_prerelease: bool,
+        channels: Vec<&str>,
+        expected: bool,
+    ) {
+        let mut data = PackageUpdateData::new();
+        data.allow_prerelease = allow_prerelease;
+        data.channels = channels.into_iter().map(String::from).collect();
+
+        let actual = data.allows_version(&Versions::parse(version).unwrap());
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest(
+        id,
+        version,
+        expected,
+        case("test-package", "1.0.0", "test-package"),
+        case("test-package", "1.0.0-beta.1", "test-package.beta"),
+        case("test-package.beta", "1.0.0-beta.1", "test-package.beta")
+    )]
+    fn package_id_should_append_suffix_to_prereleases(id: &str, version: &str, expected: &str) {
+        let mut data = PackageUpdateData::new();
+        data.prerelease_suffix = Some(".beta".into());
+
+        let actual = data.package_id(id, &Versions::parse(version).unwrap());
+
+        assert_eq!(actual, expected);
+    }
+
     #[cfg(feature = "chocolatey")]
     #[test]
     fn should_get_set_chocolatey_data() {
@@ -69,6 +868,69 @@ mod tests {
         assert_eq!(data.chocolatey(), Cow::Owned(expected));
     }
 
+    #[rstest(
+        key,
+        expected,
+        case("arch64", Some(Architecture::X64)),
+        case("installer_arm", Some(Architecture::Arm64)),
+        case("arch32", Some(Architecture::Universal)),
+        case("portable", None)
+    )]
+    fn architecture_of_should_prefer_specified_architectures(
+        key: &str,
+        expected: Option<Architecture>,
+    ) {
+        let mut data = PackageUpdateData::new();
+        data.architectures
+            .insert("installer_arm".into(), Architecture::Arm64);
+        data.architectures
+            .insert("arch32".into(), Architecture::Universal);
+
+        assert_eq!(data.architecture_of(key), expected);
+    }
+
+    #[test]
+    fn targets_should_use_shared_urls_without_targets() {
+        let mut data = PackageUpdateData::new();
+        data.urls.add_regex("arch64", "shared");
+
+        let actual = data.targets();
+
+        assert_eq!(actual, vec![(UpdaterTarget::Chocolatey, data.urls.clone())]);
+    }
+
+    #[test]
+    fn targets_should_return_empty_list_without_urls() {
+        let data = PackageUpdateData::new();
+
+        assert!(data.targets().is_empty());
+        assert_eq!(data.primary_urls(), None);
+    }
+
+    #[cfg(all(feature = "chocolatey", feature = "winget"))]
+    #[test]
+    fn targets_should_apply_shared_urls_to_every_target() {
+        let mut data = PackageUpdateData::new();
+        data.urls.parse_url = Some(ParseUrl::Url(Url::parse("https://example.org").unwrap()));
+        data.urls.add_regex("arch64", "shared");
+        let mut choco = chocolatey::ChocolateyUpdaterData::new();
+        choco.add_regex("arch32", "chocolatey");
+        data.set_chocolatey(choco);
+        let mut winget = UpdaterUrls::new();
+        winget.add_regex("arch64", "winget");
+        data.set_winget(winget);
+
+        let actual = data.targets();
+
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual[0].0, UpdaterTarget::Chocolatey);
+        assert_eq!(actual[0].1.parse_url, data.urls.parse_url);
+        assert_eq!(actual[0].1.regexes().len(), 2);
+        assert_eq!(actual[1].0, UpdaterTarget::Winget);
+        assert_eq!(actual[1].1.parse_url, data.urls.parse_url);
+        assert_eq!(actual[1].1.regexes().get("arch64"), Some(&"winget".into()));
+    }
+
     #[cfg(feature = "chocolatey")]
     #[test]
     fn should_return_default_chocolatey() {
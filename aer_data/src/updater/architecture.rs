@@ -0,0 +1,112 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+use std::fmt::Display;
+
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+/// The architectures the upstream files can be created for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(Deserialize, Serialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Architecture {
+    /// The file is created for 32bit x86 systems.
+    X86,
+    /// The file is created for 64bit x86 systems.
+    X64,
+    /// The file is created for 64bit ARM systems.
+    Arm64,
+    /// The file can be used on all architectures (*ie: an installer
+    /// containing the files of every architecture*).
+    Universal,
+}
+
+impl Architecture {
+    /// All architectures, in the order the architectures are preferred when
+    /// reading the version of the found links.
+    pub const PREFERRED: [Architecture; 4] = [
+        Architecture::X64,
+        Architecture::X86,
+        Architecture::Arm64,
+        Architecture::Universal,
+    ];
+
+    /// Returns the architecture matching the well-known name of a regex (*ie:
+    /// `arch32` or `arm64`*), nothing is returned when the name do not
+    /// reference an architecture.
+    pub fn from_key(key: &str) -> Option<Architecture> {
+        match key.trim().to_lowercase().as_str() {
+            "arch32" | "x86" | "32" => Some(Architecture::X86),
+            "arch64" | "x64" | "64" | "amd64" => Some(Architecture::X64),
+            "archarm64" | "arm64" | "aarch64" => Some(Architecture::Arm64),
+            "universal" | "neutral" => Some(Architecture::Universal),
+            _ => None,
+        }
+    }
+
+    /// Returns the canonical name of the regex matching links of the
+    /// architecture.
+    pub fn key(self) -> &'static str {
+        match self {
+            Architecture::X86 => "arch32",
+            Architecture::X64 => "arch64",
+            Architecture::Arm64 => "arm64",
+            Architecture::Universal => "universal",
+        }
+    }
+
+    /// Returns the suffix appended to the names of the values available to
+    /// the templates and scripts of the package (*ie: `64` for `url64`*). The
+    /// universal architecture do not use a suffix.
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Architecture::X86 => "32",
+            Architecture::X64 => "64",
+            Architecture::Arm64 => "Arm64",
+            Architecture::Universal => "",
+        }
+    }
+}
+
+impl Display for Architecture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Architecture::X86 => f.write_str("x86"),
+            Architecture::X64 => f.write_str("x64"),
+            Architecture::Arm64 => f.write_str("arm64"),
+            Architecture::Universal => f.write_str("universal"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest(
+        key,
+        expected,
+        case("arch32", Some(Architecture::X86)),
+        case("ARCH64", Some(Architecture::X64)),
+        case("arm64", Some(Architecture::Arm64)),
+        case("aarch64", Some(Architecture::Arm64)),
+        case(" universal ", Some(Architecture::Universal)),
+        case("checksums", None)
+    )]
+    fn from_key_should_return_expected_architecture(key: &str, expected: Option<Architecture>) {
+        assert_eq!(Architecture::from_key(key), expected);
+    }
+
+    #[test]
+    fn from_key_should_accept_canonical_keys() {
+        for arch in Architecture::PREFERRED.iter() {
+            assert_eq!(Architecture::from_key(arch.key()), Some(*arch));
+        }
+    }
+}
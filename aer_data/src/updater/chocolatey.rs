@@ -5,9 +5,12 @@
 
 use std::collections::HashMap;
 
+use aer_version::chocolatey::FixScheme;
 #[cfg(feature = "serialize")]
 use serde::{Deserialize, Serialize};
-use url::Url;
+
+pub use super::urls::ParseUrl as ChocolateyParseUrl;
+use super::urls::UpdaterUrls;
 
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
@@ -23,13 +26,6 @@ impl Default for ChocolateyUpdaterType {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize), serde(untagged))]
-pub enum ChocolateyParseUrl {
-    UrlWithRegex { url: Url, regex: String },
-    Url(Url),
-}
-
 #[derive(Debug, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
 #[non_exhaustive]
@@ -39,7 +35,15 @@ pub struct ChocolateyUpdaterData {
     #[cfg_attr(feature = "serialize", serde(default, rename = "type"))]
     pub updater_type: ChocolateyUpdaterType,
     pub parse_url: Option<ChocolateyParseUrl>,
+    /// The scheme used when creating fix versions of the package, and when
+    /// removing the fix before comparing the version of the package against
+    /// the version of the software.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub fix_scheme: FixScheme,
 
+    /// The regexes matching the links of the package, the regexes of the
+    /// shared `[updater.urls]` block are used when not specified.
+    #[cfg_attr(feature = "serialize", serde(default))]
     regexes: HashMap<String, String>,
 }
 
@@ -49,6 +53,7 @@ impl ChocolateyUpdaterData {
             embedded: false,
             updater_type: ChocolateyUpdaterType::default(),
             parse_url: None,
+            fix_scheme: FixScheme::default(),
             regexes: HashMap::new(),
         }
     }
@@ -64,6 +69,11 @@ impl ChocolateyUpdaterData {
     pub fn set_regexes(&mut self, values: HashMap<String, String>) {
         self.regexes = values;
     }
+
+    /// Returns the upstream location and the regexes of the updater data.
+    pub fn urls(&self) -> UpdaterUrls {
+        UpdaterUrls::with_values(self.parse_url.clone(), self.regexes.clone())
+    }
 }
 
 #[cfg(test)]
@@ -76,6 +86,7 @@ mod tests {
             embedded: false,
             updater_type: ChocolateyUpdaterType::default(),
             parse_url: None,
+            fix_scheme: FixScheme::Date,
             regexes: HashMap::new(),
         };
 
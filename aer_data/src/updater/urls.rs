@@ -0,0 +1,176 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// The upstream location that is parsed when finding new releases, optionally
+/// with a regex matching the release page the links should be read from.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize), serde(untagged))]
+pub enum ParseUrl {
+    UrlWithRegex { url: Url, regex: String },
+    Url(Url),
+}
+
+impl ParseUrl {
+    /// Returns the url that is parsed, without the regex.
+    pub fn url(&self) -> &Url {
+        match self {
+            ParseUrl::UrlWithRegex { url, .. } | ParseUrl::Url(url) => url,
+        }
+    }
+}
+
+/// The upstream location and the regexes used by a single target when
+/// finding new releases, or shared between all targets when specified in the
+/// `[updater.urls]` block.
+///
+/// ### Examples
+///
+/// ```toml
+/// [updater.urls]
+/// parse_url = "https://github.com/codote/Astyle/releases/latest"
+///
+/// [updater.urls.regexes]
+/// arch64 = "AStyle_(?P<version>[\\d\\.]+)_windows\\.zip$"
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Deserialize, Serialize))]
+pub struct UpdaterUrls {
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub parse_url: Option<ParseUrl>,
+
+    #[cfg_attr(feature = "serialize", serde(default))]
+    regexes: HashMap<String, String>,
+}
+
+impl UpdaterUrls {
+    /// Creates a new instance without any location or regexes.
+    pub fn new() -> UpdaterUrls {
+        UpdaterUrls::default()
+    }
+
+    /// Creates a new instance using the specified location and regexes.
+    pub fn with_values(
+        parse_url: Option<ParseUrl>,
+        regexes: HashMap<String, String>,
+    ) -> UpdaterUrls {
+        UpdaterUrls { parse_url, regexes }
+    }
+
+    /// Returns wether neither a location or any regexes have been specified.
+    pub fn is_empty(&self) -> bool {
+        self.parse_url.is_none() && self.regexes.is_empty()
+    }
+
+    pub fn regexes(&self) -> &HashMap<String, String> {
+        &self.regexes
+    }
+
+    pub fn add_regex(&mut self, name: &str, value: &str) {
+        self.regexes.insert(name.into(), value.into());
+    }
+
+    pub fn set_regexes(&mut self, values: HashMap<String, String>) {
+        self.regexes = values;
+    }
+
+    /// Returns the values of the current instance, using the values of the
+    /// shared instance for the location when no location have been specified
+    /// and for any regexes not overridden by the current instance.
+    pub fn or_shared(&self, shared: &UpdaterUrls) -> UpdaterUrls {
+        let mut regexes = shared.regexes.clone();
+        regexes.extend(
+            self.regexes
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+
+        UpdaterUrls {
+            parse_url: self.parse_url.clone().or_else(|| shared.parse_url.clone()),
+            regexes,
+        }
+    }
+}
+
+/// The targets (*ie: package managers*) that can define their own upstream
+/// location and regexes when finding new releases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UpdaterTarget {
+    /// The upstream location used when creating the Chocolatey package, this
+    /// is also the target used when only the shared location is specified.
+    Chocolatey,
+    /// The upstream location used when creating the winget manifests.
+    Winget,
+    /// The upstream location used when creating the NuGet package.
+    Nuget,
+}
+
+impl UpdaterTarget {
+    /// Returns the name of the target, as used in the package data file.
+    pub fn name(self) -> &'static str {
+        match self {
+            UpdaterTarget::Chocolatey => "chocolatey",
+            UpdaterTarget::Winget => "winget",
+            UpdaterTarget::Nuget => "nuget",
+        }
+    }
+}
+
+impl Display for UpdaterTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_url(url: &str) -> Option<ParseUrl> {
+        Some(ParseUrl::Url(Url::parse(url).unwrap()))
+    }
+
+    #[test]
+    fn or_shared_should_use_shared_values_when_not_overridden() {
+        let mut shared = UpdaterUrls::new();
+        shared.parse_url = parse_url("https://example.org/shared");
+        shared.add_regex("arch32", "shared-32");
+        shared.add_regex("arch64", "shared-64");
+        let mut target = UpdaterUrls::new();
+        target.add_regex("arch64", "target-64");
+
+        let actual = target.or_shared(&shared);
+
+        assert_eq!(actual.parse_url, parse_url("https://example.org/shared"));
+        assert_eq!(actual.regexes().get("arch32"), Some(&"shared-32".into()));
+        assert_eq!(actual.regexes().get("arch64"), Some(&"target-64".into()));
+    }
+
+    #[test]
+    fn or_shared_should_prefer_location_of_target() {
+        let mut shared = UpdaterUrls::new();
+        shared.parse_url = parse_url("https://example.org/shared");
+        let mut target = UpdaterUrls::new();
+        target.parse_url = parse_url("https://example.org/target");
+
+        let actual = target.or_shared(&shared);
+
+        assert_eq!(actual.parse_url, parse_url("https://example.org/target"));
+    }
+
+    #[test]
+    fn is_empty_should_return_false_when_regexes_are_set() {
+        let mut urls = UpdaterUrls::new();
+        assert!(urls.is_empty());
+
+        urls.add_regex("arch32", "regex");
+
+        assert!(!urls.is_empty());
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the classification of license texts, matching the text against
+//! phrases that are unique to the templates of commonly used SPDX licenses.
+//!
+//! The text is normalized before it is matched, by lowercasing the text and
+//! replacing all punctuation and whitespace with a single space. This allows
+//! the license to be detected regardless of the line wrapping, and the
+//! copyright holders inserted into the template.
+
+/// The licenses that can be detected, together with the phrases that must all
+/// be present in the normalized text. The licenses are checked in order, as
+/// the text of some licenses also contains the phrases of other licenses (*ie:
+/// the `GPL-3.0` license references the `LGPL-3.0` license*).
+const RULES: &[(&str, &[&str])] = &[
+    (
+        "AGPL-3.0-only",
+        &["gnu affero general public license version 3 19 november 2007"],
+    ),
+    (
+        "LGPL-3.0-only",
+        &["gnu lesser general public license version 3 29 june 2007"],
+    ),
+    (
+        "LGPL-2.1-only",
+        &["gnu lesser general public license version 2 1 february 1999"],
+    ),
+    (
+        "GPL-3.0-only",
+        &["gnu general public license version 3 29 june 2007"],
+    ),
+    (
+        "GPL-2.0-only",
+        &["gnu general public license version 2 june 1991"],
+    ),
+    ("Apache-2.0", &["apache license version 2 0 january 2004"]),
+    ("MPL-2.0", &["mozilla public license version 2 0"]),
+    ("BSL-1.0", &["boost software license version 1 0"]),
+    ("MS-PL", &["microsoft public license ms pl"]),
+    ("CC0-1.0", &["cc0 1 0 universal"]),
+    (
+        "Unlicense",
+        &["this is free and unencumbered software released into the public domain"],
+    ),
+    (
+        "Zlib",
+        &[
+            "this software is provided as is without any express or implied warranty",
+            "altered source versions must be plainly marked as such",
+        ],
+    ),
+    (
+        "ISC",
+        &[
+            "permission to use copy modify and or distribute this software for any purpose with \
+             or without fee is hereby granted",
+        ],
+    ),
+    (
+        "BSD-3-Clause",
+        &[
+            "redistribution and use in source and binary forms with or without modification are \
+             permitted",
+            "neither the name of",
+        ],
+    ),
+    (
+        "BSD-2-Clause",
+        &[
+            "redistribution and use in source and binary forms with or without modification are \
+             permitted",
+        ],
+    ),
+    (
+        "MIT",
+        &["permission is hereby granted free of charge to any person obtaining a copy"],
+    ),
+];
+
+/// Returns the SPDX identifier of the license the text matches, if any.
+pub(crate) fn detect(text: &str) -> Option<&'static str> {
+    let text = normalize(text);
+
+    RULES
+        .iter()
+        .find(|(_, phrases)| phrases.iter().all(|phrase| text.contains(phrase)))
+        .map(|(id, _)| *id)
+}
+
+/// Lowercases the text, and replaces all characters that are not letters or
+/// digits with a single space.
+fn normalize(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        if !normalized.is_empty() {
+            normalized.push(' ');
+        }
+        normalized.push_str(&word.to_lowercase());
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest(
+        text,
+        expected,
+        case(
+            "MIT License\n\nCopyright (c) 2021 Kim J. Nordmo\n\nPermission is hereby granted, \
+             free of charge, to any\nperson obtaining a copy of this software",
+            "MIT"
+        ),
+        case(
+            "                                 Apache License\n                           Version \
+             2.0, January 2004",
+            "Apache-2.0"
+        ),
+        case(
+            "GNU GENERAL PUBLIC LICENSE\nVersion 3, 29 June 2007\n\nuse the GNU Lesser General \
+             Public License instead",
+            "GPL-3.0-only"
+        ),
+        case(
+            "GNU LESSER GENERAL PUBLIC LICENSE\nVersion 3, 29 June 2007\n\nGNU General Public \
+             License",
+            "LGPL-3.0-only"
+        ),
+        case(
+            "Redistribution and use in source and binary forms, with or without\nmodification, \
+             are permitted provided that the following conditions are met:\n\n3. Neither the name \
+             of the copyright holder",
+            "BSD-3-Clause"
+        ),
+        case(
+            "Redistribution and use in source and binary forms, with or without\nmodification, \
+             are permitted provided that the following conditions are met:",
+            "BSD-2-Clause"
+        )
+    )]
+    fn detect_should_classify_license_text(text: &str, expected: &str) {
+        assert_eq!(detect(text), Some(expected));
+    }
+
+    #[test]
+    fn detect_should_return_none_for_unknown_text() {
+        assert_eq!(detect("All rights reserved."), None);
+    }
+
+    #[test]
+    fn normalize_should_collapse_punctuation_and_whitespace() {
+        assert_eq!(
+            normalize("  Version 2.0,\r\n  January 2004 "),
+            "version 2 0 january 2004"
+        );
+    }
+}
@@ -1,6 +1,8 @@
 // Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
 // Licensed under the MIT license. See LICENSE.txt file in the project
 
+mod detect;
+
 #[cfg(feature = "serialize")]
 use serde::{Deserialize, Serialize};
 use url::Url;
@@ -15,8 +17,9 @@ pub enum LicenseType {
     None,
     /// The remote location of an url, this can be used when there is no
     /// expression available for the package you want to create.
-    /// Depending on the package created the license may get downloaded during
-    /// updated and embedded in the package.
+    /// Chocolatey packages that embeds the software will have the license
+    /// downloaded during the update, and embedded in the package as
+    /// `legal/LICENSE.txt`.
     Location(Url),
     /// Allows specifying an expression of the License Type to use for the
     /// package.
@@ -46,6 +49,34 @@ impl Default for LicenseType {
 }
 
 impl LicenseType {
+    /// Returns wether no license have been specified.
+    pub fn is_none(&self) -> bool {
+        matches!(self, LicenseType::None)
+    }
+
+    /// Classifies the text of a license file, and returns the SPDX identifier
+    /// of the license (*ie: `MIT`*). Nothing is returned when the text do not
+    /// match any of the known licenses.
+    pub fn detect_from_text(text: &str) -> Option<&'static str> {
+        detect::detect(text)
+    }
+
+    /// Adds the expression detected from the text of the license file when
+    /// only the location of the license have been specified, any other
+    /// license is returned as is.
+    pub fn with_detected_expression(self, text: &str) -> LicenseType {
+        match self {
+            LicenseType::Location(url) => match LicenseType::detect_from_text(text) {
+                Some(expression) => LicenseType::ExpressionAndLocation {
+                    expression: expression.into(),
+                    url,
+                },
+                None => LicenseType::Location(url),
+            },
+            license => license,
+        }
+    }
+
     pub fn license_url(&self) -> Option<&str> {
         match self {
             LicenseType::Location(url) | LicenseType::ExpressionAndLocation { url, .. } => {
@@ -127,6 +158,46 @@ mod tests {
         assert_eq!(license.license_url(), None);
     }
 
+    #[test]
+    fn with_detected_expression_should_add_expression_to_location() {
+        let url = Url::parse("https://github.com/WormieCorp/aer/blob/master/LICENSE.txt").unwrap();
+        let license = LicenseType::Location(url.clone());
+
+        let actual = license.with_detected_expression(
+            "MIT License\n\nPermission is hereby granted, free of charge, to any person obtaining \
+             a copy",
+        );
+
+        assert_eq!(
+            actual,
+            LicenseType::ExpressionAndLocation {
+                expression: "MIT".into(),
+                url
+            }
+        );
+    }
+
+    #[test]
+    fn with_detected_expression_should_keep_location_on_unknown_text() {
+        let url = Url::parse("https://example.org/license").unwrap();
+        let license = LicenseType::Location(url.clone());
+
+        let actual = license.with_detected_expression("All rights reserved.");
+
+        assert_eq!(actual, LicenseType::Location(url));
+    }
+
+    #[test]
+    fn with_detected_expression_should_keep_existing_expression() {
+        let license = LicenseType::Expression("Apache-2.0".into());
+
+        let actual = license.with_detected_expression(
+            "Permission is hereby granted, free of charge, to any person obtaining a copy",
+        );
+
+        assert_eq!(actual, LicenseType::Expression("Apache-2.0".into()));
+    }
+
     #[rstest(
         expression,
         url,
@@ -0,0 +1,163 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the packer responsible for creating the `.nupkg` archive of a
+//! Chocolatey package.
+
+use std::path::{Path, PathBuf};
+
+use aer_data::prelude::*;
+
+pub use crate::opc::PACKAGE_EXTENSION;
+use crate::opc::{self, CoreProperties};
+use crate::PackError;
+
+/// Packs the directory layout of a Chocolatey package (*as created by the
+/// nuspec generator*) into a `.nupkg` archive that can be installed or pushed
+/// by Chocolatey.
+///
+/// The package directory is expected to contain the nuspec file named after
+/// the identifier of the package, all other files in the directory are added
+/// to the archive with the same relative path.
+///
+/// The packer is `Send` and `Sync`, and packing only reads the package
+/// directory, the same package directory must however not be modified while
+/// it is being packed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChocolateyPackage {
+    package_dir: PathBuf,
+}
+
+impl ChocolateyPackage {
+    /// Creates a new packer for the package in the specified directory.
+    pub fn new<P: Into<PathBuf>>(package_dir: P) -> ChocolateyPackage {
+        ChocolateyPackage {
+            package_dir: package_dir.into(),
+        }
+    }
+
+    /// Returns the directory containing the files of the package.
+    pub fn package_dir(&self) -> &Path {
+        &self.package_dir
+    }
+
+    /// Creates the `.nupkg` archive of the package inside the specified
+    /// output directory, and returns the path to the created archive. The
+    /// archive is named `<id>.<version>.nupkg`, and any existing archive with
+    /// the same name is replaced.
+    pub fn pack(&self, data: &PackageData, output_dir: &Path) -> Result<PathBuf, PackError> {
+        let choco = data.metadata().chocolatey();
+        let properties = CoreProperties {
+            creator: choco.authors().join(", "),
+            description: data.metadata().summary.clone(),
+            keywords: choco.tags().join(" "),
+        };
+
+        opc::pack(
+            &self.package_dir,
+            output_dir,
+            &package_id(data),
+            &choco.version.to_choco().to_string(),
+            &properties,
+        )
+    }
+}
+
+fn package_id(data: &PackageData) -> String {
+    let id = data.metadata().id();
+
+    if data.metadata().chocolatey().lowercase_id() {
+        id.to_lowercase()
+    } else {
+        id.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Read;
+
+    use aer_data::prelude::chocolatey::*;
+    use aer_data::testing::PackageDataFixture;
+    use static_assertions::assert_impl_all;
+
+    use super::*;
+
+    assert_impl_all!(ChocolateyPackage: Send, Sync);
+
+    fn create_package_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        let package_dir = dir.join("test-package");
+        std::fs::create_dir_all(package_dir.join("tools")).unwrap();
+        std::fs::write(package_dir.join("test-package.nuspec"), "<package />").unwrap();
+        std::fs::write(
+            package_dir.join("tools").join("chocolateyInstall.ps1"),
+            "Write-Host 'Installed'",
+        )
+        .unwrap();
+        std::fs::write(package_dir.join("tools").join("LICENSE"), "MIT").unwrap();
+
+        package_dir
+    }
+
+    fn package_data() -> PackageData {
+        PackageDataFixture::new("Test-Package")
+            .with_choco(|choco| {
+                *choco = ChocolateyMetadata::with_authors(&["AdmiringWorm"]);
+                choco.version = Versions::parse("1.2.0").unwrap();
+                choco.set_tags(&["test", "aer"]);
+            })
+            .build()
+    }
+
+    #[test]
+    fn pack_should_create_archive_with_package_files() {
+        let package_dir = create_package_dir("aer-pack-files-test");
+        let output_dir = package_dir.parent().unwrap().to_owned();
+
+        let actual = ChocolateyPackage::new(&package_dir)
+            .pack(&package_data(), &output_dir)
+            .unwrap();
+
+        assert_eq!(actual, output_dir.join("test-package.1.2.0.nupkg"));
+        let mut archive = zip::ZipArchive::new(File::open(&actual).unwrap()).unwrap();
+        let mut names: Vec<_> = archive.file_names().map(String::from).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "[Content_Types].xml",
+                "_rels/.rels",
+                "package/services/metadata/core-properties/83779cc95dd44ef3.psmdcp",
+                "test-package.nuspec",
+                "tools/LICENSE",
+                "tools/chocolateyInstall.ps1",
+            ]
+        );
+        let mut content_types = String::new();
+        archive
+            .by_name(crate::opc::CONTENT_TYPES_PART)
+            .unwrap()
+            .read_to_string(&mut content_types)
+            .unwrap();
+        assert!(content_types
+            .contains(r#"<Default Extension="ps1" ContentType="application/octet" />"#));
+        assert!(content_types
+            .contains(r#"<Override PartName="/tools/LICENSE" ContentType="application/octet" />"#));
+        let _ = std::fs::remove_dir_all(output_dir);
+    }
+
+    #[test]
+    fn pack_should_return_error_when_nuspec_is_missing() {
+        let package_dir = create_package_dir("aer-pack-missing-test");
+        let output_dir = package_dir.parent().unwrap().to_owned();
+        std::fs::remove_file(package_dir.join("test-package.nuspec")).unwrap();
+
+        let actual = ChocolateyPackage::new(&package_dir).pack(&package_data(), &output_dir);
+
+        assert!(matches!(actual, Err(PackError::MissingManifest(_))));
+        let _ = std::fs::remove_dir_all(output_dir);
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+#![deny(missing_docs)]
+
+//! This crate is responsible for packing the files and directory layout
+//! created for a package into the final package archive that can be pushed
+//! to the package manager.
+
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+
+#[cfg(feature = "chocolatey")]
+pub mod chocolatey;
+#[cfg(feature = "nuget")]
+pub mod nuget;
+#[cfg(any(feature = "chocolatey", feature = "nuget"))]
+mod opc;
+
+/// The errors that can occur when packing a package.
+#[derive(Debug)]
+pub enum PackError {
+    /// The file describing the package (*ie: the nuspec file*) could not be
+    /// found in the package directory.
+    MissingManifest(PathBuf),
+    /// Reading or writing a file failed.
+    Io(std::io::Error),
+    /// Writing the package archive failed.
+    Archive(zip::result::ZipError),
+}
+
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PackError::MissingManifest(path) => {
+                write!(f, "The package manifest '{}' do not exist!", path.display())
+            }
+            PackError::Io(err) => err.fmt(f),
+            PackError::Archive(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for PackError {}
+
+impl PackError {
+    /// Returns the stable code identifying the kind of error (*ie:
+    /// `AER0501`*), which can be used when searching the documentation.
+    pub fn code(&self) -> &'static str {
+        match self {
+            PackError::MissingManifest(_) => "AER0501",
+            PackError::Io(_) => "AER0502",
+            PackError::Archive(_) => "AER0503",
+        }
+    }
+}
+
+impl From<std::io::Error> for PackError {
+    fn from(err: std::io::Error) -> Self {
+        PackError::Io(err)
+    }
+}
+
+impl From<zip::result::ZipError> for PackError {
+    fn from(err: zip::result::ZipError) -> Self {
+        match err {
+            zip::result::ZipError::Io(err) => PackError::Io(err),
+            err => PackError::Archive(err),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the packer responsible for creating the `.nupkg` archive of a
+//! plain NuGet package.
+
+#![cfg_attr(docsrs, doc(cfg(feature = "nuget")))]
+
+use std::path::{Path, PathBuf};
+
+use aer_data::prelude::*;
+
+pub use crate::opc::PACKAGE_EXTENSION;
+use crate::opc::{self, CoreProperties};
+use crate::PackError;
+
+/// Packs the directory layout of a NuGet package (*as created by the NuGet
+/// generator*) into a `.nupkg` archive that can be pushed to nuget.org.
+///
+/// The package directory is expected to contain the nuspec file named after
+/// the identifier of the package, all other files in the directory are added
+/// to the archive with the same relative path.
+///
+/// The packer is `Send` and `Sync`, and packing only reads the package
+/// directory, the same package directory must however not be modified while
+/// it is being packed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NugetPackage {
+    package_dir: PathBuf,
+}
+
+impl NugetPackage {
+    /// Creates a new packer for the package in the specified directory.
+    pub fn new<P: Into<PathBuf>>(package_dir: P) -> NugetPackage {
+        NugetPackage {
+            package_dir: package_dir.into(),
+        }
+    }
+
+    /// Returns the directory containing the files of the package.
+    pub fn package_dir(&self) -> &Path {
+        &self.package_dir
+    }
+
+    /// Creates the `.nupkg` archive of the package inside the specified
+    /// output directory, and returns the path to the created archive. The
+    /// archive is named `<id>.<version>.nupkg`, and any existing archive with
+    /// the same name is replaced.
+    pub fn pack(&self, data: &PackageData, output_dir: &Path) -> Result<PathBuf, PackError> {
+        let metadata = data.metadata();
+        let nuget = metadata.nuget();
+        let authors = if nuget.authors().is_empty() {
+            metadata.maintainers()
+        } else {
+            nuget.authors()
+        };
+        let properties = CoreProperties {
+            creator: authors.join(", "),
+            description: metadata.summary.clone(),
+            keywords: nuget.tags().join(" "),
+        };
+
+        opc::pack(
+            &self.package_dir,
+            output_dir,
+            metadata.id(),
+            &nuget.version.to_string(),
+            &properties,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use aer_data::prelude::nuget::*;
+    use static_assertions::assert_impl_all;
+
+    use super::*;
+
+    assert_impl_all!(NugetPackage: Send, Sync);
+
+    #[test]
+    fn pack_should_keep_case_of_identifier() {
+        let dir = std::env::temp_dir().join("aer-pack-nuget-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let package_dir = dir.join("Test.Tool");
+        std::fs::create_dir_all(&package_dir).unwrap();
+        std::fs::write(package_dir.join("Test.Tool.nuspec"), "<package />").unwrap();
+        let mut data = PackageData::new("Test.Tool");
+        let mut nuget = NugetMetadata::new();
+        nuget.version = Versions::parse("2.1.0").unwrap();
+        data.metadata_mut().set_nuget(nuget);
+
+        let actual = NugetPackage::new(&package_dir).pack(&data, &dir).unwrap();
+
+        assert_eq!(actual, dir.join("Test.Tool.2.1.0.nupkg"));
+        let archive = zip::ZipArchive::new(File::open(&actual).unwrap()).unwrap();
+        assert!(archive.file_names().any(|name| name == "Test.Tool.nuspec"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
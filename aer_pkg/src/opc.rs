@@ -0,0 +1,344 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the packing of the `.nupkg` archives shared by the package
+//! managers using the NuGet package format.
+//!
+//! The archive follows the Open Packaging Conventions used by NuGet, and
+//! contains the following files in addition to the files of the package:
+//!
+//! ```text
+//! [Content_Types].xml
+//! _rels/.rels
+//! package/services/metadata/core-properties/<hash>.psmdcp
+//! <id>.nuspec
+//! ```
+
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+
+use log::debug;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::PackError;
+
+/// The extension used by packages in the NuGet package format.
+pub const PACKAGE_EXTENSION: &str = "nupkg";
+
+pub(crate) const CONTENT_TYPES_PART: &str = "[Content_Types].xml";
+const RELATIONSHIPS_PART: &str = "_rels/.rels";
+const CORE_PROPERTIES_DIR: &str = "package/services/metadata/core-properties";
+
+const CONTENT_TYPES_NAMESPACE: &str =
+    "http://schemas.openxmlformats.org/package/2006/content-types";
+const RELATIONSHIPS_NAMESPACE: &str =
+    "http://schemas.openxmlformats.org/package/2006/relationships";
+const CORE_PROPERTIES_NAMESPACE: &str =
+    "http://schemas.openxmlformats.org/package/2006/metadata/core-properties";
+const MANIFEST_RELATIONSHIP: &str = "http://schemas.microsoft.com/packaging/2010/07/manifest";
+const CORE_PROPERTIES_RELATIONSHIP: &str =
+    "http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties";
+
+const RELATIONSHIPS_CONTENT_TYPE: &str = "application/vnd.openxmlformats-package.relationships+xml";
+const CORE_PROPERTIES_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-package.core-properties+xml";
+const DEFAULT_CONTENT_TYPE: &str = "application/octet";
+
+/// The values written to the core properties of the archive.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CoreProperties {
+    /// The authors of the software.
+    pub(crate) creator: String,
+    /// The short description of the software.
+    pub(crate) description: String,
+    /// The tags of the package, separated by spaces.
+    pub(crate) keywords: String,
+}
+
+/// Packs the files in the package directory into the `<id>.<version>.nupkg`
+/// archive inside the output directory, and returns the path to the created
+/// archive. The package directory must contain the nuspec file named after
+/// the identifier of the package, and any existing archive with the same name
+/// is replaced.
+pub(crate) fn pack(
+    package_dir: &Path,
+    output_dir: &Path,
+    id: &str,
+    version: &str,
+    properties: &CoreProperties,
+) -> Result<PathBuf, PackError> {
+    let nuspec_name = format!("{}.nuspec", id);
+    let nuspec_path = package_dir.join(&nuspec_name);
+
+    if !nuspec_path.is_file() {
+        return Err(PackError::MissingManifest(nuspec_path));
+    }
+
+    let mut files = vec![];
+    collect_files(package_dir, &mut files)?;
+    files.retain(|path| path != &nuspec_path);
+    files.sort();
+
+    std::fs::create_dir_all(output_dir)?;
+    let package_path = output_dir.join(format!("{}.{}.{}", id, version, PACKAGE_EXTENSION));
+    let mut writer = ZipWriter::new(File::create(&package_path)?);
+
+    let core_properties = format!(
+        "{}/{:016x}.psmdcp",
+        CORE_PROPERTIES_DIR,
+        hash(&format!("{}.{}", id, version))
+    );
+    let mut parts = vec![nuspec_name.clone()];
+
+    add_file(&mut writer, &nuspec_name, &nuspec_path)?;
+    for file in &files {
+        let part = part_name(file.strip_prefix(package_dir).unwrap_or(file));
+        add_file(&mut writer, &part, file)?;
+        parts.push(part);
+    }
+
+    add_part(
+        &mut writer,
+        RELATIONSHIPS_PART,
+        &relationships(&nuspec_name, &core_properties),
+    )?;
+    add_part(
+        &mut writer,
+        &core_properties,
+        &core_properties_content(properties, id, version),
+    )?;
+    add_part(&mut writer, CONTENT_TYPES_PART, &content_types(&parts))?;
+
+    writer.finish()?;
+    debug!("Created package archive at '{}'", package_path.display());
+
+    Ok(package_path)
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn file_options() -> FileOptions {
+    // The default timestamp is used to ensure that packing the same files
+    // always creates the same archive.
+    FileOptions::default().compression_method(CompressionMethod::Deflated)
+}
+
+fn add_file<W: Write + Seek>(
+    writer: &mut ZipWriter<W>,
+    name: &str,
+    path: &Path,
+) -> Result<(), PackError> {
+    writer.start_file(name, file_options())?;
+    std::io::copy(&mut File::open(path)?, writer)?;
+
+    Ok(())
+}
+
+fn add_part<W: Write + Seek>(
+    writer: &mut ZipWriter<W>,
+    name: &str,
+    content: &str,
+) -> Result<(), PackError> {
+    writer.start_file(name, file_options())?;
+    writer.write_all(content.as_bytes())?;
+
+    Ok(())
+}
+
+/// Creates the name of the archive entry for the specified relative path, the
+/// name uses forward slashes and escapes the characters that are not allowed
+/// in the part names of the Open Packaging Conventions.
+fn part_name(path: &Path) -> String {
+    let mut name = String::new();
+
+    for (i, component) in path.components().enumerate() {
+        if i > 0 {
+            name.push('/');
+        }
+
+        for b in component.as_os_str().to_string_lossy().bytes() {
+            match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                    name.push(b as char)
+                }
+                _ => {
+                    let _ = write!(name, "%{:02X}", b);
+                }
+            }
+        }
+    }
+
+    name
+}
+
+fn content_types(parts: &[String]) -> String {
+    let mut extensions = BTreeSet::new();
+    let mut overrides = vec![];
+
+    for part in parts {
+        let file_name = part.rsplit('/').next().unwrap_or(part);
+        match file_name.rfind('.') {
+            Some(i) if i > 0 && i + 1 < file_name.len() => {
+                extensions.insert(file_name[i + 1..].to_lowercase());
+            }
+            _ => overrides.push(part),
+        }
+    }
+
+    let mut content = String::new();
+    let _ = write!(
+        content,
+        r#"<?xml version="1.0" encoding="utf-8"?><Types xmlns="{}">"#,
+        CONTENT_TYPES_NAMESPACE
+    );
+    let _ = write!(
+        content,
+        r#"<Default Extension="rels" ContentType="{}" />"#,
+        RELATIONSHIPS_CONTENT_TYPE
+    );
+    let _ = write!(
+        content,
+        r#"<Default Extension="psmdcp" ContentType="{}" />"#,
+        CORE_PROPERTIES_CONTENT_TYPE
+    );
+    for extension in extensions.iter().filter(|e| *e != "rels" && *e != "psmdcp") {
+        let _ = write!(
+            content,
+            r#"<Default Extension="{}" ContentType="{}" />"#,
+            escape(extension),
+            DEFAULT_CONTENT_TYPE
+        );
+    }
+    for part in overrides {
+        let _ = write!(
+            content,
+            r#"<Override PartName="/{}" ContentType="{}" />"#,
+            escape(part),
+            DEFAULT_CONTENT_TYPE
+        );
+    }
+    content.push_str("</Types>");
+
+    content
+}
+
+fn relationships(nuspec_name: &str, core_properties: &str) -> String {
+    let mut content = String::new();
+    let _ = write!(
+        content,
+        r#"<?xml version="1.0" encoding="utf-8"?><Relationships xmlns="{}">"#,
+        RELATIONSHIPS_NAMESPACE
+    );
+    for (relationship, target) in &[
+        (MANIFEST_RELATIONSHIP, nuspec_name),
+        (CORE_PROPERTIES_RELATIONSHIP, core_properties),
+    ] {
+        let _ = write!(
+            content,
+            r#"<Relationship Type="{}" Target="/{}" Id="R{:016X}" />"#,
+            relationship,
+            escape(target),
+            hash(target)
+        );
+    }
+    content.push_str("</Relationships>");
+
+    content
+}
+
+fn core_properties_content(properties: &CoreProperties, id: &str, version: &str) -> String {
+    let mut content = String::new();
+    let _ = write!(
+        content,
+        r#"<?xml version="1.0" encoding="utf-8"?><coreProperties xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns="{}">"#,
+        CORE_PROPERTIES_NAMESPACE
+    );
+    let _ = write!(
+        content,
+        "<dc:creator>{}</dc:creator>",
+        escape(&properties.creator)
+    );
+    if !properties.description.is_empty() {
+        let _ = write!(
+            content,
+            "<dc:description>{}</dc:description>",
+            escape(&properties.description)
+        );
+    }
+    let _ = write!(content, "<dc:identifier>{}</dc:identifier>", escape(id));
+    let _ = write!(content, "<version>{}</version>", escape(version));
+    if !properties.keywords.is_empty() {
+        let _ = write!(
+            content,
+            "<keywords>{}</keywords>",
+            escape(&properties.keywords)
+        );
+    }
+    content.push_str("<lastModifiedBy>aer</lastModifiedBy></coreProperties>");
+
+    content
+}
+
+/// Creates a stable hash (*FNV-1a*) of the specified value, used for the
+/// names and identifiers in the archive that only need to be unique within
+/// the package.
+fn hash(value: &str) -> u64 {
+    value.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest(
+        path,
+        expected,
+        case("tools/chocolateyInstall.ps1", "tools/chocolateyInstall.ps1"),
+        case("tools/my file.txt", "tools/my%20file.txt"),
+        case("tools/[data].json", "tools/%5Bdata%5D.json")
+    )]
+    fn part_name_should_escape_reserved_characters(path: &str, expected: &str) {
+        let path = PathBuf::from(path);
+
+        let actual = part_name(&path);
+
+        assert_eq!(actual, expected);
+    }
+}
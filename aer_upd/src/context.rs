@@ -0,0 +1,545 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the context that is passed through every step of the update
+//! process, holding the handles shared by parsers, web calls and runners.
+
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use aer_web::platform::Platform;
+use aer_web::WebRequest;
+use log::Level;
+
+use crate::observer::{Observer, Stage};
+use crate::runners::ScriptEnvironment;
+
+/// The log target used for messages that are scoped to a single package.
+pub const PACKAGE_LOG_TARGET: &str = "aer::package";
+
+/// A token that can be shared between threads to request that the update
+/// process should be stopped at the next possible step.
+#[derive(Debug, Default, Clone)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Creates a new token that have not been cancelled.
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Requests cancellation of every process using this token, or a clone of
+    /// this token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns wether cancellation have been requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// The error returned when a step was not run because cancellation have been
+/// requested.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("The update process was cancelled!")
+    }
+}
+
+impl Error for Cancelled {}
+
+/// Receives progress reports from the update process, this can be
+/// implemented to drive a progress indicator.
+pub trait ProgressSink: Send + Sync {
+    /// Called every time a package reaches a new step in the update process.
+    fn report(&self, package_id: &str, message: &str);
+}
+
+/// The default progress sink, which writes every report to the debug log.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LogProgress;
+
+impl ProgressSink for LogProgress {
+    fn report(&self, package_id: &str, message: &str) {
+        log::debug!(target: PACKAGE_LOG_TARGET, "[{}] {}", package_id, message);
+    }
+}
+
+/// Writes log messages that are prefixed with the identifier of the package
+/// being processed, making it possible to follow a single package when
+/// several packages are processed at once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageLogger {
+    package_id: String,
+}
+
+impl PackageLogger {
+    /// Writes the message using the specified log level.
+    pub fn log<M: Display>(&self, level: Level, message: M) {
+        log::log!(target: PACKAGE_LOG_TARGET, level, "[{}] {}", self.package_id, message);
+    }
+
+    /// Writes the message as an error.
+    pub fn error<M: Display>(&self, message: M) {
+        self.log(Level::Error, message);
+    }
+
+    /// Writes the message as a warning.
+    pub fn warn<M: Display>(&self, message: M) {
+        self.log(Level::Warn, message);
+    }
+
+    /// Writes the message as information.
+    pub fn info<M: Display>(&self, message: M) {
+        self.log(Level::Info, message);
+    }
+
+    /// Writes the message as a debug message.
+    pub fn debug<M: Display>(&self, message: M) {
+        self.log(Level::Debug, message);
+    }
+}
+
+/// Holds everything that is shared by the different steps when updating a
+/// package.
+///
+/// A single context is normally created for the whole run, and a child
+/// context is created for each package using
+/// [for_package](UpdateContext::for_package). Child contexts share the
+/// cancellation token and progress sink of the parent.
+///
+/// ## Thread safety
+///
+/// The context is `Send` and `Sync`, and is cheap to clone. Workers processing
+/// packages concurrently are expected to receive a child context of their
+/// own, while the cancellation token, progress sink, observers and web request
+/// stay shared between every child.
+///
+/// ## Examples
+///
+/// ```
+/// use aer_upd::context::UpdateContext;
+///
+/// let context = UpdateContext::new().with_work_dir("./work");
+/// let package = context.for_package("astyle");
+///
+/// context.cancellation().cancel();
+///
+/// assert!(package.check_cancelled().is_err());
+/// ```
+#[derive(Clone)]
+pub struct UpdateContext {
+    package_id: String,
+    work_dir: PathBuf,
+    cache_dir: PathBuf,
+    config: Option<PathBuf>,
+    request: WebRequest,
+    environment: ScriptEnvironment,
+    cancellation: CancellationToken,
+    progress: Arc<dyn ProgressSink>,
+    observers: Vec<Arc<dyn Observer>>,
+}
+
+impl UpdateContext {
+    /// Creates a new context, using the temporary directory as the work
+    /// directory and a default web request.
+    pub fn new() -> UpdateContext {
+        let temp_dir = Platform::current().temp_dir();
+
+        UpdateContext {
+            package_id: String::new(),
+            cache_dir: temp_dir.join("aer-cache"),
+            work_dir: temp_dir,
+            config: None,
+            request: WebRequest::create(),
+            environment: ScriptEnvironment::inherit(),
+            cancellation: CancellationToken::new(),
+            progress: Arc::new(LogProgress),
+            observers: vec![],
+        }
+    }
+
+    /// Creates a child context for the package with the specified identifier.
+    pub fn for_package(&self, package_id: &str) -> UpdateContext {
+        let mut context = self.clone();
+        context.package_id = package_id.into();
+        context
+    }
+
+    /// Creates a child context where the package gets a work directory and a
+    /// temporary directory of its own, and where scripts are run with a
+    /// scrubbed environment only containing the default variables and the
+    /// specified `passthrough` variables. This prevents packages that are
+    /// processed concurrently from interfering with each other.
+    ///
+    /// The temporary directory is emptied every time the context is created,
+    /// and is exposed to scripts through the `TEMP`, `TMP` and `TMPDIR`
+    /// variables.
+    pub fn isolated<I: IntoIterator<Item = S>, S: Into<String>>(
+        &self,
+        passthrough: I,
+    ) -> Result<UpdateContext, std::io::Error> {
+        let name: String = self
+            .package_id
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let work_dir = self
+            .work_dir
+            .join(if name.is_empty() { "_" } else { &name });
+        let temp_dir = work_dir.join("tmp");
+        if temp_dir.exists() {
+            std::fs::remove_dir_all(&temp_dir)?;
+        }
+        std::fs::create_dir_all(&temp_dir)?;
+
+        let mut context = self.clone();
+        context.work_dir = work_dir;
+        context.environment = ScriptEnvironment::scrubbed(passthrough)
+            .with_var("TEMP", &temp_dir)
+            .with_var("TMP", &temp_dir)
+            .with_var("TMPDIR", &temp_dir)
+            .with_protocol(self.environment.protocol());
+
+        Ok(context)
+    }
+
+    /// Sets the directory to use when downloading files and running scripts.
+    pub fn with_work_dir<P: Into<PathBuf>>(mut self, work_dir: P) -> UpdateContext {
+        self.work_dir = work_dir.into();
+        self
+    }
+
+    /// Sets the directory where cached responses should be stored.
+    pub fn with_cache_dir<P: Into<PathBuf>>(mut self, cache_dir: P) -> UpdateContext {
+        self.cache_dir = cache_dir.into();
+        self
+    }
+
+    /// Sets the path to the configuration file in use.
+    pub fn with_config<P: Into<PathBuf>>(mut self, config: P) -> UpdateContext {
+        self.config = Some(config.into());
+        self
+    }
+
+    /// Sets the web request used when contacting remote servers.
+    pub fn with_request(mut self, request: WebRequest) -> UpdateContext {
+        self.request = request;
+        self
+    }
+
+    /// Sets the environment variables that scripts are run with.
+    pub fn with_environment(mut self, environment: ScriptEnvironment) -> UpdateContext {
+        self.environment = environment;
+        self
+    }
+
+    /// Sets the token used to check if the process have been cancelled.
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> UpdateContext {
+        self.cancellation = cancellation;
+        self
+    }
+
+    /// Sets the sink that progress reports are sent to.
+    pub fn with_progress<P: ProgressSink + 'static>(mut self, progress: P) -> UpdateContext {
+        self.progress = Arc::new(progress);
+        self
+    }
+
+    /// Registers an observer that will be notified of the different stages
+    /// of the update process, several observers can be registered.
+    pub fn with_observer<O: Observer + 'static>(mut self, observer: O) -> UpdateContext {
+        self.observers.push(Arc::new(observer));
+        self
+    }
+
+    /// Returns the identifier of the package being processed, this is empty
+    /// for contexts not created for a specific package.
+    pub fn package_id(&self) -> &str {
+        &self.package_id
+    }
+
+    /// Returns the directory to use when downloading files and running
+    /// scripts.
+    pub fn work_dir(&self) -> &Path {
+        &self.work_dir
+    }
+
+    /// Returns the directory where cached responses should be stored.
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    /// Returns the path to the configuration file in use, if any.
+    pub fn config(&self) -> Option<&Path> {
+        self.config.as_deref()
+    }
+
+    /// Returns the web request to use when contacting remote servers.
+    pub fn request(&self) -> &WebRequest {
+        &self.request
+    }
+
+    /// Returns the environment variables that scripts are run with.
+    pub fn environment(&self) -> &ScriptEnvironment {
+        &self.environment
+    }
+
+    /// Returns the token used to check if the process have been cancelled.
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    /// Returns an error if cancellation have been requested, this should be
+    /// called before starting any step that may take some time.
+    pub fn check_cancelled(&self) -> Result<(), Cancelled> {
+        if self.cancellation.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Reports progress of the current package to the progress sink.
+    pub fn progress<M: Display>(&self, message: M) {
+        self.progress.report(&self.package_id, &message.to_string());
+    }
+
+    /// Runs the specified function as the specified stage, notifying the
+    /// registered observers when the stage starts and ends.
+    pub fn stage<T, E, F: FnOnce() -> Result<T, E>>(&self, stage: Stage, func: F) -> Result<T, E> {
+        for observer in &self.observers {
+            observer.on_stage_start(&self.package_id, stage);
+        }
+        self.progress(stage);
+
+        let result = func();
+
+        for observer in &self.observers {
+            observer.on_stage_end(&self.package_id, stage, result.is_ok());
+        }
+
+        result
+    }
+
+    /// Writes the warning to the log, and notifies the registered observers.
+    pub fn warn<M: Display>(&self, message: M) {
+        let message = message.to_string();
+        self.logger().warn(&message);
+
+        for observer in &self.observers {
+            observer.on_warning(&self.package_id, &message);
+        }
+    }
+
+    /// Notifies the registered observers that a file have been created.
+    pub fn artifact(&self, path: &Path) {
+        self.logger()
+            .debug(format_args!("Created '{}'", path.display()));
+
+        for observer in &self.observers {
+            observer.on_artifact(&self.package_id, path);
+        }
+    }
+
+    /// Returns the logger scoped to the current package.
+    pub fn logger(&self) -> PackageLogger {
+        PackageLogger {
+            package_id: self.package_id.clone(),
+        }
+    }
+}
+
+impl Default for UpdateContext {
+    fn default() -> UpdateContext {
+        UpdateContext::new()
+    }
+}
+
+impl fmt::Debug for UpdateContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpdateContext")
+            .field("package_id", &self.package_id)
+            .field("work_dir", &self.work_dir)
+            .field("cache_dir", &self.cache_dir)
+            .field("config", &self.config)
+            .field("environment", &self.environment)
+            .field("cancellation", &self.cancellation)
+            .field("observers", &self.observers.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use static_assertions::assert_impl_all;
+
+    use super::*;
+    use crate::runners::RunnerProtocol;
+
+    assert_impl_all!(UpdateContext: Send, Sync, Clone);
+    assert_impl_all!(CancellationToken: Send, Sync, Clone);
+    assert_impl_all!(PackageLogger: Send, Sync);
+
+    #[derive(Default)]
+    struct RecordingProgress {
+        reports: Arc<Mutex<Vec<(String, String)>>>,
+    }
+
+    impl ProgressSink for RecordingProgress {
+        fn report(&self, package_id: &str, message: &str) {
+            self.reports
+                .lock()
+                .unwrap()
+                .push((package_id.into(), message.into()));
+        }
+    }
+
+    #[test]
+    fn for_package_should_share_cancellation_with_parent() {
+        let parent = UpdateContext::new();
+        let child = parent.for_package("test-package");
+
+        assert_eq!(child.check_cancelled(), Ok(()));
+        parent.cancellation().cancel();
+
+        assert_eq!(child.check_cancelled(), Err(Cancelled));
+        assert_eq!(child.package_id(), "test-package");
+        assert_eq!(parent.package_id(), "");
+    }
+
+    #[test]
+    fn progress_should_report_to_sink_with_package_id() {
+        let progress = RecordingProgress::default();
+        let reports = progress.reports.clone();
+        let context = UpdateContext::new()
+            .with_progress(progress)
+            .for_package("test-package");
+
+        context.progress("Parsing links");
+
+        assert_eq!(
+            *reports.lock().unwrap(),
+            [("test-package".to_owned(), "Parsing links".to_owned())]
+        );
+    }
+
+    #[derive(Default)]
+    struct RecordingObserver {
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Observer for RecordingObserver {
+        fn on_stage_start(&self, package_id: &str, stage: Stage) {
+            self.push(format!("{}: start {}", package_id, stage));
+        }
+
+        fn on_stage_end(&self, package_id: &str, stage: Stage, success: bool) {
+            self.push(format!("{}: end {} ({})", package_id, stage, success));
+        }
+
+        fn on_warning(&self, package_id: &str, message: &str) {
+            self.push(format!("{}: warning {}", package_id, message));
+        }
+
+        fn on_artifact(&self, package_id: &str, path: &Path) {
+            self.push(format!("{}: artifact {}", package_id, path.display()));
+        }
+    }
+
+    impl RecordingObserver {
+        fn push(&self, event: String) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    #[test]
+    fn observers_should_be_notified_of_events() {
+        let observer = RecordingObserver::default();
+        let events = observer.events.clone();
+        let context = UpdateContext::new()
+            .with_observer(observer)
+            .for_package("test-package");
+
+        let _ = context.stage(Stage::FindLinks, || Ok::<_, Cancelled>(()));
+        let _ = context.stage(Stage::Download, || Err::<(), _>(Cancelled));
+        context.warn("No checksum");
+        context.artifact(Path::new("test.zip"));
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                "test-package: start Find Links",
+                "test-package: end Find Links (true)",
+                "test-package: start Download",
+                "test-package: end Download (false)",
+                "test-package: warning No checksum",
+                "test-package: artifact test.zip"
+            ]
+        );
+    }
+
+    #[test]
+    fn with_functions_should_set_paths() {
+        let context = UpdateContext::new()
+            .with_work_dir("work")
+            .with_cache_dir("cache")
+            .with_config("aer.toml");
+
+        assert_eq!(context.work_dir(), Path::new("work"));
+        assert_eq!(context.cache_dir(), Path::new("cache"));
+        assert_eq!(context.config(), Some(Path::new("aer.toml")));
+    }
+
+    #[test]
+    fn isolated_should_keep_runner_protocol() {
+        let dir = std::env::temp_dir().join("aer-context-isolated-protocol-test");
+        let context = UpdateContext::new()
+            .with_work_dir(&dir)
+            .with_environment(ScriptEnvironment::inherit().with_protocol(RunnerProtocol::Markers));
+
+        let actual = context.for_package("test").isolated(vec!["CI"]).unwrap();
+
+        assert_eq!(actual.environment().protocol(), RunnerProtocol::Markers);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn isolated_should_create_separate_directories_for_each_package() {
+        let dir = std::env::temp_dir().join("aer-context-isolated-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let context = UpdateContext::new().with_work_dir(&dir);
+
+        let first = context
+            .for_package("first/package")
+            .isolated(vec!["CI"])
+            .unwrap();
+        let second = context.for_package("second").isolated(vec!["CI"]).unwrap();
+
+        assert_eq!(first.work_dir(), dir.join("first_package"));
+        assert_eq!(second.work_dir(), dir.join("second"));
+        assert!(first.work_dir().join("tmp").is_dir());
+        assert!(second.work_dir().join("tmp").is_dir());
+        assert!(first.environment().is_scrubbed());
+        assert!(!context.environment().is_scrubbed());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
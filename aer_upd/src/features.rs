@@ -0,0 +1,214 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the information about the optional cargo features of the crate,
+//! and which of these was enabled when the crate was compiled. This allows
+//! reporting a clear error when a package requests a capability that have
+//! been compiled out, instead of failing with a generic error.
+//!
+//! ### Examples
+//!
+//! ```
+//! use aer_upd::features;
+//!
+//! if !features::is_enabled("powershell") {
+//!     let err = features::require("powershell", "Running 'update.ps1'").unwrap_err();
+//!     assert_eq!(err.feature, "powershell");
+//! }
+//! ```
+
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::path::Path;
+
+/// An optional cargo feature of the crate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Feature {
+    /// The name of the cargo feature (*ie: `powershell`*).
+    pub name: &'static str,
+    /// A short description of the capability the feature provides.
+    pub description: &'static str,
+    /// Wether the feature was enabled when the crate was compiled.
+    pub enabled: bool,
+}
+
+macro_rules! features {
+    ($($name:literal => $description:literal),+ $(,)?) => {
+        &[$(Feature {
+            name: $name,
+            description: $description,
+            enabled: cfg!(feature = $name),
+        }),+]
+    };
+}
+
+/// All optional features of the crate.
+pub const FEATURES: &[Feature] = features! {
+    "chocolatey" => "Creating Chocolatey packages",
+    "nuget" => "Creating NuGet packages",
+    "winget" => "Creating winget manifests",
+    "powershell" => "Running PowerShell scripts (*.ps1)",
+    "shell" => "Running shell scripts (*.sh)",
+    "lua" => "Running Lua scripts in an embedded interpreter (*.lua)",
+    "json_data" => "Reading and writing package files in JSON format (*.json)",
+    "toml_data" => "Reading and writing package files in TOML format (*.toml)",
+    "yaml_data" => "Reading and writing package files in YAML format (*.yaml, *.yml)",
+    "async" => "Asynchronous web requests",
+};
+
+/// Returns the feature with the specified name.
+pub fn feature(name: &str) -> Option<&'static Feature> {
+    FEATURES.iter().find(|feature| feature.name == name)
+}
+
+/// Returns wether the feature with the specified name was enabled when the
+/// crate was compiled, unknown features are never enabled.
+pub fn is_enabled(name: &str) -> bool {
+    feature(name)
+        .map(|feature| feature.enabled)
+        .unwrap_or(false)
+}
+
+/// Returns the names of all features that was enabled when the crate was
+/// compiled.
+pub fn enabled() -> Vec<&'static str> {
+    FEATURES
+        .iter()
+        .filter(|feature| feature.enabled)
+        .map(|feature| feature.name)
+        .collect()
+}
+
+/// Returns an error when the feature with the specified name was not enabled
+/// when the crate was compiled.
+///
+/// ## Arguments
+///
+/// - `name`: The name of the cargo feature (*ie: `powershell`*).
+/// - `capability`: The capability that was requested, used in the message of
+///   the error (*ie: `Running 'update.ps1'`*).
+pub fn require<C: Display>(name: &'static str, capability: C) -> Result<(), FeatureError> {
+    if is_enabled(name) {
+        Ok(())
+    } else {
+        Err(FeatureError {
+            feature: name,
+            capability: capability.to_string(),
+        })
+    }
+}
+
+/// Returns the name of the feature needed to read or write the package file,
+/// based on the extension of the file.
+pub fn for_package_file(path: &Path) -> Option<&'static str> {
+    match extension(path)?.as_str() {
+        "json" => Some("json_data"),
+        "toml" => Some("toml_data"),
+        "yaml" | "yml" => Some("yaml_data"),
+        _ => None,
+    }
+}
+
+/// Returns the name of the feature needed to run the script, based on the
+/// extension of the script.
+pub fn for_script(path: &Path) -> Option<&'static str> {
+    match extension(path)?.as_str() {
+        "ps1" => Some("powershell"),
+        "sh" => Some("shell"),
+        "lua" => Some("lua"),
+        _ => None,
+    }
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+}
+
+/// The error returned when a capability is requested that requires a feature
+/// that was not enabled when the crate was compiled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureError {
+    /// The name of the cargo feature that is required.
+    pub feature: &'static str,
+    /// The capability that was requested.
+    pub capability: String,
+}
+
+impl Display for FeatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let enabled = enabled();
+
+        write!(
+            f,
+            "{} requires the feature '{}', which is not enabled in this build (enabled features: \
+             {})",
+            self.capability,
+            self.feature,
+            if enabled.is_empty() {
+                "none".to_string()
+            } else {
+                enabled.join(", ")
+            }
+        )
+    }
+}
+
+impl Error for FeatureError {}
+
+impl FeatureError {
+    /// Returns the stable code identifying the kind of error (*ie:
+    /// `AER0007`*), which can be used when searching the documentation.
+    pub fn code(&self) -> &'static str {
+        "AER0007"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[test]
+    fn features_should_match_compiled_features() {
+        assert_eq!(is_enabled("chocolatey"), cfg!(feature = "chocolatey"));
+        assert_eq!(is_enabled("powershell"), cfg!(feature = "powershell"));
+        assert!(!is_enabled("unknown"));
+    }
+
+    #[test]
+    fn require_should_return_error_for_unknown_features() {
+        let actual = require("unknown", "Running 'update.unknown'").unwrap_err();
+
+        assert_eq!(actual.feature, "unknown");
+        assert!(actual
+            .to_string()
+            .starts_with("Running 'update.unknown' requires the feature 'unknown'"));
+    }
+
+    #[rstest(
+        path,
+        expected,
+        case("package.aer.toml", Some("toml_data")),
+        case("package.aer.YML", Some("yaml_data")),
+        case("package.aer.json", Some("json_data")),
+        case("package.aer.xml", None)
+    )]
+    fn for_package_file_should_return_feature_of_extension(path: &str, expected: Option<&str>) {
+        assert_eq!(for_package_file(Path::new(path)), expected);
+    }
+
+    #[rstest(
+        path,
+        expected,
+        case("update.ps1", Some("powershell")),
+        case("update.sh", Some("shell")),
+        case("update.lua", Some("lua")),
+        case("update.bat", None)
+    )]
+    fn for_script_should_return_feature_of_extension(path: &str, expected: Option<&str>) {
+        assert_eq!(for_script(Path::new(path)), expected);
+    }
+}
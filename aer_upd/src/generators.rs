@@ -0,0 +1,100 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the generators responsible for creating the files of a package,
+//! based on the package data of the package.
+
+use std::path::{Path, PathBuf};
+
+use aer_data::prelude::*;
+
+#[cfg(feature = "chocolatey")]
+pub mod chocolatey;
+pub mod description;
+#[cfg(feature = "nuget")]
+pub mod nuget;
+pub mod templates;
+#[cfg(feature = "chocolatey")]
+pub mod verification;
+#[cfg(feature = "winget")]
+pub mod winget;
+
+/// The errors that can occur when generating the files of a package.
+#[derive(Debug, thiserror::Error)]
+pub enum GeneratorError {
+    /// The package data is missing a value that is required by the package
+    /// manager.
+    #[error("The required value '{0}' have not been set!")]
+    MissingData(String),
+    /// Reading or writing a file failed.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// A template contains an unknown or malformed token.
+    #[error("Line {line}: {message}")]
+    Template {
+        /// The line the token was found on, starting at 1.
+        line: usize,
+        /// The reason the token could not be replaced.
+        message: String,
+    },
+    /// The file the description is read from could not be read.
+    #[error("Unable to read the description from '{}': {source}", path.display())]
+    DescriptionFile {
+        /// The resolved path of the file.
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+impl GeneratorError {
+    /// Returns the stable code identifying the kind of error (*ie:
+    /// `AER0301`*), which can be used when searching the documentation.
+    pub fn code(&self) -> &'static str {
+        match self {
+            GeneratorError::MissingData(_) => "AER0301",
+            GeneratorError::Io(_) => "AER0302",
+            GeneratorError::Template { .. } => "AER0303",
+            GeneratorError::DescriptionFile { .. } => "AER0305",
+        }
+    }
+}
+
+/// Generators implementing this trait are able to create the files and
+/// directory layout of a package for a specific package manager.
+///
+/// Generators must be `Send` and `Sync`, as the same generator can be used by
+/// several threads creating packages concurrently.
+pub trait PackageGenerator: Send + Sync {
+    /// Creates the files of the package inside the specified output
+    /// directory, and returns the directory the package was created in.
+    fn generate(&self, data: &PackageData, output_dir: &Path) -> Result<PathBuf, GeneratorError>;
+}
+
+/// Writes a single element of a nuspec file, escaping the value of the
+/// element.
+#[cfg(any(feature = "chocolatey", feature = "nuget"))]
+pub(crate) fn write_element(nuspec: &mut String, name: &str, value: &str) {
+    use std::fmt::Write;
+
+    let _ = writeln!(nuspec, "    <{0}>{1}</{0}>", name, escape(value));
+}
+
+/// Escapes the characters that are not allowed in the text and attribute
+/// values of xml files.
+#[cfg(any(feature = "chocolatey", feature = "nuget"))]
+pub(crate) fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
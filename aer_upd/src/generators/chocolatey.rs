@@ -0,0 +1,360 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the generator responsible for creating the nuspec file, and the
+//! directory layout used by Chocolatey packages.
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use aer_data::prelude::*;
+use log::debug;
+
+use crate::generators::description::DescriptionResolver;
+use crate::generators::verification::LEGAL_DIR;
+use crate::generators::{escape, write_element, GeneratorError, PackageGenerator};
+
+/// The namespace used by nuspec files supported by Chocolatey.
+const NUSPEC_NAMESPACE: &str = "http://schemas.microsoft.com/packaging/2015/06/nuspec.xsd";
+
+/// The name of the directory that holds the scripts and files of the package.
+pub const TOOLS_DIR: &str = "tools";
+
+/// Generates the nuspec file of a Chocolatey package, and creates the
+/// directory layout expected by Chocolatey.
+///
+/// The package is created in a directory named after the identifier of the
+/// package, with the following layout:
+///
+/// ```text
+/// <id>/
+///   <id>.nuspec
+///   tools/
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NuspecGenerator {
+    resolver: DescriptionResolver,
+}
+
+impl NuspecGenerator {
+    /// Creates a new instance of the generator.
+    pub fn new() -> NuspecGenerator {
+        NuspecGenerator::default()
+    }
+
+    /// Sets the directory that relative description locations are resolved
+    /// against, this is normally the directory of the package data file.
+    pub fn with_base_dir<P: Into<PathBuf>>(mut self, base_dir: P) -> NuspecGenerator {
+        self.resolver = self.resolver.with_base_dir(base_dir);
+        self
+    }
+
+    /// Sets the resolver used when reading the description, allowing the
+    /// cached descriptions to be shared between several generators.
+    pub fn with_resolver(mut self, resolver: DescriptionResolver) -> NuspecGenerator {
+        self.resolver = resolver;
+        self
+    }
+
+    /// Creates the content of the nuspec file for the specified package.
+    pub fn to_nuspec(&self, data: &PackageData) -> Result<String, GeneratorError> {
+        let metadata = data.metadata();
+        let choco = metadata.chocolatey();
+
+        if choco.authors().is_empty() {
+            return Err(GeneratorError::MissingData("authors".into()));
+        }
+
+        let description = self.resolver.resolve(choco.description())?;
+        if description.trim().is_empty() {
+            return Err(GeneratorError::MissingData("description".into()));
+        }
+
+        let mut nuspec = String::new();
+        let _ = writeln!(nuspec, r#"<?xml version="1.0" encoding="utf-8"?>"#);
+        let _ = writeln!(nuspec, r#"<package xmlns="{}">"#, NUSPEC_NAMESPACE);
+        let _ = writeln!(nuspec, "  <metadata>");
+        write_element(&mut nuspec, "id", &package_id(data));
+        write_element(
+            &mut nuspec,
+            "version",
+            &choco.version.to_choco().to_string(),
+        );
+        if let Some(ref title) = choco.title {
+            write_element(&mut nuspec, "title", title);
+        }
+        write_element(&mut nuspec, "authors", &choco.authors().join(", "));
+        write_element(&mut nuspec, "owners", &metadata.maintainers().join(", "));
+        write_element(&mut nuspec, "projectUrl", metadata.project_url().as_str());
+        if let Some(url) = metadata.license().license_url() {
+            write_element(&mut nuspec, "licenseUrl", url);
+        }
+        write_element(
+            &mut nuspec,
+            "requireLicenseAcceptance",
+            &choco.require_license_acceptance.to_string(),
+        );
+        if let Some(ref copyright) = choco.copyright {
+            write_element(&mut nuspec, "copyright", copyright);
+        }
+        if !choco.tags().is_empty() {
+            write_element(&mut nuspec, "tags", &choco.tags().join(" "));
+        }
+        if !metadata.summary.is_empty() {
+            write_element(&mut nuspec, "summary", &metadata.summary);
+        }
+        write_element(&mut nuspec, "description", &description);
+        if let Some(release_notes) = choco.release_notes() {
+            write_element(&mut nuspec, "releaseNotes", release_notes);
+        }
+        if let Some(ref url) = choco.documentation_url {
+            write_element(&mut nuspec, "docsUrl", url.as_str());
+        }
+        if let Some(ref url) = choco.issues_url {
+            write_element(&mut nuspec, "bugTrackerUrl", url.as_str());
+        }
+
+        if !choco.dependencies().is_empty() {
+            // The dependencies are sorted to keep the output stable between runs
+            let mut dependencies: Vec<_> = choco.dependencies().iter().collect();
+            dependencies.sort_by_key(|(id, _)| *id);
+
+            let _ = writeln!(nuspec, "    <dependencies>");
+            for (id, version) in dependencies {
+                let _ = writeln!(
+                    nuspec,
+                    r#"      <dependency id="{}" version="{}" />"#,
+                    escape(id),
+                    escape(&version.to_choco().to_string())
+                );
+            }
+            let _ = writeln!(nuspec, "    </dependencies>");
+        }
+
+        let _ = writeln!(nuspec, "  </metadata>");
+        let _ = writeln!(nuspec, "  <files>");
+        let _ = writeln!(
+            nuspec,
+            r#"    <file src="{0}\**" target="{0}" />"#,
+            TOOLS_DIR
+        );
+        if data.updater().chocolatey().embedded {
+            let _ = writeln!(
+                nuspec,
+                r#"    <file src="{0}\**" target="{0}" />"#,
+                LEGAL_DIR
+            );
+        }
+        let _ = writeln!(nuspec, "  </files>");
+        let _ = writeln!(nuspec, "</package>");
+
+        Ok(nuspec)
+    }
+}
+
+impl PackageGenerator for NuspecGenerator {
+    fn generate(&self, data: &PackageData, output_dir: &Path) -> Result<PathBuf, GeneratorError> {
+        let nuspec = self.to_nuspec(data)?;
+        let id = package_id(data);
+        let package_dir = output_dir.join(&id);
+
+        std::fs::create_dir_all(package_dir.join(TOOLS_DIR))?;
+        let nuspec_path = package_dir.join(format!("{}.nuspec", id));
+        std::fs::write(&nuspec_path, nuspec)?;
+        debug!("Created nuspec file at '{}'", nuspec_path.display());
+
+        Ok(package_dir)
+    }
+}
+
+fn package_id(data: &PackageData) -> String {
+    let id = data.metadata().id();
+
+    if data.metadata().chocolatey().lowercase_id() {
+        id.to_lowercase()
+    } else {
+        id.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_data::prelude::chocolatey::*;
+    use rstest::rstest;
+    use static_assertions::assert_impl_all;
+
+    use super::*;
+
+    assert_impl_all!(NuspecGenerator: Send, Sync);
+
+    fn full_package() -> PackageData {
+        let mut data = PackageData::new("AStyle");
+        let metadata = data.metadata_mut();
+        metadata.set_maintainers(&["AdmiringWorm"]);
+        metadata.set_project_url("http://astyle.sourceforge.net/");
+        metadata.set_license(LicenseType::Location(
+            Url::parse("https://sourceforge.net/p/astyle/code/HEAD/tree/trunk/AStyle/LICENSE.md")
+                .unwrap(),
+        ));
+        metadata.summary = "Artistic Style is a source code indenter".into();
+        metadata.set_chocolatey({
+            let mut choco = ChocolateyMetadata::with_authors(&["Jim Pattee", "Tal Davidson"]);
+            choco.version = Versions::parse("3.1").unwrap();
+            choco.set_title("Artistic Style");
+            choco.set_copyright("Copyright (c) 2014 Jim Pattee");
+            choco.set_description_str("Formats C, C++ & C# source code");
+            choco.set_tags(&["astyle", "beautifier"]);
+            choco.set_release_notes("http://astyle.sourceforge.net/notes.html");
+            choco.documentation_url =
+                Some(Url::parse("http://astyle.sourceforge.net/astyle.html").unwrap());
+            choco.issues_url = Some(Url::parse("https://sourceforge.net/p/astyle/bugs").unwrap());
+            choco.add_dependencies("chocolatey-core.extension", "1.3.3");
+            choco.add_dependencies("7zip", "19.0");
+            choco
+        });
+
+        data
+    }
+
+    #[test]
+    fn to_nuspec_should_create_expected_nuspec() {
+        let data = full_package();
+
+        let actual = NuspecGenerator::new().to_nuspec(&data).unwrap();
+
+        assert_eq!(
+            actual,
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<package xmlns="http://schemas.microsoft.com/packaging/2015/06/nuspec.xsd">
+  <metadata>
+    <id>astyle</id>
+    <version>3.1</version>
+    <title>Artistic Style</title>
+    <authors>Jim Pattee, Tal Davidson</authors>
+    <owners>AdmiringWorm</owners>
+    <projectUrl>http://astyle.sourceforge.net/</projectUrl>
+    <licenseUrl>https://sourceforge.net/p/astyle/code/HEAD/tree/trunk/AStyle/LICENSE.md</licenseUrl>
+    <requireLicenseAcceptance>true</requireLicenseAcceptance>
+    <copyright>Copyright (c) 2014 Jim Pattee</copyright>
+    <tags>astyle beautifier</tags>
+    <summary>Artistic Style is a source code indenter</summary>
+    <description>Formats C, C++ &amp; C# source code</description>
+    <releaseNotes>http://astyle.sourceforge.net/notes.html</releaseNotes>
+    <docsUrl>http://astyle.sourceforge.net/astyle.html</docsUrl>
+    <bugTrackerUrl>https://sourceforge.net/p/astyle/bugs</bugTrackerUrl>
+    <dependencies>
+      <dependency id="7zip" version="19.0" />
+      <dependency id="chocolatey-core.extension" version="1.3.3" />
+    </dependencies>
+  </metadata>
+  <files>
+    <file src="tools\**" target="tools" />
+  </files>
+</package>
+"#
+        );
+    }
+
+    #[test]
+    fn to_nuspec_should_return_error_when_description_is_missing() {
+        let mut data = PackageData::new("test-package");
+        data.metadata_mut()
+            .set_chocolatey(ChocolateyMetadata::with_authors(&["AdmiringWorm"]));
+
+        let actual = NuspecGenerator::new().to_nuspec(&data).unwrap_err();
+
+        assert_eq!(
+            actual.to_string(),
+            "The required value 'description' have not been set!"
+        );
+    }
+
+    #[rstest(
+        skip_start,
+        skip_end,
+        expected,
+        case(0, 0, "# Title\n\nSome description\n\n---"),
+        case(2, 2, "Some description")
+    )]
+    fn to_nuspec_should_read_description_from_location(
+        skip_start: u16,
+        skip_end: u16,
+        expected: &str,
+    ) {
+        let dir = std::env::temp_dir().join(format!(
+            "aer-nuspec-description-{}-{}",
+            skip_start, skip_end
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("description.md"),
+            "# Title\n\nSome description\n\n---\n",
+        )
+        .unwrap();
+        let mut data = full_package();
+        let mut choco = data.metadata().chocolatey().into_owned();
+        choco.set_description(Description::Location {
+            from: PathBuf::from("description.md"),
+            skip_start,
+            skip_end,
+        });
+        data.metadata_mut().set_chocolatey(choco);
+
+        let actual = NuspecGenerator::new()
+            .with_base_dir(&dir)
+            .to_nuspec(&data)
+            .unwrap();
+
+        assert!(actual.contains(&format!("<description>{}</description>", expected)));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn generate_should_create_package_layout() {
+        let dir = std::env::temp_dir().join("aer-nuspec-generate-test");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let actual = NuspecGenerator::new()
+            .generate(&full_package(), &dir)
+            .unwrap();
+
+        assert_eq!(actual, dir.join("astyle"));
+        assert!(actual.join("astyle.nuspec").is_file());
+        assert!(actual.join("tools").is_dir());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn to_nuspec_should_include_legal_files_of_embedded_packages() {
+        let mut data = full_package();
+        data.updater_mut().set_chocolatey({
+            let mut updater = ChocolateyUpdaterData::new();
+            updater.embedded = true;
+            updater
+        });
+
+        let actual = NuspecGenerator::new().to_nuspec(&data).unwrap();
+
+        assert!(actual.contains(
+            "    <file src=\"tools\\**\" target=\"tools\" />\n    <file src=\"legal\\**\" \
+             target=\"legal\" />\n"
+        ));
+    }
+
+    #[cfg(feature = "toml_data")]
+    #[test]
+    fn to_nuspec_should_match_golden_files() {
+        use crate::golden::{self, Golden};
+
+        for case in golden::cases("nuspec") {
+            let data = crate::parsers::read_file(&case.join("package.aer.toml")).unwrap();
+
+            let actual = NuspecGenerator::new()
+                .with_base_dir(&case)
+                .to_nuspec(&data)
+                .unwrap();
+
+            Golden::new(case.join("expected.nuspec")).assert(&actual);
+        }
+    }
+}
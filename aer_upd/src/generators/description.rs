@@ -0,0 +1,250 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the resolving of package descriptions, creating the final text of
+//! the description that is used in the generated packages.
+//!
+//! Descriptions that are read from a file are resolved relative to the base
+//! directory (*normally the directory of the package data file*), after which
+//! the configured number of lines are skipped from the start and the end of
+//! the file. Markdown files (*ie: `README.md`*) are additionally cleaned up,
+//! by removing the front matter and any HTML comments that are not rendered
+//! as part of the description.
+//!
+//! The content of the files are cached by the resolver, and only read again
+//! when the file have been modified. Clones of the resolver share the same
+//! cache.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use aer_data::prelude::*;
+
+use crate::generators::GeneratorError;
+
+/// The extensions of files that are handled as markdown files.
+const MARKDOWN_EXTENSIONS: &[&str] = &["md", "markdown"];
+
+/// The cached content of a description file, together with the time the file
+/// was last modified when it was read.
+type CachedDescription = (Option<SystemTime>, Arc<str>);
+
+/// Resolves the description of packages, reading the description from a file
+/// when necessary.
+#[derive(Debug, Default, Clone)]
+pub struct DescriptionResolver {
+    base_dir: Option<PathBuf>,
+    cache: Arc<Mutex<HashMap<PathBuf, CachedDescription>>>,
+}
+
+impl DescriptionResolver {
+    /// Creates a new instance of the resolver, relative locations are
+    /// resolved against the current directory.
+    pub fn new() -> DescriptionResolver {
+        DescriptionResolver::default()
+    }
+
+    /// Sets the directory that relative description locations are resolved
+    /// against, the cache is kept when changing the directory.
+    pub fn with_base_dir<P: Into<PathBuf>>(mut self, base_dir: P) -> DescriptionResolver {
+        self.base_dir = Some(base_dir.into());
+        self
+    }
+
+    /// Returns the path of the file the description is read from, relative
+    /// locations are resolved against the base directory.
+    pub fn path(&self, from: &Path) -> PathBuf {
+        match self.base_dir {
+            Some(ref base_dir) if from.is_relative() => base_dir.join(from),
+            _ => from.to_path_buf(),
+        }
+    }
+
+    /// Returns the final text of the description, an empty text is returned
+    /// when no description have been set.
+    pub fn resolve(&self, description: &Description) -> Result<String, GeneratorError> {
+        match description {
+            Description::None => Ok(String::new()),
+            Description::Text(text) => Ok(text.clone()),
+            Description::Location {
+                from,
+                skip_start,
+                skip_end,
+            } => {
+                let path = self.path(from);
+                let text = self.read(&path)?;
+                let lines: Vec<&str> = text.lines().collect();
+                let end = lines.len().saturating_sub(*skip_end as usize);
+                let start = (*skip_start as usize).min(end);
+                let lines = &lines[start..end];
+
+                if is_markdown(&path) {
+                    Ok(clean_markdown(lines))
+                } else {
+                    Ok(lines.join("\n"))
+                }
+            }
+        }
+    }
+
+    fn read(&self, path: &Path) -> Result<Arc<str>, GeneratorError> {
+        let error = |source| GeneratorError::DescriptionFile {
+            path: path.to_path_buf(),
+            source,
+        };
+        let modified = std::fs::metadata(path).map_err(error)?.modified().ok();
+        let mut cache = self.cache.lock().unwrap_or_else(|err| err.into_inner());
+
+        if let Some((cached, text)) = cache.get(path) {
+            if modified.is_some() && *cached == modified {
+                return Ok(text.clone());
+            }
+        }
+
+        let text: Arc<str> = std::fs::read_to_string(path).map_err(error)?.into();
+        cache.insert(path.to_path_buf(), (modified, text.clone()));
+
+        Ok(text)
+    }
+}
+
+impl PartialEq for DescriptionResolver {
+    fn eq(&self, other: &DescriptionResolver) -> bool {
+        self.base_dir == other.base_dir
+    }
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| MARKDOWN_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or_default()
+}
+
+/// Removes the front matter and HTML comments from the markdown lines, as
+/// well as any leading or trailing empty lines.
+fn clean_markdown(lines: &[&str]) -> String {
+    let mut lines = lines;
+    if lines.first().map(|line| line.trim_end()) == Some("---") {
+        if let Some(end) = lines[1..].iter().position(|line| line.trim_end() == "---") {
+            lines = &lines[end + 2..];
+        }
+    }
+
+    let mut text = lines.join("\n");
+    while let Some(start) = text.find("<!--") {
+        match text[start..].find("-->") {
+            Some(end) => text.replace_range(start..start + end + 3, ""),
+            None => break,
+        }
+    }
+
+    let cleaned: Vec<&str> = text.lines().map(str::trim_end).collect();
+    let mut result: Vec<&str> = Vec::with_capacity(cleaned.len());
+    for line in cleaned {
+        // Removed comments may leave several empty lines behind.
+        if line.is_empty() && result.last().map(|last| last.is_empty()).unwrap_or(true) {
+            continue;
+        }
+        result.push(line);
+    }
+    while result
+        .last()
+        .map(|line| line.is_empty())
+        .unwrap_or_default()
+    {
+        result.pop();
+    }
+
+    result.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn write_file(name: &str, content: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join("aer-description-resolver");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[rstest(
+        text,
+        expected,
+        case("# Title\n\nSome description\n", "# Title\n\nSome description"),
+        case(
+            "---\ntitle: Test\n---\n\n# Title\n\nSome description",
+            "# Title\n\nSome description"
+        ),
+        case(
+            "<!-- badges -->\n# Title\n\n<!--\nHidden\n-->\n\nSome <!-- inline -->description",
+            "# Title\n\nSome description"
+        ),
+        case(
+            "# Title\n\n---\n\nSome description",
+            "# Title\n\n---\n\nSome description"
+        )
+    )]
+    fn clean_markdown_should_remove_front_matter_and_comments(text: &str, expected: &str) {
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(clean_markdown(&lines), expected);
+    }
+
+    #[test]
+    fn resolve_should_skip_lines_of_text_files() {
+        let path = write_file("description.txt", "Header\n\nSome description\n\nFooter");
+        let resolver = DescriptionResolver::new().with_base_dir(path.parent().unwrap());
+
+        let actual = resolver
+            .resolve(&Description::Location {
+                from: PathBuf::from("description.txt"),
+                skip_start: 2,
+                skip_end: 2,
+            })
+            .unwrap();
+
+        assert_eq!(actual, "Some description");
+    }
+
+    #[test]
+    fn resolve_should_return_error_on_missing_file() {
+        let resolver = DescriptionResolver::new().with_base_dir("non-existing");
+
+        let actual = resolver
+            .resolve(&Description::Location {
+                from: PathBuf::from("README.md"),
+                skip_start: 0,
+                skip_end: 0,
+            })
+            .unwrap_err();
+
+        assert_eq!(actual.code(), "AER0305");
+        assert!(actual
+            .to_string()
+            .starts_with("Unable to read the description from 'non-existing"));
+    }
+
+    #[test]
+    fn resolve_should_share_cache_between_clones() {
+        let path = write_file("cached.md", "Cached description");
+        let resolver = DescriptionResolver::new();
+        let description = Description::Location {
+            from: path.clone(),
+            skip_start: 0,
+            skip_end: 0,
+        };
+
+        let first = resolver.clone().resolve(&description).unwrap();
+
+        assert_eq!(first, "Cached description");
+        assert!(resolver.cache.lock().unwrap().contains_key(&path));
+    }
+}
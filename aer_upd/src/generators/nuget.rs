@@ -0,0 +1,215 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the generator responsible for creating the nuspec file of plain
+//! NuGet packages, suitable for pushing to nuget.org.
+
+#![cfg_attr(docsrs, doc(cfg(feature = "nuget")))]
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use aer_data::prelude::nuget::*;
+use aer_data::prelude::*;
+use log::debug;
+
+use crate::generators::{escape, write_element, GeneratorError, PackageGenerator};
+
+/// The namespace used by nuspec files supported by nuget.org.
+const NUSPEC_NAMESPACE: &str = "http://schemas.microsoft.com/packaging/2013/05/nuspec.xsd";
+
+/// Generates the nuspec file of a plain NuGet package, based on the
+/// `[metadata.nuget]` section of the package data. Unlike the Chocolatey
+/// nuspec, no Chocolatey specific elements are written, and the case of the
+/// identifier is always kept.
+///
+/// The package is created in a directory named after the identifier of the
+/// package, with the following layout:
+///
+/// ```text
+/// <id>/
+///   <id>.nuspec
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NugetGenerator;
+
+impl NugetGenerator {
+    /// Creates a new instance of the generator.
+    pub fn new() -> NugetGenerator {
+        NugetGenerator
+    }
+
+    /// Creates the content of the nuspec file for the specified package.
+    pub fn to_nuspec(&self, data: &PackageData) -> Result<String, GeneratorError> {
+        let metadata = data.metadata();
+        let nuget = metadata.nuget();
+
+        let authors = if nuget.authors().is_empty() {
+            metadata.maintainers()
+        } else {
+            nuget.authors()
+        };
+        if authors.is_empty() {
+            return Err(GeneratorError::MissingData("authors".into()));
+        }
+
+        let description = nuget.description.as_deref().unwrap_or(&metadata.summary);
+        if description.trim().is_empty() {
+            return Err(GeneratorError::MissingData("description".into()));
+        }
+
+        let mut nuspec = String::new();
+        let _ = writeln!(nuspec, r#"<?xml version="1.0" encoding="utf-8"?>"#);
+        let _ = writeln!(nuspec, r#"<package xmlns="{}">"#, NUSPEC_NAMESPACE);
+        let _ = writeln!(nuspec, "  <metadata>");
+        write_element(&mut nuspec, "id", metadata.id());
+        write_element(&mut nuspec, "version", &nuget.version.to_string());
+        write_element(&mut nuspec, "authors", &authors.join(", "));
+        match metadata.license() {
+            LicenseType::None => return Err(GeneratorError::MissingData("license".into())),
+            LicenseType::Location(url) => write_element(&mut nuspec, "licenseUrl", url.as_str()),
+            LicenseType::Expression(expression)
+            | LicenseType::ExpressionAndLocation { expression, .. } => {
+                let _ = writeln!(
+                    nuspec,
+                    r#"    <license type="expression">{}</license>"#,
+                    escape(expression)
+                );
+            }
+        }
+        write_element(&mut nuspec, "projectUrl", metadata.project_url().as_str());
+        write_element(&mut nuspec, "description", description);
+        if let Some(ref release_notes) = nuget.release_notes {
+            write_element(&mut nuspec, "releaseNotes", release_notes);
+        }
+        if !nuget.tags().is_empty() {
+            write_element(&mut nuspec, "tags", &nuget.tags().join(" "));
+        }
+        if nuget.package_type != NugetPackageType::Dependency {
+            let _ = writeln!(nuspec, "    <packageTypes>");
+            let _ = writeln!(
+                nuspec,
+                r#"      <packageType name="{}" />"#,
+                nuget.package_type
+            );
+            let _ = writeln!(nuspec, "    </packageTypes>");
+        }
+        let _ = writeln!(nuspec, "  </metadata>");
+        let _ = writeln!(nuspec, "</package>");
+
+        Ok(nuspec)
+    }
+}
+
+impl PackageGenerator for NugetGenerator {
+    fn generate(&self, data: &PackageData, output_dir: &Path) -> Result<PathBuf, GeneratorError> {
+        let nuspec = self.to_nuspec(data)?;
+        let id = data.metadata().id();
+        let package_dir = output_dir.join(id);
+
+        std::fs::create_dir_all(&package_dir)?;
+        let nuspec_path = package_dir.join(format!("{}.nuspec", id));
+        std::fs::write(&nuspec_path, nuspec)?;
+        debug!("Created nuspec file at '{}'", nuspec_path.display());
+
+        Ok(package_dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_data::testing::PackageDataFixture;
+    use static_assertions::assert_impl_all;
+
+    use super::*;
+
+    assert_impl_all!(NugetGenerator: Send, Sync);
+
+    fn package() -> PackageData {
+        PackageDataFixture::new("Cake.Tool")
+            .with_project_url("https://cakebuild.net/")
+            .with_license(LicenseType::Expression("MIT".into()))
+            .with_summary("Cake (C# Make) is a build automation system")
+            .with_nuget(|nuget| {
+                nuget.version = Versions::parse("1.1.0").unwrap();
+                nuget.package_type = NugetPackageType::DotnetTool;
+                nuget.set_tags(&["cake", "build"]);
+            })
+            .build()
+    }
+
+    #[test]
+    fn to_nuspec_should_create_expected_nuspec() {
+        let actual = NugetGenerator::new().to_nuspec(&package()).unwrap();
+
+        assert_eq!(
+            actual,
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<package xmlns="http://schemas.microsoft.com/packaging/2013/05/nuspec.xsd">
+  <metadata>
+    <id>Cake.Tool</id>
+    <version>1.1.0</version>
+    <authors>AdmiringWorm</authors>
+    <license type="expression">MIT</license>
+    <projectUrl>https://cakebuild.net/</projectUrl>
+    <description>Cake (C# Make) is a build automation system</description>
+    <tags>cake build</tags>
+    <packageTypes>
+      <packageType name="DotnetTool" />
+    </packageTypes>
+  </metadata>
+</package>
+"#
+        );
+    }
+
+    #[test]
+    fn to_nuspec_should_use_authors_and_description_of_section() {
+        let mut data = package();
+        let mut nuget = data.metadata().nuget().into_owned();
+        nuget.set_authors(&["Patrik Svensson", "Mattias Karlsson"]);
+        nuget.description = Some("The Cake .NET tool".into());
+        data.metadata_mut().set_nuget(nuget);
+
+        let actual = NugetGenerator::new().to_nuspec(&data).unwrap();
+
+        assert!(actual.contains("<authors>Patrik Svensson, Mattias Karlsson</authors>"));
+        assert!(actual.contains("<description>The Cake .NET tool</description>"));
+    }
+
+    #[test]
+    fn to_nuspec_should_return_error_when_license_is_missing() {
+        let mut data = package();
+        data.metadata_mut().set_license(LicenseType::None);
+
+        let actual = NugetGenerator::new().to_nuspec(&data).unwrap_err();
+
+        assert!(matches!(actual, GeneratorError::MissingData(name) if name == "license"));
+    }
+
+    #[test]
+    fn generate_should_create_package_layout() {
+        let dir = std::env::temp_dir().join("aer-nuget-generate-test");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let actual = NugetGenerator::new().generate(&package(), &dir).unwrap();
+
+        assert_eq!(actual, dir.join("Cake.Tool"));
+        assert!(actual.join("Cake.Tool.nuspec").is_file());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(feature = "toml_data")]
+    #[test]
+    fn to_nuspec_should_match_golden_files() {
+        use crate::golden::{self, Golden};
+
+        for case in golden::cases("nuget") {
+            let data = crate::parsers::read_file(&case.join("package.aer.toml")).unwrap();
+
+            let actual = NugetGenerator::new().to_nuspec(&data).unwrap();
+
+            Golden::new(case.join("expected.nuspec")).assert(&actual);
+        }
+    }
+}
@@ -0,0 +1,232 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the templating used for the scripts of a package, where the urls
+//! and checksums change between every version of the software.
+//!
+//! Templates are normal script files containing tokens in the form
+//! `{{name}}`, which are replaced with the value of the same name when the
+//! template is rendered. A literal `{{` is written as `{{{{`.
+//!
+//! ### Examples
+//!
+//! ```
+//! use aer_upd::generators::templates::{render, TemplateValues};
+//!
+//! let values = TemplateValues::new()
+//!     .with_value("url64", "https://example.org/app-1.0.0-x64.exe")
+//!     .with_value("checksum64", "ABCDEF");
+//!
+//! let actual = render(
+//!     "$url64 = '{{url64}}'\n$checksum64 = '{{ checksum64 }}'",
+//!     &values,
+//! )
+//! .unwrap();
+//!
+//! assert_eq!(
+//!     actual,
+//!     "$url64 = 'https://example.org/app-1.0.0-x64.exe'\n$checksum64 = 'ABCDEF'"
+//! );
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use log::debug;
+
+use crate::generators::GeneratorError;
+
+/// The token used to start a replacement in a template.
+const TOKEN_START: &str = "{{";
+
+/// The token used to end a replacement in a template.
+const TOKEN_END: &str = "}}";
+
+/// The values that are available to the tokens of a template.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TemplateValues {
+    values: BTreeMap<String, String>,
+}
+
+impl TemplateValues {
+    /// Creates a new instance without any values.
+    pub fn new() -> TemplateValues {
+        TemplateValues::default()
+    }
+
+    /// Sets the value replacing the token with the specified name.
+    pub fn with_value<V: Into<String>>(mut self, name: &str, value: V) -> TemplateValues {
+        self.set(name, value);
+        self
+    }
+
+    /// Sets the value replacing the token with the specified name, overriding
+    /// any value previously set.
+    pub fn set<V: Into<String>>(&mut self, name: &str, value: V) {
+        self.values.insert(name.into(), value.into());
+    }
+
+    /// Returns the value replacing the token with the specified name.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(|value| value.as_str())
+    }
+
+    /// Returns the names of all available tokens, sorted by name.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.values.keys().map(|name| name.as_str())
+    }
+}
+
+/// Replaces all tokens in the template with the available values.
+///
+/// ## Errors
+///
+/// An error is returned when the template contains a token that do not have a
+/// value, or a token that have not been closed.
+pub fn render(template: &str, values: &TemplateValues) -> Result<String, GeneratorError> {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    let mut line = 1;
+
+    while let Some(start) = rest.find(TOKEN_START) {
+        let (text, token) = rest.split_at(start);
+        output.push_str(text);
+        line += text.matches('\n').count();
+
+        let token = &token[TOKEN_START.len()..];
+        if let Some(escaped) = token.strip_prefix(TOKEN_START) {
+            output.push_str(TOKEN_START);
+            rest = escaped;
+            continue;
+        }
+
+        let end = token
+            .find(TOKEN_END)
+            .ok_or_else(|| GeneratorError::Template {
+                line,
+                message: "The token have not been closed".into(),
+            })?;
+        let name = token[..end].trim();
+        let value = values.get(name).ok_or_else(|| GeneratorError::Template {
+            line,
+            message: format!(
+                "No value is available for the token '{}', the available tokens are: {}",
+                name,
+                values.names().collect::<Vec<_>>().join(", ")
+            ),
+        })?;
+        output.push_str(value);
+        line += token[..end].matches('\n').count();
+        rest = &token[end + TOKEN_END.len()..];
+    }
+
+    output.push_str(rest);
+
+    Ok(output)
+}
+
+/// Renders the template file at the specified source path, and writes the
+/// result to the target path. The directory of the target is created when it
+/// do not exist.
+pub fn render_file(
+    source: &Path,
+    target: &Path,
+    values: &TemplateValues,
+) -> Result<(), GeneratorError> {
+    let template = std::fs::read_to_string(source)?;
+    let output = render(&template, values).map_err(|err| match err {
+        GeneratorError::Template { line, message } => GeneratorError::Template {
+            line,
+            message: format!("{} ('{}')", message, source.display()),
+        },
+        err => err,
+    })?;
+
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(target, output)?;
+    debug!(
+        "Rendered the template '{}' to '{}'",
+        source.display(),
+        target.display()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn values() -> TemplateValues {
+        TemplateValues::new()
+            .with_value("version", "1.0.0")
+            .with_value("url32", "https://example.org/app-1.0.0.exe")
+            .with_value("checksum32", "ABCDEF")
+    }
+
+    #[rstest(
+        template,
+        expected,
+        case("No tokens", "No tokens"),
+        case("Version {{version}}", "Version 1.0.0"),
+        case(
+            "{{ url32 }}|{{checksum32}}",
+            "https://example.org/app-1.0.0.exe|ABCDEF"
+        ),
+        case(
+            "$data = @{{{{ Version = '{{version}}' }",
+            "$data = @{{ Version = '1.0.0' }"
+        ),
+        case("}} is not a token", "}} is not a token")
+    )]
+    fn render_should_replace_tokens(template: &str, expected: &str) {
+        let actual = render(template, &values()).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest(
+        template,
+        expected_line,
+        case("Line 1\nLine 2 {{url64}}", 2),
+        case("Line 1\n{{version}}\n\nLine 4 {{version", 4)
+    )]
+    fn render_should_return_error_on_invalid_tokens(template: &str, expected_line: usize) {
+        let actual = render(template, &values()).unwrap_err();
+
+        assert!(
+            matches!(actual, GeneratorError::Template { line, .. } if line == expected_line),
+            "{:?}",
+            actual
+        );
+    }
+
+    #[test]
+    fn render_file_should_write_rendered_template() {
+        let dir = std::env::temp_dir().join("aer-render-template-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("chocolateyInstall.ps1");
+        std::fs::write(
+            &source,
+            "$url = '{{url32}}'\r\n$checksum = '{{checksum32}}'\r\n",
+        )
+        .unwrap();
+        let target = dir
+            .join("package")
+            .join("tools")
+            .join("chocolateyInstall.ps1");
+
+        render_file(&source, &target, &values()).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&target).unwrap(),
+            "$url = 'https://example.org/app-1.0.0.exe'\r\n$checksum = 'ABCDEF'\r\n"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
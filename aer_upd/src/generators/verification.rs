@@ -0,0 +1,213 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the generator responsible for creating the `VERIFICATION.txt`
+//! file of Chocolatey packages that embeds the software, allowing the
+//! moderators and users to verify that the embedded files are the same files
+//! that are distributed upstream.
+//!
+//! The file is created in the `legal` directory of the package, next to the
+//! embedded license of the software.
+
+#![cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use aer_data::prelude::*;
+use log::debug;
+
+use crate::generators::{GeneratorError, PackageGenerator};
+
+/// The name of the directory holding the legal files of the package.
+pub const LEGAL_DIR: &str = "legal";
+
+/// The name of the file the embedded license is stored as.
+pub const LICENSE_FILE: &str = "LICENSE.txt";
+
+/// The name of the file the verification instructions are stored as.
+pub const VERIFICATION_FILE: &str = "VERIFICATION.txt";
+
+/// A single file that is embedded in the package.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddedFile {
+    /// The name of the file in the package.
+    pub name: String,
+    /// The url the file was downloaded from.
+    pub url: Url,
+    /// The checksum of the file.
+    pub checksum: String,
+    /// The algorithm used when generating the checksum (*ie: `sha256`*).
+    pub checksum_type: String,
+}
+
+/// Generates the `VERIFICATION.txt` file of a Chocolatey package that embeds
+/// the software.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct VerificationGenerator {
+    files: Vec<EmbeddedFile>,
+    license_url: Option<Url>,
+}
+
+impl VerificationGenerator {
+    /// Creates a new instance of the generator without any files.
+    pub fn new() -> VerificationGenerator {
+        VerificationGenerator::default()
+    }
+
+    /// Adds a file that is embedded in the package, the files are normally
+    /// the files that was downloaded during the update.
+    pub fn with_file(mut self, file: EmbeddedFile) -> VerificationGenerator {
+        self.files.push(file);
+        self
+    }
+
+    /// Sets the url the embedded license was downloaded from.
+    pub fn with_license_url(mut self, url: Url) -> VerificationGenerator {
+        self.license_url = Some(url);
+        self
+    }
+
+    /// Creates the content of the verification file for the specified
+    /// package.
+    pub fn to_verification(&self, data: &PackageData) -> Result<String, GeneratorError> {
+        if self.files.is_empty() {
+            return Err(GeneratorError::MissingData("embedded files".into()));
+        }
+
+        let mut text = String::new();
+        let _ = writeln!(text, "VERIFICATION");
+        let _ = writeln!(
+            text,
+            "Verification is intended to assist the Chocolatey moderators and community"
+        );
+        let _ = writeln!(
+            text,
+            "in verifying that this package's contents are trustworthy."
+        );
+        let _ = writeln!(text);
+        let _ = writeln!(
+            text,
+            "The embedded software have been downloaded from the listed download"
+        );
+        let _ = writeln!(text, "locations on <{}>", data.metadata().project_url());
+        let _ = writeln!(text, "and can be verified by doing the following:");
+        let _ = writeln!(text);
+        let _ = writeln!(text, "1. Download the following:");
+        for file in &self.files {
+            let _ = writeln!(text, "   - {}", file.url);
+        }
+        let _ = writeln!(
+            text,
+            "2. Get the checksum using one of the following methods:"
+        );
+        let _ = writeln!(text, "   - Using powershell function 'Get-FileHash'");
+        let _ = writeln!(text, "   - Use chocolatey utility 'checksum.exe'");
+        let _ = writeln!(text, "3. The checksums should match the following:");
+        for file in &self.files {
+            let _ = writeln!(text);
+            let _ = writeln!(text, "   file:          {}", file.name);
+            let _ = writeln!(text, "   checksum type: {}", file.checksum_type);
+            let _ = writeln!(text, "   checksum:      {}", file.checksum);
+        }
+
+        if let Some(ref url) = self.license_url {
+            let _ = writeln!(text);
+            let _ = writeln!(
+                text,
+                "The file '{}' have been obtained from <{}>",
+                LICENSE_FILE, url
+            );
+        }
+
+        Ok(text)
+    }
+}
+
+impl PackageGenerator for VerificationGenerator {
+    /// Creates the verification file in the `legal` directory of the package
+    /// directory, and returns the `legal` directory.
+    fn generate(&self, data: &PackageData, package_dir: &Path) -> Result<PathBuf, GeneratorError> {
+        let text = self.to_verification(data)?;
+        let legal_dir = package_dir.join(LEGAL_DIR);
+
+        std::fs::create_dir_all(&legal_dir)?;
+        let path = legal_dir.join(VERIFICATION_FILE);
+        std::fs::write(&path, text)?;
+        debug!("Created verification file at '{}'", path.display());
+
+        Ok(legal_dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_data::testing::PackageDataFixture;
+    use static_assertions::assert_impl_all;
+
+    use super::*;
+
+    assert_impl_all!(VerificationGenerator: Send, Sync);
+
+    fn embedded_file(name: &str) -> EmbeddedFile {
+        EmbeddedFile {
+            name: name.into(),
+            url: Url::parse(&format!("https://example.org/{}", name)).unwrap(),
+            checksum: "ABCDEF".into(),
+            checksum_type: "sha256".into(),
+        }
+    }
+
+    fn package() -> PackageData {
+        PackageDataFixture::new("test-package")
+            .with_project_url("https://example.org")
+            .build()
+    }
+
+    #[test]
+    fn to_verification_should_list_files_and_license() {
+        let actual = VerificationGenerator::new()
+            .with_file(embedded_file("app-x86.exe"))
+            .with_file(embedded_file("app-x64.exe"))
+            .with_license_url(Url::parse("https://example.org/LICENSE").unwrap())
+            .to_verification(&package())
+            .unwrap();
+
+        assert!(actual.contains("locations on <https://example.org/>"));
+        assert!(actual.contains("   - https://example.org/app-x86.exe\n"));
+        assert!(actual.contains("   - https://example.org/app-x64.exe\n"));
+        assert!(actual.contains(
+            "   file:          app-x64.exe\n   checksum type: sha256\n   checksum:      ABCDEF\n"
+        ));
+        assert!(actual.ends_with(
+            "The file 'LICENSE.txt' have been obtained from <https://example.org/LICENSE>\n"
+        ));
+    }
+
+    #[test]
+    fn to_verification_should_return_error_without_files() {
+        let actual = VerificationGenerator::new()
+            .to_verification(&package())
+            .unwrap_err();
+
+        assert_eq!(
+            actual.to_string(),
+            "The required value 'embedded files' have not been set!"
+        );
+    }
+
+    #[test]
+    fn generate_should_create_file_in_legal_directory() {
+        let dir = std::env::temp_dir().join("aer-verification-generate-test");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let actual = VerificationGenerator::new()
+            .with_file(embedded_file("app.exe"))
+            .generate(&package(), &dir)
+            .unwrap();
+
+        assert_eq!(actual, dir.join("legal"));
+        assert!(actual.join("VERIFICATION.txt").is_file());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
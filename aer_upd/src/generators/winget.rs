@@ -0,0 +1,420 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the generator responsible for creating the multi-file manifests
+//! used by the Windows Package Manager (*winget*).
+
+#![cfg_attr(docsrs, doc(cfg(feature = "winget")))]
+
+use std::fmt::{self, Display, Write};
+use std::path::{Path, PathBuf};
+
+use aer_data::prelude::*;
+use log::debug;
+
+use crate::generators::{GeneratorError, PackageGenerator};
+
+/// The version of the manifest schema the manifests are created for.
+pub const MANIFEST_VERSION: &str = "1.4.0";
+
+/// The locale used for the default locale manifest.
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+/// The architecture an installer is created for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WingetArchitecture {
+    /// The installer is created for 32bit systems.
+    X86,
+    /// The installer is created for 64bit systems.
+    X64,
+    /// The installer is created for 64bit ARM systems.
+    Arm64,
+    /// The installer can be used on all architectures.
+    Neutral,
+}
+
+impl Display for WingetArchitecture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WingetArchitecture::X86 => f.write_str("x86"),
+            WingetArchitecture::X64 => f.write_str("x64"),
+            WingetArchitecture::Arm64 => f.write_str("arm64"),
+            WingetArchitecture::Neutral => f.write_str("neutral"),
+        }
+    }
+}
+
+impl From<Architecture> for WingetArchitecture {
+    fn from(arch: Architecture) -> Self {
+        match arch {
+            Architecture::X86 => WingetArchitecture::X86,
+            Architecture::X64 => WingetArchitecture::X64,
+            Architecture::Arm64 => WingetArchitecture::Arm64,
+            Architecture::Universal => WingetArchitecture::Neutral,
+        }
+    }
+}
+
+/// A single installer of the software.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WingetInstaller {
+    /// The architecture the installer is created for.
+    pub architecture: WingetArchitecture,
+    /// The url the installer is downloaded from.
+    pub url: Url,
+    /// The SHA-256 checksum of the installer.
+    pub sha256: String,
+}
+
+/// The content of the three files a winget manifest is made of.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WingetManifests {
+    /// The version manifest, referencing the other manifests.
+    pub version: String,
+    /// The installer manifest, containing the installers of the software.
+    pub installer: String,
+    /// The default locale manifest, containing the descriptive values of the
+    /// software.
+    pub locale: String,
+}
+
+/// Generates the version, installer and default locale manifests used by
+/// winget, based on the `[metadata.winget]` section of the package data.
+///
+/// The manifests are created in a directory named after the package
+/// identifier and version, with the following layout:
+///
+/// ```text
+/// <identifier>/<version>/
+///   <identifier>.yaml
+///   <identifier>.installer.yaml
+///   <identifier>.locale.en-US.yaml
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct WingetGenerator {
+    installers: Vec<WingetInstaller>,
+}
+
+impl WingetGenerator {
+    /// Creates a new instance of the generator without any installers.
+    pub fn new() -> WingetGenerator {
+        WingetGenerator::default()
+    }
+
+    /// Adds an installer of the software, the installers are normally the
+    /// files that was downloaded during the update.
+    pub fn with_installer(mut self, installer: WingetInstaller) -> WingetGenerator {
+        self.installers.push(installer);
+        self
+    }
+
+    /// Creates the content of the manifests for the specified package.
+    pub fn to_manifests(&self, data: &PackageData) -> Result<WingetManifests, GeneratorError> {
+        let metadata = data.metadata();
+        let winget = metadata.winget();
+
+        let identifier = required("package_identifier", winget.package_identifier())?;
+        let publisher = required("publisher", winget.publisher())?;
+        let short_description = required(
+            "short_description",
+            winget
+                .short_description
+                .as_deref()
+                .unwrap_or(&metadata.summary),
+        )?;
+        let license = match metadata.license() {
+            LicenseType::None => return Err(GeneratorError::MissingData("license".into())),
+            LicenseType::Location(url) => url.as_str(),
+            LicenseType::Expression(expression)
+            | LicenseType::ExpressionAndLocation { expression, .. } => expression.as_str(),
+        };
+        if self.installers.is_empty() {
+            return Err(GeneratorError::MissingData("installers".into()));
+        }
+        let version = winget.version.to_string();
+
+        let mut version_manifest = header(identifier, &version);
+        write_value(&mut version_manifest, "DefaultLocale", DEFAULT_LOCALE);
+        footer(&mut version_manifest, "version");
+
+        let mut installer = header(identifier, &version);
+        write_value(
+            &mut installer,
+            "InstallerType",
+            &winget.installer_type.to_string(),
+        );
+        if let Some(ref silent) = winget.silent_switches {
+            let _ = writeln!(installer, "InstallerSwitches:");
+            let _ = writeln!(installer, "  Silent: {}", quote(silent));
+        }
+        let _ = writeln!(installer, "Installers:");
+        for item in &self.installers {
+            let _ = writeln!(installer, "- Architecture: {}", item.architecture);
+            let _ = writeln!(installer, "  InstallerUrl: {}", quote(item.url.as_str()));
+            let _ = writeln!(
+                installer,
+                "  InstallerSha256: {}",
+                item.sha256.to_uppercase()
+            );
+        }
+        footer(&mut installer, "installer");
+
+        let mut locale = header(identifier, &version);
+        write_value(&mut locale, "PackageLocale", DEFAULT_LOCALE);
+        write_value(&mut locale, "Publisher", publisher);
+        write_value(
+            &mut locale,
+            "PackageName",
+            winget.package_name.as_deref().unwrap_or(metadata.id()),
+        );
+        write_value(&mut locale, "PackageUrl", metadata.project_url().as_str());
+        write_value(&mut locale, "License", license);
+        if let Some(url) = metadata.license().license_url() {
+            write_value(&mut locale, "LicenseUrl", url);
+        }
+        write_value(&mut locale, "ShortDescription", short_description);
+        if let Some(ref moniker) = winget.moniker {
+            write_value(&mut locale, "Moniker", moniker);
+        }
+        if !winget.tags().is_empty() {
+            let _ = writeln!(locale, "Tags:");
+            for tag in winget.tags() {
+                let _ = writeln!(locale, "- {}", quote(tag));
+            }
+        }
+        footer(&mut locale, "defaultLocale");
+
+        Ok(WingetManifests {
+            version: version_manifest,
+            installer,
+            locale,
+        })
+    }
+}
+
+impl PackageGenerator for WingetGenerator {
+    fn generate(&self, data: &PackageData, output_dir: &Path) -> Result<PathBuf, GeneratorError> {
+        let manifests = self.to_manifests(data)?;
+        let winget = data.metadata().winget();
+        let identifier = winget.package_identifier();
+        let manifest_dir = output_dir.join(identifier).join(winget.version.to_string());
+
+        std::fs::create_dir_all(&manifest_dir)?;
+        for (name, content) in &[
+            (format!("{}.yaml", identifier), &manifests.version),
+            (
+                format!("{}.installer.yaml", identifier),
+                &manifests.installer,
+            ),
+            (
+                format!("{}.locale.{}.yaml", identifier, DEFAULT_LOCALE),
+                &manifests.locale,
+            ),
+        ] {
+            std::fs::write(manifest_dir.join(name), content)?;
+        }
+        debug!("Created winget manifests in '{}'", manifest_dir.display());
+
+        Ok(manifest_dir)
+    }
+}
+
+fn required<'a>(name: &str, value: &'a str) -> Result<&'a str, GeneratorError> {
+    if value.trim().is_empty() {
+        Err(GeneratorError::MissingData(name.into()))
+    } else {
+        Ok(value)
+    }
+}
+
+fn header(identifier: &str, version: &str) -> String {
+    let mut manifest = String::new();
+    let _ = writeln!(
+        manifest,
+        "# yaml-language-server: $schema=https://aka.ms/winget-manifest.schema.json"
+    );
+    write_value(&mut manifest, "PackageIdentifier", identifier);
+    write_value(&mut manifest, "PackageVersion", version);
+
+    manifest
+}
+
+fn footer(manifest: &mut String, manifest_type: &str) {
+    write_value(manifest, "ManifestType", manifest_type);
+    write_value(manifest, "ManifestVersion", MANIFEST_VERSION);
+}
+
+fn write_value(manifest: &mut String, name: &str, value: &str) {
+    let _ = writeln!(manifest, "{}: {}", name, quote(value));
+}
+
+/// Quotes the value when it would otherwise be interpreted as something other
+/// than a plain string by YAML parsers.
+fn quote(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value.trim() != value
+        || value.contains(": ")
+        || value.contains(" #")
+        || value.starts_with(|c: char| "!&*[]{}|>'\"%@`#,?:-".contains(c))
+        || matches!(
+            value.to_lowercase().as_str(),
+            "true" | "false" | "yes" | "no" | "on" | "off" | "null" | "~"
+        )
+        || value.parse::<f64>().is_ok();
+
+    if needs_quotes {
+        format!("'{}'", value.replace('\'', "''"))
+    } else {
+        value.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_data::prelude::winget::*;
+    use aer_data::testing::PackageDataFixture;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn installer() -> WingetInstaller {
+        WingetInstaller {
+            architecture: WingetArchitecture::X64,
+            url: Url::parse("https://example.org/app-1.0.0-x64.exe").unwrap(),
+            sha256: "abcdef".into(),
+        }
+    }
+
+    fn package() -> PackageData {
+        PackageDataFixture::new("app")
+            .with_project_url("https://example.org/")
+            .with_license(LicenseType::Expression("MIT".into()))
+            .with_summary("Application used when testing")
+            .with_winget(|winget| {
+                *winget = WingetMetadata::new("Example.App", "Example");
+                winget.version = Versions::parse("1.0.0").unwrap();
+            })
+            .build()
+    }
+
+    #[test]
+    fn to_manifests_should_create_version_manifest() {
+        let actual = WingetGenerator::new()
+            .with_installer(installer())
+            .to_manifests(&package())
+            .unwrap();
+
+        assert_eq!(
+            actual.version,
+            "# yaml-language-server: $schema=https://aka.ms/winget-manifest.schema.json
+PackageIdentifier: Example.App
+PackageVersion: 1.0.0
+DefaultLocale: en-US
+ManifestType: version
+ManifestVersion: 1.4.0
+"
+        );
+        assert!(actual.installer.contains("  InstallerSha256: ABCDEF\n"));
+    }
+
+    #[rstest(
+        field,
+        modify,
+        case("package_identifier", |data: &mut PackageData| {
+            data.metadata_mut().set_winget(WingetMetadata::new("", "Example"))
+        }),
+        case("short_description", |data: &mut PackageData| {
+            data.metadata_mut().summary = String::new()
+        }),
+        case("license", |data: &mut PackageData| {
+            data.metadata_mut().set_license(LicenseType::None)
+        })
+    )]
+    fn to_manifests_should_return_error_when_required_value_is_missing(
+        field: &str,
+        modify: fn(&mut PackageData),
+    ) {
+        let mut data = package();
+        modify(&mut data);
+
+        let actual = WingetGenerator::new()
+            .with_installer(installer())
+            .to_manifests(&data)
+            .unwrap_err();
+
+        assert!(matches!(actual, GeneratorError::MissingData(name) if name == field));
+    }
+
+    #[test]
+    fn to_manifests_should_return_error_without_installers() {
+        let actual = WingetGenerator::new().to_manifests(&package()).unwrap_err();
+
+        assert!(matches!(actual, GeneratorError::MissingData(name) if name == "installers"));
+    }
+
+    #[rstest(
+        value,
+        expected,
+        case("Example", "Example"),
+        case("1.0", "'1.0'"),
+        case("yes", "'yes'"),
+        case("It's: quoted", "'It''s: quoted'"),
+        case("", "''")
+    )]
+    fn quote_should_quote_ambiguous_values(value: &str, expected: &str) {
+        assert_eq!(quote(value), expected);
+    }
+
+    #[rstest(
+        arch,
+        expected,
+        case(Architecture::X86, "x86"),
+        case(Architecture::X64, "x64"),
+        case(Architecture::Arm64, "arm64"),
+        case(Architecture::Universal, "neutral")
+    )]
+    fn from_should_convert_architecture(arch: Architecture, expected: &str) {
+        assert_eq!(WingetArchitecture::from(arch).to_string(), expected);
+    }
+
+    #[test]
+    fn generate_should_create_manifest_layout() {
+        let dir = std::env::temp_dir().join("aer-winget-generate-test");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let actual = WingetGenerator::new()
+            .with_installer(installer())
+            .generate(&package(), &dir)
+            .unwrap();
+
+        assert_eq!(actual, dir.join("Example.App").join("1.0.0"));
+        assert!(actual.join("Example.App.yaml").is_file());
+        assert!(actual.join("Example.App.installer.yaml").is_file());
+        assert!(actual.join("Example.App.locale.en-US.yaml").is_file());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(feature = "toml_data")]
+    #[test]
+    fn to_manifests_should_match_golden_files() {
+        use crate::golden::{self, Golden};
+
+        for case in golden::cases("winget") {
+            let data = crate::parsers::read_file(&case.join("package.aer.toml")).unwrap();
+
+            let actual = WingetGenerator::new()
+                .with_installer(WingetInstaller {
+                    architecture: WingetArchitecture::X86,
+                    url: Url::parse("https://example.org/app-1.0.0.exe").unwrap(),
+                    sha256: "0123456789abcdef".into(),
+                })
+                .with_installer(installer())
+                .to_manifests(&data)
+                .unwrap();
+
+            Golden::new(case.join("expected.yaml")).assert(&actual.version);
+            Golden::new(case.join("expected.installer.yaml")).assert(&actual.installer);
+            Golden::new(case.join("expected.locale.en-US.yaml")).assert(&actual.locale);
+        }
+    }
+}
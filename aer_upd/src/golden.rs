@@ -0,0 +1,126 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the golden file framework used when testing the output of the
+//! generators. The expected output of every generator is committed next to the
+//! input it was generated from, which ensures that changes to the output
+//! format are reviewed explicitly.
+//!
+//! Every case is a directory below `test-data/golden/<generator>/`, containing
+//! the input files and the expected output of the generator. The expected
+//! output can be regenerated by running the tests with the `UPDATE_GOLDEN`
+//! environment variable set to `1`.
+
+use std::path::{Path, PathBuf};
+
+/// The environment variable used to regenerate the expected output files.
+pub(crate) const UPDATE_ENV: &str = "UPDATE_GOLDEN";
+
+/// The directory containing the cases of all generators.
+const GOLDEN_DIR: &str = "test-data/golden";
+
+/// A hook that is run on both the expected and the actual output before they
+/// are compared, used to remove the parts of the output that are expected to
+/// differ between runs (*ie: the current user*).
+pub(crate) type Normalizer = fn(&str) -> String;
+
+/// A single file containing the expected output of a generator.
+pub(crate) struct Golden {
+    path: PathBuf,
+    normalizers: Vec<Normalizer>,
+}
+
+impl Golden {
+    /// Creates a new golden file at the specified path, which is normalized
+    /// to use `\n` as line endings.
+    pub(crate) fn new<P: Into<PathBuf>>(path: P) -> Golden {
+        Golden {
+            path: path.into(),
+            normalizers: vec![normalize_line_endings],
+        }
+    }
+
+    /// Adds a hook that normalizes the output before it is compared.
+    pub(crate) fn with_normalizer(mut self, normalizer: Normalizer) -> Golden {
+        self.normalizers.push(normalizer);
+        self
+    }
+
+    /// Asserts that the actual output is equal to the content of the golden
+    /// file, or replaces the content of the golden file with the actual output
+    /// when the `UPDATE_GOLDEN` environment variable is set.
+    pub(crate) fn assert(&self, actual: &str) {
+        let actual = self.normalize(actual);
+
+        if should_update() {
+            std::fs::write(&self.path, &actual).unwrap();
+            return;
+        }
+
+        let expected = std::fs::read_to_string(&self.path).unwrap_or_else(|err| {
+            panic!(
+                "Unable to read the golden file '{}': {}. Run the tests with {}=1 to create it.",
+                self.path.display(),
+                err,
+                UPDATE_ENV
+            )
+        });
+
+        assert_eq!(
+            actual,
+            self.normalize(&expected),
+            "The output do not match the golden file '{}'. Run the tests with {}=1 if the change \
+             is expected.",
+            self.path.display(),
+            UPDATE_ENV
+        );
+    }
+
+    fn normalize(&self, text: &str) -> String {
+        self.normalizers
+            .iter()
+            .fold(text.to_owned(), |text, normalizer| normalizer(&text))
+    }
+}
+
+/// Returns the directories of all cases for the specified generator, sorted
+/// by name.
+pub(crate) fn cases(generator: &str) -> Vec<PathBuf> {
+    let dir = Path::new(GOLDEN_DIR).join(generator);
+    let mut cases: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap_or_else(|err| panic!("Unable to read '{}': {}", dir.display(), err))
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    cases.sort();
+
+    cases
+}
+
+/// Normalizes all line endings to `\n`.
+pub(crate) fn normalize_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n")
+}
+
+fn should_update() -> bool {
+    matches!(std::env::var(UPDATE_ENV).as_deref(), Ok("1") | Ok("true"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assert_should_normalize_expected_and_actual_output() {
+        let dir = std::env::temp_dir().join("aer-golden-normalize-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("expected.txt");
+        std::fs::write(&path, "Line 1\r\nLine 2\r\nUser: someone\r\n").unwrap();
+
+        Golden::new(&path)
+            .with_normalizer(|text| text.replace("someone", "<user>"))
+            .assert("Line 1\nLine 2\nUser: <user>\n");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
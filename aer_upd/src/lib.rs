@@ -13,14 +13,30 @@
 )]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+pub mod context;
+pub mod features;
+pub mod generators;
+#[cfg(test)]
+pub(crate) mod golden;
+pub mod observer;
 pub mod parsers;
 pub mod runners;
 
 pub mod data {
     pub use aer_data::prelude::*;
+    #[cfg(feature = "testing")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+    pub use aer_data::testing;
 }
 
 pub mod web {
-    pub use aer_web::response::ResponseType;
-    pub use aer_web::{errors, LinkElement, LinkType, WebRequest, WebResponse};
+    #[cfg(feature = "render")]
+    pub use aer_web::render;
+    pub use aer_web::response::{HtmlResponse, ResponseType};
+    pub use aer_web::{
+        auth, budget, checksum, crawl, delta, errors, fixtures, http_log, json_path, platform,
+        proxy, rate_limit, retry, selector, sources, virustotal, xml, LinkCheck, LinkElement,
+        LinkSource, LinkType, LinkTypeMap, NextPage, Pagination, WebRequest, WebRequestBuilder,
+        WebResponse,
+    };
 }
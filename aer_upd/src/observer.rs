@@ -0,0 +1,80 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the hooks that applications embedding the updater can implement
+//! to follow the update process, for instance to drive their own progress
+//! user interface.
+
+use std::fmt::Display;
+use std::path::Path;
+
+/// The different stages a package goes through during the update process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    /// The package data is being loaded from the data file.
+    LoadData,
+    /// The upstream location is parsed to find the links of the newest
+    /// release.
+    FindLinks,
+    /// The files of the newest release is being downloaded.
+    Download,
+    /// A user specified script is being run.
+    RunScript,
+    /// The package is being created.
+    CreatePackage,
+    /// The package is being pushed to the configured feed.
+    Publish,
+}
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            Stage::LoadData => f.write_str("Load Data"),
+            Stage::FindLinks => f.write_str("Find Links"),
+            Stage::Download => f.write_str("Download"),
+            Stage::RunScript => f.write_str("Run Script"),
+            Stage::CreatePackage => f.write_str("Create Package"),
+            Stage::Publish => f.write_str("Publish"),
+        }
+    }
+}
+
+/// Callbacks that are called during the update process. Every function have
+/// a default implementation that does nothing, so only the necessary
+/// callbacks needs to be implemented.
+///
+/// Observers are registered on the
+/// [UpdateContext](crate::context::UpdateContext) using
+/// [with_observer](crate::context::UpdateContext::with_observer).
+///
+/// ## Examples
+///
+/// ```
+/// use aer_upd::context::UpdateContext;
+/// use aer_upd::observer::{Observer, Stage};
+///
+/// struct PrintObserver;
+///
+/// impl Observer for PrintObserver {
+///     fn on_stage_start(&self, package_id: &str, stage: Stage) {
+///         println!("{}: {}", package_id, stage);
+///     }
+/// }
+///
+/// let context = UpdateContext::new().with_observer(PrintObserver);
+/// ```
+pub trait Observer: Send + Sync {
+    /// Called when a package enters a new stage.
+    fn on_stage_start(&self, _package_id: &str, _stage: Stage) {}
+
+    /// Called when a package leaves a stage, with wether the stage was
+    /// successful or not.
+    fn on_stage_end(&self, _package_id: &str, _stage: Stage, _success: bool) {}
+
+    /// Called when a warning is reported for a package.
+    fn on_warning(&self, _package_id: &str, _message: &str) {}
+
+    /// Called when a file have been created for a package (*ie: a downloaded
+    /// file or the created package*).
+    fn on_artifact(&self, _package_id: &str, _path: &Path) {}
+}
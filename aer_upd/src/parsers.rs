@@ -2,19 +2,33 @@
 // Licensed under the MIT license. See LICENSE.txt file in the project
 
 use std::fs::File;
-use std::io::{BufReader, Error as IoError, ErrorKind, Read};
+use std::io::{BufReader, Error as IoError, ErrorKind, Read, Write};
 use std::path::Path;
 
 use aer_data::prelude::*;
 use log::warn;
 
+#[cfg(any(feature = "json_data", feature = "toml_data", feature = "yaml_data"))]
+use crate::context::UpdateContext;
+#[cfg(any(feature = "json_data", feature = "toml_data", feature = "yaml_data"))]
+use crate::features;
+#[cfg(any(feature = "json_data", feature = "toml_data", feature = "yaml_data"))]
+use crate::observer::Stage;
+
 pub mod errors;
+#[cfg(feature = "json_data")]
+pub mod json;
 #[cfg(feature = "toml_data")]
 pub mod toml;
+#[cfg(feature = "yaml_data")]
+pub mod yaml;
 
 /// Parsers implementing this trait are able to read and transform a specific
 /// structure to the [PackageData] type.
-pub trait DataReader {
+///
+/// Parsers must be `Send` and `Sync`, as the same parser is used by every
+/// thread reading package files.
+pub trait DataReader: Send + Sync {
     /// Function to decide if the implemented structure can handle a certain
     /// file (usually by file extension).
     fn can_handle_file(&self, path: &Path) -> bool;
@@ -46,7 +60,20 @@ pub trait DataReader {
         };
         let mut buffer = BufReader::new(file);
 
-        self.read_data(&mut buffer)
+        self.read_file_data(&mut buffer, path)
+    }
+
+    /// Read the specified buffer holding the content of the specified file,
+    /// and return either the parsed package data or an error if one occurs.
+    /// Parsers that need the location of the file (*ie: to resolve other
+    /// files referenced by the document*) can override this function, the
+    /// default implementation calls [DataReader::read_data].
+    fn read_file_data<T: Read>(
+        &self,
+        reader: &mut T,
+        _path: &Path,
+    ) -> Result<PackageData, errors::ParserError> {
+        self.read_data(reader)
     }
 
     /// Read the specifed buffer and return either the parsed package data, or
@@ -54,8 +81,92 @@ pub trait DataReader {
     fn read_data<T: Read>(&self, reader: &mut T) -> Result<PackageData, errors::ParserError>;
 }
 
-#[cfg(any(feature = "toml_data"))]
-#[cfg_attr(docsrs, doc(cfg(any(feature = "toml_data"))))]
+/// Parsers implementing this trait are able to write the [PackageData] type
+/// back to a specific structure, allowing updated values (like the version)
+/// to be persisted in the package files.
+///
+/// Parsers must be `Send` and `Sync`, as the same parser is used by every
+/// thread writing package files.
+pub trait DataWriter: Send + Sync {
+    /// Function to decide if the implemented structure can write to a certain
+    /// file (usually by file extension).
+    fn can_write_file(&self, path: &Path) -> bool;
+
+    /// Serialize and write the package data to the specified file. When the
+    /// file already exists, the existing document is updated with the values
+    /// of the package data.
+    fn write_file(&self, data: &PackageData, path: &Path) -> Result<(), errors::ParserError> {
+        if !self.can_write_file(path) {
+            let error = IoError::new(
+                ErrorKind::InvalidData,
+                format!("The file '{}' is not a supported type.", path.display()),
+            );
+            warn!("{}", error);
+            return Err(errors::ParserError::Writing(error));
+        }
+
+        let existing = if path.exists() {
+            Some(std::fs::read_to_string(path).map_err(errors::ParserError::Writing)?)
+        } else {
+            None
+        };
+
+        // The document is written to a buffer first, to keep the existing file
+        // intact when the package data could not be serialized, or the files
+        // extended by the existing document could not be read.
+        let mut buffer = vec![];
+
+        match existing {
+            Some(existing) => self.update_file_data(data, &existing, path, &mut buffer)?,
+            None => self.write_data(data, &mut buffer)?,
+        }
+
+        let mut file = File::create(path).map_err(errors::ParserError::Writing)?;
+        file.write_all(&buffer)
+            .map_err(errors::ParserError::Writing)
+    }
+
+    /// Serialize the package data to the specified writer, returning an error
+    /// if one occurs.
+    fn write_data<W: Write>(
+        &self,
+        data: &PackageData,
+        writer: &mut W,
+    ) -> Result<(), errors::ParserError>;
+
+    /// Serialize the package data to the specified writer, as a replacement
+    /// of the existing document. Implementations should preserve the
+    /// formatting and comments of the existing document where practical, the
+    /// default implementation writes a new document.
+    fn update_data<W: Write>(
+        &self,
+        data: &PackageData,
+        _existing: &str,
+        writer: &mut W,
+    ) -> Result<(), errors::ParserError> {
+        self.write_data(data, writer)
+    }
+
+    /// Serialize the package data to the specified writer, as a replacement
+    /// of the existing document stored in the specified file. Parsers that
+    /// need the location of the file can override this function, the default
+    /// implementation calls [DataWriter::update_data].
+    fn update_file_data<W: Write>(
+        &self,
+        data: &PackageData,
+        existing: &str,
+        _path: &Path,
+        writer: &mut W,
+    ) -> Result<(), errors::ParserError> {
+        self.update_data(data, existing, writer)
+    }
+}
+
+#[cfg(any(feature = "json_data", feature = "toml_data", feature = "yaml_data"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "json_data", feature = "toml_data", feature = "yaml_data")))
+)]
 macro_rules! call_parsers {
     ($path:ident,$($parser:expr=>$feature:literal),+) => {
         $(
@@ -72,10 +183,91 @@ macro_rules! call_parsers {
     };
 }
 
-#[cfg(any(feature = "toml_data"))]
-#[cfg_attr(docsrs, doc(cfg(any(feature = "toml_data"))))]
+#[cfg(any(feature = "json_data", feature = "toml_data", feature = "yaml_data"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "json_data", feature = "toml_data", feature = "yaml_data")))
+)]
 pub fn read_file(path: &Path) -> Result<PackageData, errors::ParserError> {
-    call_parsers!(path, toml::TomlParser => "toml_data");
+    call_parsers!(
+        path,
+        toml::TomlParser => "toml_data",
+        yaml::YamlParser => "yaml_data",
+        json::JsonParser => "json_data"
+    );
+
+    Err(no_parsers(path, "Reading"))
+}
+
+#[cfg(any(feature = "json_data", feature = "toml_data", feature = "yaml_data"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "json_data", feature = "toml_data", feature = "yaml_data")))
+)]
+macro_rules! call_writers {
+    ($data:ident,$path:ident,$($writer:expr=>$feature:literal),+) => {
+        $(
+            #[cfg(feature = $feature)]
+            {
+                if $writer.can_write_file($path) {
+                    return $writer.write_file($data, $path);
+                }
+            }
+        )*
+    };
+}
+
+/// Writes the package data to the specified file, using the first parser that
+/// can write to the file.
+#[cfg(any(feature = "json_data", feature = "toml_data", feature = "yaml_data"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "json_data", feature = "toml_data", feature = "yaml_data")))
+)]
+pub fn write_file(data: &PackageData, path: &Path) -> Result<(), errors::ParserError> {
+    call_writers!(
+        data,
+        path,
+        toml::TomlParser => "toml_data",
+        yaml::YamlParser => "yaml_data",
+        json::JsonParser => "json_data"
+    );
+
+    Err(no_parsers(path, "Writing"))
+}
+
+/// Creates the error returned when no parser is able to handle the file, which
+/// names the missing feature when the parser of the file was compiled out.
+#[cfg(any(feature = "json_data", feature = "toml_data", feature = "yaml_data"))]
+fn no_parsers(path: &Path, action: &str) -> errors::ParserError {
+    features::for_package_file(path)
+        .and_then(|feature| {
+            features::require(feature, format_args!("{} '{}'", action, path.display())).err()
+        })
+        .map(errors::ParserError::FeatureDisabled)
+        .unwrap_or_else(|| errors::ParserError::NoParsers(path.to_owned()))
+}
+
+/// Reads the specified file using the first parser that can handle the file,
+/// after checking that the update process have not been cancelled.
+#[cfg(any(feature = "json_data", feature = "toml_data", feature = "yaml_data"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "json_data", feature = "toml_data", feature = "yaml_data")))
+)]
+pub fn read_file_in(
+    context: &UpdateContext,
+    path: &Path,
+) -> Result<PackageData, errors::ParserError> {
+    context
+        .check_cancelled()
+        .map_err(|err| errors::ParserError::Other {
+            inner: Box::new(err),
+        })?;
+    context.progress(format_args!(
+        "Loading package data from '{}'",
+        path.display()
+    ));
 
-    Err(errors::ParserError::NoParsers(path.to_owned()))
+    context.stage(Stage::LoadData, || read_file(path))
 }
@@ -2,35 +2,70 @@
 // Licensed under the MIT license. See LICENSE.txt file in the project
 
 use std::error::Error;
-use std::fmt;
 use std::path::PathBuf;
 
-#[derive(Debug)]
+use crate::features::FeatureError;
+
+#[derive(Debug, thiserror::Error)]
 pub enum ParserError {
+    #[error("No parser that could handle {} was found!", .0.display())]
     NoParsers(PathBuf),
+    /// The file can only be handled by a parser that was not enabled when the
+    /// crate was compiled.
+    #[error(transparent)]
+    FeatureDisabled(#[from] FeatureError),
+    #[error(transparent)]
     Loading(std::io::Error),
+    /// The package data could not be written to the file.
+    #[error(transparent)]
+    Writing(std::io::Error),
+    #[error("{0}")]
     Deserialize(String),
-    Other { inner: Box<dyn Error> },
+    /// The data could not be deserialized, and the position of the failure
+    /// in the document is known. Both the line and the column starts at `1`.
+    #[error("{message}")]
+    DeserializeAt {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    /// The package data could not be serialized to the target format.
+    #[error("{0}")]
+    Serialize(String),
+    /// The base file extended by the package file could not be read, or the
+    /// package files extend each other in a cycle. The path is the path of
+    /// the package or base file declaring the `extends` key.
+    #[error("{message}")]
+    Extends { path: PathBuf, message: String },
+    #[error(transparent)]
+    Other { inner: Box<dyn Error + Send + Sync> },
 }
 
-impl fmt::Display for ParserError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+impl ParserError {
+    /// Returns the stable code identifying the kind of error (*ie:
+    /// `AER0004`*), which can be used when searching the documentation.
+    pub fn code(&self) -> &'static str {
         match self {
-            ParserError::Loading(err) => err.fmt(f),
-            ParserError::Deserialize(s) => s.fmt(f),
-            ParserError::Other { inner } => inner.fmt(f),
-            ParserError::NoParsers(path) => {
-                write!(
-                    f,
-                    "No parser that could handle {} was found!",
-                    path.display()
-                )
-            }
+            ParserError::NoParsers(_) => "AER0001",
+            ParserError::FeatureDisabled(err) => err.code(),
+            ParserError::Loading(_) => "AER0002",
+            ParserError::Writing(_) => "AER0003",
+            ParserError::Deserialize(_) | ParserError::DeserializeAt { .. } => "AER0004",
+            ParserError::Serialize(_) => "AER0005",
+            ParserError::Extends { .. } => "AER0010",
+            ParserError::Other { .. } => "AER0006",
         }
     }
-}
 
-impl Error for ParserError {}
+    /// Returns the line and column in the document where the error occurred,
+    /// if the position is known.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        match self {
+            ParserError::DeserializeAt { line, column, .. } => Some((*line, *column)),
+            _ => None,
+        }
+    }
+}
 
 impl PartialEq for ParserError {
     fn eq(&self, other: &ParserError) -> bool {
@@ -38,15 +73,41 @@ impl PartialEq for ParserError {
             (ParserError::Deserialize(val), ParserError::Deserialize(other_val)) => {
                 val.eq(other_val)
             }
+            (
+                ParserError::DeserializeAt {
+                    message,
+                    line,
+                    column,
+                },
+                ParserError::DeserializeAt {
+                    message: other_message,
+                    line: other_line,
+                    column: other_column,
+                },
+            ) => message == other_message && line == other_line && column == other_column,
+            (ParserError::Serialize(val), ParserError::Serialize(other_val)) => val.eq(other_val),
             (ParserError::Loading(err), ParserError::Loading(other_err)) => {
                 format!("{}", err).eq(&format!("{}", other_err))
             }
+            (ParserError::Writing(err), ParserError::Writing(other_err)) => {
+                format!("{}", err).eq(&format!("{}", other_err))
+            }
             (ParserError::Other { inner: err }, ParserError::Other { inner: other_err }) => {
                 format!("{}", err).eq(&format!("{}", other_err))
             }
+            (
+                ParserError::Extends { path, message },
+                ParserError::Extends {
+                    path: other_path,
+                    message: other_message,
+                },
+            ) => path == other_path && message == other_message,
             (ParserError::NoParsers(path), ParserError::NoParsers(other_path)) => {
                 path.eq(other_path)
             }
+            (ParserError::FeatureDisabled(err), ParserError::FeatureDisabled(other_err)) => {
+                err.eq(other_err)
+            }
             _ => false,
         }
     }
@@ -0,0 +1,162 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+#![cfg_attr(docsrs, doc(cfg(feature = "json_data")))]
+
+use std::io::{Read, Write};
+use std::path::Path;
+
+use aer_data::PackageData;
+use log::{debug, error};
+
+use crate::parsers::{errors, DataReader, DataWriter};
+
+pub struct JsonParser;
+
+impl DataWriter for JsonParser {
+    fn can_write_file(&self, path: &Path) -> bool {
+        self.can_handle_file(path)
+    }
+
+    /// Serializes the package data as a pretty printed `JSON` document to the
+    /// specified writer, the written document can be read back using
+    /// [DataReader::read_data].
+    fn write_data<W: Write>(
+        &self,
+        data: &PackageData,
+        writer: &mut W,
+    ) -> Result<(), errors::ParserError> {
+        debug!("Serializing JSON Package data");
+        if let Err(err) = serde_json::to_writer_pretty(&mut *writer, data) {
+            error!("Failed to serialize package data: {:?}", err);
+            return Err(errors::ParserError::Serialize(err.to_string()));
+        }
+
+        writer
+            .write_all(b"\n")
+            .map_err(errors::ParserError::Writing)
+    }
+}
+
+/// Implements the trait necessary for reading files that are stored in the
+/// `JSON` language, using the same structure as the `TOML` files.
+impl DataReader for JsonParser {
+    fn can_handle_file(&self, path: &Path) -> bool {
+        if let Some(path) = path.to_str() {
+            path.ends_with(".aer.json")
+        } else {
+            false
+        }
+    }
+
+    /// Reads and deserializes a `JSON` document in the specified reader passed
+    /// to the function.
+    fn read_data<T>(&self, reader: &mut T) -> Result<PackageData, errors::ParserError>
+    where
+        T: Read,
+    {
+        let config_data: PackageData = {
+            let mut config_text = String::new();
+
+            match reader.read_to_string(&mut config_text) {
+                Err(err) => {
+                    error!("Failed to read data: {:?}", err);
+                    return Err(errors::ParserError::Loading(err));
+                }
+                Ok(size) => debug!("Read {} bytes!", size),
+            }
+
+            debug!("Deserializing JSON Package data");
+            match serde_json::from_str(&config_text) {
+                Err(err) => {
+                    error!("Failed to deserialize package data: {:?}", err);
+                    let message = err.to_string();
+                    return Err(if err.line() > 0 {
+                        errors::ParserError::DeserializeAt {
+                            message,
+                            line: err.line(),
+                            column: err.column(),
+                        }
+                    } else {
+                        errors::ParserError::Deserialize(message)
+                    });
+                }
+                Ok(data) => data,
+            }
+        };
+
+        debug!("Package JSON data deserialized, returning package data!");
+
+        Ok(config_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufReader, Error, ErrorKind};
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    use aer_data::prelude::*;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("test-package.aer.toml")]
+    #[case("test-package.json")]
+    #[case("test-package.xml")]
+    fn read_file_should_error_for_non_aer_json_files(#[case] file: &str) {
+        let path = PathBuf::from_str(file).unwrap();
+        let parser = JsonParser;
+
+        let r = parser.read_file(&path).unwrap_err();
+
+        assert_eq!(
+            r,
+            errors::ParserError::Loading(Error::new(
+                ErrorKind::InvalidData,
+                format!("The file '{}' is not a supported type.", file)
+            ))
+        );
+    }
+
+    #[test]
+    fn read_data_should_include_position_of_deserialization_failure() {
+        const VAL: &[u8] = b"{\n  \"metadata\": {\n    \"id\": 5";
+        let mut reader = BufReader::new(VAL);
+        let parser = JsonParser;
+
+        let actual = parser.read_data(&mut reader).unwrap_err();
+
+        assert_eq!(actual.position(), Some((3, 11)));
+    }
+
+    #[test]
+    fn read_data_should_accept_license_url() {
+        let path = PathBuf::from("test-data/license-url.aer.json");
+        let parser = JsonParser;
+        let mut expected = PackageData::new("test-package");
+        expected.metadata_mut().set_license(LicenseType::Location(
+            Url::parse("https://github.com/WormieCorp/aer/LICENSE.txt").unwrap(),
+        ));
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn write_data_should_round_trip_package_data() {
+        let expected = crate::parsers::toml::TomlParser
+            .read_file(Path::new("test-data/deserialize-full.aer.toml"))
+            .unwrap();
+        let parser = JsonParser;
+        let mut buffer = vec![];
+
+        parser.write_data(&expected, &mut buffer).unwrap();
+        let actual = parser.read_data(&mut buffer.as_slice()).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,15 +1,46 @@
 // Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
 // Licensed under the MIT license. See LICENSE.txt file in the project
 
+//! Contains the parser reading and writing package files stored in the
+//! `TOML` language.
+//!
+//! Package files can share common values (*ie: the maintainers, tags or the
+//! url pattern of the icon*) by extending a base file, using the path of the
+//! base file relative to the package file:
+//!
+//! ```toml
+//! extends = "../common.aer.toml"
+//!
+//! [metadata]
+//! id = "astyle"
+//! ```
+//!
+//! The base file is merged with the package file using the following
+//! precedence:
+//!
+//! - Values declared in the package file always override the values of the base
+//!   file.
+//! - Tables declared in both files are merged key by key, which allows a
+//!   package file to only override a single value of a nested table.
+//! - Any other value, including arrays, replaces the value of the base file as
+//!   a whole (*ie: the tags of the base file are not combined with the tags of
+//!   the package file*).
+//! - A base file can extend another base file, where the file closest to the
+//!   package file takes precedence.
+//!
+//! The base file do not need to contain every required value, the required
+//! values are only validated once the files have been merged.
+
 #![cfg_attr(docsrs, doc(cfg(feature = "toml_data")))]
 
-use std::io::Read;
-use std::path::Path;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 
 use aer_data::PackageData;
-use log::{debug, error};
+use log::{debug, error, warn};
+use toml_edit::{Document, Item, TableLike};
 
-use crate::parsers::{errors, DataReader};
+use crate::parsers::{errors, DataReader, DataWriter};
 
 pub struct TomlParser;
 
@@ -26,37 +57,384 @@ impl DataReader for TomlParser {
     }
 
     /// Reads and deserializes a `TOML` document in the specified reader passed
-    /// to the function.
+    /// to the function. Documents extending a base file can only be read
+    /// using [DataReader::read_file], as the base file is located relative to
+    /// the package file.
     fn read_data<T>(&self, reader: &mut T) -> Result<PackageData, errors::ParserError>
     where
         T: Read,
     {
-        let config_data: PackageData = {
-            let mut config_text = String::new();
+        let config_text = read_text(reader)?;
 
-            match reader.read_to_string(&mut config_text) {
-                Err(err) => {
-                    error!("Failed to read data: {:?}", err);
-                    return Err(errors::ParserError::Loading(err));
-                }
-                Ok(size) => debug!("Read {} bytes!", size),
+        if let Ok(document) = config_text.parse::<toml::Value>() {
+            if document.get(EXTENDS_KEY).is_some() {
+                error!("Unable to extend a base file without the location of the package file");
+                return Err(errors::ParserError::Extends {
+                    path: PathBuf::new(),
+                    message: format!(
+                        "The '{}' key can only be used when reading a package file!",
+                        EXTENDS_KEY
+                    ),
+                });
             }
+        }
+
+        deserialize(&config_text)
+    }
+
+    /// Reads and deserializes a `TOML` document in the specified reader,
+    /// merging the document with the base file it extends.
+    fn read_file_data<T: Read>(
+        &self,
+        reader: &mut T,
+        path: &Path,
+    ) -> Result<PackageData, errors::ParserError> {
+        let config_text = read_text(reader)?;
+
+        match config_text.parse::<toml::Value>() {
+            Ok(document) if document.get(EXTENDS_KEY).is_some() => read_extended(document, path),
+            _ => deserialize(&config_text),
+        }
+    }
+}
+
+impl DataWriter for TomlParser {
+    fn can_write_file(&self, path: &Path) -> bool {
+        self.can_handle_file(path)
+    }
+
+    /// Serializes the package data as a new `TOML` document to the specified
+    /// writer.
+    fn write_data<W: Write>(
+        &self,
+        data: &PackageData,
+        writer: &mut W,
+    ) -> Result<(), errors::ParserError> {
+        let document = serialize_document(data)?;
+
+        writer
+            .write_all(document.to_string().as_bytes())
+            .map_err(errors::ParserError::Writing)
+    }
+
+    /// Updates the existing `TOML` document with the values of the package
+    /// data. Only the values that differ from the package data stored in the
+    /// existing document are changed, which preserves the comments and the
+    /// formatting of the remaining document. A new document is written if the
+    /// existing document can not be read.
+    fn update_data<W: Write>(
+        &self,
+        data: &PackageData,
+        existing: &str,
+        writer: &mut W,
+    ) -> Result<(), errors::ParserError> {
+        let (document, old_data) = match (
+            existing.parse::<Document>(),
+            self.read_data(&mut existing.as_bytes()),
+        ) {
+            (Ok(document), Ok(old_data)) => (document, old_data),
+            _ => {
+                warn!("Unable to read the existing TOML document, a new document will be written");
+                return self.write_data(data, writer);
+            }
+        };
+
+        patch_document(document, &old_data, data, writer)
+    }
+
+    /// Updates the existing `TOML` document with the values of the package
+    /// data. Documents extending a base file are compared against the merged
+    /// package data, which keeps the values that have not changed in the base
+    /// file.
+    fn update_file_data<W: Write>(
+        &self,
+        data: &PackageData,
+        existing: &str,
+        path: &Path,
+        writer: &mut W,
+    ) -> Result<(), errors::ParserError> {
+        let document = match existing.parse::<toml::Value>() {
+            Ok(document) if document.get(EXTENDS_KEY).is_some() => document,
+            _ => return self.update_data(data, existing, writer),
+        };
+
+        let old_data = read_extended(document, path)?;
+        let document = existing
+            .parse::<Document>()
+            .map_err(|err| errors::ParserError::Deserialize(err.to_string()))?;
+
+        patch_document(document, &old_data, data, writer)
+    }
+}
+
+/// The key used in package files to reference the base file that is extended.
+pub const EXTENDS_KEY: &str = "extends";
+
+fn read_text<T: Read>(reader: &mut T) -> Result<String, errors::ParserError> {
+    let mut config_text = String::new();
+
+    match reader.read_to_string(&mut config_text) {
+        Err(err) => {
+            error!("Failed to read data: {:?}", err);
+            Err(errors::ParserError::Loading(err))
+        }
+        Ok(size) => {
+            debug!("Read {} bytes!", size);
+            Ok(config_text)
+        }
+    }
+}
+
+fn deserialize(config_text: &str) -> Result<PackageData, errors::ParserError> {
+    debug!("Deserializing TOML Package data");
+    let data = toml::from_str(config_text).map_err(|err| {
+        error!("Failed to deserialize package data: {:?}", err);
+        let message = err.to_string();
+        match err.line_col() {
+            Some((line, column)) => errors::ParserError::DeserializeAt {
+                message,
+                line: line + 1,
+                column: column + 1,
+            },
+            None => errors::ParserError::Deserialize(message),
+        }
+    })?;
+
+    debug!("Package TOML data deserialized, returning package data!");
+
+    Ok(data)
+}
+
+/// Merges the document with the base files it extends, and deserializes the
+/// merged document.
+fn read_extended(document: toml::Value, path: &Path) -> Result<PackageData, errors::ParserError> {
+    let mut files = vec![path.canonicalize().unwrap_or_else(|_| path.to_owned())];
+    let document = extend_document(document, path, &mut files)?;
+
+    debug!("Deserializing merged TOML Package data");
+    document.try_into().map_err(|err| {
+        error!("Failed to deserialize package data: {:?}", err);
+        errors::ParserError::Deserialize(format!(
+            "{} (after merging '{}' with the base files it extends)",
+            err,
+            path.display()
+        ))
+    })
+}
 
-            debug!("Deserializing TOML Package data");
-            match toml::from_str(&config_text) {
-                Err(err) => {
-                    error!("Failed to deserialize package data: {:?}", err);
-                    let fmt = err.to_string();
-                    return Err(errors::ParserError::Deserialize(fmt));
+/// Removes the `extends` key from the document, and merges the document into
+/// the base file referenced by the key. The files that have already been read
+/// are tracked to detect package files extending each other in a cycle.
+fn extend_document(
+    mut document: toml::Value,
+    path: &Path,
+    files: &mut Vec<PathBuf>,
+) -> Result<toml::Value, errors::ParserError> {
+    let extends = match document
+        .as_table_mut()
+        .and_then(|table| table.remove(EXTENDS_KEY))
+    {
+        Some(extends) => extends,
+        None => return Ok(document),
+    };
+    let extends_error = |message: String| {
+        error!("{}", message);
+        errors::ParserError::Extends {
+            path: path.to_owned(),
+            message,
+        }
+    };
+
+    let base_path = match extends.as_str() {
+        Some(base) => path.parent().unwrap_or_else(|| Path::new("")).join(base),
+        None => {
+            return Err(extends_error(format!(
+                "The '{}' key in '{}' must be the path to a base file, found: {}",
+                EXTENDS_KEY,
+                path.display(),
+                extends
+            )))
+        }
+    };
+    let base_text = std::fs::read_to_string(&base_path).map_err(|err| {
+        extends_error(format!(
+            "The base file '{}' extended by '{}' could not be read: {}",
+            base_path.display(),
+            path.display(),
+            err
+        ))
+    })?;
+
+    let canonical = base_path
+        .canonicalize()
+        .unwrap_or_else(|_| base_path.clone());
+    let is_cycle = files.contains(&canonical);
+    files.push(canonical);
+    if is_cycle {
+        return Err(extends_error(format!(
+            "The package files extend each other in a cycle: {}",
+            files
+                .iter()
+                .map(|file| format!("'{}'", file.display()))
+                .collect::<Vec<_>>()
+                .join(" -> ")
+        )));
+    }
+
+    debug!("Extending the base file '{}'", base_path.display());
+    let base = base_text.parse::<toml::Value>().map_err(|err| {
+        extends_error(format!(
+            "The base file '{}' extended by '{}' could not be parsed: {}",
+            base_path.display(),
+            path.display(),
+            err
+        ))
+    })?;
+    let mut base = extend_document(base, &base_path, files)?;
+    merge_values(&mut base, document);
+
+    Ok(base)
+}
+
+/// Merges the values of the document into the base document. Tables are
+/// merged key by key, while any other value (*including arrays*) replaces the
+/// value of the base document.
+fn merge_values(base: &mut toml::Value, document: toml::Value) {
+    match (base, document) {
+        (toml::Value::Table(base), toml::Value::Table(document)) => {
+            for (key, value) in document {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
                 }
-                Ok(data) => data,
             }
+        }
+        (base, document) => *base = document,
+    }
+}
+
+/// Applies the changes between the old package data and the new package data
+/// to the existing document, and writes the updated document to the writer.
+fn patch_document<W: Write>(
+    mut document: Document,
+    old_data: &PackageData,
+    data: &PackageData,
+    writer: &mut W,
+) -> Result<(), errors::ParserError> {
+    debug!("Updating existing TOML Package data");
+    let old = serialize_document(old_data)?;
+    let new = serialize_document(data)?;
+    patch_table(document.as_table_mut(), old.as_table(), new.as_table());
+
+    writer
+        .write_all(document.to_string().as_bytes())
+        .map_err(errors::ParserError::Writing)
+}
+
+fn serialize_document(data: &PackageData) -> Result<Document, errors::ParserError> {
+    debug!("Serializing TOML Package data");
+    toml::Value::try_from(data)
+        .and_then(|value| toml::to_string(&value))
+        .map_err(|err| err.to_string())
+        .and_then(|text| text.parse::<Document>().map_err(|err| err.to_string()))
+        .map_err(|err| {
+            error!("Failed to serialize package data: {:?}", err);
+            errors::ParserError::Serialize(err)
+        })
+}
+
+/// Applies the changes between the `old` and the `new` table to the `target`
+/// table, keys that have not changed are left untouched.
+fn patch_table(target: &mut dyn TableLike, old: &dyn TableLike, new: &dyn TableLike) {
+    for (key, new_item) in new.iter() {
+        let old_item = old.get(key);
+
+        if let (Some(old_table), Some(new_table)) = (
+            old_item.and_then(Item::as_table_like),
+            new_item.as_table_like(),
+        ) {
+            if let Some(target_table) = target.get_mut(key).and_then(Item::as_table_like_mut) {
+                patch_table(target_table, old_table, new_table);
+                continue;
+            }
+        }
+
+        if old_item.map(Item::to_string) == Some(new_item.to_string()) {
+            continue;
+        }
+
+        match (target.get_mut(key), new_item) {
+            (Some(Item::Value(value)), Item::Value(new_value)) => {
+                let prefix = value.decor().prefix().unwrap_or(" ").to_owned();
+                let suffix = value.decor().suffix().unwrap_or("").to_owned();
+                *value = new_value.clone().decorated(&prefix, &suffix);
+            }
+            _ => {
+                target.insert(key, new_item.clone());
+            }
+        }
+    }
+
+    let removed: Vec<String> = old
+        .iter()
+        .filter(|(key, _)| !new.contains_key(key))
+        .map(|(key, _)| key.to_owned())
+        .collect();
+    for key in removed {
+        target.remove(&key);
+    }
+}
+
+/// Locates the line and column (both starting at `1`) of the key with the
+/// specified dotted path (i.e. `metadata.chocolatey.authors`) in a `TOML`
+/// document. When the key itself is not declared, the position of the closest
+/// parent table is returned instead.
+pub fn key_position(text: &str, path: &str) -> Option<(usize, usize)> {
+    let mut table = String::new();
+    let mut best: Option<(usize, (usize, usize))> = None;
+
+    for (index, line) in text.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let column = line.len() - trimmed.len() + 1;
+        let position = (index + 1, column);
+
+        let full_key = if trimmed.starts_with('[') {
+            table = normalize_key(trimmed.trim_matches(|c| c == '[' || c == ']' || c == ' '));
+            table.clone()
+        } else if let Some(eq) = trimmed.find('=') {
+            if trimmed.starts_with('#') {
+                continue;
+            }
+            let key = normalize_key(&trimmed[..eq]);
+            if table.is_empty() {
+                key
+            } else {
+                format!("{}.{}", table, key)
+            }
+        } else {
+            continue;
         };
 
-        debug!("Package TOML data deserialized, returning package data!");
+        if full_key == path {
+            return Some(position);
+        }
 
-        Ok(config_data)
+        let is_parent = path.starts_with(&full_key) && path[full_key.len()..].starts_with('.');
+        if is_parent && !matches!(best, Some((len, _)) if len >= full_key.len()) {
+            best = Some((full_key.len(), position));
+        }
     }
+
+    best.map(|(_, position)| position)
+}
+
+fn normalize_key(key: &str) -> String {
+    key.split('.')
+        .map(|part| part.trim().trim_matches(|c| c == '"' || c == '\''))
+        .collect::<Vec<_>>()
+        .join(".")
 }
 
 #[cfg(test)]
@@ -150,6 +528,46 @@ mod tests {
         let _ = parser.read_data(&mut reader).unwrap();
     }
 
+    #[test]
+    fn read_data_should_include_position_of_deserialization_failure() {
+        const VAL: &[u8] = b"[metadata]\nid = 5";
+        let mut reader = BufReader::new(VAL);
+        let parser = TomlParser;
+
+        let actual = parser.read_data(&mut reader).unwrap_err();
+
+        assert_eq!(actual.position(), Some((2, 6)));
+    }
+
+    #[rstest(
+        path,
+        expected,
+        case("metadata.id", Some((2, 1))),
+        case("metadata.chocolatey.authors", Some((7, 3))),
+        case("metadata.chocolatey.tags", Some((6, 1))),
+        case("metadata.chocolatey.dependencies.7zip", Some((9, 1))),
+        case("updater.check_frequency", None)
+    )]
+    fn key_position_should_locate_key_or_closest_table(
+        path: &str,
+        expected: Option<(usize, usize)>,
+    ) {
+        const VAL: &str = r#"[metadata]
+id = "test-package"
+# summary = "commented"
+summary = "Some summary"
+
+[metadata.chocolatey]
+  authors = ["AdmiringWorm"]
+
+[ metadata.chocolatey.dependencies ]
+"#;
+
+        let actual = key_position(VAL, path);
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn read_data_should_succeed_on_required_values_defined() {
         let path = PathBuf::from("test-data/basic-metadata.aer.toml");
@@ -197,6 +615,422 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn update_data_should_only_change_modified_values() {
+        const VAL: &str = r#"# The package used when testing
+[metadata]
+id = "test-package" # The identifier
+project_url = "https://example-repo.org"
+summary = ""
+license = "https://github.com/WormieCorp/aer/LICENSE.txt"
+
+[metadata.chocolatey]
+version = "1.0.0"   # Updated automatically
+authors = ["AdmiringWorm"]
+description = "Package used when testing"
+"#;
+        let parser = TomlParser;
+        let mut data = parser.read_data(&mut VAL.as_bytes()).unwrap();
+        let mut choco = data.metadata().chocolatey().into_owned();
+        choco.version = Versions::parse("1.2.0").unwrap();
+        data.metadata_mut().set_chocolatey(choco);
+        let mut buffer = vec![];
+
+        parser.update_data(&data, VAL, &mut buffer).unwrap();
+
+        let actual = String::from_utf8(buffer).unwrap();
+        assert_eq!(
+            actual,
+            VAL.replace("version = \"1.0.0\"", "version = \"1.2.0\"")
+        );
+    }
+
+    #[test]
+    fn read_file_should_merge_extended_base_files() {
+        let path = PathBuf::from("test-data/extends/packages/app.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        let metadata = actual.metadata();
+        assert_eq!(metadata.id(), "test-package");
+        assert_eq!(metadata.summary, "Some kind of summary");
+        assert_eq!(metadata.project_url().as_str(), "https://common.org/");
+        assert_eq!(metadata.maintainers(), ["AdmiringWorm"]);
+        assert_eq!(
+            metadata.license(),
+            &LicenseType::Expression("MIT".to_owned())
+        );
+        let choco = metadata.chocolatey();
+        assert_eq!(choco.authors(), ["WormieCorp"]);
+        assert_eq!(choco.tags(), ["app"]);
+        assert_eq!(
+            choco.description(),
+            &Description::Text("Shared description".to_owned())
+        );
+    }
+
+    #[test]
+    fn read_file_should_error_when_package_files_extend_each_other() {
+        let path = PathBuf::from("test-data/extends/cycle-first.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap_err();
+
+        assert_eq!(actual.code(), "AER0010");
+        let message = actual.to_string();
+        assert!(message.starts_with("The package files extend each other in a cycle: "));
+        assert_eq!(message.matches("cycle-first.aer.toml").count(), 2);
+    }
+
+    #[test]
+    fn read_file_should_error_when_base_file_is_missing() {
+        let path = PathBuf::from("test-data/extends/missing-base.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap_err();
+
+        assert!(
+            matches!(actual, errors::ParserError::Extends { ref path, .. } if path.ends_with("missing-base.aer.toml"))
+        );
+        assert!(actual.to_string().starts_with(
+            "The base file 'test-data/extends/not-existing.aer.toml' extended by \
+             'test-data/extends/missing-base.aer.toml' could not be read: "
+        ));
+    }
+
+    #[test]
+    fn read_data_should_error_when_extending_base_file() {
+        const VAL: &[u8] = b"extends = \"common.aer.toml\"\n[metadata]\nid = \"test-package\"";
+        let parser = TomlParser;
+
+        let actual = parser.read_data(&mut BufReader::new(VAL)).unwrap_err();
+
+        assert_eq!(
+            actual.to_string(),
+            "The 'extends' key can only be used when reading a package file!"
+        );
+    }
+
+    #[test]
+    fn update_file_data_should_not_copy_values_of_base_file() {
+        let path = PathBuf::from("test-data/extends/packages/app.aer.toml");
+        let existing = std::fs::read_to_string(&path).unwrap();
+        let parser = TomlParser;
+        let mut data = parser.read_file(&path).unwrap();
+        let mut choco = data.metadata().chocolatey().into_owned();
+        choco.version = Versions::parse("1.2.0").unwrap();
+        data.metadata_mut().set_chocolatey(choco);
+        let mut buffer = vec![];
+
+        parser
+            .update_file_data(&data, &existing, &path, &mut buffer)
+            .unwrap();
+
+        let actual = String::from_utf8(buffer).unwrap();
+        assert_eq!(
+            actual,
+            existing.replace(
+                "tags = [\"app\"]\n",
+                "tags = [\"app\"]\nversion = \"1.2.0\"\n"
+            )
+        );
+    }
+
+    #[test]
+    fn write_data_should_round_trip_package_data() {
+        let parser = TomlParser;
+        let expected = parser
+            .read_file(Path::new("test-data/deserialize-full.aer.toml"))
+            .unwrap();
+        let mut buffer = vec![];
+
+        parser.write_data(&expected, &mut buffer).unwrap();
+        let actual = parser.read_data(&mut buffer.as_slice()).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn read_data_should_accept_package_accept_language() {
+        let path = PathBuf::from("test-data/accept-language.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(
+            actual.updater().accept_language,
+            Some("de-DE, de;q=0.9".to_owned())
+        );
+    }
+
+    #[test]
+    fn read_data_should_accept_checksum_file() {
+        let path = PathBuf::from("test-data/checksum-file.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(
+            actual.updater().checksum_url,
+            Some(Url::parse("https://example-repo.org/releases/SHA256SUMS").unwrap())
+        );
+        assert_eq!(
+            actual.updater().checksum_regex,
+            Some("SHA256SUMS\\.txt$".to_owned())
+        );
+    }
+
+    #[test]
+    fn read_data_should_accept_signature_settings() {
+        let path = PathBuf::from("test-data/signature.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        let signature = &actual.updater().signature;
+        assert_eq!(signature.gpg_keys, [PathBuf::from("keys/release-key.asc")]);
+        assert_eq!(signature.extension, ".asc");
+        assert!(signature.authenticode);
+        assert!(signature.required);
+    }
+
+    #[test]
+    fn read_data_should_accept_version_source() {
+        let path = PathBuf::from("test-data/version-source.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(
+            actual.updater().version_source,
+            VersionSource::ProductVersion
+        );
+    }
+
+    #[test]
+    fn read_data_should_accept_auth_rules() {
+        let path = PathBuf::from("test-data/auth-rules.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(
+            actual.updater().auth,
+            [AuthRule::new(
+                "https://downloads.example.org/private/*",
+                "internal"
+            )]
+        );
+    }
+
+    #[test]
+    fn read_data_should_accept_github_source() {
+        let path = PathBuf::from("test-data/github-source.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(actual.updater().source, UpdaterSource::GitHub);
+        assert_eq!(
+            actual.updater().repository,
+            Some("WormieCorp/aer".to_owned())
+        );
+    }
+
+    #[test]
+    fn read_data_should_accept_json_source() {
+        let path = PathBuf::from("test-data/json-source.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(actual.updater().source, UpdaterSource::Json);
+        assert_eq!(
+            actual.updater().json,
+            Some(JsonSourceData {
+                urls: "$.assets[*].browser_download_url".into(),
+                version: Some("$.tag_name".into()),
+            })
+        );
+    }
+
+    #[test]
+    fn read_data_should_accept_html_selectors() {
+        let path = PathBuf::from("test-data/html-selectors.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(actual.updater().source, UpdaterSource::Html);
+        assert_eq!(
+            actual.updater().link_selector,
+            Some("table#downloads".into())
+        );
+        assert_eq!(actual.updater().version_selector, Some("td.version".into()));
+    }
+
+    #[test]
+    fn read_data_should_accept_html_pagination() {
+        let path = PathBuf::from("test-data/html-pagination.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(
+            actual.updater().pagination,
+            Some(PaginationData {
+                next: Some("^Older releases".into()),
+                max_pages: 3
+            })
+        );
+    }
+
+    #[test]
+    fn read_data_should_accept_html_rendering() {
+        let path = PathBuf::from("test-data/html-render.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert!(actual.updater().render);
+    }
+
+    #[test]
+    fn read_data_should_accept_link_selection() {
+        let path = PathBuf::from("test-data/link-selection.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(actual.updater().selection, LinkSelection::HighestVersion);
+    }
+
+    #[test]
+    fn read_data_should_accept_prerelease_channels() {
+        let path = PathBuf::from("test-data/prerelease-channels.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert!(actual.updater().allow_prerelease);
+        assert_eq!(actual.updater().channels, vec!["beta".to_string()]);
+        assert_eq!(actual.updater().prerelease_suffix, Some(".beta".into()));
+    }
+
+    #[test]
+    fn read_data_should_accept_version_transforms() {
+        let path = PathBuf::from("test-data/version-transforms.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(
+            actual.updater().version_transforms,
+            vec![
+                VersionTransform::StripPrefix("v".into()),
+                VersionTransform::Replace {
+                    pattern: "_".into(),
+                    replacement: ".".into()
+                },
+                VersionTransform::TakeParts(3)
+            ]
+        );
+    }
+
+    #[test]
+    fn read_data_should_accept_fix_scheme() {
+        let path = PathBuf::from("test-data/fix-scheme.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(
+            actual.updater().chocolatey().fix_scheme,
+            FixScheme::Revision
+        );
+    }
+
+    #[test]
+    fn read_data_should_accept_feed_source() {
+        let path = PathBuf::from("test-data/feed-source.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(actual.updater().source, UpdaterSource::Feed);
+        assert!(matches!(
+            actual.updater().chocolatey().parse_url,
+            Some(ChocolateyParseUrl::UrlWithRegex { .. })
+        ));
+    }
+
+    #[test]
+    fn read_data_should_accept_updater_targets() {
+        let path = PathBuf::from("test-data/updater-targets.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+        let targets = actual.updater().targets();
+
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].0, UpdaterTarget::Chocolatey);
+        assert_eq!(
+            targets[0].1.regexes().get("arch64"),
+            Some(&r"-x64\.exe$".to_string())
+        );
+        assert_eq!(targets[1].0, UpdaterTarget::Winget);
+        assert_eq!(targets[1].1.parse_url, actual.updater().urls.parse_url);
+        assert_eq!(
+            targets[1].1.regexes().get("arch64"),
+            Some(&r"-x64\.msi$".to_string())
+        );
+    }
+
+    #[test]
+    fn read_data_should_accept_architectures() {
+        let path = PathBuf::from("test-data/architectures.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+        let updater = actual.updater();
+
+        assert_eq!(updater.architecture_of("arch64"), Some(Architecture::X64));
+        assert_eq!(updater.architecture_of("arm64"), Some(Architecture::Arm64));
+        assert_eq!(
+            updater.architecture_of("portable"),
+            Some(Architecture::Universal)
+        );
+    }
+
+    #[test]
+    fn read_data_should_accept_xml_source() {
+        let path = PathBuf::from("test-data/xml-source.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        let mut expected = XmlSourceData::new("//item", "enclosure/@url");
+        expected.version = Some("enclosure/@sparkle:version".into());
+        assert_eq!(actual.updater().source, UpdaterSource::Xml);
+        assert_eq!(actual.updater().xml, Some(expected));
+    }
+
+    #[test]
+    fn read_data_should_accept_lints() {
+        let path = PathBuf::from("test-data/lints.aer.toml");
+        let parser = TomlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert!(actual.lints().is_allowed("AER0203"));
+        assert!(actual.lints().is_allowed("AER0212"));
+        assert_eq!(
+            actual.lints().note("AER0203"),
+            Some("The summary is generated by the update script")
+        );
+    }
+
     #[test]
     fn read_data_should_accept_license_expression_and_url() {
         let path = PathBuf::from("test-data/license-short.aer.toml");
@@ -254,6 +1088,30 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn read_data_should_accept_publish_settings() {
+        let path = PathBuf::from("test-data/publish.aer.toml");
+        let parser = TomlParser;
+        let expected = ChocolateyPublish::new()
+            .with_feed(Url::parse("https://nexus.example.org/repository/choco-internal/").unwrap())
+            .with_api_key_env("NEXUS_API_KEY");
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(actual.metadata().chocolatey().publish, Some(expected));
+    }
+
+    #[test]
+    fn read_data_should_accept_publish_profile() {
+        let path = PathBuf::from("test-data/publish-profile.aer.toml");
+        let parser = TomlParser;
+        let expected = ChocolateyPublish::new().with_profile("internal");
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(actual.metadata().chocolatey().publish, Some(expected));
+    }
+
     #[test]
     fn read_data_should_deserialize_all_data() {
         let path = PathBuf::from("test-data/deserialize-full.aer.toml");
@@ -296,6 +1154,8 @@ mod tests {
                 choco
             });
 
+            pkg.updater_mut().check_frequency = CheckFrequency::Weekly;
+            pkg.updater_mut().link_sources = vec![LinkSource::MetaRefresh];
             pkg.updater_mut().set_chocolatey({
                 let mut choco = ChocolateyUpdaterData::new();
                 choco.embedded = true;
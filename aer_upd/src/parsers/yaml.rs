@@ -0,0 +1,193 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+#![cfg_attr(docsrs, doc(cfg(feature = "yaml_data")))]
+
+use std::io::{Read, Write};
+use std::path::Path;
+
+use aer_data::PackageData;
+use log::{debug, error};
+
+use crate::parsers::{errors, DataReader, DataWriter};
+
+pub struct YamlParser;
+
+/// Implements the trait necessary for reading files that are stored in the
+/// `YAML` language, using the same structure as the `TOML` files.
+impl DataReader for YamlParser {
+    fn can_handle_file(&self, path: &Path) -> bool {
+        if let Some(path) = path.to_str() {
+            path.ends_with(".aer.yml") || path.ends_with(".aer.yaml")
+        } else {
+            false
+        }
+    }
+
+    /// Reads and deserializes a `YAML` document in the specified reader passed
+    /// to the function.
+    fn read_data<T>(&self, reader: &mut T) -> Result<PackageData, errors::ParserError>
+    where
+        T: Read,
+    {
+        let config_data: PackageData = {
+            let mut config_text = String::new();
+
+            match reader.read_to_string(&mut config_text) {
+                Err(err) => {
+                    error!("Failed to read data: {:?}", err);
+                    return Err(errors::ParserError::Loading(err));
+                }
+                Ok(size) => debug!("Read {} bytes!", size),
+            }
+
+            debug!("Deserializing YAML Package data");
+            match serde_yaml::from_str(&config_text) {
+                Err(err) => {
+                    error!("Failed to deserialize package data: {:?}", err);
+                    let message = err.to_string();
+                    return Err(match err.location() {
+                        Some(location) => errors::ParserError::DeserializeAt {
+                            message,
+                            line: location.line(),
+                            column: location.column(),
+                        },
+                        None => errors::ParserError::Deserialize(message),
+                    });
+                }
+                Ok(data) => data,
+            }
+        };
+
+        debug!("Package YAML data deserialized, returning package data!");
+
+        Ok(config_data)
+    }
+}
+
+impl DataWriter for YamlParser {
+    fn can_write_file(&self, path: &Path) -> bool {
+        self.can_handle_file(path)
+    }
+
+    /// Serializes the package data as a `YAML` document to the specified
+    /// writer.
+    fn write_data<W: Write>(
+        &self,
+        data: &PackageData,
+        writer: &mut W,
+    ) -> Result<(), errors::ParserError> {
+        debug!("Serializing YAML Package data");
+        serde_yaml::to_writer(writer, data).map_err(|err| {
+            error!("Failed to serialize package data: {:?}", err);
+            errors::ParserError::Serialize(err.to_string())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufReader, Error, ErrorKind};
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    use aer_data::prelude::*;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("test-package.aer.toml")]
+    #[case("test-package.yml")]
+    #[case("test-package.xml")]
+    fn read_file_should_error_for_non_aer_yaml_files(#[case] file: &str) {
+        let path = PathBuf::from_str(file).unwrap();
+        let parser = YamlParser;
+
+        let r = parser.read_file(&path).unwrap_err();
+
+        assert_eq!(
+            r,
+            errors::ParserError::Loading(Error::new(
+                ErrorKind::InvalidData,
+                format!("The file '{}' is not a supported type.", file)
+            ))
+        );
+    }
+
+    #[rstest]
+    #[case("test-package.aer.yml")]
+    #[case("test-package.aer.yaml")]
+    fn can_handle_file_should_accept_both_yaml_extensions(#[case] file: &str) {
+        let parser = YamlParser;
+
+        assert!(parser.can_handle_file(Path::new(file)));
+    }
+
+    #[test]
+    fn read_data_should_include_position_of_deserialization_failure() {
+        const VAL: &[u8] = b"metadata:\n  id: [5]";
+        let mut reader = BufReader::new(VAL);
+        let parser = YamlParser;
+
+        let actual = parser.read_data(&mut reader).unwrap_err();
+
+        assert_eq!(actual.position(), Some((2, 7)));
+    }
+
+    #[test]
+    fn read_data_should_accept_license_url() {
+        let path = PathBuf::from("test-data/license-url.aer.yaml");
+        let parser = YamlParser;
+        let mut expected = PackageData::new("test-package");
+        expected.metadata_mut().set_license(LicenseType::Location(
+            Url::parse("https://github.com/WormieCorp/aer/LICENSE.txt").unwrap(),
+        ));
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn read_data_should_accept_sourceforge_source() {
+        let path = PathBuf::from("test-data/sourceforge-source.aer.yaml");
+        let parser = YamlParser;
+
+        let actual = parser.read_file(&path).unwrap();
+
+        assert_eq!(actual.updater().source, UpdaterSource::SourceForge);
+        assert_eq!(
+            actual.updater().repository,
+            Some("astyle/astyle".to_owned())
+        );
+    }
+
+    #[test]
+    fn write_data_should_round_trip_package_data() {
+        let parser = YamlParser;
+        let expected = parser
+            .read_file(Path::new("test-data/deserialize-full.aer.yml"))
+            .unwrap();
+        let mut buffer = vec![];
+
+        parser.write_data(&expected, &mut buffer).unwrap();
+        let actual = parser.read_data(&mut buffer.as_slice()).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn read_data_should_deserialize_same_data_as_toml() {
+        let expected = crate::parsers::toml::TomlParser
+            .read_file(Path::new("test-data/deserialize-full.aer.toml"))
+            .unwrap();
+        let parser = YamlParser;
+
+        let actual = parser
+            .read_file(Path::new("test-data/deserialize-full.aer.yml"))
+            .unwrap();
+
+        assert_eq!(actual, expected);
+    }
+}
@@ -2,84 +2,273 @@
 // Licensed under the MIT license. See LICENSE.txt file in the project
 
 use std::collections::HashMap;
+use std::ffi::OsString;
 use std::fmt::Debug;
 use std::path::{Path, PathBuf};
+use std::process::Command;
+#[cfg(any(feature = "powershell", feature = "shell"))]
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use aer_data::prelude::*;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use self::errors::RunnerError;
+use crate::context::UpdateContext;
+use crate::features;
+use crate::observer::Stage;
+
+pub mod errors;
+#[cfg(feature = "lua")]
+pub mod lua;
 #[cfg(feature = "powershell")]
 pub mod powershell;
+#[cfg(feature = "shell")]
+pub mod shell;
+
+/// The line written by scripts before the serialized runner data.
+#[cfg(any(feature = "powershell", feature = "shell"))]
+const DATA_START_MARKER: &str = "## AER-SCRIPT-RUNNER:START ##";
+
+/// The line written by scripts after the serialized runner data.
+#[cfg(any(feature = "powershell", feature = "shell"))]
+const DATA_END_MARKER: &str = "## AER-SCRIPT-RUNNER:END ##";
+
+/// The version of the file based protocol used when exchanging data with the
+/// scripts, scripts writing a newer version in the result file are rejected.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// The environment variable holding the path to the file containing the
+/// serialized input of the script.
+pub const INPUT_FILE_VAR: &str = "AER_INPUT_FILE";
+
+/// The environment variable holding the path to the file the script writes
+/// its serialized result to.
+pub const RESULT_FILE_VAR: &str = "AER_RESULT_FILE";
+
+/// The protocol used when exchanging data with scripts that are run by an
+/// external executable (*ie: PowerShell or shell scripts*). Scripts run by an
+/// embedded interpreter are not affected by the protocol.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RunnerProtocol {
+    /// The data is written to the file declared by the `AER_INPUT_FILE`
+    /// variable, and the script writes its result to the file declared by the
+    /// `AER_RESULT_FILE` variable.
+    #[default]
+    Files,
+    /// The legacy protocol, where the data is passed in the command or the
+    /// environment of the script, and the changed data is read from the output
+    /// written between the `## AER-SCRIPT-RUNNER:START ##` and
+    /// `## AER-SCRIPT-RUNNER:END ##` markers.
+    Markers,
+}
 
+/// The data exchanged with the scripts of a package, holding the values as
+/// text, lists of text or nested data.
+#[derive(Debug, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct RunnerData {
     #[cfg_attr(feature = "serde", serde(default, flatten))]
     data: HashMap<String, RunnerChildType>,
 }
 
+/// A single value in the data exchanged with the scripts.
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 #[cfg_attr(feature = "serde", serde(untagged))]
 pub enum RunnerChildType {
     Data(String),
+    List(Vec<String>),
     Child(RunnerData),
 }
 
 impl RunnerData {
-    fn new() -> RunnerData {
+    /// Creates a new empty structure of runner data.
+    pub fn new() -> RunnerData {
         RunnerData {
             data: HashMap::new(),
         }
     }
 
-    fn insert<T: ToString>(&mut self, key: &'static str, value: T) {
+    /// Inserts the text representation of the value with the specified key.
+    pub fn insert<K: Into<String>, T: ToString>(&mut self, key: K, value: T) {
         let val = value.to_string();
 
         self.data.insert(key.into(), RunnerChildType::Data(val));
     }
 
-    fn insert_child(&mut self, key: &'static str, value: RunnerData) {
+    /// Inserts the text representations of the values as a list with the
+    /// specified key.
+    pub fn insert_list<K: Into<String>, T: ToString>(&mut self, key: K, values: &[T]) {
+        let values = values.iter().map(|val| val.to_string()).collect();
+
+        self.data.insert(key.into(), RunnerChildType::List(values));
+    }
+
+    /// Inserts the nested data with the specified key.
+    pub fn insert_child<K: Into<String>>(&mut self, key: K, value: RunnerData) {
         self.data.insert(key.into(), RunnerChildType::Child(value));
     }
+
+    /// Returns the value with the specified key.
+    pub fn get(&self, key: &str) -> Option<&RunnerChildType> {
+        self.data.get(key)
+    }
 }
 
-pub trait ScriptRunner {
+/// The environment variables that are always passed through to scripts when
+/// the environment is scrubbed, these are needed for most processes to start
+/// at all.
+pub const DEFAULT_PASSTHROUGH: &[&str] = &[
+    "PATH",
+    "PATHEXT",
+    "SYSTEMROOT",
+    "WINDIR",
+    "COMSPEC",
+    "HOME",
+    "USERPROFILE",
+    "LANG",
+    "LC_ALL",
+    "PSModulePath",
+];
+
+/// The environment variables that scripts are run with. By default the
+/// scripts inherit the whole environment of the current process, a scrubbed
+/// environment only passes through the variables that have been explicitly
+/// allowed.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ScriptEnvironment {
+    passthrough: Option<Vec<String>>,
+    vars: Vec<(String, OsString)>,
+    protocol: RunnerProtocol,
+}
+
+impl ScriptEnvironment {
+    /// Creates a new environment that inherits every variable of the current
+    /// process.
+    pub fn inherit() -> ScriptEnvironment {
+        ScriptEnvironment::default()
+    }
+
+    /// Creates a new environment that only passes through the
+    /// [default](DEFAULT_PASSTHROUGH) variables, and the specified variables
+    /// of the current process.
+    pub fn scrubbed<I: IntoIterator<Item = S>, S: Into<String>>(
+        passthrough: I,
+    ) -> ScriptEnvironment {
+        let mut names: Vec<String> = DEFAULT_PASSTHROUGH.iter().map(|&n| n.into()).collect();
+        for name in passthrough {
+            let name = name.into();
+            if !names.iter().any(|n| n.eq_ignore_ascii_case(&name)) {
+                names.push(name);
+            }
+        }
+
+        ScriptEnvironment {
+            passthrough: Some(names),
+            vars: vec![],
+            protocol: RunnerProtocol::default(),
+        }
+    }
+
+    /// Sets a variable that will always be passed to the scripts, overriding
+    /// the value of the current process.
+    pub fn with_var<K: Into<String>, V: Into<OsString>>(mut self, key: K, val: V) -> Self {
+        let key = key.into();
+        self.vars.retain(|(k, _)| k != &key);
+        self.vars.push((key, val.into()));
+        self
+    }
+
+    /// Sets the protocol used when exchanging data with the scripts.
+    pub fn with_protocol(mut self, protocol: RunnerProtocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Returns the protocol used when exchanging data with the scripts.
+    pub fn protocol(&self) -> RunnerProtocol {
+        self.protocol
+    }
+
+    /// Returns wether only the allowed variables are passed to the scripts.
+    pub fn is_scrubbed(&self) -> bool {
+        self.passthrough.is_some()
+    }
+
+    /// Returns the variables that will be set in addition to the variables
+    /// that are passed through.
+    pub fn vars(&self) -> &[(String, OsString)] {
+        &self.vars
+    }
+
+    /// Applies the environment to the specified command.
+    pub fn apply(&self, cmd: &mut Command) {
+        if let Some(ref passthrough) = self.passthrough {
+            cmd.env_clear();
+            for name in passthrough {
+                if let Some(val) = std::env::var_os(name) {
+                    cmd.env(name, val);
+                }
+            }
+        }
+
+        for (key, val) in &self.vars {
+            cmd.env(key, val);
+        }
+    }
+}
+
+/// Runners implementing this trait are able to run the scripts of a package
+/// written in a specific language.
+///
+/// Runners must be `Send` and `Sync`, and must not keep any state between
+/// runs, as the same runner is used by every thread running scripts.
+pub trait ScriptRunner: Send + Sync {
     fn can_run(&self, script_path: &Path) -> bool;
     fn run<'a, T: RunnerCombiner + Debug>(
         &self,
         work_dir: &'a Path,
+        env: &'a ScriptEnvironment,
         script_path: PathBuf,
         data: &'a mut T,
-    ) -> Result<(), String>;
+    ) -> Result<(), RunnerError>;
 }
 
-#[cfg(any(feature = "powershell"))]
-#[cfg_attr(docsrs, doc(cfg(any(feature = "powershell"))))]
 macro_rules! call_runners {
-    ($work_dir:ident,$script_path:ident,$data:ident,$($runner:expr=>$feature:literal),+) => {
+    ($work_dir:ident,$env:ident,$script_path:ident,$data:ident,$($runner:expr=>$feature:literal),+) => {
         let script_path = $script_path.canonicalize().unwrap();
         let work_dir = $work_dir.canonicalize().unwrap();
         $(
             #[cfg(feature = $feature)]
             if $runner.can_run(&script_path) {
-                return $runner.run(&work_dir, script_path, $data);
+                return $runner.run(&work_dir, $env, script_path, $data);
             }
         )*
     };
 }
 
-#[cfg(any(feature = "powershell"))]
-#[cfg_attr(docsrs, doc(cfg(any(feature = "powershell"))))]
 pub fn run_script<T: RunnerCombiner + Debug>(
     work_dir: &Path,
     script_path: PathBuf,
     data: &mut T,
-) -> Result<(), String> {
+) -> Result<(), RunnerError> {
+    run_script_with_env(work_dir, &ScriptEnvironment::inherit(), script_path, data)
+}
+
+/// Runs the specified script in the work directory, using the specified
+/// environment variables instead of inheriting the whole environment.
+pub fn run_script_with_env<T: RunnerCombiner + Debug>(
+    work_dir: &Path,
+    env: &ScriptEnvironment,
+    script_path: PathBuf,
+    data: &mut T,
+) -> Result<(), RunnerError> {
     if !work_dir.exists() {
         if let Err(err) = std::fs::create_dir_all(work_dir) {
-            let msg = format!("Failed to create work directory: '{}'", err);
-            log::error!("{}", msg);
-            return Err(msg);
+            let err = RunnerError::WorkDirectory(err);
+            log::error!("{}", err);
+            return Err(err);
         }
     }
 
@@ -90,23 +279,201 @@ pub fn run_script<T: RunnerCombiner + Debug>(
     };
 
     if !work_dir.is_dir() {
-        return Err(format!(
-            "The specified directory '{}' is not a directory!",
-            work_dir.display()
-        ));
+        return Err(RunnerError::NotADirectory(work_dir.clone()));
     }
 
     call_runners!(
         work_dir,
+        env,
         script_path,
         data,
-        powershell::PowershellRunner => "powershell"
+        powershell::PowershellRunner => "powershell",
+        shell::ShellRunner => "shell",
+        lua::LuaRunner => "lua"
     );
 
-    Err(format!(
-        "No supported runner was found for '{}'",
-        script_path.display()
-    ))
+    if let Some(feature) = features::for_script(&script_path) {
+        features::require(feature, format_args!("Running '{}'", script_path.display()))?;
+    }
+
+    Err(RunnerError::NoRunner(script_path))
+}
+
+/// Runs the specified script in the work directory and the environment of the
+/// context, after checking that the update process have not been cancelled.
+pub fn run_script_in<T: RunnerCombiner + Debug>(
+    context: &UpdateContext,
+    script_path: PathBuf,
+    data: &mut T,
+) -> Result<(), RunnerError> {
+    context.check_cancelled()?;
+    context.progress(format_args!("Running script '{}'", script_path.display()));
+
+    context.stage(Stage::RunScript, || {
+        run_script_with_env(context.work_dir(), context.environment(), script_path, data)
+    })
+}
+
+/// Reads the output written by a script, and returns the serialized runner
+/// data written between the start and end markers. Every other line is
+/// logged, lines starting with `WARNING:` are logged as warnings.
+#[cfg(any(feature = "powershell", feature = "shell"))]
+fn read_runner_output(stdout: &str) -> String {
+    let mut run_data = String::new();
+    let mut in_data = false;
+    log::debug!("AER-SCRIPT-RUNNER STDOUT:");
+
+    for line in stdout.lines() {
+        match line.trim() {
+            DATA_START_MARKER => in_data = true,
+            DATA_END_MARKER => in_data = false,
+            line => {
+                if in_data {
+                    run_data.push_str(line);
+                } else if line.starts_with("WARNING:") {
+                    log::warn!("{}", line);
+                } else {
+                    log::debug!("{}", line);
+                }
+            }
+        }
+    }
+
+    run_data
+}
+
+/// The content of the input file written before a script is run.
+#[cfg(any(feature = "powershell", feature = "shell"))]
+#[derive(Serialize)]
+struct RunnerInput<'a> {
+    protocol_version: u32,
+    data: &'a RunnerData,
+}
+
+/// The content of the result file written by a script, holding either the
+/// changed data or the error reported by the script.
+#[cfg(any(feature = "powershell", feature = "shell"))]
+#[derive(Deserialize)]
+struct RunnerResult {
+    protocol_version: u32,
+    #[serde(default)]
+    data: Option<RunnerData>,
+    #[serde(default)]
+    error: Option<ScriptError>,
+}
+
+/// A structured error reported by a script in its result file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ScriptError {
+    /// The message describing the error.
+    pub message: String,
+    /// The code identifying the kind of error, if the script specified one.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub code: Option<String>,
+}
+
+impl std::fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.code {
+            Some(ref code) => write!(f, "[{}] {}", code, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// The input and result files used when exchanging data with a script using
+/// the file based protocol, both files are removed when dropped.
+#[cfg(any(feature = "powershell", feature = "shell"))]
+pub(crate) struct ExchangeFiles {
+    input: PathBuf,
+    result: PathBuf,
+}
+
+#[cfg(any(feature = "powershell", feature = "shell"))]
+impl ExchangeFiles {
+    /// Writes the input file containing the specified data to the work
+    /// directory.
+    pub(crate) fn create(work_dir: &Path, data: &RunnerData) -> Result<ExchangeFiles, RunnerError> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let name = format!(
+            "aer-runner-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let files = ExchangeFiles {
+            input: work_dir.join(format!("{}.input.json", name)),
+            result: work_dir.join(format!("{}.result.json", name)),
+        };
+
+        let input = serde_json::to_string(&RunnerInput {
+            protocol_version: PROTOCOL_VERSION,
+            data,
+        })
+        .map_err(|err| RunnerError::Serialize(err.to_string()))?;
+        std::fs::write(&files.input, input).map_err(|source| RunnerError::WriteInput {
+            path: files.input.clone(),
+            source,
+        })?;
+
+        Ok(files)
+    }
+
+    /// Sets the variables declaring the input and result files on the
+    /// specified command.
+    pub(crate) fn apply(&self, cmd: &mut Command) {
+        cmd.env(INPUT_FILE_VAR, &self.input)
+            .env(RESULT_FILE_VAR, &self.result);
+    }
+
+    /// Reads the result file written by the script, no data is returned when
+    /// the script did not write a result file.
+    pub(crate) fn read_result(&self) -> Result<Option<RunnerData>, RunnerError> {
+        if !self.result.exists() {
+            log::trace!("No result file was written by the script");
+            return Ok(None);
+        }
+
+        let text =
+            std::fs::read_to_string(&self.result).map_err(|source| RunnerError::ReadResult {
+                path: self.result.clone(),
+                source,
+            })?;
+
+        parse_result(&text)
+    }
+}
+
+#[cfg(any(feature = "powershell", feature = "shell"))]
+impl Drop for ExchangeFiles {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.input);
+        let _ = std::fs::remove_file(&self.result);
+    }
+}
+
+/// Parses the content of a result file, returning the error reported by the
+/// script or the changed data.
+#[cfg(any(feature = "powershell", feature = "shell"))]
+fn parse_result(text: &str) -> Result<Option<RunnerData>, RunnerError> {
+    // PowerShell may write the result file with a byte order mark.
+    let text = text.trim_start_matches('\u{feff}');
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let result: RunnerResult = serde_json::from_str(text)
+        .map_err(|err| RunnerError::DeserializeResult(err.to_string()))?;
+
+    if result.protocol_version == 0 || result.protocol_version > PROTOCOL_VERSION {
+        return Err(RunnerError::UnsupportedProtocol(result.protocol_version));
+    }
+
+    if let Some(error) = result.error {
+        return Err(RunnerError::Script(error));
+    }
+
+    Ok(result.data)
 }
 
 pub trait RunnerCombiner {
@@ -115,6 +482,35 @@ pub trait RunnerCombiner {
     fn from_runner_data(&mut self, data: RunnerData);
 }
 
+/// The package data is exposed to the scripts with the following values,
+/// where only the values marked as mutable are read back after the script
+/// have been run. Any other changes made by the script are ignored.
+///
+/// | Key                          | Type      | Mutable |
+/// |------------------------------|-----------|---------|
+/// | `id`                         | text      | no      |
+/// | `url`                        | text      | no      |
+/// | `project_url`                | text      | yes     |
+/// | `summary`                    | text      | yes     |
+/// | `maintainers`                | list      | yes     |
+/// | `license.url`                | text      | yes     |
+/// | `license.expr`               | text      | yes     |
+/// | `version`                    | text      | yes     |
+/// | `chocolatey.title`           | text      | yes     |
+/// | `chocolatey.copyright`       | text      | yes     |
+/// | `chocolatey.authors`         | list      | yes     |
+/// | `chocolatey.description`     | text      | yes     |
+/// | `chocolatey.release_notes`   | text      | yes     |
+/// | `chocolatey.tags`            | list      | yes     |
+/// | `chocolatey.dependencies`    | id=version| yes     |
+/// | `updater.repository`         | text      | no      |
+/// | `updater.accept_language`    | text      | no      |
+/// | `updater.chocolatey.regexes` | name=regex| no      |
+///
+/// The `url` value is an alias of `project_url` that is kept for existing
+/// scripts, and the `version` value is the version of the Chocolatey package.
+/// Values that can not be parsed (*ie: invalid versions*) are ignored with a
+/// warning.
 impl RunnerCombiner for aer_data::PackageData {
     fn to_runner_data(&self) -> RunnerData {
         let mut data = RunnerData::new();
@@ -123,6 +519,9 @@ impl RunnerCombiner for aer_data::PackageData {
             let metadata = self.metadata();
             data.insert("id", metadata.id());
             data.insert("url", metadata.project_url());
+            data.insert("project_url", metadata.project_url());
+            data.insert("summary", &metadata.summary);
+            data.insert_list("maintainers", metadata.maintainers());
 
             let license = metadata.license();
             let mut license_child = RunnerData::new();
@@ -140,6 +539,38 @@ impl RunnerCombiner for aer_data::PackageData {
             }
 
             data.insert_child("license", license_child);
+
+            #[cfg(feature = "chocolatey")]
+            {
+                let choco = metadata.chocolatey();
+                data.insert("version", &choco.version);
+                data.insert_child("chocolatey", chocolatey_runner_data(&choco));
+            }
+        }
+
+        {
+            let updater = self.updater();
+            let mut updater_child = RunnerData::new();
+
+            if let Some(ref repository) = updater.repository {
+                updater_child.insert("repository", repository);
+            }
+            if let Some(ref accept_language) = updater.accept_language {
+                updater_child.insert("accept_language", accept_language);
+            }
+
+            #[cfg(feature = "chocolatey")]
+            {
+                let mut choco_child = RunnerData::new();
+                let mut regexes = RunnerData::new();
+                for (name, regex) in updater.chocolatey().regexes() {
+                    regexes.insert(name.as_str(), regex);
+                }
+                choco_child.insert_child("regexes", regexes);
+                updater_child.insert_child("chocolatey", choco_child);
+            }
+
+            data.insert_child("updater", updater_child);
         }
 
         data
@@ -151,18 +582,104 @@ impl RunnerCombiner for aer_data::PackageData {
                 RunnerChildType::Data(val) => match key.trim() {
                     "project_url" => self.metadata_mut().set_project_url(&val),
                     "summary" => self.metadata_mut().summary = val,
+                    #[cfg(feature = "chocolatey")]
+                    "version" => match Versions::parse(&val) {
+                        Ok(version) => {
+                            let mut choco = self.metadata().chocolatey().into_owned();
+                            choco.version = version;
+                            self.metadata_mut().set_chocolatey(choco);
+                        }
+                        Err(err) => {
+                            log::warn!("Ignoring invalid version '{}' set by script: {}", val, err)
+                        }
+                    },
                     _ => {}
                 },
-                RunnerChildType::Child(val) => {
-                    if let "license" = key.trim() {
-                        self.metadata_mut().set_license(get_license(val));
+                RunnerChildType::List(val) => {
+                    if let "maintainers" = key.trim() {
+                        self.metadata_mut().set_maintainers(&val);
                     }
                 }
+                RunnerChildType::Child(val) => match key.trim() {
+                    "license" => self.metadata_mut().set_license(get_license(val)),
+                    #[cfg(feature = "chocolatey")]
+                    "chocolatey" => {
+                        let mut choco = self.metadata().chocolatey().into_owned();
+                        update_chocolatey(&mut choco, val);
+                        self.metadata_mut().set_chocolatey(choco);
+                    }
+                    _ => {}
+                },
             }
         }
     }
 }
 
+#[cfg(feature = "chocolatey")]
+fn chocolatey_runner_data(choco: &chocolatey::ChocolateyMetadata) -> RunnerData {
+    let mut data = RunnerData::new();
+
+    if let Some(ref title) = choco.title {
+        data.insert("title", title);
+    }
+    if let Some(ref copyright) = choco.copyright {
+        data.insert("copyright", copyright);
+    }
+    data.insert_list("authors", choco.authors());
+    if let Description::Text(ref description) = choco.description() {
+        data.insert("description", description);
+    }
+    if let Some(release_notes) = choco.release_notes() {
+        data.insert("release_notes", release_notes);
+    }
+    data.insert_list("tags", choco.tags());
+
+    let mut dependencies = RunnerData::new();
+    for (id, version) in choco.dependencies() {
+        dependencies.insert(id.as_str(), version);
+    }
+    data.insert_child("dependencies", dependencies);
+
+    data
+}
+
+#[cfg(feature = "chocolatey")]
+fn update_chocolatey(choco: &mut chocolatey::ChocolateyMetadata, data: RunnerData) {
+    for (key, val) in data.data {
+        match (key.trim(), val) {
+            ("title", RunnerChildType::Data(val)) => choco.set_title(&val),
+            ("copyright", RunnerChildType::Data(val)) => choco.set_copyright(&val),
+            ("description", RunnerChildType::Data(val)) => choco.set_description_str(&val),
+            ("release_notes", RunnerChildType::Data(val)) => choco.set_release_notes(&val),
+            ("authors", RunnerChildType::List(val)) if !val.is_empty() => choco.set_authors(&val),
+            ("tags", RunnerChildType::List(val)) => {
+                choco.set_tags(&val);
+            }
+            ("dependencies", RunnerChildType::Child(val)) => {
+                let mut dependencies = HashMap::new();
+                for (id, version) in val.data {
+                    if let RunnerChildType::Data(version) = version {
+                        match Versions::parse(&version) {
+                            Ok(version) => {
+                                dependencies.insert(id, version);
+                            }
+                            Err(err) => log::warn!(
+                                "Ignoring invalid version '{}' of dependency '{}' set by script: \
+                                 {}",
+                                version,
+                                id,
+                                err
+                            ),
+                        }
+                    }
+                }
+                choco.set_dependencies(dependencies);
+            }
+            _ => {}
+        }
+    }
+}
+
 fn get_license(values: RunnerData) -> LicenseType {
     let mut license = LicenseType::None;
 
@@ -206,6 +723,117 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn scrubbed_should_only_add_passthrough_variables_once() {
+        let env = ScriptEnvironment::scrubbed(vec!["path", "CI", "CI"]);
+
+        assert_eq!(
+            env.passthrough.unwrap().len(),
+            DEFAULT_PASSTHROUGH.len() + 1
+        );
+    }
+
+    #[test]
+    fn apply_should_clear_environment_and_set_variables() {
+        let env = ScriptEnvironment::scrubbed(Vec::<String>::new()).with_var("TEMP", "/tmp/aer");
+        let mut cmd = Command::new("test");
+
+        env.apply(&mut cmd);
+
+        let vars: Vec<_> = cmd
+            .get_envs()
+            .map(|(key, _)| key.to_string_lossy().into_owned())
+            .collect();
+        assert!(vars
+            .iter()
+            .all(|key| key == "TEMP" || DEFAULT_PASSTHROUGH.iter().any(|name| name == key)));
+        assert!(vars.contains(&"TEMP".to_owned()));
+    }
+
+    #[test]
+    fn to_runner_data_should_include_metadata_and_updater_data() {
+        let mut data = PackageData::new("test-package");
+        data.metadata_mut().set_maintainers(&["AdmiringWorm"]);
+        let mut choco = chocolatey::ChocolateyMetadata::with_authors(&["WormieCorp"]);
+        choco.version = Versions::parse("1.2.0").unwrap();
+        choco.set_tags(&["aer", "updater"]);
+        choco.add_dependencies("dotnet", "4.8");
+        data.metadata_mut().set_chocolatey(choco);
+        data.updater_mut().repository = Some("WormieCorp/aer".into());
+
+        let actual = data.to_runner_data();
+
+        assert_eq!(
+            actual.get("id"),
+            Some(&RunnerChildType::Data("test-package".into()))
+        );
+        assert_eq!(
+            actual.get("version"),
+            Some(&RunnerChildType::Data("1.2.0".into()))
+        );
+        assert_eq!(
+            actual.get("maintainers"),
+            Some(&RunnerChildType::List(vec!["AdmiringWorm".into()]))
+        );
+        let choco = match actual.get("chocolatey") {
+            Some(RunnerChildType::Child(choco)) => choco,
+            val => panic!("Unexpected chocolatey data: {:?}", val),
+        };
+        assert_eq!(
+            choco.get("tags"),
+            Some(&RunnerChildType::List(vec!["aer".into(), "updater".into()]))
+        );
+        assert!(
+            matches!(choco.get("dependencies"), Some(RunnerChildType::Child(deps)) if deps.get("dotnet") == Some(&RunnerChildType::Data("4.8".into())))
+        );
+        assert!(
+            matches!(actual.get("updater"), Some(RunnerChildType::Child(updater)) if updater.get("repository") == Some(&RunnerChildType::Data("WormieCorp/aer".into())))
+        );
+    }
+
+    #[test]
+    fn from_runner_data_should_only_change_mutable_values() {
+        let mut data = PackageData::new("test-package");
+        let mut choco_data = RunnerData::new();
+        choco_data.insert("title", "Test Package");
+        choco_data.insert_list("authors", &["WormieCorp"]);
+        choco_data.insert_list("tags", &["aer"]);
+        choco_data.insert("release_notes", "https://example.org/changelog");
+        let mut runner_data = RunnerData::new();
+        runner_data.insert("id", "new-identifier");
+        runner_data.insert("version", "2.0.0");
+        runner_data.insert_list("maintainers", &["AdmiringWorm"]);
+        runner_data.insert_child("chocolatey", choco_data);
+
+        data.from_runner_data(runner_data);
+
+        let choco = data.metadata().chocolatey();
+        assert_eq!(data.metadata().id(), "test-package");
+        assert_eq!(data.metadata().maintainers(), ["AdmiringWorm"]);
+        assert_eq!(choco.version, Versions::parse("2.0.0").unwrap());
+        assert_eq!(choco.title.as_deref(), Some("Test Package"));
+        assert_eq!(choco.authors(), ["WormieCorp"]);
+        assert_eq!(choco.tags(), ["aer"]);
+        assert_eq!(choco.release_notes(), Some("https://example.org/changelog"));
+    }
+
+    #[test]
+    fn from_runner_data_should_ignore_invalid_versions() {
+        let mut data = PackageData::new("test-package");
+        let mut runner_data = RunnerData::new();
+        runner_data.insert("version", "not a version");
+
+        data.from_runner_data(runner_data);
+
+        assert_eq!(
+            data.metadata().chocolatey().version,
+            PackageData::new("test-package")
+                .metadata()
+                .chocolatey()
+                .version
+        );
+    }
+
     #[test]
     fn get_license_should_get_license_expression() {
         let mut data = RunnerData::new();
@@ -246,6 +874,77 @@ mod tests {
         );
     }
 
+    #[cfg(any(feature = "powershell", feature = "shell"))]
+    #[test]
+    fn read_runner_output_should_only_return_data_between_markers() {
+        let stdout = "Some output\n## AER-SCRIPT-RUNNER:START ##\n{\n  \"summary\": \
+                      \"test\"\n}\n## AER-SCRIPT-RUNNER:END ##\nWARNING: more output";
+
+        let actual = read_runner_output(stdout);
+
+        assert_eq!(actual, "{\"summary\": \"test\"}");
+    }
+
+    #[cfg(any(feature = "powershell", feature = "shell"))]
+    #[test]
+    fn parse_result_should_return_data() {
+        let actual = parse_result(
+            "\u{feff}{ \"protocol_version\": 2, \"data\": { \"summary\": \"test\" } }",
+        );
+
+        let mut expected = RunnerData::new();
+        expected.insert("summary", "test");
+        assert_eq!(actual.map_err(|err| err.to_string()), Ok(Some(expected)));
+    }
+
+    #[cfg(any(feature = "powershell", feature = "shell"))]
+    #[rstest::rstest(text, case(""), case("{ \"protocol_version\": 1 }"))]
+    fn parse_result_should_return_no_data(text: &str) {
+        assert_eq!(parse_result(text).map_err(|err| err.to_string()), Ok(None));
+    }
+
+    #[cfg(any(feature = "powershell", feature = "shell"))]
+    #[test]
+    fn parse_result_should_return_reported_error() {
+        let actual = parse_result(
+            r#"{ "protocol_version": 2, "error": { "message": "Not found", "code": "E404" } }"#,
+        );
+
+        assert_eq!(
+            actual.map_err(|err| err.to_string()),
+            Err("The script reported an error: [E404] Not found".into())
+        );
+    }
+
+    #[cfg(any(feature = "powershell", feature = "shell"))]
+    #[rstest::rstest(
+        text,
+        case("{ \"protocol_version\": 3 }"),
+        case("{ \"protocol_version\": 0 }"),
+        case("{ \"data\": {} }")
+    )]
+    fn parse_result_should_return_error_on_invalid_result(text: &str) {
+        assert!(parse_result(text).is_err());
+    }
+
+    #[cfg(any(feature = "powershell", feature = "shell"))]
+    #[test]
+    fn exchange_files_should_write_input_and_remove_files_when_dropped() {
+        let work_dir = std::env::temp_dir();
+        let mut data = RunnerData::new();
+        data.insert("id", "test");
+
+        let files = ExchangeFiles::create(&work_dir, &data).unwrap();
+        let input = std::fs::read_to_string(&files.input).unwrap();
+        std::fs::write(&files.result, "").unwrap();
+        let paths = (files.input.clone(), files.result.clone());
+        drop(files);
+
+        assert_eq!(input, r#"{"protocol_version":2,"data":{"id":"test"}}"#);
+        assert!(!paths.0.exists());
+        assert!(!paths.1.exists());
+    }
+
     #[test]
     fn get_license_should_return_no_license_on_invalid_data() {
         let mut data = RunnerData::new();
@@ -282,7 +981,8 @@ mod tests {
                 &PathBuf::from("."),
                 PathBuf::from(file_path),
                 &mut PackageData::new("test-package"),
-            );
+            )
+            .map_err(|err| err.to_string());
 
             assert_eq!(result, Ok(()))
         }
@@ -296,7 +996,8 @@ mod tests {
                 &PathBuf::from(".").canonicalize().unwrap(),
                 file_path.clone(),
                 &mut PackageData::new("test-package"),
-            );
+            )
+            .map_err(|err| err.to_string());
 
             assert_eq!(
                 result,
@@ -314,7 +1015,8 @@ mod tests {
                 &work_dir,
                 PathBuf::from("test"),
                 &mut PackageData::new("test-data"),
-            );
+            )
+            .map_err(|err| err.to_string());
 
             assert_eq!(
                 result,
@@ -325,6 +1027,21 @@ mod tests {
             );
         }
 
+        #[test]
+        fn run_script_in_should_return_error_when_cancelled() {
+            let context = UpdateContext::new().with_work_dir(".");
+            context.cancellation().cancel();
+
+            let result = run_script_in(
+                &context,
+                PathBuf::from("test"),
+                &mut PackageData::new("test-package"),
+            )
+            .map_err(|err| err.to_string());
+
+            assert_eq!(result, Err("The update process was cancelled!".into()));
+        }
+
         #[test]
         fn run_script_should_create_work_directory_if_not_exists() {
             let path = PathBuf::from("test-files/work_dir");
@@ -0,0 +1,130 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+use std::path::PathBuf;
+use std::process::ExitStatus;
+
+use super::{ScriptError, PROTOCOL_VERSION};
+use crate::context::Cancelled;
+use crate::features::FeatureError;
+
+/// The errors that can occur when running the scripts of a package.
+#[derive(Debug, thiserror::Error)]
+pub enum RunnerError {
+    /// The work directory the script is run in could not be created.
+    #[error("Failed to create work directory: '{0}'")]
+    WorkDirectory(#[source] std::io::Error),
+    /// The work directory the script is run in is not a directory.
+    #[error("The specified directory '{}' is not a directory!", .0.display())]
+    NotADirectory(PathBuf),
+    /// The script can only be run by a runner that was not enabled when the
+    /// crate was compiled.
+    #[error(transparent)]
+    FeatureDisabled(#[from] FeatureError),
+    /// None of the runners are able to run the script.
+    #[error("No supported runner was found for '{}'", .0.display())]
+    NoRunner(PathBuf),
+    /// The update process was cancelled before the script was run.
+    #[error(transparent)]
+    Cancelled(#[from] Cancelled),
+    /// The script do not exist.
+    #[error("Unable to find '{}': {source}", path.display())]
+    ScriptNotFound {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The script could not be read.
+    #[error("Unable to read '{}': {source}", path.display())]
+    ReadScript {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The executable running the script do not exist.
+    #[error("No {0} executable was found!")]
+    MissingExecutable(&'static str),
+    /// The executable running the script could not be started.
+    #[error("No {runner} executable was found! ({source})")]
+    Spawn {
+        /// The name of the executable (*ie: `shell`*).
+        runner: &'static str,
+        source: std::io::Error,
+    },
+    /// Waiting on the executable running the script failed.
+    #[error("The running of {runner} failed with '{source}'")]
+    Wait {
+        /// The description of what was running (*ie: `the shell script`*).
+        runner: &'static str,
+        source: std::io::Error,
+    },
+    /// The script exited with a non-zero exit code.
+    #[error(
+        "The {runner} script exited with a non-zero exit code ({status})!{}",
+        with_output(output)
+    )]
+    ExitCode {
+        /// The name of the script language (*ie: `PowerShell`*).
+        runner: &'static str,
+        status: ExitStatus,
+        /// The output the script wrote to the standard error.
+        output: String,
+    },
+    /// The PowerShell script wrote to the standard error, holding the output.
+    #[error("An exception occurred when running the PowerShell script!\n{0}")]
+    Exception(String),
+    /// The Lua script raised an error.
+    #[cfg(feature = "lua")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "lua")))]
+    #[error("An error occurred when running the Lua script!\n{0}")]
+    Lua(#[source] mlua::Error),
+    /// The data passed to the script could not be serialized.
+    #[error("Serializing script runner data failed with: {0}")]
+    Serialize(String),
+    /// The data written by the script between the output markers could not be
+    /// deserialized.
+    #[error("Deserializing script runner data failed with: {0}")]
+    Deserialize(String),
+    /// The result file written by the script could not be deserialized.
+    #[error("Deserializing script result failed with: {0}")]
+    DeserializeResult(String),
+    /// The input file of the script could not be written.
+    #[error("Failed to write the script input file '{}': {source}", path.display())]
+    WriteInput {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The result file written by the script could not be read.
+    #[error("Failed to read the script result file '{}': {source}", path.display())]
+    ReadResult {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The result file uses a protocol version that is not supported.
+    #[error(
+        "The script result uses the unsupported protocol version {0} (supported: {})!",
+        PROTOCOL_VERSION
+    )]
+    UnsupportedProtocol(u32),
+    /// The script reported an error in its result file.
+    #[error("The script reported an error: {0}")]
+    Script(ScriptError),
+}
+
+impl RunnerError {
+    /// Returns the stable code identifying the kind of error (*ie:
+    /// `AER0401`*), which can be used when searching the documentation.
+    pub fn code(&self) -> &'static str {
+        match self {
+            RunnerError::FeatureDisabled(err) => err.code(),
+            RunnerError::Cancelled(_) => "AER0402",
+            _ => "AER0401",
+        }
+    }
+}
+
+fn with_output(output: &str) -> String {
+    if output.is_empty() {
+        String::new()
+    } else {
+        format!("\n{}", output)
+    }
+}
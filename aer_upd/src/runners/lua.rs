@@ -0,0 +1,270 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the runner responsible for running Lua scripts (`*.lua`) in an
+//! embedded interpreter, allowing hooks to be written without requiring any
+//! external interpreter to be installed.
+//!
+//! The package data is exposed to the script as the global `data` table,
+//! which the script can modify. Only the values that are allowed to be
+//! changed by scripts are read back from the table (*ie: the identifier of
+//! the package is never changed*). The scripts are run in a sandbox where
+//! only the `table`, `string`, `math` and `utf8` libraries are available, and
+//! `print` writes to the log of the application. Lists are exposed as
+//! sequences, and tables only containing the keys `1..n` (*or no keys at
+//! all*) are read back as lists.
+//!
+//! ```lua
+//! data.summary = "The summary of " .. data.id
+//! data.license = { expr = "MIT" }
+//! table.insert(data.chocolatey.tags, "updater")
+//! ```
+
+#![cfg_attr(docsrs, doc(cfg(feature = "lua")))]
+
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+
+use log::{error, info, trace};
+use mlua::{Lua, LuaOptions, StdLib, Table, Value, Variadic};
+
+use crate::runners::errors::RunnerError;
+use crate::runners::{
+    RunnerChildType, RunnerCombiner, RunnerData, ScriptEnvironment, ScriptRunner,
+};
+
+/// The name of the global table holding the package data.
+const DATA_GLOBAL: &str = "data";
+
+pub struct LuaRunner;
+
+impl ScriptRunner for LuaRunner {
+    fn can_run(&self, script_path: &Path) -> bool {
+        script_path.to_string_lossy().ends_with(".lua")
+    }
+
+    fn run<'a, T: RunnerCombiner + Debug>(
+        &self,
+        _cwd: &'a Path,
+        _env: &'a ScriptEnvironment,
+        script: PathBuf,
+        data: &'a mut T,
+    ) -> Result<(), RunnerError> {
+        let source =
+            std::fs::read_to_string(&script).map_err(|source| RunnerError::ReadScript {
+                path: script.clone(),
+                source,
+            })?;
+        trace!("Data before running: {:?}", data);
+        info!("Running script: {}", script.display());
+
+        let run_data = run_source(
+            &source,
+            &script.display().to_string(),
+            data.to_runner_data(),
+        )
+        .map_err(|err| {
+            error!("{}", err);
+            RunnerError::Lua(err)
+        })?;
+
+        data.from_runner_data(run_data);
+        trace!("Data after running: {:?}", data);
+
+        Ok(())
+    }
+}
+
+fn run_source(source: &str, name: &str, data: RunnerData) -> mlua::Result<RunnerData> {
+    let lua = Lua::new_with(
+        StdLib::TABLE | StdLib::STRING | StdLib::MATH | StdLib::UTF8,
+        LuaOptions::default(),
+    )?;
+    let globals = lua.globals();
+
+    globals.set(
+        "print",
+        lua.create_function(|_, values: Variadic<Value>| {
+            let line: Vec<String> = values.iter().map(display_value).collect();
+            info!("{}", line.join("\t"));
+            Ok(())
+        })?,
+    )?;
+    globals.set(DATA_GLOBAL, to_table(&lua, &data)?)?;
+
+    lua.load(source).set_name(name).exec()?;
+
+    let run_data = match globals.get::<_, Value>(DATA_GLOBAL)? {
+        Value::Table(table) => from_table(table),
+        _ => Err(mlua::Error::RuntimeError(format!(
+            "The global '{}' table was replaced by the script",
+            DATA_GLOBAL
+        ))),
+    };
+
+    run_data
+}
+
+fn to_table<'lua>(lua: &'lua Lua, data: &RunnerData) -> mlua::Result<Table<'lua>> {
+    let table = lua.create_table()?;
+
+    for (key, val) in &data.data {
+        match val {
+            RunnerChildType::Data(val) => table.set(key.as_str(), val.as_str())?,
+            RunnerChildType::List(values) => table.set(
+                key.as_str(),
+                lua.create_sequence_from(values.iter().cloned())?,
+            )?,
+            RunnerChildType::Child(child) => table.set(key.as_str(), to_table(lua, child)?)?,
+        }
+    }
+
+    Ok(table)
+}
+
+fn from_table(table: Table) -> mlua::Result<RunnerData> {
+    let mut data = RunnerData::new();
+
+    for pair in table.pairs::<Value, Value>() {
+        let (key, val) = pair?;
+        let key = match key {
+            Value::String(key) => key.to_str()?.to_owned(),
+            _ => continue,
+        };
+        let val = match val {
+            Value::Table(child) if is_sequence(&child)? => RunnerChildType::List(
+                child
+                    .sequence_values::<Value>()
+                    .map(|val| val.map(|val| display_value(&val)))
+                    .collect::<mlua::Result<_>>()?,
+            ),
+            Value::Table(child) => RunnerChildType::Child(from_table(child)?),
+            Value::Nil => continue,
+            val => RunnerChildType::Data(display_value(&val)),
+        };
+
+        data.data.insert(key, val);
+    }
+
+    Ok(data)
+}
+
+/// Returns wether the table only contains values with the keys `1..n`, empty
+/// tables are considered to be empty lists.
+fn is_sequence(table: &Table) -> mlua::Result<bool> {
+    let len = table.raw_len();
+    let mut count = 0;
+
+    for pair in table.clone().pairs::<Value, Value>() {
+        match pair?.0 {
+            Value::Integer(index) if index >= 1 && index as usize <= len => count += 1,
+            _ => return Ok(false),
+        }
+    }
+
+    Ok(count == len)
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::Nil => "nil".into(),
+        Value::Boolean(val) => val.to_string(),
+        Value::Integer(val) => val.to_string(),
+        Value::Number(val) => val.to_string(),
+        Value::String(val) => val.to_string_lossy().into_owned(),
+        val => val.type_name().into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_data::prelude::*;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn run(name: &str, data: &mut PackageData) -> Result<(), String> {
+        LuaRunner
+            .run(
+                &PathBuf::from("."),
+                &ScriptEnvironment::inherit(),
+                PathBuf::from("test-data/lua").join(name),
+                data,
+            )
+            .map_err(|err| err.to_string())
+    }
+
+    #[rstest(
+        name,
+        expected,
+        case("update.lua", true),
+        case("update.ps1", false),
+        case("update.luac", false)
+    )]
+    fn can_run_should_only_return_true_on_lua_scripts(name: &str, expected: bool) {
+        assert_eq!(LuaRunner.can_run(Path::new(name)), expected);
+    }
+
+    #[test]
+    fn run_should_allow_changes_to_summary_using_identifier() {
+        let mut data = PackageData::new("lua-test");
+
+        let result = run("change-summary.lua", &mut data);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(data.metadata().summary, "Summary of lua-test");
+    }
+
+    #[test]
+    fn run_should_not_use_changes_to_identifier() {
+        let mut data = PackageData::new("test");
+
+        let result = run("change-identifier.lua", &mut data);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(data.metadata().id(), "test");
+    }
+
+    #[test]
+    fn run_should_allow_changing_license_expression_and_url() {
+        let mut data = PackageData::new("codecov");
+
+        let result = run("change-license-full.lua", &mut data);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            data.metadata().license(),
+            &LicenseType::ExpressionAndLocation {
+                url: Url::parse(
+                    "https://github.com/AdmiringWorm/chocolatey-packages/blob/master/LICENSE.txt"
+                )
+                .unwrap(),
+                expression: "Apache-2.0".into()
+            }
+        );
+    }
+
+    #[test]
+    fn run_should_allow_changes_to_lists() {
+        let mut data = PackageData::new("test");
+        data.metadata_mut().set_maintainers(&["AdmiringWorm"]);
+
+        let result = run("change-lists.lua", &mut data);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            data.metadata().maintainers(),
+            ["AdmiringWorm", "WormieCorp"]
+        );
+        assert_eq!(data.metadata().chocolatey().tags(), ["aer", "test"]);
+    }
+
+    #[rstest(name, case("with-error.lua"), case("uses-io.lua"))]
+    fn run_should_return_error_when_script_fails(name: &str) {
+        let mut data = PackageData::new("test");
+
+        let result = run(name, &mut data).unwrap_err();
+
+        assert!(result.starts_with("An error occurred when running the Lua script!"));
+    }
+}
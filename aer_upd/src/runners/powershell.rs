@@ -10,16 +10,22 @@ use std::process::{Command, Stdio};
 use lazy_static::lazy_static;
 use log::{debug, error, info, trace, warn};
 
-use crate::runners::{RunnerCombiner, RunnerData, ScriptRunner};
+use crate::runners::errors::RunnerError;
+use crate::runners::{
+    read_runner_output, ExchangeFiles, RunnerCombiner, RunnerData, RunnerProtocol,
+    ScriptEnvironment, ScriptRunner, PROTOCOL_VERSION,
+};
+use crate::web::platform::Platform;
 
 lazy_static! {
     static ref POWERSHELL_EXEC: PathBuf = {
-        const NAMES: [&str; 3] = ["pwsh", "pwsh.exe", "powershell.exe"];
-        let paths = get_env_paths();
+        let platform = Platform::current();
+        let mut paths: Vec<PathBuf> = get_env_paths().into_iter().map(PathBuf::from).collect();
+        paths.extend(platform.powershell_locations());
 
-        for name in NAMES.iter() {
+        for name in platform.powershell_names() {
             for path in paths.iter() {
-                let path = PathBuf::from(path).join(name).canonicalize();
+                let path = path.join(name).canonicalize();
 
                 if let Ok(path) = path {
                     return path;
@@ -31,6 +37,10 @@ lazy_static! {
     };
 }
 
+/// The environment variable holding the path to the script that is run by
+/// the wrapper when the file based protocol is used.
+const SCRIPT_PATH_VAR: &str = "AER_SCRIPT_PATH";
+
 fn get_powershell_path() -> &'static Path {
     POWERSHELL_EXEC.as_path()
 }
@@ -45,32 +55,52 @@ impl ScriptRunner for PowershellRunner {
     fn run<'a, T: RunnerCombiner + Debug>(
         &self,
         cwd: &'a Path,
+        env: &'a ScriptEnvironment,
         script: PathBuf,
         data: &'a mut T,
-    ) -> Result<(), String> {
+    ) -> Result<(), RunnerError> {
         let path = get_powershell_path();
 
         if !path.is_file() {
             error!("No powershell executable was found!");
-            return Err("No powershell executable was found!!".into());
+            return Err(RunnerError::MissingExecutable("powershell"));
         }
-        let runner_data = serde_json::to_string(&data.to_runner_data()).unwrap();
+        let runner_data = data.to_runner_data();
         let script = script.canonicalize().unwrap();
         let override_script = if cfg!(windows) {
             "Set-ExecutionPolicy Bypass -Scope Process -Force;"
         } else {
             ""
         };
-        let runner_template = format!(
-            include_str!("wrapper.ps1"),
-            extra_code = override_script,
-            script_data = runner_data.replace("\"", "`\""),
-            script_path = script.display()
-        );
+        let exchange = match env.protocol() {
+            RunnerProtocol::Files => Some(ExchangeFiles::create(cwd, &runner_data)?),
+            RunnerProtocol::Markers => None,
+        };
+        let runner_template = match exchange {
+            Some(_) => format!(
+                include_str!("wrapper-files.ps1"),
+                extra_code = override_script,
+                protocol_version = PROTOCOL_VERSION
+            ),
+            None => format!(
+                include_str!("wrapper.ps1"),
+                extra_code = override_script,
+                script_data = serde_json::to_string(&runner_data)
+                    .unwrap()
+                    .replace("\"", "`\""),
+                script_path = script.display()
+            ),
+        };
         trace!("Data before running: {:?}", data);
         info!("Running script: {}", script.display());
 
-        let cmd = Command::new(path)
+        let mut cmd = Command::new(path);
+        env.apply(&mut cmd);
+        if let Some(ref exchange) = exchange {
+            exchange.apply(&mut cmd);
+            cmd.env(SCRIPT_PATH_VAR, &script);
+        }
+        let cmd = cmd
             .current_dir(cwd)
             .env("POWERSHELL_TELEMETRY_OPTOUT", "1")
             .args(&[
@@ -88,9 +118,12 @@ impl ScriptRunner for PowershellRunner {
 
         let cmd = cmd.wait_with_output();
 
-        if let Err(cmd) = cmd {
-            error!("{}", cmd);
-            return Err(format!("The running of powershell failed with '{}'", cmd));
+        if let Err(source) = cmd {
+            error!("{}", source);
+            return Err(RunnerError::Wait {
+                runner: "powershell",
+                source,
+            });
         }
 
         let cmd = cmd.unwrap();
@@ -101,28 +134,13 @@ impl ScriptRunner for PowershellRunner {
             )
         }
 
-        let mut run_data = String::new();
-        {
-            let stdout = String::from_utf8_lossy(&cmd.stdout);
-            let mut in_data = false;
-            debug!("AER-SCRIPT-RUNNER STDOUT:");
-
-            for line in stdout.lines() {
-                match line.trim() {
-                    "## AER-SCRIPT-RUNNER:START ##" => in_data = true,
-                    "## AER-SCRIPT-RUNNER:END ##" => in_data = false,
-                    line => {
-                        if in_data {
-                            run_data.push_str(line);
-                        } else if line.starts_with("WARNING:") {
-                            warn!("{}", line);
-                        } else {
-                            debug!("{}", line);
-                        }
-                    }
-                }
-            }
-        }
+        let run_data = read_runner_output(&String::from_utf8_lossy(&cmd.stdout));
+        // Errors reported by the script are more descriptive than the output
+        // on the standard error, as such the result is read first.
+        let result = match exchange {
+            Some(ref exchange) => exchange.read_result()?,
+            None => None,
+        };
 
         {
             let stderr = String::from_utf8_lossy(&cmd.stderr);
@@ -139,13 +157,27 @@ impl ScriptRunner for PowershellRunner {
             }
 
             if fail {
-                return Err(format!(
-                    "An exception occurred when running the PowerShell script!\n{}",
-                    stderr
-                ));
+                return Err(RunnerError::Exception(stderr.into_owned()));
             }
         }
 
+        if exchange.is_some() {
+            if !cmd.status.success() {
+                return Err(RunnerError::ExitCode {
+                    runner: "PowerShell",
+                    status: cmd.status,
+                    output: String::new(),
+                });
+            }
+
+            if let Some(result) = result {
+                data.from_runner_data(result);
+                trace!("Data after running: {:?}", data);
+            }
+
+            return Ok(());
+        }
+
         match serde_json::from_str::<RunnerData>(&run_data) {
             Ok(package_data) => {
                 data.from_runner_data(package_data);
@@ -154,10 +186,7 @@ impl ScriptRunner for PowershellRunner {
             }
             Err(err) => {
                 error!("{}", err);
-                Err(format!(
-                    "Deserializing script runner data failed with: {}",
-                    err
-                ))
+                Err(RunnerError::Deserialize(err.to_string()))
             }
         }
     }
@@ -207,13 +236,21 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "An exception occurred when running the PowerShell script!")]
+    #[should_panic(expected = "The script reported an error")]
     fn run_should_return_error_when_file_is_directory() {
         let runner = PowershellRunner;
         let dir = PathBuf::from("src");
         let mut data = PackageData::new("test");
 
-        let _ = runner.run(&PathBuf::from("."), dir, &mut data).unwrap();
+        let _ = runner
+            .run(
+                &PathBuf::from("."),
+                &ScriptEnvironment::inherit(),
+                dir,
+                &mut data,
+            )
+            .map_err(|err| err.to_string())
+            .unwrap();
     }
 
     #[rstest(name, case("empty-run.ps1"), case("empty-run-with-data.ps1"))]
@@ -222,7 +259,14 @@ mod tests {
         let path = PathBuf::from("test-data/ps1").join(name);
         let mut data = PackageData::new("test");
 
-        let result = runner.run(&PathBuf::from("."), path, &mut data);
+        let result = runner
+            .run(
+                &PathBuf::from("."),
+                &ScriptEnvironment::inherit(),
+                path,
+                &mut data,
+            )
+            .map_err(|err| err.to_string());
 
         assert_eq!(result, Ok(()));
     }
@@ -233,7 +277,14 @@ mod tests {
         let path = PathBuf::from("test-data/ps1/change-identifier.ps1");
         let mut data = PackageData::new("test");
 
-        let result = runner.run(&PathBuf::from("."), path, &mut data);
+        let result = runner
+            .run(
+                &PathBuf::from("."),
+                &ScriptEnvironment::inherit(),
+                path,
+                &mut data,
+            )
+            .map_err(|err| err.to_string());
 
         assert_eq!(result, Ok(()));
         assert_eq!(data.metadata().id(), "test");
@@ -245,7 +296,14 @@ mod tests {
         let path = PathBuf::from("test-data/ps1/change-summary.ps1");
         let mut data = PackageData::new("test");
 
-        let result = runner.run(&PathBuf::from("."), path, &mut data);
+        let result = runner
+            .run(
+                &PathBuf::from("."),
+                &ScriptEnvironment::inherit(),
+                path,
+                &mut data,
+            )
+            .map_err(|err| err.to_string());
 
         assert_eq!(result, Ok(()));
         assert_eq!(
@@ -260,7 +318,14 @@ mod tests {
         let path = PathBuf::from("test-data/ps1/change-project_url.ps1");
         let mut data = PackageData::new("test");
 
-        let result = runner.run(&PathBuf::from("."), path, &mut data);
+        let result = runner
+            .run(
+                &PathBuf::from("."),
+                &ScriptEnvironment::inherit(),
+                path,
+                &mut data,
+            )
+            .map_err(|err| err.to_string());
 
         assert_eq!(result, Ok(()));
         assert_eq!(
@@ -275,7 +340,14 @@ mod tests {
         let path = PathBuf::from("test-data/ps1/change-license-expression.ps1");
         let mut data = PackageData::new("test");
 
-        let result = runner.run(&PathBuf::from("."), path, &mut data);
+        let result = runner
+            .run(
+                &PathBuf::from("."),
+                &ScriptEnvironment::inherit(),
+                path,
+                &mut data,
+            )
+            .map_err(|err| err.to_string());
 
         assert_eq!(result, Ok(()));
         assert_eq!(
@@ -290,7 +362,14 @@ mod tests {
         let path = PathBuf::from("test-data/ps1/change-license-url.ps1");
         let mut data = PackageData::new("test");
 
-        let result = runner.run(&PathBuf::from("."), path, &mut data);
+        let result = runner
+            .run(
+                &PathBuf::from("."),
+                &ScriptEnvironment::inherit(),
+                path,
+                &mut data,
+            )
+            .map_err(|err| err.to_string());
 
         assert_eq!(result, Ok(()));
         assert_eq!(
@@ -310,7 +389,14 @@ mod tests {
         let path = PathBuf::from("test-data/ps1/change-license-full.ps1");
         let mut data = PackageData::new("codecov");
 
-        let result = runner.run(&PathBuf::from("."), path, &mut data);
+        let result = runner
+            .run(
+                &PathBuf::from("."),
+                &ScriptEnvironment::inherit(),
+                path,
+                &mut data,
+            )
+            .map_err(|err| err.to_string());
 
         assert_eq!(result, Ok(()));
         assert_eq!(
@@ -326,32 +412,77 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "An exception occurred when running the PowerShell script!")]
-    fn run_should_return_error_when_an_exception_occurrs() {
+    fn run_should_return_error_reported_by_script_when_an_exception_occurrs() {
         let runner = PowershellRunner;
         let path = PathBuf::from("test-data/ps1/with-exception.ps1");
         let mut data = PackageData::new("ansible");
 
-        let _ = runner.run(&PathBuf::from("."), path, &mut data).unwrap();
+        let result = runner
+            .run(
+                &PathBuf::from("."),
+                &ScriptEnvironment::inherit(),
+                path,
+                &mut data,
+            )
+            .map_err(|err| err.to_string());
+
+        assert_eq!(
+            result,
+            Err("The script reported an error: We are throwing this exception on purpose".into())
+        );
     }
 
     #[test]
     #[should_panic(expected = "An exception occurred when running the PowerShell script!")]
+    fn run_legacy_should_return_error_when_an_exception_occurrs() {
+        let runner = PowershellRunner;
+        let path = PathBuf::from("test-data/ps1/with-exception.ps1");
+        let mut data = PackageData::new("ansible");
+
+        let _ = runner
+            .run(
+                &PathBuf::from("."),
+                &ScriptEnvironment::inherit().with_protocol(RunnerProtocol::Markers),
+                path,
+                &mut data,
+            )
+            .map_err(|err| err.to_string())
+            .unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "The PowerShell script exited with a non-zero exit code")]
     fn run_should_return_error_when_script_exits_with_non_zero_exit_code() {
         let runner = PowershellRunner;
         let path = PathBuf::from("test-data/ps1/exit-code.ps1");
         let mut data = PackageData::new("ansible");
 
-        let _ = runner.run(&PathBuf::from("."), path, &mut data).unwrap();
+        let _ = runner
+            .run(
+                &PathBuf::from("."),
+                &ScriptEnvironment::inherit(),
+                path,
+                &mut data,
+            )
+            .map_err(|err| err.to_string())
+            .unwrap();
     }
 
     #[test]
-    #[should_panic(expected = "An exception occurred when running the PowerShell script!")]
+    #[should_panic(expected = "The script reported an error")]
     fn run_should_return_error_when_script_is_invalid() {
         let runner = PowershellRunner;
         let path = PathBuf::from("test-data/ps1/invalid-powershell.ps1");
         let mut data = PackageData::new("ansible");
 
-        let _ = runner.run(&PathBuf::from("."), path, &mut data).unwrap();
+        let _ = runner
+            .run(
+                &PathBuf::from("."),
+                &ScriptEnvironment::inherit(),
+                path,
+                &mut data,
+            )
+            .map_err(|err| err.to_string())
+            .unwrap();
     }
 }
@@ -0,0 +1,348 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the runner responsible for running shell scripts (`*.sh`), using
+//! the `sh` executable found in the `PATH` environment variable.
+//!
+//! By default the runner data is exchanged using files, the path to the file
+//! holding the serialized input is declared by the `AER_INPUT_FILE`
+//! environment variable, and the script writes its result to the file
+//! declared by the `AER_RESULT_FILE` environment variable. Only the values
+//! written by the script are changed, and nothing is changed when the script
+//! do not write a result file.
+//!
+//! ```sh
+//! #!/bin/sh
+//!
+//! cat > "$AER_RESULT_FILE" <<'JSON'
+//! { "protocol_version": 2, "data": { "summary": "The summary of the software" } }
+//! JSON
+//! ```
+//!
+//! Scripts can report a failure by writing an error object instead of the
+//! data (*ie: `{ "protocol_version": 2, "error": { "message": "Not found",
+//! "code": "E404" } }`*).
+//!
+//! When the [legacy protocol](crate::runners::RunnerProtocol::Markers) is
+//! used, the serialized runner data is passed to the script both in the
+//! `AER_RUNNER_DATA` environment variable and on the standard input. Changes
+//! to the data are returned by writing the serialized data between the same
+//! markers that are used by the PowerShell runner.
+//!
+//! ```sh
+//! #!/bin/sh
+//!
+//! echo "## AER-SCRIPT-RUNNER:START ##"
+//! echo '{ "summary": "The summary of the software" }'
+//! echo "## AER-SCRIPT-RUNNER:END ##"
+//! ```
+
+#![cfg_attr(docsrs, doc(cfg(feature = "shell")))]
+
+use std::fmt::Debug;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use log::{debug, error, info, trace, warn};
+
+use crate::runners::errors::RunnerError;
+use crate::runners::{
+    read_runner_output, ExchangeFiles, RunnerCombiner, RunnerData, RunnerProtocol,
+    ScriptEnvironment, ScriptRunner,
+};
+
+/// The environment variable holding the serialized runner data.
+pub const RUNNER_DATA_VAR: &str = "AER_RUNNER_DATA";
+
+/// The executable used to run the shell scripts.
+const SHELL_EXEC: &str = "sh";
+
+pub struct ShellRunner;
+
+impl ScriptRunner for ShellRunner {
+    fn can_run(&self, script_path: &Path) -> bool {
+        script_path.to_string_lossy().ends_with(".sh")
+    }
+
+    fn run<'a, T: RunnerCombiner + Debug>(
+        &self,
+        cwd: &'a Path,
+        env: &'a ScriptEnvironment,
+        script: PathBuf,
+        data: &'a mut T,
+    ) -> Result<(), RunnerError> {
+        let runner_data = data.to_runner_data();
+        let script = script
+            .canonicalize()
+            .map_err(|source| RunnerError::ScriptNotFound {
+                path: script.clone(),
+                source,
+            })?;
+        trace!("Data before running: {:?}", data);
+        info!("Running script: {}", script.display());
+
+        let mut cmd = Command::new(SHELL_EXEC);
+        env.apply(&mut cmd);
+        cmd.current_dir(cwd)
+            .arg(&script)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let (exchange, legacy_data) = match env.protocol() {
+            RunnerProtocol::Files => {
+                let exchange = ExchangeFiles::create(cwd, &runner_data)?;
+                exchange.apply(&mut cmd);
+                cmd.stdin(Stdio::null());
+                (Some(exchange), None)
+            }
+            RunnerProtocol::Markers => {
+                let runner_data = serde_json::to_string(&runner_data).unwrap();
+                cmd.env(RUNNER_DATA_VAR, &runner_data).stdin(Stdio::piped());
+                (None, Some(runner_data))
+            }
+        };
+
+        let mut child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(source) => {
+                let err = RunnerError::Spawn {
+                    runner: "shell",
+                    source,
+                };
+                error!("{}", err);
+                return Err(err);
+            }
+        };
+
+        if let (Some(mut stdin), Some(runner_data)) = (child.stdin.take(), legacy_data) {
+            // Scripts are not required to read the data from the standard
+            // input, as such failures when writing the data are ignored.
+            let _ = stdin.write_all(runner_data.as_bytes());
+        }
+
+        let output = child
+            .wait_with_output()
+            .map_err(|source| RunnerError::Wait {
+                runner: "the shell script",
+                source,
+            })?;
+
+        let run_data = read_runner_output(&String::from_utf8_lossy(&output.stdout));
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        debug!("AER-SCRIPT-RUNNER STDERR:");
+        for line in stderr.lines() {
+            warn!("{}", line);
+        }
+
+        // Errors reported by the script are more descriptive than the exit
+        // code, as such the result is read before checking the exit code.
+        let result = match exchange {
+            Some(ref exchange) => exchange.read_result()?,
+            None => None,
+        };
+
+        if !output.status.success() {
+            error!("Shell script runner returned {} error code!", output.status);
+            return Err(RunnerError::ExitCode {
+                runner: "shell",
+                status: output.status,
+                output: stderr.into_owned(),
+            });
+        }
+
+        let result = match exchange {
+            Some(_) => result,
+            None if run_data.trim().is_empty() => None,
+            None => match serde_json::from_str::<RunnerData>(&run_data) {
+                Ok(result) => Some(result),
+                Err(err) => {
+                    error!("{}", err);
+                    return Err(RunnerError::Deserialize(err.to_string()));
+                }
+            },
+        };
+
+        match result {
+            Some(result) => {
+                data.from_runner_data(result);
+                trace!("Data after running: {:?}", data);
+            }
+            None => trace!("No data was returned by the script"),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use aer_data::prelude::*;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn run(name: &str, data: &mut PackageData) -> Result<(), String> {
+        run_with(ScriptEnvironment::inherit(), name, data)
+    }
+
+    fn run_legacy(name: &str, data: &mut PackageData) -> Result<(), String> {
+        run_with(
+            ScriptEnvironment::inherit().with_protocol(RunnerProtocol::Markers),
+            name,
+            data,
+        )
+    }
+
+    fn run_with(env: ScriptEnvironment, name: &str, data: &mut PackageData) -> Result<(), String> {
+        ShellRunner
+            .run(
+                &PathBuf::from("."),
+                &env,
+                PathBuf::from("test-data/sh").join(name),
+                data,
+            )
+            .map_err(|err| err.to_string())
+    }
+
+    #[rstest(
+        name,
+        expected,
+        case("update.sh", true),
+        case("update.ps1", false),
+        case("update.bash", false)
+    )]
+    fn can_run_should_only_return_true_on_shell_scripts(name: &str, expected: bool) {
+        assert_eq!(ShellRunner.can_run(Path::new(name)), expected);
+    }
+
+    #[rstest(name, case("empty-run.sh"), case("result-empty.sh"))]
+    fn run_should_succeed_when_script_do_not_return_data(name: &str) {
+        let mut data = PackageData::new("test");
+
+        let result = run(name, &mut data);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(data, PackageData::new("test"));
+    }
+
+    #[test]
+    fn run_should_allow_changes_to_summary() {
+        let mut data = PackageData::new("test");
+
+        let result = run("result-change-summary.sh", &mut data);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            data.metadata().summary,
+            "The summary was changed to something else"
+        );
+    }
+
+    #[test]
+    fn run_should_pass_input_file_to_script() {
+        let mut data = PackageData::new("shell-test");
+
+        let result = run("result-summary-from-input.sh", &mut data);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(data.metadata().summary, "Summary of shell-test");
+    }
+
+    #[test]
+    fn run_should_return_error_reported_by_script() {
+        let mut data = PackageData::new("test");
+
+        let result = run("result-error.sh", &mut data);
+
+        assert_eq!(
+            result,
+            Err("The script reported an error: [E404] The release was not found".into())
+        );
+        assert_eq!(data, PackageData::new("test"));
+    }
+
+    #[test]
+    fn run_should_return_error_on_unsupported_protocol_version() {
+        let mut data = PackageData::new("test");
+
+        let result = run("result-unsupported-version.sh", &mut data).unwrap_err();
+
+        assert!(
+            result.contains("unsupported protocol version 99"),
+            "{}",
+            result
+        );
+    }
+
+    #[test]
+    fn run_should_ignore_markers_when_exchanging_files() {
+        let mut data = PackageData::new("test");
+
+        let result = run("change-summary.sh", &mut data);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(data, PackageData::new("test"));
+    }
+
+    #[test]
+    fn run_legacy_should_allow_changes_to_summary() {
+        let mut data = PackageData::new("test");
+
+        let result = run_legacy("change-summary.sh", &mut data);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            data.metadata().summary,
+            "The summary was changed to something else"
+        );
+    }
+
+    #[test]
+    fn run_legacy_should_allow_changing_license_expression_and_url() {
+        let mut data = PackageData::new("codecov");
+
+        let result = run_legacy("change-license-full.sh", &mut data);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            data.metadata().license(),
+            &LicenseType::ExpressionAndLocation {
+                url: Url::parse(
+                    "https://github.com/AdmiringWorm/chocolatey-packages/blob/master/LICENSE.txt"
+                )
+                .unwrap(),
+                expression: "Apache-2.0".into()
+            }
+        );
+    }
+
+    #[rstest(name, case("summary-from-env.sh"), case("summary-from-stdin.sh"))]
+    fn run_legacy_should_pass_data_to_script(name: &str) {
+        let mut data = PackageData::new("shell-test");
+
+        let result = run_legacy(name, &mut data);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(data.metadata().summary, "Summary of shell-test");
+    }
+
+    #[test]
+    fn run_should_return_error_when_script_exits_with_non_zero_exit_code() {
+        let mut data = PackageData::new("test");
+
+        let result = run("exit-code.sh", &mut data).unwrap_err();
+
+        assert!(result.starts_with("The shell script exited with a non-zero exit code"));
+    }
+
+    #[test]
+    fn run_should_return_error_when_script_do_not_exist() {
+        let mut data = PackageData::new("test");
+
+        let result = run("non-existing.sh", &mut data);
+
+        assert!(result.is_err());
+    }
+}
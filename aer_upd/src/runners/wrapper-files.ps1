@@ -0,0 +1,27 @@
+$ErrorActionPreference = 'Stop';
+$InformationPreference = 'Continue';
+$VerbosePreference = 'Continue';
+$DebugPreference = 'Continue';
+{extra_code} # Any code that is necessary on different platfroms.
+$data = (Get-Content -LiteralPath $env:AER_INPUT_FILE -Raw | ConvertFrom-Json -AsHashtable).data
+
+[int]$exitCode = 0;
+$result = @{{ protocol_version = {protocol_version} }};
+
+try {{
+    & $env:AER_SCRIPT_PATH $data;
+    [int]$exitCode = $LASTEXITCODE;
+    $result.data = $data;
+}}
+catch {{
+    $result.error = @{{ message = "$_" }};
+    if ($_.FullyQualifiedErrorId -ne "$_") {{
+        $result.error.code = $_.FullyQualifiedErrorId;
+    }}
+    if ($LASTEXITCODE -eq 0) {{
+        [int]$exitCode = 1;
+    }}
+}}
+
+$result | ConvertTo-Json -Depth 10 | Set-Content -LiteralPath $env:AER_RESULT_FILE -Encoding utf8;
+exit $exitCode;
@@ -19,7 +19,7 @@ catch {{
 }}
 
 Write-Host "## AER-SCRIPT-RUNNER:START ##";
-Write-Host ($data | ConvertTo-Json);
+Write-Host ($data | ConvertTo-Json -Depth 10);
 Write-Host "## AER-SCRIPT-RUNNER:END ##";
 if ($exitCode -ne 0) {{
     throw "Non-Zero exit code: $exitCode";
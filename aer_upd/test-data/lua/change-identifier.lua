@@ -0,0 +1,2 @@
+print("This script changes the identifier, this should not be respected!")
+data.id = "new-identifier"
@@ -0,0 +1,2 @@
+table.insert(data.maintainers, "WormieCorp")
+data.chocolatey.tags = { "aer", "test" }
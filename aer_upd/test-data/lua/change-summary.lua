@@ -0,0 +1,2 @@
+print("This script changes the summary, this should be respected!")
+data.summary = "Summary of " .. data.id
@@ -0,0 +1,2 @@
+-- The io library is not available in the sandbox
+io.write("Should not be written")
@@ -0,0 +1 @@
+error("Something went wrong")
@@ -0,0 +1,13 @@
+#!/bin/sh
+
+echo "WARNING: This script changes the license expression and url, this should be respected!"
+echo "## AER-SCRIPT-RUNNER:START ##"
+cat <<'JSON'
+{
+  "license": {
+    "expr": "Apache-2.0",
+    "url": "https://github.com/AdmiringWorm/chocolatey-packages/blob/master/LICENSE.txt"
+  }
+}
+JSON
+echo "## AER-SCRIPT-RUNNER:END ##"
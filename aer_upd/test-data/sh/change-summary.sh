@@ -0,0 +1,6 @@
+#!/bin/sh
+
+echo "This script changes the summary, this should be respected!"
+echo "## AER-SCRIPT-RUNNER:START ##"
+echo '{ "summary": "The summary was changed to something else" }'
+echo "## AER-SCRIPT-RUNNER:END ##"
@@ -0,0 +1,3 @@
+#!/bin/sh
+
+echo "This script do not change anything"
@@ -0,0 +1,4 @@
+#!/bin/sh
+
+echo "Something went wrong" >&2
+exit 5
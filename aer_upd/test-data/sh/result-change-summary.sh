@@ -0,0 +1,9 @@
+#!/bin/sh
+
+echo "This script changes the summary, this should be respected!"
+cat > "$AER_RESULT_FILE" <<'JSON'
+{
+  "protocol_version": 2,
+  "data": { "summary": "The summary was changed to something else" }
+}
+JSON
@@ -0,0 +1,3 @@
+#!/bin/sh
+
+echo '{ "protocol_version": 2 }' > "$AER_RESULT_FILE"
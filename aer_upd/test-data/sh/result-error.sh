@@ -0,0 +1,9 @@
+#!/bin/sh
+
+cat > "$AER_RESULT_FILE" <<'JSON'
+{
+  "protocol_version": 2,
+  "error": { "message": "The release was not found", "code": "E404" }
+}
+JSON
+exit 1
@@ -0,0 +1,5 @@
+#!/bin/sh
+
+id=$(sed -n 's/.*"id":"\([^"]*\)".*/\1/p' "$AER_INPUT_FILE")
+
+echo "{ \"protocol_version\": 2, \"data\": { \"summary\": \"Summary of $id\" } }" > "$AER_RESULT_FILE"
@@ -0,0 +1,3 @@
+#!/bin/sh
+
+echo '{ "protocol_version": 99, "data": { "summary": "Unsupported" } }' > "$AER_RESULT_FILE"
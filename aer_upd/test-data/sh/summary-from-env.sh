@@ -0,0 +1,7 @@
+#!/bin/sh
+
+id=$(printf '%s' "$AER_RUNNER_DATA" | sed -n 's/.*"id":"\([^"]*\)".*/\1/p')
+
+echo "## AER-SCRIPT-RUNNER:START ##"
+echo "{ \"summary\": \"Summary of $id\" }"
+echo "## AER-SCRIPT-RUNNER:END ##"
@@ -0,0 +1,173 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the reading of the version embedded in installers and
+//! executables, for software where the version can not be found in the
+//! download url.
+//!
+//! The version of executables (*ie: `.exe` and `.dll` files*) is read from
+//! the fixed file information of the version resource, while the version of
+//! Windows Installer packages (*ie: `.msi` files*) is read from the
+//! `ProductVersion` property in the property table of the package.
+//!
+//! ### Examples
+//!
+//! ```no_run
+//! use aer_version::binary::{read_version, BinaryVersionKind};
+//!
+//! let version = read_version("app-setup.exe", BinaryVersionKind::ProductVersion).unwrap();
+//! println!("Found version {}", version);
+//! ```
+
+mod cfb;
+mod msi;
+mod pe;
+
+use std::path::Path;
+
+use crate::{SemanticVersionError, Versions};
+
+/// The version that is read from executables, Windows Installer packages
+/// only have a product version which is used for both kinds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryVersionKind {
+    /// The version of the product the file is distributed with.
+    ProductVersion,
+    /// The version of the file itself.
+    FileVersion,
+}
+
+/// The errors that can occur when reading the version of a file.
+#[derive(Debug, thiserror::Error)]
+pub enum BinaryVersionError {
+    /// The file could not be read.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// The file is neither an executable or a Windows Installer package.
+    #[error("The file is not an executable or Windows Installer package!")]
+    Unsupported,
+    /// The file is malformed, holding a description of the problem.
+    #[error("The file is malformed: {0}")]
+    Malformed(String),
+    /// The file do not contain any version information.
+    #[error("The file do not contain a version!")]
+    Missing,
+    /// The version of the file could not be parsed.
+    #[error("The version of the file could not be parsed: {0}")]
+    Parse(#[source] SemanticVersionError),
+}
+
+/// The signature of portable executables (*ie: `.exe` and `.dll` files*).
+const PE_SIGNATURE: &[u8] = b"MZ";
+/// The signature of compound files, used by Windows Installer packages.
+const CFB_SIGNATURE: &[u8] = &[0xd0, 0xcf, 0x11, 0xe0, 0xa1, 0xb1, 0x1a, 0xe1];
+
+/// Reads the version of the executable or Windows Installer package at the
+/// specified path, the kind of file is detected from the content of the file.
+pub fn read_version<P: AsRef<Path>>(
+    path: P,
+    kind: BinaryVersionKind,
+) -> Result<Versions, BinaryVersionError> {
+    let bytes = std::fs::read(path)?;
+
+    parse_version(&version_string(&bytes, kind)?)
+}
+
+/// Reads the version string of the executable or Windows Installer package,
+/// without parsing the version.
+pub fn version_string(bytes: &[u8], kind: BinaryVersionKind) -> Result<String, BinaryVersionError> {
+    if bytes.starts_with(PE_SIGNATURE) {
+        pe::read_version(bytes, kind)
+    } else if bytes.starts_with(CFB_SIGNATURE) {
+        msi::read_product_version(bytes)
+    } else {
+        Err(BinaryVersionError::Unsupported)
+    }
+}
+
+/// Parses the version string read from a file, a trailing revision of `0` is
+/// removed so the version matches the versions found in download urls (*ie:
+/// `1.2.3.0` is parsed as `1.2.3`*).
+fn parse_version(value: &str) -> Result<Versions, BinaryVersionError> {
+    let value = value.trim();
+    let value = match value.strip_suffix(".0") {
+        Some(trimmed) if value.split('.').count() == 4 => trimmed,
+        _ => value,
+    };
+
+    Versions::parse(value).map_err(BinaryVersionError::Parse)
+}
+
+/// Reads a little endian `u16` at the specified offset.
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, BinaryVersionError> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| out_of_bounds(offset))
+}
+
+/// Reads a little endian `u32` at the specified offset.
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, BinaryVersionError> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| out_of_bounds(offset))
+}
+
+fn out_of_bounds(offset: usize) -> BinaryVersionError {
+    BinaryVersionError::Malformed(format!("Unexpected end of file at offset {}", offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest(
+        name,
+        kind,
+        expected,
+        case("version.exe", BinaryVersionKind::ProductVersion, "1.2.3"),
+        case("version.exe", BinaryVersionKind::FileVersion, "1.2.3.4"),
+        case("version.msi", BinaryVersionKind::ProductVersion, "2.5.1"),
+        case("version.msi", BinaryVersionKind::FileVersion, "2.5.1")
+    )]
+    fn read_version_should_return_embedded_version(
+        name: &str,
+        kind: BinaryVersionKind,
+        expected: &str,
+    ) {
+        let actual = read_version(Path::new("test-data").join(name), kind).unwrap();
+
+        assert_eq!(actual.to_string(), expected);
+    }
+
+    #[test]
+    fn version_string_should_return_error_on_unsupported_files() {
+        let actual = version_string(b"#!/bin/sh\n", BinaryVersionKind::ProductVersion);
+
+        assert!(matches!(actual, Err(BinaryVersionError::Unsupported)));
+    }
+
+    #[test]
+    fn version_string_should_return_error_on_truncated_executable() {
+        let bytes = std::fs::read("test-data/version.exe").unwrap();
+
+        let actual = version_string(&bytes[..0x50], BinaryVersionKind::ProductVersion);
+
+        assert!(matches!(actual, Err(BinaryVersionError::Malformed(_))));
+    }
+
+    #[rstest(
+        value,
+        expected,
+        case("1.2.3.0", "1.2.3"),
+        case("1.2.3.4", "1.2.3.4"),
+        case(" 2.0 ", "2.0"),
+        case("1.0.0.0", "1.0.0")
+    )]
+    fn parse_version_should_remove_empty_revision(value: &str, expected: &str) {
+        assert_eq!(parse_version(value).unwrap().to_string(), expected);
+    }
+}
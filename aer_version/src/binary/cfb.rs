@@ -0,0 +1,220 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! A minimal reader of compound files (*the container format used by
+//! Windows Installer packages*), only supporting reading the streams stored
+//! in the file.
+
+use super::{read_u16, read_u32, BinaryVersionError};
+
+/// Marks the end of a chain of sectors.
+const END_OF_CHAIN: u32 = 0xffff_fffe;
+/// Marks a sector that is not in use.
+const FREE_SECTOR: u32 = 0xffff_ffff;
+/// The size of a single entry in the directory.
+const DIRECTORY_ENTRY_SIZE: usize = 128;
+/// The type of directory entries that are streams.
+const STREAM_ENTRY: u8 = 2;
+/// The type of the root directory entry, which holds the mini stream.
+const ROOT_ENTRY: u8 = 5;
+
+/// A single entry in the directory of the compound file.
+struct Entry {
+    /// The raw name of the entry, as UTF-16 code units.
+    name: Vec<u16>,
+    entry_type: u8,
+    start: u32,
+    size: u64,
+}
+
+/// A compound file read into memory.
+pub(super) struct CompoundFile<'a> {
+    bytes: &'a [u8],
+    sector_size: usize,
+    mini_sector_size: usize,
+    mini_cutoff: u64,
+    fat: Vec<u32>,
+    mini_fat: Vec<u32>,
+    entries: Vec<Entry>,
+    mini_stream: Vec<u8>,
+}
+
+impl<'a> CompoundFile<'a> {
+    /// Parses the header, allocation tables and directory of the compound
+    /// file.
+    pub fn parse(bytes: &'a [u8]) -> Result<CompoundFile<'a>, BinaryVersionError> {
+        let sector_shift = read_u16(bytes, 0x1e)?;
+        let mini_shift = read_u16(bytes, 0x20)?;
+        if !(7..=16).contains(&sector_shift) || mini_shift >= sector_shift {
+            return Err(BinaryVersionError::Malformed(
+                "Invalid sector size of compound file".into(),
+            ));
+        }
+
+        let mut file = CompoundFile {
+            bytes,
+            sector_size: 1 << sector_shift,
+            mini_sector_size: 1 << mini_shift,
+            mini_cutoff: u64::from(read_u32(bytes, 0x38)?),
+            fat: vec![],
+            mini_fat: vec![],
+            entries: vec![],
+            mini_stream: vec![],
+        };
+
+        file.fat = file.read_fat()?;
+        file.mini_fat = to_u32s(&file.read_chain(read_u32(bytes, 0x3c)?, None)?);
+        file.entries = file
+            .read_chain(read_u32(bytes, 0x30)?, None)?
+            .chunks_exact(DIRECTORY_ENTRY_SIZE)
+            .map(|entry| parse_entry(entry, sector_shift > 9))
+            .collect::<Result<_, _>>()?;
+
+        if let Some(root) = file.entries.iter().find(|e| e.entry_type == ROOT_ENTRY) {
+            file.mini_stream = file.read_chain(root.start, Some(root.size))?;
+        }
+
+        Ok(file)
+    }
+
+    /// Reads the content of the stream with the specified raw name, nothing
+    /// is returned when the stream do not exist.
+    pub fn read_stream(&self, name: &[u16]) -> Result<Option<Vec<u8>>, BinaryVersionError> {
+        let entry = match self
+            .entries
+            .iter()
+            .find(|entry| entry.entry_type == STREAM_ENTRY && entry.name == name)
+        {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        if entry.size < self.mini_cutoff {
+            self.read_mini_chain(entry.start, entry.size).map(Some)
+        } else {
+            self.read_chain(entry.start, Some(entry.size)).map(Some)
+        }
+    }
+
+    fn sector(&self, sector: u32) -> Result<&'a [u8], BinaryVersionError> {
+        let offset = (sector as usize + 1) * self.sector_size;
+        self.bytes
+            .get(offset..offset + self.sector_size)
+            .ok_or_else(|| {
+                BinaryVersionError::Malformed(format!(
+                    "The sector {} is outside of the file",
+                    sector
+                ))
+            })
+    }
+
+    /// Reads the sectors of the allocation table, which are listed in the
+    /// header followed by the chain of additional list sectors.
+    fn read_fat(&self) -> Result<Vec<u32>, BinaryVersionError> {
+        let count = read_u32(self.bytes, 0x2c)? as usize;
+        let mut sectors: Vec<u32> = (0..109)
+            .map(|index| read_u32(self.bytes, 0x4c + index * 4))
+            .collect::<Result<_, _>>()?;
+        let mut next = read_u32(self.bytes, 0x44)?;
+        let per_sector = self.sector_size / 4 - 1;
+
+        while next != END_OF_CHAIN && next != FREE_SECTOR && sectors.len() < count {
+            let values = to_u32s(self.sector(next)?);
+            sectors.extend_from_slice(&values[..per_sector]);
+            next = values[per_sector];
+        }
+
+        let mut fat = vec![];
+        for &sector in sectors.iter().take(count) {
+            fat.extend(to_u32s(self.sector(sector)?));
+        }
+
+        Ok(fat)
+    }
+
+    fn read_chain(&self, start: u32, size: Option<u64>) -> Result<Vec<u8>, BinaryVersionError> {
+        let mut content = vec![];
+        let mut sector = start;
+
+        while sector != END_OF_CHAIN && sector != FREE_SECTOR {
+            if content.len() > self.bytes.len() {
+                return Err(BinaryVersionError::Malformed(
+                    "The sector chain contains a loop".into(),
+                ));
+            }
+            content.extend_from_slice(self.sector(sector)?);
+            sector = *self.fat.get(sector as usize).ok_or_else(|| {
+                BinaryVersionError::Malformed(format!("The sector {} is not allocated", sector))
+            })?;
+        }
+
+        truncate(content, size)
+    }
+
+    fn read_mini_chain(&self, start: u32, size: u64) -> Result<Vec<u8>, BinaryVersionError> {
+        let mut content = vec![];
+        let mut sector = start;
+
+        while sector != END_OF_CHAIN && sector != FREE_SECTOR {
+            let offset = sector as usize * self.mini_sector_size;
+            let data = self
+                .mini_stream
+                .get(offset..offset + self.mini_sector_size)
+                .filter(|_| content.len() <= self.mini_stream.len())
+                .ok_or_else(|| {
+                    BinaryVersionError::Malformed(format!("Invalid mini sector {}", sector))
+                })?;
+            content.extend_from_slice(data);
+            sector = *self.mini_fat.get(sector as usize).ok_or_else(|| {
+                BinaryVersionError::Malformed(format!(
+                    "The mini sector {} is not allocated",
+                    sector
+                ))
+            })?;
+        }
+
+        truncate(content, Some(size))
+    }
+}
+
+/// Parses a single directory entry, the high part of the stream size is only
+/// used by files with sectors larger than 512 bytes (*version 4*).
+fn parse_entry(bytes: &[u8], large_sizes: bool) -> Result<Entry, BinaryVersionError> {
+    let high = if large_sizes {
+        read_u32(bytes, 124)?
+    } else {
+        0
+    };
+    let name_length = (read_u16(bytes, 64)? as usize / 2)
+        .saturating_sub(1)
+        .min(31);
+
+    Ok(Entry {
+        name: (0..name_length)
+            .map(|index| read_u16(bytes, index * 2))
+            .collect::<Result<_, _>>()?,
+        entry_type: bytes[66],
+        start: read_u32(bytes, 116)?,
+        size: u64::from(read_u32(bytes, 120)?) | u64::from(high) << 32,
+    })
+}
+
+fn to_u32s(bytes: &[u8]) -> Vec<u32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+fn truncate(mut content: Vec<u8>, size: Option<u64>) -> Result<Vec<u8>, BinaryVersionError> {
+    if let Some(size) = size {
+        if content.len() < size as usize {
+            return Err(BinaryVersionError::Malformed(
+                "The stream is shorter than its recorded size".into(),
+            ));
+        }
+        content.truncate(size as usize);
+    }
+
+    Ok(content)
+}
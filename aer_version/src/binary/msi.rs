@@ -0,0 +1,152 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Reads the product version from the property table of Windows Installer
+//! packages.
+
+use super::cfb::CompoundFile;
+use super::{read_u16, read_u32, BinaryVersionError};
+
+/// The flag in the code page of the string pool that marks string references
+/// as being 3 bytes instead of 2 bytes.
+const LONG_STRING_REFS: u32 = 0x8000_0000;
+
+pub(super) fn read_product_version(bytes: &[u8]) -> Result<String, BinaryVersionError> {
+    let file = CompoundFile::parse(bytes)?;
+    let pool = read_table(&file, "_StringPool")?;
+    let data = read_table(&file, "_StringData")?;
+    let strings = StringTable::parse(&pool, &data)?;
+    let properties = read_table(&file, "Property")?;
+
+    let rows = properties.len() / (strings.ref_size * 2);
+    let read_ref = |column: usize, row: usize| -> Result<u32, BinaryVersionError> {
+        let offset = (column * rows + row) * strings.ref_size;
+        let low = u32::from(read_u16(&properties, offset)?);
+        if strings.ref_size == 3 {
+            Ok(low | u32::from(properties[offset + 2]) << 16)
+        } else {
+            Ok(low)
+        }
+    };
+
+    for row in 0..rows {
+        if strings.get(read_ref(0, row)?) == Some("ProductVersion") {
+            return strings
+                .get(read_ref(1, row)?)
+                .map(String::from)
+                .ok_or(BinaryVersionError::Missing);
+        }
+    }
+
+    Err(BinaryVersionError::Missing)
+}
+
+fn read_table(file: &CompoundFile, name: &str) -> Result<Vec<u8>, BinaryVersionError> {
+    file.read_stream(&encode_table_name(name))?
+        .ok_or_else(|| BinaryVersionError::Malformed(format!("The table '{}' do not exist", name)))
+}
+
+/// Encodes the name of a table the same way as the Windows Installer does
+/// when storing the table as a stream, where pairs of characters are packed
+/// into a single code unit.
+fn encode_table_name(name: &str) -> Vec<u16> {
+    let mut encoded = vec![0x4840];
+    let mut chars = name.chars().peekable();
+
+    while let Some(current) = chars.next() {
+        match (
+            encode_char(current),
+            chars.peek().copied().and_then(encode_char),
+        ) {
+            (Some(first), Some(second)) => {
+                chars.next();
+                encoded.push(0x3800 + first + (second << 6));
+            }
+            (Some(first), None) => encoded.push(0x4800 + first),
+            (None, _) => {
+                let mut buffer = [0; 2];
+                encoded.extend_from_slice(current.encode_utf16(&mut buffer));
+            }
+        }
+    }
+
+    encoded
+}
+
+fn encode_char(value: char) -> Option<u16> {
+    match value {
+        '0'..='9' => Some(value as u16 - '0' as u16),
+        'A'..='Z' => Some(value as u16 - 'A' as u16 + 10),
+        'a'..='z' => Some(value as u16 - 'a' as u16 + 36),
+        '.' => Some(62),
+        '_' => Some(63),
+        _ => None,
+    }
+}
+
+/// The strings of the package, referenced by the tables using the index of
+/// the string.
+struct StringTable {
+    ref_size: usize,
+    strings: Vec<String>,
+}
+
+impl StringTable {
+    /// Parses the string pool, holding the length and reference count of
+    /// every string, together with the data of the concatenated strings.
+    fn parse(pool: &[u8], data: &[u8]) -> Result<StringTable, BinaryVersionError> {
+        let code_page = read_u32(pool, 0)?;
+        let mut strings = vec![String::new()];
+        let mut offset = 0;
+        let mut index = 4;
+
+        while index + 4 <= pool.len() {
+            let mut length = read_u16(pool, index)? as usize;
+            let references = read_u16(pool, index + 2)?;
+            index += 4;
+
+            // Strings longer than 65535 bytes store the length in the
+            // following entry, with the high part in the reference count of
+            // that entry.
+            if length == 0 && references != 0 {
+                length =
+                    (read_u16(pool, index + 2)? as usize) << 16 | read_u16(pool, index)? as usize;
+                index += 4;
+            }
+
+            let value = data.get(offset..offset + length).ok_or_else(|| {
+                BinaryVersionError::Malformed("The string data is truncated".into())
+            })?;
+            strings.push(String::from_utf8_lossy(value).into_owned());
+            offset += length;
+        }
+
+        Ok(StringTable {
+            ref_size: if code_page & LONG_STRING_REFS != 0 {
+                3
+            } else {
+                2
+            },
+            strings,
+        })
+    }
+
+    fn get(&self, index: u32) -> Option<&str> {
+        match index {
+            0 => None,
+            index => self.strings.get(index as usize).map(String::as_str),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_table_name_should_pack_pairs_of_characters() {
+        let actual = encode_table_name("Property");
+
+        assert_eq!(actual, [0x4840, 0x4559, 0x44f2, 0x4568, 0x4737]);
+    }
+}
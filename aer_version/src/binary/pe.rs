@@ -0,0 +1,146 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Reads the version from the version resource of portable executables.
+
+use super::{read_u16, read_u32, BinaryVersionError, BinaryVersionKind};
+
+/// The index of the resource table in the data directories.
+const RESOURCE_DIRECTORY: usize = 2;
+/// The identifier of version resources (`RT_VERSION`).
+const RT_VERSION: u32 = 16;
+/// The signature of the fixed file information (`VS_FIXEDFILEINFO`).
+const FIXED_FILE_INFO_SIGNATURE: u32 = 0xfeef_04bd;
+
+/// A section of the executable, used to translate relative virtual addresses
+/// to offsets in the file.
+struct Section {
+    virtual_address: u32,
+    virtual_size: u32,
+    raw_offset: u32,
+    raw_size: u32,
+}
+
+pub(super) fn read_version(
+    bytes: &[u8],
+    kind: BinaryVersionKind,
+) -> Result<String, BinaryVersionError> {
+    let pe_offset = read_u32(bytes, 0x3c)? as usize;
+    if bytes.get(pe_offset..pe_offset + 4) != Some(b"PE\0\0") {
+        return Err(BinaryVersionError::Malformed(
+            "The PE signature is missing".into(),
+        ));
+    }
+
+    let coff = pe_offset + 4;
+    let section_count = read_u16(bytes, coff + 2)? as usize;
+    let optional_size = read_u16(bytes, coff + 16)? as usize;
+    let optional = coff + 20;
+    let directories = match read_u16(bytes, optional)? {
+        0x10b => optional + 96,
+        0x20b => optional + 112,
+        magic => {
+            return Err(BinaryVersionError::Malformed(format!(
+                "Unknown optional header magic {:#x}",
+                magic
+            )))
+        }
+    };
+    let resource_rva = read_u32(bytes, directories + RESOURCE_DIRECTORY * 8)?;
+    if resource_rva == 0 {
+        return Err(BinaryVersionError::Missing);
+    }
+
+    let sections = (0..section_count)
+        .map(|index| {
+            let offset = optional + optional_size + index * 40;
+            Ok(Section {
+                virtual_size: read_u32(bytes, offset + 8)?,
+                virtual_address: read_u32(bytes, offset + 12)?,
+                raw_size: read_u32(bytes, offset + 16)?,
+                raw_offset: read_u32(bytes, offset + 20)?,
+            })
+        })
+        .collect::<Result<Vec<_>, BinaryVersionError>>()?;
+
+    let resources = rva_to_offset(&sections, resource_rva)?;
+    let (data_rva, size) = find_version_resource(bytes, resources)?;
+    let data_offset = rva_to_offset(&sections, data_rva)?;
+    let data = bytes
+        .get(data_offset..data_offset + size as usize)
+        .ok_or_else(|| BinaryVersionError::Malformed("The version resource is truncated".into()))?;
+
+    fixed_file_version(data, kind)
+}
+
+fn rva_to_offset(sections: &[Section], rva: u32) -> Result<usize, BinaryVersionError> {
+    sections
+        .iter()
+        .find(|section| {
+            let size = section.virtual_size.max(section.raw_size);
+            rva >= section.virtual_address && rva < section.virtual_address + size
+        })
+        .map(|section| (rva - section.virtual_address + section.raw_offset) as usize)
+        .ok_or_else(|| {
+            BinaryVersionError::Malformed(format!("The address {:#x} is not in any section", rva))
+        })
+}
+
+/// Walks the three levels of the resource directory (*type, name and
+/// language*), and returns the address and size of the first version
+/// resource.
+fn find_version_resource(bytes: &[u8], resources: usize) -> Result<(u32, u32), BinaryVersionError> {
+    let mut directory = resources;
+    let mut wanted = Some(RT_VERSION);
+
+    for _ in 0..3 {
+        let named = read_u16(bytes, directory + 12)? as usize;
+        let ids = read_u16(bytes, directory + 14)? as usize;
+        let mut entry = None;
+
+        for index in 0..named + ids {
+            let offset = directory + 16 + index * 8;
+            let id = read_u32(bytes, offset)?;
+            if wanted.map(|wanted| wanted == id).unwrap_or(true) {
+                entry = Some(read_u32(bytes, offset + 4)?);
+                break;
+            }
+        }
+
+        let entry = entry.ok_or(BinaryVersionError::Missing)?;
+        wanted = None;
+        if entry & 0x8000_0000 == 0 {
+            let data = resources + entry as usize;
+            return Ok((read_u32(bytes, data)?, read_u32(bytes, data + 4)?));
+        }
+        directory = resources + (entry & 0x7fff_ffff) as usize;
+    }
+
+    Err(BinaryVersionError::Malformed(
+        "The resource directory is nested too deeply".into(),
+    ))
+}
+
+/// Reads the version from the fixed file information of the `VS_VERSIONINFO`
+/// structure.
+fn fixed_file_version(data: &[u8], kind: BinaryVersionKind) -> Result<String, BinaryVersionError> {
+    let info = (0..data.len().saturating_sub(4))
+        .step_by(4)
+        .find(|&offset| read_u32(data, offset).ok() == Some(FIXED_FILE_INFO_SIGNATURE))
+        .ok_or(BinaryVersionError::Missing)?;
+
+    let offset = match kind {
+        BinaryVersionKind::FileVersion => info + 8,
+        BinaryVersionKind::ProductVersion => info + 16,
+    };
+    let most = read_u32(data, offset)?;
+    let least = read_u32(data, offset + 4)?;
+
+    Ok(format!(
+        "{}.{}.{}.{}",
+        most >> 16,
+        most & 0xffff,
+        least >> 16,
+        least & 0xffff
+    ))
+}
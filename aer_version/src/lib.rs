@@ -2,20 +2,31 @@
 // Licensed under the MIT license. See LICENSE.txt file in the project
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+pub mod binary;
+pub mod transform;
 mod versions;
 
-use std::error::Error;
+use std::cmp::Ordering;
 use std::fmt::Display;
+use std::num::ParseIntError;
 
 pub use semver::Version as SemVersion;
 #[cfg(feature = "serialize")]
 use serde::{Deserialize, Serialize};
+pub use transform::VersionTransform;
 #[cfg(feature = "chocolatey")]
 pub use versions::chocolatey;
 pub use versions::FixVersion;
 
+/// A version of the software, either a semantic version or a version
+/// compatible with Chocolatey.
+///
+/// Versions are compared by their value regardless of the variant, by
+/// normalizing the versions through semver while keeping the build version
+/// (*the fourth part*) of Chocolatey versions. As such `1.0.0` and `1.0.0.0`
+/// are considered equal, and `1.0.0.1` is newer than both.
 #[cfg_attr(feature = "serialize", derive(Deserialize, Serialize), serde(untagged))]
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum Versions {
     SemVer(SemVersion),
     #[cfg(feature = "chocolatey")]
@@ -23,27 +34,36 @@ pub enum Versions {
     Choco(chocolatey::ChocoVersion),
 }
 
-/// An error type for this crate
-///
-/// Currently, just a generic error.
-#[derive(Clone, PartialEq, Debug, PartialOrd)]
+/// The errors that can occur when parsing or transforming versions.
+#[derive(Clone, PartialEq, Debug, thiserror::Error)]
 pub enum SemanticVersionError {
-    /// An error occurred while parsing.
+    /// The version string is not a valid version, holding a description of
+    /// the problem.
+    #[error("{0}")]
     ParseError(String),
+    /// A part of the version string is not a valid number.
+    #[error(transparent)]
+    InvalidNumber(#[from] ParseIntError),
+    /// The version string is not a valid semantic version.
+    #[error(transparent)]
+    SemVer(#[from] semver::SemVerError),
+    /// The value of a setting used when creating versions is not one of the
+    /// supported values.
+    #[error(
+        "The value '{value}' is not a supported {setting}, the value must be either {expected}!"
+    )]
+    UnsupportedSetting {
+        /// The name of the setting the value was used for.
+        setting: &'static str,
+        /// The value that is not supported.
+        value: String,
+        /// The values that are supported by the setting.
+        expected: &'static str,
+    },
 }
 
-impl Display for SemanticVersionError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
-        match self {
-            SemanticVersionError::ParseError(ref m) => write!(f, "{}", m),
-        }
-    }
-}
-
-impl Error for SemanticVersionError {}
-
 impl Versions {
-    pub fn parse(val: &str) -> Result<Versions, Box<dyn std::error::Error>> {
+    pub fn parse(val: &str) -> Result<Versions, SemanticVersionError> {
         #[cfg(not(feature = "chocolatey"))]
         {
             Ok(Versions::SemVer(SemVersion::parse(val)?))
@@ -76,8 +96,102 @@ impl Versions {
             Versions::Choco(ver) => SemVersion::from(ver.clone()),
         }
     }
+
+    /// Returns wether this version is newer than the other version, the
+    /// versions may be of different variants.
+    pub fn is_newer_than(&self, other: &Versions) -> bool {
+        self > other
+    }
+
+    /// Returns wether this version is older than the other version, the
+    /// versions may be of different variants.
+    pub fn is_older_than(&self, other: &Versions) -> bool {
+        self < other
+    }
+
+    /// Returns the version without the fix created using the specified
+    /// scheme, semantic versions are returned as is as these can not be fix
+    /// versions.
+    #[cfg(feature = "chocolatey")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
+    pub fn without_fix(&self, scheme: chocolatey::FixScheme) -> Versions {
+        match self {
+            Versions::SemVer(_) => self.clone(),
+            Versions::Choco(ver) => Versions::Choco(ver.without_fix(scheme)),
+        }
+    }
+
+    /// Returns wether this version of the software is newer than the version
+    /// of the package, ignoring any fix the package version may have. A
+    /// package version with only a fix applied is as such not considered to
+    /// be newer than the version of the software.
+    #[cfg(feature = "chocolatey")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chocolatey")))]
+    pub fn is_newer_than_package(&self, package: &Versions, scheme: chocolatey::FixScheme) -> bool {
+        self.is_newer_than(&package.without_fix(scheme))
+    }
+
+    /// Returns the build version (*the fourth part*) of Chocolatey versions,
+    /// semantic versions do not have a build version.
+    fn build(&self) -> u32 {
+        match self {
+            Versions::SemVer(_) => 0,
+            #[cfg(feature = "chocolatey")]
+            Versions::Choco(ver) => ver.build().unwrap_or(0),
+        }
+    }
+
+    /// Returns wether the version is a pre-release (*ie: `1.0.0-beta.1`*).
+    pub fn is_prerelease(&self) -> bool {
+        self.to_semver().is_prerelease()
+    }
+
+    /// Returns the release channel of a pre-release version, this is the
+    /// leading letters of the first pre-release identifier in lowercase (*ie:
+    /// `beta` for both `1.0.0-beta.1` and `1.0.0.5-beta0005`*). Stable
+    /// versions do not have a channel.
+    pub fn channel(&self) -> Option<String> {
+        let identifier = self.to_semver().pre.into_iter().next()?.to_string();
+        let channel: String = identifier
+            .chars()
+            .take_while(|ch| ch.is_ascii_alphabetic())
+            .collect();
+
+        if channel.is_empty() {
+            Some(identifier.to_lowercase())
+        } else {
+            Some(channel.to_lowercase())
+        }
+    }
+}
+
+impl Ord for Versions {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (left, right) = (self.to_semver(), other.to_semver());
+
+        left.major
+            .cmp(&right.major)
+            .then(left.minor.cmp(&right.minor))
+            .then(left.patch.cmp(&right.patch))
+            .then_with(|| self.build().cmp(&other.build()))
+            .then_with(|| left.cmp(&right))
+    }
+}
+
+impl PartialOrd for Versions {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
+impl PartialEq for Versions {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Versions {}
+
 impl Display for Versions {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
         match self {
@@ -100,6 +214,7 @@ mod tests {
         let expected = Versions::Choco(chocolatey::ChocoVersion::with_build(5, 1, 6, 4));
         let version = Versions::parse("5.1.6.4").unwrap();
 
+        assert!(matches!(version, Versions::Choco(_)));
         assert_eq!(version, expected);
     }
 
@@ -108,6 +223,7 @@ mod tests {
         let expected = Versions::SemVer(SemVersion::new(5, 1, 0));
         let version = Versions::parse("5.1.0").unwrap();
 
+        assert!(matches!(version, Versions::SemVer(_)));
         assert_eq!(version, expected);
     }
 
@@ -198,6 +314,110 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[rstest]
+    #[case("1.0.0", false)]
+    #[case("1.0.0-beta.1", true)]
+    #[cfg_attr(feature = "chocolatey", case("1.0.0.5", false))]
+    #[cfg_attr(feature = "chocolatey", case("1.0.0.5-rc0002", true))]
+    fn is_prerelease_should_return_expected_value(#[case] test: &str, #[case] expected: bool) {
+        let version = Versions::parse(test).unwrap();
+
+        assert_eq!(version.is_prerelease(), expected);
+    }
+
+    #[rstest]
+    #[case("1.0.0", None)]
+    #[case("1.0.0-beta.1", Some("beta"))]
+    #[case("1.0.0-RC2", Some("rc"))]
+    #[case("1.0.0-5", Some("5"))]
+    #[cfg_attr(feature = "chocolatey", case("2.1.0.5-alpha0055", Some("alpha")))]
+    fn channel_should_return_leading_letters_of_prerelease(
+        #[case] test: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        let version = Versions::parse(test).unwrap();
+
+        assert_eq!(version.channel().as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case("1.0.0", "1.0.1", Ordering::Less)]
+    #[case("1.10.0", "1.9.0", Ordering::Greater)]
+    #[case("1.0.0-beta.2", "1.0.0", Ordering::Less)]
+    #[case("1.0.0-alpha.1", "1.0.0-beta.1", Ordering::Less)]
+    #[case("1.0.0+5", "1.0.0+6", Ordering::Equal)]
+    #[cfg_attr(feature = "chocolatey", case("1.0", "1.0.0", Ordering::Equal))]
+    #[cfg_attr(feature = "chocolatey", case("1.0.0.0", "1.0.0", Ordering::Equal))]
+    #[cfg_attr(feature = "chocolatey", case("1.0.0.1", "1.0.0", Ordering::Greater))]
+    #[cfg_attr(feature = "chocolatey", case("1.0.0.1", "1.0.1", Ordering::Less))]
+    #[cfg_attr(feature = "chocolatey", case("2.1.0.5", "2.1.0.12", Ordering::Less))]
+    #[cfg_attr(
+        feature = "chocolatey",
+        case("1.0.0.0-beta0002", "1.0.0-beta.2", Ordering::Equal)
+    )]
+    #[cfg_attr(
+        feature = "chocolatey",
+        case("1.0.0.1-beta0001", "1.0.0", Ordering::Greater)
+    )]
+    #[cfg_attr(
+        feature = "chocolatey",
+        case("1.0.0.1-beta0001", "1.0.0.1", Ordering::Less)
+    )]
+    fn cmp_should_compare_versions_across_variants(
+        #[case] left: &str,
+        #[case] right: &str,
+        #[case] expected: Ordering,
+    ) {
+        let left = Versions::parse(left).unwrap();
+        let right = Versions::parse(right).unwrap();
+
+        assert_eq!(left.cmp(&right), expected);
+        assert_eq!(right.cmp(&left), expected.reverse());
+        assert_eq!(left == right, expected == Ordering::Equal);
+    }
+
+    #[test]
+    #[cfg(feature = "chocolatey")]
+    fn is_newer_than_should_compare_choco_and_semver_versions() {
+        let packaged = Versions::Choco(chocolatey::ChocoVersion::with_build(1, 2, 3, 4));
+        let upstream = Versions::SemVer(SemVersion::new(1, 2, 4));
+
+        assert!(upstream.is_newer_than(&packaged));
+        assert!(!packaged.is_newer_than(&upstream));
+        assert!(packaged.is_older_than(&upstream));
+        assert!(!upstream.is_newer_than(&upstream.clone()));
+    }
+
+    #[rstest]
+    #[cfg(feature = "chocolatey")]
+    #[case("1.2.3", "1.2.3.1", chocolatey::FixScheme::Revision, false)]
+    #[case("1.2.4", "1.2.3.1", chocolatey::FixScheme::Revision, true)]
+    #[case("1.2.3", "1.2.3.20210615", chocolatey::FixScheme::Date, false)]
+    #[case("1.2.3.1", "1.2.3.20210615", chocolatey::FixScheme::Date, true)]
+    #[case("1.2.3", "1.2.3.1", chocolatey::FixScheme::Date, false)]
+    fn is_newer_than_package_should_ignore_fix_of_package(
+        #[case] software: &str,
+        #[case] package: &str,
+        #[case] scheme: chocolatey::FixScheme,
+        #[case] expected: bool,
+    ) {
+        let software = Versions::parse(software).unwrap();
+        let package = Versions::parse(package).unwrap();
+
+        assert_eq!(software.is_newer_than_package(&package, scheme), expected);
+    }
+
+    #[test]
+    fn max_should_return_highest_version() {
+        let versions = ["1.2.0", "1.10.0-beta.1", "1.9.5", "1.10.0"]
+            .iter()
+            .map(|v| Versions::parse(v).unwrap());
+
+        let actual = versions.max().unwrap();
+
+        assert_eq!(actual.to_string(), "1.10.0");
+    }
+
     #[rstest]
     #[case("4.2.1-alpha.5+6", "4.2.1-alpha.5+6")]
     #[cfg_attr(feature = "chocolatey", case("3.2", "3.2"))]
@@ -0,0 +1,342 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the transformations that can be applied to the upstream version
+//! of a software before the version is parsed, used when the upstream version
+//! do not match the version that should be used for the package.
+//!
+//! Each transformation is specified as the name of the operation followed by
+//! its argument, separated by a colon (*ie: `strip_prefix:v`*). The
+//! transformations are applied in the order they are specified.
+//!
+//! | Operation         | Example              | Result                        |
+//! | ----------------- | -------------------- | ----------------------------- |
+//! | `strip_prefix`    | `strip_prefix:v`     | `v1.2.3` becomes `1.2.3`      |
+//! | `take_parts`      | `take_parts:2`       | `1.2.3` becomes `1.2`         |
+//! | `pad_parts`       | `pad_parts:4`        | `1.2` becomes `1.2.0.0`       |
+//! | `append_revision` | `append_revision:1`  | `1.2.3` becomes `1.2.3.1`     |
+//! | `replace`         | `replace:/_/./`      | `1_2_3` becomes `1.2.3`       |
+//!
+//! The `replace` operation uses the first character after the colon as the
+//! delimiter of the regex and the replacement, the replacement may reference
+//! the groups captured by the regex (*ie: `replace:#(\d+)-(\d+)#$1.$2#`*).
+//!
+//! ### Examples
+//!
+//! ```
+//! use aer_version::transform::{transform, VersionTransform};
+//!
+//! let transforms = vec![
+//!     "strip_prefix:v".parse::<VersionTransform>().unwrap(),
+//!     "take_parts:3".parse().unwrap(),
+//! ];
+//!
+//! let version = transform("v1.2.3.4-beta", &transforms).unwrap();
+//!
+//! assert_eq!(version, "1.2.3-beta");
+//! ```
+
+use std::convert::TryFrom;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use regex::Regex;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use crate::SemanticVersionError;
+
+/// A single transformation of an upstream version.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(Deserialize, Serialize),
+    serde(try_from = "String", into = "String")
+)]
+pub enum VersionTransform {
+    /// Removes the prefix from the start of the version, the version is kept
+    /// as is when it do not start with the prefix.
+    StripPrefix(String),
+    /// Keeps the specified number of leading numeric parts of the version,
+    /// any pre-release or build suffix is kept.
+    TakeParts(usize),
+    /// Appends `0` parts to the version until the version has the specified
+    /// number of numeric parts.
+    PadParts(usize),
+    /// Appends the revision as an additional numeric part of the version,
+    /// used when the package have been changed without a new upstream
+    /// version.
+    AppendRevision(u32),
+    /// Replaces every match of the regex with the replacement.
+    Replace {
+        /// The regex matching the text to replace.
+        pattern: String,
+        /// The replacement, which may reference the captured groups.
+        replacement: String,
+    },
+}
+
+impl VersionTransform {
+    /// Applies the transformation to the version, returning an error if the
+    /// regex used to replace parts of the version is invalid.
+    pub fn apply(&self, version: &str) -> Result<String, SemanticVersionError> {
+        let result = match self {
+            VersionTransform::StripPrefix(prefix) => version
+                .strip_prefix(prefix.as_str())
+                .unwrap_or(version)
+                .to_owned(),
+            VersionTransform::TakeParts(count) => {
+                let (parts, suffix) = split_parts(version);
+                let parts: Vec<_> = parts.into_iter().take(*count).collect();
+                format!("{}{}", parts.join("."), suffix)
+            }
+            VersionTransform::PadParts(count) => {
+                let (mut parts, suffix) = split_parts(version);
+                while parts.len() < *count {
+                    parts.push("0");
+                }
+                format!("{}{}", parts.join("."), suffix)
+            }
+            VersionTransform::AppendRevision(revision) => {
+                let (parts, suffix) = split_parts(version);
+                format!("{}.{}{}", parts.join("."), revision, suffix)
+            }
+            VersionTransform::Replace {
+                pattern,
+                replacement,
+            } => compile(pattern)?
+                .replace_all(version, replacement.as_str())
+                .into_owned(),
+        };
+
+        Ok(result)
+    }
+}
+
+/// Applies the transformations in order to the version, and returns the
+/// transformed version.
+pub fn transform(
+    version: &str,
+    transforms: &[VersionTransform],
+) -> Result<String, SemanticVersionError> {
+    transforms
+        .iter()
+        .try_fold(version.to_owned(), |version, transform| {
+            transform.apply(&version)
+        })
+}
+
+/// Splits the version into the leading numeric parts and the remaining
+/// suffix (*ie: `["1", "2", "3"]` and `-beta` for `1.2.3-beta`*).
+fn split_parts(version: &str) -> (Vec<&str>, &str) {
+    let end = version
+        .find(|ch: char| !ch.is_ascii_digit() && ch != '.')
+        .unwrap_or(version.len());
+    let (parts, suffix) = version.split_at(end);
+
+    (
+        parts.split('.').filter(|part| !part.is_empty()).collect(),
+        suffix,
+    )
+}
+
+fn compile(pattern: &str) -> Result<Regex, SemanticVersionError> {
+    Regex::new(pattern).map_err(|err| {
+        SemanticVersionError::ParseError(format!(
+            "The regex '{}' used to replace the version is invalid: {}",
+            pattern, err
+        ))
+    })
+}
+
+impl FromStr for VersionTransform {
+    type Err = SemanticVersionError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (operation, argument) = match value.find(':') {
+            Some(index) => (value[..index].trim(), &value[index + 1..]),
+            None => (value.trim(), ""),
+        };
+
+        match operation.to_lowercase().replace('-', "_").as_str() {
+            "strip_prefix" if !argument.is_empty() => {
+                Ok(VersionTransform::StripPrefix(argument.to_owned()))
+            }
+            "take_parts" => Ok(VersionTransform::TakeParts(number(value, argument)?)),
+            "pad_parts" => Ok(VersionTransform::PadParts(number(value, argument)?)),
+            "append_revision" => Ok(VersionTransform::AppendRevision(number(value, argument)?)),
+            "replace" => parse_replace(value, argument),
+            _ => Err(invalid(value)),
+        }
+    }
+}
+
+impl TryFrom<String> for VersionTransform {
+    type Error = SemanticVersionError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<VersionTransform> for String {
+    fn from(transform: VersionTransform) -> Self {
+        transform.to_string()
+    }
+}
+
+impl Display for VersionTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            VersionTransform::StripPrefix(prefix) => write!(f, "strip_prefix:{}", prefix),
+            VersionTransform::TakeParts(count) => write!(f, "take_parts:{}", count),
+            VersionTransform::PadParts(count) => write!(f, "pad_parts:{}", count),
+            VersionTransform::AppendRevision(revision) => {
+                write!(f, "append_revision:{}", revision)
+            }
+            VersionTransform::Replace {
+                pattern,
+                replacement,
+            } => {
+                let delimiter = ['/', '#', '|', '!', '~']
+                    .iter()
+                    .find(|ch| !pattern.contains(**ch) && !replacement.contains(**ch))
+                    .unwrap_or(&'/');
+                write!(
+                    f,
+                    "replace:{0}{1}{0}{2}{0}",
+                    delimiter, pattern, replacement
+                )
+            }
+        }
+    }
+}
+
+fn number<T: FromStr>(value: &str, argument: &str) -> Result<T, SemanticVersionError> {
+    argument.trim().parse().map_err(|_| invalid(value))
+}
+
+/// Parses the `replace` operation, where the first character of the argument
+/// is used as the delimiter of the regex and the replacement.
+fn parse_replace(value: &str, argument: &str) -> Result<VersionTransform, SemanticVersionError> {
+    let delimiter = argument.chars().next().ok_or_else(|| invalid(value))?;
+    let parts: Vec<_> = argument[delimiter.len_utf8()..].split(delimiter).collect();
+
+    match parts.as_slice() {
+        [pattern, replacement] | [pattern, replacement, ""] if !pattern.is_empty() => {
+            compile(pattern)?;
+            Ok(VersionTransform::Replace {
+                pattern: (*pattern).to_owned(),
+                replacement: (*replacement).to_owned(),
+            })
+        }
+        _ => Err(invalid(value)),
+    }
+}
+
+fn invalid(value: &str) -> SemanticVersionError {
+    SemanticVersionError::ParseError(format!(
+        "'{}' is not a valid version transformation!",
+        value
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("strip_prefix:v", VersionTransform::StripPrefix("v".into()))]
+    #[case("strip-prefix:release-", VersionTransform::StripPrefix("release-".into()))]
+    #[case("take_parts:3", VersionTransform::TakeParts(3))]
+    #[case("pad_parts: 4", VersionTransform::PadParts(4))]
+    #[case("append_revision:1", VersionTransform::AppendRevision(1))]
+    #[case("replace:/_/./", VersionTransform::Replace { pattern: "_".into(), replacement: ".".into() })]
+    #[case("replace:#(\\d+)/(\\d+)#$1.$2", VersionTransform::Replace { pattern: "(\\d+)/(\\d+)".into(), replacement: "$1.$2".into() })]
+    fn parse_should_create_transform(#[case] value: &str, #[case] expected: VersionTransform) {
+        let actual: VersionTransform = value.parse().unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case("unknown:5")]
+    #[case("strip_prefix")]
+    #[case("take_parts:three")]
+    #[case("replace:/_")]
+    #[case("replace://./")]
+    #[case("replace:/(/./")]
+    fn parse_should_return_error_on_invalid_transform(#[case] value: &str) {
+        let actual = value.parse::<VersionTransform>();
+
+        assert!(actual.is_err());
+    }
+
+    #[rstest]
+    #[case("strip_prefix:v")]
+    #[case("take_parts:3")]
+    #[case("pad_parts:4")]
+    #[case("append_revision:20210615")]
+    #[case("replace:/_/./")]
+    #[case("replace:#(\\d+)/(\\d+)#$1.$2#")]
+    fn display_should_return_parsable_transform(#[case] value: &str) {
+        let transform: VersionTransform = value.parse().unwrap();
+
+        assert_eq!(transform.to_string(), value);
+    }
+
+    #[rstest]
+    #[case("strip_prefix:v", "v1.2.3", "1.2.3")]
+    #[case("strip_prefix:v", "1.2.3", "1.2.3")]
+    #[case("take_parts:2", "1.2.3.4", "1.2")]
+    #[case("take_parts:3", "1.2.3.4-beta.1", "1.2.3-beta.1")]
+    #[case("take_parts:3", "1.2", "1.2")]
+    #[case("pad_parts:4", "1.2", "1.2.0.0")]
+    #[case("pad_parts:3", "1.2-rc1", "1.2.0-rc1")]
+    #[case("append_revision:1", "1.2.3", "1.2.3.1")]
+    #[case("append_revision:1", "1.2.3-beta", "1.2.3.1-beta")]
+    #[case("replace:/_/./", "1_2_3", "1.2.3")]
+    #[case("replace:/^(\\d+)r(\\d+)$/$1.$2/", "4r12", "4.12")]
+    fn apply_should_transform_version(
+        #[case] transform: &str,
+        #[case] version: &str,
+        #[case] expected: &str,
+    ) {
+        let transform: VersionTransform = transform.parse().unwrap();
+
+        let actual = transform.apply(version).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn apply_should_return_error_on_invalid_regex() {
+        let transform = VersionTransform::Replace {
+            pattern: "(".into(),
+            replacement: String::new(),
+        };
+
+        let actual = transform.apply("1.0.0");
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn transform_should_apply_transforms_in_order() {
+        let transforms = vec![
+            VersionTransform::StripPrefix("release-".into()),
+            VersionTransform::Replace {
+                pattern: "_".into(),
+                replacement: ".".into(),
+            },
+            VersionTransform::PadParts(3),
+            VersionTransform::AppendRevision(2),
+        ];
+
+        let actual = transform("release-5_1", &transforms).unwrap();
+
+        assert_eq!(actual, "5.1.0.2");
+    }
+}
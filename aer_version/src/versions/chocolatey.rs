@@ -6,7 +6,9 @@
 
 use std::cmp::Ordering;
 use std::fmt::Display;
+use std::str::FromStr;
 
+use chrono::NaiveDate;
 use semver::Identifier;
 #[cfg(feature = "serialize")]
 use serde::de::{self, Visitor};
@@ -18,6 +20,99 @@ use crate::{FixVersion, SemVersion, SemanticVersionError};
 #[allow(clippy::inconsistent_digit_grouping)] // We want it to be shown in the ISO date format
 const FIX_THRESHOLD: u32 = 2007_01_01;
 
+/// The time zone that is used to get the current date when creating fix
+/// versions. Defaults to [FixTimeZone::Utc] so the same fix version is created
+/// independently of the time zone the updater is running in.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum FixTimeZone {
+    /// Use the current date in the UTC time zone.
+    #[default]
+    Utc,
+    /// Use the current date in the local time zone of the machine.
+    Local,
+}
+
+impl FixTimeZone {
+    /// Returns the current date in the time zone.
+    pub fn today(&self) -> NaiveDate {
+        match self {
+            FixTimeZone::Utc => chrono::Utc::today().naive_utc(),
+            FixTimeZone::Local => chrono::Local::today().naive_local(),
+        }
+    }
+}
+
+impl FromStr for FixTimeZone {
+    type Err = SemanticVersionError;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val.trim().to_lowercase().as_str() {
+            "utc" => Ok(FixTimeZone::Utc),
+            "local" => Ok(FixTimeZone::Local),
+            _ => Err(SemanticVersionError::UnsupportedSetting {
+                setting: "fix time zone",
+                value: val.to_owned(),
+                expected: "'utc' or 'local'",
+            }),
+        }
+    }
+}
+
+impl Display for FixTimeZone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            FixTimeZone::Utc => f.write_str("utc"),
+            FixTimeZone::Local => f.write_str("local"),
+        }
+    }
+}
+
+/// The scheme used when creating fix versions, these are used when a package
+/// needs to be changed without a new version of the software being released.
+/// Defaults to [FixScheme::Date].
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serialize",
+    derive(Deserialize, Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum FixScheme {
+    /// Use the current date as the fix (*ie: `1.2.3.20210615` or
+    /// `1.2.3-beta0001-20210615`*).
+    #[default]
+    Date,
+    /// Use an incrementing revision as the build version (*ie: `1.2.3.1`,
+    /// then `1.2.3.2`*). This scheme should only be used when the software do
+    /// not use the build version itself, as any build version is considered
+    /// to be a revision.
+    Revision,
+}
+
+impl FromStr for FixScheme {
+    type Err = SemanticVersionError;
+
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val.trim().to_lowercase().as_str() {
+            "date" => Ok(FixScheme::Date),
+            "revision" => Ok(FixScheme::Revision),
+            _ => Err(SemanticVersionError::UnsupportedSetting {
+                setting: "fix scheme",
+                value: val.to_owned(),
+                expected: "'date' or 'revision'",
+            }),
+        }
+    }
+}
+
+impl Display for FixScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            FixScheme::Date => f.write_str("date"),
+            FixScheme::Revision => f.write_str("revision"),
+        }
+    }
+}
+
 /// Holds the relevant portions of a version that is compatible with the
 /// chocolatey package manager.
 ///
@@ -25,11 +120,14 @@ const FIX_THRESHOLD: u32 = 2007_01_01;
 /// these compatible with chocolatey, converting between chocolatey and semver
 /// as well as allowing fix versions to be created for both stable and unstable
 /// versions.
+///
+/// Every part of the version is stored as an unsigned 32bit integer, as
+/// versions of vendors routinely uses parts above 255 (*ie: `2021.10.3`*).
 #[derive(Default, Debug, Clone, Eq)]
 pub struct ChocoVersion {
-    major: u8,
-    minor: u8,
-    patch: Option<u8>,
+    major: u32,
+    minor: u32,
+    patch: Option<u32>,
     /// The build part of the version, this is specified as an unsigned 32bit
     /// integer to allow fix versions.
     build: Option<u32>,
@@ -39,7 +137,7 @@ pub struct ChocoVersion {
 impl ChocoVersion {
     /// Creates a new instance of the [ChocoVersion] structure with the major
     /// and minor version set to the specified values (`major.minor`).
-    pub fn new(major: u8, minor: u8) -> ChocoVersion {
+    pub fn new(major: u32, minor: u32) -> ChocoVersion {
         ChocoVersion {
             major,
             minor,
@@ -49,7 +147,7 @@ impl ChocoVersion {
 
     /// Creates a new instance of the [ChocoVersion] structure with the
     /// specified major, minor and patch versions (`major.minor.patch`).
-    pub fn with_patch(major: u8, minor: u8, patch: u8) -> ChocoVersion {
+    pub fn with_patch(major: u32, minor: u32, patch: u32) -> ChocoVersion {
         let mut choco = ChocoVersion::new(major, minor);
         choco.set_patch(patch);
         choco
@@ -58,7 +156,7 @@ impl ChocoVersion {
     /// Creates a new instance of the [ChocoVersion] structure with the
     /// specified major, minor, patch and build versions
     /// (`major.minor.patch.build`).
-    pub fn with_build(major: u8, minor: u8, patch: u8, build: u32) -> ChocoVersion {
+    pub fn with_build(major: u32, minor: u32, patch: u32, build: u32) -> ChocoVersion {
         let mut choco = ChocoVersion::with_patch(major, minor, patch);
         choco.set_build(build);
         choco
@@ -67,15 +165,15 @@ impl ChocoVersion {
     /// Parses the specified string reference and tries to extract a new
     /// instance of [ChocoVersion]. Returns a failure if the parsing of the
     /// string was not successful.
-    pub fn parse(val: &str) -> Result<ChocoVersion, Box<dyn std::error::Error>> {
+    pub fn parse(val: &str) -> Result<ChocoVersion, SemanticVersionError> {
         if val.is_empty() {
-            return Err(Box::new(SemanticVersionError::ParseError(
+            return Err(SemanticVersionError::ParseError(
                 "There is no version string to parse".into(),
-            )));
+            ));
         } else if !val.chars().next().unwrap_or('.').is_digit(10) {
-            return Err(Box::new(SemanticVersionError::ParseError(
+            return Err(SemanticVersionError::ParseError(
                 "The version string do not start with a number".into(),
-            )));
+            ));
         }
 
         let mut major = 0;
@@ -95,11 +193,11 @@ impl ChocoVersion {
                     2 => patch = Some(ver_str.parse()?),
                     3 => build = Some(ver_str.parse()?),
                     _ => {
-                        return Err(Box::new(SemanticVersionError::ParseError(
+                        return Err(SemanticVersionError::ParseError(
                             "There were additional numeric characters after the first 4 parts of \
                              the version"
                                 .into(),
-                        )));
+                        ));
                     }
                 };
 
@@ -118,11 +216,11 @@ impl ChocoVersion {
                 2 => patch = Some(ver_str.parse()?),
                 3 => build = Some(ver_str.parse()?),
                 _ => {
-                    return Err(Box::new(SemanticVersionError::ParseError(
+                    return Err(SemanticVersionError::ParseError(
                         "There were additional numeric characters after the first 4 parts of the \
                          version"
                             .into(),
-                    )));
+                    ));
                 }
             };
             ver_str.clear();
@@ -146,8 +244,13 @@ impl ChocoVersion {
         Ok(result)
     }
 
+    /// Returns the build version (fourth part of the version), if set.
+    pub(crate) fn build(&self) -> Option<u32> {
+        self.build
+    }
+
     /// Specifically sets the patch version (third part of the version).
-    pub fn set_patch(&mut self, patch: u8) {
+    pub fn set_patch(&mut self, patch: u32) {
         self.patch = Some(patch);
     }
 
@@ -231,8 +334,22 @@ impl FixVersion for ChocoVersion {
     }
 
     fn add_fix(&mut self) -> Result<(), std::num::ParseIntError> {
+        self.add_fix_in(FixTimeZone::default())
+    }
+}
+
+impl ChocoVersion {
+    /// Creates a fix version by using the current date in the specified time
+    /// zone.
+    pub fn add_fix_in(&mut self, time_zone: FixTimeZone) -> Result<(), std::num::ParseIntError> {
+        self.add_fix_on(time_zone.today())
+    }
+
+    /// Creates a fix version by using the specified date, this is mostly
+    /// useful when the date needs to be controlled (*ie: during testing*).
+    pub fn add_fix_on(&mut self, date: NaiveDate) -> Result<(), std::num::ParseIntError> {
         if self.build.is_none() || self.is_fix_version() {
-            let fix = format!("{}", chrono::Local::today().format("%Y%m%d"));
+            let fix = format!("{}", date.format("%Y%m%d"));
             let num_fix = fix.parse()?;
 
             if self.pre_release.is_empty() {
@@ -250,15 +367,71 @@ impl FixVersion for ChocoVersion {
 
         Ok(())
     }
+
+    /// Returns wether the version is a fix version created using the
+    /// specified scheme.
+    pub fn is_fix_version_in(&self, scheme: FixScheme) -> bool {
+        match scheme {
+            FixScheme::Date => self.is_fix_version(),
+            FixScheme::Revision => {
+                matches!(self.build, Some(build) if build > 0 && !num_is_fix(build))
+            }
+        }
+    }
+
+    /// Creates a fix version using the specified scheme, the date is only
+    /// used by the [FixScheme::Date] scheme.
+    pub fn add_fix_with(
+        &mut self,
+        scheme: FixScheme,
+        date: NaiveDate,
+    ) -> Result<(), std::num::ParseIntError> {
+        match scheme {
+            FixScheme::Date => self.add_fix_on(date),
+            FixScheme::Revision => {
+                self.add_revision();
+                Ok(())
+            }
+        }
+    }
+
+    /// Creates a fix version by incrementing the build version, a date fix
+    /// version is replaced with the first revision.
+    pub fn add_revision(&mut self) {
+        let revision = match self.build {
+            Some(build) if !num_is_fix(build) => build + 1,
+            _ => 1,
+        };
+
+        self.set_build(revision);
+    }
+
+    /// Returns the version without the fix created using the specified
+    /// scheme, used when comparing the version of a package against the
+    /// version of the software. The version is returned as is when it is not
+    /// a fix version.
+    pub fn without_fix(&self, scheme: FixScheme) -> ChocoVersion {
+        let mut version = self.clone();
+
+        if scheme == FixScheme::Revision || matches!(self.build, Some(build) if num_is_fix(build)) {
+            version.build = None;
+        } else if let Some(Identifier::Numeric(num)) = self.pre_release.last() {
+            if num_is_fix(*num) {
+                version.pre_release.pop();
+            }
+        }
+
+        version
+    }
 }
 
 impl From<SemVersion> for ChocoVersion {
     fn from(semver: SemVersion) -> Self {
         let mut choco = ChocoVersion::new(
-            get_val(semver.major, u8::MAX as u64) as u8,
-            get_val(semver.minor, u8::MAX as u64) as u8,
+            get_val(semver.major, u32::MAX as u64) as u32,
+            get_val(semver.minor, u32::MAX as u64) as u32,
         );
-        choco.set_patch(get_val(semver.patch, u8::MAX as u64) as u8);
+        choco.set_patch(get_val(semver.patch, u32::MAX as u64) as u32);
         let mut pre_releases = vec![];
         for identifier in semver.pre {
             match identifier {
@@ -599,13 +772,163 @@ mod tests {
     fn add_fix_should_create_correct_fix_version() {
         let mut version = ChocoVersion::new(2, 1);
         version.add_fix().unwrap();
-        let expected = format!("2.1.0.{}", chrono::Local::now().format("%Y%m%d"));
+        let expected = format!("2.1.0.{}", chrono::Utc::now().format("%Y%m%d"));
+
+        let actual = version.to_string();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest(time_zone, case(FixTimeZone::Utc), case(FixTimeZone::Local))]
+    fn add_fix_in_should_use_date_of_time_zone(time_zone: FixTimeZone) {
+        let mut version = ChocoVersion::new(2, 1);
+        version.add_fix_in(time_zone).unwrap();
+        let expected = format!("2.1.0.{}", time_zone.today().format("%Y%m%d"));
 
         let actual = version.to_string();
 
         assert_eq!(actual, expected);
     }
 
+    #[rstest(
+        version,
+        expected,
+        case("1.0", "1.0.0.20210615"),
+        case("1.0.0.20200101", "1.0.0.20210615"),
+        case("1.0-beta", "1.0-beta-20210615"),
+        case("1.0.0.5", "1.0.0.5")
+    )]
+    fn add_fix_on_should_use_specified_date(version: &str, expected: &str) {
+        let mut version = ChocoVersion::parse(version).unwrap();
+        version
+            .add_fix_on(NaiveDate::from_ymd(2021, 6, 15))
+            .unwrap();
+
+        let actual = version.to_string();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest(
+        value,
+        expected,
+        case("utc", FixTimeZone::Utc),
+        case("UTC", FixTimeZone::Utc),
+        case(" Local", FixTimeZone::Local)
+    )]
+    fn fix_time_zone_from_str_should_parse_value(value: &str, expected: FixTimeZone) {
+        let actual = FixTimeZone::from_str(value).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn fix_time_zone_from_str_should_return_error_on_unknown_value() {
+        let actual = FixTimeZone::from_str("Europe/Oslo").unwrap_err();
+
+        assert_eq!(
+            actual.to_string(),
+            "The value 'Europe/Oslo' is not a supported fix time zone, the value must be either \
+             'utc' or 'local'!"
+        );
+    }
+
+    #[test]
+    fn fix_time_zone_default_should_be_utc() {
+        assert_eq!(FixTimeZone::default(), FixTimeZone::Utc);
+    }
+
+    #[rstest(
+        value,
+        expected,
+        case("date", FixScheme::Date),
+        case(" Revision", FixScheme::Revision)
+    )]
+    fn fix_scheme_from_str_should_parse_value(value: &str, expected: FixScheme) {
+        let actual = FixScheme::from_str(value).unwrap();
+
+        assert_eq!(actual, expected);
+        assert_eq!(FixScheme::from_str(&actual.to_string()).unwrap(), actual);
+    }
+
+    #[test]
+    fn fix_scheme_from_str_should_return_error_on_unknown_value() {
+        let actual = FixScheme::from_str("semver").unwrap_err();
+
+        assert_eq!(
+            actual.to_string(),
+            "The value 'semver' is not a supported fix scheme, the value must be either 'date' or \
+             'revision'!"
+        );
+    }
+
+    #[rstest(
+        version,
+        expected,
+        case("1.2.3", "1.2.3.1"),
+        case("1.2.3.1", "1.2.3.2"),
+        case("1.2.3.20210615", "1.2.3.1"),
+        case("1.2-beta0001", "1.2.0.1-beta0001")
+    )]
+    fn add_revision_should_increment_build_version(version: &str, expected: &str) {
+        let mut version = ChocoVersion::parse(version).unwrap();
+
+        version.add_revision();
+
+        assert_eq!(version.to_string(), expected);
+    }
+
+    #[rstest(
+        version,
+        scheme,
+        expected,
+        case("1.2.3", FixScheme::Date, false),
+        case("1.2.3.20210615", FixScheme::Date, true),
+        case("1.2.3.2", FixScheme::Date, false),
+        case("1.2.3.2", FixScheme::Revision, true),
+        case("1.2.3.20210615", FixScheme::Revision, false),
+        case("1.2.3", FixScheme::Revision, false)
+    )]
+    fn is_fix_version_in_should_detect_fix_of_scheme(
+        version: &str,
+        scheme: FixScheme,
+        expected: bool,
+    ) {
+        let version = ChocoVersion::parse(version).unwrap();
+
+        assert_eq!(version.is_fix_version_in(scheme), expected);
+    }
+
+    #[rstest(
+        version,
+        scheme,
+        expected,
+        case("1.2.3", FixScheme::Date, "1.2.3"),
+        case("1.2.3.20210615", FixScheme::Date, "1.2.3"),
+        case("1.2.3.5", FixScheme::Date, "1.2.3.5"),
+        case("1.2.3-beta-20210615", FixScheme::Date, "1.2.3-beta"),
+        case("1.2.3.5", FixScheme::Revision, "1.2.3"),
+        case("1.2.3.5-beta0001", FixScheme::Revision, "1.2.3-beta0001")
+    )]
+    fn without_fix_should_remove_fix_of_scheme(version: &str, scheme: FixScheme, expected: &str) {
+        let version = ChocoVersion::parse(version).unwrap();
+
+        let actual = version.without_fix(scheme);
+
+        assert_eq!(actual.to_string(), expected);
+    }
+
+    #[test]
+    fn add_fix_with_should_use_date_of_scheme() {
+        let mut version = ChocoVersion::parse("1.2.3").unwrap();
+
+        version
+            .add_fix_with(FixScheme::Date, NaiveDate::from_ymd(2021, 6, 15))
+            .unwrap();
+
+        assert_eq!(version.to_string(), "1.2.3.20210615");
+    }
+
     #[test]
     fn add_fix_should_not_create_fix_version_when_build_is_in_use() {
         let mut version = ChocoVersion::new(0, 2);
@@ -623,7 +946,7 @@ mod tests {
         let mut version = ChocoVersion::new(3, 3);
         version.set_build(20200826);
         version.add_fix().unwrap();
-        let expected = format!("3.3.0.{}", chrono::Local::now().format("%Y%m%d"));
+        let expected = format!("3.3.0.{}", chrono::Utc::now().format("%Y%m%d"));
 
         let actual = version.to_string();
 
@@ -634,7 +957,7 @@ mod tests {
     fn add_fix_should_create_prerelease_fix_version() {
         let mut version = ChocoVersion::parse("3.1.1-alpha").unwrap();
         version.add_fix().unwrap();
-        let expected = format!("3.1.1-alpha-{}", chrono::Local::now().format("%Y%m%d"));
+        let expected = format!("3.1.1-alpha-{}", chrono::Utc::now().format("%Y%m%d"));
 
         let actual = format!("{}", version);
 
@@ -645,7 +968,7 @@ mod tests {
     fn add_fix_should_replace_old_prelease_date_fix_with_new_date() {
         let mut version = ChocoVersion::parse("5.1.7-ceta-20100602").unwrap();
         version.add_fix().unwrap();
-        let expected = format!("5.1.7-ceta-{}", chrono::Local::now().format("%Y%m%d"));
+        let expected = format!("5.1.7-ceta-{}", chrono::Utc::now().format("%Y%m%d"));
 
         let actual = format!("{}", version);
 
@@ -656,7 +979,7 @@ mod tests {
     fn add_fix_should_not_replace_non_date_number_in_prerelease() {
         let mut version = ChocoVersion::parse("2.1.1-alpha-0010").unwrap();
         version.add_fix().unwrap();
-        let expected = format!("2.1.1-alpha0010-{}", chrono::Local::now().format("%Y%m%d"));
+        let expected = format!("2.1.1-alpha0010-{}", chrono::Utc::now().format("%Y%m%d"));
 
         let actual = format!("{}", version);
 
@@ -677,7 +1000,9 @@ mod tests {
         case("4.0.0.2-beta.5", "4.0.0.2-beta0005"),
         case("0.1.0-55", "0.1.0-unstable0055"),
         case("4.2.1-alpha54.2", "4.2.1-alpha0054-0002"),
-        case("6.1.0-55-alpha", "6.1.0-alpha0055")
+        case("6.1.0-55-alpha", "6.1.0-alpha0055"),
+        case("2021.10.3", "2021.10.3"),
+        case("300.256.1024.70000", "300.256.1024.70000")
     )]
     fn parse_should_create_correct_versions(v: &str, expected: &str) {
         let version = ChocoVersion::parse(v).unwrap();
@@ -691,7 +1016,8 @@ mod tests {
         case(""),
         case("6.2.2.2.1"),
         case("no-version"),
-        case("6.2.1.1.3.4")
+        case("6.2.1.1.3.4"),
+        case("4294967296.0")
     )]
     #[should_panic]
     fn parse_should_return_none(val: &str) {
@@ -712,6 +1038,16 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn from_should_not_clamp_parts_above_255() {
+        let expected = ChocoVersion::with_patch(2021, 10, 300);
+
+        let actual = ChocoVersion::from(SemVersion::parse("2021.10.300").unwrap());
+
+        assert_eq!(actual, expected);
+        assert_eq!(actual.to_string(), "2021.10.300");
+    }
+
     #[rstest(test, expected,
         case(
             "1.255.3-alpha+446",
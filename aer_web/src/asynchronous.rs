@@ -0,0 +1,408 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the asynchronous variant of the requests and responses, allowing
+//! several pages and binaries to be requested concurrently without blocking
+//! the current thread. The configuration of the client, the headers of the
+//! requests and the handling of the responses are shared with the blocking
+//! variant.
+//!
+//! ## Examples
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), aer_web::errors::WebError> {
+//! use aer_web::WebRequest;
+//!
+//! let request = WebRequest::create_async();
+//! let response = request
+//!     .get_html_response("https://httpbin.org/links/5/2")
+//!     .await?;
+//! let (parent_link, links) = response.read(None).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use log::{info, warn};
+use reqwest::{header, Client, Response, Url};
+
+use crate::errors::WebError;
+use crate::request::{
+    binary_headers, configure_client, handle_exit_code, not_modified, ACCEPTED_TYPES,
+};
+use crate::response::{get_file_name, parse_html, ResponseType};
+use crate::selector::{HtmlSelector, LinkSelectors};
+use crate::{LinkElement, LinkSource, LinkType, LinkTypeMap, WebRequest, WebRequestBuilder};
+
+/// Holds the necessary information to create asynchronous requests to
+/// websites, the requests uses the same headers as the blocking
+/// [WebRequest].
+#[derive(Clone)]
+pub struct AsyncWebRequest {
+    client: Client,
+    link_types: LinkTypeMap,
+    accept_language: Option<String>,
+}
+
+/// Holds a single html response that was requested asynchronously.
+#[derive(Debug)]
+pub struct AsyncHtmlResponse {
+    response: Response,
+    link_types: LinkTypeMap,
+    link_sources: Vec<LinkSource>,
+    selectors: LinkSelectors,
+}
+
+/// Holds a single binary response that was requested asynchronously, and
+/// allows downloading the remote file.
+#[derive(Debug)]
+pub struct AsyncBinaryResponse {
+    response: Response,
+    url: Url,
+    work_dir: PathBuf,
+}
+
+impl PartialEq for AsyncBinaryResponse {
+    fn eq(&self, rhs: &AsyncBinaryResponse) -> bool {
+        // We do not compare the actual response, as it is not interesting
+        self.work_dir == rhs.work_dir
+    }
+}
+
+impl WebRequest {
+    /// Creates a new instance of an asynchronous web request, using the same
+    /// user agent, headers and proxy as the blocking request.
+    pub fn create_async() -> AsyncWebRequest {
+        WebRequest::builder().build_async().unwrap()
+    }
+}
+
+impl WebRequestBuilder {
+    /// Creates the asynchronous web request, returning an error if the root
+    /// certificates could not be loaded.
+    pub fn build_async(self) -> Result<AsyncWebRequest, WebError> {
+        Ok(AsyncWebRequest {
+            client: configure_client!(Client::builder(), self),
+            link_types: LinkTypeMap::default(),
+            accept_language: None,
+        })
+    }
+}
+
+impl AsyncWebRequest {
+    /// Sets the languages that should be requested from the web servers,
+    /// instead of the default `en-US, en;q=0.8, *;q=0.5`.
+    pub fn with_accept_language<L: Into<String>>(mut self, accept_language: L) -> AsyncWebRequest {
+        self.accept_language = Some(accept_language.into());
+        self
+    }
+
+    /// Registers an additional MIME type that should be mapped to the
+    /// specified link type, this takes precedence over the default mappings.
+    pub fn with_mime_type(mut self, mime_type: &str, link_type: LinkType) -> AsyncWebRequest {
+        self.link_types.add_mime_type(mime_type, link_type);
+        self
+    }
+
+    /// Registers an additional file extension that should be mapped to the
+    /// specified link type, this takes precedence over the default mappings.
+    pub fn with_extension(mut self, extension: &str, link_type: LinkType) -> AsyncWebRequest {
+        self.link_types.add_extension(extension, link_type);
+        self
+    }
+
+    /// Makes a request to a website and requests the html at the location,
+    /// the links in the response can be found by calling
+    /// [read](AsyncHtmlResponse::read).
+    pub async fn get_html_response(&self, url: &str) -> Result<AsyncHtmlResponse, WebError> {
+        let url = Url::parse(url).map_err(|err| WebError::Other(err.to_string()))?;
+        let request = self
+            .client
+            .get(url)
+            .header(header::ACCEPT, ACCEPTED_TYPES["html"]);
+        let response = self.send(request).await?;
+
+        handle_exit_code(response, |response| AsyncHtmlResponse {
+            response,
+            link_types: self.link_types.clone(),
+            link_sources: vec![],
+            selectors: LinkSelectors::default(),
+        })
+    }
+
+    /// Makes a request to a web endpoint and requests a binary file, without
+    /// downloading the file. If an etag or last_modified argument is
+    /// specified, a [ResponseType::Updated] is returned when the server
+    /// responds that the file have not been modified.
+    pub async fn get_binary_response(
+        &self,
+        url: &str,
+        etag: Option<&str>,
+        last_modified: Option<&str>,
+    ) -> Result<ResponseType<AsyncBinaryResponse>, WebError> {
+        let url = Url::parse(url).map_err(|err| WebError::Other(err.to_string()))?;
+        let headers = binary_headers(etag, last_modified)?;
+        let response = self
+            .send(self.client.get(url.clone()).headers(headers))
+            .await?;
+        let status = response.status();
+
+        if let Some(updated) = not_modified(status) {
+            return Ok(updated);
+        }
+
+        handle_exit_code(response, |response| {
+            ResponseType::New(
+                AsyncBinaryResponse {
+                    response,
+                    url,
+                    work_dir: PathBuf::new(),
+                },
+                status.as_u16(),
+            )
+        })
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Response, WebError> {
+        let request = match self.accept_language {
+            Some(ref accept_language) => request.header(header::ACCEPT_LANGUAGE, accept_language),
+            None => request,
+        };
+
+        request.send().await.map_err(WebError::Request)
+    }
+}
+
+impl AsyncHtmlResponse {
+    /// Sets the additional sources that links should be extracted from, in
+    /// addition to the anchor elements in the response.
+    pub fn with_link_sources(mut self, link_sources: &[LinkSource]) -> AsyncHtmlResponse {
+        self.link_sources = link_sources.to_vec();
+        self
+    }
+
+    /// Sets the selector matching the anchor elements, or the sections of the
+    /// page containing the anchor elements, that links should be extracted
+    /// from.
+    pub fn with_link_selector(mut self, selector: HtmlSelector) -> AsyncHtmlResponse {
+        self.selectors.links = Some(selector);
+        self
+    }
+
+    /// Sets the selector matching the elements the version of the links are
+    /// read from, taking precedence over the version captured by the regex.
+    pub fn with_version_selector(mut self, selector: HtmlSelector) -> AsyncHtmlResponse {
+        self.selectors.version = Some(selector);
+        self
+    }
+
+    /// Returns the actual response that was returned by the server.
+    pub fn response(&self) -> &Response {
+        &self.response
+    }
+
+    /// Reads the current response, and returns the link the response came
+    /// from together with any link elements that were found in the body. Only
+    /// links matching the specified regex is returned when one is specified.
+    pub async fn read(self, re: Option<&str>) -> Result<(LinkElement, Vec<LinkElement>), WebError> {
+        let url = self.response.url().clone();
+        let headers = self.response.headers().clone();
+        let body = self.response.text().await.map_err(WebError::Request)?;
+
+        parse_html(
+            url,
+            &headers,
+            body,
+            re,
+            &self.link_types,
+            &self.link_sources,
+            &self.selectors,
+        )
+    }
+}
+
+impl AsyncBinaryResponse {
+    /// Sets the directory the file will be downloaded to.
+    pub fn set_work_dir(&mut self, path: &Path) {
+        self.work_dir = PathBuf::from(path);
+    }
+
+    /// Returns the actual response that was returned by the server.
+    pub fn response(&self) -> &Response {
+        &self.response
+    }
+
+    /// Tries to get the name of the remote file by either reading the
+    /// disposition header, or checking the url if it contains an extension.
+    pub fn file_name(&self) -> Option<String> {
+        get_file_name(self.response.headers(), self.response.url())
+    }
+
+    /// Downloads the file to the work directory, using the specified output
+    /// name or the name resolved from the response. The path of the written
+    /// file is returned on a successful download.
+    pub async fn read(self, output: Option<&str>) -> Result<PathBuf, WebError> {
+        let output = match output {
+            Some(output) => output.into(),
+            None => self
+                .file_name()
+                .ok_or_else(|| WebError::Other("Unable to extract file name request".into()))?,
+        };
+        let output = self.work_dir.join(output);
+        let mut response = self.response;
+
+        info!("Downloading '{}' to '{}'", self.url, output.display());
+
+        let file = File::create(&output).map_err(WebError::IoError)?;
+        let mut writer = BufWriter::new(file);
+
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => writer.write_all(&chunk).map_err(WebError::IoError)?,
+                Ok(None) => break,
+                Err(err) => {
+                    warn!("Failed to download '{}'", self.url);
+                    return Err(WebError::Request(err));
+                }
+            }
+        }
+        writer.flush().map_err(WebError::IoError)?;
+
+        info!("Successfully downloaded '{}'", output.display());
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    use super::*;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    fn html_response(body: &'static str) -> AsyncHtmlResponse {
+        let response = http::Response::builder()
+            .header(header::CONTENT_TYPE, "text/html")
+            .body(body)
+            .unwrap();
+
+        AsyncHtmlResponse {
+            response: Response::from(response),
+            link_types: LinkTypeMap::default(),
+            link_sources: vec![],
+            selectors: LinkSelectors::default(),
+        }
+    }
+
+    #[test]
+    fn read_should_parse_links_in_html_body() {
+        let response = html_response(
+            "<html><body><a href=\"https://example.org/app-1.0.0.zip\">App</a><a \
+             href=\"https://example.org/about\">About</a></body></html>",
+        );
+
+        let (parent, links) =
+            block_on(response.read(Some(r"app-(?P<version>[\d\.]+)\.zip"))).unwrap();
+
+        assert_eq!(parent.link_type, LinkType::Html);
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].link.as_str(), "https://example.org/app-1.0.0.zip");
+        assert_eq!(links[0].link_type, LinkType::Binary);
+        assert!(links[0].version.is_some());
+    }
+
+    /// Starts a web server answering a single request with the specified
+    /// response, returning the url of the server and the request that was
+    /// received by the server.
+    fn http_server(response: &'static str) -> (String, std::thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![];
+            let mut buffer = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let read = stream.read(&mut buffer).unwrap();
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..read]);
+            }
+            stream.write_all(response.as_bytes()).unwrap();
+
+            String::from_utf8(request).unwrap().to_lowercase()
+        });
+
+        (url, server)
+    }
+
+    fn create_request() -> AsyncWebRequest {
+        WebRequest::builder().without_proxy().build_async().unwrap()
+    }
+
+    #[test]
+    fn get_html_response_should_read_links_from_server() {
+        let (url, server) = http_server(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 58\r\nConnection: \
+             close\r\n\r\n<html><body><a href=\"/app-1.0.0.zip\">App</a></body></html>",
+        );
+        let request = create_request().with_accept_language("nb-NO");
+
+        let (parent, links) = block_on(async {
+            let response = request.get_html_response(&url).await?;
+            response.read(None).await
+        })
+        .unwrap();
+        let received = server.join().unwrap();
+
+        assert_eq!(parent.link.as_str(), url);
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].link.as_str(), format!("{}app-1.0.0.zip", url));
+        assert!(received.contains("accept-language: nb-no\r\n"));
+        assert!(received.contains("accept: text/html; charset=utf-8\r\n"));
+    }
+
+    #[test]
+    fn get_html_response_should_give_error_on_404_status_code() {
+        let (url, server) =
+            http_server("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+
+        let actual = block_on(create_request().get_html_response(&url));
+        server.join().unwrap();
+
+        assert!(matches!(actual, Err(WebError::Request(_))));
+    }
+
+    #[test]
+    fn get_binary_response_should_return_updated_response_by_etag() {
+        let (url, server) = http_server(
+            "HTTP/1.1 304 Not Modified\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        );
+
+        let actual = block_on(create_request().get_binary_response(&url, Some("abc"), None));
+        let received = server.join().unwrap();
+
+        assert_eq!(actual.unwrap(), ResponseType::Updated(304));
+        assert!(received.contains("if-none-match: \"abc\"\r\n"));
+        assert!(received.contains("accept: application/octet-stream\r\n"));
+    }
+
+    #[test]
+    fn create_async_should_build_client() {
+        let _ = WebRequest::create_async().with_accept_language("nb-NO");
+
+        // Nothing more is done, as we only test if a panic happens which we do
+        // not expect.
+    }
+}
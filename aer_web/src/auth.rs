@@ -0,0 +1,621 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the named authentication profiles used when communicating with
+//! private or internal feeds (*ie: Nexus or Artifactory repositories*).
+//!
+//! A profile holds the url of the feed and references to the credentials of
+//! the feed, the credentials themselves are read from environment variables
+//! or from the secrets loaded from a separate credentials file. Every request
+//! sent to a location below the url of a profile is authenticated using the
+//! credentials of the profile, using basic authentication, a bearer token
+//! and/or custom headers.
+//!
+//! Rules can be added to select the profile used for the urls matching a
+//! pattern, these takes precedence over the urls of the feeds. As the rules are
+//! read from the package files, a rule is only used for the host of the feed
+//! of the profile, or for the additional hosts the profile have been allowed
+//! for in the configuration file. This prevents a package from sending the
+//! credentials of a private feed to any other host.
+//!
+//! ### Examples
+//!
+//! ```
+//! use aer_web::auth::{AuthProfile, AuthProfiles};
+//! use reqwest::Url;
+//!
+//! let profiles = AuthProfiles::new().with_profile(
+//!     AuthProfile::new(
+//!         "internal",
+//!         Url::parse("https://nexus.example.org/repository/choco/").unwrap(),
+//!     )
+//!     .with_credentials("ci-user", "NEXUS_PASSWORD"),
+//! );
+//!
+//! let url = Url::parse("https://nexus.example.org/repository/choco/aer/1.0.0").unwrap();
+//! assert_eq!(
+//!     profiles.find(&url).map(|profile| profile.name()),
+//!     Some("internal")
+//! );
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use log::warn;
+use reqwest::blocking::RequestBuilder;
+use reqwest::header::{HeaderMap, AUTHORIZATION};
+use reqwest::Url;
+use serde::Deserialize;
+
+/// The secrets of a profile that are read from a credentials file, used when
+/// the corresponding environment variables have not been set. The values are
+/// never included when the secrets are formatted for debugging.
+#[derive(Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Secrets {
+    /// The API key used when pushing packages to the feed.
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// The password of the user of the profile.
+    #[serde(default)]
+    pub password: Option<String>,
+    /// The bearer token sent in the `Authorization` header.
+    #[serde(default)]
+    pub token: Option<String>,
+    /// The values of custom headers, keyed by the name of the header.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl fmt::Debug for Secrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let redact = |value: &Option<String>| value.as_ref().map(|_| "***");
+
+        f.debug_struct("Secrets")
+            .field("api_key", &redact(&self.api_key))
+            .field("password", &redact(&self.password))
+            .field("token", &redact(&self.token))
+            .field("headers", &self.headers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// A named profile holding the url of a feed, and the references to the
+/// credentials used when communicating with the feed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthProfile {
+    name: String,
+    feed: Url,
+    /// The name of the environment variable holding the API key used when
+    /// pushing packages to the feed.
+    pub api_key_env: Option<String>,
+    /// The user name used when authenticating with the feed.
+    pub username: Option<String>,
+    /// The name of the environment variable holding the password of the user.
+    pub password_env: Option<String>,
+    /// The name of the environment variable holding the bearer token sent in
+    /// the `Authorization` header.
+    pub token_env: Option<String>,
+    /// The names of the environment variables holding the values of custom
+    /// headers, keyed by the name of the header.
+    pub headers_env: BTreeMap<String, String>,
+    /// The additional hosts the rules of packages are allowed to use the
+    /// profile for, the host of the feed is always allowed.
+    pub hosts: Vec<String>,
+    secrets: Secrets,
+}
+
+impl AuthProfile {
+    /// Creates a new profile with the specified name for the feed, without
+    /// any credentials.
+    pub fn new<N: Into<String>>(name: N, feed: Url) -> AuthProfile {
+        AuthProfile {
+            name: name.into(),
+            feed,
+            api_key_env: None,
+            username: None,
+            password_env: None,
+            token_env: None,
+            headers_env: BTreeMap::new(),
+            hosts: vec![],
+            secrets: Secrets::default(),
+        }
+    }
+
+    /// Sets the name of the environment variable holding the API key.
+    pub fn with_api_key_env<T: Into<String>>(mut self, api_key_env: T) -> AuthProfile {
+        self.api_key_env = Some(api_key_env.into());
+        self
+    }
+
+    /// Sets the user name, and the name of the environment variable holding
+    /// the password used when authenticating with the feed.
+    pub fn with_credentials<U: Into<String>, P: Into<String>>(
+        mut self,
+        username: U,
+        password_env: P,
+    ) -> AuthProfile {
+        self.username = Some(username.into());
+        self.password_env = Some(password_env.into());
+        self
+    }
+
+    /// Sets the name of the environment variable holding the bearer token.
+    pub fn with_token_env<T: Into<String>>(mut self, token_env: T) -> AuthProfile {
+        self.token_env = Some(token_env.into());
+        self
+    }
+
+    /// Adds a custom header, where the value is read from the environment
+    /// variable with the specified name.
+    pub fn with_header_env<H: Into<String>, E: Into<String>>(
+        mut self,
+        header: H,
+        env: E,
+    ) -> AuthProfile {
+        self.headers_env.insert(header.into(), env.into());
+        self
+    }
+
+    /// Allows the rules of packages to use the profile for the specified
+    /// additional hosts (*ie: the host the files of the feed are downloaded
+    /// from*).
+    pub fn with_hosts<I: IntoIterator<Item = S>, S: Into<String>>(
+        mut self,
+        hosts: I,
+    ) -> AuthProfile {
+        self.hosts.extend(hosts.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets the secrets read from a credentials file, which are used when the
+    /// environment variables have not been set.
+    pub fn with_secrets(mut self, secrets: Secrets) -> AuthProfile {
+        self.secrets = secrets;
+        self
+    }
+
+    /// Returns the name of the profile.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the url of the feed the profile is used for.
+    pub fn feed(&self) -> &Url {
+        &self.feed
+    }
+
+    /// Returns the API key stored in the configured environment variable, or
+    /// in the secrets of the profile when the variable have not been set.
+    pub fn api_key(&self) -> Option<String> {
+        read_secret(self.api_key_env.as_deref(), &self.secrets.api_key)
+    }
+
+    /// Returns the password stored in the configured environment variable, or
+    /// in the secrets of the profile when the variable have not been set.
+    pub fn password(&self) -> Option<String> {
+        read_secret(self.password_env.as_deref(), &self.secrets.password)
+    }
+
+    /// Returns the bearer token stored in the configured environment
+    /// variable, or in the secrets of the profile when the variable have not
+    /// been set.
+    pub fn token(&self) -> Option<String> {
+        read_secret(self.token_env.as_deref(), &self.secrets.token)
+    }
+
+    /// Returns the custom headers of the profile, the values stored in the
+    /// environment variables takes precedence over the values in the secrets
+    /// of the profile. Headers without a value are not returned.
+    pub fn headers(&self) -> BTreeMap<String, String> {
+        let mut headers = self.secrets.headers.clone();
+
+        for (header, env) in &self.headers_env {
+            if let Some(value) = read_env(env) {
+                headers.insert(header.clone(), value);
+            }
+        }
+
+        headers
+    }
+
+    /// Returns wether the specified url is located below the url of the feed,
+    /// the scheme, host and port must be equal.
+    pub fn matches(&self, url: &Url) -> bool {
+        let feed_path = self.feed.path().trim_end_matches('/');
+        let path = url.path();
+
+        url.scheme() == self.feed.scheme()
+            && url.host_str() == self.feed.host_str()
+            && url.port_or_known_default() == self.feed.port_or_known_default()
+            && path.starts_with(feed_path)
+            && matches!(path[feed_path.len()..].chars().next(), None | Some('/'))
+    }
+
+    /// Returns wether the rules of packages are allowed to use the profile
+    /// for the specified url, which is only the case for the host of the feed
+    /// and the additional hosts of the profile.
+    pub fn allows_host(&self, url: &Url) -> bool {
+        match url.host_str() {
+            Some(host) => self
+                .feed
+                .host_str()
+                .into_iter()
+                .chain(self.hosts.iter().map(String::as_str))
+                .any(|allowed| allowed.eq_ignore_ascii_case(host)),
+            None => false,
+        }
+    }
+
+    /// Removes the basic authentication, the bearer token and the custom
+    /// headers of the profile from the specified headers.
+    pub(crate) fn remove_credentials(&self, headers: &mut HeaderMap) {
+        headers.remove(AUTHORIZATION);
+        for header in self.headers_env.keys().chain(self.secrets.headers.keys()) {
+            headers.remove(header.as_str());
+        }
+    }
+
+    /// Adds the basic authentication, the bearer token and the custom
+    /// headers of the profile to the request. The request is returned
+    /// unchanged when the profile have no credentials.
+    pub(crate) fn authenticate(&self, request: RequestBuilder) -> RequestBuilder {
+        let request = match self.username {
+            Some(ref username) => request.basic_auth(username, self.password()),
+            None => request,
+        };
+        let request = match self.token() {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        };
+
+        self.headers()
+            .into_iter()
+            .fold(request, |request, (header, value)| {
+                request.header(header.as_str(), value)
+            })
+    }
+}
+
+/// A collection of authentication profiles, together with the rules
+/// selecting the profile used for the urls matching a pattern.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AuthProfiles {
+    profiles: Vec<AuthProfile>,
+    rules: Vec<(String, String)>,
+}
+
+impl AuthProfiles {
+    /// Creates a new empty collection of profiles.
+    pub fn new() -> AuthProfiles {
+        AuthProfiles::default()
+    }
+
+    /// Adds the profile to the collection, replacing any existing profile
+    /// with the same name.
+    pub fn with_profile(mut self, profile: AuthProfile) -> AuthProfiles {
+        self.add(profile);
+        self
+    }
+
+    /// Adds the profile to the collection, replacing any existing profile
+    /// with the same name.
+    pub fn add(&mut self, profile: AuthProfile) {
+        self.profiles
+            .retain(|existing| existing.name != profile.name);
+        self.profiles.push(profile);
+    }
+
+    /// Adds a rule using the profile with the specified name for every url
+    /// matching the pattern, where `*` matches any number of characters. In
+    /// the scheme and host of the pattern (*ie: `https://*.example.org`*), `*`
+    /// never matches a `/`, `?` or `@`. Rules are matched in the order they
+    /// are added.
+    pub fn with_rule<P: Into<String>, N: Into<String>>(
+        mut self,
+        pattern: P,
+        profile: N,
+    ) -> AuthProfiles {
+        self.rules.push((pattern.into(), profile.into()));
+        self
+    }
+
+    /// Returns the profile with the specified name.
+    pub fn get(&self, name: &str) -> Option<&AuthProfile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    /// Returns the profile used for the specified url, the profile of the
+    /// first rule matching the url is preferred. Rules are ignored for hosts
+    /// the profile is not allowed to be used for. Otherwise, when several
+    /// profiles matches the url the profile with the longest feed url is
+    /// returned.
+    pub fn find(&self, url: &Url) -> Option<&AuthProfile> {
+        let rule = self
+            .rules
+            .iter()
+            .filter(|(pattern, _)| matches_pattern(pattern, url))
+            .filter_map(|(_, name)| self.get(name))
+            .find(|profile| {
+                let allowed = profile.allows_host(url);
+                if !allowed {
+                    warn!(
+                        "Ignoring the rule using the '{}' profile for '{}', the profile is not \
+                         allowed for this host!",
+                        profile.name,
+                        url.host_str().unwrap_or_default()
+                    );
+                }
+                allowed
+            });
+        if rule.is_some() {
+            return rule;
+        }
+
+        self.profiles
+            .iter()
+            .filter(|profile| profile.matches(url))
+            .max_by_key(|profile| profile.feed.path().len())
+    }
+
+    /// Returns wether the collection contains any profiles.
+    pub fn is_empty(&self) -> bool {
+        self.profiles.is_empty()
+    }
+
+    /// Returns an iterator over all profiles in the collection.
+    pub fn iter(&self) -> impl Iterator<Item = &AuthProfile> {
+        self.profiles.iter()
+    }
+}
+
+/// Returns wether the url matches the pattern, where `*` matches any number
+/// of characters. The scheme and host of the pattern are matched separately
+/// from the rest of the url, where `*` never matches a `/`, `?` or `@`.
+fn matches_pattern(pattern: &str, url: &Url) -> bool {
+    let text = url.as_str();
+
+    match (split_authority(pattern), split_authority(text)) {
+        (Some((pattern_authority, pattern_rest)), Some((authority, rest))) => {
+            matches_wildcard(pattern_authority, authority, &['/', '?', '@'])
+                && matches_wildcard(pattern_rest, rest, &[])
+        }
+        _ => matches_wildcard(pattern, text, &[]),
+    }
+}
+
+/// Splits the url (or pattern) into the scheme and host (*ie:
+/// `https://example.org`*), and the rest of the url.
+fn split_authority(text: &str) -> Option<(&str, &str)> {
+    let start = text.find("://")? + 3;
+    let end = text[start..]
+        .find(['/', '?', '#'])
+        .map_or(text.len(), |index| start + index);
+
+    Some((&text[..end], &text[end..]))
+}
+
+/// Returns wether the text matches the pattern, where `*` matches any number
+/// of characters except the specified characters.
+fn matches_wildcard(pattern: &str, text: &str, excluded: &[char]) -> bool {
+    let skips = |skipped: &str| !skipped.contains(|c| excluded.contains(&c));
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    if !text.starts_with(first) {
+        return false;
+    }
+
+    let mut rest = &text[first.len()..];
+    let mut parts = parts.peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            return rest.ends_with(part) && skips(&rest[..rest.len() - part.len()]);
+        }
+        match rest.find(part) {
+            Some(index) if skips(&rest[..index]) => rest = &rest[index + part.len()..],
+            _ => return false,
+        }
+    }
+
+    rest.is_empty()
+}
+
+fn read_secret(env: Option<&str>, secret: &Option<String>) -> Option<String> {
+    env.and_then(read_env).or_else(|| secret.clone())
+}
+
+fn read_env(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn profile(name: &str, feed: &str) -> AuthProfile {
+        AuthProfile::new(name, Url::parse(feed).unwrap())
+    }
+
+    #[rstest(
+        url,
+        expected,
+        case("https://nexus.example.org/repository/choco/aer.1.0.0.nupkg", true),
+        case("https://nexus.example.org/repository/choco", true),
+        case("https://nexus.example.org:443/repository/choco/", true),
+        case("https://nexus.example.org/repository/other/", false),
+        case("https://nexus.example.org/repository/chocolatey/", false),
+        case("http://nexus.example.org/repository/choco/", false),
+        case("https://example.org/repository/choco/", false)
+    )]
+    fn matches_should_only_match_urls_below_feed(url: &str, expected: bool) {
+        let profile = profile("internal", "https://nexus.example.org/repository/choco/");
+
+        assert_eq!(profile.matches(&Url::parse(url).unwrap()), expected);
+    }
+
+    #[test]
+    fn find_should_return_most_specific_profile() {
+        let profiles = AuthProfiles::new()
+            .with_profile(profile("nexus", "https://nexus.example.org/"))
+            .with_profile(profile(
+                "choco",
+                "https://nexus.example.org/repository/choco/",
+            ));
+
+        let actual = profiles.find(
+            &Url::parse("https://nexus.example.org/repository/choco/api/v2/package/").unwrap(),
+        );
+
+        assert_eq!(actual.map(|profile| profile.name()), Some("choco"));
+    }
+
+    #[test]
+    fn add_should_replace_profile_with_same_name() {
+        let mut profiles =
+            AuthProfiles::new().with_profile(profile("internal", "https://old.example.org/"));
+
+        profiles.add(profile("internal", "https://new.example.org/"));
+
+        assert_eq!(profiles.iter().count(), 1);
+        assert_eq!(
+            profiles.get("internal").unwrap().feed().as_str(),
+            "https://new.example.org/"
+        );
+    }
+
+    #[rstest(
+        pattern,
+        text,
+        expected,
+        case("https://example.org/*", "https://example.org/file.zip", true),
+        case("https://example.org/*", "https://example.com/file.zip", false),
+        case(
+            "https://*.example.org/*.zip",
+            "https://cdn.example.org/file.zip",
+            true
+        ),
+        case(
+            "https://*.example.org/*.zip",
+            "https://cdn.example.org/file.exe",
+            false
+        ),
+        case("https://example.org/file.zip", "https://example.org/file.zip", true),
+        case(
+            "https://example.org/file.zip",
+            "https://example.org/file.zip.asc",
+            false
+        ),
+        case("*", "https://example.org/", true),
+        case("https://*.example.org/*", "https://evil.test/?.example.org/x", false),
+        case(
+            "https://*.example.org/*",
+            "https://evil.test/cdn.example.org/x",
+            false
+        ),
+        case("https://*.example.org/*", "https://evil.test?.example.org/x", false),
+        case("https://*example.org/*", "https://cdn.example.org@evil.test/x", false)
+    )]
+    fn matches_pattern_should_match_wildcards(pattern: &str, text: &str, expected: bool) {
+        assert_eq!(
+            matches_pattern(pattern, &Url::parse(text).unwrap()),
+            expected
+        );
+    }
+
+    #[test]
+    fn find_should_prefer_profile_of_matching_rule() {
+        let profiles = AuthProfiles::new()
+            .with_profile(profile("nexus", "https://nexus.example.org/"))
+            .with_profile(
+                profile("releases", "https://releases.example.org/")
+                    .with_hosts(vec!["nexus.example.org"]),
+            )
+            .with_rule("https://nexus.example.org/private/*", "releases")
+            .with_rule("https://nexus.example.org/*", "missing");
+
+        let private = profiles.find(&Url::parse("https://nexus.example.org/private/a").unwrap());
+        let public = profiles.find(&Url::parse("https://nexus.example.org/public/a").unwrap());
+
+        assert_eq!(private.map(|profile| profile.name()), Some("releases"));
+        assert_eq!(public.map(|profile| profile.name()), Some("nexus"));
+    }
+
+    #[rstest(
+        url,
+        expected,
+        case("https://releases.example.org/app.exe", Some("releases")),
+        case("https://DOWNLOADS.example.org/app.exe", Some("releases")),
+        case("https://evil.test/app.exe", None),
+        case("https://evil.test/releases.example.org/app.exe", None)
+    )]
+    fn find_should_only_use_rules_for_allowed_hosts(url: &str, expected: Option<&str>) {
+        let profiles = AuthProfiles::new()
+            .with_profile(
+                profile("releases", "https://releases.example.org/")
+                    .with_hosts(vec!["downloads.example.org"]),
+            )
+            .with_rule("*", "releases");
+
+        let actual = profiles.find(&Url::parse(url).unwrap());
+
+        assert_eq!(actual.map(|profile| profile.name()), expected);
+    }
+
+    #[test]
+    fn password_should_fall_back_to_secrets() {
+        let profile = profile("internal", "https://nexus.example.org/")
+            .with_credentials("ci-user", "AER_TEST_MISSING_PROFILE_PASSWORD")
+            .with_token_env("AER_TEST_MISSING_PROFILE_TOKEN")
+            .with_secrets(Secrets {
+                password: Some("secret".into()),
+                token: Some("token".into()),
+                ..Secrets::default()
+            });
+
+        assert_eq!(profile.password().as_deref(), Some("secret"));
+        assert_eq!(profile.token().as_deref(), Some("token"));
+    }
+
+    #[test]
+    fn headers_should_prefer_environment_variables() {
+        std::env::set_var("AER_TEST_PROFILE_HEADER", "from-env");
+        let mut secrets = Secrets::default();
+        secrets
+            .headers
+            .insert("X-Api-Key".into(), "from-file".into());
+        secrets.headers.insert("X-Team".into(), "packaging".into());
+        let profile = profile("internal", "https://nexus.example.org/")
+            .with_header_env("X-Api-Key", "AER_TEST_PROFILE_HEADER")
+            .with_secrets(secrets);
+
+        let actual = profile.headers();
+
+        assert_eq!(actual["X-Api-Key"], "from-env");
+        assert_eq!(actual["X-Team"], "packaging");
+    }
+
+    #[test]
+    fn secrets_should_not_be_formatted_for_debugging() {
+        let secrets = Secrets {
+            password: Some("super-secret".into()),
+            ..Secrets::default()
+        };
+
+        let actual = format!("{:?}", secrets);
+
+        assert!(!actual.contains("super-secret"), "{}", actual);
+    }
+
+    #[test]
+    fn api_key_should_return_none_without_environment_variable() {
+        let profile = profile("internal", "https://nexus.example.org/")
+            .with_api_key_env("AER_TEST_MISSING_PROFILE_KEY");
+
+        assert_eq!(profile.api_key(), None);
+    }
+}
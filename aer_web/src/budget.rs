@@ -0,0 +1,208 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the budget limiting how many transfers may run concurrently, and
+//! how many bytes may be downloaded at the same time. Metadata fetches (*html
+//! pages and API calls*) are prioritized over binary downloads, making sure
+//! a batch keeps making progress when huge files are downloaded on
+//! constrained machines.
+
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex};
+
+/// The kind of transfer that is requesting a permit from the budget.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransferKind {
+    /// A small fetch of an html page or an API endpoint.
+    Metadata,
+    /// The download of a binary file with the specified size in bytes, the
+    /// size is `0` when the server did not report the size of the file.
+    Binary(u64),
+}
+
+#[derive(Debug, Default)]
+struct BudgetState {
+    transfers: usize,
+    bytes: u64,
+    waiting_metadata: usize,
+}
+
+/// The budget shared between every clone of a web request, limiting the
+/// number of concurrent transfers and the total number of bytes in flight.
+///
+/// ## Examples
+///
+/// ```
+/// use aer_web::budget::{TransferBudget, TransferKind};
+///
+/// let budget = TransferBudget::new(2, 1024 * 1024);
+/// let permit = budget.acquire(TransferKind::Binary(512 * 1024));
+///
+/// assert_eq!(budget.in_flight(), (1, 512 * 1024));
+/// drop(permit);
+/// assert_eq!(budget.in_flight(), (0, 0));
+/// ```
+#[derive(Clone)]
+pub struct TransferBudget {
+    max_transfers: usize,
+    max_bytes: u64,
+    state: Arc<(Mutex<BudgetState>, Condvar)>,
+}
+
+impl TransferBudget {
+    /// Creates a new budget allowing the specified number of concurrent
+    /// transfers, and the specified number of bytes to be downloaded at the
+    /// same time. A value of `0` means the limit is not used.
+    pub fn new(max_transfers: usize, max_bytes: u64) -> TransferBudget {
+        TransferBudget {
+            max_transfers: if max_transfers == 0 {
+                usize::MAX
+            } else {
+                max_transfers
+            },
+            max_bytes: if max_bytes == 0 { u64::MAX } else { max_bytes },
+            state: Arc::new((Mutex::new(BudgetState::default()), Condvar::new())),
+        }
+    }
+
+    /// Waits until the budget allows the transfer to start, and returns the
+    /// permit that releases the reserved budget when dropped.
+    ///
+    /// Binary downloads wait until no metadata fetches are waiting, and until
+    /// the size of the file fits within the byte limit. Files larger than the
+    /// byte limit are only started when no other binary is downloaded.
+    pub fn acquire(&self, kind: TransferKind) -> TransferPermit {
+        let (ref lock, ref condvar) = *self.state;
+        let mut state = lock.lock().unwrap();
+        let bytes = match kind {
+            TransferKind::Metadata => {
+                state.waiting_metadata += 1;
+                0
+            }
+            TransferKind::Binary(bytes) => bytes,
+        };
+
+        while !self.can_start(&state, kind) {
+            state = condvar.wait(state).unwrap();
+        }
+
+        if kind == TransferKind::Metadata {
+            state.waiting_metadata -= 1;
+        }
+        state.transfers += 1;
+        state.bytes += bytes;
+
+        TransferPermit {
+            budget: self.clone(),
+            bytes,
+        }
+    }
+
+    /// Returns the number of transfers and the number of bytes that are
+    /// currently in flight.
+    pub fn in_flight(&self) -> (usize, u64) {
+        let state = self.state.0.lock().unwrap();
+
+        (state.transfers, state.bytes)
+    }
+
+    fn can_start(&self, state: &BudgetState, kind: TransferKind) -> bool {
+        if state.transfers >= self.max_transfers {
+            return false;
+        }
+
+        match kind {
+            TransferKind::Metadata => true,
+            TransferKind::Binary(bytes) => {
+                state.waiting_metadata == 0
+                    && (state.bytes == 0 || state.bytes.saturating_add(bytes) <= self.max_bytes)
+            }
+        }
+    }
+
+    fn release(&self, bytes: u64) {
+        let (ref lock, ref condvar) = *self.state;
+        let mut state = lock.lock().unwrap();
+        state.transfers -= 1;
+        state.bytes -= bytes;
+        condvar.notify_all();
+    }
+}
+
+impl fmt::Debug for TransferBudget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransferBudget")
+            .field("max_transfers", &self.max_transfers)
+            .field("max_bytes", &self.max_bytes)
+            .finish()
+    }
+}
+
+/// The reservation of a single transfer, the reserved budget is released
+/// when the permit is dropped.
+#[derive(Debug)]
+pub struct TransferPermit {
+    budget: TransferBudget,
+    bytes: u64,
+}
+
+impl Drop for TransferPermit {
+    fn drop(&mut self) {
+        self.budget.release(self.bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest(
+        transfers,
+        bytes,
+        waiting_metadata,
+        kind,
+        expected,
+        case(0, 0, 0, TransferKind::Metadata, true),
+        case(2, 0, 0, TransferKind::Metadata, false),
+        case(1, 0, 1, TransferKind::Binary(10), false),
+        case(1, 60, 0, TransferKind::Binary(40), true),
+        case(1, 60, 0, TransferKind::Binary(41), false),
+        case(0, 0, 0, TransferKind::Binary(500), true)
+    )]
+    fn can_start_should_respect_limits(
+        transfers: usize,
+        bytes: u64,
+        waiting_metadata: usize,
+        kind: TransferKind,
+        expected: bool,
+    ) {
+        let budget = TransferBudget::new(2, 100);
+        let state = BudgetState {
+            transfers,
+            bytes,
+            waiting_metadata,
+        };
+
+        assert_eq!(budget.can_start(&state, kind), expected);
+    }
+
+    #[test]
+    fn acquire_should_wait_for_released_permits() {
+        let budget = TransferBudget::new(1, 0);
+        let permit = budget.acquire(TransferKind::Binary(10));
+
+        let waiting = budget.clone();
+        let handle = std::thread::spawn(move || {
+            let _permit = waiting.acquire(TransferKind::Metadata);
+            waiting.in_flight()
+        });
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert_eq!(budget.in_flight(), (1, 10));
+        drop(permit);
+
+        assert_eq!(handle.join().unwrap(), (1, 0));
+        assert_eq!(budget.in_flight(), (0, 0));
+    }
+}
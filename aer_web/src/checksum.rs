@@ -0,0 +1,786 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the checksum types supported when generating and verifying the
+//! checksums of downloaded files, together with an incremental hasher and a
+//! writer generating the checksum of the content while it is written.
+//!
+//! Checksum files published next to the downloaded files (*ie: `SHA256SUMS`
+//! or `setup.exe.sha256`*) can be parsed using [parse_checksum_file], which
+//! supports the output of `sha256sum` and similar tools, the BSD style output
+//! (*ie: `SHA256 (setup.exe) = <checksum>`*) and files only containing a
+//! single checksum.
+
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use blake2::Blake2b;
+use log::info;
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use sha3::{Sha3_256, Sha3_512};
+
+use crate::errors::WebError;
+use crate::request::handle_exit_code;
+use crate::WebRequest;
+
+/// The algorithms that can be used when generating checksums.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChecksumType {
+    /// The MD5 algorithm, only kept for servers still publishing these.
+    Md5,
+    /// The SHA-1 algorithm.
+    Sha1,
+    /// The SHA-256 algorithm, which is the default algorithm.
+    Sha256,
+    /// The SHA-512 algorithm.
+    Sha512,
+    /// The SHA3-256 algorithm.
+    Sha3_256,
+    /// The SHA3-512 algorithm.
+    Sha3_512,
+    /// The BLAKE2b algorithm, generating a 512 bit checksum.
+    Blake2b,
+    /// The BLAKE3 algorithm, generating a 256 bit checksum.
+    Blake3,
+}
+
+impl FromStr for ChecksumType {
+    type Err = &'static str;
+
+    fn from_str(val: &str) -> std::result::Result<Self, <Self as std::str::FromStr>::Err> {
+        let val: &str = &val.trim().to_lowercase();
+
+        match val {
+            "md5" => Ok(ChecksumType::Md5),
+            "sha1" => Ok(ChecksumType::Sha1),
+            "sha2" | "sha256" => Ok(ChecksumType::Sha256),
+            "sha512" => Ok(ChecksumType::Sha512),
+            "sha3" | "sha3-256" => Ok(ChecksumType::Sha3_256),
+            "sha3-512" => Ok(ChecksumType::Sha3_512),
+            "blake2" | "blake2b" => Ok(ChecksumType::Blake2b),
+            "blake3" => Ok(ChecksumType::Blake3),
+            _ => Err("The value is not a supported checksum type!"),
+        }
+    }
+}
+
+impl Display for ChecksumType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            ChecksumType::Md5 => f.write_str("md5"),
+            ChecksumType::Sha1 => f.write_str("sha1"),
+            ChecksumType::Sha256 => f.write_str("sha256"),
+            ChecksumType::Sha512 => f.write_str("sha512"),
+            ChecksumType::Sha3_256 => f.write_str("sha3-256"),
+            ChecksumType::Sha3_512 => f.write_str("sha3-512"),
+            ChecksumType::Blake2b => f.write_str("blake2b"),
+            ChecksumType::Blake3 => f.write_str("blake3"),
+        }
+    }
+}
+
+impl Default for ChecksumType {
+    fn default() -> Self {
+        Self::Sha256
+    }
+}
+
+impl ChecksumType {
+    /// Returns all of the supported checksum types.
+    pub fn variants() -> &'static [ChecksumType] {
+        static VARIANTS: &[ChecksumType] = &[
+            ChecksumType::Md5,
+            ChecksumType::Sha1,
+            ChecksumType::Sha256,
+            ChecksumType::Sha512,
+            ChecksumType::Sha3_256,
+            ChecksumType::Sha3_512,
+            ChecksumType::Blake2b,
+            ChecksumType::Blake3,
+        ];
+
+        VARIANTS
+    }
+
+    /// Returns the names of all the supported checksum types.
+    pub fn variants_str() -> &'static [&'static str] {
+        static VARIANTS: &[&str] = &[
+            "md5", "sha1", "sha256", "sha512", "sha3-256", "sha3-512", "blake2b", "blake3",
+        ];
+
+        VARIANTS
+    }
+
+    /// Returns wether the checksum type can be used in the install scripts of
+    /// Chocolatey packages, which only supports the MD5 and SHA algorithms.
+    pub fn is_chocolatey_supported(&self) -> bool {
+        matches!(
+            self,
+            ChecksumType::Md5 | ChecksumType::Sha1 | ChecksumType::Sha256 | ChecksumType::Sha512
+        )
+    }
+
+    /// Generates the checksum of the file at the specified path, the checksum
+    /// is returned as a lowercase hex string.
+    pub fn generate(&self, path: &Path) -> Result<String, std::io::Error> {
+        let mut f = File::open(path)?;
+
+        self.generate_from_reader(&mut f)
+    }
+
+    /// Generates the checksum of the content read from the specified reader
+    /// until the end is reached, the checksum is returned as a lowercase hex
+    /// string.
+    pub fn generate_from_reader<R: Read + ?Sized>(
+        &self,
+        reader: &mut R,
+    ) -> Result<String, std::io::Error> {
+        let mut hasher = ChecksumHasher::new(*self);
+        std::io::copy(reader, &mut hasher)?;
+
+        Ok(hasher.finalize())
+    }
+
+    /// Creates a new hasher generating the checksum incrementally using this
+    /// algorithm.
+    pub fn hasher(&self) -> ChecksumHasher {
+        ChecksumHasher::new(*self)
+    }
+}
+
+/// A single checksum read from a checksum file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChecksumEntry {
+    /// The name of the file the checksum was generated from, files only
+    /// containing a single checksum do not name the file.
+    pub file_name: Option<String>,
+    /// The checksum as a lowercase hex string.
+    pub checksum: String,
+    /// The algorithm used when generating the checksum, this is either the
+    /// algorithm named by BSD style entries or guessed from the length of the
+    /// checksum.
+    pub checksum_type: Option<ChecksumType>,
+}
+
+impl ChecksumEntry {
+    /// Returns wether the entry holds the checksum of the file with the
+    /// specified name, any directories in the name of the entry are ignored.
+    /// Entries that do not name the file matches every file.
+    pub fn matches(&self, file_name: &str) -> bool {
+        match self.file_name {
+            Some(ref name) => name.rsplit(&['/', '\\'][..]).next() == Some(file_name),
+            None => true,
+        }
+    }
+}
+
+/// Parses the content of a checksum file, and returns the checksums found in
+/// the file. Empty lines, comments and lines that do not contain a valid
+/// checksum are ignored.
+///
+/// ## Examples
+///
+/// ```
+/// use aer_web::checksum::{parse_checksum_file, ChecksumType};
+///
+/// let entries = parse_checksum_file(
+///     "SHA1 (aer.zip) = \
+///      86263d6db9edba53dca1cafca3853e2c81983afa\nab66430167ceb33784387abe71cf7c7d *aer.exe\n",
+/// );
+///
+/// assert_eq!(entries.len(), 2);
+/// assert_eq!(entries[0].checksum_type, Some(ChecksumType::Sha1));
+/// assert_eq!(entries[1].file_name.as_deref(), Some("aer.exe"));
+/// assert_eq!(entries[1].checksum_type, Some(ChecksumType::Md5));
+/// ```
+pub fn parse_checksum_file(content: &str) -> Vec<ChecksumEntry> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| parse_bsd_line(line).or_else(|| parse_line(line)))
+        .collect()
+}
+
+/// Returns the checksum of the file with the specified name, a file only
+/// containing a single checksum without a file name matches any file.
+pub fn find_checksum<'a>(
+    entries: &'a [ChecksumEntry],
+    file_name: &str,
+) -> Option<&'a ChecksumEntry> {
+    entries
+        .iter()
+        .find(|entry| entry.file_name.is_some() && entry.matches(file_name))
+        .or_else(|| match entries {
+            [entry] if entry.file_name.is_none() => Some(entry),
+            _ => None,
+        })
+}
+
+/// Parses a BSD style line in the form `SHA256 (file) = checksum`.
+fn parse_bsd_line(line: &str) -> Option<ChecksumEntry> {
+    let (name, checksum) = line.rsplit_once('=')?;
+    let (algorithm, file_name) = name.trim().split_once(" (")?;
+    let file_name = file_name.strip_suffix(')')?;
+    let checksum = checksum.trim();
+
+    if !is_checksum(checksum) {
+        return None;
+    }
+
+    Some(ChecksumEntry {
+        file_name: Some(file_name.into()),
+        checksum: checksum.to_lowercase(),
+        checksum_type: ChecksumType::from_str(algorithm)
+            .ok()
+            .or_else(|| guess_checksum_type(checksum)),
+    })
+}
+
+/// Parses a line in the form `checksum  file`, `checksum *file` or a line
+/// only containing the checksum.
+fn parse_line(line: &str) -> Option<ChecksumEntry> {
+    let (checksum, file_name) = match line.split_once(char::is_whitespace) {
+        Some((checksum, file_name)) => {
+            let file_name = file_name.trim_start();
+            let file_name = file_name.strip_prefix('*').unwrap_or(file_name);
+            (checksum, Some(file_name.to_string()))
+        }
+        None => (line, None),
+    };
+
+    if !is_checksum(checksum) {
+        return None;
+    }
+
+    Some(ChecksumEntry {
+        file_name: file_name.filter(|name| !name.is_empty()),
+        checksum: checksum.to_lowercase(),
+        checksum_type: guess_checksum_type(checksum),
+    })
+}
+
+fn is_checksum(value: &str) -> bool {
+    value.len() >= 32 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Guesses the algorithm used when generating the checksum from the length of
+/// the checksum, the MD5 and SHA algorithms are preferred when several
+/// algorithms generates checksums of the same length.
+fn guess_checksum_type(checksum: &str) -> Option<ChecksumType> {
+    match checksum.len() {
+        32 => Some(ChecksumType::Md5),
+        40 => Some(ChecksumType::Sha1),
+        64 => Some(ChecksumType::Sha256),
+        128 => Some(ChecksumType::Sha512),
+        _ => None,
+    }
+}
+
+impl WebRequest {
+    /// Downloads the checksum file at the specified url, and returns the
+    /// checksums found in the file.
+    pub fn get_checksum_file(&self, url: &str) -> Result<Vec<ChecksumEntry>, WebError> {
+        let url = reqwest::Url::parse(url).map_err(|err| WebError::Other(err.to_string()))?;
+
+        info!("Reading checksums from '{}'", url);
+
+        let response = self.get_api_response(&url, "text/plain, */*", None)?;
+        let text = handle_exit_code(response, |rsp| rsp.text())?.map_err(WebError::Request)?;
+
+        Ok(parse_checksum_file(&text))
+    }
+}
+
+enum Hasher {
+    Md5(Md5),
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Sha512(Sha512),
+    Sha3_256(Sha3_256),
+    Sha3_512(Sha3_512),
+    Blake2b(Blake2b),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    fn new(checksum_type: ChecksumType) -> Hasher {
+        match checksum_type {
+            ChecksumType::Md5 => Hasher::Md5(Md5::new()),
+            ChecksumType::Sha1 => Hasher::Sha1(Sha1::new()),
+            ChecksumType::Sha256 => Hasher::Sha256(Sha256::new()),
+            ChecksumType::Sha512 => Hasher::Sha512(Sha512::new()),
+            ChecksumType::Sha3_256 => Hasher::Sha3_256(Sha3_256::new()),
+            ChecksumType::Sha3_512 => Hasher::Sha3_512(Sha3_512::new()),
+            ChecksumType::Blake2b => Hasher::Blake2b(Blake2b::new()),
+            ChecksumType::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Md5(hasher) => hasher.update(data),
+            Hasher::Sha1(hasher) => hasher.update(data),
+            Hasher::Sha256(hasher) => hasher.update(data),
+            Hasher::Sha512(hasher) => hasher.update(data),
+            Hasher::Sha3_256(hasher) => hasher.update(data),
+            Hasher::Sha3_512(hasher) => hasher.update(data),
+            Hasher::Blake2b(hasher) => hasher.update(data),
+            Hasher::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    fn finalize(self) -> String {
+        match self {
+            Hasher::Md5(hasher) => format!("{:x}", hasher.finalize()),
+            Hasher::Sha1(hasher) => format!("{:x}", hasher.finalize()),
+            Hasher::Sha256(hasher) => format!("{:x}", hasher.finalize()),
+            Hasher::Sha512(hasher) => format!("{:x}", hasher.finalize()),
+            Hasher::Sha3_256(hasher) => format!("{:x}", hasher.finalize()),
+            Hasher::Sha3_512(hasher) => format!("{:x}", hasher.finalize()),
+            Hasher::Blake2b(hasher) => format!("{:x}", hasher.finalize()),
+            Hasher::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+/// Generates a checksum incrementally from the content it is updated with,
+/// allowing the checksum to be generated while the content is processed
+/// without reading the content a second time. The hasher can also be used as
+/// a writer, where every written byte is included in the checksum.
+///
+/// ## Examples
+///
+/// ```
+/// use aer_web::checksum::{ChecksumHasher, ChecksumType};
+///
+/// let mut hasher = ChecksumHasher::new(ChecksumType::Sha256);
+/// hasher.update(b"a");
+/// hasher.update(b"er");
+///
+/// assert_eq!(
+///     hasher.finalize(),
+///     ChecksumType::Sha256
+///         .generate_from_reader(&mut &b"aer"[..])
+///         .unwrap()
+/// );
+/// ```
+pub struct ChecksumHasher {
+    checksum_type: ChecksumType,
+    hasher: Hasher,
+}
+
+impl ChecksumHasher {
+    /// Creates a new hasher generating a checksum using the specified
+    /// algorithm.
+    pub fn new(checksum_type: ChecksumType) -> ChecksumHasher {
+        ChecksumHasher {
+            checksum_type,
+            hasher: Hasher::new(checksum_type),
+        }
+    }
+
+    /// Returns the algorithm used when generating the checksum.
+    pub fn checksum_type(&self) -> ChecksumType {
+        self.checksum_type
+    }
+
+    /// Includes the specified content in the checksum.
+    pub fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+
+    /// Returns the checksum of all the content the hasher have been updated
+    /// with, the checksum is a lowercase hex string.
+    pub fn finalize(self) -> String {
+        self.hasher.finalize()
+    }
+}
+
+impl Write for ChecksumHasher {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A writer that generates the checksum of all the content written through
+/// it, before passing the content on to the inner writer.
+///
+/// ## Examples
+///
+/// ```
+/// use std::io::{Read, Write};
+///
+/// use aer_web::checksum::{ChecksumType, ChecksumWriter};
+///
+/// let mut writer = ChecksumWriter::new(vec![], ChecksumType::Sha1);
+/// writer.write_all(b"aer").unwrap();
+/// let (content, checksum) = writer.finish();
+///
+/// assert_eq!(content, b"aer");
+/// assert_eq!(checksum.len(), 40);
+/// ```
+pub struct ChecksumWriter<W: Write> {
+    inner: W,
+    hasher: ChecksumHasher,
+}
+
+impl<W: Write> ChecksumWriter<W> {
+    /// Creates a new writer generating a checksum using the specified
+    /// algorithm.
+    pub fn new(inner: W, checksum_type: ChecksumType) -> ChecksumWriter<W> {
+        ChecksumWriter {
+            inner,
+            hasher: ChecksumHasher::new(checksum_type),
+        }
+    }
+
+    /// Includes the content of the specified reader in the checksum, without
+    /// writing the content to the inner writer. This is used when appending
+    /// to content that have already been written.
+    pub(crate) fn digest_existing<R: Read>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        std::io::copy(reader, &mut self.hasher).map(|_| ())
+    }
+
+    /// Returns the inner writer together with the checksum of the content
+    /// that was written, the checksum is a lowercase hex string.
+    pub fn finish(self) -> (W, String) {
+        (self.inner, self.hasher.finalize())
+    }
+}
+
+impl<W: Write> Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[test]
+    fn default_should_be_sha256() {
+        assert_eq!(ChecksumType::default(), ChecksumType::Sha256);
+    }
+
+    #[test]
+    fn variants_should_return_supported_values() {
+        let expected = &[
+            ChecksumType::Md5,
+            ChecksumType::Sha1,
+            ChecksumType::Sha256,
+            ChecksumType::Sha512,
+            ChecksumType::Sha3_256,
+            ChecksumType::Sha3_512,
+            ChecksumType::Blake2b,
+            ChecksumType::Blake3,
+        ];
+
+        let actual = ChecksumType::variants();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn variants_str_should_return_supported_values_as_a_string() {
+        let expected = &[
+            "md5", "sha1", "sha256", "sha512", "sha3-256", "sha3-512", "blake2b", "blake3",
+        ];
+
+        let actual = ChecksumType::variants_str();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest(
+        test,
+        expected,
+        case(ChecksumType::Md5, "md5"),
+        case(ChecksumType::Sha1, "sha1"),
+        case(ChecksumType::Sha256, "sha256"),
+        case(ChecksumType::Sha512, "sha512"),
+        case(ChecksumType::Sha3_256, "sha3-256"),
+        case(ChecksumType::Sha3_512, "sha3-512"),
+        case(ChecksumType::Blake2b, "blake2b"),
+        case(ChecksumType::Blake3, "blake3")
+    )]
+    fn fmt_should_format_checksum_type_in_lowercase(test: ChecksumType, expected: &str) {
+        let actual = test.to_string();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest(
+        test,
+        expected,
+        case("Md5", ChecksumType::Md5),
+        case("sha1", ChecksumType::Sha1),
+        case("SHA2", ChecksumType::Sha256),
+        case("sha256", ChecksumType::Sha256),
+        case("Sha512", ChecksumType::Sha512),
+        case("SHA3", ChecksumType::Sha3_256),
+        case("sha3-256", ChecksumType::Sha3_256),
+        case("sha3-512", ChecksumType::Sha3_512),
+        case("blake2", ChecksumType::Blake2b),
+        case("Blake2b", ChecksumType::Blake2b),
+        case("BLAKE3", ChecksumType::Blake3)
+    )]
+    fn from_str_should_create_expected_type(test: &str, expected: ChecksumType) {
+        let actual = ChecksumType::from_str(test);
+
+        assert_eq!(actual, Ok(expected));
+    }
+
+    #[test]
+    fn from_str_should_parse_all_variant_names() {
+        for (name, expected) in ChecksumType::variants_str()
+            .iter()
+            .zip(ChecksumType::variants())
+        {
+            assert_eq!(ChecksumType::from_str(name), Ok(*expected));
+        }
+    }
+
+    #[rstest(
+        test,
+        expected,
+        case(ChecksumType::Md5, true),
+        case(ChecksumType::Sha512, true),
+        case(ChecksumType::Sha3_256, false),
+        case(ChecksumType::Blake3, false)
+    )]
+    fn is_chocolatey_supported_should_only_accept_md5_and_sha_algorithms(
+        test: ChecksumType,
+        expected: bool,
+    ) {
+        assert_eq!(test.is_chocolatey_supported(), expected);
+    }
+
+    #[test]
+    fn from_str_should_return_error_on_unknown_value() {
+        let actual = ChecksumType::from_str("unknown value").unwrap_err();
+
+        assert_eq!(actual, "The value is not a supported checksum type!")
+    }
+
+    #[rstest(
+        algorithm,
+        expected,
+        case(ChecksumType::Md5, "ab66430167ceb33784387abe71cf7c7d"),
+        case(ChecksumType::Sha1, "86263d6db9edba53dca1cafca3853e2c81983afa"),
+        case(ChecksumType::Sha256, "856ee247a62ef795346a4e5f9d1106373a2add6185aa2b2609e6816496c7c839"),
+        case(ChecksumType::Sha512, "dfa0d071ed794349d2f67f452a8cb08fcf9f572653cccd193ebd62b5baefd93059d4178615dd7587bd2d6146b9be689418029d28d2d32d7551edc04606a1d204"),
+        case(ChecksumType::Sha3_256, "432dbf403701f1ecb0aff1c3990742895b880ae7e1b6b5cf2a17e50be5294ff9"),
+        case(ChecksumType::Sha3_512, "c376cbcd174610300ec2922fa17243b0c5207df204d6ecef59039f7bc57a00783f06ccbb71cb3592691f5f4c2a1f320fd991b153e55a6d336c6f528bce6cca8c"),
+        case(ChecksumType::Blake2b, "2a859a19e1e52677ac0dcf4d1b6013c6217c531a1e254bb62c2ee00251ce598d096d7353e67e0281d9005219bd349034df8623c2a9998f8f6961403aad5fb0f3")
+    )]
+    fn generate_should_generate_correct_checksum(
+        algorithm: ChecksumType,
+        expected: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let path = PathBuf::from("test-data/checksum-test.bin.txt");
+
+        let actual = algorithm.generate(&path)?;
+
+        assert_eq!(actual, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn generate_from_reader_should_generate_blake3_checksum() {
+        let actual = ChecksumType::Blake3.generate_from_reader(&mut std::io::empty());
+
+        assert_eq!(
+            actual.unwrap(),
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+    }
+
+    #[test]
+    fn generate_should_return_error_on_non_existing_file() {
+        let path = PathBuf::from("non-existing");
+
+        let actual = ChecksumType::default().generate(&path).unwrap_err();
+
+        assert_eq!(actual.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[rstest(
+        algorithm,
+        case(ChecksumType::Md5),
+        case(ChecksumType::Sha1),
+        case(ChecksumType::Sha256),
+        case(ChecksumType::Sha512),
+        case(ChecksumType::Sha3_256),
+        case(ChecksumType::Blake2b),
+        case(ChecksumType::Blake3)
+    )]
+    fn generate_from_reader_should_generate_same_checksum_as_file(algorithm: ChecksumType) {
+        let path = PathBuf::from("test-data/checksum-test.bin.txt");
+        let content = std::fs::read(&path).unwrap();
+
+        let actual = algorithm.generate_from_reader(&mut content.as_slice());
+
+        assert_eq!(actual.unwrap(), algorithm.generate(&path).unwrap());
+    }
+
+    #[test]
+    fn checksum_hasher_should_generate_checksum_of_updated_content() {
+        let content = std::fs::read("test-data/checksum-test.bin.txt").unwrap();
+        let mut hasher = ChecksumType::Sha256.hasher();
+
+        for chunk in content.chunks(5) {
+            hasher.update(chunk);
+        }
+
+        assert_eq!(hasher.checksum_type(), ChecksumType::Sha256);
+        assert_eq!(
+            hasher.finalize(),
+            "856ee247a62ef795346a4e5f9d1106373a2add6185aa2b2609e6816496c7c839"
+        );
+    }
+
+    #[test]
+    fn parse_checksum_file_should_parse_sha256sum_output() {
+        let content = "# Checksums of the release
+856ee247a62ef795346a4e5f9d1106373a2add6185aa2b2609e6816496c7c839  aer-x64.zip
+856EE247A62EF795346A4E5F9D1106373A2ADD6185AA2B2609E6816496C7C839 *dist/aer-x86.zip
+
+invalid line
+";
+
+        let actual = parse_checksum_file(content);
+
+        assert_eq!(
+            actual,
+            [
+                ChecksumEntry {
+                    file_name: Some("aer-x64.zip".into()),
+                    checksum: "856ee247a62ef795346a4e5f9d1106373a2add6185aa2b2609e6816496c7c839"
+                        .into(),
+                    checksum_type: Some(ChecksumType::Sha256)
+                },
+                ChecksumEntry {
+                    file_name: Some("dist/aer-x86.zip".into()),
+                    checksum: "856ee247a62ef795346a4e5f9d1106373a2add6185aa2b2609e6816496c7c839"
+                        .into(),
+                    checksum_type: Some(ChecksumType::Sha256)
+                }
+            ]
+        );
+    }
+
+    #[rstest(
+        line,
+        expected_name,
+        expected_type,
+        case(
+            "SHA512 (aer setup.exe) = dfa0d071ed794349d2f67f452a8cb08fcf9f572653cccd193ebd62b5baefd93059d4178615dd7587bd2d6146b9be689418029d28d2d32d7551edc04606a1d204",
+            Some("aer setup.exe"),
+            Some(ChecksumType::Sha512)
+        ),
+        case(
+            "SHA3-256 (aer.zip) = 432dbf403701f1ecb0aff1c3990742895b880ae7e1b6b5cf2a17e50be5294ff9",
+            Some("aer.zip"),
+            Some(ChecksumType::Sha3_256)
+        ),
+        case(
+            "ab66430167ceb33784387abe71cf7c7d",
+            None,
+            Some(ChecksumType::Md5)
+        ),
+        case(
+            "2a859a19e1e52677ac0dcf4d1b6013c6217c531a1e254bb62c2ee00251ce598d096d7353e67e0281d9005219bd349034df8623c2a9998f8f6961403aad5fb0f3  aer.zip",
+            Some("aer.zip"),
+            Some(ChecksumType::Sha512)
+        )
+    )]
+    fn parse_checksum_file_should_parse_supported_formats(
+        line: &str,
+        expected_name: Option<&str>,
+        expected_type: Option<ChecksumType>,
+    ) {
+        let actual = parse_checksum_file(line);
+
+        assert_eq!(actual.len(), 1);
+        assert_eq!(actual[0].file_name.as_deref(), expected_name);
+        assert_eq!(actual[0].checksum_type, expected_type);
+    }
+
+    #[rstest(
+        content,
+        file_name,
+        expected,
+        case(
+            "ab66430167ceb33784387abe71cf7c7d",
+            "aer.zip",
+            Some("ab66430167ceb33784387abe71cf7c7d")
+        ),
+        case(
+            "ab66430167ceb33784387abe71cf7c7d  \
+             other.zip\n86263d6db9edba53dca1cafca3853e2c81983afa  ./aer.zip",
+            "aer.zip",
+            Some("86263d6db9edba53dca1cafca3853e2c81983afa")
+        ),
+        case("ab66430167ceb33784387abe71cf7c7d  other.zip", "aer.zip", None),
+        case(
+            "ab66430167ceb33784387abe71cf7c7d\n86263d6db9edba53dca1cafca3853e2c81983afa",
+            "aer.zip",
+            None
+        )
+    )]
+    fn find_checksum_should_find_checksum_of_file(
+        content: &str,
+        file_name: &str,
+        expected: Option<&str>,
+    ) {
+        let entries = parse_checksum_file(content);
+
+        let actual = find_checksum(&entries, file_name);
+
+        assert_eq!(actual.map(|entry| entry.checksum.as_str()), expected);
+    }
+
+    #[test]
+    fn checksum_writer_should_generate_checksum_of_written_content() {
+        let content = std::fs::read("test-data/checksum-test.bin.txt").unwrap();
+        let mut writer = ChecksumWriter::new(vec![], ChecksumType::Sha256);
+
+        for chunk in content.chunks(7) {
+            writer.write_all(chunk).unwrap();
+        }
+        let (written, checksum) = writer.finish();
+
+        assert_eq!(written, content);
+        assert_eq!(
+            checksum,
+            "856ee247a62ef795346a4e5f9d1106373a2add6185aa2b2609e6816496c7c839"
+        );
+    }
+}
@@ -0,0 +1,300 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the crawling of html pages, following the links found on a page
+//! to other pages on the same host and collecting the binary links found on
+//! every page that was read.
+//!
+//! This automates the common pattern of parsing a release listing to find the
+//! page of the latest release, and then parsing that page to find the files
+//! of the release.
+
+use std::collections::{HashSet, VecDeque};
+
+use aer_version::Versions;
+use log::{info, warn};
+use regex::Regex;
+use reqwest::Url;
+
+use crate::errors::WebError;
+use crate::request::WebRequest;
+use crate::{LinkElement, LinkType, WebResponse};
+
+/// The maximum number of pages that are read when crawling, unless changed
+/// in the options.
+pub const DEFAULT_MAX_PAGES: usize = 50;
+
+/// The settings used when crawling html pages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrawlOptions {
+    /// The number of links that are followed from the first page, a depth of
+    /// `0` only reads the first page.
+    pub depth: usize,
+    /// The regex the links must match to be followed, all html links on the
+    /// same host are followed when no regex have been specified.
+    pub follow: Option<String>,
+    /// The regex the binary links must match to be collected, the version of
+    /// the links are read from the `version` group of the regex.
+    pub regex: Option<String>,
+    /// The maximum number of pages to read, including the first page.
+    pub max_pages: usize,
+}
+
+impl CrawlOptions {
+    /// Creates a new instance of the crawl settings, following links up to
+    /// the specified depth.
+    pub fn new(depth: usize) -> CrawlOptions {
+        CrawlOptions {
+            depth,
+            follow: None,
+            regex: None,
+            max_pages: DEFAULT_MAX_PAGES,
+        }
+    }
+
+    /// Only follow the links matching the specified regex.
+    pub fn with_follow<S: Into<String>>(mut self, regex: S) -> CrawlOptions {
+        self.follow = Some(regex.into());
+        self
+    }
+
+    /// Only collect the binary links matching the specified regex.
+    pub fn with_regex<S: Into<String>>(mut self, regex: S) -> CrawlOptions {
+        self.regex = Some(regex.into());
+        self
+    }
+}
+
+impl WebRequest {
+    /// Crawls the html page at the specified url, following the links to
+    /// other pages on the same host up to the depth of the options. The
+    /// binary links found on every page are returned in the order they were
+    /// found, without any duplicates.
+    ///
+    /// Pages that can not be read after the first page are skipped with a
+    /// warning, while an error is returned when the first page can not be
+    /// read.
+    pub fn crawl(&self, url: &str, options: &CrawlOptions) -> Result<Vec<LinkElement>, WebError> {
+        let url = Url::parse(url).map_err(|err| WebError::Other(err.to_string()))?;
+        let follow = compile(options.follow.as_deref())?;
+        let regex = compile(options.regex.as_deref())?;
+        let host = url.host_str().map(String::from);
+
+        let mut queue = VecDeque::new();
+        let mut visited = HashSet::new();
+        let mut results: Vec<LinkElement> = vec![];
+        let mut pages = 0;
+        visited.insert(without_fragment(&url));
+        queue.push_back((url, 0));
+
+        while let Some((url, depth)) = queue.pop_front() {
+            if pages >= options.max_pages {
+                warn!(
+                    "Maximum number of pages ({}) reached, stopping!",
+                    options.max_pages
+                );
+                break;
+            }
+            pages += 1;
+
+            info!("Crawling '{}' (depth: {})", url, depth);
+            let links = match self
+                .get_html_response(url.as_str())
+                .and_then(|response| response.read(None))
+            {
+                Ok((_, links)) => links,
+                Err(err) if depth > 0 => {
+                    warn!("Unable to read the page '{}': {}", url, err);
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+
+            for mut link in links {
+                if link.is_binary() {
+                    if let Some(ref regex) = regex {
+                        let captures = match regex.captures(link.link.as_str()) {
+                            Some(captures) => captures,
+                            None => continue,
+                        };
+                        link.version = captures
+                            .name("version")
+                            .and_then(|version| Versions::parse(version.as_str()).ok());
+                    }
+                    if !results.iter().any(|result| result.link == link.link) {
+                        results.push(link);
+                    }
+                } else if depth < options.depth
+                    && is_page(&link)
+                    && link.link.host_str().map(String::from) == host
+                    && follow
+                        .as_ref()
+                        .map(|follow| follow.is_match(link.link.as_str()))
+                        .unwrap_or(true)
+                {
+                    let next = without_fragment(&link.link);
+                    if visited.insert(next.clone()) {
+                        queue.push_back((next, depth + 1));
+                    }
+                }
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+fn compile(regex: Option<&str>) -> Result<Option<Regex>, WebError> {
+    regex
+        .map(|regex| Regex::new(regex).map_err(|err| WebError::Other(err.to_string())))
+        .transpose()
+}
+
+/// Returns wether the link can be an html page that is worth following.
+fn is_page(link: &LinkElement) -> bool {
+    matches!(link.link_type, LinkType::Html | LinkType::Unknown)
+        && matches!(link.link.scheme(), "http" | "https")
+}
+
+fn without_fragment(url: &Url) -> Url {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::fixtures::{FixtureMode, Fixtures};
+
+    fn create_fixtures(name: &str, pages: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let fixtures = Fixtures::new(&dir, FixtureMode::Replay);
+
+        for (url, body) in pages {
+            let recorded = json!({
+                "url": url,
+                "final_url": url,
+                "status": 200,
+                "headers": { "content-type": "text/html" },
+                "body": body,
+            });
+            std::fs::write(
+                fixtures.path_for(&Url::parse(url).unwrap()),
+                recorded.to_string(),
+            )
+            .unwrap();
+        }
+
+        dir
+    }
+
+    fn request(dir: &Path) -> WebRequest {
+        WebRequest::create().with_fixtures(Fixtures::new(dir, FixtureMode::Replay))
+    }
+
+    const PAGES: &[(&str, &str)] = &[
+        (
+            "https://example.org/releases",
+            r#"<a href="/releases/tag/2.0.0">2.0.0</a>
+               <a href="/releases/tag/1.0.0#notes">1.0.0</a>
+               <a href="https://other.org/releases/tag/3.0.0">3.0.0</a>
+               <a href="/about">About</a>"#,
+        ),
+        (
+            "https://example.org/releases/tag/2.0.0",
+            r#"<a href="/releases/download/2.0.0/app-x64.exe">x64</a>
+               <a href="/releases/download/2.0.0/app-x64.msi">x64</a>
+               <a href="/releases/tag/2.0.0/assets">Assets</a>"#,
+        ),
+        (
+            "https://example.org/releases/tag/1.0.0",
+            r#"<a href="/releases/download/1.0.0/app-x64.exe">x64</a>"#,
+        ),
+        (
+            "https://example.org/releases/tag/2.0.0/assets",
+            r#"<a href="/releases/download/2.0.0/app-arm64.exe">arm64</a>"#,
+        ),
+    ];
+
+    #[test]
+    fn crawl_should_collect_binary_links_of_followed_pages() {
+        let dir = create_fixtures("aer-web-crawl-depth-test", PAGES);
+        let options = CrawlOptions::new(1)
+            .with_follow("/releases/tag/")
+            .with_regex(r"/(?P<version>[\d\.]+)/app-x64\.exe$");
+
+        let actual = request(&dir)
+            .crawl("https://example.org/releases", &options)
+            .unwrap();
+
+        assert_eq!(
+            actual
+                .iter()
+                .map(|link| (link.link.as_str(), link.version.clone()))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    "https://example.org/releases/download/2.0.0/app-x64.exe",
+                    Some(Versions::parse("2.0.0").unwrap())
+                ),
+                (
+                    "https://example.org/releases/download/1.0.0/app-x64.exe",
+                    Some(Versions::parse("1.0.0").unwrap())
+                )
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn crawl_should_follow_links_up_to_depth() {
+        let dir = create_fixtures("aer-web-crawl-nested-test", PAGES);
+        let options = CrawlOptions::new(2).with_follow("/releases/tag/2");
+
+        let actual = request(&dir)
+            .crawl("https://example.org/releases", &options)
+            .unwrap();
+
+        assert_eq!(
+            actual
+                .iter()
+                .map(|link| link.link.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "https://example.org/releases/download/2.0.0/app-x64.exe",
+                "https://example.org/releases/download/2.0.0/app-x64.msi",
+                "https://example.org/releases/download/2.0.0/app-arm64.exe"
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn crawl_should_only_read_first_page_without_depth() {
+        let dir = create_fixtures("aer-web-crawl-no-depth-test", PAGES);
+
+        let actual = request(&dir)
+            .crawl("https://example.org/releases", &CrawlOptions::new(0))
+            .unwrap();
+
+        assert!(actual.is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn crawl_should_return_error_when_first_page_can_not_be_read() {
+        let dir = create_fixtures("aer-web-crawl-missing-test", &[]);
+
+        let actual = request(&dir).crawl("https://example.org/releases", &CrawlOptions::new(1));
+
+        assert!(matches!(actual, Err(WebError::Other(_))));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,351 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the delta downloads, allowing a re-uploaded file to be assembled
+//! from the unchanged chunks of a previously downloaded copy of the file
+//! together with the chunks that have changed upstream.
+//!
+//! Each chunk is compared by range-fetching small samples from the start and
+//! the end of the chunk, and comparing the hashes of the samples with the
+//! hashes of the same bytes in the previous file. Only the chunks where the
+//! samples differ are downloaded. A full download is used whenever the server
+//! do not support ranges, or the size of the remote file have changed.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use log::{info, warn};
+use reqwest::blocking::Response;
+use reqwest::{header, StatusCode, Url};
+
+use crate::budget::TransferKind;
+use crate::errors::WebError;
+use crate::response::ResponseType;
+use crate::{WebRequest, WebResponse};
+
+/// The options controlling how a delta download is split into chunks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeltaOptions {
+    /// The size in bytes of each chunk that is compared with the previous
+    /// file.
+    pub chunk_size: u64,
+    /// The number of bytes fetched from the start and the end of each chunk
+    /// when comparing the chunk with the previous file.
+    pub sample_size: u64,
+}
+
+impl Default for DeltaOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: 1024 * 1024,
+            sample_size: 64,
+        }
+    }
+}
+
+/// The outcome of a delta download.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeltaDownload {
+    /// The path the file was written to.
+    pub path: PathBuf,
+    /// The number of bytes that was reused from the previous file.
+    pub reused: u64,
+    /// The number of bytes that was downloaded from the remote server,
+    /// including the samples used when comparing chunks.
+    pub downloaded: u64,
+    /// Wether the delta could not be used, and the whole file was downloaded
+    /// instead.
+    pub full: bool,
+}
+
+/// The bytes of a requested range, together with the total size of the
+/// remote file when reported by the server.
+type RangeContent = (Vec<u8>, Option<u64>);
+
+/// A single range of bytes in the remote file, the end is inclusive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ByteRange {
+    start: u64,
+    end: u64,
+}
+
+impl ByteRange {
+    fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+}
+
+impl WebRequest {
+    /// Downloads the file at the specified url to the output path, reusing
+    /// the unchanged chunks of the previously downloaded file. The whole file
+    /// is downloaded when the previous file do not exist, the server do not
+    /// support range requests or the size of the file have changed.
+    ///
+    /// ## Warning
+    ///
+    /// Chunks are only compared by samples of the chunk, as the server do not
+    /// expose the hashes of the remote chunks. Changes in the middle of a
+    /// chunk that leaves the size of the file unchanged will not be detected,
+    /// as such delta downloads should only be used when the reduced transfer
+    /// is worth this trade-off.
+    pub fn download_delta(
+        &self,
+        url: &str,
+        previous: &Path,
+        output: &Path,
+        options: &DeltaOptions,
+    ) -> Result<DeltaDownload, WebError> {
+        let url = Url::parse(url).map_err(|err| WebError::Other(err.to_string()))?;
+        let previous_size = match std::fs::metadata(previous) {
+            Ok(metadata) if metadata.is_file() => metadata.len(),
+            _ => return self.download_full(&url, output),
+        };
+
+        let (probe, remote_size) = self
+            .get_range(&url, ByteRange { start: 0, end: 0 })?
+            .unwrap_or_default();
+        if remote_size != Some(previous_size) || previous_size == 0 {
+            info!(
+                "Unable to use a delta download for '{}', falling back to a full download",
+                url
+            );
+            return self.download_full(&url, output);
+        }
+
+        let mut source = File::open(previous).map_err(WebError::IoError)?;
+        let mut writer = BufWriter::new(File::create(output).map_err(WebError::IoError)?);
+        let mut reused = 0;
+        let mut downloaded = probe.len() as u64;
+
+        for chunk in split_chunks(previous_size, options.chunk_size) {
+            let mut unchanged = true;
+            for sample in sample_ranges(chunk, options.sample_size) {
+                let remote = match self.get_range(&url, sample)? {
+                    Some((bytes, _)) => bytes,
+                    None => return self.download_full(&url, output),
+                };
+                downloaded += remote.len() as u64;
+
+                if hash_bytes(&remote) != hash_bytes(&read_range(&mut source, sample)?) {
+                    unchanged = false;
+                    break;
+                }
+            }
+
+            if unchanged {
+                writer
+                    .write_all(&read_range(&mut source, chunk)?)
+                    .map_err(WebError::IoError)?;
+                reused += chunk.len();
+            } else {
+                let remote = match self.get_range(&url, chunk)? {
+                    Some((bytes, _)) if bytes.len() as u64 == chunk.len() => bytes,
+                    _ => return self.download_full(&url, output),
+                };
+                writer.write_all(&remote).map_err(WebError::IoError)?;
+                downloaded += chunk.len();
+            }
+        }
+        writer.flush().map_err(WebError::IoError)?;
+
+        info!(
+            "Assembled '{}' reusing {} bytes, and downloading {} bytes",
+            output.display(),
+            reused,
+            downloaded
+        );
+
+        Ok(DeltaDownload {
+            path: output.to_path_buf(),
+            reused,
+            downloaded,
+            full: false,
+        })
+    }
+
+    /// Requests a single range of the remote file, returning the bytes of the
+    /// range together with the total size of the file. Nothing is returned
+    /// when the server responds with the whole file instead of the range.
+    fn get_range(&self, url: &Url, range: ByteRange) -> Result<Option<RangeContent>, WebError> {
+        let _permit = self.acquire(TransferKind::Binary(range.len()));
+        let request = self.client.get(url.clone()).header(
+            header::RANGE,
+            format!("bytes={}-{}", range.start, range.end),
+        );
+        let response = self.send(request, url)?;
+
+        if response.status() != StatusCode::PARTIAL_CONTENT {
+            return Ok(None);
+        }
+
+        let size = total_size(&response);
+        let bytes = response.bytes().map_err(WebError::Request)?;
+
+        Ok(Some((bytes.to_vec(), size)))
+    }
+
+    fn download_full(&self, url: &Url, output: &Path) -> Result<DeltaDownload, WebError> {
+        let file_name = output
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| WebError::Other("The output path must include a file name".into()))?;
+
+        match self.get_binary_response(url.as_str(), None, None)? {
+            ResponseType::New(mut response, _) => {
+                if let Some(parent) = output.parent() {
+                    response.set_work_dir(parent);
+                }
+                let path = response.read(Some(file_name))?;
+                let downloaded = std::fs::metadata(&path).map_err(WebError::IoError)?.len();
+
+                Ok(DeltaDownload {
+                    path,
+                    reused: 0,
+                    downloaded,
+                    full: true,
+                })
+            }
+            ResponseType::Updated(status) => {
+                warn!("The server responded with status {} on '{}'", status, url);
+                Err(WebError::Other(format!(
+                    "The server responded with status {} when downloading '{}'",
+                    status, url
+                )))
+            }
+        }
+    }
+}
+
+/// Reads the total size of the remote file from the `Content-Range` header
+/// (*ie: `bytes 0-0/1024`*).
+fn total_size(response: &Response) -> Option<u64> {
+    response
+        .headers()
+        .get(header::CONTENT_RANGE)
+        .and_then(|range| range.to_str().ok())
+        .and_then(|range| range.rsplit('/').next())
+        .and_then(|size| size.trim().parse().ok())
+}
+
+fn split_chunks(size: u64, chunk_size: u64) -> Vec<ByteRange> {
+    let chunk_size = chunk_size.max(1);
+
+    (0..size)
+        .step_by(chunk_size as usize)
+        .map(|start| ByteRange {
+            start,
+            end: (start + chunk_size).min(size) - 1,
+        })
+        .collect()
+}
+
+/// Returns the ranges sampled from the start and the end of the chunk, the
+/// whole chunk is used as a single sample when the chunk is too small to be
+/// sampled twice.
+fn sample_ranges(chunk: ByteRange, sample_size: u64) -> Vec<ByteRange> {
+    let sample_size = sample_size.max(1);
+
+    if chunk.len() <= sample_size * 2 {
+        return vec![chunk];
+    }
+
+    vec![
+        ByteRange {
+            start: chunk.start,
+            end: chunk.start + sample_size - 1,
+        },
+        ByteRange {
+            start: chunk.end + 1 - sample_size,
+            end: chunk.end,
+        },
+    ]
+}
+
+fn read_range(file: &mut File, range: ByteRange) -> Result<Vec<u8>, WebError> {
+    let mut buffer = vec![0; range.len() as usize];
+    file.seek(SeekFrom::Start(range.start))
+        .and_then(|_| file.read_exact(&mut buffer))
+        .map_err(WebError::IoError)?;
+
+    Ok(buffer)
+}
+
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest(
+        size,
+        chunk_size,
+        expected,
+        case(10, 4, vec![(0, 3), (4, 7), (8, 9)]),
+        case(8, 4, vec![(0, 3), (4, 7)]),
+        case(3, 4, vec![(0, 2)]),
+        case(0, 4, vec![])
+    )]
+    fn split_chunks_should_cover_whole_file(size: u64, chunk_size: u64, expected: Vec<(u64, u64)>) {
+        let expected: Vec<ByteRange> = expected
+            .into_iter()
+            .map(|(start, end)| ByteRange { start, end })
+            .collect();
+
+        let actual = split_chunks(size, chunk_size);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest(
+        start,
+        end,
+        expected,
+        case(0, 99, vec![(0, 9), (90, 99)]),
+        case(100, 119, vec![(100, 119)]),
+        case(100, 120, vec![(100, 109), (111, 120)])
+    )]
+    fn sample_ranges_should_sample_start_and_end_of_chunk(
+        start: u64,
+        end: u64,
+        expected: Vec<(u64, u64)>,
+    ) {
+        let expected: Vec<ByteRange> = expected
+            .into_iter()
+            .map(|(start, end)| ByteRange { start, end })
+            .collect();
+
+        let actual = sample_ranges(ByteRange { start, end }, 10);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn download_delta_should_download_full_file_without_previous_file() {
+        let dir = std::env::temp_dir().join("aer-delta-full-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let request = WebRequest::create();
+
+        let actual = request
+            .download_delta(
+                "https://httpbin.org/bytes/512",
+                &dir.join("missing.bin"),
+                &dir.join("output.bin"),
+                &DeltaOptions::default(),
+            )
+            .unwrap();
+
+        assert!(actual.full);
+        assert_eq!(actual.downloaded, 512);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
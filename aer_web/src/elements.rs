@@ -15,7 +15,7 @@ use reqwest::Url;
 ///
 /// This can be incorrect in cases
 /// where the the link is only checked but not the request have been parsed.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum LinkType {
     /// The current link uses an html extension, or have the mime type of
     /// `text/html`.
@@ -41,6 +41,9 @@ pub enum LinkType {
     /// The current link is not a known type, this could be because no file
     /// extension is used, or the request have been sent to the url.
     Unknown,
+    /// The current link is of a type registered by the user of the library,
+    /// that do not fit into any of the other types.
+    Other(String),
 }
 
 impl Default for LinkType {
@@ -58,6 +61,7 @@ impl Display for LinkType {
             Self::Json => f.write_str("JSON"),
             Self::Text => f.write_str("Text"),
             Self::Unknown => f.write_str("Unknown"),
+            Self::Other(name) => f.write_str(name),
         }
     }
 }
@@ -77,6 +81,172 @@ impl LinkType {
     }
 }
 
+/// Defines additional places in an html page where links can be extracted
+/// from, in addition to the anchor (`<a href>`) elements that are always
+/// used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkSource {
+    /// Links in `<meta http-equiv="refresh">` elements, often used by pages
+    /// that automatically start a download.
+    MetaRefresh,
+    /// Links assigned to variables or properties in script elements (*ie:
+    /// `var downloadUrl = "..."`*). Only variables with a name containing
+    /// `url`, `link`, `href` or `download` are used.
+    ScriptVariables,
+    /// Any absolute link found in JSON embedded in the page using a
+    /// `<script type="application/json">` (or `application/ld+json`)
+    /// element.
+    ScriptJson,
+}
+
+impl LinkSource {
+    /// Returns the name of the link source, this is the name that will be
+    /// added as the `source` attribute of links extracted from the source.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LinkSource::MetaRefresh => "meta-refresh",
+            LinkSource::ScriptVariables => "script-variable",
+            LinkSource::ScriptJson => "script-json",
+        }
+    }
+}
+
+impl Display for LinkSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        f.write_str(self.name())
+    }
+}
+
+/// Defines how the link to the next page of a paginated html page (*ie: a
+/// release listing*) is found.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NextPage {
+    /// The first anchor (`<a rel="next">`) or link (`<link rel="next">`)
+    /// element with the `next` relation.
+    Rel,
+    /// The first anchor element where either the link or the trimmed text of
+    /// the element matches the regex (*ie: `^Next`*).
+    Regex(String),
+}
+
+/// The settings used when following the pages of a paginated html page, the
+/// links found on every page are returned together in the order the pages
+/// were read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pagination {
+    /// How the link to the next page is found.
+    pub next: NextPage,
+    /// The maximum number of pages to read, including the first page.
+    pub max_pages: usize,
+}
+
+impl Pagination {
+    /// Creates a new instance of the pagination settings, reading up to the
+    /// specified number of pages.
+    pub fn new(next: NextPage, max_pages: usize) -> Pagination {
+        Pagination { next, max_pages }
+    }
+}
+
+/// Holds the mappings used to decide the [LinkType] of a link, either by the
+/// MIME type reported by a server or by the extension of the link.
+///
+/// The default mappings can be extended with additional MIME types and
+/// extensions, these will take precedence over the default mappings.
+///
+/// ## Examples
+///
+/// ```
+/// use aer_web::{LinkType, LinkTypeMap};
+///
+/// let mut map = LinkTypeMap::default();
+/// map.add_extension(".AppImage", LinkType::Binary)
+///     .add_mime_type("application/x-apple-diskimage", LinkType::Binary);
+///
+/// assert_eq!(map.from_path("/releases/app.AppImage"), LinkType::Binary);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkTypeMap {
+    mime_types: Vec<(String, LinkType)>,
+    extensions: Vec<(String, LinkType)>,
+}
+
+impl LinkTypeMap {
+    /// Creates a new map without any mappings.
+    pub fn empty() -> LinkTypeMap {
+        LinkTypeMap {
+            mime_types: vec![],
+            extensions: vec![],
+        }
+    }
+
+    /// Adds a new mapping between a MIME type and a link type. Any content
+    /// type containing the MIME type will be considered a match.
+    pub fn add_mime_type(&mut self, mime_type: &str, link_type: LinkType) -> &mut Self {
+        self.mime_types
+            .insert(0, (mime_type.to_lowercase(), link_type));
+        self
+    }
+
+    /// Adds a new mapping between a file extension (*ie: `.dmg`*) and a link
+    /// type. Extensions are matched without regard to the casing.
+    pub fn add_extension(&mut self, extension: &str, link_type: LinkType) -> &mut Self {
+        self.extensions
+            .insert(0, (extension.to_lowercase(), link_type));
+        self
+    }
+
+    /// Returns the link type matching the specified content type, or
+    /// [LinkType::Unknown] if there is no match.
+    pub fn from_mime_type(&self, content_type: &str) -> LinkType {
+        let content_type = content_type.to_lowercase();
+
+        self.mime_types
+            .iter()
+            .find(|(key, _)| content_type.contains(key.as_str()))
+            .map(|(_, val)| val.clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns the link type matching the extension of the specified path, or
+    /// [LinkType::Unknown] if there is no match.
+    pub fn from_path(&self, path: &str) -> LinkType {
+        let path = path.to_lowercase();
+
+        self.extensions
+            .iter()
+            .find(|(key, _)| path.ends_with(key.as_str()))
+            .map(|(_, val)| val.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl Default for LinkTypeMap {
+    /// Creates a new map with the mappings that are supported by default.
+    fn default() -> LinkTypeMap {
+        let mut map = LinkTypeMap::empty();
+        map.add_mime_type("text/html", LinkType::Html)
+            .add_mime_type("text/plain", LinkType::Text)
+            .add_mime_type("text/json", LinkType::Json)
+            .add_mime_type("application/json", LinkType::Json)
+            .add_mime_type("text/css", LinkType::Css)
+            .add_mime_type("application/octet-stream", LinkType::Binary);
+
+        for extension in &[
+            ".zip", ".7z", ".exe", ".msi", ".tar", ".tar.gz", ".tar.bz2", ".nupkg",
+        ] {
+            map.add_extension(extension, LinkType::Binary);
+        }
+
+        map.add_extension(".html", LinkType::Html)
+            .add_extension(".json", LinkType::Json)
+            .add_extension(".css", LinkType::Css)
+            .add_extension(".txt", LinkType::Text);
+
+        map
+    }
+}
+
 /// Stores information that are know about the current link.
 #[derive(Debug, Clone, PartialEq)]
 pub struct LinkElement {
@@ -129,3 +299,58 @@ impl Default for LinkElement {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest(
+        path,
+        expected,
+        case("/index.html", LinkType::Html),
+        case("/releases/app-1.0.0.tar.gz", LinkType::Binary),
+        case("/releases/APP.EXE", LinkType::Binary),
+        case("/releases/app.dmg", LinkType::Unknown)
+    )]
+    fn from_path_should_use_default_mappings(path: &str, expected: LinkType) {
+        let map = LinkTypeMap::default();
+
+        let actual = map.from_path(path);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn from_path_should_prefer_custom_mappings() {
+        let mut map = LinkTypeMap::default();
+        map.add_extension(".deb", LinkType::Binary)
+            .add_extension(".zip", LinkType::Other("Archive".into()));
+
+        assert_eq!(map.from_path("/app.deb"), LinkType::Binary);
+        assert_eq!(map.from_path("/app.zip"), LinkType::Other("Archive".into()));
+    }
+
+    #[rstest(
+        content_type,
+        expected,
+        case("text/html; charset=utf-8", LinkType::Html),
+        case("application/json", LinkType::Json),
+        case("application/x-custom", LinkType::Other("Custom".into())),
+        case("image/png", LinkType::Unknown)
+    )]
+    fn from_mime_type_should_return_expected_type(content_type: &str, expected: LinkType) {
+        let mut map = LinkTypeMap::default();
+        map.add_mime_type("application/x-custom", LinkType::Other("Custom".into()));
+
+        let actual = map.from_mime_type(content_type);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn other_should_display_custom_name() {
+        assert_eq!(LinkType::Other("Package".into()).to_string(), "Package");
+    }
+}
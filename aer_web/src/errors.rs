@@ -3,35 +3,52 @@
 
 //! Contains the necessary logic for creating errors for the library.
 
-use std::error::Error;
-use std::fmt::Display;
-
 /// Common error collector for different errors that can be found in the
 /// library.
-#[derive(Debug)]
+#[derive(Debug, thiserror::Error)]
 pub enum WebError {
     /// An error happened when trying to request a web site.
-    Request(reqwest::Error),
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
     /// An error that occurred while reading or writing to the file system
-    IoError(std::io::Error),
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    /// The checksum of a downloaded file did not match the expected
+    /// checksum.
+    #[error("The checksum of the downloaded file was '{actual}', but '{expected}' was expected!")]
+    ChecksumMismatch {
+        /// The checksum the file was expected to have.
+        expected: String,
+        /// The checksum of the downloaded file.
+        actual: String,
+    },
     /// Any other type of error not covered by the other types.
+    #[error("{0}")]
     Other(String),
 }
 
-impl Error for WebError {}
-
-impl Display for WebError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+impl WebError {
+    /// Removes the url from the error of a request, used when the url of the
+    /// request contains secrets that should never be logged. Other errors
+    /// are returned as is.
+    pub(crate) fn without_url(self) -> WebError {
         match self {
-            WebError::Request(err) => err.fmt(f),
-            WebError::IoError(err) => err.fmt(f),
-            WebError::Other(val) => f.write_str(&val),
+            WebError::Request(err) => WebError::Request(err.without_url()),
+            err => err,
         }
     }
-}
 
-impl From<std::io::Error> for WebError {
-    fn from(err: std::io::Error) -> Self {
-        WebError::IoError(err)
+    /// Returns the stable code identifying the kind of error (*ie:
+    /// `AER0104`*), which can be used when searching the documentation.
+    pub fn code(&self) -> &'static str {
+        match self {
+            WebError::Request(err) if err.is_timeout() => "AER0103",
+            WebError::Request(err) if err.is_connect() => "AER0104",
+            WebError::Request(err) if err.is_status() => "AER0102",
+            WebError::Request(_) => "AER0101",
+            WebError::IoError(_) => "AER0105",
+            WebError::ChecksumMismatch { .. } => "AER0106",
+            WebError::Other(_) => "AER0100",
+        }
     }
 }
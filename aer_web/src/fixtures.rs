@@ -0,0 +1,214 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for recording html responses to a fixture directory, and
+//! replaying these recorded responses instead of contacting the remote
+//! servers. This allows package definitions to be regression tested without
+//! any network access.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use reqwest::blocking::Response;
+use reqwest::{ResponseBuilderExt, Url};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::WebError;
+
+/// Decides if responses should be recorded or replayed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FixtureMode {
+    /// Requests are sent to the remote server as usual, and the response is
+    /// stored in the fixture directory.
+    Record,
+    /// No requests are sent, instead the responses are read from the fixture
+    /// directory. An error is returned if no response have been recorded for
+    /// the requested url.
+    Replay,
+}
+
+/// Holds the directory where fixtures are stored, and if they should be
+/// recorded or replayed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fixtures {
+    dir: PathBuf,
+    mode: FixtureMode,
+}
+
+#[derive(Deserialize, Serialize)]
+struct RecordedResponse {
+    url: String,
+    final_url: String,
+    status: u16,
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+impl Fixtures {
+    /// Creates a new instance of the fixtures, using the specified directory
+    /// and mode.
+    pub fn new<P: Into<PathBuf>>(dir: P, mode: FixtureMode) -> Fixtures {
+        Fixtures {
+            dir: dir.into(),
+            mode,
+        }
+    }
+
+    /// Returns the directory where the fixtures are stored.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns whether fixtures are recorded or replayed.
+    pub fn mode(&self) -> FixtureMode {
+        self.mode
+    }
+
+    /// Returns the path to the fixture file used for the specified url.
+    pub fn path_for(&self, url: &Url) -> PathBuf {
+        let host: String = url
+            .host_str()
+            .unwrap_or("unknown")
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+
+        self.dir
+            .join(format!("{}-{:016x}.json", host, hash(url.as_str())))
+    }
+
+    /// Reads the response previously recorded for the specified url.
+    pub fn load(&self, url: &Url) -> Result<Response, WebError> {
+        let path = self.path_for(url);
+        if !path.exists() {
+            return Err(WebError::Other(format!(
+                "No fixture have been recorded for '{}' (expected '{}')",
+                url,
+                path.display()
+            )));
+        }
+
+        let text = std::fs::read_to_string(&path)?;
+        let recorded: RecordedResponse =
+            serde_json::from_str(&text).map_err(|err| WebError::Other(err.to_string()))?;
+
+        build_response(recorded)
+    }
+
+    /// Reads the body of the specified response and stores it in the fixture
+    /// directory. As the body can only be read once, a new response with the
+    /// same content is returned.
+    pub fn save(&self, url: &Url, response: Response) -> Result<Response, WebError> {
+        let final_url = response.url().to_string();
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(key, val)| Some((key.to_string(), val.to_str().ok()?.to_owned())))
+            .collect();
+        let body = response.text().map_err(WebError::Request)?;
+
+        let recorded = RecordedResponse {
+            url: url.to_string(),
+            final_url,
+            status,
+            headers,
+            body,
+        };
+
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(
+            self.path_for(url),
+            serde_json::to_string_pretty(&recorded)
+                .map_err(|err| WebError::Other(err.to_string()))?,
+        )?;
+
+        build_response(recorded)
+    }
+}
+
+fn build_response(recorded: RecordedResponse) -> Result<Response, WebError> {
+    let final_url =
+        Url::parse(&recorded.final_url).map_err(|err| WebError::Other(err.to_string()))?;
+    let mut builder = http::Response::builder()
+        .status(recorded.status)
+        .url(final_url);
+
+    for (key, val) in recorded.headers {
+        builder = builder.header(key.as_str(), val.as_str());
+    }
+
+    let response = builder
+        .body(recorded.body)
+        .map_err(|err| WebError::Other(err.to_string()))?;
+
+    Ok(Response::from(response))
+}
+
+/// FNV-1a, used as the file names needs to be stable between runs.
+fn hash(value: &str) -> u64 {
+    value.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+
+    use super::*;
+
+    #[test]
+    fn path_for_should_create_stable_file_name() {
+        let fixtures = Fixtures::new("fixtures", FixtureMode::Replay);
+        let url = Url::parse("https://github.com/WormieCorp/aer/releases").unwrap();
+
+        let first = fixtures.path_for(&url);
+        let second = fixtures.path_for(&url);
+
+        assert_eq!(first, second);
+        assert!(first.starts_with("fixtures"));
+        assert!(first
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("github_com-"));
+    }
+
+    #[test]
+    fn load_should_return_error_when_not_recorded() {
+        let fixtures = Fixtures::new("non-existing", FixtureMode::Replay);
+        let url = Url::parse("https://test.com/").unwrap();
+
+        let actual = fixtures.load(&url);
+
+        assert!(matches!(actual, Err(WebError::Other(_))));
+    }
+
+    #[test]
+    fn save_should_store_response_that_can_be_loaded() {
+        let dir = std::env::temp_dir().join("aer-web-fixtures-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        let fixtures = Fixtures::new(&dir, FixtureMode::Record);
+        let url = Url::parse("https://test.com/releases").unwrap();
+        let response = build_response(RecordedResponse {
+            url: url.to_string(),
+            final_url: "https://test.com/releases/latest".into(),
+            status: 200,
+            headers: vec![("content-type".to_owned(), "text/html".to_owned())]
+                .into_iter()
+                .collect(),
+            body: "<a href=\"/test.zip\">Test</a>".into(),
+        })
+        .unwrap();
+
+        let saved = fixtures.save(&url, response).unwrap();
+        let loaded = fixtures.load(&url).unwrap();
+
+        assert_eq!(saved.status(), StatusCode::OK);
+        assert_eq!(loaded.url().as_str(), "https://test.com/releases/latest");
+        assert_eq!(loaded.headers()["content-type"], "text/html");
+        assert_eq!(loaded.text().unwrap(), "<a href=\"/test.zip\">Test</a>");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
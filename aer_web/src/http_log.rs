@@ -0,0 +1,159 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Allows recording every request that is sent to remote servers, together
+//! with the timing and caching information of the request.
+//!
+//! Each request is written as a single JSON object on its own line, making it
+//! easy to figure out why a run was slow or got rate limited.
+
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use log::warn;
+use serde::Serialize;
+
+/// Wether the response of a request was served from a cache.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CacheStatus {
+    /// The response was sent by the remote server.
+    Miss,
+    /// The remote server responded that the previously downloaded content
+    /// (identified by the etag or modification date) is still up to date.
+    NotModified,
+    /// The response was replayed from a recorded fixture.
+    Fixture,
+}
+
+/// A single request that was sent, or replayed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HttpLogEntry {
+    /// The HTTP method of the request.
+    pub method: String,
+    /// The url the request was sent to.
+    pub url: String,
+    /// The status code of the response, not set when the request failed
+    /// before a response was received.
+    pub status: Option<u16>,
+    /// The number of milliseconds it took to receive the response headers.
+    pub duration_ms: u128,
+    /// The length of the response body, if the server reported it.
+    pub bytes: Option<u64>,
+    /// Wether the response was served from a cache.
+    pub cache: CacheStatus,
+    /// The error that occurred while sending the request, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl HttpLogEntry {
+    /// Creates a new entry for a `GET` request to the specified url.
+    pub fn get<U: ToString>(url: U, duration: Duration, cache: CacheStatus) -> HttpLogEntry {
+        HttpLogEntry {
+            method: "GET".into(),
+            url: url.to_string(),
+            status: None,
+            duration_ms: duration.as_millis(),
+            bytes: None,
+            cache,
+            error: None,
+        }
+    }
+}
+
+/// The log that requests are written to, cloned instances writes to the
+/// same destination.
+#[derive(Clone)]
+pub struct HttpLog {
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl HttpLog {
+    /// Creates a new log that appends to the file at the specified path, the
+    /// file is created if it do not exist.
+    pub fn create(path: &Path) -> Result<HttpLog, std::io::Error> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(HttpLog::from_writer(file))
+    }
+
+    /// Creates a new log that writes to the specified writer.
+    pub fn from_writer<W: Write + Send + 'static>(writer: W) -> HttpLog {
+        HttpLog {
+            writer: Arc::new(Mutex::new(Box::new(writer))),
+        }
+    }
+
+    /// Writes the entry to the log, failures are only reported as warnings
+    /// as they should never stop the request from being processed.
+    pub fn record(&self, entry: &HttpLogEntry) {
+        let line = match serde_json::to_string(entry) {
+            Ok(line) => line + "\n",
+            Err(err) => {
+                warn!("Unable to serialize the http log entry: {}", err);
+                return;
+            }
+        };
+
+        let mut writer = match self.writer.lock() {
+            Ok(writer) => writer,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if let Err(err) = writer
+            .write_all(line.as_bytes())
+            .and_then(|_| writer.flush())
+        {
+            warn!("Unable to write to the http log: {}", err);
+        }
+    }
+}
+
+impl fmt::Debug for HttpLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpLog").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn record_should_write_entry_as_json_line() {
+        let buffer = SharedBuffer::default();
+        let log = HttpLog::from_writer(buffer.clone());
+        let mut entry = HttpLogEntry::get(
+            "https://example.org/",
+            Duration::from_millis(25),
+            CacheStatus::NotModified,
+        );
+        entry.status = Some(304);
+
+        log.record(&entry);
+
+        let actual = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(
+            actual,
+            "{\"method\":\"GET\",\"url\":\"https://example.org/\",\"status\":304,\"duration_ms\":25,\
+             \"bytes\":null,\"cache\":\"not-modified\"}\n"
+        );
+    }
+}
@@ -0,0 +1,352 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the expressions used to select values from JSON documents, used
+//! when extracting the versions and urls from the responses of JSON APIs.
+//!
+//! Three kinds of expressions are supported:
+//!
+//! - A subset of JSONPath, starting with `$` (*ie:
+//!   `$.assets[*].browser_download_url`*). Child names (`.name` and
+//!   `['name']`), indexes (`[0]` and `[-1]`), wildcards (`.*` and `[*]`) and
+//!   recursive descent (`..name`) are supported, filters and slices are not.
+//! - JSON pointers, starting with `/` (*ie: `/assets/0/browser_download_url`*).
+//! - Dotted paths, which are JSONPath expressions without the leading `$.`
+//!   (*ie: `assets.0.browser_download_url` or `assets[*].name`*).
+//!
+//! Numeric child names are used as indexes when selecting from arrays, which
+//! allows dotted paths to select items of arrays.
+//!
+//! ### Examples
+//!
+//! ```
+//! use aer_web::json_path::JsonPath;
+//! use serde_json::json;
+//!
+//! let value = json!({
+//!     "tag_name": "v1.2.0",
+//!     "assets": [
+//!         { "browser_download_url": "https://example.org/app-x86.zip" },
+//!         { "browser_download_url": "https://example.org/app-x64.zip" }
+//!     ]
+//! });
+//!
+//! let path = JsonPath::parse("$.assets[*].browser_download_url").unwrap();
+//! assert_eq!(
+//!     path.strings(&value),
+//!     [
+//!         "https://example.org/app-x86.zip",
+//!         "https://example.org/app-x64.zip"
+//!     ]
+//! );
+//!
+//! let path = JsonPath::parse("tag_name").unwrap();
+//! assert_eq!(path.strings(&value), ["v1.2.0"]);
+//! ```
+
+use std::fmt::Display;
+
+use serde_json::Value;
+
+use crate::errors::WebError;
+
+/// A single step of a JSONPath expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    /// Selects the child with the specified name, or the item at the index
+    /// when the name is numeric and the value is an array.
+    Child(String),
+    /// Selects the item at the index of an array, negative indexes are
+    /// counted from the end of the array.
+    Index(i64),
+    /// Selects every child of an object, or every item of an array.
+    Wildcard,
+}
+
+/// A step of a JSONPath expression, optionally applied to every descendant of
+/// the selected values.
+#[derive(Debug, Clone, PartialEq)]
+struct Segment {
+    step: Step,
+    recursive: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Selector {
+    Pointer(String),
+    Path(Vec<Segment>),
+}
+
+/// A parsed expression selecting values from a JSON document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath {
+    expression: String,
+    selector: Selector,
+}
+
+impl JsonPath {
+    /// Parses the specified JSONPath, JSON pointer or dotted path expression.
+    pub fn parse(expression: &str) -> Result<JsonPath, WebError> {
+        let trimmed = expression.trim();
+        let selector = if trimmed.is_empty() || trimmed.starts_with('/') {
+            Selector::Pointer(trimmed.into())
+        } else if let Some(path) = trimmed.strip_prefix('$') {
+            Selector::Path(parse_segments(path, expression)?)
+        } else {
+            Selector::Path(parse_segments(&format!(".{}", trimmed), expression)?)
+        };
+
+        Ok(JsonPath {
+            expression: expression.into(),
+            selector,
+        })
+    }
+
+    /// Returns the expression the path was parsed from.
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// Returns every value in the document matching the expression, in the
+    /// order they appear in the document.
+    pub fn select<'a>(&self, value: &'a Value) -> Vec<&'a Value> {
+        let segments = match self.selector {
+            Selector::Pointer(ref pointer) => return value.pointer(pointer).into_iter().collect(),
+            Selector::Path(ref segments) => segments,
+        };
+
+        segments.iter().fold(vec![value], |current, segment| {
+            let mut next = vec![];
+            for value in current {
+                if segment.recursive {
+                    let mut descendants = vec![];
+                    collect_descendants(value, &mut descendants);
+                    for value in descendants {
+                        apply_step(&segment.step, value, &mut next);
+                    }
+                } else {
+                    apply_step(&segment.step, value, &mut next);
+                }
+            }
+            next
+        })
+    }
+
+    /// Returns the text of every string, number and boolean in the document
+    /// matching the expression, any other values are ignored.
+    pub fn strings(&self, value: &Value) -> Vec<String> {
+        self.select(value)
+            .into_iter()
+            .filter_map(|value| match value {
+                Value::String(value) => Some(value.clone()),
+                Value::Number(value) => Some(value.to_string()),
+                Value::Bool(value) => Some(value.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl Display for JsonPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        f.write_str(&self.expression)
+    }
+}
+
+fn parse_segments(path: &str, expression: &str) -> Result<Vec<Segment>, WebError> {
+    let invalid = |reason: &str| {
+        WebError::Other(format!(
+            "The JSON path '{}' is not valid: {}!",
+            expression, reason
+        ))
+    };
+    let chars: Vec<char> = path.chars().collect();
+    let mut segments = vec![];
+    let mut index = 0;
+
+    while index < chars.len() {
+        let recursive = chars[index] == '.' && chars.get(index + 1) == Some(&'.');
+        let step = match chars[index] {
+            '.' => {
+                index += if recursive { 2 } else { 1 };
+                if chars.get(index) == Some(&'[') && recursive {
+                    parse_bracket(&chars, &mut index).map_err(|err| invalid(&err))?
+                } else if chars.get(index) == Some(&'*') {
+                    index += 1;
+                    Step::Wildcard
+                } else {
+                    let start = index;
+                    while index < chars.len() && chars[index] != '.' && chars[index] != '[' {
+                        index += 1;
+                    }
+                    if start == index {
+                        return Err(invalid("a name is missing after '.'"));
+                    }
+                    Step::Child(chars[start..index].iter().collect())
+                }
+            }
+            '[' => parse_bracket(&chars, &mut index).map_err(|err| invalid(&err))?,
+            c => return Err(invalid(&format!("unexpected character '{}'", c))),
+        };
+
+        segments.push(Segment { step, recursive });
+    }
+
+    Ok(segments)
+}
+
+/// Parses the bracket starting at the index, and moves the index past the
+/// closing bracket.
+fn parse_bracket(chars: &[char], index: &mut usize) -> Result<Step, String> {
+    let start = *index + 1;
+    let mut end = start;
+    let mut quote = None;
+
+    while end < chars.len() {
+        match (quote, chars[end]) {
+            (None, '\'') | (None, '"') => quote = Some(chars[end]),
+            (Some(q), c) if q == c => quote = None,
+            (None, ']') => break,
+            _ => {}
+        }
+        end += 1;
+    }
+
+    if end >= chars.len() {
+        return Err("a closing bracket is missing".into());
+    }
+    *index = end + 1;
+
+    let content: String = chars[start..end].iter().collect();
+    let content = content.trim();
+
+    if content == "*" {
+        Ok(Step::Wildcard)
+    } else if content.len() >= 2
+        && (content.starts_with('\'') && content.ends_with('\'')
+            || content.starts_with('"') && content.ends_with('"'))
+    {
+        Ok(Step::Child(content[1..content.len() - 1].into()))
+    } else {
+        content
+            .parse()
+            .map(Step::Index)
+            .map_err(|_| format!("'{}' is not a supported selector", content))
+    }
+}
+
+fn apply_step<'a>(step: &Step, value: &'a Value, selected: &mut Vec<&'a Value>) {
+    match (step, value) {
+        (Step::Child(name), Value::Object(map)) => selected.extend(map.get(name)),
+        (Step::Child(name), Value::Array(items)) => {
+            if let Ok(index) = name.parse::<usize>() {
+                selected.extend(items.get(index));
+            }
+        }
+        (Step::Index(index), Value::Array(items)) => {
+            let index = if *index < 0 {
+                items.len() as i64 + index
+            } else {
+                *index
+            };
+            if index >= 0 {
+                selected.extend(items.get(index as usize));
+            }
+        }
+        (Step::Wildcard, Value::Object(map)) => selected.extend(map.values()),
+        (Step::Wildcard, Value::Array(items)) => selected.extend(items.iter()),
+        _ => {}
+    }
+}
+
+fn collect_descendants<'a>(value: &'a Value, descendants: &mut Vec<&'a Value>) {
+    descendants.push(value);
+
+    match value {
+        Value::Object(map) => map
+            .values()
+            .for_each(|value| collect_descendants(value, descendants)),
+        Value::Array(items) => items
+            .iter()
+            .for_each(|value| collect_descendants(value, descendants)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::*;
+
+    fn document() -> Value {
+        json!({
+            "name": "Test Release",
+            "tag_name": "v2.1.0",
+            "draft": false,
+            "assets": [
+                { "name": "app-x86.zip", "url": "https://example.org/app-x86.zip", "size": 1024 },
+                { "name": "app-x64.zip", "url": "https://example.org/app-x64.zip", "size": 2048 }
+            ],
+            "downloads": {
+                "windows": { "url": "https://example.org/app.exe" },
+                "linux": { "url": "https://example.org/app.tar.gz" }
+            },
+            "key.with.dots": "dotted"
+        })
+    }
+
+    #[rstest(
+        expression,
+        expected,
+        case("$.tag_name", vec!["v2.1.0"]),
+        case("tag_name", vec!["v2.1.0"]),
+        case("/tag_name", vec!["v2.1.0"]),
+        case("$.assets[*].url", vec!["https://example.org/app-x86.zip", "https://example.org/app-x64.zip"]),
+        case("assets[*].name", vec!["app-x86.zip", "app-x64.zip"]),
+        case("$.assets[0].url", vec!["https://example.org/app-x86.zip"]),
+        case("$.assets[-1].name", vec!["app-x64.zip"]),
+        case("assets.1.name", vec!["app-x64.zip"]),
+        case("/assets/1/size", vec!["2048"]),
+        case("$['assets'][0]['size']", vec!["1024"]),
+        case("$.downloads.*.url", vec!["https://example.org/app.tar.gz", "https://example.org/app.exe"]),
+        case("$.downloads..url", vec!["https://example.org/app.tar.gz", "https://example.org/app.exe"]),
+        case("$..assets[*].size", vec!["1024", "2048"]),
+        case("$['key.with.dots']", vec!["dotted"]),
+        case("$.draft", vec!["false"]),
+        case("$.assets[5].name", vec![]),
+        case("$.missing.url", vec![]),
+        case("$.assets", vec![])
+    )]
+    fn strings_should_select_expected_values(expression: &str, expected: Vec<&str>) {
+        let path = JsonPath::parse(expression).unwrap();
+
+        let actual = path.strings(&document());
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn select_should_return_document_for_root_expression() {
+        let value = document();
+
+        let actual = JsonPath::parse("$").unwrap().select(&value);
+
+        assert_eq!(actual, [&value]);
+    }
+
+    #[rstest(
+        expression,
+        case("$.assets["),
+        case("$.assets[name]"),
+        case("$."),
+        case("$assets"),
+        case("$.assets[1:2]")
+    )]
+    fn parse_should_fail_on_invalid_expressions(expression: &str) {
+        let actual = JsonPath::parse(expression);
+
+        assert!(actual.is_err(), "{:?}", actual);
+    }
+}
@@ -23,11 +23,34 @@
 //! ```
 
 mod elements;
+mod publish;
+mod webhook;
 
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod auth;
+pub mod budget;
+pub mod checksum;
+pub mod crawl;
+pub mod delta;
 pub mod errors;
+pub mod fixtures;
+pub mod http_log;
+pub mod json_path;
+pub mod license;
+pub mod platform;
+pub mod proxy;
+pub mod rate_limit;
+#[cfg(feature = "render")]
+pub mod render;
 pub mod request;
 pub mod response;
+pub mod retry;
+pub mod selector;
+pub mod sources;
+pub mod virustotal;
+pub mod xml;
 
-pub use elements::{LinkElement, LinkType};
-pub use request::WebRequest;
+pub use elements::{LinkElement, LinkSource, LinkType, LinkTypeMap, NextPage, Pagination};
+pub use request::{HeadResponse, LinkCheck, WebRequest, WebRequestBuilder};
 pub use response::WebResponse;
@@ -0,0 +1,233 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the downloading of the license files of software, used when the
+//! license is embedded in the created packages.
+//!
+//! Links to the license file in the web interface of GitHub and GitLab are
+//! rewritten to the raw file, as the web interface only returns the license
+//! wrapped in a html page. The downloaded license is verified to be a plain
+//! text file before it is returned, and can be classified to find the SPDX
+//! expression of the license.
+
+use aer_license::LicenseType;
+use log::info;
+use reqwest::{header, Url};
+
+use crate::errors::WebError;
+use crate::request::handle_exit_code;
+use crate::WebRequest;
+
+/// The number of bytes that are checked when verifying that the license is
+/// not a html page.
+const HTML_PREFIX_LENGTH: usize = 512;
+
+/// A license file that was downloaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LicenseFile {
+    /// The url the license was downloaded from, after rewriting the url to
+    /// the raw file.
+    pub url: Url,
+    /// The text of the license.
+    pub text: String,
+}
+
+impl LicenseFile {
+    /// Returns the SPDX identifier of the license (*ie: `MIT`*), if the text
+    /// matches any of the known licenses.
+    pub fn detect_expression(&self) -> Option<&'static str> {
+        LicenseType::detect_from_text(&self.text)
+    }
+}
+
+impl WebRequest {
+    /// Downloads the license file at the specified url, and verifies that the
+    /// license is a plain text file.
+    pub fn get_license_file(&self, url: &str) -> Result<LicenseFile, WebError> {
+        let url = Url::parse(url).map_err(|err| WebError::Other(err.to_string()))?;
+        let url = raw_license_url(&url);
+
+        info!("Downloading the license from '{}'", url);
+
+        let response = self.get_api_response(&url, "text/plain, */*", None)?;
+        let content_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+        let bytes = handle_exit_code(response, |rsp| rsp.bytes())?.map_err(WebError::Request)?;
+        let text = verify_text(content_type.as_deref(), &bytes).map_err(|reason| {
+            WebError::Other(format!(
+                "The license at '{}' is not a text file ({})!",
+                url, reason
+            ))
+        })?;
+
+        Ok(LicenseFile { url, text })
+    }
+
+    /// Downloads the license file at the specified url, and returns the SPDX
+    /// identifier of the license. Nothing is returned when the license do not
+    /// match any of the known licenses.
+    pub fn detect_license_from_url(&self, url: &str) -> Result<Option<&'static str>, WebError> {
+        let license = self.get_license_file(url)?;
+        let expression = license.detect_expression();
+
+        if let Some(expression) = expression {
+            info!(
+                "Detected the license '{}' from '{}'",
+                expression, license.url
+            );
+        }
+
+        Ok(expression)
+    }
+}
+
+/// Rewrites links to license files in the web interface of GitHub and GitLab
+/// to the raw file, other links are returned as is.
+pub fn raw_license_url(url: &Url) -> Url {
+    let segments: Vec<&str> = match url.path_segments() {
+        Some(segments) => segments.collect(),
+        None => return url.clone(),
+    };
+
+    let path = match url.host_str() {
+        Some("github.com") if segments.len() > 4 && segments[2] == "blob" => Some(format!(
+            "https://raw.githubusercontent.com/{}/{}/{}",
+            segments[0],
+            segments[1],
+            segments[3..].join("/")
+        )),
+        Some(host) if host.contains("gitlab") => segments
+            .iter()
+            .position(|segment| *segment == "blob")
+            .filter(|index| *index > 0 && segments[index - 1] == "-")
+            .map(|index| {
+                let mut segments = segments.clone();
+                segments[index] = "raw";
+                format!(
+                    "{}://{}/{}",
+                    url.scheme(),
+                    url.host_str().unwrap_or_default(),
+                    segments.join("/")
+                )
+            }),
+        _ => None,
+    };
+
+    path.and_then(|path| Url::parse(&path).ok())
+        .unwrap_or_else(|| url.clone())
+}
+
+/// Verifies that the content is a plain text file, and returns the content
+/// as text. The reason the content is rejected is returned as the error.
+fn verify_text(content_type: Option<&str>, bytes: &[u8]) -> Result<String, String> {
+    if let Some(content_type) = content_type {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+        let is_text = mime.starts_with("text/") || mime == "application/octet-stream";
+        if !is_text || mime == "text/html" {
+            return Err(format!("the server responded with '{}'", mime));
+        }
+    }
+
+    if bytes.is_empty() {
+        return Err("the file is empty".into());
+    } else if bytes.contains(&0) {
+        return Err("the file contains binary data".into());
+    }
+
+    let text = String::from_utf8_lossy(bytes);
+    let text = text.trim_start_matches('\u{feff}');
+    let prefix: String = text
+        .trim_start()
+        .chars()
+        .take(HTML_PREFIX_LENGTH)
+        .collect::<String>()
+        .to_lowercase();
+    if prefix.starts_with("<!doctype html") || prefix.starts_with("<html") {
+        return Err("the file is a html page".into());
+    }
+
+    Ok(text.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest(
+        url,
+        expected,
+        case(
+            "https://github.com/WormieCorp/aer/blob/master/LICENSE.txt",
+            "https://raw.githubusercontent.com/WormieCorp/aer/master/LICENSE.txt"
+        ),
+        case(
+            "https://gitlab.com/group/project/-/blob/main/docs/LICENSE",
+            "https://gitlab.com/group/project/-/raw/main/docs/LICENSE"
+        ),
+        case(
+            "https://raw.githubusercontent.com/WormieCorp/aer/master/LICENSE.txt",
+            "https://raw.githubusercontent.com/WormieCorp/aer/master/LICENSE.txt"
+        ),
+        case(
+            "https://github.com/WormieCorp/aer",
+            "https://github.com/WormieCorp/aer"
+        ),
+        case("https://example.org/license", "https://example.org/license")
+    )]
+    fn raw_license_url_should_rewrite_web_interface_links(url: &str, expected: &str) {
+        let actual = raw_license_url(&Url::parse(url).unwrap());
+
+        assert_eq!(actual.as_str(), expected);
+    }
+
+    #[rstest(
+        content_type,
+        content,
+        case(Some("text/plain; charset=utf-8"), "MIT License"),
+        case(Some("application/octet-stream"), "\u{feff}MIT License"),
+        case(None, "MIT License")
+    )]
+    fn verify_text_should_accept_plain_text(content_type: Option<&str>, content: &str) {
+        let actual = verify_text(content_type, content.as_bytes());
+
+        assert_eq!(actual, Ok("MIT License".to_owned()));
+    }
+
+    #[rstest(
+        content_type,
+        content,
+        expected,
+        case(
+            Some("text/html"),
+            "MIT License",
+            "the server responded with 'text/html'"
+        ),
+        case(
+            Some("application/zip"),
+            "PK",
+            "the server responded with 'application/zip'"
+        ),
+        case(None, "<!DOCTYPE html><html></html>", "the file is a html page"),
+        case(None, "MIT\0License", "the file contains binary data"),
+        case(None, "", "the file is empty")
+    )]
+    fn verify_text_should_reject_other_content(
+        content_type: Option<&str>,
+        content: &str,
+        expected: &str,
+    ) {
+        let actual = verify_text(content_type, content.as_bytes());
+
+        assert_eq!(actual, Err(expected.to_owned()));
+    }
+}
@@ -0,0 +1,260 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Detects the capabilities of the platform the application is running on,
+//! used by the downloaders and runners to behave correctly on less common
+//! targets (*ie: windows-arm64 and musl based linux distributions*).
+
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+/// The operating systems that the application is known to run on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperatingSystem {
+    /// Any version of Microsoft Windows.
+    Windows,
+    /// Apple macOS.
+    MacOs,
+    /// Any linux distribution, using either glibc or musl.
+    Linux,
+    /// Any other operating system.
+    Other,
+}
+
+/// The processor architectures that the application is known to run on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arch {
+    /// 32bit x86 processors.
+    X86,
+    /// 64bit x86 processors.
+    X86_64,
+    /// 64bit ARM processors.
+    Aarch64,
+    /// Any other processor architecture.
+    Other,
+}
+
+/// The TLS implementation used when sending requests to remote servers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlsBackend {
+    /// The TLS implementation of the operating system (*schannel, secure
+    /// transport or openssl*).
+    Native,
+    /// The pure rust TLS implementation, used where the native
+    /// implementation is not available or can not be linked statically.
+    Rustls,
+}
+
+/// The detected capabilities of the current platform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Platform {
+    os: OperatingSystem,
+    arch: Arch,
+    musl: bool,
+}
+
+impl Platform {
+    /// Returns the platform the application was built for and is currently
+    /// running on.
+    pub fn current() -> Platform {
+        let os = if cfg!(windows) {
+            OperatingSystem::Windows
+        } else if cfg!(target_os = "macos") {
+            OperatingSystem::MacOs
+        } else if cfg!(target_os = "linux") {
+            OperatingSystem::Linux
+        } else {
+            OperatingSystem::Other
+        };
+        let arch = if cfg!(target_arch = "x86") {
+            Arch::X86
+        } else if cfg!(target_arch = "x86_64") {
+            Arch::X86_64
+        } else if cfg!(target_arch = "aarch64") {
+            Arch::Aarch64
+        } else {
+            Arch::Other
+        };
+
+        Platform {
+            os,
+            arch,
+            musl: cfg!(target_env = "musl"),
+        }
+    }
+
+    /// Returns the operating system of the platform.
+    pub fn os(&self) -> OperatingSystem {
+        self.os
+    }
+
+    /// Returns the processor architecture of the platform.
+    pub fn arch(&self) -> Arch {
+        self.arch
+    }
+
+    /// Returns wether the platform uses the musl C library.
+    pub fn is_musl(&self) -> bool {
+        self.musl
+    }
+
+    /// Returns the TLS implementation that should be used on the platform.
+    /// Musl based builds can not rely on a system installed openssl library,
+    /// and the rust implementation is used on windows except on arm64 where
+    /// schannel is the only supported implementation.
+    pub fn tls_backend(&self) -> TlsBackend {
+        match (self.os, self.arch) {
+            (..) if self.musl => TlsBackend::Rustls,
+            (OperatingSystem::Windows, Arch::Aarch64) => TlsBackend::Native,
+            (OperatingSystem::Windows, _) => TlsBackend::Rustls,
+            _ => TlsBackend::Native,
+        }
+    }
+
+    /// Returns the names of the PowerShell executables in the order they
+    /// should be preferred.
+    pub fn powershell_names(&self) -> &'static [&'static str] {
+        match self.os {
+            OperatingSystem::Windows => &["pwsh.exe", "powershell.exe"],
+            _ => &["pwsh"],
+        }
+    }
+
+    /// Returns the well-known directories where PowerShell is installed on the
+    /// platform, these are searched in addition to the directories in the
+    /// `PATH` environment variable.
+    pub fn powershell_locations(&self) -> Vec<PathBuf> {
+        match self.os {
+            OperatingSystem::Windows => {
+                let mut locations = vec![];
+                // ARM64 machines may have the x64 build of PowerShell installed
+                // in the emulated program files directory.
+                for var in &["ProgramFiles", "ProgramW6432", "ProgramFiles(x86)"] {
+                    if let Some(dir) = std::env::var_os(var) {
+                        locations.push(PathBuf::from(dir).join("PowerShell").join("7"));
+                    }
+                }
+                if let Some(root) = std::env::var_os("SystemRoot") {
+                    locations.push(
+                        PathBuf::from(root)
+                            .join("System32")
+                            .join("WindowsPowerShell")
+                            .join("v1.0"),
+                    );
+                }
+
+                locations
+            }
+            OperatingSystem::MacOs => vec![
+                PathBuf::from("/usr/local/microsoft/powershell/7"),
+                PathBuf::from("/usr/local/bin"),
+                PathBuf::from("/opt/homebrew/bin"),
+            ],
+            _ => vec![
+                PathBuf::from("/opt/microsoft/powershell/7"),
+                PathBuf::from("/usr/bin"),
+                PathBuf::from("/usr/local/bin"),
+            ],
+        }
+    }
+
+    /// Returns the directory used for temporary files. The directory is
+    /// created if it is missing, as is common in minimal (*musl based*)
+    /// containers, and the current directory is used if the directory can
+    /// not be created.
+    pub fn temp_dir(&self) -> PathBuf {
+        let dir = std::env::temp_dir();
+
+        if is_usable_dir(&dir) || std::fs::create_dir_all(&dir).is_ok() {
+            dir
+        } else {
+            std::env::current_dir().unwrap_or_default()
+        }
+    }
+}
+
+impl Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        let os = match self.os {
+            OperatingSystem::Windows => "windows",
+            OperatingSystem::MacOs => "macos",
+            OperatingSystem::Linux => "linux",
+            OperatingSystem::Other => "other",
+        };
+        let arch = match self.arch {
+            Arch::X86 => "x86",
+            Arch::X86_64 => "x86_64",
+            Arch::Aarch64 => "aarch64",
+            Arch::Other => "other",
+        };
+
+        write!(f, "{}-{}", os, arch)?;
+        if self.musl {
+            f.write_str("-musl")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn is_usable_dir(path: &Path) -> bool {
+    path.is_dir()
+        && !std::fs::metadata(path)
+            .map(|m| m.permissions().readonly())
+            .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest(
+        os,
+        arch,
+        musl,
+        expected,
+        case(OperatingSystem::Windows, Arch::X86_64, false, TlsBackend::Rustls),
+        case(OperatingSystem::Windows, Arch::Aarch64, false, TlsBackend::Native),
+        case(OperatingSystem::Linux, Arch::X86_64, true, TlsBackend::Rustls),
+        case(OperatingSystem::Linux, Arch::Aarch64, false, TlsBackend::Native),
+        case(OperatingSystem::MacOs, Arch::Aarch64, false, TlsBackend::Native)
+    )]
+    fn tls_backend_should_return_expected_backend(
+        os: OperatingSystem,
+        arch: Arch,
+        musl: bool,
+        expected: TlsBackend,
+    ) {
+        let platform = Platform { os, arch, musl };
+
+        assert_eq!(platform.tls_backend(), expected);
+    }
+
+    #[rstest(
+        os,
+        arch,
+        musl,
+        expected,
+        case(OperatingSystem::Windows, Arch::Aarch64, false, "windows-aarch64"),
+        case(OperatingSystem::Linux, Arch::X86_64, true, "linux-x86_64-musl")
+    )]
+    fn fmt_should_output_target_triple_like_name(
+        os: OperatingSystem,
+        arch: Arch,
+        musl: bool,
+        expected: &str,
+    ) {
+        let platform = Platform { os, arch, musl };
+
+        assert_eq!(platform.to_string(), expected);
+    }
+
+    #[test]
+    fn temp_dir_should_return_existing_directory() {
+        let actual = Platform::current().temp_dir();
+
+        assert!(actual.is_dir());
+    }
+}
@@ -0,0 +1,309 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the settings of the proxy that requests are sent through, for
+//! users behind a corporate proxy.
+//!
+//! The proxy is read from the `HTTPS_PROXY`, `HTTP_PROXY` and `ALL_PROXY`
+//! environment variables by default, with the hosts listed in the `NO_PROXY`
+//! environment variable bypassing the proxy. The proxy can also be specified
+//! explicitly, optionally with the credentials used for basic authentication
+//! with the proxy. The same as with the authentication profiles, the password
+//! is always read from an environment variable and is never stored in the
+//! settings.
+//!
+//! Proxies only accepting NTLM authentication are not supported directly,
+//! these can be used through a local authenticating proxy (*ie: `cntlm` or
+//! `px`*) instead.
+//!
+//! ### Examples
+//!
+//! ```
+//! use aer_web::proxy::ProxySettings;
+//! use reqwest::Url;
+//!
+//! let proxy = ProxySettings::new(Url::parse("http://proxy.example.org:8080").unwrap())
+//!     .with_no_proxy(vec!["localhost", ".example.org"])
+//!     .with_credentials("ci-user", "PROXY_PASSWORD");
+//!
+//! let url = Url::parse("https://nexus.example.org/repository/choco/").unwrap();
+//! assert_eq!(proxy.proxy_for(&url), None);
+//! ```
+
+use std::net::IpAddr;
+
+use log::warn;
+use reqwest::{Proxy, Url};
+
+/// The environment variables holding the proxy of https requests, the first
+/// variable that is set is used.
+const HTTPS_PROXY_VARS: &[&str] = &["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"];
+/// The environment variables holding the proxy of http requests, the first
+/// variable that is set is used.
+const HTTP_PROXY_VARS: &[&str] = &["HTTP_PROXY", "http_proxy", "ALL_PROXY", "all_proxy"];
+/// The environment variables holding the hosts that bypass the proxy.
+const NO_PROXY_VARS: &[&str] = &["NO_PROXY", "no_proxy"];
+
+/// The proxy that requests are sent through, together with the hosts that
+/// bypass the proxy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProxySettings {
+    http: Option<Url>,
+    https: Option<Url>,
+    no_proxy: Vec<String>,
+    /// The user name used when authenticating with the proxy.
+    pub username: Option<String>,
+    /// The name of the environment variable holding the password of the user.
+    pub password_env: Option<String>,
+}
+
+impl ProxySettings {
+    /// Creates new settings sending both http and https requests through the
+    /// specified proxy.
+    pub fn new(url: Url) -> ProxySettings {
+        ProxySettings {
+            http: Some(url.clone()),
+            https: Some(url),
+            no_proxy: vec![],
+            username: None,
+            password_env: None,
+        }
+    }
+
+    /// Reads the proxy from the environment variables, nothing is returned
+    /// when no proxy have been set. Proxies that are not valid urls are
+    /// ignored.
+    pub fn from_env() -> Option<ProxySettings> {
+        ProxySettings::from_vars(|name| std::env::var(name).ok())
+    }
+
+    fn from_vars<F: Fn(&str) -> Option<String>>(var: F) -> Option<ProxySettings> {
+        let read = |names: &[&str]| {
+            names
+                .iter()
+                .filter_map(|name| var(name))
+                .find(|value| !value.trim().is_empty())
+        };
+        let http = read(HTTP_PROXY_VARS).and_then(|value| parse_proxy(&value));
+        let https = read(HTTPS_PROXY_VARS).and_then(|value| parse_proxy(&value));
+
+        if http.is_none() && https.is_none() {
+            return None;
+        }
+
+        Some(ProxySettings {
+            http,
+            https,
+            no_proxy: read(NO_PROXY_VARS)
+                .map(|value| value.split(',').map(String::from).collect())
+                .unwrap_or_default(),
+            username: None,
+            password_env: None,
+        })
+    }
+
+    /// Adds hosts that bypass the proxy, a host starting with a dot (*ie:
+    /// `.example.org`*) matches every sub domain of the host, and `*` matches
+    /// every host.
+    pub fn with_no_proxy<I: IntoIterator<Item = S>, S: Into<String>>(
+        mut self,
+        hosts: I,
+    ) -> ProxySettings {
+        self.no_proxy.extend(hosts.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets the user name, and the name of the environment variable holding
+    /// the password used when authenticating with the proxy.
+    pub fn with_credentials<U: Into<String>, P: Into<String>>(
+        mut self,
+        username: U,
+        password_env: P,
+    ) -> ProxySettings {
+        self.username = Some(username.into());
+        self.password_env = Some(password_env.into());
+        self
+    }
+
+    /// Returns the hosts that bypass the proxy.
+    pub fn no_proxy(&self) -> &[String] {
+        &self.no_proxy
+    }
+
+    /// Returns the proxy the request to the specified url is sent through,
+    /// nothing is returned when the request should bypass the proxy.
+    pub fn proxy_for(&self, url: &Url) -> Option<&Url> {
+        if self.is_bypassed(url) {
+            return None;
+        }
+
+        match url.scheme() {
+            "https" => self.https.as_ref(),
+            "http" => self.http.as_ref(),
+            _ => None,
+        }
+    }
+
+    fn is_bypassed(&self, url: &Url) -> bool {
+        let host = match url.host_str() {
+            Some(host) => host.trim_start_matches('[').trim_end_matches(']'),
+            None => return true,
+        };
+
+        self.no_proxy
+            .iter()
+            .map(|pattern| pattern.trim())
+            .filter(|pattern| !pattern.is_empty())
+            .any(|pattern| {
+                if pattern == "*" {
+                    return true;
+                }
+                let pattern = strip_port(pattern);
+                if let (Ok(pattern), Ok(host)) = (pattern.parse::<IpAddr>(), host.parse::<IpAddr>())
+                {
+                    return pattern == host;
+                }
+                let domain = pattern.trim_start_matches('.');
+
+                host.eq_ignore_ascii_case(domain)
+                    || host
+                        .to_ascii_lowercase()
+                        .ends_with(&format!(".{}", domain.to_ascii_lowercase()))
+            })
+    }
+
+    /// Creates the proxy used by the client, the password is read from the
+    /// environment variable when credentials have been set.
+    pub(crate) fn to_proxy(&self) -> Proxy {
+        let settings = self.clone();
+        let proxy = Proxy::custom(move |url| settings.proxy_for(url).cloned());
+
+        match (
+            self.username.as_deref(),
+            self.password_env.as_deref().map(std::env::var),
+        ) {
+            (Some(username), Some(Ok(password))) => proxy.basic_auth(username, &password),
+            (Some(username), Some(Err(_))) => {
+                warn!(
+                    "The environment variable '{}' holding the password of the proxy user '{}' is \
+                     not set",
+                    self.password_env.as_deref().unwrap_or_default(),
+                    username
+                );
+                proxy
+            }
+            _ => proxy,
+        }
+    }
+}
+
+/// Parses the url of a proxy, a proxy without a scheme (*ie:
+/// `proxy.example.org:8080`*) is assumed to be a http proxy.
+fn parse_proxy(value: &str) -> Option<Url> {
+    let value = value.trim();
+    if value.contains("://") {
+        Url::parse(value).ok()
+    } else {
+        Url::parse(&format!("http://{}", value)).ok()
+    }
+}
+
+fn strip_port(pattern: &str) -> &str {
+    if pattern.starts_with('[') {
+        return pattern
+            .trim_start_matches('[')
+            .split(']')
+            .next()
+            .unwrap_or_default();
+    }
+
+    match pattern.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') && port.parse::<u16>().is_ok() => host,
+        _ => pattern,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn settings() -> ProxySettings {
+        ProxySettings::new(Url::parse("http://proxy.example.org:8080").unwrap()).with_no_proxy(
+            vec![
+                "localhost",
+                ".internal.example.org",
+                "nexus.example.com:443",
+                "10.0.0.1",
+            ],
+        )
+    }
+
+    #[rstest(
+        url,
+        expected,
+        case("https://chocolatey.org/packages", true),
+        case("http://github.com/", true),
+        case("http://localhost:5000/", false),
+        case("https://internal.example.org/", false),
+        case("https://feed.Internal.example.org/", false),
+        case("https://notinternal.example.org/", true),
+        case("https://nexus.example.com/", false),
+        case("https://10.0.0.1/", false),
+        case("ftp://ftp.example.org/", false)
+    )]
+    fn proxy_for_should_honor_no_proxy(url: &str, expected: bool) {
+        let settings = settings();
+
+        let actual = settings.proxy_for(&Url::parse(url).unwrap());
+
+        assert_eq!(actual.is_some(), expected);
+    }
+
+    #[test]
+    fn proxy_for_should_bypass_every_host_with_wildcard() {
+        let settings = settings().with_no_proxy(vec!["*"]);
+
+        let actual = settings.proxy_for(&Url::parse("https://chocolatey.org/").unwrap());
+
+        assert_eq!(actual, None);
+    }
+
+    #[test]
+    fn from_vars_should_read_proxies_by_scheme() {
+        let vars: HashMap<&str, &str> = vec![
+            ("https_proxy", "http://secure.example.org:3128"),
+            ("ALL_PROXY", "proxy.example.org:8080"),
+            ("NO_PROXY", "localhost,.example.com"),
+        ]
+        .into_iter()
+        .collect();
+
+        let actual =
+            ProxySettings::from_vars(|name| vars.get(name).map(|v| v.to_string())).unwrap();
+
+        assert_eq!(
+            actual.proxy_for(&Url::parse("https://chocolatey.org/").unwrap()),
+            Some(&Url::parse("http://secure.example.org:3128").unwrap())
+        );
+        assert_eq!(
+            actual.proxy_for(&Url::parse("http://chocolatey.org/").unwrap()),
+            Some(&Url::parse("http://proxy.example.org:8080").unwrap())
+        );
+        assert_eq!(actual.no_proxy(), ["localhost", ".example.com"]);
+    }
+
+    #[test]
+    fn from_vars_should_return_none_without_proxy() {
+        let actual = ProxySettings::from_vars(|name| match name {
+            "NO_PROXY" => Some("localhost".into()),
+            "HTTP_PROXY" => Some(" ".into()),
+            _ => None,
+        });
+
+        assert_eq!(actual, None);
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for pushing packages to feeds implementing the NuGet v2
+//! push API, like the Chocolatey Community Repository or internal feeds.
+
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::info;
+use reqwest::{header, Url};
+
+use crate::errors::WebError;
+use crate::fixtures::FixtureMode;
+use crate::request::handle_exit_code;
+use crate::WebRequest;
+
+/// The path of the push endpoint, relative to the url of the feed.
+const PUSH_PATH: &str = "api/v2/package/";
+
+/// The header used to send the API key to the feed.
+const API_KEY_HEADER: &str = "X-NuGet-ApiKey";
+
+impl WebRequest {
+    /// Pushes the package file to the specified NuGet v2 feed, by uploading
+    /// the package to the push endpoint of the feed.
+    ///
+    /// ## Arguments
+    ///
+    /// - `feed`: The url of the feed (*ie: `https://push.chocolatey.org/`*),
+    ///   the push endpoint is appended when it is not already part of the url.
+    /// - `api_key`: The API key that will be used when communicating with the
+    ///   feed.
+    /// - `package`: The path to the package file (*ie: the `.nupkg` file*) that
+    ///   should be pushed.
+    ///
+    /// ## Returns
+    ///
+    /// The status code the feed responded with, an unsuccessful status code
+    /// (*ie: the package already exists*) is returned as an error.
+    pub fn push_package(&self, feed: &str, api_key: &str, package: &Path) -> Result<u16, WebError> {
+        let url = push_url(feed)?;
+
+        if let Some(ref fixtures) = self.fixtures {
+            if fixtures.mode() == FixtureMode::Replay {
+                return Err(WebError::Other(format!(
+                    "Packages can not be pushed when replaying fixtures ('{}')",
+                    url
+                )));
+            }
+        }
+
+        let file_name = package
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| WebError::Other(format!("'{}' is not a file", package.display())))?;
+        let content = std::fs::read(package)?;
+        let boundary = format!(
+            "aer-boundary-{:x}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_nanos())
+                .unwrap_or_default()
+        );
+
+        info!("Pushing the package '{}' to '{}'", file_name, url);
+
+        let request = self
+            .client
+            .put(url.clone())
+            .header(API_KEY_HEADER, api_key)
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={}", boundary),
+            )
+            .body(multipart_body(&boundary, &file_name, &content));
+        let response = self.send(request, &url)?;
+
+        handle_exit_code(response, |response| response.status().as_u16())
+    }
+}
+
+/// Creates the url of the push endpoint of the specified feed.
+fn push_url(feed: &str) -> Result<Url, WebError> {
+    let feed = feed.trim();
+    if feed
+        .trim_end_matches('/')
+        .ends_with(PUSH_PATH.trim_end_matches('/'))
+    {
+        return Url::parse(feed).map_err(|err| WebError::Other(err.to_string()));
+    }
+
+    let feed = if feed.ends_with('/') {
+        feed.to_owned()
+    } else {
+        format!("{}/", feed)
+    };
+
+    Url::parse(&feed)
+        .and_then(|url| url.join(PUSH_PATH))
+        .map_err(|err| WebError::Other(err.to_string()))
+}
+
+/// Creates the multipart form data containing the package file, which is the
+/// format expected by the push API.
+fn multipart_body(boundary: &str, file_name: &str, content: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(content.len() + 256);
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"package\"; \
+             filename=\"{}\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+            boundary,
+            file_name.replace('"', "")
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(content);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest(
+        feed,
+        expected,
+        case(
+            "https://push.chocolatey.org/",
+            "https://push.chocolatey.org/api/v2/package/"
+        ),
+        case(
+            "https://nexus.example.org/repository/choco",
+            "https://nexus.example.org/repository/choco/api/v2/package/"
+        ),
+        case(
+            "https://example.org/feed/api/v2/package",
+            "https://example.org/feed/api/v2/package"
+        )
+    )]
+    fn push_url_should_append_push_endpoint(feed: &str, expected: &str) {
+        let actual = push_url(feed).unwrap();
+
+        assert_eq!(actual.as_str(), expected);
+    }
+
+    #[test]
+    fn multipart_body_should_contain_package_file() {
+        let actual = multipart_body("boundary", "test.1.0.0.nupkg", b"content");
+
+        assert_eq!(
+            String::from_utf8(actual).unwrap(),
+            "--boundary\r\nContent-Disposition: form-data; name=\"package\"; \
+             filename=\"test.1.0.0.nupkg\"\r\nContent-Type: \
+             application/octet-stream\r\n\r\ncontent\r\n--boundary--\r\n"
+        );
+    }
+
+    #[test]
+    fn push_package_should_not_push_when_replaying_fixtures() {
+        let dir = std::env::temp_dir().join("aer-push-fixtures-test");
+        let request = WebRequest::create()
+            .with_fixtures(crate::fixtures::Fixtures::new(&dir, FixtureMode::Replay));
+
+        let actual = request.push_package(
+            "https://push.chocolatey.org/",
+            "api-key",
+            Path::new("test.1.0.0.nupkg"),
+        );
+
+        assert!(matches!(actual, Err(WebError::Other(_))));
+    }
+}
@@ -0,0 +1,191 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the rate limiter spacing out the requests sent to the same host,
+//! avoiding hammering a server (*and getting banned by it*) when several
+//! packages are updated from the same host during large batch runs.
+//!
+//! Every host is limited to the default number of requests per second, unless
+//! a different rate have been set for the host. A rate set for a host also
+//! applies to every sub domain of the host (*ie: a rate for `github.com` is
+//! also used for `api.github.com`*). The number of concurrent transfers is
+//! limited separately by the [TransferBudget](crate::budget::TransferBudget).
+//!
+//! ### Examples
+//!
+//! ```
+//! use aer_web::rate_limit::RateLimiter;
+//! use reqwest::Url;
+//!
+//! let limiter = RateLimiter::new(2.0).with_host_rate("sourceforge.net", 0.5);
+//!
+//! let url = Url::parse("https://downloads.sourceforge.net/project/file.zip").unwrap();
+//! assert_eq!(limiter.rate(&url), Some(0.5));
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use log::debug;
+use reqwest::Url;
+
+/// The rate limiter shared between every clone of a web request, spacing out
+/// the requests sent to each host.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    default_rate: Option<f64>,
+    host_rates: BTreeMap<String, f64>,
+    next_slots: Arc<Mutex<HashMap<String, Instant>>>,
+}
+
+impl RateLimiter {
+    /// Creates a new rate limiter allowing the specified number of requests
+    /// per second to every host, a value of `0` means hosts without a rate of
+    /// their own are not limited.
+    pub fn new(requests_per_second: f64) -> RateLimiter {
+        RateLimiter {
+            default_rate: Some(requests_per_second).filter(|rate| *rate > 0.0),
+            host_rates: BTreeMap::new(),
+            next_slots: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Sets the number of requests per second allowed to the specified host
+    /// and its sub domains, a value of `0` means the host is not limited.
+    pub fn with_host_rate<H: Into<String>>(
+        mut self,
+        host: H,
+        requests_per_second: f64,
+    ) -> RateLimiter {
+        self.host_rates.insert(
+            host.into().trim_start_matches('.').to_ascii_lowercase(),
+            requests_per_second,
+        );
+        self
+    }
+
+    /// Returns the number of requests per second allowed to the host of the
+    /// specified url, nothing is returned when the host is not limited.
+    pub fn rate(&self, url: &Url) -> Option<f64> {
+        let host = url.host_str()?.to_ascii_lowercase();
+
+        self.host_rates
+            .iter()
+            .filter(|(name, _)| host == **name || host.ends_with(&format!(".{}", name)))
+            .max_by_key(|(name, _)| name.len())
+            .map(|(_, rate)| *rate)
+            .or(self.default_rate)
+            .filter(|rate| *rate > 0.0)
+    }
+
+    /// Waits until the request to the specified url is allowed to be sent.
+    pub fn wait(&self, url: &Url) {
+        let delay = self.reserve(url, Instant::now());
+
+        if delay > Duration::from_secs(0) {
+            debug!(
+                "Waiting {:.2} seconds before sending the request to '{}'",
+                delay.as_secs_f32(),
+                url
+            );
+            std::thread::sleep(delay);
+        }
+    }
+
+    /// Reserves the next available slot of the host of the url, and returns
+    /// how long to wait until the slot is reached.
+    fn reserve(&self, url: &Url, now: Instant) -> Duration {
+        let (host, rate) = match (url.host_str(), self.rate(url)) {
+            (Some(host), Some(rate)) => (host.to_ascii_lowercase(), rate),
+            _ => return Duration::from_secs(0),
+        };
+        let interval = Duration::from_secs_f64(1.0 / rate);
+        let mut next_slots = self.next_slots.lock().unwrap();
+        let slot = match next_slots.get(&host) {
+            Some(next) if *next > now => *next,
+            _ => now,
+        };
+        next_slots.insert(host, slot + interval);
+
+        slot - now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn limiter() -> RateLimiter {
+        RateLimiter::new(2.0)
+            .with_host_rate("github.com", 1.0)
+            .with_host_rate("objects.github.com", 0.0)
+    }
+
+    #[rstest(
+        url,
+        expected,
+        case("https://chocolatey.org/", Some(2.0)),
+        case("https://github.com/WormieCorp", Some(1.0)),
+        case("https://API.github.com/repos", Some(1.0)),
+        case("https://notgithub.com/", Some(2.0)),
+        case("https://objects.github.com/file.zip", None)
+    )]
+    fn rate_should_use_rate_of_most_specific_host(url: &str, expected: Option<f64>) {
+        let actual = limiter().rate(&Url::parse(url).unwrap());
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn rate_should_not_limit_hosts_without_default_rate() {
+        let limiter = RateLimiter::new(0.0).with_host_rate("github.com", 1.0);
+
+        let actual = limiter.rate(&Url::parse("https://chocolatey.org/").unwrap());
+
+        assert_eq!(actual, None);
+    }
+
+    #[test]
+    fn reserve_should_space_requests_to_same_host() {
+        let limiter = limiter();
+        let url = Url::parse("https://chocolatey.org/").unwrap();
+        let now = Instant::now();
+
+        let actual: Vec<_> = (0..3).map(|_| limiter.reserve(&url, now)).collect();
+
+        assert_eq!(
+            actual,
+            [
+                Duration::from_secs(0),
+                Duration::from_millis(500),
+                Duration::from_millis(1000)
+            ]
+        );
+    }
+
+    #[test]
+    fn reserve_should_not_delay_requests_to_other_hosts() {
+        let limiter = limiter();
+        let now = Instant::now();
+
+        limiter.reserve(&Url::parse("https://github.com/").unwrap(), now);
+        let actual = limiter.reserve(&Url::parse("https://chocolatey.org/").unwrap(), now);
+
+        assert_eq!(actual, Duration::from_secs(0));
+    }
+
+    #[test]
+    fn reserve_should_share_slots_between_clones() {
+        let limiter = limiter();
+        let url = Url::parse("https://github.com/").unwrap();
+        let now = Instant::now();
+
+        limiter.clone().reserve(&url, now);
+        let actual = limiter.reserve(&url, now);
+
+        assert_eq!(actual, Duration::from_secs(1));
+    }
+}
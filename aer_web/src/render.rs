@@ -0,0 +1,188 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the rendering of html pages using a headless Chromium based
+//! browser, used for download pages that are rendered entirely by JavaScript
+//! where the links are not part of the html returned by the server.
+//!
+//! The browser is started once for every page, and the html of the page is
+//! read after the scripts of the page have been run (*using the `--dump-dom`
+//! argument*). The browser is located using the `AER_BROWSER` environment
+//! variable, or otherwise the first of the known Chromium, Chrome or Edge
+//! executables available on the path.
+//!
+//! ### Examples
+//!
+//! ```no_run
+//! use aer_web::render::BrowserRenderer;
+//! use aer_web::{WebRequest, WebResponse};
+//!
+//! let request = WebRequest::create();
+//! let renderer = BrowserRenderer::new();
+//! let (_, links) = request
+//!     .get_rendered_html_response("https://example.org/downloads", &renderer)
+//!     .unwrap()
+//!     .read(None)
+//!     .unwrap();
+//! ```
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+use log::debug;
+use reqwest::Url;
+
+use crate::errors::WebError;
+
+/// The environment variable that can be used to specify the browser used
+/// when rendering pages.
+pub const BROWSER_ENV: &str = "AER_BROWSER";
+
+/// The executables that are tried, in order, when no browser have been
+/// specified.
+const BROWSER_EXECS: &[&str] = &[
+    "chromium",
+    "chromium-browser",
+    "google-chrome",
+    "google-chrome-stable",
+    "chrome",
+    "msedge",
+];
+
+/// Renders html pages using a headless Chromium based browser.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrowserRenderer {
+    executable: Option<PathBuf>,
+    wait: Duration,
+}
+
+impl BrowserRenderer {
+    /// Creates a new renderer using the browser specified in the
+    /// `AER_BROWSER` environment variable, or the first known browser on the
+    /// path. The scripts of a page are given 5 seconds to run.
+    pub fn new() -> BrowserRenderer {
+        BrowserRenderer {
+            executable: std::env::var_os(BROWSER_ENV)
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from),
+            wait: Duration::from_secs(5),
+        }
+    }
+
+    /// Sets the executable of the browser used when rendering pages.
+    pub fn with_executable<P: Into<PathBuf>>(mut self, executable: P) -> BrowserRenderer {
+        self.executable = Some(executable.into());
+        self
+    }
+
+    /// Sets how long the scripts of a page are allowed to run before the html
+    /// of the page is read.
+    pub fn with_wait(mut self, wait: Duration) -> BrowserRenderer {
+        self.wait = wait;
+        self
+    }
+
+    /// Renders the page at the specified url, and returns the html of the page
+    /// after the scripts of the page have been run. This function will return
+    /// an error if no browser could be started, or the browser fails to render
+    /// the page.
+    pub fn render(&self, url: &Url) -> Result<String, WebError> {
+        let args = self.args(url);
+        let executables = match self.executable {
+            Some(ref executable) => vec![executable.clone()],
+            None => BROWSER_EXECS.iter().map(PathBuf::from).collect(),
+        };
+
+        for executable in executables {
+            debug!("Rendering '{}' using '{}'", url, executable.display());
+            let output = match Command::new(&executable).args(&args).output() {
+                Ok(output) => output,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(WebError::IoError(err)),
+            };
+
+            return if output.status.success() {
+                Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+            } else {
+                Err(WebError::Other(format!(
+                    "'{}' failed to render '{}': {}",
+                    executable.display(),
+                    url,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )))
+            };
+        }
+
+        Err(WebError::Other(format!(
+            "A Chromium based browser is required to render '{}', set the '{}' environment \
+             variable to the path of the browser!",
+            url, BROWSER_ENV
+        )))
+    }
+
+    fn args(&self, url: &Url) -> Vec<OsString> {
+        vec![
+            "--headless".into(),
+            "--disable-gpu".into(),
+            "--no-first-run".into(),
+            format!("--virtual-time-budget={}", self.wait.as_millis()).into(),
+            "--dump-dom".into(),
+            url.as_str().into(),
+        ]
+    }
+}
+
+impl Default for BrowserRenderer {
+    fn default() -> BrowserRenderer {
+        BrowserRenderer::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url() -> Url {
+        Url::parse("https://example.org/downloads").unwrap()
+    }
+
+    #[test]
+    fn args_should_dump_dom_of_url_after_wait() {
+        let renderer = BrowserRenderer::new().with_wait(Duration::from_millis(2500));
+
+        let args = renderer.args(&url());
+
+        assert!(args.contains(&"--headless".into()));
+        assert!(args.contains(&"--virtual-time-budget=2500".into()));
+        assert_eq!(
+            args[args.len() - 2..],
+            [
+                OsString::from("--dump-dom"),
+                OsString::from("https://example.org/downloads")
+            ]
+        );
+    }
+
+    #[test]
+    fn render_should_return_error_when_browser_is_not_found() {
+        let renderer = BrowserRenderer::new().with_executable("aer-missing-browser");
+
+        let actual = renderer.render(&url());
+
+        assert!(actual.is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn render_should_return_output_of_browser() {
+        let renderer = BrowserRenderer::new().with_executable("echo");
+
+        let actual = renderer.render(&url()).unwrap();
+
+        assert!(actual
+            .trim()
+            .ends_with("--dump-dom https://example.org/downloads"));
+    }
+}
@@ -4,25 +4,51 @@
 //! Section responsible for allowing requests to be sent to remote locations.
 
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 use lazy_static::lazy_static;
-use log::info;
-use reqwest::blocking::{Client, Response};
+use log::{info, warn};
+use reqwest::blocking::{Client, RequestBuilder, Response};
 use reqwest::header::{HeaderMap, HeaderValue};
-use reqwest::{header, StatusCode, Url};
+use reqwest::redirect::Policy;
+use reqwest::{header, Certificate, Method, StatusCode, Url};
 
+use crate::auth::AuthProfiles;
+use crate::budget::{TransferBudget, TransferKind, TransferPermit};
 use crate::errors::WebError;
-use crate::response::{BinaryResponse, HtmlResponse, ResponseType};
+use crate::fixtures::{FixtureMode, Fixtures};
+use crate::http_log::{CacheStatus, HttpLog, HttpLogEntry};
+use crate::proxy::ProxySettings;
+use crate::rate_limit::RateLimiter;
+#[cfg(feature = "render")]
+use crate::render::BrowserRenderer;
+use crate::response::{
+    BinaryResponse, FeedResponse, HtmlResponse, JsonResponse, ResponseType, XmlResponse,
+};
+use crate::retry::RetryPolicy;
+use crate::{LinkType, LinkTypeMap};
+
+/// The maximum number of redirects that are followed, the same as the default
+/// redirect policy of the client.
+const MAX_REDIRECTS: usize = 10;
 
 /// The name of the application + the version, which should be sent with every
 /// request to the websites.
-const APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+pub(crate) const APP_USER_AGENT: &str =
+    concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
 
 lazy_static! {
-    static ref ACCEPTED_TYPES: HashMap<&'static str, &'static str> = {
+    pub(crate) static ref ACCEPTED_TYPES: HashMap<&'static str, &'static str> = {
         let mut map = HashMap::new();
         map.insert("html", "text/html; charset=UTF-8");
         map.insert("binary", "application/octet-stream");
+        map.insert("json", "application/json");
+        map.insert("xml", "application/xml, text/xml;q=0.9");
+        map.insert(
+            "feed",
+            "application/rss+xml, application/atom+xml, application/xml;q=0.9",
+        );
 
         map
     };
@@ -32,6 +58,13 @@ lazy_static! {
 /// Also responsible for having a structure instance that can be used to get
 /// different types of responses.
 ///
+/// ## Thread safety
+///
+/// The request is `Send` and `Sync`, and every method only requires a shared
+/// reference. Clones share the underlying connection pool, the fixtures, the
+/// HTTP log, the transfer budget and the rate limiter, which makes cloning the
+/// request the preferred way of handing it to worker threads.
+///
 /// ## Examples
 ///
 /// Aquiring html response.
@@ -43,8 +76,297 @@ lazy_static! {
 ///     .get_html_response("https://httpbin.org/get")
 ///     .unwrap();
 /// ```
+#[derive(Clone)]
 pub struct WebRequest {
-    client: Client,
+    pub(crate) client: Client,
+    authenticated_client: Client,
+    pub(crate) fixtures: Option<Fixtures>,
+    link_types: LinkTypeMap,
+    http_log: Option<HttpLog>,
+    accept_language: Option<String>,
+    budget: Option<TransferBudget>,
+    rate_limiter: Option<RateLimiter>,
+    pub(crate) github_token: Option<String>,
+    pub(crate) gitlab_token: Option<String>,
+    auth_profiles: AuthProfiles,
+    retry: RetryPolicy,
+}
+
+/// Builds a [WebRequest], configuring the proxy the requests are sent
+/// through and the additional root certificates that are trusted.
+///
+/// ## Examples
+///
+/// ```
+/// use aer_web::proxy::ProxySettings;
+/// use aer_web::WebRequest;
+/// use reqwest::Url;
+///
+/// let request = WebRequest::builder()
+///     .with_proxy(ProxySettings::new(
+///         Url::parse("http://proxy.example.org:8080").unwrap(),
+///     ))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebRequestBuilder {
+    proxy: Option<ProxySettings>,
+    root_certificates: Vec<PathBuf>,
+}
+
+impl Default for WebRequestBuilder {
+    fn default() -> Self {
+        Self {
+            proxy: ProxySettings::from_env(),
+            root_certificates: vec![],
+        }
+    }
+}
+
+impl WebRequestBuilder {
+    /// Sets the proxy that requests are sent through, instead of the proxy
+    /// read from the environment variables.
+    pub fn with_proxy(mut self, proxy: ProxySettings) -> WebRequestBuilder {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Sends every request directly to the remote server, ignoring the proxy
+    /// read from the environment variables.
+    pub fn without_proxy(mut self) -> WebRequestBuilder {
+        self.proxy = None;
+        self
+    }
+
+    /// Adds a file holding root certificates that should be trusted in
+    /// addition to the root certificates of the system, needed in
+    /// environments where the TLS connections are inspected. The file is
+    /// either a bundle of PEM encoded certificates, or a single DER encoded
+    /// certificate.
+    pub fn with_root_certificates<P: Into<PathBuf>>(mut self, path: P) -> WebRequestBuilder {
+        self.root_certificates.push(path.into());
+        self
+    }
+
+    /// Returns the proxy that requests are sent through, if any.
+    pub fn proxy(&self) -> Option<&ProxySettings> {
+        self.proxy.as_ref()
+    }
+
+    /// Creates the web request, returning an error if the root certificates
+    /// could not be loaded.
+    pub fn build(self) -> Result<WebRequest, WebError> {
+        Ok(WebRequest {
+            client: configure_client!(Client::builder(), self),
+            authenticated_client: configure_client!(
+                Client::builder().redirect(same_host_redirects()),
+                self
+            ),
+            fixtures: None,
+            link_types: LinkTypeMap::default(),
+            http_log: None,
+            accept_language: None,
+            budget: None,
+            rate_limiter: None,
+            github_token: None,
+            gitlab_token: None,
+            auth_profiles: AuthProfiles::default(),
+            retry: RetryPolicy::default(),
+        })
+    }
+}
+
+/// Configures and builds either the blocking or the asynchronous client from
+/// the settings of a [WebRequestBuilder], as the builders of the clients do
+/// not share a common type.
+macro_rules! configure_client {
+    ($client:expr, $settings:expr) => {{
+        let client = $client
+            .user_agent($crate::request::APP_USER_AGENT)
+            .default_headers($crate::request::default_headers())
+            .no_proxy();
+        // The rust TLS implementation is only compiled in for the targets where
+        // it can be selected
+        #[cfg(any(all(windows, not(target_arch = "aarch64")), target_env = "musl"))]
+        let client = if $crate::platform::Platform::current().tls_backend()
+            == $crate::platform::TlsBackend::Rustls
+        {
+            client.use_rustls_tls()
+        } else {
+            client
+        };
+        let mut client = match $settings.proxy() {
+            Some(proxy) => client.proxy(proxy.to_proxy()),
+            None => client,
+        };
+        for certificate in $settings.certificates()? {
+            client = client.add_root_certificate(certificate);
+        }
+
+        client.build().map_err(|err| {
+            $crate::errors::WebError::Other(format!("Unable to create the web client: {}", err))
+        })?
+    }};
+}
+
+pub(crate) use configure_client;
+
+impl WebRequestBuilder {
+    /// Loads every root certificate that should be trusted in addition to the
+    /// root certificates of the system.
+    pub(crate) fn certificates(&self) -> Result<Vec<Certificate>, WebError> {
+        let mut certificates = vec![];
+        for path in &self.root_certificates {
+            certificates.extend(load_certificates(path)?);
+        }
+
+        Ok(certificates)
+    }
+}
+
+/// The line starting every PEM encoded certificate.
+const PEM_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+/// The line ending every PEM encoded certificate.
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+/// Loads the certificates in the file at the specified path, every
+/// certificate in a PEM bundle is loaded separately as not every TLS
+/// implementation accepts bundles.
+fn load_certificates(path: &Path) -> Result<Vec<Certificate>, WebError> {
+    let bytes = std::fs::read(path)?;
+    let invalid = |err: reqwest::Error| {
+        WebError::Other(format!(
+            "The certificate '{}' is not valid: {}",
+            path.display(),
+            err
+        ))
+    };
+
+    let text = String::from_utf8_lossy(&bytes);
+    if !text.contains(PEM_BEGIN) {
+        return Ok(vec![Certificate::from_der(&bytes).map_err(invalid)?]);
+    }
+
+    text.split(PEM_BEGIN)
+        .skip(1)
+        .map(|block| {
+            let body = block.split(PEM_END).next().unwrap_or_default();
+            Certificate::from_pem(format!("{}{}{}\n", PEM_BEGIN, body, PEM_END).as_bytes())
+                .map_err(invalid)
+        })
+        .collect()
+}
+
+/// The outcome of checking if a link can still be reached.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkCheck {
+    /// The status code the server responded with.
+    pub status: u16,
+    /// The final location of the link, after all redirects have been
+    /// followed.
+    pub url: Url,
+    /// The entity tag the server responded with, if any.
+    pub etag: Option<String>,
+}
+
+impl LinkCheck {
+    /// Returns wether the server responded with a successful status code.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+impl From<&Response> for LinkCheck {
+    fn from(response: &Response) -> Self {
+        LinkCheck {
+            status: response.status().as_u16(),
+            url: response.url().clone(),
+            etag: header_value(response, header::ETAG),
+        }
+    }
+}
+
+/// The metadata of a remote file, read from the headers the server responded
+/// with without downloading the file itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadResponse {
+    /// The status code the server responded with.
+    pub status: u16,
+    /// The final location of the file, after all redirects have been
+    /// followed.
+    pub url: Url,
+    /// The size of the file in bytes, if reported by the server.
+    pub content_length: Option<u64>,
+    /// The media type of the file, if reported by the server.
+    pub content_type: Option<String>,
+    /// The entity tag the server responded with, if any.
+    pub etag: Option<String>,
+    /// The date the file was last modified, if reported by the server.
+    pub last_modified: Option<String>,
+}
+
+impl HeadResponse {
+    /// Returns wether the server responded with a successful status code.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Returns wether the server responded that the file have not been
+    /// modified since the specified etag or last modified date.
+    pub fn is_not_modified(&self) -> bool {
+        self.status == StatusCode::NOT_MODIFIED.as_u16()
+    }
+}
+
+impl From<&Response> for HeadResponse {
+    fn from(response: &Response) -> Self {
+        HeadResponse {
+            status: response.status().as_u16(),
+            url: response.url().clone(),
+            content_length: header_value(response, header::CONTENT_LENGTH)
+                .and_then(|length| length.parse().ok()),
+            content_type: header_value(response, header::CONTENT_TYPE),
+            etag: header_value(response, header::ETAG),
+            last_modified: header_value(response, header::LAST_MODIFIED),
+        }
+    }
+}
+
+/// Returns the value of the specified header, if the server responded with
+/// the header.
+fn header_value(response: &Response, name: header::HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+}
+
+/// Creates the headers used when making a conditional request, only
+/// requesting the content when it do not match the etag or have been modified
+/// since the last modified date.
+pub(crate) fn conditional_headers(
+    etag: Option<&str>,
+    last_modified: Option<&str>,
+) -> Result<HeaderMap, WebError> {
+    let mut headers = HeaderMap::new();
+    if let Some(etag) = etag {
+        let new_etag = format!("\"{}\"", etag.trim_matches('"'));
+
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_str(&new_etag).map_err(|err| WebError::Other(err.to_string()))?,
+        );
+    }
+    if let Some(last_modified) = last_modified {
+        headers.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_str(last_modified).map_err(|err| WebError::Other(err.to_string()))?,
+        );
+    }
+
+    Ok(headers)
 }
 
 macro_rules! headers {
@@ -58,25 +380,158 @@ macro_rules! headers {
     };
 }
 
+/// Returns the headers that are sent with every request, a do not track
+/// header, a header requesting to upgrade insecure requests and the default
+/// languages.
+pub(crate) fn default_headers() -> HeaderMap {
+    headers!(
+        header::ACCEPT_LANGUAGE => "en-US, en;q=0.8, *;q=0.5",
+        header::DNT => "1",
+        header::UPGRADE_INSECURE_REQUESTS => "1"
+    )
+}
+
+impl std::fmt::Debug for WebRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebRequest")
+            .field("fixtures", &self.fixtures)
+            .field("link_types", &self.link_types)
+            .field("accept_language", &self.accept_language)
+            .field("retry", &self.retry)
+            .finish_non_exhaustive()
+    }
+}
+
 impl WebRequest {
     /// Creates a new instance of a web request. This also creates a client with
     /// the information set to the current application+version, a do not track
     /// header and a header requesting to upgrade insecure requests.
     pub fn create() -> WebRequest {
-        let mut client = Client::builder()
-            .user_agent(APP_USER_AGENT)
-            .default_headers(headers!(
-                header::ACCEPT_LANGUAGE => "en-US, en;q=0.8, *;q=0.5",
-                header::DNT => "1",
-                header::UPGRADE_INSECURE_REQUESTS => "1"
-            ));
-        if cfg!(windows) {
-            client = client.use_rustls_tls();
-        }
+        WebRequest::builder().build().unwrap()
+    }
 
-        WebRequest {
-            client: client.build().unwrap(),
-        }
+    /// Creates a builder of a web request, allowing the proxy and additional
+    /// root certificates to be configured. The proxy is read from the
+    /// `HTTPS_PROXY`, `HTTP_PROXY` and `ALL_PROXY` environment variables by
+    /// default.
+    pub fn builder() -> WebRequestBuilder {
+        WebRequestBuilder::default()
+    }
+
+    /// Sets the fixtures that html responses will be recorded to, or replayed
+    /// from instead of sending the request to the remote server.
+    pub fn with_fixtures(mut self, fixtures: Fixtures) -> WebRequest {
+        self.fixtures = Some(fixtures);
+        self
+    }
+
+    /// Sets the log that every request sent (or replayed) will be recorded
+    /// to, together with the timing and caching information of the request.
+    pub fn with_http_log(mut self, http_log: HttpLog) -> WebRequest {
+        self.http_log = Some(http_log);
+        self
+    }
+
+    /// Sets the languages that should be requested from the web servers,
+    /// instead of the default `en-US, en;q=0.8, *;q=0.5`. The value is sent
+    /// as-is in the `Accept-Language` header.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use aer_web::WebRequest;
+    ///
+    /// let request = WebRequest::create().with_accept_language("nb-NO, nb;q=0.9, en;q=0.5");
+    /// ```
+    pub fn with_accept_language<L: Into<String>>(mut self, accept_language: L) -> WebRequest {
+        self.accept_language = Some(accept_language.into());
+        self
+    }
+
+    /// Sets the budget limiting the number of concurrent transfers and the
+    /// number of bytes in flight. The budget is shared between every clone of
+    /// the request.
+    pub fn with_budget(mut self, budget: TransferBudget) -> WebRequest {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Sets the rate limiter spacing out the requests sent to the same host.
+    /// The rate limiter is shared between every clone of the request.
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> WebRequest {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Sets the personal access token that is used when reading releases
+    /// from GitHub, requests without a token are subject to a lower rate
+    /// limit.
+    pub fn with_github_token<T: Into<String>>(mut self, token: T) -> WebRequest {
+        self.github_token = Some(token.into());
+        self
+    }
+
+    /// Sets the personal access token that is used when reading releases
+    /// from GitLab, this is required for private projects.
+    pub fn with_gitlab_token<T: Into<String>>(mut self, token: T) -> WebRequest {
+        self.gitlab_token = Some(token.into());
+        self
+    }
+
+    /// Sets the authentication profiles of private feeds, every request sent
+    /// to the url of a feed is authenticated with the credentials of the
+    /// matching profile.
+    pub fn with_auth_profiles(mut self, auth_profiles: AuthProfiles) -> WebRequest {
+        self.auth_profiles = auth_profiles;
+        self
+    }
+
+    /// Returns the authentication profiles of private feeds.
+    pub fn auth_profiles(&self) -> &AuthProfiles {
+        &self.auth_profiles
+    }
+
+    /// Sets the policy deciding when failed requests are retried, by default
+    /// requests are retried 3 times with an exponential delay between each
+    /// attempt.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> WebRequest {
+        self.retry = retry;
+        self
+    }
+
+    /// Returns the policy deciding when failed requests are retried.
+    pub fn retry(&self) -> &RetryPolicy {
+        &self.retry
+    }
+
+    /// Registers an additional MIME type that should be mapped to the
+    /// specified link type, this takes precedence over the default mappings.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use aer_web::{LinkType, WebRequest};
+    ///
+    /// let request = WebRequest::create()
+    ///     .with_mime_type("application/vnd.debian.binary-package", LinkType::Binary);
+    /// ```
+    pub fn with_mime_type(mut self, mime_type: &str, link_type: LinkType) -> WebRequest {
+        self.link_types.add_mime_type(mime_type, link_type);
+        self
+    }
+
+    /// Registers an additional file extension (*ie: `.AppImage`*) that should
+    /// be mapped to the specified link type, this takes precedence over the
+    /// default mappings.
+    pub fn with_extension(mut self, extension: &str, link_type: LinkType) -> WebRequest {
+        self.link_types.add_extension(extension, link_type);
+        self
+    }
+
+    /// Returns the mappings used to decide the type of links in html
+    /// responses.
+    pub fn link_types(&self) -> &LinkTypeMap {
+        &self.link_types
     }
 
     /// Makes a request to a website and requesting the html at the location
@@ -86,17 +541,386 @@ impl WebRequest {
     /// the response can be found by calling the
     /// [read](crate::response::HtmlResponse::read) function.
     pub fn get_html_response(&self, url: &str) -> Result<HtmlResponse, WebError> {
+        let response = self.get_document(url, ACCEPTED_TYPES["html"])?;
+
+        handle_exit_code(response, |response| self.new_html_response(response))
+    }
+
+    /// Renders the page at the location using a headless browser, and returns
+    /// the html of the page after the scripts of the page have been run. This
+    /// is used for download pages that are rendered by JavaScript, where the
+    /// links are not part of the html returned by the server.
+    ///
+    /// The `Ok` value should be an instance of [HtmlResponse], and the links in
+    /// the response can be found by calling the
+    /// [read](crate::response::HtmlResponse::read) function.
+    #[cfg(feature = "render")]
+    pub fn get_rendered_html_response(
+        &self,
+        url: &str,
+        renderer: &BrowserRenderer,
+    ) -> Result<HtmlResponse, WebError> {
+        use reqwest::ResponseBuilderExt;
+
         let url = Url::parse(url).map_err(|err| WebError::Other(err.to_string()))?;
+        if let Some(ref fixtures) = self.fixtures {
+            if fixtures.mode() == FixtureMode::Replay {
+                return self.get_html_response(url.as_str());
+            }
+        }
 
-        let client = &self.client;
+        if let Some(ref rate_limiter) = self.rate_limiter {
+            rate_limiter.wait(&url);
+        }
+        let _permit = self.acquire(TransferKind::Metadata);
+        info!("Rendering '{}' using a headless browser", url);
+        let body = renderer.render(&url)?;
+        let response = http::Response::builder()
+            .status(StatusCode::OK)
+            .url(url.clone())
+            .header(header::CONTENT_TYPE, "text/html")
+            .body(body)
+            .map_err(|err| WebError::Other(err.to_string()))?;
+        let response = match self.fixtures {
+            Some(ref fixtures) => fixtures.save(&url, Response::from(response))?,
+            None => Response::from(response),
+        };
 
-        let response = client
-            .get(url)
-            .header(header::ACCEPT, ACCEPTED_TYPES["html"])
-            .send()
-            .map_err(WebError::Request)?;
+        Ok(self.new_html_response(response))
+    }
+
+    /// Makes a request to a JSON endpoint (*ie: the API of a vendor*) and
+    /// requests the JSON document at the location.
+    ///
+    /// The `Ok` value should be an instance of [JsonResponse], and the parsed
+    /// document can be read by calling the
+    /// [read](crate::response::JsonResponse::read) function, or the links
+    /// selected by a JSONPath expression by calling the
+    /// [read_links](crate::response::JsonResponse::read_links) function.
+    pub fn get_json_response(&self, url: &str) -> Result<JsonResponse, WebError> {
+        let response = self.get_document(url, ACCEPTED_TYPES["json"])?;
+
+        handle_exit_code(response, |response| {
+            JsonResponse::new(response).with_link_types(self.link_types.clone())
+        })
+    }
 
-        handle_exit_code(response, HtmlResponse::new)
+    /// Makes a request to an RSS or Atom feed (*ie: the release feed of a
+    /// project*) and requests the feed at the location.
+    ///
+    /// The `Ok` value should be an instance of [FeedResponse], and the links of
+    /// the entries in the feed can be found by calling the
+    /// [read](crate::response::FeedResponse::read) function.
+    pub fn get_feed_response(&self, url: &str) -> Result<FeedResponse, WebError> {
+        let response = self.get_document(url, ACCEPTED_TYPES["feed"])?;
+
+        handle_exit_code(response, |response| {
+            FeedResponse::new(response).with_link_types(self.link_types.clone())
+        })
+    }
+
+    /// Makes a request to an XML document (*ie: an appcast feed or an update
+    /// manifest*) and requests the document at the location.
+    ///
+    /// The `Ok` value should be an instance of [XmlResponse], and the parsed
+    /// document can be read by calling the
+    /// [read](crate::response::XmlResponse::read) function, or the links and
+    /// versions selected by XPath-like queries by calling the
+    /// [read_links](crate::response::XmlResponse::read_links) function.
+    pub fn get_xml_response(&self, url: &str) -> Result<XmlResponse, WebError> {
+        let response = self.get_document(url, ACCEPTED_TYPES["xml"])?;
+
+        handle_exit_code(response, |response| {
+            XmlResponse::new(response).with_link_types(self.link_types.clone())
+        })
+    }
+
+    fn get_document(&self, url: &str, accept: &str) -> Result<Response, WebError> {
+        let url = Url::parse(url).map_err(|err| WebError::Other(err.to_string()))?;
+
+        self.get_api_response(&url, accept, None)
+    }
+
+    pub(crate) fn send(&self, request: RequestBuilder, url: &Url) -> Result<Response, WebError> {
+        self.send_logged(request, url, url.as_str())
+    }
+
+    /// Sends the request the same way as [send](WebRequest::send), but only
+    /// includes the scheme and host of the url in the http log and the logged
+    /// messages. This is used for urls containing secrets (*ie: the token of a
+    /// webhook*), the url is also stripped from any returned error.
+    pub(crate) fn send_redacted(
+        &self,
+        request: RequestBuilder,
+        url: &Url,
+    ) -> Result<Response, WebError> {
+        self.send_logged(request, url, &redacted(url))
+            .map_err(WebError::without_url)
+    }
+
+    fn send_logged(
+        &self,
+        request: RequestBuilder,
+        url: &Url,
+        logged_url: &str,
+    ) -> Result<Response, WebError> {
+        let request = match self.accept_language {
+            Some(ref accept_language) => request.header(header::ACCEPT_LANGUAGE, accept_language),
+            None => request,
+        };
+        let profile = self.auth_profiles.find(url);
+        let request = match profile {
+            Some(profile) => profile.authenticate(request),
+            None => request,
+        };
+        let request = request.build().map_err(WebError::Request)?;
+        let method = request.method().clone();
+        // The credentials of the profile are never sent along when redirected
+        // to another host, the redirect is followed without the credentials.
+        let (client, redirect_headers) = match profile {
+            Some(profile) => {
+                let mut headers = request.headers().clone();
+                profile.remove_credentials(&mut headers);
+                (&self.authenticated_client, Some(headers))
+            }
+            None => (&self.client, None),
+        };
+        let mut attempt = 0;
+        let mut next = Some(request);
+
+        loop {
+            let request = next.take().unwrap();
+            next = request.try_clone();
+            if let Some(ref rate_limiter) = self.rate_limiter {
+                rate_limiter.wait(url);
+            }
+            let started = Instant::now();
+            let result = client.execute(request);
+
+            match result {
+                Ok(ref response) => {
+                    let cache = if response.status() == StatusCode::NOT_MODIFIED {
+                        CacheStatus::NotModified
+                    } else {
+                        CacheStatus::Miss
+                    };
+                    self.log_response(logged_url, method.as_str(), started, response, cache);
+                }
+                Err(ref err) => {
+                    if let Some(ref http_log) = self.http_log {
+                        let mut entry =
+                            HttpLogEntry::get(logged_url, started.elapsed(), CacheStatus::Miss);
+                        entry.method = method.to_string();
+                        entry.status = err.status().map(|status| status.as_u16());
+                        entry.error = Some(match err.url() {
+                            Some(err_url) if err_url.as_str() != logged_url => {
+                                err.to_string().replace(err_url.as_str(), logged_url)
+                            }
+                            _ => err.to_string(),
+                        });
+                        http_log.record(&entry);
+                    }
+                }
+            }
+
+            match self.retry.delay(attempt, &method, &result) {
+                Some(delay) if next.is_some() => {
+                    attempt += 1;
+                    warn!(
+                        "The request to '{}' failed, retrying in {:.1} seconds (attempt {} of {})",
+                        logged_url,
+                        delay.as_secs_f32(),
+                        attempt,
+                        self.retry.max_retries()
+                    );
+                    std::thread::sleep(delay);
+                }
+                _ => {
+                    let response = result.map_err(WebError::Request)?;
+                    return match redirect_headers {
+                        Some(headers) => self.follow_redirect(response, &method, headers),
+                        None => Ok(response),
+                    };
+                }
+            }
+        }
+    }
+
+    /// Follows the redirect to another host that the client of authenticated
+    /// requests stopped at, using a new request without the credentials of
+    /// the profile. Only `GET` and `HEAD` requests are followed, other
+    /// responses are returned unchanged.
+    fn follow_redirect(
+        &self,
+        response: Response,
+        method: &Method,
+        headers: HeaderMap,
+    ) -> Result<Response, WebError> {
+        let location = match redirect_location(&response) {
+            Some(location) if *method == Method::GET || *method == Method::HEAD => location,
+            _ => return Ok(response),
+        };
+
+        info!(
+            "Following the redirect to '{}' without the credentials of the authentication profile",
+            location.host_str().unwrap_or_default()
+        );
+        self.send(
+            self.client
+                .request(method.clone(), location.clone())
+                .headers(headers),
+            &location,
+        )
+    }
+
+    fn log_response(
+        &self,
+        url: &str,
+        method: &str,
+        started: Instant,
+        response: &Response,
+        cache: CacheStatus,
+    ) {
+        if let Some(ref http_log) = self.http_log {
+            let mut entry = HttpLogEntry::get(url, started.elapsed(), cache);
+            entry.method = method.into();
+            entry.status = Some(response.status().as_u16());
+            entry.bytes = response.content_length();
+            http_log.record(&entry);
+        }
+    }
+
+    /// Checks if the link at the specified url can still be reached, without
+    /// downloading the content at the location. A `HEAD` request is sent
+    /// first, and servers that do not allow `HEAD` requests are checked
+    /// using a `GET` request instead.
+    ///
+    /// Redirects are followed, and the returned [LinkCheck] contains the
+    /// status code and the final location of the link. Unlike the other
+    /// requests, an unsuccessful status code is not returned as an error.
+    pub fn check_link(&self, url: &str) -> Result<LinkCheck, WebError> {
+        let url = Url::parse(url).map_err(|err| WebError::Other(err.to_string()))?;
+
+        if let Some(ref fixtures) = self.fixtures {
+            if fixtures.mode() == FixtureMode::Replay {
+                let started = Instant::now();
+                let response = fixtures.load(&url)?;
+                self.log_response(
+                    url.as_str(),
+                    "HEAD",
+                    started,
+                    &response,
+                    CacheStatus::Fixture,
+                );
+                return Ok(LinkCheck::from(&response));
+            }
+        }
+
+        let _permit = self.acquire(TransferKind::Metadata);
+        let response = self.send(self.client.head(url.clone()), &url)?;
+        let response = match response.status() {
+            StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED => {
+                self.send(self.client.get(url.clone()), &url)?
+            }
+            _ => response,
+        };
+
+        Ok(LinkCheck::from(&response))
+    }
+
+    /// Makes a `HEAD` request to the specified url, and returns the metadata
+    /// of the remote file without downloading the file. Servers that do not
+    /// allow `HEAD` requests are queried using a `GET` request instead, where
+    /// only the headers of the response are read.
+    ///
+    /// When an etag or last modified date is specified, these are sent along
+    /// with the request the same way as when downloading binary files,
+    /// allowing the conditional download settings to be verified. Unlike the
+    /// other requests, an unsuccessful status code is not returned as an
+    /// error.
+    pub fn get_head_response(
+        &self,
+        url: &str,
+        etag: Option<&str>,
+        last_modified: Option<&str>,
+    ) -> Result<HeadResponse, WebError> {
+        let url = Url::parse(url).map_err(|err| WebError::Other(err.to_string()))?;
+
+        if let Some(ref fixtures) = self.fixtures {
+            if fixtures.mode() == FixtureMode::Replay {
+                let started = Instant::now();
+                let response = fixtures.load(&url)?;
+                self.log_response(
+                    url.as_str(),
+                    "HEAD",
+                    started,
+                    &response,
+                    CacheStatus::Fixture,
+                );
+                return Ok(HeadResponse::from(&response));
+            }
+        }
+
+        let headers = conditional_headers(etag, last_modified)?;
+        let _permit = self.acquire(TransferKind::Metadata);
+        let response = self.send(self.client.head(url.clone()).headers(headers.clone()), &url)?;
+        let response = match response.status() {
+            StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED => {
+                self.send(self.client.get(url.clone()).headers(headers), &url)?
+            }
+            _ => response,
+        };
+
+        Ok(HeadResponse::from(&response))
+    }
+
+    /// Makes a `GET` request to a REST API endpoint, accepting the specified
+    /// media type and optionally authorizing with the specified value. The
+    /// response is recorded to, or replayed from, the fixtures the same way as
+    /// html responses.
+    pub(crate) fn get_api_response(
+        &self,
+        url: &Url,
+        accept: &str,
+        authorization: Option<&str>,
+    ) -> Result<Response, WebError> {
+        if let Some(ref fixtures) = self.fixtures {
+            if fixtures.mode() == FixtureMode::Replay {
+                let started = Instant::now();
+                let response = fixtures.load(url)?;
+                self.log_response(
+                    url.as_str(),
+                    "GET",
+                    started,
+                    &response,
+                    CacheStatus::Fixture,
+                );
+                return Ok(response);
+            }
+        }
+
+        let request = self.client.get(url.clone()).header(header::ACCEPT, accept);
+        let request = match authorization {
+            Some(authorization) => request.header(header::AUTHORIZATION, authorization),
+            None => request,
+        };
+        let _permit = self.acquire(TransferKind::Metadata);
+        let response = self.send(request, url)?;
+
+        match self.fixtures {
+            Some(ref fixtures) => fixtures.save(url, response),
+            None => Ok(response),
+        }
+    }
+
+    pub(crate) fn acquire(&self, kind: TransferKind) -> Option<TransferPermit> {
+        self.budget.as_ref().map(|budget| budget.acquire(kind))
+    }
+
+    fn new_html_response(&self, response: Response) -> HtmlResponse {
+        HtmlResponse::new(response)
+            .with_link_types(self.link_types.clone())
+            .with_request(self.clone())
     }
 
     /// Makes a request to a web endpoint and requests a result in the type of a
@@ -127,67 +951,153 @@ impl WebRequest {
     ) -> Result<ResponseType<BinaryResponse>, WebError> {
         let url = Url::parse(url).map_err(|err| WebError::Other(err.to_string()))?;
 
-        let client = &self.client;
-        let headers = {
-            let mut headers = HeaderMap::new();
-            headers.insert(
-                header::ACCEPT,
-                HeaderValue::from_static(ACCEPTED_TYPES["binary"]),
-            );
-            if let Some(etag) = etag {
-                let new_etag = format!("\"{}\"", etag.trim_matches('"'));
-
-                headers.insert(
-                    header::IF_NONE_MATCH,
-                    HeaderValue::from_str(&new_etag)
-                        .map_err(|err| WebError::Other(err.to_string()))?,
-                );
-            }
-            if let Some(last_modified) = last_modified {
-                headers.insert(
-                    header::IF_MODIFIED_SINCE,
-                    HeaderValue::from_str(last_modified)
-                        .map_err(|err| WebError::Other(err.to_string()))?,
-                );
+        if let Some(ref fixtures) = self.fixtures {
+            if fixtures.mode() == FixtureMode::Replay {
+                return Err(WebError::Other(format!(
+                    "Binary files can not be replayed from fixtures ('{}')",
+                    url
+                )));
             }
+        }
 
-            headers
-        };
-
-        let response = client
-            .get(url.clone())
-            .headers(headers)
-            .send()
-            .map_err(WebError::Request)?;
+        let client = &self.client;
+        let headers = binary_headers(etag, last_modified)?;
+        let response = self.send(client.get(url.clone()).headers(headers), &url)?;
         let status = response.status();
 
-        if status == StatusCode::NOT_MODIFIED {
-            info!("The web server responded with status: {}!", status);
-
-            Ok(ResponseType::Updated(status.as_u16()))
+        if let Some(updated) = not_modified(status) {
+            Ok(updated)
         } else {
+            let permit = self.acquire(TransferKind::Binary(
+                response.content_length().unwrap_or_default(),
+            ));
             handle_exit_code(response, move |rsp| {
-                ResponseType::New(BinaryResponse::new(rsp, url), status.as_u16())
+                ResponseType::New(
+                    BinaryResponse::new(rsp, url)
+                        .with_permit(permit)
+                        .with_client(client.clone()),
+                    status.as_u16(),
+                )
             })
         }
     }
 }
 
-fn handle_exit_code<T, F: FnOnce(Response) -> T>(
-    response: Response,
+/// The redirect policy of the client used for authenticated requests, which
+/// stops at the first redirect to another host (or port) than the host the
+/// request was sent to. This prevents the credentials of a profile, including
+/// custom headers, from being sent to other hosts.
+fn same_host_redirects() -> Policy {
+    Policy::custom(|attempt| {
+        let origin = |url: &Url| {
+            (
+                url.host_str().map(str::to_owned),
+                url.port_or_known_default(),
+            )
+        };
+        let same_host = attempt
+            .previous()
+            .iter()
+            .all(|previous| origin(previous) == origin(attempt.url()));
+
+        if !same_host {
+            attempt.stop()
+        } else if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error("too many redirects")
+        } else {
+            attempt.follow()
+        }
+    })
+}
+
+/// Returns the location the response redirects to, when the response is a
+/// redirect.
+fn redirect_location(response: &Response) -> Option<Url> {
+    let status = response.status();
+    if !status.is_redirection() || status == StatusCode::NOT_MODIFIED {
+        return None;
+    }
+
+    let location = response.headers().get(header::LOCATION)?.to_str().ok()?;
+    response.url().join(location).ok()
+}
+
+/// Returns the scheme and host of the url, used when logging urls that may
+/// contain secrets in the path or query.
+pub(crate) fn redacted(url: &Url) -> String {
+    format!("{}://{}", url.scheme(), url.host_str().unwrap_or_default())
+}
+
+/// Creates the headers used when requesting a binary file, including the
+/// conditional headers when an etag or last modified date is specified.
+pub(crate) fn binary_headers(
+    etag: Option<&str>,
+    last_modified: Option<&str>,
+) -> Result<HeaderMap, WebError> {
+    let mut headers = conditional_headers(etag, last_modified)?;
+    headers.insert(
+        header::ACCEPT,
+        HeaderValue::from_static(ACCEPTED_TYPES["binary"]),
+    );
+
+    Ok(headers)
+}
+
+/// Returns [ResponseType::Updated] when the server responded that the
+/// requested file have not been modified.
+pub(crate) fn not_modified<T>(status: StatusCode) -> Option<ResponseType<T>> {
+    if status == StatusCode::NOT_MODIFIED {
+        info!("The web server responded with status: {}!", status);
+        Some(ResponseType::Updated(status.as_u16()))
+    } else {
+        None
+    }
+}
+
+/// The status of a response, implemented by both the blocking and the
+/// asynchronous responses so the status is handled the same way.
+pub(crate) trait ResponseStatus: Sized {
+    /// Returns the status code of the response.
+    fn status_code(&self) -> StatusCode;
+
+    /// Returns the error of the response, if the status is not successful.
+    fn into_error(self) -> Option<reqwest::Error>;
+}
+
+impl ResponseStatus for Response {
+    fn status_code(&self) -> StatusCode {
+        self.status()
+    }
+
+    fn into_error(self) -> Option<reqwest::Error> {
+        self.error_for_status().err()
+    }
+}
+
+#[cfg(feature = "async")]
+impl ResponseStatus for reqwest::Response {
+    fn status_code(&self) -> StatusCode {
+        self.status()
+    }
+
+    fn into_error(self) -> Option<reqwest::Error> {
+        self.error_for_status().err()
+    }
+}
+
+pub(crate) fn handle_exit_code<R: ResponseStatus, T, F: FnOnce(R) -> T>(
+    response: R,
     creation: F,
 ) -> Result<T, WebError> {
-    if !response.status().is_success() {
-        return match response.error_for_status() {
-            Err(err) => Err(WebError::Request(err)),
-            Ok(_) => unreachable!(),
+    let status = response.status_code();
+    if !status.is_success() {
+        return match response.into_error() {
+            Some(err) => Err(WebError::Request(err)),
+            None => unreachable!(),
         };
     }
 
-    info!(
-        "The web server responded with status: {}!",
-        response.status()
-    );
+    info!("The web server responded with status: {}!", status);
 
     Ok(creation(response))
 }
@@ -195,9 +1105,41 @@ fn handle_exit_code<T, F: FnOnce(Response) -> T>(
 #[cfg(test)]
 mod tests {
     use reqwest::StatusCode;
+    use rstest::rstest;
+    use static_assertions::assert_impl_all;
 
     use super::*;
     use crate::response::*;
+    use crate::{NextPage, Pagination};
+
+    assert_impl_all!(WebRequest: Send, Sync, Clone);
+
+    #[test]
+    fn build_should_load_root_certificates() {
+        let actual = WebRequest::builder()
+            .without_proxy()
+            .with_root_certificates("test-data/root-ca.pem")
+            .with_root_certificates("test-data/root-ca.der")
+            .build();
+
+        assert!(actual.is_ok());
+    }
+
+    #[test]
+    fn build_should_return_error_on_missing_root_certificates() {
+        let actual = WebRequest::builder()
+            .with_root_certificates("test-data/non-existing.pem")
+            .build();
+
+        assert!(matches!(actual, Err(WebError::IoError(_))));
+    }
+
+    #[test]
+    fn load_certificates_should_split_pem_bundles() {
+        let actual = load_certificates(Path::new("test-data/root-ca.pem")).unwrap();
+
+        assert_eq!(actual.len(), 2);
+    }
 
     #[test]
     fn create_should_build_client_with_expected_values() {
@@ -207,6 +1149,194 @@ mod tests {
         // not expect.
     }
 
+    #[test]
+    fn get_html_response_should_record_replayed_fixtures_to_http_log() {
+        let path = std::env::temp_dir().join("aer-web-http-log-fixture.jsonl");
+        let _ = std::fs::remove_file(&path);
+        let request = WebRequest::create()
+            .with_fixtures(Fixtures::new(
+                "../aer/test-data/simulate",
+                FixtureMode::Replay,
+            ))
+            .with_http_log(HttpLog::create(&path).unwrap());
+
+        let _ = request
+            .get_html_response("https://example.org/releases")
+            .unwrap();
+
+        let actual = std::fs::read_to_string(&path).unwrap();
+        assert!(actual.contains("\"url\":\"https://example.org/releases\""));
+        assert!(actual.contains("\"status\":200"));
+        assert!(actual.contains("\"cache\":\"fixture\""));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn get_json_response_should_read_links_from_replayed_fixture() {
+        let dir = std::env::temp_dir().join("aer-web-json-fixture");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let fixtures = Fixtures::new(&dir, FixtureMode::Replay);
+        let url = Url::parse("https://api.example.org/releases/latest").unwrap();
+        std::fs::write(
+            fixtures.path_for(&url),
+            serde_json::json!({
+                "url": url.as_str(),
+                "final_url": url.as_str(),
+                "status": 200,
+                "headers": { "content-type": "application/json" },
+                "body": r#"{"version":"1.2.0","files":[{"url":"/files/app-1.2.0.msi"}]}"#
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let request = WebRequest::create().with_fixtures(fixtures);
+
+        let (parent, links) = request
+            .get_json_response(url.as_str())
+            .unwrap()
+            .read_links("files[*].url", Some("version"))
+            .unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(parent.version.unwrap().to_string(), "1.2.0");
+        assert_eq!(links.len(), 1);
+        assert_eq!(
+            links[0].link.as_str(),
+            "https://api.example.org/files/app-1.2.0.msi"
+        );
+        assert!(links[0].is_binary());
+    }
+
+    #[rstest(max_pages, expected, case(5, 3), case(2, 2), case(1, 1))]
+    fn get_html_response_should_follow_next_pages_from_replayed_fixtures(
+        max_pages: usize,
+        expected: usize,
+    ) {
+        let dir = std::env::temp_dir().join(format!("aer-web-pagination-{}", max_pages));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let fixtures = Fixtures::new(&dir, FixtureMode::Replay);
+        let pages = [
+            (
+                1,
+                r#"<a href="/files/app-3.0.0.exe">3.0.0</a><a rel="next" href="?page=2">Next</a>"#,
+            ),
+            (
+                2,
+                r#"<a href="/files/app-2.0.0.exe">2.0.0</a><a rel="next" href="?page=3">Next</a>"#,
+            ),
+            // The last page links back to the first page, which should not be read again
+            (
+                3,
+                r#"<a href="/files/app-1.0.0.exe">1.0.0</a><a rel="next" href="?page=1">Next</a>"#,
+            ),
+        ];
+        for (page, body) in pages.iter() {
+            let url = Url::parse(&format!("https://example.org/releases?page={}", page)).unwrap();
+            std::fs::write(
+                fixtures.path_for(&url),
+                serde_json::json!({
+                    "url": url.as_str(),
+                    "final_url": url.as_str(),
+                    "status": 200,
+                    "headers": { "content-type": "text/html" },
+                    "body": body
+                })
+                .to_string(),
+            )
+            .unwrap();
+        }
+        let request = WebRequest::create().with_fixtures(fixtures);
+
+        let (parent, links) = request
+            .get_html_response("https://example.org/releases?page=1")
+            .unwrap()
+            .with_pagination(Pagination::new(NextPage::Rel, max_pages))
+            .read(Some(r"app-(?P<version>[\d\.]+)\.exe$"))
+            .unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(parent.link.as_str(), "https://example.org/releases?page=1");
+        assert_eq!(
+            links
+                .iter()
+                .map(|link| link.version.as_ref().unwrap().to_string())
+                .collect::<Vec<_>>(),
+            ["3.0.0", "2.0.0", "1.0.0"][..expected]
+        );
+    }
+
+    #[test]
+    fn check_link_should_return_status_of_replayed_fixture() {
+        let request = WebRequest::create().with_fixtures(Fixtures::new(
+            "../aer/test-data/simulate",
+            FixtureMode::Replay,
+        ));
+
+        let actual = request.check_link("https://example.org/releases").unwrap();
+
+        assert!(actual.is_success());
+        assert_eq!(actual.url.as_str(), "https://example.org/releases");
+    }
+
+    #[test]
+    fn get_head_response_should_return_headers_of_replayed_fixture() {
+        let request = WebRequest::create().with_fixtures(Fixtures::new(
+            "../aer/test-data/simulate",
+            FixtureMode::Replay,
+        ));
+
+        let actual = request
+            .get_head_response("https://example.org/releases", None, None)
+            .unwrap();
+
+        assert!(actual.is_success());
+        assert!(!actual.is_not_modified());
+        assert_eq!(actual.url.as_str(), "https://example.org/releases");
+        assert_eq!(
+            actual.content_type.as_deref(),
+            Some("text/html; charset=utf-8")
+        );
+    }
+
+    #[rstest(
+        etag,
+        last_modified,
+        expected_etag,
+        expected_modified,
+        case(None, None, None, None),
+        case(Some("abcdef"), None, Some("\"abcdef\""), None),
+        case(Some("\"abcdef\""), None, Some("\"abcdef\""), None),
+        case(
+            None,
+            Some("Mon, 29 Mar 2021 14:28:12 GMT"),
+            None,
+            Some("Mon, 29 Mar 2021 14:28:12 GMT")
+        )
+    )]
+    fn conditional_headers_should_set_matching_headers(
+        etag: Option<&str>,
+        last_modified: Option<&str>,
+        expected_etag: Option<&str>,
+        expected_modified: Option<&str>,
+    ) {
+        let actual = conditional_headers(etag, last_modified).unwrap();
+
+        assert_eq!(
+            actual
+                .get(header::IF_NONE_MATCH)
+                .map(|value| value.to_str().unwrap()),
+            expected_etag
+        );
+        assert_eq!(
+            actual
+                .get(header::IF_MODIFIED_SINCE)
+                .map(|value| value.to_str().unwrap()),
+            expected_modified
+        );
+    }
+
     #[test]
     fn get_html_response_should_create_response() {
         let url = Url::parse("https://httpbin.org/get").unwrap();
@@ -272,6 +1402,80 @@ mod tests {
         assert_eq!(response.response().url(), &final_url);
     }
 
+    /// Starts a server that answers a single request with the specified
+    /// response, and sends the headers of the received request back over the
+    /// returned channel.
+    fn http_server(response: String) -> (u16, std::sync::mpsc::Receiver<String>) {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![];
+            let mut buffer = [0; 1024];
+            while !String::from_utf8_lossy(&request).contains("\r\n\r\n") {
+                let read = stream.read(&mut buffer).unwrap();
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..read]);
+            }
+            let _ = stream.write_all(response.as_bytes());
+            let _ = sender.send(String::from_utf8_lossy(&request).to_lowercase());
+        });
+
+        (port, receiver)
+    }
+
+    #[test]
+    fn get_html_response_should_not_send_credentials_of_profile_when_redirected_to_other_host() {
+        let (target_port, target) = http_server(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 0\r\nConnection: \
+             close\r\n\r\n"
+                .into(),
+        );
+        let (port, origin) = http_server(format!(
+            "HTTP/1.1 302 Found\r\nLocation: http://localhost:{}/releases\r\nContent-Length: \
+             0\r\nConnection: close\r\n\r\n",
+            target_port
+        ));
+        let mut secrets = crate::auth::Secrets {
+            token: Some("secret-token".into()),
+            ..Default::default()
+        };
+        secrets
+            .headers
+            .insert("X-Api-Key".into(), "secret-key".into());
+        let profile = crate::auth::AuthProfile::new(
+            "internal",
+            Url::parse(&format!("http://127.0.0.1:{}/", port)).unwrap(),
+        )
+        .with_secrets(secrets);
+        let request = WebRequest::create().with_auth_profiles(
+            AuthProfiles::new()
+                .with_profile(profile)
+                .with_rule("*", "internal"),
+        );
+
+        let response = request
+            .get_html_response(&format!("http://127.0.0.1:{}/releases", port))
+            .unwrap();
+        let origin = origin.recv().unwrap();
+        let target = target.recv().unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.response().url().as_str(),
+            format!("http://localhost:{}/releases", target_port)
+        );
+        assert!(origin.contains("x-api-key: secret-key"));
+        assert!(origin.contains("authorization: bearer secret-token"));
+        assert!(!target.contains("secret-key"));
+        assert!(!target.contains("secret-token"));
+    }
+
     #[test]
     #[cfg_attr(
         not(windows),
@@ -6,34 +6,32 @@
 
 /// Contains code related to handling binary responses (normally downloading).
 mod binary;
+/// Contains code related to handling RSS and Atom feed responses.
+mod feed;
 /// Contains code related to handling html responses.
 mod html;
+/// Contains code related to handling JSON responses.
+mod json;
+/// Contains code related to handling XML responses.
+mod xml;
 
 use std::collections::HashMap;
 use std::path::Path;
 
+#[cfg(feature = "async")]
+pub(crate) use binary::get_file_name;
 pub use binary::BinaryResponse;
+pub use feed::FeedResponse;
+#[cfg(feature = "async")]
+pub(crate) use html::parse_html;
 pub use html::HtmlResponse;
-use lazy_static::lazy_static;
+pub use json::JsonResponse;
 use reqwest::blocking::Response;
-use reqwest::StatusCode;
+use reqwest::{header, StatusCode};
+pub use xml::XmlResponse;
 
-use crate::elements::LinkType;
 use crate::errors::WebError;
 
-lazy_static! {
-    static ref MIME_TYPES: HashMap<&'static str, LinkType> = {
-        let mut map = HashMap::new();
-        map.insert("text/html", LinkType::Html);
-        map.insert("text/plain", LinkType::Text);
-        map.insert("text/json", LinkType::Json);
-        map.insert("application/json", LinkType::Json);
-        map.insert("text/css", LinkType::Css);
-        map.insert("application/octet-stream", LinkType::Binary);
-        map
-    };
-}
-
 /// A simple enumerator that holds information of wether the response returned
 /// by a server said the content is up to date, or if there is new content
 /// available.
@@ -43,7 +41,7 @@ lazy_static! {
 /// - Calling any child response may panic if a function is called, and the
 ///   server returned an not modified response.
 #[derive(Debug, PartialEq)]
-pub enum ResponseType<T: WebResponse> {
+pub enum ResponseType<T> {
     /// The response returned by the server was considered up to date, and no
     /// further processing is available. Sets the server status code as a
     /// member.
@@ -97,6 +95,13 @@ impl ResponseType<BinaryResponse> {
 ///   generally for aquiring links on a web page_.
 /// - [BinaryResponse](BinaryResponse): _Responsible for downloading a remote
 ///   file to a specified location_
+/// - [FeedResponse](FeedResponse): _Responsible of parsing RSS and Atom feeds,
+///   generally for aquiring the links of the published releases_.
+/// - [JsonResponse](JsonResponse): _Responsible of parsing JSON documents,
+///   generally for aquiring versions and links from an API_.
+/// - [XmlResponse](XmlResponse): _Responsible of parsing XML documents,
+///   generally for aquiring versions and links from appcasts or update
+///   manifests_.
 pub trait WebResponse {
     /// The response content that will be returned by any implementation of
     /// [WebResponse]. This can be anything that would be expected by the
@@ -123,6 +128,15 @@ pub trait WebResponse {
         headers
     }
 
+    /// Returns the language of the content that the web server negotiated
+    /// based on the `Accept-Language` header, if the server reported it.
+    fn content_language(&self) -> Option<&str> {
+        self.response()
+            .headers()
+            .get(header::CONTENT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+    }
+
     /// Returns the status that was returned with the rest of the response.
     fn status(&self) -> StatusCode {
         self.response().status()
@@ -1,19 +1,29 @@
 // Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
 // Licensed under the MIT license. See LICENSE.txt file in the project
 
-use std::fs::File;
+use std::fs::{File, OpenOptions};
 use std::io::BufWriter;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use log::{info, warn};
-use reqwest::blocking::Response;
+use reqwest::blocking::{Client, Response};
 use reqwest::header::{HeaderMap, HeaderValue};
-use reqwest::{header, Url};
+use reqwest::{header, StatusCode, Url};
 
+use crate::budget::TransferPermit;
+use crate::checksum::{ChecksumType, ChecksumWriter};
+use crate::request::ACCEPTED_TYPES;
 use crate::response::WebError;
 use crate::WebResponse;
 
+/// The extension appended to the name of a file while it is being downloaded.
+const PARTIAL_EXTENSION: &str = "part";
+
+/// The extension appended to the name of a file while it is being downloaded,
+/// for the file holding the etag or last modified date of the partial file.
+const VALIDATOR_EXTENSION: &str = "part-validator";
+
 /// Contains functions and items necessary for parsing and downloading binary
 /// files.
 ///
@@ -24,6 +34,8 @@ pub struct BinaryResponse {
     response: Response,
     url: Url,
     work_dir: PathBuf,
+    permit: Option<TransferPermit>,
+    client: Option<Client>,
 }
 
 impl PartialEq for BinaryResponse {
@@ -41,9 +53,25 @@ impl BinaryResponse {
             response,
             url,
             work_dir: PathBuf::new(),
+            permit: None,
+            client: None,
         }
     }
 
+    /// Sets the client used when resuming a partially downloaded file,
+    /// downloads are never resumed when no client have been set.
+    pub(crate) fn with_client(mut self, client: Client) -> BinaryResponse {
+        self.client = Some(client);
+        self
+    }
+
+    /// Sets the permit reserving the budget for downloading the file, the
+    /// budget is released when the response is downloaded or dropped.
+    pub(crate) fn with_permit(mut self, permit: Option<TransferPermit>) -> BinaryResponse {
+        self.permit = permit;
+        self
+    }
+
     /// Sets the current work directory (the directory where files will be
     /// downloaded). If this function is never called, the current directory
     /// (based on the execution location of the program) will be used. As such,
@@ -55,14 +83,36 @@ impl BinaryResponse {
     /// Tries to get the name of the remote file by either reading the
     /// disposition header, or checking the url if it contains an extension.
     pub fn file_name(&self) -> Option<String> {
-        if let Some(name) = get_from_disposition(self.response.headers()) {
-            Some(name)
-        } else if let Some(name) = get_from_url(self.response.url()) {
-            Some(name)
-        } else {
-            None
-        }
+        get_file_name(self.response.headers(), self.response.url())
+    }
+
+    /// Returns the etag the server responded with, if any.
+    pub fn etag(&self) -> Option<&str> {
+        self.response
+            .headers()
+            .get(header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
     }
+
+    /// Returns the last modified date the server responded with, if any.
+    pub fn last_modified(&self) -> Option<&str> {
+        self.response
+            .headers()
+            .get(header::LAST_MODIFIED)
+            .and_then(|date| date.to_str().ok())
+    }
+
+    /// Returns the url the file is downloaded from, after any redirects have
+    /// been followed.
+    pub fn final_url(&self) -> &Url {
+        self.response.url()
+    }
+}
+
+/// Tries to get the name of a remote file from the disposition header, or
+/// from the url if it contains an extension.
+pub(crate) fn get_file_name(headers: &HeaderMap<HeaderValue>, url: &Url) -> Option<String> {
+    get_from_disposition(headers).or_else(|| get_from_url(url))
 }
 
 fn get_from_url(url: &Url) -> Option<String> {
@@ -142,6 +192,55 @@ impl WebResponse for BinaryResponse {
     /// The `output` argument will be combined with the previously set work
     /// directory.
     fn read(self, output: Option<&str>) -> Result<Self::ResponseContent, WebError> {
+        self.download(output, None).map(|(path, _)| path)
+    }
+}
+
+impl BinaryResponse {
+    /// Downloads the response content, while generating the checksum of the
+    /// content using the specified algorithm. This avoids reading the file
+    /// again after it have been downloaded.
+    ///
+    /// The `output` argument is handled the same way as when calling
+    /// [read](WebResponse::read).
+    pub fn read_with_checksum(
+        self,
+        output: Option<&str>,
+        checksum_type: ChecksumType,
+    ) -> Result<(PathBuf, String), WebError> {
+        let (path, checksum) = self.download(output, Some(checksum_type))?;
+
+        Ok((path, checksum.unwrap_or_default()))
+    }
+
+    /// Downloads the response content, and verifies that the checksum of the
+    /// content matches the expected checksum. The downloaded file is removed
+    /// and a [WebError::ChecksumMismatch] is returned when the checksums do
+    /// not match.
+    pub fn read_verified(
+        self,
+        output: Option<&str>,
+        checksum_type: ChecksumType,
+        expected: &str,
+    ) -> Result<PathBuf, WebError> {
+        let (path, actual) = self.read_with_checksum(output, checksum_type)?;
+
+        if actual.eq_ignore_ascii_case(expected.trim()) {
+            Ok(path)
+        } else {
+            let _ = std::fs::remove_file(&path);
+            Err(WebError::ChecksumMismatch {
+                expected: expected.trim().to_lowercase(),
+                actual,
+            })
+        }
+    }
+
+    fn download(
+        self,
+        output: Option<&str>,
+        checksum_type: Option<ChecksumType>,
+    ) -> Result<(PathBuf, Option<String>), WebError> {
         let output = if let Some(output) = output {
             output.into()
         } else {
@@ -150,27 +249,156 @@ impl WebResponse for BinaryResponse {
         };
 
         let output = self.work_dir.join(output);
+        let partial = with_extension(&output, PARTIAL_EXTENSION);
+        let validator_path = with_extension(&output, VALIDATOR_EXTENSION);
+        let validator = get_validator(self.response.headers());
 
+        let url = &self.url;
         let mut response = self.response;
+        let mut offset = 0;
+
+        if let Some((resumed, resumed_offset)) = self
+            .client
+            .as_ref()
+            .and_then(|client| resume(client, url, &partial, &validator_path))
+        {
+            response = resumed;
+            offset = resumed_offset;
+        }
 
-        info!("Downloading '{}' to '{}'", self.url, output.display());
+        if offset > 0 {
+            info!(
+                "Resuming download of '{}' to '{}' from byte {}",
+                self.url,
+                output.display(),
+                offset
+            );
+        } else {
+            info!("Downloading '{}' to '{}'", self.url, output.display());
+
+            match validator {
+                Some(validator) => {
+                    std::fs::write(&validator_path, validator).map_err(WebError::IoError)?
+                }
+                None => {
+                    let _ = std::fs::remove_file(&validator_path);
+                }
+            }
+        }
 
-        let file = File::create(output.clone()).map_err(WebError::IoError)?;
-        let mut writer = BufWriter::new(&file);
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(offset > 0)
+            .truncate(offset == 0)
+            .open(&partial)
+            .map_err(WebError::IoError)?;
+        let writer = BufWriter::new(&file);
+        let result = match checksum_type {
+            Some(checksum_type) => {
+                let mut writer = ChecksumWriter::new(writer, checksum_type);
+                if offset > 0 {
+                    let mut existing = File::open(&partial).map_err(WebError::IoError)?;
+                    writer
+                        .digest_existing(&mut existing)
+                        .map_err(WebError::IoError)?;
+                }
+                response
+                    .copy_to(&mut writer)
+                    .map(|_| Some(writer.finish().1))
+            }
+            None => {
+                let mut writer = writer;
+                response.copy_to(&mut writer).map(|_| None)
+            }
+        };
 
-        match response.copy_to(&mut writer) {
+        match result {
             Err(err) => {
                 warn!("Failed to download '{}'", self.url);
                 Err(WebError::Request(err))
             }
-            Ok(_) => {
+            Ok(checksum) => {
+                drop(file);
+                std::fs::rename(&partial, &output).map_err(WebError::IoError)?;
+                let _ = std::fs::remove_file(&validator_path);
                 info!("Successfully downloaded '{}'", output.display());
-                Ok(output)
+                Ok((output, checksum))
             }
         }
     }
 }
 
+/// Tries to resume the download of a partially downloaded file, by sending a
+/// range request for the remaining bytes together with the etag or last
+/// modified date the partial file was downloaded with. The new response is
+/// returned together with the offset to continue at, an offset of `0` means
+/// the server responded with the whole file.
+fn resume(
+    client: &Client,
+    url: &Url,
+    partial: &Path,
+    validator_path: &Path,
+) -> Option<(Response, u64)> {
+    let offset = std::fs::metadata(partial).ok()?.len();
+    let validator = std::fs::read_to_string(validator_path).ok()?;
+
+    if offset == 0 || validator.is_empty() {
+        return None;
+    }
+
+    let response = client
+        .get(url.clone())
+        .header(header::ACCEPT, ACCEPTED_TYPES["binary"])
+        .header(header::RANGE, format!("bytes={}-", offset))
+        .header(header::IF_RANGE, validator)
+        .send()
+        .ok()?;
+
+    match response.status() {
+        StatusCode::PARTIAL_CONTENT if starts_at(response.headers(), offset) => {
+            Some((response, offset))
+        }
+        status if status.is_success() => Some((response, 0)),
+        _ => None,
+    }
+}
+
+/// Returns the etag of the response, or the last modified date when the
+/// server did not respond with a strong etag. Weak etags can not be used when
+/// resuming downloads.
+fn get_validator(headers: &HeaderMap<HeaderValue>) -> Option<String> {
+    headers
+        .get(header::ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| {
+            headers
+                .get(header::LAST_MODIFIED)
+                .and_then(|date| date.to_str().ok())
+        })
+        .map(String::from)
+}
+
+/// Checks that the content range of a partial response starts at the
+/// specified offset (*ie: `bytes 1024-2047/2048`*).
+fn starts_at(headers: &HeaderMap<HeaderValue>, offset: u64) -> bool {
+    headers
+        .get(header::CONTENT_RANGE)
+        .and_then(|range| range.to_str().ok())
+        .and_then(|range| range.trim_start_matches("bytes ").split('-').next())
+        .and_then(|start| start.trim().parse::<u64>().ok())
+        == Some(offset)
+}
+
+fn with_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+
+    PathBuf::from(path)
+}
+
 #[cfg(test)]
 mod tests {
     use reqwest::{header, Url};
@@ -275,4 +503,128 @@ mod tests {
 
         let _ = std::fs::remove_file(expected);
     }
+
+    #[rstest(
+        etag,
+        last_modified,
+        expected,
+        case(Some("\"abc\""), None, Some("\"abc\"")),
+        case(
+            Some("W/\"abc\""),
+            Some("Tue, 16 Feb 2021 03:33:36 GMT"),
+            Some("Tue, 16 Feb 2021 03:33:36 GMT")
+        ),
+        case(
+            None,
+            Some("Tue, 16 Feb 2021 03:33:36 GMT"),
+            Some("Tue, 16 Feb 2021 03:33:36 GMT")
+        ),
+        case(Some("W/\"abc\""), None, None)
+    )]
+    fn get_validator_should_prefer_strong_etag(
+        etag: Option<&'static str>,
+        last_modified: Option<&'static str>,
+        expected: Option<&str>,
+    ) {
+        let mut headers = HeaderMap::new();
+        if let Some(etag) = etag {
+            headers.insert(header::ETAG, HeaderValue::from_static(etag));
+        }
+        if let Some(last_modified) = last_modified {
+            headers.insert(
+                header::LAST_MODIFIED,
+                HeaderValue::from_static(last_modified),
+            );
+        }
+
+        let validator = get_validator(&headers);
+
+        assert_eq!(validator.as_deref(), expected);
+    }
+
+    #[rstest(
+        range,
+        expected,
+        case("bytes 1024-2047/2048", true),
+        case("bytes 0-2047/2048", false),
+        case("invalid", false)
+    )]
+    fn starts_at_should_compare_start_of_content_range(range: &'static str, expected: bool) {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_RANGE, HeaderValue::from_static(range));
+
+        assert_eq!(starts_at(&headers, 1024), expected);
+    }
+
+    #[test]
+    fn with_extension_should_append_extension() {
+        let path = with_extension(Path::new("downloads/app-1.0.0.exe"), PARTIAL_EXTENSION);
+
+        assert_eq!(path, PathBuf::from("downloads/app-1.0.0.exe.part"));
+    }
+
+    #[test]
+    fn read_should_resume_partially_downloaded_file() {
+        let work_dir = std::env::temp_dir().join("aer-resume-download-test");
+        let _ = std::fs::remove_dir_all(&work_dir);
+        std::fs::create_dir_all(&work_dir).unwrap();
+        let expected: Vec<u8> = (0..1024u32).map(|i| b'a' + (i % 26) as u8).collect();
+        std::fs::write(work_dir.join("range.bin.part"), &expected[..512]).unwrap();
+        std::fs::write(work_dir.join("range.bin.part-validator"), "range1024").unwrap();
+        let request = WebRequest::create();
+        let mut response = request
+            .get_binary_response("https://httpbin.org/range/1024", None, None)
+            .unwrap();
+        response.set_work_dir(&work_dir);
+
+        let path = response.read(Some("range.bin")).unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), expected);
+        assert!(!work_dir.join("range.bin.part").exists());
+        let _ = std::fs::remove_dir_all(&work_dir);
+    }
+
+    fn local_response(body: &'static str) -> BinaryResponse {
+        let response = http::Response::builder().body(body).unwrap();
+
+        BinaryResponse::new(
+            Response::from(response),
+            Url::parse("https://example.org/test.txt").unwrap(),
+        )
+    }
+
+    #[test]
+    fn read_with_checksum_should_return_checksum_of_downloaded_file() {
+        let work_dir = std::env::temp_dir().join("aer-read-checksum-test");
+        std::fs::create_dir_all(&work_dir).unwrap();
+        let mut response = local_response("aer");
+        response.set_work_dir(&work_dir);
+
+        let (path, checksum) = response
+            .read_with_checksum(Some("checksum.txt"), ChecksumType::Sha256)
+            .unwrap();
+
+        assert_eq!(checksum, ChecksumType::Sha256.generate(&path).unwrap());
+        let _ = std::fs::remove_dir_all(&work_dir);
+    }
+
+    #[test]
+    fn read_verified_should_return_mismatch_error_and_remove_file() {
+        let work_dir = std::env::temp_dir().join("aer-read-verified-test");
+        std::fs::create_dir_all(&work_dir).unwrap();
+        let mut response = local_response("aer");
+        response.set_work_dir(&work_dir);
+
+        let result = response.read_verified(Some("verified.txt"), ChecksumType::Md5, "ABC");
+
+        match result {
+            Err(WebError::ChecksumMismatch { expected, actual }) => {
+                assert_eq!(expected, "abc");
+                assert_eq!(actual.len(), 32);
+            }
+            _ => panic!("Expected a checksum mismatch"),
+        }
+        assert!(!work_dir.join("verified.txt").exists());
+        let _ = std::fs::remove_dir_all(&work_dir);
+    }
 }
@@ -0,0 +1,331 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+use aer_version::Versions;
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::blocking::Response;
+use reqwest::Url;
+
+use crate::response::WebError;
+use crate::sources::parse_text_version;
+use crate::xml::decode;
+use crate::{LinkElement, LinkType, LinkTypeMap, WebResponse};
+
+lazy_static! {
+    static ref ENTRY_RE: Regex =
+        Regex::new(r"(?s)<(item|entry)(?:\s[^>]*)?>(.*?)</(?:item|entry)>").unwrap();
+    static ref LINK_TAG_RE: Regex = Regex::new(r"(?s)<(link|enclosure)\s([^>]*?)/?>").unwrap();
+    static ref ATTRIBUTE_RE: Regex =
+        Regex::new(r#"([\w:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+}
+
+/// Contains functions and structure for holding a single RSS or Atom feed
+/// response, and extracting the entries of the feed as link elements.
+///
+/// Implements the [WebResponse] trait, and are not meant to be created directly
+/// by a user.
+#[derive(Debug)]
+pub struct FeedResponse {
+    response: Response,
+    link_types: LinkTypeMap,
+}
+
+impl FeedResponse {
+    /// Creates a new instance of the [FeedResponse] structure to hold the
+    /// current response, and allow reading the entries from that response.
+    pub fn new(response: Response) -> FeedResponse {
+        FeedResponse {
+            response,
+            link_types: LinkTypeMap::default(),
+        }
+    }
+
+    /// Sets the mappings that should be used to decide the type of the links
+    /// that are found in the feed.
+    pub fn with_link_types(mut self, link_types: LinkTypeMap) -> FeedResponse {
+        self.link_types = link_types;
+        self
+    }
+}
+
+impl WebResponse for FeedResponse {
+    /// Sets the response type that will be returned when calling the
+    /// [read](FeedResponse::read) function. The first item is the link the
+    /// response came from, and the second item holds the links of every entry
+    /// in the feed, in the order they appear in the feed.
+    type ResponseContent = (LinkElement, Vec<LinkElement>);
+
+    fn response(&self) -> &Response {
+        &self.response
+    }
+
+    /// Reads the current response, and creates a link element for the link
+    /// and the enclosures of every entry in the feed. When a regex is
+    /// specified, only entries with a title matching the regex are returned
+    /// and the version is parsed from the `version` group of the regex.
+    /// Otherwise the version is parsed from the first word of the title
+    /// containing a digit, ignoring any prefix before the first digit. This
+    /// function will return an error if the body could not be read, or the
+    /// regex is not valid.
+    fn read(self, re: Option<&str>) -> Result<Self::ResponseContent, WebError> {
+        let url = self.response.url().clone();
+        let body = self.response.text().map_err(WebError::Request)?;
+
+        parse_feed(url, &body, re, &self.link_types)
+    }
+}
+
+/// Parses the entries of an RSS (*`<item>`*) or Atom (*`<entry>`*) feed, and
+/// returns the link the feed came from together with the links of the
+/// entries.
+fn parse_feed(
+    url: Url,
+    body: &str,
+    re: Option<&str>,
+    link_types: &LinkTypeMap,
+) -> Result<(LinkElement, Vec<LinkElement>), WebError> {
+    let re = match re {
+        Some(re) => Some(Regex::new(re).map_err(|err| WebError::Other(err.to_string()))?),
+        None => None,
+    };
+    let mut links = vec![];
+
+    for entry in ENTRY_RE.captures_iter(body) {
+        let entry = &entry[2];
+        let title = element(entry, "title").unwrap_or_default();
+        let version = match re {
+            Some(ref re) => match re.captures(&title) {
+                Some(capture) => capture
+                    .name("version")
+                    .and_then(|version| Versions::parse(version.as_str()).ok()),
+                None => continue,
+            },
+            None => parse_text_version(&title),
+        };
+        let published_at = element(entry, "pubDate")
+            .or_else(|| element(entry, "published"))
+            .or_else(|| element(entry, "updated"));
+        let id = element(entry, "guid").or_else(|| element(entry, "id"));
+
+        for (href, kind) in entry_links(entry) {
+            let href = match url.join(&href) {
+                Ok(href) => href,
+                Err(_) => continue,
+            };
+            let mut link = LinkElement::new(href.clone(), link_types.from_path(href.path()));
+            link.title = title.clone();
+            link.text = title.clone();
+            link.version = version.clone();
+            link.attributes.insert("feed-link".into(), kind.into());
+            if let Some(ref published_at) = published_at {
+                link.attributes
+                    .insert("published-at".into(), published_at.clone());
+            }
+            if let Some(ref id) = id {
+                link.attributes.insert("id".into(), id.clone());
+            }
+            links.push(link);
+        }
+    }
+
+    Ok((LinkElement::new(url, LinkType::Text), links))
+}
+
+/// Returns the links of an entry together with the kind of the link, which
+/// is either `alternate` for the page of the entry or `enclosure` for the
+/// files attached to the entry.
+fn entry_links(entry: &str) -> Vec<(String, &'static str)> {
+    let mut links = vec![];
+
+    // RSS feeds holds the link as the content of the element.
+    if let Some(link) = element(entry, "link").filter(|link| !link.is_empty()) {
+        links.push((link, "alternate"));
+    }
+
+    // Atom feeds holds the link in the attributes, as do the RSS enclosures.
+    for tag in LINK_TAG_RE.captures_iter(entry) {
+        let attributes = &tag[2];
+        let attribute = |name: &str| {
+            ATTRIBUTE_RE
+                .captures_iter(attributes)
+                .find(|attr| attr[1].eq_ignore_ascii_case(name))
+                .and_then(|attr| attr.get(2).or_else(|| attr.get(3)))
+                .map(|value| decode(value.as_str()))
+        };
+
+        let (href, kind) = if &tag[1] == "enclosure" {
+            (attribute("url"), "enclosure")
+        } else {
+            match attribute("rel").as_deref() {
+                Some("enclosure") => (attribute("href"), "enclosure"),
+                None | Some("alternate") => (attribute("href"), "alternate"),
+                Some(_) => continue,
+            }
+        };
+
+        if let Some(href) = href.filter(|href| !href.is_empty()) {
+            links.push((href, kind));
+        }
+    }
+
+    links
+}
+
+/// Returns the decoded text of the first element with the specified name,
+/// self-closing elements are ignored.
+fn element(text: &str, name: &str) -> Option<String> {
+    let re = Regex::new(&format!(
+        r"(?s)<{0}(?:\s[^>]*[^>/])?>(.*?)</{0}>",
+        regex::escape(name)
+    ))
+    .ok()?;
+    let value = re.captures(text)?.get(1)?.as_str().trim();
+    let value = value
+        .strip_prefix("<![CDATA[")
+        .and_then(|value| value.strip_suffix("]]>"))
+        .map(String::from)
+        .unwrap_or_else(|| decode(value));
+
+    Some(value.trim().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    const RSS: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<rss version="2.0">
+  <channel>
+    <title>Test Releases</title>
+    <link>https://example.org/</link>
+    <item>
+      <title>Release v2.1.0</title>
+      <link>https://example.org/releases/2.1.0</link>
+      <guid>release-2.1.0</guid>
+      <pubDate>Tue, 01 Jun 2021 10:00:00 GMT</pubDate>
+      <enclosure url="https://example.org/files/app-2.1.0.exe" length="1024" type="application/octet-stream" />
+    </item>
+    <item>
+      <title><![CDATA[Release v2.0.0 & notes]]></title>
+      <link>/releases/2.0.0</link>
+    </item>
+    <item>
+      <title>Nightly build</title>
+      <link>https://example.org/nightly</link>
+    </item>
+  </channel>
+</rss>"#;
+
+    const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Test Releases</title>
+  <link href="https://example.org/" rel="self" />
+  <entry>
+    <title type="html">App 3.0.0-beta.1</title>
+    <link rel="alternate" type="text/html" href="https://example.org/releases/3.0.0-beta.1"/>
+    <link rel="enclosure" href="https://example.org/files/app-3.0.0-beta.1.zip"/>
+    <id>tag:example.org,2021:3.0.0-beta.1</id>
+    <updated>2021-06-01T10:00:00Z</updated>
+  </entry>
+  <entry>
+    <title>App 2.9.1</title>
+    <link href='https://example.org/releases/2.9.1'/>
+  </entry>
+</feed>"#;
+
+    fn url() -> Url {
+        Url::parse("https://example.org/releases.xml").unwrap()
+    }
+
+    fn summary(links: &[LinkElement]) -> Vec<(&str, Option<String>)> {
+        links
+            .iter()
+            .map(|link| {
+                (
+                    link.link.as_str(),
+                    link.version.as_ref().map(|v| v.to_string()),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_feed_should_read_rss_items() {
+        let (parent, links) = parse_feed(url(), RSS, None, &LinkTypeMap::default()).unwrap();
+
+        assert_eq!(parent.link, url());
+        assert_eq!(
+            summary(&links),
+            [
+                ("https://example.org/releases/2.1.0", Some("2.1.0".into())),
+                (
+                    "https://example.org/files/app-2.1.0.exe",
+                    Some("2.1.0".into())
+                ),
+                ("https://example.org/releases/2.0.0", Some("2.0.0".into())),
+                ("https://example.org/nightly", None),
+            ]
+        );
+        assert_eq!(links[1].link_type, LinkType::Binary);
+        assert_eq!(links[1].attributes["feed-link"], "enclosure");
+        assert_eq!(links[1].attributes["id"], "release-2.1.0");
+        assert_eq!(
+            links[1].attributes["published-at"],
+            "Tue, 01 Jun 2021 10:00:00 GMT"
+        );
+        assert_eq!(links[2].title, "Release v2.0.0 & notes");
+    }
+
+    #[test]
+    fn parse_feed_should_read_atom_entries() {
+        let (_, links) = parse_feed(url(), ATOM, None, &LinkTypeMap::default()).unwrap();
+
+        assert_eq!(
+            summary(&links),
+            [
+                (
+                    "https://example.org/releases/3.0.0-beta.1",
+                    Some("3.0.0-beta.1".into())
+                ),
+                (
+                    "https://example.org/files/app-3.0.0-beta.1.zip",
+                    Some("3.0.0-beta.1".into())
+                ),
+                ("https://example.org/releases/2.9.1", Some("2.9.1".into())),
+            ]
+        );
+        assert_eq!(links[0].attributes["published-at"], "2021-06-01T10:00:00Z");
+        assert_eq!(links[2].title, "App 2.9.1");
+    }
+
+    #[rstest(
+        re,
+        expected,
+        case(
+            r"^Release v(?P<version>[\d\.]+)$",
+            vec![
+                ("https://example.org/releases/2.1.0", Some("2.1.0".to_string())),
+                ("https://example.org/files/app-2.1.0.exe", Some("2.1.0".to_string()))
+            ]
+        ),
+        case(r"^Nightly", vec![("https://example.org/nightly", None)])
+    )]
+    fn parse_feed_should_only_include_entries_matching_regex(
+        re: &str,
+        expected: Vec<(&str, Option<String>)>,
+    ) {
+        let (_, links) = parse_feed(url(), RSS, Some(re), &LinkTypeMap::default()).unwrap();
+
+        assert_eq!(summary(&links), expected);
+    }
+
+    #[test]
+    fn parse_feed_should_return_error_on_invalid_regex() {
+        let actual = parse_feed(url(), RSS, Some("(?P<version"), &LinkTypeMap::default());
+
+        assert!(actual.is_err());
+    }
+}
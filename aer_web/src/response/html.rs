@@ -2,14 +2,30 @@
 // Licensed under the MIT license. See LICENSE.txt file in the project
 
 use aer_version::Versions;
+use lazy_static::lazy_static;
+use log::{info, warn};
 use regex::{Captures, Regex};
 use reqwest::blocking::Response;
+use reqwest::header::HeaderMap;
 use reqwest::{header, Url};
 use select::document::Document;
-use select::predicate::Name;
-
-use crate::response::{WebError, MIME_TYPES};
-use crate::{LinkElement, LinkType, WebResponse};
+use select::node::Node;
+use select::predicate::{Attr, Name, Predicate};
+use serde_json::Value;
+
+use crate::request::WebRequest;
+use crate::response::WebError;
+use crate::selector::{select_anchors, HtmlSelector, LinkSelectors};
+use crate::sources::parse_text_version;
+use crate::{LinkElement, LinkSource, LinkType, LinkTypeMap, NextPage, Pagination, WebResponse};
+
+lazy_static! {
+    static ref META_REFRESH_URL: Regex = Regex::new(r#"(?i)url\s*=\s*['"]?([^'"\s]+)"#).unwrap();
+    static ref SCRIPT_VARIABLE_URL: Regex = Regex::new(
+        r#"(?i)[\w$]*(?:url|link|href|download)[\w$]*['"]?\s*[:=]\s*['"]([^'"\s]+)['"]"#
+    )
+    .unwrap();
+}
 
 /// Contains functions and structure for holding a single html response, and
 /// extracting any necessary information out of the html page.
@@ -19,13 +35,73 @@ use crate::{LinkElement, LinkType, WebResponse};
 #[derive(Debug)]
 pub struct HtmlResponse {
     response: Response,
+    link_types: LinkTypeMap,
+    link_sources: Vec<LinkSource>,
+    selectors: LinkSelectors,
+    pagination: Option<Pagination>,
+    request: Option<WebRequest>,
 }
 
 impl HtmlResponse {
     /// Creates a new instance of the [HtmlResponse] structe to hold the current
     /// response, and allow reading the content from that response.
     pub fn new(response: Response) -> HtmlResponse {
-        HtmlResponse { response }
+        HtmlResponse {
+            response,
+            link_types: LinkTypeMap::default(),
+            link_sources: vec![],
+            selectors: LinkSelectors::default(),
+            pagination: None,
+            request: None,
+        }
+    }
+
+    /// Sets the mappings that should be used to decide the type of the links
+    /// that are found in the response.
+    pub fn with_link_types(mut self, link_types: LinkTypeMap) -> HtmlResponse {
+        self.link_types = link_types;
+        self
+    }
+
+    /// Sets the additional sources that links should be extracted from, in
+    /// addition to the anchor elements in the response.
+    pub fn with_link_sources(mut self, link_sources: &[LinkSource]) -> HtmlResponse {
+        self.link_sources = link_sources.to_vec();
+        self
+    }
+
+    /// Sets the selector matching the anchor elements, or the sections of the
+    /// page containing the anchor elements, that links should be extracted
+    /// from. Links from the additional sources are not affected by the
+    /// selector.
+    pub fn with_link_selector(mut self, selector: HtmlSelector) -> HtmlResponse {
+        self.selectors.links = Some(selector);
+        self
+    }
+
+    /// Sets the selector matching the elements the version of the links are
+    /// read from (*ie: a table cell next to the link*), the version is parsed
+    /// from the first word of the element containing a digit. This takes
+    /// precedence over the version captured by the regex used when reading
+    /// the response.
+    pub fn with_version_selector(mut self, selector: HtmlSelector) -> HtmlResponse {
+        self.selectors.version = Some(selector);
+        self
+    }
+
+    /// Sets the settings used to follow the pages of a paginated html page,
+    /// the links of every page that was read are returned together when
+    /// reading the response. Only responses created by a [WebRequest] are able
+    /// to follow the pages.
+    pub fn with_pagination(mut self, pagination: Pagination) -> HtmlResponse {
+        self.pagination = Some(pagination);
+        self
+    }
+
+    /// Sets the request used when requesting the next pages of the response.
+    pub(crate) fn with_request(mut self, request: WebRequest) -> HtmlResponse {
+        self.request = Some(request);
+        self
     }
 }
 
@@ -47,38 +123,120 @@ impl WebResponse for HtmlResponse {
     /// body fails.
     fn read(self, re: Option<&str>) -> Result<Self::ResponseContent, WebError> {
         let response_url = self.response.url().clone();
-
-        let parent_link = get_parent_link_element(&self);
-
+        let headers = self.response.headers().clone();
         let body = self.response.text().map_err(WebError::Request)?;
-        let links = get_link_elements(body, response_url, re)?;
+        let next = match self.pagination {
+            Some(ref pagination) => get_next_page(&body, &response_url, &pagination.next)?,
+            None => None,
+        };
+
+        let (parent, mut links) = parse_html(
+            response_url,
+            &headers,
+            body,
+            re,
+            &self.link_types,
+            &self.link_sources,
+            &self.selectors,
+        )?;
+
+        if let (Some(pagination), Some(request)) = (self.pagination, self.request) {
+            let mut visited = vec![parent.link.clone()];
+            let mut next = next;
+
+            while let Some(url) = next.take() {
+                if visited.len() >= pagination.max_pages {
+                    warn!(
+                        "Maximum number of pages ({}) reached, stopping!",
+                        pagination.max_pages
+                    );
+                    break;
+                }
+                if visited.contains(&url) {
+                    break;
+                }
+                visited.push(url.clone());
+
+                info!("Following the next page '{}'", url);
+                let response = match request.get_html_response(url.as_str()) {
+                    Ok(response) => response.response,
+                    Err(err) => {
+                        warn!("Unable to read the next page '{}': {}", url, err);
+                        break;
+                    }
+                };
+                let url = response.url().clone();
+                let headers = response.headers().clone();
+                let body = response.text().map_err(WebError::Request)?;
+                next = get_next_page(&body, &url, &pagination.next)?;
+
+                let (_, page_links) = parse_html(
+                    url,
+                    &headers,
+                    body,
+                    re,
+                    &self.link_types,
+                    &self.link_sources,
+                    &self.selectors,
+                )?;
+                links.extend(page_links);
+            }
+        }
 
-        Ok((parent_link, links))
+        Ok((parent, links))
     }
 }
 
-fn get_parent_link_element<T: WebResponse>(content: &T) -> LinkElement {
-    let headers = content.get_headers();
-    let url = content.response().url();
-    let response_type = headers
-        .get(header::CONTENT_TYPE.as_str())
-        .unwrap_or(&"UNKNOWN");
+/// Parses the body of an html response, and returns the link the response
+/// came from together with the link elements found in the body. This is
+/// shared between the blocking and the asynchronous responses.
+pub(crate) fn parse_html(
+    url: Url,
+    headers: &HeaderMap,
+    body: String,
+    re: Option<&str>,
+    link_types: &LinkTypeMap,
+    link_sources: &[LinkSource],
+    selectors: &LinkSelectors,
+) -> Result<(LinkElement, Vec<LinkElement>), WebError> {
+    let parent_link = get_parent_link_element(&url, headers, link_types);
+    let links = get_link_elements(body, url, re, link_types, link_sources, selectors)?;
+
+    Ok((parent_link, links))
+}
 
-    for (key, val) in MIME_TYPES.iter() {
-        if response_type.contains(key) {
-            return LinkElement::new(url.clone(), *val);
-        }
+fn get_parent_link_element(
+    url: &Url,
+    headers: &HeaderMap,
+    link_types: &LinkTypeMap,
+) -> LinkElement {
+    let response_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("UNKNOWN");
+
+    let mut link = LinkElement::new(url.clone(), link_types.from_mime_type(response_type));
+    if let Some(language) = headers
+        .get(header::CONTENT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+    {
+        link.attributes
+            .insert(header::CONTENT_LANGUAGE.as_str().into(), language.into());
     }
 
-    LinkElement::new(url.clone(), LinkType::Unknown)
+    link
 }
 
 fn get_link_elements(
     text: String,
     parent_url: Url,
     re: Option<&str>,
+    link_types: &LinkTypeMap,
+    link_sources: &[LinkSource],
+    selectors: &LinkSelectors,
 ) -> Result<Vec<LinkElement>, WebError> {
     let document = Document::from(text.as_str());
+    let base_url = get_base_url(&document, parent_url);
 
     let re = if let Some(re) = re {
         Some(Regex::new(&re).map_err(|err| WebError::Other(err.to_string()))?)
@@ -86,11 +244,11 @@ fn get_link_elements(
         None
     };
 
-    let results = document
-        .find(Name("a"))
-        .filter_map(|n| {
+    let mut results: Vec<LinkElement> = select_anchors(&document, selectors)
+        .into_iter()
+        .filter_map(|(n, version)| {
             let mut link = {
-                let href = match n.attr("href") {
+                let href = match n.attr("href").map(str::trim) {
                     Some(n) => {
                         if n.is_empty() {
                             return None;
@@ -101,21 +259,10 @@ fn get_link_elements(
                     _ => return None,
                 };
 
-                let href =
-                    if href.starts_with('/') || href.starts_with('.') || href.starts_with('#') {
-                        parent_url.join(&href)
-                    } else {
-                        Url::parse(href)
-                    }
-                    .ok()?;
+                let href = base_url.join(href).ok()?;
                 LinkElement::new(href, LinkType::Unknown)
             };
 
-            if let Some(re) = &re {
-                let capture = re.captures(link.link.as_str())?;
-                link.version = parse_version(capture);
-            }
-
             link.text = n.text().trim().into();
 
             for (key, val) in n.attrs() {
@@ -129,34 +276,175 @@ fn get_link_elements(
                 }
             }
 
-            let path = link.link.path();
-            if path.ends_with(".html") {
-                link.link_type = LinkType::Html;
-            } else if path.ends_with(".json") {
-                link.link_type = LinkType::Json;
-            } else if path.ends_with(".css") {
-                link.link_type = LinkType::Css;
-            } else if path.ends_with(".txt") {
-                link.link_type = LinkType::Text;
-            } else if path.ends_with(".zip")
-                || path.ends_with(".7z")
-                || path.ends_with(".exe")
-                || path.ends_with(".msi")
-                || path.ends_with(".tar")
-                || path.ends_with(".tar.gz")
-                || path.ends_with(".tar.bz2")
-                || path.ends_with(".nupkg")
-            {
-                link.link_type = LinkType::Binary;
+            let mut link = finish_link(link, re.as_ref(), link_types)?;
+            if let Some(version) = version.as_deref().and_then(parse_text_version) {
+                link.version = Some(version);
             }
 
             Some(link)
         })
         .collect();
 
+    for source in link_sources {
+        let hrefs = match source {
+            LinkSource::MetaRefresh => get_meta_refresh_hrefs(&document),
+            LinkSource::ScriptVariables => get_script_variable_hrefs(&document),
+            LinkSource::ScriptJson => get_script_json_hrefs(&document),
+        };
+
+        results.extend(hrefs.iter().filter_map(|href| {
+            let mut link = LinkElement::new(base_url.join(href).ok()?, LinkType::Unknown);
+            let _ = link
+                .attributes
+                .insert("source".into(), source.name().into());
+
+            finish_link(link, re.as_ref(), link_types)
+        }));
+    }
+
     Ok(results)
 }
 
+/// Filters out the link if it do not match the specified regex, and sets the
+/// version and link type of the link.
+fn finish_link(
+    mut link: LinkElement,
+    re: Option<&Regex>,
+    link_types: &LinkTypeMap,
+) -> Option<LinkElement> {
+    if let Some(re) = re {
+        let capture = re.captures(link.link.as_str())?;
+        link.version = parse_version(capture);
+    }
+
+    let link_type = link_types.from_path(link.link.path());
+    if link_type != LinkType::Unknown {
+        link.link_type = link_type;
+    }
+
+    Some(link)
+}
+
+fn get_meta_refresh_hrefs(document: &Document) -> Vec<String> {
+    document
+        .find(Name("meta"))
+        .filter(|n| {
+            n.attr("http-equiv")
+                .map(|val| val.eq_ignore_ascii_case("refresh"))
+                .unwrap_or_default()
+        })
+        .filter_map(|n| {
+            let content = n.attr("content")?;
+            let capture = META_REFRESH_URL.captures(content)?;
+            Some(capture[1].to_owned())
+        })
+        .collect()
+}
+
+fn get_script_variable_hrefs(document: &Document) -> Vec<String> {
+    document
+        .find(Name("script"))
+        .flat_map(|n| {
+            let text = n.text();
+            SCRIPT_VARIABLE_URL
+                .captures_iter(&text)
+                .map(|capture| capture[1].to_owned())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn get_script_json_hrefs(document: &Document) -> Vec<String> {
+    let is_json = |n: &Node| {
+        n.attr("type")
+            .map(|val| {
+                let val = val.trim().to_lowercase();
+                val == "application/json" || val == "application/ld+json"
+            })
+            .unwrap_or_default()
+    };
+
+    let mut hrefs = vec![];
+    for n in document.find(Name("script").and(Attr("type", ()))) {
+        if !is_json(&n) {
+            continue;
+        }
+        if let Ok(value) = serde_json::from_str::<Value>(&n.text()) {
+            collect_json_hrefs(&value, &mut hrefs);
+        }
+    }
+
+    hrefs
+}
+
+fn collect_json_hrefs(value: &Value, hrefs: &mut Vec<String>) {
+    match value {
+        Value::String(val) => {
+            let lower = val.to_lowercase();
+            if lower.starts_with("https://")
+                || lower.starts_with("http://")
+                || lower.starts_with("//")
+            {
+                hrefs.push(val.clone());
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|val| collect_json_hrefs(val, hrefs)),
+        Value::Object(items) => items
+            .values()
+            .for_each(|val| collect_json_hrefs(val, hrefs)),
+        _ => {}
+    }
+}
+
+/// Returns the link to the next page of the html page, resolved against the
+/// base url of the page.
+fn get_next_page(text: &str, url: &Url, next: &NextPage) -> Result<Option<Url>, WebError> {
+    let document = Document::from(text);
+    let base_url = get_base_url(&document, url.clone());
+
+    let href = match next {
+        NextPage::Rel => document
+            .find(Name("a").or(Name("link")))
+            .find(|n| {
+                n.attr("rel")
+                    .map(|rel| {
+                        rel.split_whitespace()
+                            .any(|rel| rel.eq_ignore_ascii_case("next"))
+                    })
+                    .unwrap_or_default()
+            })
+            .and_then(|n| n.attr("href")),
+        NextPage::Regex(re) => {
+            let re = Regex::new(re).map_err(|err| WebError::Other(err.to_string()))?;
+            document
+                .find(Name("a"))
+                .find(|n| {
+                    let href = n.attr("href").map(str::trim).unwrap_or_default();
+                    !href.is_empty() && (re.is_match(href) || re.is_match(n.text().trim()))
+                })
+                .and_then(|n| n.attr("href"))
+        }
+    };
+
+    Ok(href
+        .map(str::trim)
+        .filter(|href| !href.is_empty())
+        .and_then(|href| base_url.join(href).ok()))
+}
+
+/// Returns the url that relative links should be resolved against, this is
+/// the first `<base href>` element in the document when it is available, and
+/// otherwise the url of the response itself.
+fn get_base_url(document: &Document, parent_url: Url) -> Url {
+    document
+        .find(Name("base"))
+        .filter_map(|n| n.attr("href"))
+        .map(str::trim)
+        .find(|href| !href.is_empty())
+        .and_then(|href| parent_url.join(href).ok())
+        .unwrap_or(parent_url)
+}
+
 fn parse_version(captures: Captures<'_>) -> Option<Versions> {
     Versions::parse(captures.name("version")?.as_str()).ok()
 }
@@ -165,6 +453,8 @@ fn parse_version(captures: Captures<'_>) -> Option<Versions> {
 mod tests {
     use std::collections::HashMap;
 
+    use rstest::rstest;
+
     use super::*;
     use crate::WebRequest;
 
@@ -318,4 +608,260 @@ mod tests {
             6
         );
     }
+
+    #[test]
+    fn get_link_elements_should_use_custom_link_types() {
+        let mut link_types = LinkTypeMap::default();
+        link_types.add_extension(".AppImage", LinkType::Binary);
+        let text = r#"<a href="/app-1.0.AppImage">Linux</a><a href="/app-1.0.dmg">Mac</a>"#;
+
+        let links = get_link_elements(
+            text.into(),
+            Url::parse("https://test.com/releases").unwrap(),
+            None,
+            &link_types,
+            &[],
+            &LinkSelectors::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            links
+                .iter()
+                .map(|l| l.link_type.clone())
+                .collect::<Vec<LinkType>>(),
+            [LinkType::Binary, LinkType::Unknown]
+        );
+    }
+
+    #[rstest(
+        href,
+        expected,
+        case("/file.zip", "https://test.com/file.zip"),
+        case("./file.zip", "https://test.com/releases/file.zip"),
+        case("../file.zip", "https://test.com/file.zip"),
+        case("file.zip", "https://test.com/releases/file.zip"),
+        case("?file=x.zip", "https://test.com/releases/latest?file=x.zip"),
+        case("#assets", "https://test.com/releases/latest#assets"),
+        case("//cdn.test.com/file.zip", "https://cdn.test.com/file.zip"),
+        case("http://other.com/file.zip", "http://other.com/file.zip")
+    )]
+    fn get_link_elements_should_resolve_relative_links(href: &str, expected: &str) {
+        let text = format!(r#"<a href="{}">File</a>"#, href);
+
+        let links = get_link_elements(
+            text,
+            Url::parse("https://test.com/releases/latest").unwrap(),
+            None,
+            &LinkTypeMap::default(),
+            &[],
+            &LinkSelectors::default(),
+        )
+        .unwrap();
+
+        assert_eq!(links[0].link.as_str(), expected);
+    }
+
+    #[rstest(
+        base,
+        expected,
+        case("https://cdn.test.com/files/", "https://cdn.test.com/files/file.zip"),
+        case("/downloads/", "https://test.com/downloads/file.zip"),
+        case("", "https://test.com/releases/file.zip")
+    )]
+    fn get_link_elements_should_resolve_links_against_base_href(base: &str, expected: &str) {
+        let text = format!(
+            r#"<html><head><base href="{}"></head><body><a href="file.zip">File</a></body></html>"#,
+            base
+        );
+
+        let links = get_link_elements(
+            text,
+            Url::parse("https://test.com/releases/latest").unwrap(),
+            None,
+            &LinkTypeMap::default(),
+            &[],
+            &LinkSelectors::default(),
+        )
+        .unwrap();
+
+        assert_eq!(links[0].link.as_str(), expected);
+    }
+
+    #[rstest(
+        source,
+        text,
+        expected,
+        case(
+            LinkSource::MetaRefresh,
+            r#"<meta http-equiv="Refresh" content="5; URL='/download/app-1.2.0.exe'">"#,
+            "https://test.com/download/app-1.2.0.exe"
+        ),
+        case(
+            LinkSource::ScriptVariables,
+            r#"<script>var downloadUrl = "https://cdn.test.com/app-1.2.0.exe";</script>"#,
+            "https://cdn.test.com/app-1.2.0.exe"
+        ),
+        case(
+            LinkSource::ScriptVariables,
+            r#"<script>window.config = { "installerLink": 'app-1.2.0.exe' };</script>"#,
+            "https://test.com/releases/app-1.2.0.exe"
+        ),
+        case(
+            LinkSource::ScriptJson,
+            r#"<script type="application/json">{"assets":[{"name":"App","url":"https://cdn.test.com/app-1.2.0.exe"}]}</script>"#,
+            "https://cdn.test.com/app-1.2.0.exe"
+        )
+    )]
+    fn get_link_elements_should_extract_links_from_additional_sources(
+        source: LinkSource,
+        text: &str,
+        expected: &str,
+    ) {
+        let links = get_link_elements(
+            text.into(),
+            Url::parse("https://test.com/releases/latest").unwrap(),
+            Some(r"app-(?P<version>[\d\.]+)\.exe$"),
+            &LinkTypeMap::default(),
+            &[source],
+            &LinkSelectors::default(),
+        )
+        .unwrap();
+
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].link.as_str(), expected);
+        assert_eq!(links[0].link_type, LinkType::Binary);
+        assert_eq!(links[0].version, Some(Versions::parse("1.2.0").unwrap()));
+        assert_eq!(links[0].attributes["source"], source.name());
+    }
+
+    #[test]
+    fn get_link_elements_should_ignore_additional_sources_when_not_enabled() {
+        let text = r#"<meta http-equiv="refresh" content="0; url=/app.exe"><script>var downloadUrl = "/app.exe";</script>"#;
+
+        let links = get_link_elements(
+            text.into(),
+            Url::parse("https://test.com/releases/latest").unwrap(),
+            None,
+            &LinkTypeMap::default(),
+            &[],
+            &LinkSelectors::default(),
+        )
+        .unwrap();
+
+        assert!(links.is_empty());
+    }
+
+    const DOWNLOADS: &str = r#"<html><body>
+        <nav><a href="/about">About</a></nav>
+        <table id="downloads">
+          <tr class="release"><td class="version">Version 2.1.0</td><td><a href="/files/app-setup.exe">Installer</a></td></tr>
+          <tr class="release"><td class="version">v2.0.5</td><td><a href="/files/app-old.exe">Installer</a></td></tr>
+        </table>
+        <a href="/files/app-nightly.exe">Nightly</a>
+    </body></html>"#;
+
+    #[rstest(
+        links,
+        version,
+        case("table#downloads", "td.version"),
+        case("#downloads tr > td a[href$='.exe']", "tr.release .version"),
+        case("//table[@id='downloads']", "./td[@class='version']"),
+        case("//tr[@class='release']/td/a", "./td[1]")
+    )]
+    fn get_link_elements_should_use_selectors(links: &str, version: &str) {
+        let selectors = LinkSelectors {
+            links: Some(HtmlSelector::parse(links).unwrap()),
+            version: Some(HtmlSelector::parse(version).unwrap()),
+        };
+
+        let links = get_link_elements(
+            DOWNLOADS.into(),
+            Url::parse("https://test.com/").unwrap(),
+            None,
+            &LinkTypeMap::default(),
+            &[],
+            &selectors,
+        )
+        .unwrap();
+
+        assert_eq!(
+            links
+                .iter()
+                .map(|l| (l.link.as_str(), l.version.as_ref().map(|v| v.to_string())))
+                .collect::<Vec<_>>(),
+            [
+                ("https://test.com/files/app-setup.exe", Some("2.1.0".into())),
+                ("https://test.com/files/app-old.exe", Some("2.0.5".into()))
+            ]
+        );
+    }
+
+    #[test]
+    fn get_link_elements_should_fall_back_to_regex_version_when_selector_is_not_found() {
+        let selectors = LinkSelectors {
+            links: None,
+            version: Some(HtmlSelector::parse("span.version").unwrap()),
+        };
+
+        let links = get_link_elements(
+            r#"<p><a href="/app-1.2.3.exe">App</a></p>"#.into(),
+            Url::parse("https://test.com/").unwrap(),
+            Some(r"app-(?P<version>[\d\.]+)\.exe$"),
+            &LinkTypeMap::default(),
+            &[],
+            &selectors,
+        )
+        .unwrap();
+
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].version, Some(Versions::parse("1.2.3").unwrap()));
+    }
+
+    #[rstest(
+        text,
+        next,
+        expected,
+        case(
+            r#"<html><head><link rel="next" href="/releases?page=2"></head></html>"#,
+            NextPage::Rel,
+            Some("https://test.com/releases?page=2")
+        ),
+        case(
+            r#"<a href="?page=1" rel="prev">Newer</a><a href="?page=3" rel="Next nofollow">Older</a>"#,
+            NextPage::Rel,
+            Some("https://test.com/releases?page=3")
+        ),
+        case(
+            r#"<a href="?page=1">Newer releases</a><a href="?page=3"> Older releases </a>"#,
+            NextPage::Regex("^Older".into()),
+            Some("https://test.com/releases?page=3")
+        ),
+        case(
+            r#"<a href="/releases/after/v1.2.0">More</a>"#,
+            NextPage::Regex("/after/".into()),
+            Some("https://test.com/releases/after/v1.2.0")
+        ),
+        case(r#"<a href="?page=3">Older</a>"#, NextPage::Rel, None)
+    )]
+    fn get_next_page_should_find_link_to_next_page(
+        text: &str,
+        next: NextPage,
+        expected: Option<&str>,
+    ) {
+        let url = Url::parse("https://test.com/releases?page=2").unwrap();
+
+        let actual = get_next_page(text, &url, &next).unwrap();
+
+        assert_eq!(actual.as_ref().map(Url::as_str), expected);
+    }
+
+    #[test]
+    fn get_next_page_should_return_error_on_invalid_regex() {
+        let url = Url::parse("https://test.com/releases").unwrap();
+
+        let actual = get_next_page("", &url, &NextPage::Regex("(".into()));
+
+        assert!(actual.is_err());
+    }
 }
@@ -0,0 +1,211 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+use reqwest::blocking::Response;
+use reqwest::Url;
+use serde_json::Value;
+
+use crate::json_path::JsonPath;
+use crate::response::WebError;
+use crate::sources::parse_tag_version;
+use crate::{LinkElement, LinkType, LinkTypeMap, WebResponse};
+
+/// Contains functions and structure for holding a single JSON response, and
+/// extracting values from the JSON document using JSONPath expressions.
+///
+/// Implements the [WebResponse] trait, and are not meant to be created directly
+/// by a user.
+#[derive(Debug)]
+pub struct JsonResponse {
+    response: Response,
+    link_types: LinkTypeMap,
+}
+
+impl JsonResponse {
+    /// Creates a new instance of the [JsonResponse] structure to hold the
+    /// current response, and allow reading the content from that response.
+    pub fn new(response: Response) -> JsonResponse {
+        JsonResponse {
+            response,
+            link_types: LinkTypeMap::default(),
+        }
+    }
+
+    /// Sets the mappings that should be used to decide the type of the links
+    /// that are extracted from the response.
+    pub fn with_link_types(mut self, link_types: LinkTypeMap) -> JsonResponse {
+        self.link_types = link_types;
+        self
+    }
+
+    /// Reads the current response, and extracts the links selected by the
+    /// `urls` expression. When a `version` expression is specified, the first
+    /// value it selects is parsed as the version of the link the response
+    /// came from and of every extracted link.
+    ///
+    /// The first item returned is the link the response came from, and the
+    /// second item holds the links that were extracted from the document.
+    pub fn read_links(
+        self,
+        urls: &str,
+        version: Option<&str>,
+    ) -> Result<(LinkElement, Vec<LinkElement>), WebError> {
+        let url = self.response.url().clone();
+        let link_types = self.link_types.clone();
+        let value = self.read(None)?;
+
+        parse_json_links(url, &value, urls, version, &link_types)
+    }
+}
+
+impl WebResponse for JsonResponse {
+    /// Sets the response type that will be returned when calling the
+    /// [read](JsonResponse::read) function, this is the parsed JSON document.
+    type ResponseContent = Value;
+
+    fn response(&self) -> &Response {
+        &self.response
+    }
+
+    /// Reads the current response and parses the body as JSON. When an
+    /// expression is specified, an array of the values selected by the
+    /// expression is returned instead of the whole document. This function
+    /// will return an error if the body could not be read, is not valid JSON
+    /// or the expression is not valid.
+    fn read(self, re: Option<&str>) -> Result<Self::ResponseContent, WebError> {
+        let url = self.response.url().clone();
+        let body = self.response.text().map_err(WebError::Request)?;
+        let value = parse_json(&url, &body)?;
+
+        match re {
+            Some(expression) => Ok(Value::Array(
+                JsonPath::parse(expression)?
+                    .select(&value)
+                    .into_iter()
+                    .cloned()
+                    .collect(),
+            )),
+            None => Ok(value),
+        }
+    }
+}
+
+fn parse_json(url: &Url, body: &str) -> Result<Value, WebError> {
+    serde_json::from_str(body).map_err(|err| {
+        WebError::Other(format!(
+            "The response from '{}' is not valid JSON: {}",
+            url, err
+        ))
+    })
+}
+
+/// Extracts the links selected by the `urls` expression from the JSON
+/// document, relative urls are resolved against the url of the response.
+fn parse_json_links(
+    url: Url,
+    value: &Value,
+    urls: &str,
+    version: Option<&str>,
+    link_types: &LinkTypeMap,
+) -> Result<(LinkElement, Vec<LinkElement>), WebError> {
+    let version = match version {
+        Some(expression) => JsonPath::parse(expression)?
+            .strings(value)
+            .first()
+            .and_then(|version| parse_tag_version(version)),
+        None => None,
+    };
+
+    let mut parent = LinkElement::new(url.clone(), LinkType::Json);
+    parent.version = version.clone();
+
+    let links = JsonPath::parse(urls)?
+        .strings(value)
+        .into_iter()
+        .filter_map(|link| url.join(link.trim()).ok())
+        .map(|link| {
+            let mut element = LinkElement::new(link.clone(), link_types.from_path(link.path()));
+            element.version = version.clone();
+            element
+        })
+        .collect();
+
+    Ok((parent, links))
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_version::Versions;
+    use serde_json::json;
+
+    use super::*;
+
+    fn document() -> Value {
+        json!({
+            "tag_name": "v3.0.1",
+            "assets": [
+                { "url": "https://example.org/download/app-3.0.1-x64.exe" },
+                { "url": "/download/app-3.0.1.zip" },
+                { "url": null }
+            ]
+        })
+    }
+
+    #[test]
+    fn parse_json_links_should_extract_links_and_version() {
+        let url = Url::parse("https://api.example.org/releases/latest").unwrap();
+
+        let (parent, links) = parse_json_links(
+            url.clone(),
+            &document(),
+            "$.assets[*].url",
+            Some("$.tag_name"),
+            &LinkTypeMap::default(),
+        )
+        .unwrap();
+
+        let version = Some(Versions::parse("3.0.1").unwrap());
+        assert_eq!(parent.link, url);
+        assert_eq!(parent.link_type, LinkType::Json);
+        assert_eq!(parent.version, version);
+        assert_eq!(
+            links
+                .iter()
+                .map(|link| link.link.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "https://example.org/download/app-3.0.1-x64.exe",
+                "https://api.example.org/download/app-3.0.1.zip"
+            ]
+        );
+        assert!(links.iter().all(|link| link.is_binary()));
+        assert!(links.iter().all(|link| link.version == version));
+    }
+
+    #[test]
+    fn parse_json_links_should_not_set_version_without_expression() {
+        let url = Url::parse("https://api.example.org/releases/latest").unwrap();
+
+        let (parent, links) = parse_json_links(
+            url,
+            &document(),
+            "assets.0.url",
+            None,
+            &LinkTypeMap::default(),
+        )
+        .unwrap();
+
+        assert_eq!(parent.version, None);
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].version, None);
+    }
+
+    #[test]
+    fn parse_json_should_fail_on_invalid_documents() {
+        let url = Url::parse("https://api.example.org/releases/latest").unwrap();
+
+        let actual = parse_json(&url, "<html></html>");
+
+        assert!(actual.is_err());
+    }
+}
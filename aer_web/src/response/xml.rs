@@ -0,0 +1,248 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+use reqwest::blocking::Response;
+use reqwest::Url;
+
+use crate::response::WebError;
+use crate::sources::parse_tag_version;
+use crate::xml::{XPath, XPathValue, XmlDocument};
+use crate::{LinkElement, LinkType, LinkTypeMap, WebResponse};
+
+/// Contains functions and structure for holding a single XML response (*ie:
+/// an appcast feed or an update manifest*), and extracting values from the
+/// document using XPath-like queries.
+///
+/// Implements the [WebResponse] trait, and are not meant to be created directly
+/// by a user.
+#[derive(Debug)]
+pub struct XmlResponse {
+    response: Response,
+    link_types: LinkTypeMap,
+}
+
+impl XmlResponse {
+    /// Creates a new instance of the [XmlResponse] structure to hold the
+    /// current response, and allow reading the content from that response.
+    pub fn new(response: Response) -> XmlResponse {
+        XmlResponse {
+            response,
+            link_types: LinkTypeMap::default(),
+        }
+    }
+
+    /// Sets the mappings that should be used to decide the type of the links
+    /// that are extracted from the response.
+    pub fn with_link_types(mut self, link_types: LinkTypeMap) -> XmlResponse {
+        self.link_types = link_types;
+        self
+    }
+
+    /// Reads the current response, and extracts a link and version pair from
+    /// every element selected by the `items` query. The `url` and `version`
+    /// queries are evaluated against each of the selected elements, unless the
+    /// query is absolute (*ie: starts with `/`*).
+    ///
+    /// The first item returned is the link the response came from, and the
+    /// second item holds the links that were extracted from the document.
+    pub fn read_links(
+        self,
+        items: &str,
+        url: &str,
+        version: Option<&str>,
+    ) -> Result<(LinkElement, Vec<LinkElement>), WebError> {
+        let response_url = self.response.url().clone();
+        let link_types = self.link_types.clone();
+        let document = self.read(None)?;
+
+        parse_xml_links(response_url, &document, items, url, version, &link_types)
+    }
+}
+
+impl WebResponse for XmlResponse {
+    /// Sets the response type that will be returned when calling the
+    /// [read](XmlResponse::read) function, this is the parsed XML document.
+    type ResponseContent = XmlDocument;
+
+    fn response(&self) -> &Response {
+        &self.response
+    }
+
+    /// Reads the current response and parses the body as XML. This function
+    /// will return an error if the body could not be read or is not valid XML,
+    /// the argument is not used.
+    fn read(self, _: Option<&str>) -> Result<Self::ResponseContent, WebError> {
+        let url = self.response.url().clone();
+        let body = self.response.text().map_err(WebError::Request)?;
+
+        XmlDocument::parse(&body).map_err(|err| {
+            WebError::Other(format!("The response from '{}' is not valid: {}", url, err))
+        })
+    }
+}
+
+/// Extracts a link, and optionally the version, from every element selected
+/// by the `items` query. Relative urls are resolved against the url of the
+/// response, and the version of the first item is used for the link the
+/// response came from.
+fn parse_xml_links(
+    response_url: Url,
+    document: &XmlDocument,
+    items: &str,
+    url: &str,
+    version: Option<&str>,
+    link_types: &LinkTypeMap,
+) -> Result<(LinkElement, Vec<LinkElement>), WebError> {
+    let items = XPath::parse(items)?;
+    let url = XPath::parse(url)?;
+    let version = version.map(XPath::parse).transpose()?;
+    let title = XPath::parse("title")?;
+    let mut links: Vec<LinkElement> = vec![];
+
+    for item in document.select(&items) {
+        let item = match item {
+            XPathValue::Element(item) => item,
+            XPathValue::Text(_) => continue,
+        };
+        let version = version
+            .as_ref()
+            .and_then(|version| {
+                document
+                    .strings_from(item, version)
+                    .into_iter()
+                    .find(|version| !version.is_empty())
+            })
+            .and_then(|version| parse_tag_version(&version));
+        let title = document
+            .strings_from(item, &title)
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+
+        for href in document.strings_from(item, &url) {
+            let href = match response_url.join(&href) {
+                Ok(href) => href,
+                Err(_) => continue,
+            };
+            let mut link = LinkElement::new(href.clone(), link_types.from_path(href.path()));
+            link.title = title.clone();
+            link.version = version.clone();
+            links.push(link);
+        }
+    }
+
+    let mut parent = LinkElement::new(response_url, LinkType::Text);
+    parent.version = links.first().and_then(|link| link.version.clone());
+
+    Ok((parent, links))
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_version::Versions;
+
+    use super::*;
+
+    const MANIFEST: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<updates>
+  <update channel="stable">
+    <title>Test App 4.2</title>
+    <version>v4.2.0</version>
+    <files>
+      <file arch="x64">/downloads/app-4.2.0-x64.msi</file>
+      <file arch="x86">/downloads/app-4.2.0-x86.msi</file>
+    </files>
+  </update>
+  <update channel="beta">
+    <version>4.3.0-beta.1</version>
+    <files>
+      <file arch="x64">https://cdn.example.org/app-4.3.0-beta.1-x64.msi</file>
+    </files>
+  </update>
+</updates>"#;
+
+    fn url() -> Url {
+        Url::parse("https://example.org/updates.xml").unwrap()
+    }
+
+    #[test]
+    fn parse_xml_links_should_extract_link_and_version_pairs() {
+        let document = XmlDocument::parse(MANIFEST).unwrap();
+
+        let (parent, links) = parse_xml_links(
+            url(),
+            &document,
+            "//update",
+            "files/file",
+            Some("version"),
+            &LinkTypeMap::default(),
+        )
+        .unwrap();
+
+        assert_eq!(parent.version, Some(Versions::parse("4.2.0").unwrap()));
+        assert_eq!(
+            links
+                .iter()
+                .map(|link| (
+                    link.link.as_str(),
+                    link.version.as_ref().map(|v| v.to_string())
+                ))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    "https://example.org/downloads/app-4.2.0-x64.msi",
+                    Some("4.2.0".to_string())
+                ),
+                (
+                    "https://example.org/downloads/app-4.2.0-x86.msi",
+                    Some("4.2.0".to_string())
+                ),
+                (
+                    "https://cdn.example.org/app-4.3.0-beta.1-x64.msi",
+                    Some("4.3.0-beta.1".to_string())
+                )
+            ]
+        );
+        assert!(links.iter().all(|link| link.is_binary()));
+        assert_eq!(links[0].title, "Test App 4.2");
+        assert_eq!(links[2].title, "");
+    }
+
+    #[test]
+    fn parse_xml_links_should_filter_items_with_predicates() {
+        let document = XmlDocument::parse(MANIFEST).unwrap();
+
+        let (_, links) = parse_xml_links(
+            url(),
+            &document,
+            "//update[@channel='stable']/files/file[@arch='x64']",
+            ".",
+            Some("/updates/update[1]/version"),
+            &LinkTypeMap::default(),
+        )
+        .unwrap();
+
+        assert_eq!(links.len(), 1);
+        assert_eq!(
+            links[0].link.as_str(),
+            "https://example.org/downloads/app-4.2.0-x64.msi"
+        );
+        assert_eq!(links[0].version, Some(Versions::parse("4.2.0").unwrap()));
+    }
+
+    #[test]
+    fn parse_xml_links_should_return_error_on_invalid_query() {
+        let document = XmlDocument::parse(MANIFEST).unwrap();
+
+        let actual = parse_xml_links(
+            url(),
+            &document,
+            "//update[",
+            "files/file",
+            None,
+            &LinkTypeMap::default(),
+        );
+
+        assert!(actual.is_err());
+    }
+}
@@ -0,0 +1,287 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the policy deciding when a failed request is retried, and how
+//! long to wait before the request is sent again.
+//!
+//! Requests are retried when the server is rate limiting the requests (*ie:
+//! status `429`*), when the server responds with an error status (*ie: status
+//! `5xx`*) or when the connection is reset or times out. The delay between
+//! each attempt grows exponentially, with a random jitter to avoid several
+//! clients retrying at the same time. A delay requested by the server through
+//! the `Retry-After` header is used instead when available.
+//!
+//! Only requests that can safely be sent more than once (*ie: `GET` and
+//! `HEAD` requests*) are retried.
+
+use std::collections::hash_map::RandomState;
+use std::error::Error;
+use std::hash::{BuildHasher, Hasher};
+use std::io::ErrorKind;
+use std::time::{Duration, SystemTime};
+
+use reqwest::blocking::Response;
+use reqwest::{header, Method, StatusCode};
+
+/// The policy used to decide if, and when, a failed request should be sent
+/// again.
+///
+/// ## Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use aer_web::retry::RetryPolicy;
+/// use aer_web::WebRequest;
+///
+/// let request = WebRequest::create().with_retry(
+///     RetryPolicy::new(5)
+///         .with_backoff_base(Duration::from_secs(1))
+///         .with_max_delay(Duration::from_secs(60)),
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    backoff_base: Duration,
+    max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    /// Creates a policy retrying a request 3 times, starting with a delay of
+    /// half a second and never waiting longer than 30 seconds.
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            backoff_base: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a new policy retrying a failed request the specified number of
+    /// times, a value of `0` disables retrying requests.
+    pub fn new(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// Creates a policy that never retries failed requests.
+    pub fn disabled() -> RetryPolicy {
+        RetryPolicy::new(0)
+    }
+
+    /// Sets the delay before the first retry, the delay is doubled for each
+    /// following retry.
+    pub fn with_backoff_base(mut self, backoff_base: Duration) -> RetryPolicy {
+        self.backoff_base = backoff_base;
+        self
+    }
+
+    /// Sets the longest delay to wait before retrying a request, this also
+    /// limits the delay requested by the server.
+    pub fn with_max_delay(mut self, max_delay: Duration) -> RetryPolicy {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Returns the number of times a failed request is retried.
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Returns the delay before the first retry.
+    pub fn backoff_base(&self) -> Duration {
+        self.backoff_base
+    }
+
+    /// Returns the longest delay to wait before retrying a request.
+    pub fn max_delay(&self) -> Duration {
+        self.max_delay
+    }
+
+    /// Returns the delay to wait before sending the request again, nothing is
+    /// returned when the request should not be retried.
+    ///
+    /// ## Arguments
+    ///
+    /// - `attempt`: The number of retries that have already been done.
+    /// - `method`: The method of the request that was sent.
+    /// - `result`: The response, or error, of the request that was sent.
+    pub fn delay(
+        &self,
+        attempt: u32,
+        method: &Method,
+        result: &Result<Response, reqwest::Error>,
+    ) -> Option<Duration> {
+        if attempt >= self.max_retries || !is_idempotent(method) {
+            return None;
+        }
+
+        match result {
+            Ok(response) if is_retryable_status(response.status()) => {
+                match retry_after(response.headers().get(header::RETRY_AFTER)) {
+                    Some(delay) => Some(delay.min(self.max_delay)),
+                    None => Some(self.backoff(attempt)),
+                }
+            }
+            Ok(_) => None,
+            Err(err) if is_retryable_error(err) => Some(self.backoff(attempt)),
+            Err(_) => None,
+        }
+    }
+
+    /// Returns the exponential delay of the specified attempt, where a random
+    /// jitter of up to half of the delay is subtracted.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .backoff_base
+            .checked_mul(2u32.saturating_pow(attempt))
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+        let jitter = delay.as_millis() as u64 / 2;
+
+        match jitter {
+            0 => delay,
+            jitter => delay - Duration::from_millis(random() % (jitter + 1)),
+        }
+    }
+}
+
+/// Returns wether the response status indicates a transient failure, the
+/// server is either rate limiting requests or is failing to handle them.
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || (status.is_server_error() && status != StatusCode::NOT_IMPLEMENTED)
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    *method == Method::GET || *method == Method::HEAD
+}
+
+/// Returns wether the request timed out, or the connection was reset while
+/// the request was sent.
+fn is_retryable_error(err: &reqwest::Error) -> bool {
+    if err.is_timeout() {
+        return true;
+    }
+
+    let mut source = err.source();
+    while let Some(err) = source {
+        if let Some(err) = err.downcast_ref::<std::io::Error>() {
+            if matches!(
+                err.kind(),
+                ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted | ErrorKind::BrokenPipe
+            ) {
+                return true;
+            }
+        }
+        source = err.source();
+    }
+
+    false
+}
+
+/// Parses the value of the `Retry-After` header, which is either the number
+/// of seconds to wait or the date when the request can be retried.
+fn retry_after(value: Option<&header::HeaderValue>) -> Option<Duration> {
+    let value = value?.to_str().ok()?.trim();
+
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => httpdate::parse_http_date(value)
+            .ok()
+            .map(|date| date.duration_since(SystemTime::now()).unwrap_or_default()),
+    }
+}
+
+fn random() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest(
+        status,
+        expected,
+        case(StatusCode::TOO_MANY_REQUESTS, true),
+        case(StatusCode::INTERNAL_SERVER_ERROR, true),
+        case(StatusCode::BAD_GATEWAY, true),
+        case(StatusCode::SERVICE_UNAVAILABLE, true),
+        case(StatusCode::NOT_IMPLEMENTED, false),
+        case(StatusCode::NOT_FOUND, false),
+        case(StatusCode::OK, false)
+    )]
+    fn is_retryable_status_should_only_match_transient_failures(
+        status: StatusCode,
+        expected: bool,
+    ) {
+        assert_eq!(is_retryable_status(status), expected);
+    }
+
+    #[rstest(
+        attempt,
+        min,
+        max,
+        case(0, 250, 500),
+        case(1, 500, 1000),
+        case(2, 1000, 2000),
+        case(10, 15000, 30000),
+        case(40, 15000, 30000)
+    )]
+    fn backoff_should_grow_exponentially_with_jitter(attempt: u32, min: u64, max: u64) {
+        let policy = RetryPolicy::default();
+
+        let actual = policy.backoff(attempt);
+
+        assert!(actual >= Duration::from_millis(min), "{:?}", actual);
+        assert!(actual <= Duration::from_millis(max), "{:?}", actual);
+    }
+
+    #[test]
+    fn backoff_should_not_delay_without_backoff_base() {
+        let policy = RetryPolicy::new(2).with_backoff_base(Duration::from_secs(0));
+
+        assert_eq!(policy.backoff(1), Duration::from_secs(0));
+    }
+
+    #[rstest(
+        value,
+        expected,
+        case("120", Some(Duration::from_secs(120))),
+        case(" 5 ", Some(Duration::from_secs(5))),
+        case("Wed, 21 Oct 2015 07:28:00 GMT", Some(Duration::from_secs(0))),
+        case("tomorrow", None)
+    )]
+    fn retry_after_should_parse_seconds_and_dates(value: &str, expected: Option<Duration>) {
+        let value = HeaderValue::from_str(value).unwrap();
+
+        assert_eq!(retry_after(Some(&value)), expected);
+    }
+
+    #[test]
+    fn retry_after_should_return_remaining_time_until_date() {
+        let date = SystemTime::now() + Duration::from_secs(3600);
+        let value = HeaderValue::from_str(&httpdate::fmt_http_date(date)).unwrap();
+
+        let actual = retry_after(Some(&value)).unwrap();
+
+        assert!(actual > Duration::from_secs(3500), "{:?}", actual);
+        assert!(actual <= Duration::from_secs(3600), "{:?}", actual);
+    }
+
+    #[test]
+    fn disabled_should_not_retry_requests() {
+        let policy = RetryPolicy::disabled();
+
+        assert_eq!(policy.max_retries(), 0);
+    }
+}
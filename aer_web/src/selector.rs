@@ -0,0 +1,681 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the selectors used to restrict which links are extracted from an
+//! html page, and which elements the version of the links is read from. This
+//! allows picking the links from a specific table or section of a page,
+//! instead of every anchor element on the page.
+//!
+//! Selectors starting with `/` or `./` are XPath-like queries (*see the
+//! [xml](crate::xml) module for the supported subset*), everything else is
+//! parsed as a CSS selector. The CSS selectors supports:
+//!
+//! - Element names and `*`.
+//! - Ids (`#downloads`) and classes (`.release`).
+//! - Attributes, optionally compared to a value (`[data-version]`,
+//!   `[href$='.exe']`) using `=`, `~=`, `^=`, `$=` or `*=`.
+//! - Descendant (` `) and child (`>`) combinators.
+//! - Multiple selectors separated by a comma.
+//!
+//! ### Examples
+//!
+//! ```
+//! use aer_web::selector::HtmlSelector;
+//!
+//! let css = HtmlSelector::parse("table#downloads > tbody > tr").unwrap();
+//! let xpath = HtmlSelector::parse("//table[@id='downloads']//tr").unwrap();
+//!
+//! assert!(matches!(css, HtmlSelector::Css(_)));
+//! assert!(matches!(xpath, HtmlSelector::XPath(_)));
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
+
+use select::document::Document;
+use select::node::Node;
+use select::predicate::Name;
+
+use crate::errors::WebError;
+use crate::xml::{XPath, XPathValue, XmlElement, XmlNode};
+
+/// A selector matching elements of an html page, either a CSS selector or an
+/// XPath-like query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HtmlSelector {
+    /// A CSS selector (*ie: `table#downloads td.version`*).
+    Css(CssSelector),
+    /// An XPath-like query (*ie: `//table[@id='downloads']//td[2]`*).
+    XPath(XPath),
+}
+
+impl HtmlSelector {
+    /// Parses the specified expression, expressions starting with `/` or `./`
+    /// are parsed as XPath-like queries and every other expression as a CSS
+    /// selector.
+    pub fn parse(expression: &str) -> Result<HtmlSelector, WebError> {
+        let trimmed = expression.trim();
+
+        if trimmed.starts_with('/') || trimmed.starts_with("./") {
+            Ok(HtmlSelector::XPath(XPath::parse(trimmed)?))
+        } else {
+            Ok(HtmlSelector::Css(CssSelector::parse(trimmed)?))
+        }
+    }
+
+    /// Returns the expression the selector was parsed from.
+    pub fn expression(&self) -> &str {
+        match self {
+            HtmlSelector::Css(css) => css.expression(),
+            HtmlSelector::XPath(xpath) => xpath.expression(),
+        }
+    }
+}
+
+impl Display for HtmlSelector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.expression())
+    }
+}
+
+/// The selectors used when extracting the links of an html page.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LinkSelectors {
+    /// The selector matching the anchor elements, or the section of the page
+    /// containing the anchor elements, that links should be extracted from.
+    /// Every anchor element is used when no selector is set.
+    pub links: Option<HtmlSelector>,
+    /// The selector matching the elements the version of a link is read from.
+    /// The version is read from the first matching element inside the
+    /// closest parent of the anchor element that holds a matching element (*ie:
+    /// the cell of the same table row*).
+    pub version: Option<HtmlSelector>,
+}
+
+impl LinkSelectors {
+    /// Returns wether no selectors have been set.
+    pub fn is_empty(&self) -> bool {
+        self.links.is_none() && self.version.is_none()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operator {
+    Exists,
+    Equals,
+    Includes,
+    Prefix,
+    Suffix,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct AttributeTest {
+    name: String,
+    operator: Operator,
+    value: String,
+}
+
+impl AttributeTest {
+    fn matches(&self, node: &Node) -> bool {
+        let actual = match node.attr(&self.name) {
+            Some(actual) => actual,
+            None => return false,
+        };
+        let value = self.value.as_str();
+
+        match self.operator {
+            Operator::Exists => true,
+            Operator::Equals => actual == value,
+            Operator::Includes => actual.split_whitespace().any(|word| word == value),
+            Operator::Prefix => !value.is_empty() && actual.starts_with(value),
+            Operator::Suffix => !value.is_empty() && actual.ends_with(value),
+            Operator::Contains => !value.is_empty() && actual.contains(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Compound {
+    name: Option<String>,
+    attributes: Vec<AttributeTest>,
+}
+
+impl Compound {
+    fn matches(&self, node: &Node) -> bool {
+        let name = match node.name() {
+            Some(name) => name,
+            None => return false,
+        };
+
+        let name_matches = match self.name {
+            Some(ref expected) => name.eq_ignore_ascii_case(expected),
+            None => true,
+        };
+
+        name_matches && self.attributes.iter().all(|test| test.matches(node))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Complex {
+    compounds: Vec<Compound>,
+    /// The combinator between every compound and the next, holds one item
+    /// less than the compounds.
+    combinators: Vec<Combinator>,
+}
+
+impl Complex {
+    fn matches(&self, node: &Node) -> bool {
+        self.matches_from(self.compounds.len() - 1, node)
+    }
+
+    fn matches_from(&self, index: usize, node: &Node) -> bool {
+        if !self.compounds[index].matches(node) {
+            return false;
+        }
+        if index == 0 {
+            return true;
+        }
+
+        match self.combinators[index - 1] {
+            Combinator::Child => match node.parent() {
+                Some(parent) => self.matches_from(index - 1, &parent),
+                None => false,
+            },
+            Combinator::Descendant => {
+                let mut current = node.parent();
+                while let Some(parent) = current {
+                    if self.matches_from(index - 1, &parent) {
+                        return true;
+                    }
+                    current = parent.parent();
+                }
+
+                false
+            }
+        }
+    }
+}
+
+/// A CSS selector matching elements of an html page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CssSelector {
+    expression: String,
+    selectors: Vec<Complex>,
+}
+
+impl CssSelector {
+    /// Parses the specified CSS selector, returning an error if the selector
+    /// is empty or uses syntax that is not supported (*ie: pseudo classes*).
+    pub fn parse(expression: &str) -> Result<CssSelector, WebError> {
+        let selectors = CssParser::new(expression).parse().map_err(|reason| {
+            WebError::Other(format!(
+                "The CSS selector '{}' is not valid: {}!",
+                expression, reason
+            ))
+        })?;
+
+        Ok(CssSelector {
+            expression: expression.to_owned(),
+            selectors,
+        })
+    }
+
+    /// Returns the expression the selector was parsed from.
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// Returns wether the specified node matches the selector.
+    pub fn matches(&self, node: &Node) -> bool {
+        self.selectors.iter().any(|selector| selector.matches(node))
+    }
+}
+
+impl Display for CssSelector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+struct CssParser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl CssParser {
+    fn new(expression: &str) -> CssParser {
+        CssParser {
+            chars: expression.chars().collect(),
+            position: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn peek_is<F: Fn(char) -> bool>(&self, predicate: F) -> bool {
+        matches!(self.peek(), Some(c) if predicate(c))
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.position;
+        while self.peek_is(char::is_whitespace) {
+            self.position += 1;
+        }
+
+        self.position > start
+    }
+
+    fn parse(mut self) -> Result<Vec<Complex>, String> {
+        let mut selectors = vec![self.parse_complex()?];
+
+        while self.peek() == Some(',') {
+            self.position += 1;
+            selectors.push(self.parse_complex()?);
+        }
+
+        match self.peek() {
+            None => Ok(selectors),
+            Some(c) => Err(format!("unexpected character '{}'", c)),
+        }
+    }
+
+    fn parse_complex(&mut self) -> Result<Complex, String> {
+        self.skip_whitespace();
+        let mut compounds = vec![self.parse_compound()?];
+        let mut combinators = vec![];
+
+        loop {
+            let whitespace = self.skip_whitespace();
+            let combinator = match self.peek() {
+                Some('>') => {
+                    self.position += 1;
+                    self.skip_whitespace();
+                    Combinator::Child
+                }
+                None | Some(',') => break,
+                Some(_) if whitespace => Combinator::Descendant,
+                Some(c) => return Err(format!("unexpected character '{}'", c)),
+            };
+
+            combinators.push(combinator);
+            compounds.push(self.parse_compound()?);
+        }
+
+        Ok(Complex {
+            compounds,
+            combinators,
+        })
+    }
+
+    fn parse_compound(&mut self) -> Result<Compound, String> {
+        let mut compound = Compound {
+            name: None,
+            attributes: vec![],
+        };
+        let mut empty = true;
+
+        if self.peek() == Some('*') {
+            self.position += 1;
+            empty = false;
+        } else if self.peek_is(is_ident_char) {
+            compound.name = Some(self.parse_ident()?.to_lowercase());
+            empty = false;
+        }
+
+        loop {
+            let test = match self.peek() {
+                Some('#') => {
+                    self.position += 1;
+                    AttributeTest {
+                        name: "id".into(),
+                        operator: Operator::Equals,
+                        value: self.parse_ident()?,
+                    }
+                }
+                Some('.') => {
+                    self.position += 1;
+                    AttributeTest {
+                        name: "class".into(),
+                        operator: Operator::Includes,
+                        value: self.parse_ident()?,
+                    }
+                }
+                Some('[') => {
+                    self.position += 1;
+                    self.parse_attribute()?
+                }
+                Some(':') => return Err("pseudo classes are not supported".into()),
+                _ => break,
+            };
+
+            compound.attributes.push(test);
+            empty = false;
+        }
+
+        if empty {
+            match self.peek() {
+                Some(c) => Err(format!("unexpected character '{}'", c)),
+                None => Err("a selector was expected".into()),
+            }
+        } else {
+            Ok(compound)
+        }
+    }
+
+    fn parse_attribute(&mut self) -> Result<AttributeTest, String> {
+        self.skip_whitespace();
+        let name = self.parse_ident()?.to_lowercase();
+        self.skip_whitespace();
+
+        let operator = match self.peek() {
+            Some(']') => {
+                self.position += 1;
+                return Ok(AttributeTest {
+                    name,
+                    operator: Operator::Exists,
+                    value: String::new(),
+                });
+            }
+            Some('=') => Operator::Equals,
+            Some('~') => Operator::Includes,
+            Some('^') => Operator::Prefix,
+            Some('$') => Operator::Suffix,
+            Some('*') => Operator::Contains,
+            _ => return Err(format!("the attribute '{}' is not closed", name)),
+        };
+        self.position += 1;
+        if operator != Operator::Equals {
+            if self.peek() != Some('=') {
+                return Err(format!("the attribute '{}' is not closed", name));
+            }
+            self.position += 1;
+        }
+
+        self.skip_whitespace();
+        let value = match self.peek() {
+            Some(quote) if quote == '\'' || quote == '"' => {
+                self.position += 1;
+                let start = self.position;
+                while self.peek_is(|c| c != quote) {
+                    self.position += 1;
+                }
+                if self.peek().is_none() {
+                    return Err(format!(
+                        "the value of the attribute '{}' is not closed",
+                        name
+                    ));
+                }
+                let value = self.chars[start..self.position].iter().collect();
+                self.position += 1;
+                value
+            }
+            _ => self.parse_ident()?,
+        };
+        self.skip_whitespace();
+
+        if self.peek() != Some(']') {
+            return Err(format!("the attribute '{}' is not closed", name));
+        }
+        self.position += 1;
+
+        Ok(AttributeTest {
+            name,
+            operator,
+            value,
+        })
+    }
+
+    fn parse_ident(&mut self) -> Result<String, String> {
+        let start = self.position;
+        while self.peek_is(is_ident_char) {
+            self.position += 1;
+        }
+
+        if self.position == start {
+            match self.peek() {
+                Some(c) => Err(format!("unexpected character '{}'", c)),
+                None => Err("a name was expected".into()),
+            }
+        } else {
+            Ok(self.chars[start..self.position].iter().collect())
+        }
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_'
+}
+
+/// Returns the anchor elements of the document matching the link selector,
+/// together with the text of the element the version should be read from.
+/// Every anchor element is returned when no link selector is set.
+pub(crate) fn select_anchors<'a>(
+    document: &'a Document,
+    selectors: &LinkSelectors,
+) -> Vec<(Node<'a>, Option<String>)> {
+    let anchors = document.find(Name("a"));
+
+    if selectors.is_empty() {
+        return anchors.map(|anchor| (anchor, None)).collect();
+    }
+
+    // The XPath-like queries are evaluated against a converted copy of the
+    // document, where the elements are mapped back to the nodes by index.
+    let root = to_root_element(document);
+    let elements = index_elements(&root, document);
+    let linked: HashSet<usize> = match selectors.links {
+        Some(HtmlSelector::XPath(ref xpath)) => {
+            let indices: HashMap<*const XmlElement, usize> = elements
+                .iter()
+                .map(|(index, element)| (*element as *const XmlElement, *index))
+                .collect();
+
+            xpath
+                .select(&root)
+                .into_iter()
+                .filter_map(|value| match value {
+                    XPathValue::Element(element) => {
+                        indices.get(&(element as *const XmlElement)).copied()
+                    }
+                    XPathValue::Text(_) => None,
+                })
+                .collect()
+        }
+        _ => HashSet::new(),
+    };
+
+    anchors
+        .filter(|anchor| match selectors.links {
+            Some(HtmlSelector::Css(ref css)) => ancestors(anchor).any(|node| css.matches(&node)),
+            Some(HtmlSelector::XPath(_)) => {
+                ancestors(anchor).any(|node| linked.contains(&node.index()))
+            }
+            None => true,
+        })
+        .map(|anchor| {
+            let version = match selectors.version {
+                Some(HtmlSelector::Css(ref css)) => css_version_text(&anchor, css),
+                Some(HtmlSelector::XPath(ref xpath)) if xpath.is_absolute() => {
+                    first_text(xpath.select(&root))
+                }
+                Some(HtmlSelector::XPath(ref xpath)) => ancestors(&anchor).find_map(|node| {
+                    let element = elements.get(&node.index())?;
+                    first_text(xpath.select(element))
+                }),
+                None => None,
+            };
+
+            (anchor, version)
+        })
+        .collect()
+}
+
+/// Returns the text of the first element matching the selector inside the
+/// closest ancestor of the anchor element holding a matching element.
+fn css_version_text(anchor: &Node, css: &CssSelector) -> Option<String> {
+    ancestors(anchor).find_map(|ancestor| {
+        std::iter::once(ancestor)
+            .chain(ancestor.descendants())
+            .filter(|node| css.matches(node))
+            .map(|node| node.text().trim().to_owned())
+            .find(|text| !text.is_empty())
+    })
+}
+
+fn first_text(values: Vec<XPathValue<'_>>) -> Option<String> {
+    values
+        .iter()
+        .map(XPathValue::text)
+        .find(|text| !text.is_empty())
+}
+
+/// Returns the node together with every ancestor of the node, starting with
+/// the node itself.
+fn ancestors<'a>(node: &Node<'a>) -> impl Iterator<Item = Node<'a>> {
+    std::iter::successors(Some(*node), Node::parent)
+}
+
+fn top_level(document: &Document) -> impl Iterator<Item = Node<'_>> {
+    document.find(|node: &Node| node.parent().is_none())
+}
+
+/// Converts the document to an element holding the top level nodes of the
+/// document, used as the context of the XPath-like queries.
+fn to_root_element(document: &Document) -> XmlElement {
+    XmlElement {
+        children: top_level(document).filter_map(to_xml).collect(),
+        ..Default::default()
+    }
+}
+
+fn to_xml(node: Node) -> Option<XmlNode> {
+    if let Some(text) = node.as_text() {
+        return Some(XmlNode::Text(text.to_owned()));
+    }
+
+    let name = node.name()?;
+    Some(XmlNode::Element(XmlElement {
+        name: name.to_owned(),
+        attributes: node
+            .attrs()
+            .map(|(key, value)| (key.to_owned(), value.to_owned()))
+            .collect(),
+        children: node.children().filter_map(to_xml).collect(),
+    }))
+}
+
+/// Returns the converted elements by the index of the node they were
+/// converted from.
+fn index_elements<'t>(root: &'t XmlElement, document: &Document) -> HashMap<usize, &'t XmlElement> {
+    let mut elements = HashMap::new();
+    let nodes = top_level(document).filter(|node| node.name().is_some());
+    for (element, node) in root.elements().zip(nodes) {
+        collect_elements(element, node, &mut elements);
+    }
+
+    elements
+}
+
+fn collect_elements<'t>(
+    element: &'t XmlElement,
+    node: Node,
+    elements: &mut HashMap<usize, &'t XmlElement>,
+) {
+    elements.insert(node.index(), element);
+    let nodes = node.children().filter(|node| node.name().is_some());
+    for (element, node) in element.elements().zip(nodes) {
+        collect_elements(element, node, elements);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    const HTML: &str = r#"<html><body>
+        <div id="content" class="main wide">
+          <ul><li data-version="1.0"><a href="/a.zip" title="Download">A</a></li></ul>
+          <p><a href="/b.exe">B</a></p>
+        </div>
+        <a href="/c.msi">C</a>
+    </body></html>"#;
+
+    fn matching(selector: &str) -> Vec<String> {
+        let document = Document::from(HTML);
+        let selectors = LinkSelectors {
+            links: Some(HtmlSelector::parse(selector).unwrap()),
+            version: None,
+        };
+
+        select_anchors(&document, &selectors)
+            .into_iter()
+            .filter_map(|(anchor, _)| anchor.attr("href").map(String::from))
+            .collect()
+    }
+
+    #[rstest(
+        selector,
+        expected,
+        case("a", vec!["/a.zip", "/b.exe", "/c.msi"]),
+        case("#content", vec!["/a.zip", "/b.exe"]),
+        case("div.wide > p", vec!["/b.exe"]),
+        case("div > a", vec![]),
+        case("body > a, li[data-version]", vec!["/a.zip", "/c.msi"]),
+        case("[class~=main] [title='Download']", vec!["/a.zip"]),
+        case("a[href^='/b'], a[href*=msi]", vec!["/b.exe", "/c.msi"]),
+        case("*[href$=\".zip\"]", vec!["/a.zip"]),
+        case("//div[@id='content']/ul", vec!["/a.zip"]),
+        case("//body/a", vec!["/c.msi"])
+    )]
+    fn select_anchors_should_only_return_anchors_matching_selector(
+        selector: &str,
+        expected: Vec<&str>,
+    ) {
+        assert_eq!(matching(selector), expected);
+    }
+
+    #[rstest(
+        selector,
+        expected,
+        case("li[data-version]", Some("A")),
+        case("//li/@data-version", Some("1.0")),
+        case("span", None)
+    )]
+    fn select_anchors_should_return_version_text(selector: &str, expected: Option<&str>) {
+        let document = Document::from(HTML);
+        let selectors = LinkSelectors {
+            links: None,
+            version: Some(HtmlSelector::parse(selector).unwrap()),
+        };
+
+        let anchors = select_anchors(&document, &selectors);
+
+        assert_eq!(anchors[0].1.as_deref(), expected);
+    }
+
+    #[rstest(
+        selector,
+        case(""),
+        case("a:first-child"),
+        case("a[href"),
+        case("a[href='x]"),
+        case("a >"),
+        case("a + b"),
+        case("//a[")
+    )]
+    fn parse_should_fail_on_invalid_selectors(selector: &str) {
+        assert!(HtmlSelector::parse(selector).is_err());
+    }
+}
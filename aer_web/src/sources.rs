@@ -0,0 +1,163 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Holds the sources that can be used to find new releases of the software,
+//! by using the API of the service the software is hosted on instead of
+//! parsing html pages.
+
+pub mod github;
+pub mod gitlab;
+pub mod sourceforge;
+
+use aer_version::Versions;
+use reqwest::header::{HeaderMap, LINK};
+use reqwest::Url;
+
+use crate::{LinkElement, LinkType};
+
+/// A single published release of the software, read from one of the
+/// supported sources.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceRelease {
+    /// The name of the tag the release was created from.
+    pub tag: String,
+    /// The version parsed from the tag name, if the tag is a valid version.
+    pub version: Option<Versions>,
+    /// Whether the release have been marked as a pre-release.
+    pub prerelease: bool,
+    /// The link to the html page of the release.
+    pub link: LinkElement,
+    /// The links to the assets uploaded to the release, the version of each
+    /// asset is set to the version of the release.
+    pub assets: Vec<LinkElement>,
+}
+
+impl SourceRelease {
+    /// Creates a new release for the specified tag without any assets, the
+    /// title of the release defaults to the name of the tag.
+    pub(crate) fn new(tag: String, title: Option<String>, url: Url) -> SourceRelease {
+        let version = parse_tag_version(&tag);
+        let mut link = LinkElement::new(url, LinkType::Html);
+        link.title = title.unwrap_or_else(|| tag.clone());
+        link.text = tag.clone();
+        link.version = version.clone();
+        link.attributes.insert("tag".into(), tag.clone());
+
+        SourceRelease {
+            tag,
+            version,
+            prerelease: false,
+            link,
+            assets: vec![],
+        }
+    }
+
+    /// Creates a new link to an asset of the release, using the version of
+    /// the release.
+    pub(crate) fn new_asset(&self, name: String, url: Url, link_type: LinkType) -> LinkElement {
+        let mut link = LinkElement::new(url, link_type);
+        link.title = name.clone();
+        link.text = name;
+        link.version = self.version.clone();
+        link.attributes.insert("tag".into(), self.tag.clone());
+
+        link
+    }
+}
+
+/// Parses the version from the specified tag name, ignoring any prefix before
+/// the first digit (*ie: `v1.0.0` or `release-1.0.0`*).
+pub fn parse_tag_version(tag: &str) -> Option<Versions> {
+    Versions::parse(tag.trim_start_matches(|c: char| !c.is_ascii_digit())).ok()
+}
+
+/// Parses the version from the first word of the specified text containing a
+/// digit, ignoring any prefix before the first digit (*ie: `Release v1.0.0`*).
+pub fn parse_text_version(text: &str) -> Option<Versions> {
+    text.split_whitespace()
+        .find(|word| word.chars().any(|c| c.is_ascii_digit()))
+        .and_then(parse_tag_version)
+}
+
+/// Returns the url of the next page of an API response, read from the
+/// `Link` header of the response (*ie: `<https://api.github.com/...?page=2>;
+/// rel="next"`*). This is how both GitHub and GitLab paginates their APIs.
+pub(crate) fn next_page(headers: &HeaderMap) -> Option<Url> {
+    headers
+        .get_all(LINK)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .find_map(|link| {
+            let mut parts = link.split(';');
+            let url = parts.next()?.trim();
+            let is_next = parts.any(|param| {
+                let param = param.trim().replace(' ', "");
+                param.eq_ignore_ascii_case("rel=\"next\"") || param.eq_ignore_ascii_case("rel=next")
+            });
+            if !is_next {
+                return None;
+            }
+
+            Url::parse(url.strip_prefix('<')?.strip_suffix('>')?).ok()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest(
+        tag,
+        expected,
+        case("v1.0.0", Some("1.0.0")),
+        case("release-2.1", Some("2.1")),
+        case("3.0.0-beta.1", Some("3.0.0-beta.1")),
+        case("nightly", None)
+    )]
+    fn parse_tag_version_should_ignore_prefix(tag: &str, expected: Option<&str>) {
+        let actual = parse_tag_version(tag);
+
+        assert_eq!(actual, expected.map(|v| Versions::parse(v).unwrap()));
+    }
+
+    #[rstest(
+        text,
+        expected,
+        case("Release v1.0.0", Some("1.0.0")),
+        case("  2.1 (latest)", Some("2.1")),
+        case("Nightly build", None)
+    )]
+    fn parse_text_version_should_use_first_word_with_digit(text: &str, expected: Option<&str>) {
+        let actual = parse_text_version(text);
+
+        assert_eq!(actual, expected.map(|v| Versions::parse(v).unwrap()));
+    }
+
+    #[rstest(
+        value,
+        expected,
+        case(
+            r#"<https://api.github.com/repositories/1/releases?page=2>; rel="next", <https://api.github.com/repositories/1/releases?page=5>; rel="last""#,
+            Some("https://api.github.com/repositories/1/releases?page=2")
+        ),
+        case(
+            r#"<https://gitlab.com/api/v4/projects/1/releases?page=1>; rel="first", <https://gitlab.com/api/v4/projects/1/releases?page=3>; rel="next""#,
+            Some("https://gitlab.com/api/v4/projects/1/releases?page=3")
+        ),
+        case(
+            r#"<https://api.github.com/repositories/1/releases?page=1>; rel="prev""#,
+            None
+        )
+    )]
+    fn next_page_should_use_link_marked_as_next(value: &str, expected: Option<&str>) {
+        let mut headers = HeaderMap::new();
+        headers.insert(LINK, value.parse().unwrap());
+
+        let actual = next_page(&headers);
+
+        assert_eq!(actual, expected.map(|url| Url::parse(url).unwrap()));
+    }
+}
@@ -0,0 +1,450 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for finding releases of software hosted on
+//! [GitHub](https://github.com), by using the GitHub Releases API. The
+//! information of a repository (*ie: the description and license*) can also be
+//! read, used when creating new packages.
+
+use log::{info, warn};
+use reqwest::Url;
+use serde::Deserialize;
+
+use crate::errors::WebError;
+use crate::request::handle_exit_code;
+use crate::sources::{next_page, SourceRelease};
+use crate::{LinkTypeMap, WebRequest};
+
+/// The base url to the GitHub REST API.
+const API_URL: &str = "https://api.github.com";
+
+/// The media type requested from the GitHub REST API.
+const ACCEPT_TYPE: &str = "application/vnd.github.v3+json";
+
+/// The number of releases requested on each page, this is the maximum number
+/// of items allowed by the GitHub REST API.
+const PER_PAGE: u32 = 100;
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    #[serde(default)]
+    name: Option<String>,
+    html_url: Url,
+    #[serde(default)]
+    prerelease: bool,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    published_at: Option<String>,
+    #[serde(default)]
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Repository {
+    full_name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    homepage: Option<String>,
+    html_url: Url,
+    #[serde(default)]
+    license: Option<License>,
+    #[serde(default)]
+    topics: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct License {
+    #[serde(default)]
+    spdx_id: Option<String>,
+}
+
+/// The information of a repository hosted on GitHub.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitHubRepository {
+    /// The repository in the form `owner/repo`.
+    pub name: String,
+    /// The description of the repository.
+    pub description: Option<String>,
+    /// The homepage of the software, when it is not the repository itself.
+    pub homepage: Option<Url>,
+    /// The url to the repository on GitHub.
+    pub html_url: Url,
+    /// The SPDX identifier of the license detected by GitHub.
+    pub license: Option<String>,
+    /// The topics the repository have been tagged with.
+    pub topics: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: Url,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    content_type: Option<String>,
+    #[serde(default)]
+    download_count: u64,
+}
+
+impl WebRequest {
+    /// Reads the published releases of a repository hosted on GitHub, newest
+    /// releases first. Draft releases are never returned.
+    ///
+    /// The `repository` must be specified in the form `owner/repo`, and the
+    /// token set with [with_github_token](WebRequest::with_github_token) is
+    /// used to authorize the request when available.
+    pub fn get_github_releases(&self, repository: &str) -> Result<Vec<SourceRelease>, WebError> {
+        let url = releases_url(repository)?;
+
+        info!("Reading the releases of '{}' from GitHub", repository);
+
+        let authorization = self
+            .github_token
+            .as_ref()
+            .map(|token| format!("token {}", token));
+        let response = self.get_api_response(&url, ACCEPT_TYPE, authorization.as_deref())?;
+        let text = handle_exit_code(response, |rsp| rsp.text())?.map_err(WebError::Request)?;
+
+        parse_releases(&text, self.link_types())
+    }
+
+    /// Reads the published releases of a repository hosted on GitHub, newest
+    /// releases first, by following the pages of the releases until no more
+    /// pages are found or `max_pages` have been read. Draft releases are never
+    /// returned.
+    ///
+    /// This is used when looking for historical releases, as only the newest
+    /// releases are returned by
+    /// [get_github_releases](WebRequest::get_github_releases).
+    pub fn get_github_release_history(
+        &self,
+        repository: &str,
+        max_pages: usize,
+    ) -> Result<Vec<SourceRelease>, WebError> {
+        let mut url = releases_url(repository)?;
+        url.query_pairs_mut()
+            .append_pair("per_page", &PER_PAGE.to_string());
+        let mut next = Some(url);
+        let mut releases = vec![];
+        let mut pages = 0;
+
+        info!(
+            "Reading the release history of '{}' from GitHub",
+            repository
+        );
+
+        let authorization = self
+            .github_token
+            .as_ref()
+            .map(|token| format!("token {}", token));
+        while let Some(url) = next.take() {
+            if pages >= max_pages {
+                warn!("Maximum number of pages ({}) reached, stopping!", max_pages);
+                break;
+            }
+            pages += 1;
+
+            let response = self.get_api_response(&url, ACCEPT_TYPE, authorization.as_deref())?;
+            next = next_page(response.headers());
+            let text = handle_exit_code(response, |rsp| rsp.text())?.map_err(WebError::Request)?;
+            releases.extend(parse_releases(&text, self.link_types())?);
+        }
+
+        Ok(releases)
+    }
+
+    /// Reads the information of a repository hosted on GitHub (*ie: the
+    /// description, homepage and license of the repository*).
+    ///
+    /// The `repository` must be specified in the form `owner/repo`, and the
+    /// token set with [with_github_token](WebRequest::with_github_token) is
+    /// used to authorize the request when available.
+    pub fn get_github_repository(&self, repository: &str) -> Result<GitHubRepository, WebError> {
+        let (owner, repo) = parse_repository(repository)?;
+        let url = Url::parse(&format!("{}/repos/{}/{}", API_URL, owner, repo))
+            .map_err(|err| WebError::Other(err.to_string()))?;
+
+        info!("Reading the repository '{}' from GitHub", repository);
+
+        let authorization = self
+            .github_token
+            .as_ref()
+            .map(|token| format!("token {}", token));
+        let response = self.get_api_response(&url, ACCEPT_TYPE, authorization.as_deref())?;
+        let text = handle_exit_code(response, |rsp| rsp.text())?.map_err(WebError::Request)?;
+
+        parse_repository_info(&text)
+    }
+}
+
+/// Returns the repository in the form `owner/repo` when the url points to a
+/// repository on GitHub (*ie: `https://github.com/owner/repo`*).
+pub fn repository_from_url(url: &Url) -> Option<String> {
+    if !matches!(url.host_str(), Some("github.com") | Some("www.github.com")) {
+        return None;
+    }
+
+    let mut segments = url.path_segments()?.filter(|segment| !segment.is_empty());
+    match (segments.next(), segments.next()) {
+        (Some(owner), Some(repo)) => Some(format!(
+            "{}/{}",
+            owner,
+            repo.strip_suffix(".git").unwrap_or(repo)
+        )),
+        _ => None,
+    }
+}
+
+/// Returns the url to the API endpoint listing the releases of the specified
+/// repository, in the form `owner/repo`.
+pub fn releases_url(repository: &str) -> Result<Url, WebError> {
+    let (owner, repo) = parse_repository(repository)?;
+
+    Url::parse(&format!("{}/repos/{}/{}/releases", API_URL, owner, repo))
+        .map_err(|err| WebError::Other(err.to_string()))
+}
+
+/// Splits the specified repository in the form `owner/repo` into the owner
+/// and the name of the repository.
+pub fn parse_repository(repository: &str) -> Result<(&str, &str), WebError> {
+    let mut parts = repository.trim().trim_matches('/').split('/');
+
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(owner), Some(repo), None) if !owner.is_empty() && !repo.is_empty() => {
+            Ok((owner, repo))
+        }
+        _ => Err(WebError::Other(format!(
+            "The repository '{}' is not in the form 'owner/repo'!",
+            repository
+        ))),
+    }
+}
+
+fn parse_releases(text: &str, link_types: &LinkTypeMap) -> Result<Vec<SourceRelease>, WebError> {
+    let releases: Vec<Release> =
+        serde_json::from_str(text).map_err(|err| WebError::Other(err.to_string()))?;
+
+    Ok(releases
+        .into_iter()
+        .filter(|release| !release.draft)
+        .map(|release| new_release(release, link_types))
+        .collect())
+}
+
+fn parse_repository_info(text: &str) -> Result<GitHubRepository, WebError> {
+    let repository: Repository =
+        serde_json::from_str(text).map_err(|err| WebError::Other(err.to_string()))?;
+
+    Ok(GitHubRepository {
+        name: repository.full_name,
+        description: repository
+            .description
+            .map(|description| description.trim().to_owned())
+            .filter(|description| !description.is_empty()),
+        homepage: repository
+            .homepage
+            .and_then(|homepage| Url::parse(homepage.trim()).ok()),
+        html_url: repository.html_url,
+        license: repository
+            .license
+            .and_then(|license| license.spdx_id)
+            .filter(|id| id != "NOASSERTION"),
+        topics: repository.topics,
+    })
+}
+
+fn new_release(release: Release, link_types: &LinkTypeMap) -> SourceRelease {
+    let mut result = SourceRelease::new(release.tag_name, release.name, release.html_url);
+    result.prerelease = release.prerelease;
+    if let Some(published_at) = release.published_at {
+        result
+            .link
+            .attributes
+            .insert("published-at".into(), published_at);
+    }
+
+    for asset in release.assets {
+        let link_type = link_types.from_path(asset.browser_download_url.path());
+        let mut link = result.new_asset(asset.name, asset.browser_download_url, link_type);
+        link.attributes
+            .insert("size".into(), asset.size.to_string());
+        link.attributes
+            .insert("download-count".into(), asset.download_count.to_string());
+        if let Some(content_type) = asset.content_type {
+            link.attributes.insert("content-type".into(), content_type);
+        }
+        result.assets.push(link);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_version::Versions;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::LinkType;
+
+    const RELEASES: &str = r#"[
+        {
+            "tag_name": "v2.0.0-beta.1",
+            "name": "Beta",
+            "html_url": "https://github.com/owner/repo/releases/tag/v2.0.0-beta.1",
+            "draft": false,
+            "prerelease": true,
+            "published_at": "2021-06-01T10:00:00Z",
+            "assets": []
+        },
+        {
+            "tag_name": "v1.1.0",
+            "name": null,
+            "html_url": "https://github.com/owner/repo/releases/tag/v1.1.0",
+            "draft": true,
+            "prerelease": false,
+            "assets": []
+        },
+        {
+            "tag_name": "v1.0.0",
+            "name": "First release",
+            "html_url": "https://github.com/owner/repo/releases/tag/v1.0.0",
+            "draft": false,
+            "prerelease": false,
+            "published_at": "2021-05-01T10:00:00Z",
+            "assets": [
+                {
+                    "name": "app-1.0.0.exe",
+                    "browser_download_url": "https://github.com/owner/repo/releases/download/v1.0.0/app-1.0.0.exe",
+                    "size": 1024,
+                    "content_type": "application/x-msdownload",
+                    "download_count": 42
+                }
+            ]
+        }
+    ]"#;
+
+    #[test]
+    fn parse_releases_should_skip_draft_releases() {
+        let actual = parse_releases(RELEASES, &LinkTypeMap::default()).unwrap();
+
+        let tags: Vec<&str> = actual.iter().map(|release| release.tag.as_str()).collect();
+        assert_eq!(tags, ["v2.0.0-beta.1", "v1.0.0"]);
+        assert!(actual[0].prerelease);
+        assert!(!actual[1].prerelease);
+    }
+
+    #[test]
+    fn parse_releases_should_create_links_for_release_and_assets() {
+        let actual = parse_releases(RELEASES, &LinkTypeMap::default()).unwrap();
+        let release = &actual[1];
+        let version = Versions::parse("1.0.0").ok();
+
+        assert_eq!(release.version, version);
+        assert_eq!(
+            release.link.link.as_str(),
+            "https://github.com/owner/repo/releases/tag/v1.0.0"
+        );
+        assert_eq!(release.link.title, "First release");
+        assert_eq!(release.assets.len(), 1);
+        let asset = &release.assets[0];
+        assert_eq!(
+            asset.link.as_str(),
+            "https://github.com/owner/repo/releases/download/v1.0.0/app-1.0.0.exe"
+        );
+        assert_eq!(asset.link_type, LinkType::Binary);
+        assert_eq!(asset.version, version);
+        assert_eq!(asset.attributes["size"], "1024");
+        assert_eq!(asset.attributes["content-type"], "application/x-msdownload");
+        assert_eq!(asset.attributes["tag"], "v1.0.0");
+    }
+
+    #[test]
+    fn parse_releases_should_return_error_on_invalid_response() {
+        let actual = parse_releases("{ \"message\": \"Not Found\" }", &LinkTypeMap::default());
+
+        assert!(matches!(actual, Err(WebError::Other(_))));
+    }
+
+    #[rstest(
+        repository,
+        expected,
+        case("owner/repo", Some(("owner", "repo"))),
+        case(" /owner/repo/ ", Some(("owner", "repo"))),
+        case("owner", None),
+        case("owner/repo/releases", None),
+        case("/repo", None)
+    )]
+    fn parse_repository_should_split_owner_and_repo(
+        repository: &str,
+        expected: Option<(&str, &str)>,
+    ) {
+        let actual = parse_repository(repository).ok();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn parse_repository_info_should_read_description_and_license() {
+        let actual = parse_repository_info(
+            r#"{
+                "full_name": "owner/repo",
+                "description": " A tool for testing ",
+                "homepage": "https://repo.example.org/",
+                "html_url": "https://github.com/owner/repo",
+                "license": { "key": "mit", "spdx_id": "MIT" },
+                "topics": ["cli", "testing"]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            actual,
+            GitHubRepository {
+                name: "owner/repo".into(),
+                description: Some("A tool for testing".into()),
+                homepage: Some(Url::parse("https://repo.example.org/").unwrap()),
+                html_url: Url::parse("https://github.com/owner/repo").unwrap(),
+                license: Some("MIT".into()),
+                topics: vec!["cli".into(), "testing".into()],
+            }
+        );
+    }
+
+    #[test]
+    fn parse_repository_info_should_ignore_unknown_license_and_empty_values() {
+        let actual = parse_repository_info(
+            r#"{
+                "full_name": "owner/repo",
+                "description": "",
+                "homepage": "",
+                "html_url": "https://github.com/owner/repo",
+                "license": { "key": "other", "spdx_id": "NOASSERTION" }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(actual.description, None);
+        assert_eq!(actual.homepage, None);
+        assert_eq!(actual.license, None);
+    }
+
+    #[rstest(
+        url,
+        expected,
+        case("https://github.com/owner/repo", Some("owner/repo")),
+        case("https://github.com/owner/repo.git", Some("owner/repo")),
+        case("https://github.com/owner/repo/releases/latest", Some("owner/repo")),
+        case("https://github.com/owner", None),
+        case("https://gitlab.com/owner/repo", None)
+    )]
+    fn repository_from_url_should_return_owner_and_repo(url: &str, expected: Option<&str>) {
+        let actual = repository_from_url(&Url::parse(url).unwrap());
+
+        assert_eq!(actual.as_deref(), expected);
+    }
+}
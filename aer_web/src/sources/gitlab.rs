@@ -0,0 +1,483 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for finding releases of software hosted on
+//! [GitLab](https://gitlab.com), or on a self-hosted GitLab instance, by using
+//! the GitLab Releases and Tags API.
+
+use log::{info, warn};
+use reqwest::Url;
+use serde::Deserialize;
+
+use crate::errors::WebError;
+use crate::request::handle_exit_code;
+use crate::sources::{next_page, SourceRelease};
+use crate::{LinkType, LinkTypeMap, WebRequest};
+
+/// The instance used when the repository is not specified as a full url.
+const DEFAULT_INSTANCE: &str = "https://gitlab.com";
+
+/// The media type requested from the GitLab REST API.
+const ACCEPT_TYPE: &str = "application/json";
+
+/// The number of releases requested on each page, this is the maximum number
+/// of items allowed by the GitLab API.
+const PER_PAGE: u32 = 100;
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    released_at: Option<String>,
+    #[serde(default)]
+    upcoming_release: bool,
+    #[serde(default)]
+    assets: Assets,
+}
+
+#[derive(Default, Deserialize)]
+struct Assets {
+    #[serde(default)]
+    links: Vec<AssetLink>,
+}
+
+#[derive(Deserialize)]
+struct AssetLink {
+    name: String,
+    url: Url,
+    #[serde(default)]
+    direct_asset_url: Option<Url>,
+    #[serde(default)]
+    link_type: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Tag {
+    name: String,
+}
+
+/// The location of a project hosted on a GitLab instance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitLabProject {
+    /// The url to the GitLab instance the project is hosted on.
+    pub instance: Url,
+    /// The full path of the project, including any groups and subgroups.
+    pub path: String,
+}
+
+impl GitLabProject {
+    /// Parses the specified repository, either as the path of a project on
+    /// gitlab.com (*ie: `group/project`*) or as the full url to a project on a
+    /// self-hosted instance (*ie: `https://gitlab.example.org/group/project`*).
+    pub fn parse(repository: &str) -> Result<GitLabProject, WebError> {
+        let repository = repository.trim();
+        let (instance, path) = match Url::parse(repository) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {
+                let mut instance = url.clone();
+                instance.set_path("");
+                (instance, url.path().to_owned())
+            }
+            _ => (Url::parse(DEFAULT_INSTANCE).unwrap(), repository.to_owned()),
+        };
+        let path = path.trim_matches('/').trim_end_matches(".git");
+
+        if !path.contains('/') || path.split('/').any(str::is_empty) {
+            return Err(WebError::Other(format!(
+                "The repository '{}' is not in the form 'group/project'!",
+                repository
+            )));
+        }
+
+        Ok(GitLabProject {
+            instance,
+            path: path.into(),
+        })
+    }
+
+    /// Returns the url to the API endpoint listing the releases of the
+    /// project.
+    pub fn releases_url(&self) -> Result<Url, WebError> {
+        self.api_url("releases")
+    }
+
+    fn api_url(&self, endpoint: &str) -> Result<Url, WebError> {
+        let url = format!(
+            "{}/api/v4/projects/{}/{}",
+            self.instance.as_str().trim_end_matches('/'),
+            self.path.replace('/', "%2F"),
+            endpoint
+        );
+
+        Url::parse(&url).map_err(|err| WebError::Other(err.to_string()))
+    }
+
+    fn page_url(&self, page: &str) -> Result<Url, WebError> {
+        let url = format!(
+            "{}/{}/-/{}",
+            self.instance.as_str().trim_end_matches('/'),
+            self.path,
+            page
+        );
+
+        Url::parse(&url).map_err(|err| WebError::Other(err.to_string()))
+    }
+}
+
+impl WebRequest {
+    /// Reads the releases of a project hosted on GitLab, newest releases
+    /// first. Projects without any releases fall back to using the tags of
+    /// the repository, these releases do not have any assets.
+    ///
+    /// The `repository` is either the path of a project on gitlab.com, or the
+    /// full url to a project on a self-hosted instance. The token set with
+    /// [with_gitlab_token](WebRequest::with_gitlab_token) is used to authorize
+    /// the request when available.
+    pub fn get_gitlab_releases(&self, repository: &str) -> Result<Vec<SourceRelease>, WebError> {
+        let project = GitLabProject::parse(repository)?;
+
+        info!("Reading the releases of '{}' from GitLab", project.path);
+        let releases = parse_releases(
+            &self.get_gitlab_text(&project.releases_url()?)?,
+            &project,
+            self.link_types(),
+        )?;
+
+        if !releases.is_empty() {
+            return Ok(releases);
+        }
+
+        warn!(
+            "No releases was found for '{}', using the tags instead!",
+            project.path
+        );
+        parse_tags(
+            &self.get_gitlab_text(&project.api_url("repository/tags")?)?,
+            &project,
+        )
+    }
+
+    /// Reads the releases of a project hosted on GitLab, newest releases
+    /// first, by following the pages of the releases until no more pages are
+    /// found or `max_pages` have been read. Projects without any releases fall
+    /// back to using the tags of the repository, the same way as
+    /// [get_gitlab_releases](WebRequest::get_gitlab_releases).
+    ///
+    /// This is used when looking for historical releases, as only the newest
+    /// releases are returned by
+    /// [get_gitlab_releases](WebRequest::get_gitlab_releases).
+    pub fn get_gitlab_release_history(
+        &self,
+        repository: &str,
+        max_pages: usize,
+    ) -> Result<Vec<SourceRelease>, WebError> {
+        let project = GitLabProject::parse(repository)?;
+        let mut url = project.releases_url()?;
+        url.query_pairs_mut()
+            .append_pair("per_page", &PER_PAGE.to_string());
+        let mut next = Some(url);
+        let mut releases = vec![];
+        let mut pages = 0;
+
+        info!(
+            "Reading the release history of '{}' from GitLab",
+            project.path
+        );
+        while let Some(url) = next.take() {
+            if pages >= max_pages {
+                warn!("Maximum number of pages ({}) reached, stopping!", max_pages);
+                break;
+            }
+            pages += 1;
+
+            let (text, next_url) = self.get_gitlab_page(&url)?;
+            next = next_url;
+            releases.extend(parse_releases(&text, &project, self.link_types())?);
+        }
+
+        if !releases.is_empty() {
+            return Ok(releases);
+        }
+
+        warn!(
+            "No releases was found for '{}', using the tags instead!",
+            project.path
+        );
+        parse_tags(
+            &self.get_gitlab_text(&project.api_url("repository/tags")?)?,
+            &project,
+        )
+    }
+
+    fn get_gitlab_text(&self, url: &Url) -> Result<String, WebError> {
+        self.get_gitlab_page(url).map(|(text, _)| text)
+    }
+
+    /// Reads the text of the specified API endpoint, together with the url of
+    /// the next page of the endpoint when there are more pages.
+    fn get_gitlab_page(&self, url: &Url) -> Result<(String, Option<Url>), WebError> {
+        let authorization = self
+            .gitlab_token
+            .as_ref()
+            .map(|token| format!("Bearer {}", token));
+        let response = self.get_api_response(url, ACCEPT_TYPE, authorization.as_deref())?;
+        let next = next_page(response.headers());
+        let text = handle_exit_code(response, |rsp| rsp.text())?.map_err(WebError::Request)?;
+
+        Ok((text, next))
+    }
+}
+
+fn parse_releases(
+    text: &str,
+    project: &GitLabProject,
+    link_types: &LinkTypeMap,
+) -> Result<Vec<SourceRelease>, WebError> {
+    let releases: Vec<Release> =
+        serde_json::from_str(text).map_err(|err| WebError::Other(err.to_string()))?;
+
+    releases
+        .into_iter()
+        .map(|release| {
+            let url = project.page_url(&format!("releases/{}", release.tag_name))?;
+            let mut result = SourceRelease::new(release.tag_name, release.name, url);
+            result.prerelease = release.upcoming_release;
+            if let Some(released_at) = release.released_at {
+                result
+                    .link
+                    .attributes
+                    .insert("published-at".into(), released_at);
+            }
+
+            for asset in release.assets.links {
+                let url = asset.direct_asset_url.unwrap_or(asset.url);
+                // Asset links are not required to have an extension, as such
+                // any link we can not decide the type of is expected to be a
+                // binary file.
+                let link_type = match link_types.from_path(url.path()) {
+                    LinkType::Unknown => LinkType::Binary,
+                    link_type => link_type,
+                };
+                let mut link = result.new_asset(asset.name, url, link_type);
+                if let Some(asset_type) = asset.link_type {
+                    link.attributes.insert("asset-type".into(), asset_type);
+                }
+                result.assets.push(link);
+            }
+
+            Ok(result)
+        })
+        .collect()
+}
+
+fn parse_tags(text: &str, project: &GitLabProject) -> Result<Vec<SourceRelease>, WebError> {
+    let tags: Vec<Tag> =
+        serde_json::from_str(text).map_err(|err| WebError::Other(err.to_string()))?;
+
+    tags.into_iter()
+        .map(|tag| {
+            let url = project.page_url(&format!("tags/{}", tag.name))?;
+            Ok(SourceRelease::new(tag.name, None, url))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_version::Versions;
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::*;
+    use crate::fixtures::{FixtureMode, Fixtures};
+
+    const RELEASES: &str = r#"[
+        {
+            "tag_name": "v1.1.0",
+            "name": "Upcoming",
+            "released_at": "2099-01-01T00:00:00Z",
+            "upcoming_release": true,
+            "assets": { "count": 0, "sources": [], "links": [] }
+        },
+        {
+            "tag_name": "v1.0.0",
+            "name": "First release",
+            "released_at": "2021-05-01T10:00:00Z",
+            "upcoming_release": false,
+            "assets": {
+                "count": 1,
+                "sources": [
+                    {
+                        "format": "zip",
+                        "url": "https://gitlab.com/group/project/-/archive/v1.0.0/project-v1.0.0.zip"
+                    }
+                ],
+                "links": [
+                    {
+                        "id": 1,
+                        "name": "Windows installer",
+                        "url": "https://gitlab.com/group/project/-/package_files/1/download",
+                        "direct_asset_url": "https://gitlab.com/group/project/-/releases/v1.0.0/downloads/app-1.0.0.exe",
+                        "link_type": "package"
+                    },
+                    {
+                        "id": 2,
+                        "name": "Linux binary",
+                        "url": "https://gitlab.com/group/project/-/package_files/2/download",
+                        "link_type": "other"
+                    }
+                ]
+            }
+        }
+    ]"#;
+
+    fn project() -> GitLabProject {
+        GitLabProject::parse("group/project").unwrap()
+    }
+
+    #[test]
+    fn parse_releases_should_create_links_for_release_and_assets() {
+        let actual = parse_releases(RELEASES, &project(), &LinkTypeMap::default()).unwrap();
+
+        assert_eq!(actual.len(), 2);
+        assert!(actual[0].prerelease);
+        let release = &actual[1];
+        let version = Versions::parse("1.0.0").ok();
+        assert!(!release.prerelease);
+        assert_eq!(release.version, version);
+        assert_eq!(
+            release.link.link.as_str(),
+            "https://gitlab.com/group/project/-/releases/v1.0.0"
+        );
+        let links: Vec<&str> = release.assets.iter().map(|a| a.link.as_str()).collect();
+        assert_eq!(
+            links,
+            [
+                "https://gitlab.com/group/project/-/releases/v1.0.0/downloads/app-1.0.0.exe",
+                "https://gitlab.com/group/project/-/package_files/2/download"
+            ]
+        );
+        assert!(release.assets.iter().all(|a| a.is_binary()));
+        assert!(release.assets.iter().all(|a| a.version == version));
+        assert_eq!(release.assets[0].attributes["asset-type"], "package");
+    }
+
+    #[test]
+    fn parse_tags_should_create_releases_without_assets() {
+        let text = r#"[{ "name": "v2.0.0", "message": "" }, { "name": "v1.9.0" }]"#;
+
+        let actual = parse_tags(text, &project()).unwrap();
+
+        let tags: Vec<&str> = actual.iter().map(|r| r.tag.as_str()).collect();
+        assert_eq!(tags, ["v2.0.0", "v1.9.0"]);
+        assert_eq!(actual[0].version, Versions::parse("2.0.0").ok());
+        assert_eq!(
+            actual[0].link.link.as_str(),
+            "https://gitlab.com/group/project/-/tags/v2.0.0"
+        );
+        assert!(actual[0].assets.is_empty());
+    }
+
+    #[test]
+    fn parse_releases_should_return_error_on_invalid_response() {
+        let actual = parse_releases(
+            "{ \"message\": \"404 Project Not Found\" }",
+            &project(),
+            &LinkTypeMap::default(),
+        );
+
+        assert!(matches!(actual, Err(WebError::Other(_))));
+    }
+
+    #[rstest(
+        repository,
+        instance,
+        path,
+        case("group/project", "https://gitlab.com/", "group/project"),
+        case(
+            "group/subgroup/project/",
+            "https://gitlab.com/",
+            "group/subgroup/project"
+        ),
+        case(
+            "https://gitlab.example.org/group/project.git",
+            "https://gitlab.example.org/",
+            "group/project"
+        )
+    )]
+    fn parse_should_split_instance_and_path(repository: &str, instance: &str, path: &str) {
+        let actual = GitLabProject::parse(repository).unwrap();
+
+        assert_eq!(actual.instance.as_str(), instance);
+        assert_eq!(actual.path, path);
+    }
+
+    #[rstest(
+        repository,
+        case("project"),
+        case("https://gitlab.com/project"),
+        case("group//project")
+    )]
+    fn parse_should_return_error_on_invalid_repository(repository: &str) {
+        let actual = GitLabProject::parse(repository);
+
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn api_url_should_encode_project_path() {
+        let actual = project().api_url("releases").unwrap();
+
+        assert_eq!(
+            actual.as_str(),
+            "https://gitlab.com/api/v4/projects/group%2Fproject/releases"
+        );
+    }
+
+    #[rstest(max_pages, expected, case(1, &["3.0.0"]), case(2, &["3.0.0", "2.0.0"]))]
+    fn get_gitlab_release_history_should_follow_pages_of_releases(
+        max_pages: usize,
+        expected: &[&str],
+    ) {
+        let dir = std::env::temp_dir().join(format!("aer-gitlab-history-test-{}", max_pages));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let fixtures = Fixtures::new(&dir, FixtureMode::Replay);
+        let mut first = project().releases_url().unwrap();
+        first.query_pairs_mut().append_pair("per_page", "100");
+        let mut second = first.clone();
+        second.query_pairs_mut().append_pair("page", "2");
+        for (url, version, next) in &[(&first, "3.0.0", Some(&second)), (&second, "2.0.0", None)] {
+            let mut headers = json!({ "content-type": "application/json" });
+            if let Some(next) = next {
+                headers["link"] = json!(format!("<{}>; rel=\"next\"", next));
+            }
+            let body = json!([{
+                "tag_name": format!("v{}", version),
+                "name": version,
+                "assets": { "count": 0, "sources": [], "links": [] }
+            }]);
+            let recorded = json!({
+                "url": url.as_str(),
+                "final_url": url.as_str(),
+                "status": 200,
+                "headers": headers,
+                "body": body.to_string(),
+            });
+            std::fs::write(fixtures.path_for(url), recorded.to_string()).unwrap();
+        }
+        let request = WebRequest::create().with_fixtures(fixtures);
+
+        let actual = request
+            .get_gitlab_release_history("group/project", max_pages)
+            .unwrap();
+
+        let actual: Vec<_> = actual
+            .iter()
+            .filter_map(|release| release.version.as_ref().map(|v| v.to_string()))
+            .collect();
+        assert_eq!(actual, expected);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
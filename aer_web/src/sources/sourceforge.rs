@@ -0,0 +1,277 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for finding releases of software hosted on
+//! [SourceForge](https://sourceforge.net), by using the RSS feed of the files
+//! in the project.
+
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use log::info;
+use regex::Regex;
+use reqwest::Url;
+
+use crate::errors::WebError;
+use crate::request::handle_exit_code;
+use crate::sources::SourceRelease;
+use crate::{LinkTypeMap, WebRequest};
+
+/// The base url to the SourceForge projects.
+const PROJECTS_URL: &str = "https://sourceforge.net/projects";
+
+/// The media type requested when reading the RSS feed.
+const ACCEPT_TYPE: &str = "application/rss+xml";
+
+lazy_static! {
+    static ref ITEM_RE: Regex = Regex::new(r"(?s)<item>(.*?)</item>").unwrap();
+    static ref SIZE_RE: Regex = Regex::new(r#"filesize="(\d+)""#).unwrap();
+}
+
+/// The location of a project hosted on SourceForge, and the folder of the
+/// project files that should be used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceForgeProject {
+    /// The name of the project.
+    pub name: String,
+    /// The folder of the files that should be read, `/` when every file of
+    /// the project should be read.
+    pub path: String,
+}
+
+impl SourceForgeProject {
+    /// Parses the specified repository in the form `project`, or
+    /// `project/folder` when only the files in a specific folder should be
+    /// read (*ie: `astyle/astyle`*).
+    pub fn parse(repository: &str) -> Result<SourceForgeProject, WebError> {
+        let repository = repository.trim().trim_matches('/');
+        let (name, path) = match repository.find('/') {
+            Some(index) => (&repository[..index], &repository[index..]),
+            None => (repository, "/"),
+        };
+
+        let invalid_path = path != "/" && path[1..].split('/').any(str::is_empty);
+        if name.is_empty() || invalid_path {
+            return Err(WebError::Other(format!(
+                "The repository '{}' is not in the form 'project' or 'project/folder'!",
+                repository
+            )));
+        }
+
+        Ok(SourceForgeProject {
+            name: name.into(),
+            path: path.into(),
+        })
+    }
+
+    /// Returns the url to the RSS feed of the files in the project folder.
+    pub fn feed_url(&self) -> Result<Url, WebError> {
+        let mut url = Url::parse(&format!("{}/{}/rss", PROJECTS_URL, self.name))
+            .map_err(|err| WebError::Other(err.to_string()))?;
+        url.query_pairs_mut().append_pair("path", &self.path);
+
+        Ok(url)
+    }
+
+    fn folder_url(&self, folder: &str) -> Result<Url, WebError> {
+        let url = format!(
+            "{}/{}/files{}/",
+            PROJECTS_URL,
+            self.name,
+            folder.trim_end_matches('/')
+        );
+
+        Url::parse(&url).map_err(|err| WebError::Other(err.to_string()))
+    }
+}
+
+impl WebRequest {
+    /// Reads the files of a project hosted on SourceForge, grouped into one
+    /// release for each folder the files are located in. The newest releases
+    /// are returned first, and the version of each release is parsed from the
+    /// name of the folder (*ie: `astyle 3.1`*).
+    pub fn get_sourceforge_releases(
+        &self,
+        repository: &str,
+    ) -> Result<Vec<SourceRelease>, WebError> {
+        let project = SourceForgeProject::parse(repository)?;
+
+        info!("Reading the files of '{}' from SourceForge", project.name);
+
+        let response = self.get_api_response(&project.feed_url()?, ACCEPT_TYPE, None)?;
+        let text = handle_exit_code(response, |rsp| rsp.text())?.map_err(WebError::Request)?;
+
+        parse_feed(&text, &project, self.link_types())
+    }
+}
+
+fn parse_feed(
+    text: &str,
+    project: &SourceForgeProject,
+    link_types: &LinkTypeMap,
+) -> Result<Vec<SourceRelease>, WebError> {
+    let mut releases: Vec<SourceRelease> = vec![];
+    let mut indexes: HashMap<String, usize> = HashMap::new();
+
+    for item in ITEM_RE.captures_iter(text) {
+        let item = &item[1];
+        let (title, link) = match (element(item, "title"), element(item, "link")) {
+            (Some(title), Some(link)) => (title, link),
+            _ => continue,
+        };
+        // The title holds the full path of the file within the project, the
+        // folder of the file is used as the release the file belongs to.
+        let (folder, name) = match title.rfind('/') {
+            Some(index) if index > 0 => (&title[..index], &title[index + 1..]),
+            _ => continue,
+        };
+        if name.is_empty() {
+            continue;
+        }
+        let url = Url::parse(&link).map_err(|err| WebError::Other(err.to_string()))?;
+
+        let index = match indexes.get(folder) {
+            Some(&index) => index,
+            None => {
+                let tag = folder.rsplit('/').next().unwrap_or(folder).to_owned();
+                let mut release = SourceRelease::new(tag, None, project.folder_url(folder)?);
+                if let Some(published_at) = element(item, "pubDate") {
+                    release
+                        .link
+                        .attributes
+                        .insert("published-at".into(), published_at);
+                }
+                releases.push(release);
+                indexes.insert(folder.into(), releases.len() - 1);
+                releases.len() - 1
+            }
+        };
+
+        let release = &mut releases[index];
+        let mut asset = release.new_asset(name.into(), url, link_types.from_path(name));
+        if let Some(size) = SIZE_RE.captures(item) {
+            asset.attributes.insert("size".into(), size[1].into());
+        }
+        release.assets.push(asset);
+    }
+
+    Ok(releases)
+}
+
+fn element(text: &str, name: &str) -> Option<String> {
+    let start = format!("<{}>", name);
+    let end = format!("</{}>", name);
+    let value = text.split(&start).nth(1)?.split(&end).next()?.trim();
+    let value = value
+        .strip_prefix("<![CDATA[")
+        .and_then(|value| value.strip_suffix("]]>"))
+        .unwrap_or(value);
+
+    Some(
+        value
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&amp;", "&"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use aer_version::Versions;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::LinkType;
+
+    const FEED: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<rss xmlns:media="http://video.search.yahoo.com/mrss/" version="2.0">
+  <channel>
+    <title>Artistic Style</title>
+    <item>
+      <title><![CDATA[/astyle/astyle 3.1/astyle_3.1_windows.zip]]></title>
+      <link>https://sourceforge.net/projects/astyle/files/astyle/astyle%203.1/astyle_3.1_windows.zip/download</link>
+      <pubDate>Sun, 20 Jan 2018 13:51:48 UT</pubDate>
+      <media:content url="https://sourceforge.net/projects/astyle/files/astyle/astyle%203.1/astyle_3.1_windows.zip/download" type="application/zip; charset=binary" filesize="1074380"></media:content>
+    </item>
+    <item>
+      <title><![CDATA[/astyle/astyle 3.1/astyle_3.1_linux.tar.gz]]></title>
+      <link>https://sourceforge.net/projects/astyle/files/astyle/astyle%203.1/astyle_3.1_linux.tar.gz/download</link>
+      <pubDate>Sun, 20 Jan 2018 13:51:40 UT</pubDate>
+    </item>
+    <item>
+      <title><![CDATA[/astyle/astyle 3.0.1/AStyle_3.0.1_windows.zip]]></title>
+      <link>https://sourceforge.net/projects/astyle/files/astyle/astyle%203.0.1/AStyle_3.0.1_windows.zip/download</link>
+      <pubDate>Sun, 21 May 2017 17:01:26 UT</pubDate>
+    </item>
+  </channel>
+</rss>"#;
+
+    fn project() -> SourceForgeProject {
+        SourceForgeProject::parse("astyle").unwrap()
+    }
+
+    #[test]
+    fn parse_feed_should_group_files_by_folder() {
+        let actual = parse_feed(FEED, &project(), &LinkTypeMap::default()).unwrap();
+
+        let tags: Vec<&str> = actual.iter().map(|r| r.tag.as_str()).collect();
+        assert_eq!(tags, ["astyle 3.1", "astyle 3.0.1"]);
+        assert_eq!(actual[0].version, Versions::parse("3.1").ok());
+        assert_eq!(actual[0].assets.len(), 2);
+        assert_eq!(actual[1].assets.len(), 1);
+    }
+
+    #[test]
+    fn parse_feed_should_create_links_for_release_and_files() {
+        let actual = parse_feed(FEED, &project(), &LinkTypeMap::default()).unwrap();
+        let release = &actual[0];
+
+        assert_eq!(
+            release.link.link.as_str(),
+            "https://sourceforge.net/projects/astyle/files/astyle/astyle%203.1/"
+        );
+        let asset = &release.assets[0];
+        assert_eq!(
+            asset.link.as_str(),
+            "https://sourceforge.net/projects/astyle/files/astyle/astyle%203.1/astyle_3.1_windows.zip/download"
+        );
+        assert_eq!(asset.title, "astyle_3.1_windows.zip");
+        assert_eq!(asset.link_type, LinkType::Binary);
+        assert_eq!(asset.version, Versions::parse("3.1").ok());
+        assert_eq!(asset.attributes["size"], "1074380");
+    }
+
+    #[rstest(
+        repository,
+        name,
+        path,
+        case("astyle", "astyle", "/"),
+        case("astyle/astyle", "astyle", "/astyle"),
+        case("/astyle/astyle/", "astyle", "/astyle")
+    )]
+    fn parse_should_split_project_and_folder(repository: &str, name: &str, path: &str) {
+        let actual = SourceForgeProject::parse(repository).unwrap();
+
+        assert_eq!(actual.name, name);
+        assert_eq!(actual.path, path);
+    }
+
+    #[rstest(repository, case(""), case("astyle//files"))]
+    fn parse_should_return_error_on_invalid_repository(repository: &str) {
+        assert!(SourceForgeProject::parse(repository).is_err());
+    }
+
+    #[test]
+    fn feed_url_should_include_folder() {
+        let actual = SourceForgeProject::parse("astyle/astyle")
+            .unwrap()
+            .feed_url()
+            .unwrap();
+
+        assert_eq!(
+            actual.as_str(),
+            "https://sourceforge.net/projects/astyle/rss?path=%2Fastyle"
+        );
+    }
+}
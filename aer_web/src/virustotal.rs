@@ -0,0 +1,216 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for looking up the reputation of downloaded files by
+//! using the [VirusTotal](https://www.virustotal.com) API.
+
+use std::fmt::Display;
+
+use log::{info, warn};
+use reqwest::{StatusCode, Url};
+use serde::Deserialize;
+
+use crate::budget::TransferKind;
+use crate::errors::WebError;
+use crate::fixtures::FixtureMode;
+use crate::request::handle_exit_code;
+use crate::WebRequest;
+
+/// The base url to the VirusTotal endpoint used for looking up file reports.
+const API_URL: &str = "https://www.virustotal.com/api/v3/files/";
+
+/// Holds the number of anti virus engines that considered a file to be
+/// malicious, compared to the total number of engines that scanned the file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DetectionRatio {
+    /// The number of engines that flagged the file as being malicious.
+    pub positives: u32,
+    /// The total number of engines that scanned the file.
+    pub total: u32,
+}
+
+impl DetectionRatio {
+    /// Returns `true` if the number of positive detections is higher than the
+    /// specified threshold.
+    pub fn exceeds(&self, threshold: u32) -> bool {
+        self.positives > threshold
+    }
+}
+
+impl Display for DetectionRatio {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        write!(f, "{}/{}", self.positives, self.total)
+    }
+}
+
+#[derive(Deserialize)]
+struct FileReport {
+    data: FileData,
+}
+
+#[derive(Deserialize)]
+struct FileData {
+    attributes: FileAttributes,
+}
+
+#[derive(Deserialize)]
+struct FileAttributes {
+    last_analysis_stats: AnalysisStats,
+}
+
+#[derive(Deserialize)]
+struct AnalysisStats {
+    #[serde(default)]
+    harmless: u32,
+    #[serde(default)]
+    malicious: u32,
+    #[serde(default)]
+    suspicious: u32,
+    #[serde(default)]
+    undetected: u32,
+}
+
+impl From<AnalysisStats> for DetectionRatio {
+    fn from(stats: AnalysisStats) -> Self {
+        DetectionRatio {
+            positives: stats.malicious,
+            total: stats.harmless + stats.malicious + stats.suspicious + stats.undetected,
+        }
+    }
+}
+
+impl WebRequest {
+    /// Looks up the report of a previously scanned file on VirusTotal by using
+    /// the checksum of the file.
+    ///
+    /// ## Arguments
+    ///
+    /// - `hash`: The `md5`, `sha1` or `sha256` checksum of the file to look up.
+    /// - `api_key`: The personal API key that will be used when communicating
+    ///   with VirusTotal.
+    ///
+    /// ## Returns
+    ///
+    /// The detection ratio of the file, or `None` if VirusTotal have never seen
+    /// the file before. The report is recorded to, or replayed from, the
+    /// fixtures the same way as html responses.
+    pub fn get_virustotal_report(
+        &self,
+        hash: &str,
+        api_key: &str,
+    ) -> Result<Option<DetectionRatio>, WebError> {
+        let url = Url::parse(API_URL)
+            .and_then(|url| url.join(&hash.to_lowercase()))
+            .map_err(|err| WebError::Other(err.to_string()))?;
+
+        info!("Looking up the file '{}' on VirusTotal", hash);
+
+        let response = match self.fixtures {
+            Some(ref fixtures) if fixtures.mode() == FixtureMode::Replay => fixtures.load(&url)?,
+            _ => {
+                let _permit = self.acquire(TransferKind::Metadata);
+                let response = self.send(
+                    self.client.get(url.clone()).header("x-apikey", api_key),
+                    &url,
+                )?;
+                match self.fixtures {
+                    Some(ref fixtures) => fixtures.save(&url, response)?,
+                    None => response,
+                }
+            }
+        };
+
+        if response.status() == StatusCode::NOT_FOUND {
+            warn!("The file '{}' have not been scanned by VirusTotal!", hash);
+            return Ok(None);
+        }
+
+        let text = handle_exit_code(response, |rsp| rsp.text())?.map_err(WebError::Request)?;
+
+        parse_report(&text).map(Some)
+    }
+}
+
+fn parse_report(text: &str) -> Result<DetectionRatio, WebError> {
+    let report: FileReport =
+        serde_json::from_str(text).map_err(|err| WebError::Other(err.to_string()))?;
+
+    Ok(report.data.attributes.last_analysis_stats.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[test]
+    fn parse_report_should_extract_detection_ratio() {
+        const REPORT: &str = r#"{
+            "data": {
+                "type": "file",
+                "attributes": {
+                    "last_analysis_stats": {
+                        "harmless": 0,
+                        "type-unsupported": 4,
+                        "suspicious": 1,
+                        "confirmed-timeout": 0,
+                        "timeout": 0,
+                        "failure": 0,
+                        "malicious": 2,
+                        "undetected": 67
+                    }
+                }
+            }
+        }"#;
+
+        let actual = parse_report(REPORT).unwrap();
+
+        assert_eq!(
+            actual,
+            DetectionRatio {
+                positives: 2,
+                total: 70
+            }
+        );
+    }
+
+    #[test]
+    fn parse_report_should_return_error_on_invalid_report() {
+        let actual = parse_report("{ \"error\": { \"code\": \"NotFoundError\" } }");
+
+        assert!(matches!(actual, Err(WebError::Other(_))));
+    }
+
+    #[rstest(
+        positives,
+        threshold,
+        expected,
+        case(0, 0, false),
+        case(1, 0, true),
+        case(3, 3, false),
+        case(4, 3, true)
+    )]
+    fn exceeds_should_compare_positives_against_threshold(
+        positives: u32,
+        threshold: u32,
+        expected: bool,
+    ) {
+        let ratio = DetectionRatio {
+            positives,
+            total: 70,
+        };
+
+        assert_eq!(ratio.exceeds(threshold), expected);
+    }
+
+    #[test]
+    fn display_should_output_ratio() {
+        let ratio = DetectionRatio {
+            positives: 5,
+            total: 68,
+        };
+
+        assert_eq!(ratio.to_string(), "5/68");
+    }
+}
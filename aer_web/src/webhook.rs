@@ -0,0 +1,199 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains the logic for posting JSON messages to webhooks, like the
+//! incoming webhooks of Slack, Discord or Microsoft Teams.
+
+use log::info;
+use reqwest::{header, Url};
+
+use crate::errors::WebError;
+use crate::fixtures::FixtureMode;
+use crate::request::handle_exit_code;
+use crate::WebRequest;
+
+impl WebRequest {
+    /// Posts the JSON message to the specified webhook.
+    ///
+    /// ## Arguments
+    ///
+    /// - `url`: The url of the webhook, which normally includes the secret
+    ///   token of the webhook. Only the scheme and host of the url is included
+    ///   in logged messages, the http log and returned errors.
+    /// - `message`: The JSON message that will be posted as the body of the
+    ///   request.
+    ///
+    /// ## Returns
+    ///
+    /// The status code the webhook responded with, an unsuccessful status code
+    /// is returned as an error.
+    pub fn post_json(&self, url: &str, message: &serde_json::Value) -> Result<u16, WebError> {
+        let url = Url::parse(url).map_err(|err| WebError::Other(err.to_string()))?;
+
+        if let Some(ref fixtures) = self.fixtures {
+            if fixtures.mode() == FixtureMode::Replay {
+                return Err(WebError::Other(format!(
+                    "Messages can not be posted when replaying fixtures ('{}')",
+                    url.host_str().unwrap_or_default()
+                )));
+            }
+        }
+
+        info!(
+            "Posting message to the webhook on '{}'",
+            url.host_str().unwrap_or_default()
+        );
+
+        let request = self
+            .client
+            .post(url.clone())
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(message.to_string());
+        let response = self.send_redacted(request, &url)?;
+
+        handle_exit_code(response, |response| response.status().as_u16())
+            .map_err(WebError::without_url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::fixtures::Fixtures;
+    use crate::http_log::HttpLog;
+
+    #[test]
+    fn post_json_should_not_post_when_replaying_fixtures() {
+        let dir = std::env::temp_dir().join("aer-webhook-fixtures-test");
+        let request = WebRequest::create().with_fixtures(Fixtures::new(&dir, FixtureMode::Replay));
+
+        let actual = request.post_json("https://hooks.example.org/secret", &json!({ "text": "" }));
+
+        assert!(matches!(actual, Err(WebError::Other(_))));
+    }
+
+    #[test]
+    fn post_json_should_return_error_on_invalid_url() {
+        let request = WebRequest::create();
+
+        let actual = request.post_json("not a url", &json!({ "text": "" }));
+
+        assert!(matches!(actual, Err(WebError::Other(_))));
+    }
+
+    /// Starts a web server answering a single request with the specified
+    /// status, returning the address of the server.
+    fn http_server(status: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![];
+            let mut buffer = [0; 1024];
+            // The body of the message may be received together with the
+            // headers, so the whole message is read before responding.
+            loop {
+                let read = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request);
+                let received = match text.find("\r\n\r\n") {
+                    Some(end) => {
+                        let length: usize = text[..end]
+                            .lines()
+                            .find_map(|line| {
+                                line.to_lowercase()
+                                    .strip_prefix("content-length:")?
+                                    .trim()
+                                    .parse()
+                                    .ok()
+                            })
+                            .unwrap_or(0);
+                        request.len() >= end + 4 + length
+                    }
+                    None => false,
+                };
+                if received || read == 0 {
+                    break;
+                }
+            }
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status
+            );
+        });
+
+        address
+    }
+
+    #[test]
+    fn post_json_should_not_log_path_or_query_of_webhook() {
+        let path = std::env::temp_dir().join("aer-webhook-http-log.jsonl");
+        let _ = std::fs::remove_file(&path);
+        let address = http_server("404 Not Found");
+        let request = WebRequest::builder()
+            .without_proxy()
+            .build()
+            .unwrap()
+            .with_http_log(HttpLog::create(&path).unwrap());
+
+        let actual = request
+            .post_json(
+                &format!("http://{}/hooks/secret-path?token=secret-query", address),
+                &json!({ "text": "" }),
+            )
+            .unwrap_err()
+            .to_string();
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        assert!(log.contains("\"url\":\"http://127.0.0.1\""));
+        assert!(log.contains("\"status\":404"));
+        for text in &[&actual, &log] {
+            assert!(
+                !text.contains("secret"),
+                "The secret was found in '{}'",
+                text
+            );
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn post_json_should_not_log_path_or_query_of_unreachable_webhook() {
+        let path = std::env::temp_dir().join("aer-webhook-unreachable-http-log.jsonl");
+        let _ = std::fs::remove_file(&path);
+        let address = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let request = WebRequest::builder()
+            .without_proxy()
+            .build()
+            .unwrap()
+            .with_http_log(HttpLog::create(&path).unwrap());
+
+        let actual = request
+            .post_json(
+                &format!("http://{}/hooks/secret-path?token=secret-query", address),
+                &json!({ "text": "" }),
+            )
+            .unwrap_err()
+            .to_string();
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        assert!(log.contains("\"error\":"));
+        for text in &[&actual, &log] {
+            assert!(
+                !text.contains("secret"),
+                "The secret was found in '{}'",
+                text
+            );
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+}
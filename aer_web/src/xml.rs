@@ -0,0 +1,780 @@
+// Copyright (c) 2021 Kim J. Nordmo and WormieCorp.
+// Licensed under the MIT license. See LICENSE.txt file in the project
+
+//! Contains a minimal XML document, and the XPath-like queries used to select
+//! values from the document. This is used when extracting the versions and
+//! urls from update manifests and appcast (*Sparkle*) feeds.
+//!
+//! The document keeps the elements, attributes and text of the XML, while
+//! processing instructions, comments and document types are ignored. Names
+//! are kept as they are written, including any namespace prefix.
+//!
+//! The queries supports a subset of XPath:
+//!
+//! - Child (`/`) and descendant (`//`) steps, where a leading `/` starts at the
+//!   document.
+//! - Element names, `*`, `.`, attributes (`@name`) and `text()`.
+//! - The predicates `[n]`, `[last()]`, `[@name]`, `[@name='value']`, `[name]`
+//!   and `[name='value']`.
+//!
+//! A name without a namespace prefix also matches names with a prefix (*ie:
+//! `@version` matches `sparkle:version`*).
+//!
+//! ### Examples
+//!
+//! ```
+//! use aer_web::xml::{XPath, XmlDocument};
+//!
+//! let document = XmlDocument::parse(
+//!     r#"<rss><channel>
+//!         <item><enclosure url="https://example.org/app-1.1.zip" sparkle:version="1.1" /></item>
+//!         <item><enclosure url="https://example.org/app-1.0.zip" sparkle:version="1.0" /></item>
+//!     </channel></rss>"#,
+//! )
+//! .unwrap();
+//!
+//! let path = XPath::parse("//item[1]/enclosure/@version").unwrap();
+//! assert_eq!(document.strings(&path), ["1.1"]);
+//! ```
+
+use crate::errors::WebError;
+
+/// A node in the XML document, either an element or text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum XmlNode {
+    /// An element with attributes and children.
+    Element(XmlElement),
+    /// The text between elements, including the content of CDATA sections.
+    Text(String),
+}
+
+/// A single element of the XML document.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct XmlElement {
+    /// The name of the element, including any namespace prefix.
+    pub name: String,
+    /// The attributes of the element, in the order they are written.
+    pub attributes: Vec<(String, String)>,
+    /// The elements and text inside the element.
+    pub children: Vec<XmlNode>,
+}
+
+impl XmlElement {
+    /// Returns the value of the attribute with the specified name.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| name_matches(name, key))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the child elements of the element.
+    pub fn elements(&self) -> impl Iterator<Item = &XmlElement> {
+        self.children.iter().filter_map(|child| match child {
+            XmlNode::Element(element) => Some(element),
+            XmlNode::Text(_) => None,
+        })
+    }
+
+    /// Returns the text of the element and every descendant, with the
+    /// surrounding whitespace removed.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        self.collect_text(&mut text);
+        text.trim().to_owned()
+    }
+
+    fn collect_text(&self, text: &mut String) {
+        for child in &self.children {
+            match child {
+                XmlNode::Element(element) => element.collect_text(text),
+                XmlNode::Text(value) => text.push_str(value),
+            }
+        }
+    }
+
+    fn descendants<'a>(&'a self, descendants: &mut Vec<&'a XmlElement>) {
+        for element in self.elements() {
+            descendants.push(element);
+            element.descendants(descendants);
+        }
+    }
+}
+
+/// A parsed XML document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XmlDocument {
+    /// The node holding the root element, used as the context of absolute
+    /// queries.
+    node: XmlElement,
+}
+
+impl XmlDocument {
+    /// Parses the specified text into a document, returning an error if the
+    /// elements are not closed properly or no root element exists.
+    pub fn parse(text: &str) -> Result<XmlDocument, WebError> {
+        let node = parse_document(text)
+            .map_err(|reason| WebError::Other(format!("The XML is not valid: {}!", reason)))?;
+
+        if node.elements().next().is_none() {
+            return Err(WebError::Other(
+                "The XML is not valid: no root element was found!".into(),
+            ));
+        }
+
+        Ok(XmlDocument { node })
+    }
+
+    /// Returns the root element of the document.
+    pub fn root(&self) -> &XmlElement {
+        self.node
+            .elements()
+            .next()
+            .expect("The document is validated to have a root element")
+    }
+
+    /// Returns every value in the document matching the query.
+    pub fn select<'a>(&'a self, path: &XPath) -> Vec<XPathValue<'a>> {
+        path.select(&self.node)
+    }
+
+    /// Returns the text of every value in the document matching the query.
+    pub fn strings(&self, path: &XPath) -> Vec<String> {
+        to_strings(self.select(path))
+    }
+
+    /// Returns the text of every value matching the query, relative queries
+    /// are evaluated against the specified element and absolute queries
+    /// against the document.
+    pub fn strings_from(&self, element: &XmlElement, path: &XPath) -> Vec<String> {
+        if path.is_absolute() {
+            self.strings(path)
+        } else {
+            to_strings(path.select(element))
+        }
+    }
+}
+
+/// A value selected by a query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum XPathValue<'a> {
+    /// An element of the document.
+    Element(&'a XmlElement),
+    /// The value of an attribute, or the text of an element.
+    Text(&'a str),
+}
+
+impl XPathValue<'_> {
+    /// Returns the text of the value, for elements this is the text of the
+    /// element and every descendant.
+    pub fn text(&self) -> String {
+        match self {
+            XPathValue::Element(element) => element.text(),
+            XPathValue::Text(text) => text.trim().to_owned(),
+        }
+    }
+}
+
+fn to_strings(values: Vec<XPathValue<'_>>) -> Vec<String> {
+    values.iter().map(XPathValue::text).collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Test {
+    Name(String),
+    Attribute(String),
+    Text,
+    Current,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Predicate {
+    Position(usize),
+    Last,
+    HasAttribute(String),
+    AttributeEquals(String, String),
+    HasChild(String),
+    ChildEquals(String, String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    descendant: bool,
+    test: Test,
+    predicates: Vec<Predicate>,
+}
+
+/// A parsed XPath-like query selecting values from an XML document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XPath {
+    expression: String,
+    absolute: bool,
+    steps: Vec<Step>,
+}
+
+impl XPath {
+    /// Parses the specified query.
+    pub fn parse(expression: &str) -> Result<XPath, WebError> {
+        let invalid = |reason: String| {
+            WebError::Other(format!(
+                "The XPath '{}' is not valid: {}!",
+                expression, reason
+            ))
+        };
+        let trimmed = expression.trim();
+        if trimmed.is_empty() {
+            return Err(invalid("the query is empty".into()));
+        }
+
+        let chars: Vec<char> = trimmed.chars().collect();
+        let mut steps = vec![];
+        let mut index = 0;
+
+        while index < chars.len() {
+            let descendant = if chars[index..].starts_with(&['/', '/']) {
+                index += 2;
+                true
+            } else if chars[index] == '/' {
+                index += 1;
+                false
+            } else if index == 0 {
+                false
+            } else {
+                return Err(invalid(format!("unexpected character '{}'", chars[index])));
+            };
+
+            let start = index;
+            let mut depth = 0;
+            let mut quote = None;
+            while index < chars.len() {
+                match (quote, chars[index]) {
+                    (Some(q), c) if q == c => quote = None,
+                    (Some(_), _) => {}
+                    (None, '\'') | (None, '"') => quote = Some(chars[index]),
+                    (None, '[') => depth += 1,
+                    (None, ']') => depth -= 1,
+                    (None, '/') if depth == 0 => break,
+                    _ => {}
+                }
+                index += 1;
+            }
+
+            let step: String = chars[start..index].iter().collect();
+            steps.push(parse_step(&step, descendant).map_err(invalid)?);
+        }
+
+        Ok(XPath {
+            expression: expression.into(),
+            absolute: trimmed.starts_with('/'),
+            steps,
+        })
+    }
+
+    /// Returns the query the path was parsed from.
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// Returns wether the query starts at the document, instead of at the
+    /// element it is evaluated against.
+    pub fn is_absolute(&self) -> bool {
+        self.absolute
+    }
+
+    /// Returns every value matching the query, evaluated against the
+    /// specified element.
+    pub fn select<'a>(&self, context: &'a XmlElement) -> Vec<XPathValue<'a>> {
+        let mut current = vec![XPathValue::Element(context)];
+
+        for step in &self.steps {
+            let mut next = vec![];
+            for value in current {
+                if let XPathValue::Element(element) = value {
+                    next.extend(apply_step(step, element));
+                }
+            }
+            current = next;
+        }
+
+        current
+    }
+}
+
+fn parse_step(step: &str, descendant: bool) -> Result<Step, String> {
+    let (test, predicates) = match step.find('[') {
+        Some(index) => (&step[..index], &step[index..]),
+        None => (step, ""),
+    };
+    let test = match test.trim() {
+        "" => return Err("a step is empty".into()),
+        "." => Test::Current,
+        "text()" => Test::Text,
+        test if test.starts_with('@') && test.len() > 1 => Test::Attribute(test[1..].into()),
+        test if test.chars().all(is_name_char) || test == "*" => Test::Name(test.into()),
+        test => return Err(format!("'{}' is not a supported step", test)),
+    };
+
+    let mut parsed = vec![];
+    let mut rest = predicates.trim();
+    while !rest.is_empty() {
+        let end = rest
+            .find(']')
+            .filter(|_| rest.starts_with('['))
+            .ok_or_else(|| format!("the predicate '{}' is not closed", rest))?;
+        parsed.push(parse_predicate(rest[1..end].trim())?);
+        rest = rest[end + 1..].trim();
+    }
+
+    Ok(Step {
+        descendant,
+        test,
+        predicates: parsed,
+    })
+}
+
+fn parse_predicate(predicate: &str) -> Result<Predicate, String> {
+    if predicate == "last()" {
+        return Ok(Predicate::Last);
+    }
+    if let Ok(position) = predicate.parse::<usize>() {
+        return match position {
+            0 => Err("positions starts at 1".into()),
+            position => Ok(Predicate::Position(position)),
+        };
+    }
+
+    let (name, value) = match predicate.split_once('=') {
+        Some((name, value)) => {
+            let value = value.trim();
+            let unquoted = value
+                .strip_prefix('\'')
+                .and_then(|value| value.strip_suffix('\''))
+                .or_else(|| {
+                    value
+                        .strip_prefix('"')
+                        .and_then(|value| value.strip_suffix('"'))
+                })
+                .ok_or_else(|| format!("the value {} is not quoted", value))?;
+            (name.trim(), Some(unquoted.to_owned()))
+        }
+        None => (predicate, None),
+    };
+
+    match (name.strip_prefix('@'), value) {
+        (Some(name), Some(value)) => Ok(Predicate::AttributeEquals(name.into(), value)),
+        (Some(name), None) => Ok(Predicate::HasAttribute(name.into())),
+        (None, _) if name.is_empty() || !name.chars().all(is_name_char) => {
+            Err(format!("'{}' is not a supported predicate", predicate))
+        }
+        (None, Some(value)) => Ok(Predicate::ChildEquals(name.into(), value)),
+        (None, None) => Ok(Predicate::HasChild(name.into())),
+    }
+}
+
+fn apply_step<'a>(step: &Step, element: &'a XmlElement) -> Vec<XPathValue<'a>> {
+    let mut candidates = vec![element];
+    if step.descendant {
+        element.descendants(&mut candidates);
+    }
+
+    match step.test {
+        Test::Name(ref name) => {
+            let mut matches = vec![];
+            // The candidates holds every descendant when the step is a
+            // descendant step, as such only the direct children of each
+            // candidate is checked.
+            for candidate in candidates {
+                let children = candidate
+                    .elements()
+                    .filter(|child| name_matches(name, &child.name))
+                    .collect();
+                matches.extend(filter_predicates(children, &step.predicates));
+            }
+            matches.into_iter().map(XPathValue::Element).collect()
+        }
+        Test::Current => filter_predicates(vec![element], &step.predicates)
+            .into_iter()
+            .map(XPathValue::Element)
+            .collect(),
+        Test::Attribute(ref name) => candidates
+            .into_iter()
+            .flat_map(|candidate| {
+                candidate
+                    .attributes
+                    .iter()
+                    .filter(move |(key, _)| name == "*" || name_matches(name, key))
+                    .map(|(_, value)| XPathValue::Text(value.as_str()))
+            })
+            .collect(),
+        Test::Text => candidates
+            .into_iter()
+            .flat_map(|candidate| {
+                candidate.children.iter().filter_map(|child| match child {
+                    XmlNode::Text(text) if !text.trim().is_empty() => {
+                        Some(XPathValue::Text(text.as_str()))
+                    }
+                    _ => None,
+                })
+            })
+            .collect(),
+    }
+}
+
+fn filter_predicates<'a>(
+    mut elements: Vec<&'a XmlElement>,
+    predicates: &[Predicate],
+) -> Vec<&'a XmlElement> {
+    for predicate in predicates {
+        elements = match predicate {
+            Predicate::Position(position) => {
+                elements.get(position - 1).copied().into_iter().collect()
+            }
+            Predicate::Last => elements.last().copied().into_iter().collect(),
+            Predicate::HasAttribute(name) => elements
+                .into_iter()
+                .filter(|element| element.attribute(name).is_some())
+                .collect(),
+            Predicate::AttributeEquals(name, value) => elements
+                .into_iter()
+                .filter(|element| element.attribute(name) == Some(value.as_str()))
+                .collect(),
+            Predicate::HasChild(name) => elements
+                .into_iter()
+                .filter(|element| {
+                    element
+                        .elements()
+                        .any(|child| name_matches(name, &child.name))
+                })
+                .collect(),
+            Predicate::ChildEquals(name, value) => elements
+                .into_iter()
+                .filter(|element| {
+                    element
+                        .elements()
+                        .any(|child| name_matches(name, &child.name) && child.text() == *value)
+                })
+                .collect(),
+        };
+    }
+
+    elements
+}
+
+/// Returns wether the name in the query matches the name in the document, a
+/// query without a namespace prefix matches any prefix.
+fn name_matches(query: &str, name: &str) -> bool {
+    query == "*"
+        || query == name
+        || (!query.contains(':') && name.rsplit(':').next() == Some(query))
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':')
+}
+
+fn parse_document(text: &str) -> Result<XmlElement, String> {
+    let mut stack = vec![XmlElement::default()];
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<?") {
+            rest = skip_past(after, "?>")?;
+        } else if let Some(after) = rest.strip_prefix("<!--") {
+            rest = skip_past(after, "-->")?;
+        } else if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = after.find("]]>").ok_or("a CDATA section is not closed")?;
+            push_text(&mut stack, after[..end].into());
+            rest = &after[end + 3..];
+        } else if let Some(after) = rest.strip_prefix("<!") {
+            rest = skip_past(after, ">")?;
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = after.find('>').ok_or("a closing tag is not closed")?;
+            let name = after[..end].trim();
+            let element = stack.pop().filter(|_| !stack.is_empty());
+            match element {
+                Some(element) if element.name == name => {
+                    push_element(&mut stack, element);
+                }
+                Some(element) => {
+                    return Err(format!(
+                        "the element '{}' was closed by '</{}>'",
+                        element.name, name
+                    ))
+                }
+                None => return Err(format!("the closing tag '</{}>' is unexpected", name)),
+            }
+            rest = &after[end + 1..];
+        } else if let Some(after) = rest.strip_prefix('<') {
+            let (element, closed, after) = parse_tag(after)?;
+            if closed {
+                push_element(&mut stack, element);
+            } else {
+                stack.push(element);
+            }
+            rest = after;
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            push_text(&mut stack, decode(&rest[..end]));
+            rest = &rest[end..];
+        }
+    }
+
+    if stack.len() > 1 {
+        return Err(format!(
+            "the element '{}' is not closed",
+            stack
+                .last()
+                .map(|element| element.name.as_str())
+                .unwrap_or_default()
+        ));
+    }
+
+    Ok(stack.pop().unwrap_or_default())
+}
+
+fn skip_past<'a>(text: &'a str, end: &str) -> Result<&'a str, String> {
+    text.find(end)
+        .map(|index| &text[index + end.len()..])
+        .ok_or_else(|| format!("'{}' is missing", end))
+}
+
+fn push_text(stack: &mut [XmlElement], text: String) {
+    // Text outside of the root element is ignored.
+    if stack.len() > 1 && !text.is_empty() {
+        if let Some(parent) = stack.last_mut() {
+            parent.children.push(XmlNode::Text(text));
+        }
+    }
+}
+
+fn push_element(stack: &mut [XmlElement], element: XmlElement) {
+    if let Some(parent) = stack.last_mut() {
+        parent.children.push(XmlNode::Element(element));
+    }
+}
+
+/// Parses the name and attributes of an opening tag, and returns the element
+/// together with wether the tag is self-closing and the text after the tag.
+fn parse_tag(text: &str) -> Result<(XmlElement, bool, &str), String> {
+    let name_end = text
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .ok_or("a tag is not closed")?;
+    let mut element = XmlElement {
+        name: text[..name_end].into(),
+        ..XmlElement::default()
+    };
+    if element.name.is_empty() {
+        return Err("a tag is missing its name".into());
+    }
+    let mut rest = &text[name_end..];
+
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix("/>") {
+            return Ok((element, true, after));
+        } else if let Some(after) = rest.strip_prefix('>') {
+            return Ok((element, false, after));
+        } else if rest.is_empty() {
+            return Err(format!("the tag '{}' is not closed", element.name));
+        }
+
+        let equals = rest
+            .find('=')
+            .ok_or_else(|| format!("an attribute of '{}' is missing a value", element.name))?;
+        let name = rest[..equals].trim();
+        let value = rest[equals + 1..].trim_start();
+        let quote = value
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'')
+            .ok_or_else(|| format!("the attribute '{}' is not quoted", name))?;
+        let end = value[1..]
+            .find(quote)
+            .ok_or_else(|| format!("the attribute '{}' is not closed", name))?;
+        element
+            .attributes
+            .push((name.into(), decode(&value[1..end + 1])));
+        rest = &value[end + 2..];
+    }
+}
+
+/// Decodes the predefined and numeric character references in the text.
+pub(crate) fn decode(text: &str) -> String {
+    if !text.contains('&') {
+        return text.into();
+    }
+
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = match after.find(';').filter(|end| *end <= 10) {
+            Some(end) => end,
+            None => {
+                decoded.push('&');
+                rest = after;
+                continue;
+            }
+        };
+        let character = match &after[..end] {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            reference => reference
+                .strip_prefix("#x")
+                .or_else(|| reference.strip_prefix("#X"))
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| reference.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(std::char::from_u32),
+        };
+        match character {
+            Some(character) => {
+                decoded.push(character);
+                rest = &after[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = after;
+            }
+        }
+    }
+    decoded.push_str(rest);
+
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    const APPCAST: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<!-- Generated appcast -->
+<rss version="2.0" xmlns:sparkle="http://www.andymatuschak.org/xml-namespaces/sparkle">
+  <channel>
+    <title>Test App</title>
+    <item>
+      <title>Version 2.1.0</title>
+      <pubDate>Tue, 01 Jun 2021 10:00:00 GMT</pubDate>
+      <enclosure url="https://example.org/app-2.1.0.exe" sparkle:version="2.1.0" sparkle:os="windows" />
+      <enclosure url="https://example.org/app-2.1.0.dmg" sparkle:version="2.1.0" sparkle:os="macos" />
+    </item>
+    <item>
+      <title><![CDATA[Version 2.0.0 & older]]></title>
+      <enclosure url="https://example.org/app-2.0.0.exe?a=1&amp;b=2" sparkle:version="2.0.0" sparkle:os="windows" />
+    </item>
+  </channel>
+</rss>"#;
+
+    fn document() -> XmlDocument {
+        XmlDocument::parse(APPCAST).unwrap()
+    }
+
+    #[rstest(
+        expression,
+        expected,
+        case("/rss/channel/title", vec!["Test App"]),
+        case("rss/channel/item/title", vec!["Version 2.1.0", "Version 2.0.0 & older"]),
+        case("//item/title/text()", vec!["Version 2.1.0", "Version 2.0.0 & older"]),
+        case("//enclosure/@url", vec![
+            "https://example.org/app-2.1.0.exe",
+            "https://example.org/app-2.1.0.dmg",
+            "https://example.org/app-2.0.0.exe?a=1&b=2"
+        ]),
+        case("//enclosure[@os='windows']/@sparkle:version", vec!["2.1.0", "2.0.0"]),
+        case("//item[1]/enclosure[last()]/@url", vec!["https://example.org/app-2.1.0.dmg"]),
+        case("//item[title='Version 2.1.0']/pubDate", vec!["Tue, 01 Jun 2021 10:00:00 GMT"]),
+        case("//item[pubDate]/enclosure[2]/@version", vec!["2.1.0"]),
+        case("/rss/@version", vec!["2.0"]),
+        case("//channel/*[2]/title", vec!["Version 2.1.0"]),
+        case("//item[3]", vec![]),
+        case("/channel", vec![])
+    )]
+    fn strings_should_select_expected_values(expression: &str, expected: Vec<&str>) {
+        let path = XPath::parse(expression).unwrap();
+
+        let actual = document().strings(&path);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn strings_from_should_evaluate_relative_queries_against_element() {
+        let document = document();
+        let items = XPath::parse("//item").unwrap();
+        let url = XPath::parse("enclosure[1]/@url").unwrap();
+        let title = XPath::parse("/rss/channel/title").unwrap();
+
+        let actual: Vec<_> = document
+            .select(&items)
+            .into_iter()
+            .filter_map(|item| match item {
+                XPathValue::Element(item) => Some((
+                    document.strings_from(item, &url),
+                    document.strings_from(item, &title),
+                )),
+                XPathValue::Text(_) => None,
+            })
+            .collect();
+
+        assert_eq!(
+            actual,
+            [
+                (
+                    vec!["https://example.org/app-2.1.0.exe".to_string()],
+                    vec!["Test App".to_string()]
+                ),
+                (
+                    vec!["https://example.org/app-2.0.0.exe?a=1&b=2".to_string()],
+                    vec!["Test App".to_string()]
+                )
+            ]
+        );
+    }
+
+    #[rstest(
+        text,
+        case("<rss><channel></rss>"),
+        case("<rss><channel>"),
+        case("</rss>"),
+        case("<rss attr=value></rss>"),
+        case("just text"),
+        case("<!-- only a comment -->")
+    )]
+    fn parse_should_fail_on_invalid_documents(text: &str) {
+        let actual = XmlDocument::parse(text);
+
+        assert!(actual.is_err(), "{:?}", actual);
+    }
+
+    #[rstest(
+        expression,
+        case(""),
+        case("//item["),
+        case("//item[0]"),
+        case("//item[title=value]"),
+        case("//item/count(enclosure)")
+    )]
+    fn parse_should_fail_on_invalid_queries(expression: &str) {
+        let actual = XPath::parse(expression);
+
+        assert!(actual.is_err(), "{:?}", actual);
+    }
+
+    #[rstest(
+        text,
+        expected,
+        case("a &lt;b&gt; &amp; &quot;c&quot; &apos;d&apos;", "a <b> & \"c\" 'd'"),
+        case("&#65;&#x42;&#X43;", "ABC"),
+        case("AT&T & co", "AT&T & co"),
+        case("&unknown;", "&unknown;")
+    )]
+    fn decode_should_replace_character_references(text: &str, expected: &str) {
+        assert_eq!(decode(text), expected);
+    }
+}